@@ -866,14 +866,20 @@ pub enum ResourceType {
     Silo,
     SiloUser,
     SiloGroup,
+    ServiceAccount,
+    ServiceAccountToken,
     SiloQuotas,
+    ProjectQuotas,
     IdentityProvider,
     SamlIdentityProvider,
+    OidcIdentityProvider,
     SshKey,
+    ApiToken,
     Certificate,
     ConsoleSession,
     DeviceAuthRequest,
     DeviceAccessToken,
+    ScimClientBearerToken,
     Project,
     Dataset,
     Disk,
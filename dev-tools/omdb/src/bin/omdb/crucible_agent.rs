@@ -10,8 +10,11 @@ use clap::Args;
 use clap::Subcommand;
 use crucible_agent_client::types::RegionId;
 use crucible_agent_client::Client;
+use serde::Serialize;
 use tabled::Tabled;
 
+use crate::helpers::print_rows;
+use crate::helpers::OutputFormat;
 use crate::helpers::CONNECTION_OPTIONS_HEADING;
 use crate::Omdb;
 
@@ -58,7 +61,7 @@ impl CrucibleAgentArgs {
     /// Run a `omdb crucible-agent` subcommand.
     pub(crate) async fn run_cmd(
         &self,
-        _omdb: &Omdb,
+        omdb: &Omdb,
     ) -> Result<(), anyhow::Error> {
         // The crucible agent URL is required, but can come
         // from the environment, in which case it won't be on the command line.
@@ -73,16 +76,16 @@ impl CrucibleAgentArgs {
 
         match &self.command {
             CrucibleAgentCommands::Regions(RegionCommands::List) => {
-                cmd_region_list(&client).await
+                cmd_region_list(&client, omdb.format).await
             }
             CrucibleAgentCommands::Snapshots(SnapshotCommands::List) => {
-                cmd_snapshot_list(&client).await
+                cmd_snapshot_list(&client, omdb.format).await
             }
         }
     }
 }
 
-#[derive(Tabled)]
+#[derive(Serialize, Tabled)]
 #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
 struct Region {
     region_id: String,
@@ -96,6 +99,7 @@ struct Region {
 /// Runs `omdb crucible-agent regions list`
 async fn cmd_region_list(
     client: &crucible_agent_client::Client,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
     let regions = client.region_list().await.context("listing regions")?;
 
@@ -110,16 +114,10 @@ async fn cmd_region_list(
             port: region.port_number.to_string(),
         });
     }
-    let table = tabled::Table::new(rows)
-        .with(tabled::settings::Style::empty())
-        .with(tabled::settings::Padding::new(0, 1, 0, 0))
-        .to_string();
-
-    println!("{}", table);
-    Ok(())
+    print_rows(format, rows)
 }
 
-#[derive(Tabled)]
+#[derive(Serialize, Tabled)]
 #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
 struct Snapshot {
     region_id: String,
@@ -130,6 +128,7 @@ struct Snapshot {
 /// Runs `omdb crucible-agent snapshot list`
 async fn cmd_snapshot_list(
     client: &crucible_agent_client::Client,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
     let regions = client.region_list().await.context("listing regions")?;
 
@@ -141,7 +140,7 @@ async fn cmd_snapshot_list(
         {
             Ok(snapshots) => snapshots,
             Err(e) => {
-                println!(
+                eprintln!(
                     "Error {} looking at region {} for snapshots",
                     e,
                     region.id.to_string()
@@ -173,12 +172,5 @@ async fn cmd_snapshot_list(
             }
         }
     }
-    let table = tabled::Table::new(rows)
-        .with(tabled::settings::Style::empty())
-        .with(tabled::settings::Padding::new(0, 1, 0, 0))
-        .to_string();
-
-    println!("{}", table);
-
-    Ok(())
+    print_rows(format, rows)
 }
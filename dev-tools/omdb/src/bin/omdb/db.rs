@@ -15,6 +15,12 @@
 // NOTE: emanates from Tabled macros
 #![allow(clippy::useless_vec)]
 
+use crate::check_allow_destructive::DestructiveOperationToken;
+use crate::helpers::ensure_not_csv;
+use crate::helpers::ensure_table_format;
+use crate::helpers::print_json;
+use crate::helpers::print_rows;
+use crate::helpers::OutputFormat;
 use crate::helpers::CONNECTION_OPTIONS_HEADING;
 use crate::helpers::DATABASE_OPTIONS_HEADING;
 use crate::Omdb;
@@ -25,7 +31,9 @@ use async_bb8_diesel::AsyncConnection;
 use async_bb8_diesel::AsyncRunQueryDsl;
 use async_bb8_diesel::AsyncSimpleConnection;
 use camino::Utf8PathBuf;
+use chrono::DateTime;
 use chrono::SecondsFormat;
+use chrono::Utc;
 use clap::ArgAction;
 use clap::Args;
 use clap::Subcommand;
@@ -41,7 +49,11 @@ use diesel::TextExpressionMethods;
 use gateway_client::types::SpType;
 use ipnetwork::IpNetwork;
 use nexus_config::PostgresConfigWithUrl;
+use nexus_db_model::saga_types::Saga;
+use nexus_db_model::saga_types::SagaNodeEvent;
+use nexus_db_model::saga_types::SagaState;
 use nexus_db_model::Dataset;
+use nexus_db_model::DatasetKind;
 use nexus_db_model::Disk;
 use nexus_db_model::DnsGroup;
 use nexus_db_model::DnsName;
@@ -49,6 +61,7 @@ use nexus_db_model::DnsVersion;
 use nexus_db_model::DnsZone;
 use nexus_db_model::ExternalIp;
 use nexus_db_model::HwBaseboardId;
+use nexus_db_model::Image;
 use nexus_db_model::Instance;
 use nexus_db_model::InvCollection;
 use nexus_db_model::InvPhysicalDisk;
@@ -56,6 +69,8 @@ use nexus_db_model::IpAttachState;
 use nexus_db_model::IpKind;
 use nexus_db_model::NetworkInterface;
 use nexus_db_model::NetworkInterfaceKind;
+use nexus_db_model::PhysicalDisk;
+use nexus_db_model::PhysicalDiskPolicy;
 use nexus_db_model::Probe;
 use nexus_db_model::Project;
 use nexus_db_model::Region;
@@ -100,7 +115,12 @@ use omicron_common::api::external::MacAddr;
 use omicron_uuid_kinds::CollectionUuid;
 use omicron_uuid_kinds::GenericUuid;
 use omicron_uuid_kinds::SledUuid;
+use reedline::DefaultPrompt;
+use reedline::DefaultPromptSegment;
+use reedline::Reedline;
+use serde::Serialize;
 use sled_agent_client::types::VolumeConstructionRequest;
+use slog_error_chain::InlineErrorChain;
 use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
@@ -108,6 +128,8 @@ use std::collections::BTreeSet;
 use std::collections::HashMap;
 use std::collections::HashSet;
 use std::fmt::Display;
+use std::net::Ipv6Addr;
+use std::net::SocketAddrV6;
 use std::num::NonZeroU32;
 use std::sync::Arc;
 use strum::IntoEnumIterator;
@@ -117,7 +139,9 @@ use uuid::Uuid;
 const NO_ACTIVE_PROPOLIS_MSG: &str = "<no active Propolis>";
 const NOT_ON_SLED_MSG: &str = "<not on any sled>";
 
+#[derive(Serialize)]
 struct MaybePropolisId(Option<Uuid>);
+#[derive(Serialize)]
 struct MaybeSledId(Option<Uuid>);
 
 impl From<&InstanceAndActiveVmm> for MaybePropolisId {
@@ -270,8 +294,12 @@ enum DbCommands {
     Inventory(InventoryArgs),
     /// Save the current Reconfigurator inputs to a file
     ReconfiguratorSave(ReconfiguratorSaveArgs),
+    /// Print information about sagas
+    Sagas(SagasArgs),
     /// Print information about sleds
     Sleds(SledsArgs),
+    /// Print information about a specific customer instance
+    Instance(InstanceArgs),
     /// Print information about customer instances
     Instances(InstancesOptions),
     /// Print information about the network
@@ -280,6 +308,8 @@ enum DbCommands {
     Snapshots(SnapshotArgs),
     /// Validate the contents of the database
     Validate(ValidateArgs),
+    /// Print information about Crucible volumes
+    Volume(VolumeArgs),
 }
 
 #[derive(Debug, Args)]
@@ -362,6 +392,32 @@ impl CliDnsGroup {
     }
 }
 
+#[derive(Debug, Args)]
+struct InstanceArgs {
+    #[command(subcommand)]
+    command: InstanceCommands,
+}
+
+#[derive(Debug, Subcommand)]
+enum InstanceCommands {
+    /// Show an instance's VMM and migration history, networking, disks, and
+    /// related sagas
+    Show(InstanceShowArgs),
+}
+
+#[derive(Debug, Args)]
+struct InstanceShowArgs {
+    /// The UUID of the instance
+    id: Uuid,
+
+    /// Don't look for the instance's sagas (or the migrations they record)
+    ///
+    /// Finding them requires a full scan of the saga table, which can be
+    /// expensive on a system that has run many sagas.
+    #[clap(long)]
+    no_sagas: bool,
+}
+
 #[derive(Debug, Args)]
 struct InstancesOptions {
     /// Only show the running instances
@@ -452,6 +508,50 @@ enum NetworkCommands {
     ListVnics,
 }
 
+#[derive(Debug, Args)]
+struct SagasArgs {
+    #[command(subcommand)]
+    command: SagasCommands,
+}
+
+#[derive(Debug, Subcommand)]
+enum SagasCommands {
+    /// List sagas that are running or unwinding
+    List(SagasListArgs),
+    /// Show a saga's DAG, with the state and output of each node
+    Show(SagaIdArgs),
+    /// Mark a stuck saga as abandoned and release the database records it
+    /// left behind
+    ///
+    /// This does not stop a Nexus that is still executing the saga: it keeps
+    /// running the saga's actions, but can no longer record their progress.
+    /// No Nexus will recover the saga after it has been abandoned.
+    Abandon(SagaAbandonArgs),
+}
+
+#[derive(Debug, Args)]
+struct SagasListArgs {
+    /// Also list sagas that have finished or been abandoned
+    #[clap(long)]
+    all: bool,
+}
+
+#[derive(Debug, Args)]
+struct SagaIdArgs {
+    /// The UUID of the saga
+    saga_id: Uuid,
+}
+
+#[derive(Debug, Args)]
+struct SagaAbandonArgs {
+    /// The UUID of the saga
+    saga_id: Uuid,
+
+    /// Abandon the saga even if the Nexus that owns it is still in service
+    #[clap(long)]
+    force: bool,
+}
+
 #[derive(Debug, Args)]
 struct SnapshotArgs {
     #[command(subcommand)]
@@ -472,6 +572,38 @@ struct SnapshotInfoArgs {
     uuid: Uuid,
 }
 
+#[derive(Debug, Args)]
+struct VolumeArgs {
+    #[command(subcommand)]
+    command: VolumeCommands,
+}
+
+#[derive(Debug, Subcommand)]
+enum VolumeCommands {
+    /// Show a volume and the storage backing each of its targets
+    Show(VolumeShowArgs),
+    /// List the volumes backed by storage on a sled or physical disk
+    Affected(VolumeAffectedArgs),
+}
+
+#[derive(Debug, Args)]
+struct VolumeShowArgs {
+    /// The UUID of the volume
+    uuid: Uuid,
+}
+
+#[derive(Debug, Args)]
+#[group(required = true, multiple = false)]
+struct VolumeAffectedArgs {
+    /// Find volumes with storage on this sled
+    #[clap(long)]
+    sled: Option<Uuid>,
+
+    /// Find volumes with storage on this physical disk
+    #[clap(long)]
+    physical_disk: Option<Uuid>,
+}
+
 #[derive(Debug, Args)]
 struct ValidateArgs {
     #[command(subcommand)]
@@ -498,32 +630,53 @@ impl DbArgs {
     ) -> Result<(), anyhow::Error> {
         let datastore = self.db_url_opts.connect(omdb, log).await?;
         let opctx = OpContext::for_tests(log.clone(), datastore.clone());
+        let format = omdb.format;
         match &self.command {
             DbCommands::Rack(RackArgs { command: RackCommands::List }) => {
-                cmd_db_rack_list(&opctx, &datastore, &self.fetch_opts).await
+                cmd_db_rack_list(&opctx, &datastore, &self.fetch_opts, format)
+                    .await
             }
             DbCommands::Disks(DiskArgs {
                 command: DiskCommands::Info(uuid),
-            }) => cmd_db_disk_info(&opctx, &datastore, uuid).await,
+            }) => cmd_db_disk_info(&opctx, &datastore, uuid, format).await,
             DbCommands::Disks(DiskArgs { command: DiskCommands::List }) => {
-                cmd_db_disk_list(&datastore, &self.fetch_opts).await
+                cmd_db_disk_list(&datastore, &self.fetch_opts, format).await
             }
             DbCommands::Disks(DiskArgs {
                 command: DiskCommands::Physical(uuid),
             }) => {
-                cmd_db_disk_physical(&opctx, &datastore, &self.fetch_opts, uuid)
-                    .await
+                cmd_db_disk_physical(
+                    &opctx,
+                    &datastore,
+                    &self.fetch_opts,
+                    uuid,
+                    format,
+                )
+                .await
             }
             DbCommands::Dns(DnsArgs { command: DnsCommands::Show }) => {
-                cmd_db_dns_show(&opctx, &datastore, &self.fetch_opts).await
+                cmd_db_dns_show(&opctx, &datastore, &self.fetch_opts, format)
+                    .await
             }
             DbCommands::Dns(DnsArgs { command: DnsCommands::Diff(args) }) => {
-                cmd_db_dns_diff(&opctx, &datastore, &self.fetch_opts, args)
-                    .await
+                cmd_db_dns_diff(
+                    &opctx,
+                    &datastore,
+                    &self.fetch_opts,
+                    args,
+                    format,
+                )
+                .await
             }
             DbCommands::Dns(DnsArgs { command: DnsCommands::Names(args) }) => {
-                cmd_db_dns_names(&opctx, &datastore, &self.fetch_opts, args)
-                    .await
+                cmd_db_dns_names(
+                    &opctx,
+                    &datastore,
+                    &self.fetch_opts,
+                    args,
+                    format,
+                )
+                .await
             }
             DbCommands::Inventory(inventory_args) => {
                 cmd_db_inventory(
@@ -531,6 +684,7 @@ impl DbArgs {
                     &datastore,
                     &self.fetch_opts,
                     inventory_args,
+                    format,
                 )
                 .await
             }
@@ -542,8 +696,36 @@ impl DbArgs {
                 )
                 .await
             }
+            DbCommands::Sagas(SagasArgs {
+                command: SagasCommands::List(args),
+            }) => {
+                cmd_db_sagas_list(&datastore, &self.fetch_opts, args, format)
+                    .await
+            }
+            DbCommands::Sagas(SagasArgs {
+                command: SagasCommands::Show(args),
+            }) => cmd_db_sagas_show(&datastore, args, format).await,
+            DbCommands::Sagas(SagasArgs {
+                command: SagasCommands::Abandon(args),
+            }) => {
+                let token = omdb.check_allow_destructive()?;
+                cmd_db_sagas_abandon(&opctx, &datastore, args, token).await
+            }
             DbCommands::Sleds(args) => {
-                cmd_db_sleds(&opctx, &datastore, &self.fetch_opts, args).await
+                cmd_db_sleds(&opctx, &datastore, &self.fetch_opts, args, format)
+                    .await
+            }
+            DbCommands::Instance(InstanceArgs {
+                command: InstanceCommands::Show(args),
+            }) => {
+                cmd_db_instance_show(
+                    &opctx,
+                    &datastore,
+                    &self.fetch_opts,
+                    args,
+                    format,
+                )
+                .await
             }
             DbCommands::Instances(instances_options) => {
                 cmd_db_instances(
@@ -551,6 +733,7 @@ impl DbArgs {
                     &datastore,
                     &self.fetch_opts,
                     instances_options.running,
+                    format,
                 )
                 .await
             }
@@ -558,8 +741,14 @@ impl DbArgs {
                 command: NetworkCommands::ListEips,
                 verbose,
             }) => {
-                cmd_db_eips(&opctx, &datastore, &self.fetch_opts, *verbose)
-                    .await
+                cmd_db_eips(
+                    &opctx,
+                    &datastore,
+                    &self.fetch_opts,
+                    *verbose,
+                    format,
+                )
+                .await
             }
             DbCommands::Network(NetworkArgs {
                 command: NetworkCommands::ListVnics,
@@ -569,21 +758,38 @@ impl DbArgs {
                     &datastore,
                     &self.fetch_opts,
                     *verbose,
+                    format,
                 )
                 .await
             }
             DbCommands::Snapshots(SnapshotArgs {
                 command: SnapshotCommands::Info(uuid),
-            }) => cmd_db_snapshot_info(&opctx, &datastore, uuid).await,
+            }) => cmd_db_snapshot_info(&opctx, &datastore, uuid, format).await,
             DbCommands::Snapshots(SnapshotArgs {
                 command: SnapshotCommands::List,
-            }) => cmd_db_snapshot_list(&datastore, &self.fetch_opts).await,
+            }) => {
+                cmd_db_snapshot_list(&datastore, &self.fetch_opts, format).await
+            }
             DbCommands::Validate(ValidateArgs {
                 command: ValidateCommands::ValidateVolumeReferences,
-            }) => cmd_db_validate_volume_references(&datastore).await,
+            }) => cmd_db_validate_volume_references(&datastore, format).await,
             DbCommands::Validate(ValidateArgs {
                 command: ValidateCommands::ValidateRegionSnapshots,
-            }) => cmd_db_validate_region_snapshots(&datastore).await,
+            }) => cmd_db_validate_region_snapshots(&datastore, format).await,
+            DbCommands::Volume(VolumeArgs {
+                command: VolumeCommands::Show(args),
+            }) => cmd_db_volume_show(&datastore, args, format).await,
+            DbCommands::Volume(VolumeArgs {
+                command: VolumeCommands::Affected(args),
+            }) => {
+                cmd_db_volume_affected(
+                    &datastore,
+                    &self.fetch_opts,
+                    args,
+                    format,
+                )
+                .await
+            }
         }
     }
 }
@@ -663,6 +869,21 @@ where
     );
 }
 
+/// Display an empty cell for an Option<T> if it's None.
+fn display_option_blank<T: Display>(opt: &Option<T>) -> String {
+    opt.as_ref().map(|x| x.to_string()).unwrap_or_else(|| "".to_string())
+}
+
+/// Display a timestamp in RFC 3339 format, to the second.
+fn display_timestamp(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339_opts(SecondsFormat::Secs, true)
+}
+
+/// Display an empty cell for a timestamp if it's None.
+fn display_timestamp_blank(time: &Option<DateTime<Utc>>) -> String {
+    time.as_ref().map(display_timestamp).unwrap_or_default()
+}
+
 /// Returns pagination parameters to fetch the first page of results for a
 /// paginated endpoint
 fn first_page<'a, T>(limit: NonZeroU32) -> DataPageParams<'a, T> {
@@ -776,8 +997,9 @@ async fn lookup_project(
 async fn cmd_db_disk_list(
     datastore: &DataStore,
     fetch_opts: &DbFetchOptions,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
-    #[derive(Tabled)]
+    #[derive(Serialize, Tabled)]
     #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
     struct DiskRow {
         name: String,
@@ -814,14 +1036,7 @@ async fn cmd_db_disk_list(
             None => "-".to_string(),
         },
     });
-    let table = tabled::Table::new(rows)
-        .with(tabled::settings::Style::empty())
-        .with(tabled::settings::Padding::new(0, 1, 0, 0))
-        .to_string();
-
-    println!("{}", table);
-
-    Ok(())
+    print_rows(format, rows)
 }
 
 /// Run `omdb db rack info`.
@@ -829,8 +1044,9 @@ async fn cmd_db_rack_list(
     opctx: &OpContext,
     datastore: &DataStore,
     fetch_opts: &DbFetchOptions,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
-    #[derive(Tabled)]
+    #[derive(Serialize, Tabled)]
     #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
     struct RackRow {
         id: String,
@@ -858,14 +1074,7 @@ async fn cmd_db_rack_list(
             .unwrap_or_else(|| "-".to_string()),
     });
 
-    let table = tabled::Table::new(rows)
-        .with(tabled::settings::Style::empty())
-        .with(tabled::settings::Padding::new(0, 1, 0, 0))
-        .to_string();
-
-    println!("{}", table);
-
-    Ok(())
+    print_rows(format, rows)
 }
 
 /// Run `omdb db disk info <UUID>`.
@@ -873,9 +1082,12 @@ async fn cmd_db_disk_info(
     opctx: &OpContext,
     datastore: &DataStore,
     args: &DiskInfoArgs,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
+    ensure_not_csv(format, "omdb db disks info")?;
+
     // The row describing the instance
-    #[derive(Tabled)]
+    #[derive(Serialize, Tabled)]
     #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
     struct UpstairsRow {
         host_serial: String,
@@ -887,7 +1099,7 @@ async fn cmd_db_disk_info(
     }
 
     // The rows describing the downstairs regions for this disk/volume
-    #[derive(Tabled)]
+    #[derive(Serialize, Tabled)]
     #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
     struct DownstairsRow {
         host_serial: String,
@@ -912,9 +1124,6 @@ async fn cmd_db_disk_info(
         bail!("no disk: {} found", args.uuid);
     };
 
-    // For information about where this disk is attached.
-    let mut rows = Vec::new();
-
     // If the disk is attached to an instance, show information
     // about that instance.
     let usr = if let Some(instance_uuid) = disk.runtime().attach_instance_id {
@@ -985,14 +1194,6 @@ async fn cmd_db_disk_info(
             disk_state: disk.runtime_state.disk_state.to_string(),
         }
     };
-    rows.push(usr);
-
-    let table = tabled::Table::new(rows)
-        .with(tabled::settings::Style::empty())
-        .with(tabled::settings::Padding::new(0, 1, 0, 0))
-        .to_string();
-
-    println!("{}", table);
 
     // Get the dataset backing this volume.
     let regions = datastore.get_allocated_regions(disk.volume_id).await?;
@@ -1022,6 +1223,22 @@ async fn cmd_db_disk_info(
         });
     }
 
+    if format == OutputFormat::Json {
+        #[derive(Serialize)]
+        struct DiskInfoReport {
+            upstairs: UpstairsRow,
+            downstairs: Vec<DownstairsRow>,
+        }
+        return print_json(&DiskInfoReport { upstairs: usr, downstairs: rows });
+    }
+
+    let table = tabled::Table::new([usr])
+        .with(tabled::settings::Style::empty())
+        .with(tabled::settings::Padding::new(0, 1, 0, 0))
+        .to_string();
+
+    println!("{}", table);
+
     let table = tabled::Table::new(rows)
         .with(tabled::settings::Style::empty())
         .with(tabled::settings::Padding::new(0, 1, 0, 0))
@@ -1038,7 +1255,44 @@ async fn cmd_db_disk_physical(
     datastore: &DataStore,
     fetch_opts: &DbFetchOptions,
     args: &DiskPhysicalArgs,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
+    ensure_not_csv(format, "omdb db disks physical")?;
+
+    // Everything found on the physical disk, for JSON output
+    #[derive(Default, Serialize)]
+    struct DiskPhysicalReport {
+        physical_disk_id: Uuid,
+        sled_serials: Vec<String>,
+        dataset_ids: Vec<Uuid>,
+        disks: Vec<DiskRow>,
+        region_snapshots: Vec<RegionSnapshotRow>,
+        snapshots: Vec<SnapshotRow>,
+    }
+
+    #[derive(Serialize, Tabled)]
+    #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
+    struct DiskRow {
+        disk_name: String,
+        id: String,
+        state: String,
+        instance_name: String,
+    }
+
+    // The row describing the region_snapshot.
+    #[derive(Serialize, Tabled)]
+    #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
+    struct RegionSnapshotRow {
+        dataset_id: String,
+        region_id: String,
+        snapshot_id: String,
+        volume_references: String,
+    }
+
+    let mut report = DiskPhysicalReport {
+        physical_disk_id: args.uuid,
+        ..Default::default()
+    };
     let conn = datastore.pool_connection_for_tests().await?;
 
     // We start by finding any zpools that are using the physical disk.
@@ -1059,6 +1313,9 @@ async fn cmd_db_disk_physical(
     let mut dataset_ids = HashSet::new();
 
     if zpools.is_empty() {
+        if format == OutputFormat::Json {
+            return print_json(&report);
+        }
         println!("Found no zpools on physical disk UUID {}", args.uuid);
         return Ok(());
     }
@@ -1099,13 +1356,10 @@ async fn cmd_db_disk_physical(
             .await
             .context("failed to look up sled")?;
 
-        println!(
-            "Physical disk: {} found on sled: {}",
-            args.uuid,
-            my_sled.serial_number()
-        );
+        report.sled_serials.push(my_sled.serial_number().to_string());
     }
-    println!("DATASETS: {:?}", dataset_ids);
+    report.dataset_ids = dataset_ids.iter().copied().collect();
+    report.dataset_ids.sort();
 
     let mut volume_ids = HashSet::new();
     // Now, take the list of datasets we found and search all the regions
@@ -1145,17 +1399,6 @@ async fn cmd_db_disk_physical(
 
     check_limit(&disks, fetch_opts.fetch_limit, || "listing disks".to_string());
 
-    #[derive(Tabled)]
-    #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
-    struct DiskRow {
-        disk_name: String,
-        id: String,
-        state: String,
-        instance_name: String,
-    }
-
-    let mut rows = Vec::new();
-
     for disk in disks {
         // If the disk is attached to an instance, determine the name of the
         // instance.
@@ -1180,7 +1423,7 @@ async fn cmd_db_disk_physical(
                 "-".to_string()
             };
 
-        rows.push(DiskRow {
+        report.disks.push(DiskRow {
             disk_name: disk.name().to_string(),
             id: disk.id().to_string(),
             state: disk.runtime().disk_state,
@@ -1188,18 +1431,11 @@ async fn cmd_db_disk_physical(
         });
     }
 
-    let table = tabled::Table::new(rows)
-        .with(tabled::settings::Style::empty())
-        .with(tabled::settings::Padding::new(0, 1, 0, 0))
-        .to_string();
-
-    println!("{}", table);
-
     // Collect the region_snapshots associated with the dataset IDs
     let limit = fetch_opts.fetch_limit;
     use db::schema::region_snapshot::dsl as region_snapshot_dsl;
     let region_snapshots = region_snapshot_dsl::region_snapshot
-        .filter(region_snapshot_dsl::dataset_id.eq_any(dataset_ids))
+        .filter(region_snapshot_dsl::dataset_id.eq_any(dataset_ids.clone()))
         .limit(i64::from(u32::from(limit)))
         .select(RegionSnapshot::as_select())
         .load_async(&*conn)
@@ -1210,17 +1446,6 @@ async fn cmd_db_disk_physical(
         "listing region snapshots".to_string()
     });
 
-    // The row describing the region_snapshot.
-    #[derive(Tabled)]
-    #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
-    struct RegionSnapshotRow {
-        dataset_id: String,
-        region_id: String,
-        snapshot_id: String,
-        volume_references: String,
-    }
-    let mut rsnap = Vec::new();
-
     // From each region snapshot:
     // Collect the snapshot IDs for later use.
     // Display the region snapshot rows.
@@ -1233,14 +1458,8 @@ async fn cmd_db_disk_physical(
             snapshot_id: rs.snapshot_id.to_string(),
             volume_references: rs.volume_references.to_string(),
         };
-        rsnap.push(rs);
+        report.region_snapshots.push(rs);
     }
-    let table = tabled::Table::new(rsnap)
-        .with(tabled::settings::Style::empty())
-        .with(tabled::settings::Padding::new(0, 1, 0, 0))
-        .to_string();
-
-    println!("{}", table);
 
     // Get the snapshots from the list of IDs we built above.
     // Display information about those snapshots.
@@ -1260,14 +1479,31 @@ async fn cmd_db_disk_physical(
 
     check_limit(&snapshots, limit, || "listing snapshots".to_string());
 
-    let rows =
-        snapshots.into_iter().map(|snapshot| SnapshotRow::from(snapshot));
-    let table = tabled::Table::new(rows)
-        .with(tabled::settings::Style::empty())
-        .with(tabled::settings::Padding::new(0, 1, 0, 0))
-        .to_string();
+    report.snapshots = snapshots
+        .into_iter()
+        .map(|snapshot| SnapshotRow::from(snapshot))
+        .collect();
 
-    println!("{}", table);
+    if format == OutputFormat::Json {
+        return print_json(&report);
+    }
+
+    for serial in &report.sled_serials {
+        println!("Physical disk: {} found on sled: {}", args.uuid, serial);
+    }
+    println!("DATASETS: {:?}", dataset_ids);
+
+    for table in [
+        tabled::Table::new(report.disks),
+        tabled::Table::new(report.region_snapshots),
+        tabled::Table::new(report.snapshots),
+    ] {
+        let table = table
+            .with(tabled::settings::Style::empty())
+            .with(tabled::settings::Padding::new(0, 1, 0, 0))
+            .to_string();
+        println!("{}", table);
+    }
     Ok(())
 }
 
@@ -1294,7 +1530,7 @@ fn format_snapshot(state: &SnapshotState) -> impl Display {
 }
 
 // The row describing the snapshot
-#[derive(Tabled)]
+#[derive(Serialize, Tabled)]
 #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
 struct SnapshotRow {
     snap_name: String,
@@ -1324,6 +1560,7 @@ impl From<Snapshot> for SnapshotRow {
 async fn cmd_db_snapshot_list(
     datastore: &DataStore,
     fetch_opts: &DbFetchOptions,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
     let ctx = || "listing snapshots".to_string();
     let limit = fetch_opts.fetch_limit;
@@ -1345,14 +1582,7 @@ async fn cmd_db_snapshot_list(
 
     let rows =
         snapshots.into_iter().map(|snapshot| SnapshotRow::from(snapshot));
-    let table = tabled::Table::new(rows)
-        .with(tabled::settings::Style::empty())
-        .with(tabled::settings::Padding::new(0, 1, 0, 0))
-        .to_string();
-
-    println!("{}", table);
-
-    Ok(())
+    print_rows(format, rows)
 }
 
 /// Run `omdb db snapshot info <UUID>`.
@@ -1360,9 +1590,12 @@ async fn cmd_db_snapshot_info(
     opctx: &OpContext,
     datastore: &DataStore,
     args: &SnapshotInfoArgs,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
+    ensure_not_csv(format, "omdb db snapshots info")?;
+
     // The rows describing the downstairs regions for this snapshot/volume
-    #[derive(Tabled)]
+    #[derive(Serialize, Tabled)]
     #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
     struct DownstairsRow {
         host_serial: String,
@@ -1380,58 +1613,510 @@ async fn cmd_db_snapshot_info(
         .await
         .context("loading requested snapshot")?;
 
-    let mut dest_volume_ids = Vec::new();
-    let rows = snapshots.into_iter().map(|snapshot| {
-        dest_volume_ids.push(snapshot.destination_volume_id);
-        SnapshotRow::from(snapshot)
-    });
-    if rows.len() == 0 {
+    let Some(snapshot) = snapshots.into_iter().next() else {
         bail!("No snapshout with UUID: {} found", args.uuid);
-    }
+    };
+    let dest_volume_id = snapshot.destination_volume_id;
+    let snapshot = SnapshotRow::from(snapshot);
 
-    let table = tabled::Table::new(rows)
-        .with(tabled::settings::Style::empty())
-        .with(tabled::settings::Padding::new(0, 1, 0, 0))
-        .to_string();
+    // Get the dataset backing this volume.
+    let regions = datastore.get_allocated_regions(dest_volume_id).await?;
 
-    println!("{}", table);
+    let mut rows = Vec::with_capacity(3);
+    for (dataset, region) in regions {
+        let my_pool_id = dataset.pool_id;
+        let (_, my_zpool) = LookupPath::new(opctx, datastore)
+            .zpool_id(my_pool_id)
+            .fetch()
+            .await
+            .context("failed to look up zpool")?;
 
-    for vol_id in dest_volume_ids {
-        // Get the dataset backing this volume.
-        let regions = datastore.get_allocated_regions(vol_id).await?;
+        let my_sled_id = my_zpool.sled_id;
 
-        let mut rows = Vec::with_capacity(3);
-        for (dataset, region) in regions {
-            let my_pool_id = dataset.pool_id;
-            let (_, my_zpool) = LookupPath::new(opctx, datastore)
-                .zpool_id(my_pool_id)
-                .fetch()
-                .await
-                .context("failed to look up zpool")?;
+        let (_, my_sled) = LookupPath::new(opctx, datastore)
+            .sled_id(my_sled_id)
+            .fetch()
+            .await
+            .context("failed to look up sled")?;
 
-            let my_sled_id = my_zpool.sled_id;
+        rows.push(DownstairsRow {
+            host_serial: my_sled.serial_number().to_string(),
+            region: region.id().to_string(),
+            zone: format!("oxz_crucible_{}", dataset.id()),
+            physical_disk: my_zpool.physical_disk_id.to_string(),
+        });
+    }
 
-            let (_, my_sled) = LookupPath::new(opctx, datastore)
-                .sled_id(my_sled_id)
-                .fetch()
+    if format == OutputFormat::Json {
+        #[derive(Serialize)]
+        struct SnapshotInfoReport {
+            snapshot: SnapshotRow,
+            downstairs: Vec<DownstairsRow>,
+        }
+        return print_json(&SnapshotInfoReport { snapshot, downstairs: rows });
+    }
+
+    let table = tabled::Table::new([snapshot])
+        .with(tabled::settings::Style::empty())
+        .with(tabled::settings::Padding::new(0, 1, 0, 0))
+        .to_string();
+
+    println!("{}", table);
+
+    let table = tabled::Table::new(rows)
+        .with(tabled::settings::Style::empty())
+        .with(tabled::settings::Padding::new(0, 1, 0, 0))
+        .to_string();
+
+    println!("{}", table);
+
+    Ok(())
+}
+
+// Sagas
+
+#[derive(Serialize, Tabled)]
+#[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
+struct SagaRow {
+    id: Uuid,
+    name: String,
+    state: String,
+    current_sec: String,
+    time_created: String,
+}
+
+impl From<Saga> for SagaRow {
+    fn from(saga: Saga) -> Self {
+        SagaRow {
+            id: saga.id.0.into(),
+            name: saga.name,
+            state: saga.saga_state.to_string(),
+            current_sec: saga
+                .current_sec
+                .map(|sec| sec.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            time_created: saga
+                .time_created
+                .to_rfc3339_opts(SecondsFormat::Secs, true),
+        }
+    }
+}
+
+/// Run `omdb db sagas list`.
+async fn cmd_db_sagas_list(
+    datastore: &DataStore,
+    fetch_opts: &DbFetchOptions,
+    args: &SagasListArgs,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    let ctx = || "listing sagas".to_string();
+    let limit = fetch_opts.fetch_limit;
+    let all = args.all;
+
+    let sagas: Vec<Saga> = datastore
+        .pool_connection_for_tests()
+        .await?
+        .transaction_async(|conn| async move {
+            // There's no index that covers listing sagas in order of creation.
+            conn.batch_execute_async(ALLOW_FULL_TABLE_SCAN_SQL).await?;
+
+            use db::schema::saga::dsl;
+            let mut query = dsl::saga.into_boxed();
+            if !all {
+                query =
+                    query.filter(dsl::saga_state.eq_any(vec![
+                        SagaState::Running,
+                        SagaState::Unwinding,
+                    ]));
+            }
+            query
+                .order_by(dsl::time_created)
+                .limit(i64::from(u32::from(limit)))
+                .select(Saga::as_select())
+                .load_async(&conn)
                 .await
-                .context("failed to look up sled")?;
+        })
+        .await
+        .context("loading sagas")?;
 
-            rows.push(DownstairsRow {
-                host_serial: my_sled.serial_number().to_string(),
-                region: region.id().to_string(),
-                zone: format!("oxz_crucible_{}", dataset.id()),
-                physical_disk: my_zpool.physical_disk_id.to_string(),
-            });
+    check_limit(&sagas, limit, ctx);
+
+    let rows = sagas.into_iter().map(SagaRow::from);
+    print_rows(format, rows)
+}
+
+/// Returns the id, name, and label of each node in a saga's DAG, in DAG order.
+fn saga_dag_nodes(
+    saga: &Saga,
+) -> Result<Vec<(u32, String, String)>, anyhow::Error> {
+    let dag: steno::SagaDag = serde_json::from_value(saga.saga_dag.clone())
+        .context("failed to parse saga DAG")?;
+    dag.get_nodes()
+        .map(|node| {
+            let node_id = u32::try_from(node.index().index())
+                .context("saga node index")?;
+            Ok((
+                node_id,
+                node.name().as_ref().to_string(),
+                node.label().to_string(),
+            ))
+        })
+        .collect()
+}
+
+/// Summarizes a saga node's progress from the events recorded for it.
+fn saga_node_state(events: &[&SagaNodeEvent]) -> &'static str {
+    let has = |event_type: &str| {
+        events.iter().any(|event| event.event_type == event_type)
+    };
+    if has("undo_finished") {
+        "undone"
+    } else if has("undo_started") {
+        "undoing"
+    } else if has("failed") {
+        "failed"
+    } else if has("succeeded") {
+        "succeeded"
+    } else if has("started") {
+        "running"
+    } else {
+        "pending"
+    }
+}
+
+/// Run `omdb db sagas show <UUID>`.
+async fn cmd_db_sagas_show(
+    datastore: &DataStore,
+    args: &SagaIdArgs,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    ensure_not_csv(format, "omdb db sagas show")?;
+
+    #[derive(Serialize)]
+    struct SagaReport {
+        id: Uuid,
+        name: String,
+        state: String,
+        creator: Uuid,
+        current_sec: Option<Uuid>,
+        time_created: DateTime<Utc>,
+        adopt_time: DateTime<Utc>,
+        adopt_generation: Generation,
+        nodes: Vec<SagaNodeReport>,
+    }
+
+    #[derive(Serialize)]
+    struct SagaNodeReport {
+        node: u32,
+        name: String,
+        label: String,
+        state: &'static str,
+        events: Vec<SagaNodeEventReport>,
+    }
+
+    #[derive(Serialize)]
+    struct SagaNodeEventReport {
+        event_type: String,
+        event_time: DateTime<Utc>,
+        data: Option<serde_json::Value>,
+    }
+
+    #[derive(Tabled)]
+    #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
+    struct SagaNodeRow {
+        node: u32,
+        name: String,
+        label: String,
+        state: &'static str,
+        last_event: String,
+    }
+
+    let conn = datastore.pool_connection_for_tests().await?;
+    let saga_id = nexus_db_model::saga_types::SagaId::from(
+        steno::SagaId::from(args.saga_id),
+    );
+
+    use db::schema::saga::dsl as saga_dsl;
+    let saga = saga_dsl::saga
+        .filter(saga_dsl::id.eq(saga_id))
+        .select(Saga::as_select())
+        .first_async(&*conn)
+        .await
+        .optional()
+        .context("loading saga")?
+        .ok_or_else(|| anyhow!("no saga with id {}", args.saga_id))?;
+
+    use db::schema::saga_node_event::dsl as event_dsl;
+    let events = event_dsl::saga_node_event
+        .filter(event_dsl::saga_id.eq(saga_id))
+        .order_by(event_dsl::event_time)
+        .select(SagaNodeEvent::as_select())
+        .load_async(&*conn)
+        .await
+        .context("loading saga node events")?;
+
+    let mut events_by_node: BTreeMap<u32, Vec<&SagaNodeEvent>> =
+        BTreeMap::new();
+    for event in &events {
+        events_by_node
+            .entry(u32::from(event.node_id.0))
+            .or_default()
+            .push(event);
+    }
+
+    // Nodes that appear in the DAG are shown in DAG order, even if they
+    // haven't started.  Events for nodes we can't find in the DAG (which
+    // shouldn't happen) are shown afterwards.
+    let mut nodes = saga_dag_nodes(&saga).unwrap_or_else(|error| {
+        eprintln!("warning: {:#}", error);
+        Vec::new()
+    });
+    let known: BTreeSet<u32> = nodes.iter().map(|(id, _, _)| *id).collect();
+    for node_id in events_by_node.keys() {
+        if !known.contains(node_id) {
+            nodes.push((*node_id, "-".to_string(), "-".to_string()));
         }
+    }
 
-        let table = tabled::Table::new(rows)
-            .with(tabled::settings::Style::empty())
-            .with(tabled::settings::Padding::new(0, 1, 0, 0))
-            .to_string();
+    if format == OutputFormat::Json {
+        let nodes = nodes
+            .iter()
+            .map(|(node_id, name, label)| {
+                let node_events = events_by_node
+                    .get(node_id)
+                    .map(|e| e.as_slice())
+                    .unwrap_or(&[]);
+                SagaNodeReport {
+                    node: *node_id,
+                    name: name.clone(),
+                    label: label.clone(),
+                    state: saga_node_state(node_events),
+                    events: node_events
+                        .iter()
+                        .map(|event| SagaNodeEventReport {
+                            event_type: event.event_type.clone(),
+                            event_time: event.event_time,
+                            data: event.data.clone(),
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+        return print_json(&SagaReport {
+            id: args.saga_id,
+            name: saga.name,
+            state: saga.saga_state.to_string(),
+            creator: saga.creator.0,
+            current_sec: saga.current_sec.map(|sec| sec.0),
+            time_created: saga.time_created,
+            adopt_time: saga.adopt_time,
+            adopt_generation: *saga.adopt_generation,
+            nodes,
+        });
+    }
 
-        println!("{}", table);
+    println!("saga {}", args.saga_id);
+    println!("    name:          {}", saga.name);
+    println!("    state:         {}", saga.saga_state);
+    println!("    creator:       {}", saga.creator);
+    println!(
+        "    current SEC:   {}",
+        saga.current_sec
+            .map(|sec| sec.to_string())
+            .unwrap_or_else(|| "-".to_string())
+    );
+    println!("    created:       {}", saga.time_created);
+    println!(
+        "    adopted:       {} (generation {})",
+        saga.adopt_time, *saga.adopt_generation
+    );
+
+    let rows = nodes.iter().map(|(node_id, name, label)| {
+        let node_events =
+            events_by_node.get(node_id).map(|e| e.as_slice()).unwrap_or(&[]);
+        SagaNodeRow {
+            node: *node_id,
+            name: name.clone(),
+            label: label.clone(),
+            state: saga_node_state(node_events),
+            last_event: node_events
+                .last()
+                .map(|event| {
+                    event
+                        .event_time
+                        .to_rfc3339_opts(SecondsFormat::Millis, true)
+                })
+                .unwrap_or_else(|| "-".to_string()),
+        }
+    });
+    let table = tabled::Table::new(rows)
+        .with(tabled::settings::Style::empty())
+        .with(tabled::settings::Padding::new(0, 1, 0, 0))
+        .to_string();
+    println!("\n{}", table);
+
+    if saga.saga_state == SagaState::Unwinding {
+        let undoing: Vec<_> = nodes
+            .iter()
+            .filter(|(node_id, _, _)| {
+                events_by_node.get(node_id).map_or(false, |node_events| {
+                    saga_node_state(node_events) == "undoing"
+                })
+            })
+            .map(|(_, name, _)| name.as_str())
+            .collect();
+        if undoing.is_empty() {
+            println!("\nsaga is unwinding (no undo actions in progress)");
+        } else {
+            println!(
+                "\nsaga is unwinding (undo in progress: {})",
+                undoing.join(", ")
+            );
+        }
+    }
+
+    println!("\nnode outputs:");
+    for (node_id, name, _) in &nodes {
+        let Some(node_events) = events_by_node.get(node_id) else {
+            continue;
+        };
+        for event in node_events {
+            if let Some(data) = &event.data {
+                println!(
+                    "    node {} ({}) {}: {}",
+                    node_id,
+                    name,
+                    event.event_type,
+                    serde_json::to_string(data)
+                        .context("serializing node output")?
+                );
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Run `omdb db sagas abandon <UUID>`.
+async fn cmd_db_sagas_abandon(
+    opctx: &OpContext,
+    datastore: &DataStore,
+    args: &SagaAbandonArgs,
+    _destruction_token: DestructiveOperationToken,
+) -> Result<(), anyhow::Error> {
+    let plan = datastore
+        .saga_abandon_plan(opctx, steno::SagaId::from(args.saga_id))
+        .await
+        .with_context(|| format!("examining saga {}", args.saga_id))?;
+
+    // Abandoning a saga out from under a Nexus that is still executing it
+    // would let that Nexus keep acting on records we're about to release.
+    // Only do that if the operator insists.
+    if let Some(sec) = plan.current_sec {
+        let owner_in_service =
+            match datastore.blueprint_target_get_current_full(opctx).await {
+                Ok((_, blueprint)) => {
+                    lookup_service_info(sec.0, &blueprint).await?.map(|info| {
+                        info.disposition
+                            .matches(BlueprintZoneFilter::ShouldBeRunning)
+                    })
+                }
+                Err(error) if args.force => {
+                    eprintln!(
+                    "WARNING: failed to load the current target blueprint to \
+                     check whether Nexus {} is in service: {}",
+                    sec,
+                    InlineErrorChain::new(&error),
+                );
+                    None
+                }
+                Err(error) => {
+                    return Err(error).context(
+                        "loading current target blueprint to check the saga's \
+                     owner (use --force to skip this check)",
+                    );
+                }
+            };
+        match owner_in_service {
+            Some(true) if !args.force => bail!(
+                "saga {} is owned by Nexus {}, which is still in service; \
+                 make sure that Nexus is not running, then use --force",
+                args.saga_id,
+                sec,
+            ),
+            Some(true) => eprintln!(
+                "WARNING: saga {} is owned by Nexus {}, which is still in \
+                 service",
+                args.saga_id, sec,
+            ),
+            Some(false) | None => (),
+        }
+    }
+
+    println!(
+        "saga {} ({}, {}, owned by {})",
+        args.saga_id,
+        plan.saga_name,
+        plan.saga_state,
+        plan.current_sec
+            .map(|sec| sec.to_string())
+            .unwrap_or_else(|| String::from("-")),
+    );
+    println!("abandoning this saga will:");
+    if plan.instances_to_unlock.is_empty() {
+        println!("    clear no instance migrations");
+    } else {
+        println!("    clear the migration of instances:");
+        for id in &plan.instances_to_unlock {
+            println!("        {}", id);
+        }
+    }
+    if plan.vmms_to_delete.is_empty() {
+        println!("    mark no VMM records destroyed");
+    } else {
+        println!("    mark these VMM records destroyed:");
+        for vmm in &plan.vmms_to_delete {
+            println!(
+                "        {} (instance {}, sled {})",
+                vmm.id, vmm.instance_id, vmm.sled_id
+            );
+        }
     }
+    if plan.sled_reservations_to_delete.is_empty() {
+        println!("    delete no sled resource reservations");
+    } else {
+        println!("    delete these sled resource reservations:");
+        for id in &plan.sled_reservations_to_delete {
+            println!("        {}", id);
+        }
+    }
+    if !plan.vmms_to_delete.is_empty() {
+        eprintln!(
+            "WARNING: make sure that the sled agent on each of the sleds \
+             above no longer has the VMM listed for it.  Marking a VMM that \
+             still exists destroyed leaks its resources on that sled."
+        );
+    }
+
+    let mut line_editor = Reedline::create();
+    let prompt = DefaultPrompt::new(
+        DefaultPromptSegment::Basic(String::from("abandon saga? y/N")),
+        DefaultPromptSegment::Empty,
+    );
+    let confirmed = matches!(
+        line_editor.read_line(&prompt),
+        Ok(reedline::Signal::Success(input)) if input == "y"
+    );
+    if !confirmed {
+        eprintln!("abandonment not confirmed: aborting");
+        return Ok(());
+    }
+
+    datastore
+        .saga_abandon(opctx, &plan)
+        .await
+        .with_context(|| format!("abandoning saga {}", args.saga_id))?;
+    println!("abandoned saga {}", args.saga_id);
 
     Ok(())
 }
@@ -1447,7 +2132,7 @@ struct ServiceInstanceSledRow {
     addr: String,
 }
 
-#[derive(Tabled)]
+#[derive(Serialize, Tabled)]
 #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
 struct SledRow {
     serial: String,
@@ -1477,6 +2162,7 @@ async fn cmd_db_sleds(
     datastore: &DataStore,
     fetch_opts: &DbFetchOptions,
     args: &SledsArgs,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
     let limit = fetch_opts.fetch_limit;
     let filter = match args.filter {
@@ -1497,6 +2183,9 @@ async fn cmd_db_sleds(
     check_limit(&sleds, limit, || String::from("listing sleds"));
 
     let rows = sleds.into_iter().map(|s| SledRow::from(s));
+    if format != OutputFormat::Table {
+        return print_rows(format, rows);
+    }
     let table = tabled::Table::new(rows)
         .with(tabled::settings::Style::empty())
         .with(tabled::settings::Padding::new(1, 1, 0, 0))
@@ -1507,7 +2196,7 @@ async fn cmd_db_sleds(
     Ok(())
 }
 
-#[derive(Tabled)]
+#[derive(Serialize, Tabled)]
 #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
 struct CustomerInstanceRow {
     id: String,
@@ -1524,6 +2213,7 @@ async fn cmd_db_instances(
     datastore: &DataStore,
     fetch_opts: &DbFetchOptions,
     running: bool,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
     use db::schema::instance::dsl;
     use db::schema::vmm::dsl as vmm_dsl;
@@ -1534,73 +2224,1214 @@ async fn cmd_db_instances(
         query = query.filter(dsl::time_deleted.is_null());
     }
 
-    let instances: Vec<InstanceAndActiveVmm> = query
-        .left_join(
-            vmm_dsl::vmm.on(vmm_dsl::id
-                .nullable()
-                .eq(dsl::active_propolis_id)
-                .and(vmm_dsl::time_deleted.is_null())),
-        )
-        .limit(i64::from(u32::from(limit)))
-        .select((Instance::as_select(), Option::<Vmm>::as_select()))
-        .load_async(&*datastore.pool_connection_for_tests().await?)
-        .await
-        .context("loading instances")?
-        .into_iter()
-        .map(|i: (Instance, Option<Vmm>)| i.into())
-        .collect();
+    let instances: Vec<InstanceAndActiveVmm> = query
+        .left_join(
+            vmm_dsl::vmm.on(vmm_dsl::id
+                .nullable()
+                .eq(dsl::active_propolis_id)
+                .and(vmm_dsl::time_deleted.is_null())),
+        )
+        .limit(i64::from(u32::from(limit)))
+        .select((Instance::as_select(), Option::<Vmm>::as_select()))
+        .load_async(&*datastore.pool_connection_for_tests().await?)
+        .await
+        .context("loading instances")?
+        .into_iter()
+        .map(|i: (Instance, Option<Vmm>)| i.into())
+        .collect();
+
+    let ctx = || "listing instances".to_string();
+    check_limit(&instances, limit, ctx);
+
+    let mut rows = Vec::new();
+    let mut h_to_s: HashMap<Uuid, String> = HashMap::new();
+
+    for i in instances {
+        let host_serial = if i.vmm().is_some() {
+            if let std::collections::hash_map::Entry::Vacant(e) =
+                h_to_s.entry(i.sled_id().unwrap())
+            {
+                let (_, my_sled) = LookupPath::new(opctx, datastore)
+                    .sled_id(i.sled_id().unwrap())
+                    .fetch()
+                    .await
+                    .context("failed to look up sled")?;
+
+                let host_serial = my_sled.serial_number().to_string();
+                e.insert(host_serial.to_string());
+                host_serial.to_string()
+            } else {
+                h_to_s.get(&i.sled_id().unwrap()).unwrap().to_string()
+            }
+        } else {
+            "-".to_string()
+        };
+
+        if running && i.effective_state() != InstanceState::Running {
+            continue;
+        }
+
+        let cir = CustomerInstanceRow {
+            id: i.instance().id().to_string(),
+            name: i.instance().name().to_string(),
+            state: i.effective_state().to_string(),
+            propolis_id: (&i).into(),
+            sled_id: (&i).into(),
+            host_serial,
+        };
+
+        rows.push(cir);
+    }
+
+    print_rows(format, rows)
+}
+
+/// Everything `omdb db instance show` reports about an instance
+#[derive(Serialize)]
+struct InstanceReport {
+    id: Uuid,
+    name: String,
+    project_id: Uuid,
+    hostname: String,
+    ncpus: u16,
+    memory_bytes: u64,
+    time_created: DateTime<Utc>,
+    time_deleted: Option<DateTime<Utc>>,
+    state: String,
+    state_generation: Generation,
+    time_state_updated: DateTime<Utc>,
+    active_propolis_id: Option<Uuid>,
+    target_propolis_id: Option<Uuid>,
+    migration_id: Option<Uuid>,
+    vmms: Vec<InstanceVmmRow>,
+    migrations: Vec<InstanceMigrationRow>,
+    nics: Vec<InstanceNicRow>,
+    external_ips: Vec<InstanceExternalIpRow>,
+    disks: Vec<InstanceDiskRow>,
+    regions: Vec<InstanceRegionRow>,
+    sagas: Vec<InstanceSagaRow>,
+}
+
+#[derive(Serialize, Tabled)]
+#[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
+struct InstanceVmmRow {
+    id: Uuid,
+    role: &'static str,
+    state: String,
+    #[tabled(rename = "GEN")]
+    generation: Generation,
+    sled_id: Uuid,
+    host_serial: String,
+    propolis_address: String,
+    #[tabled(display_with = "display_timestamp")]
+    time_created: DateTime<Utc>,
+    #[tabled(display_with = "display_timestamp_blank")]
+    time_deleted: Option<DateTime<Utc>>,
+}
+
+#[derive(Serialize, Tabled)]
+#[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
+struct InstanceMigrationRow {
+    #[tabled(display_with = "display_option_blank")]
+    migration_id: Option<Uuid>,
+    #[tabled(display_with = "display_option_blank")]
+    source_vmm: Option<Uuid>,
+    #[tabled(display_with = "display_option_blank")]
+    target_vmm: Option<Uuid>,
+    #[tabled(display_with = "display_option_blank")]
+    saga_id: Option<Uuid>,
+    #[tabled(display_with = "display_option_blank")]
+    saga_state: Option<String>,
+}
+
+#[derive(Serialize, Tabled)]
+#[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
+struct InstanceNicRow {
+    id: Uuid,
+    name: String,
+    ip: String,
+    mac: String,
+    slot: u8,
+    primary: bool,
+    vpc_id: Uuid,
+    subnet_id: Uuid,
+}
+
+#[derive(Serialize, Tabled)]
+#[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
+struct InstanceExternalIpRow {
+    id: Uuid,
+    ip: String,
+    kind: String,
+    ports: String,
+    state: String,
+    #[tabled(display_with = "display_option_blank")]
+    name: Option<String>,
+}
+
+#[derive(Serialize, Tabled)]
+#[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
+struct InstanceDiskRow {
+    id: Uuid,
+    name: String,
+    state: String,
+    #[tabled(display_with = "display_option_blank")]
+    slot: Option<u8>,
+    volume_id: Uuid,
+}
+
+#[derive(Serialize, Tabled)]
+#[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
+struct InstanceRegionRow {
+    disk_id: Uuid,
+    region_id: Uuid,
+    dataset_id: Uuid,
+    dataset_address: String,
+    zpool_id: Uuid,
+    physical_disk_id: Uuid,
+    host_serial: String,
+}
+
+#[derive(Serialize, Tabled)]
+#[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
+struct InstanceSagaRow {
+    id: Uuid,
+    name: String,
+    state: String,
+    #[tabled(display_with = "display_timestamp")]
+    time_created: DateTime<Utc>,
+}
+
+/// Looks up the serial number of a sled, remembering it in `cache`.
+async fn sled_serial_cached(
+    opctx: &OpContext,
+    datastore: &DataStore,
+    cache: &mut BTreeMap<Uuid, String>,
+    sled_id: Uuid,
+) -> String {
+    if let Some(serial) = cache.get(&sled_id) {
+        return serial.clone();
+    }
+
+    let serial = match LookupPath::new(opctx, datastore)
+        .sled_id(sled_id)
+        .fetch()
+        .await
+    {
+        Ok((_, sled)) => sled.serial_number().to_string(),
+        Err(_) => "<unknown sled>".to_string(),
+    };
+    cache.insert(sled_id, serial.clone());
+    serial
+}
+
+/// Reconstructs an instance's migrations from its `instance-migrate` sagas.
+///
+/// The migration id, target VMM, and source VMM are recovered from the
+/// outputs of the nodes that generated or recorded them, so a migration whose
+/// saga didn't get far enough to record one of these will be missing it.
+async fn instance_migrations(
+    datastore: &DataStore,
+    sagas: &[Saga],
+) -> Result<Vec<InstanceMigrationRow>, anyhow::Error> {
+    let migrate_sagas: Vec<&Saga> =
+        sagas.iter().filter(|saga| saga.name == "instance-migrate").collect();
+    if migrate_sagas.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    use db::schema::saga_node_event::dsl;
+    let events = dsl::saga_node_event
+        .filter(dsl::saga_id.eq_any(
+            migrate_sagas.iter().map(|saga| saga.id).collect::<Vec<_>>(),
+        ))
+        .filter(dsl::event_type.eq("succeeded"))
+        .select(SagaNodeEvent::as_select())
+        .load_async(&*datastore.pool_connection_for_tests().await?)
+        .await
+        .context("loading migration saga events")?;
+
+    let mut rows = Vec::new();
+    for saga in migrate_sagas {
+        let Ok(nodes) = saga_dag_nodes(saga) else {
+            continue;
+        };
+        let output = |node_name: &str| {
+            let (node_id, _, _) =
+                nodes.iter().find(|(_, name, _)| name == node_name)?;
+            events
+                .iter()
+                .find(|event| {
+                    event.saga_id == saga.id
+                        && u32::from(event.node_id.0) == *node_id
+                })
+                .and_then(|event| event.data.as_ref())
+        };
+        let as_uuid = |value: Option<&serde_json::Value>| {
+            value.and_then(|v| v.as_str()).and_then(|s| s.parse::<Uuid>().ok())
+        };
+
+        rows.push(InstanceMigrationRow {
+            migration_id: as_uuid(output("migrate_id")),
+            source_vmm: as_uuid(
+                output("set_migration_ids")
+                    .and_then(|v| v.pointer("/runtime_state/propolis_id")),
+            ),
+            target_vmm: as_uuid(output("dst_propolis_id")),
+            saga_id: Some(saga.id.0.into()),
+            saga_state: Some(saga.saga_state.to_string()),
+        });
+    }
+
+    Ok(rows)
+}
+
+/// Run `omdb db instance show <UUID>`.
+async fn cmd_db_instance_show(
+    opctx: &OpContext,
+    datastore: &DataStore,
+    fetch_opts: &DbFetchOptions,
+    args: &InstanceShowArgs,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    ensure_not_csv(format, "omdb db instance show")?;
+    let instance_id = args.id;
+    let limit = fetch_opts.fetch_limit;
+    let conn = datastore.pool_connection_for_tests().await?;
+    let mut sled_serials = BTreeMap::new();
+
+    use db::schema::instance::dsl as instance_dsl;
+    let instance = instance_dsl::instance
+        .filter(instance_dsl::id.eq(instance_id))
+        .select(Instance::as_select())
+        .first_async(&*conn)
+        .await
+        .optional()
+        .context("loading instance")?
+        .ok_or_else(|| anyhow!("no instance with id {}", instance_id))?;
+    let runtime = instance.runtime();
+
+    // Every VMM that has ever incarnated this instance, including destroyed
+    // ones, oldest first.
+    use db::schema::vmm::dsl as vmm_dsl;
+    let vmms = vmm_dsl::vmm
+        .filter(vmm_dsl::instance_id.eq(instance_id))
+        .order_by(vmm_dsl::time_created)
+        .limit(i64::from(u32::from(limit)))
+        .select(Vmm::as_select())
+        .load_async(&*conn)
+        .await
+        .context("loading VMMs")?;
+    check_limit(&vmms, limit, || "listing VMMs".to_string());
+    let mut vmm_rows = Vec::with_capacity(vmms.len());
+    for vmm in vmms {
+        let role = if Some(vmm.id) == runtime.propolis_id {
+            "active"
+        } else if Some(vmm.id) == runtime.dst_propolis_id {
+            "target"
+        } else {
+            ""
+        };
+        vmm_rows.push(InstanceVmmRow {
+            id: vmm.id,
+            role,
+            state: vmm.runtime.state.to_string(),
+            generation: *vmm.runtime.gen,
+            sled_id: vmm.sled_id,
+            host_serial: sled_serial_cached(
+                opctx,
+                datastore,
+                &mut sled_serials,
+                vmm.sled_id,
+            )
+            .await,
+            propolis_address: format!(
+                "[{}]:{}",
+                vmm.propolis_ip.ip(),
+                *vmm.propolis_port
+            ),
+            time_created: vmm.time_created,
+            time_deleted: vmm.time_deleted,
+        });
+    }
+
+    use db::schema::network_interface::dsl as nic_dsl;
+    let nics = nic_dsl::network_interface
+        .filter(nic_dsl::parent_id.eq(instance_id))
+        .filter(nic_dsl::kind.eq(NetworkInterfaceKind::Instance))
+        .filter(nic_dsl::time_deleted.is_null())
+        .order_by(nic_dsl::slot)
+        .select(NetworkInterface::as_select())
+        .load_async(&*conn)
+        .await
+        .context("loading network interfaces")?;
+    let nic_rows = nics
+        .into_iter()
+        .map(|nic| InstanceNicRow {
+            id: nic.id(),
+            name: nic.name().to_string(),
+            ip: nic.ip.ip().to_string(),
+            mac: nic.mac.to_string(),
+            slot: *nic.slot,
+            primary: nic.primary,
+            vpc_id: nic.vpc_id,
+            subnet_id: nic.subnet_id,
+        })
+        .collect();
+
+    use db::schema::external_ip::dsl as ip_dsl;
+    let ips = ip_dsl::external_ip
+        .filter(ip_dsl::parent_id.eq(instance_id))
+        .filter(ip_dsl::time_deleted.is_null())
+        .select(ExternalIp::as_select())
+        .load_async(&*conn)
+        .await
+        .context("loading external IPs")?;
+    let ip_rows = ips
+        .into_iter()
+        .map(|ip| InstanceExternalIpRow {
+            id: ip.id,
+            ip: ip.ip.ip().to_string(),
+            kind: ip.kind.to_string(),
+            ports: format!("{}-{}", *ip.first_port, *ip.last_port),
+            state: ip.state.to_string(),
+            name: ip.name.map(|name| name.to_string()),
+        })
+        .collect();
+
+    use db::schema::disk::dsl as disk_dsl;
+    let disks = disk_dsl::disk
+        .filter(disk_dsl::attach_instance_id.eq(instance_id))
+        .filter(disk_dsl::time_deleted.is_null())
+        .order_by(disk_dsl::slot)
+        .select(Disk::as_select())
+        .load_async(&*conn)
+        .await
+        .context("loading attached disks")?;
+    let mut disk_rows = Vec::with_capacity(disks.len());
+    let mut region_rows = Vec::new();
+    for disk in disks {
+        for (dataset, region) in
+            datastore.get_allocated_regions(disk.volume_id).await?
+        {
+            let (_, zpool) = LookupPath::new(opctx, datastore)
+                .zpool_id(dataset.pool_id)
+                .fetch()
+                .await
+                .context("failed to look up zpool")?;
+            region_rows.push(InstanceRegionRow {
+                disk_id: disk.id(),
+                region_id: region.id(),
+                dataset_id: dataset.id(),
+                dataset_address: dataset.address().to_string(),
+                zpool_id: dataset.pool_id,
+                physical_disk_id: zpool.physical_disk_id,
+                host_serial: sled_serial_cached(
+                    opctx,
+                    datastore,
+                    &mut sled_serials,
+                    zpool.sled_id,
+                )
+                .await,
+            });
+        }
+        disk_rows.push(InstanceDiskRow {
+            id: disk.id(),
+            name: disk.name().to_string(),
+            state: disk.runtime_state.disk_state.clone(),
+            slot: disk.slot.map(|slot| *slot),
+            volume_id: disk.volume_id,
+        });
+    }
+
+    // Sagas record their parameters (which include the instance's id) in
+    // their DAG.  There's no index for this, so it requires a full scan of
+    // the saga table and a text search of every DAG in it.  The most recent
+    // sagas are the interesting ones, so those are the ones kept if there are
+    // more than the fetch limit.
+    let sagas: Vec<Saga> = if args.no_sagas {
+        Vec::new()
+    } else {
+        eprintln!(
+            "note: scanning the entire saga table for this instance's sagas \
+             (use --no-sagas to skip this)"
+        );
+        let sagas = datastore
+            .pool_connection_for_tests()
+            .await?
+            .transaction_async(|conn| async move {
+                conn.batch_execute_async(ALLOW_FULL_TABLE_SCAN_SQL).await?;
+
+                use db::schema::saga::dsl;
+                dsl::saga
+                    .filter(diesel::dsl::sql::<diesel::sql_types::Bool>(
+                        &format!("saga_dag::STRING LIKE '%{}%'", instance_id),
+                    ))
+                    .order_by(dsl::time_created.desc())
+                    .limit(i64::from(u32::from(limit)))
+                    .select(Saga::as_select())
+                    .load_async(&conn)
+                    .await
+            })
+            .await
+            .context("loading sagas")?;
+        check_limit(&sagas, limit, || "listing sagas".to_string());
+        sagas
+    };
+
+    let mut migrations = instance_migrations(datastore, &sagas).await?;
+    if let Some(migration_id) = runtime.migration_id {
+        if !migrations.iter().any(|m| m.migration_id == Some(migration_id)) {
+            migrations.push(InstanceMigrationRow {
+                migration_id: Some(migration_id),
+                source_vmm: runtime.propolis_id,
+                target_vmm: runtime.dst_propolis_id,
+                saga_id: None,
+                saga_state: None,
+            });
+        }
+    }
+
+    let saga_rows = sagas
+        .into_iter()
+        .map(|saga| InstanceSagaRow {
+            id: saga.id.0.into(),
+            name: saga.name,
+            state: saga.saga_state.to_string(),
+            time_created: saga.time_created,
+        })
+        .collect();
+
+    let report = InstanceReport {
+        id: instance.id(),
+        name: instance.name().to_string(),
+        project_id: instance.project_id,
+        hostname: instance.hostname.clone(),
+        ncpus: instance.ncpus.0 .0,
+        memory_bytes: instance.memory.to_bytes(),
+        time_created: instance.time_created(),
+        time_deleted: instance.time_deleted(),
+        state: runtime.nexus_state.to_string(),
+        state_generation: *runtime.gen,
+        time_state_updated: runtime.time_updated,
+        active_propolis_id: runtime.propolis_id,
+        target_propolis_id: runtime.dst_propolis_id,
+        migration_id: runtime.migration_id,
+        vmms: vmm_rows,
+        migrations,
+        nics: nic_rows,
+        external_ips: ip_rows,
+        disks: disk_rows,
+        regions: region_rows,
+        sagas: saga_rows,
+    };
+
+    if format == OutputFormat::Json {
+        return print_json(&report);
+    }
+
+    let display_id =
+        |id: Option<Uuid>| id.map(|id| id.to_string()).unwrap_or_default();
+    println!("instance {} ({})", report.id, report.name);
+    println!("    project:            {}", report.project_id);
+    println!("    hostname:           {}", report.hostname);
+    println!(
+        "    size:               {} vCPUs, {} bytes",
+        report.ncpus, report.memory_bytes
+    );
+    println!(
+        "    created:            {}",
+        display_timestamp(&report.time_created)
+    );
+    if let Some(time_deleted) = &report.time_deleted {
+        println!("    deleted:            {}", display_timestamp(time_deleted));
+    }
+    println!(
+        "    state:              {} (generation {}, updated {})",
+        report.state,
+        report.state_generation,
+        display_timestamp(&report.time_state_updated)
+    );
+    println!(
+        "    active propolis:    {}",
+        display_id(report.active_propolis_id)
+    );
+    println!(
+        "    target propolis:    {}",
+        display_id(report.target_propolis_id)
+    );
+    println!("    migration:          {}", display_id(report.migration_id));
+
+    fn print_table<T: Tabled>(title: &str, rows: &[T]) {
+        println!("\n{}", title);
+        if rows.is_empty() {
+            println!("    (none)");
+            return;
+        }
+        let table = tabled::Table::new(rows)
+            .with(tabled::settings::Style::empty())
+            .with(tabled::settings::Padding::new(0, 1, 0, 0))
+            .to_string();
+        println!("{}", table);
+    }
+
+    print_table("VMMS", &report.vmms);
+    print_table("MIGRATIONS", &report.migrations);
+    print_table("NETWORK INTERFACES", &report.nics);
+    print_table("EXTERNAL IPS", &report.external_ips);
+    print_table("DISKS", &report.disks);
+    print_table("REGIONS", &report.regions);
+    if args.no_sagas {
+        println!("\nSAGAS\n    (skipped because of --no-sagas)");
+    } else {
+        print_table("SAGAS", &report.sagas);
+    }
+
+    Ok(())
+}
+
+// Volumes
+
+/// The control plane's records of Crucible storage, used to resolve volume
+/// targets to the hardware backing them
+///
+/// These tables are small (one row per sled, disk, or Crucible dataset), so
+/// they're loaded in their entirety, including deleted records.
+struct CrucibleStorage {
+    datasets: BTreeMap<Uuid, Dataset>,
+    zpools: BTreeMap<Uuid, Zpool>,
+    physical_disks: BTreeMap<Uuid, PhysicalDisk>,
+    sleds: BTreeMap<Uuid, Sled>,
+}
+
+impl CrucibleStorage {
+    async fn load(datastore: &DataStore) -> Result<Self, anyhow::Error> {
+        let (datasets, zpools, physical_disks, sleds) = datastore
+            .pool_connection_for_tests()
+            .await?
+            .transaction_async(|conn| async move {
+                conn.batch_execute_async(ALLOW_FULL_TABLE_SCAN_SQL).await?;
+
+                use db::schema::dataset::dsl as dataset_dsl;
+                use db::schema::physical_disk::dsl as disk_dsl;
+                use db::schema::sled::dsl as sled_dsl;
+                use db::schema::zpool::dsl as zpool_dsl;
+                let datasets = dataset_dsl::dataset
+                    .filter(dataset_dsl::kind.eq(DatasetKind::Crucible))
+                    .select(Dataset::as_select())
+                    .load_async(&conn)
+                    .await?;
+                let zpools = zpool_dsl::zpool
+                    .select(Zpool::as_select())
+                    .load_async(&conn)
+                    .await?;
+                let physical_disks = disk_dsl::physical_disk
+                    .select(PhysicalDisk::as_select())
+                    .load_async(&conn)
+                    .await?;
+                let sleds = sled_dsl::sled
+                    .select(Sled::as_select())
+                    .load_async(&conn)
+                    .await?;
+                Ok((datasets, zpools, physical_disks, sleds))
+            })
+            .await
+            .context("loading storage records")?;
+
+        Ok(CrucibleStorage {
+            datasets: datasets
+                .into_iter()
+                .map(|dataset| (dataset.id(), dataset))
+                .collect(),
+            zpools: zpools
+                .into_iter()
+                .map(|zpool| (zpool.id(), zpool))
+                .collect(),
+            physical_disks: physical_disks
+                .into_iter()
+                .map(|disk| (disk.id(), disk))
+                .collect(),
+            sleds: sleds.into_iter().map(|sled| (sled.id(), sled)).collect(),
+        })
+    }
+
+    /// Returns the Crucible datasets listening on the given address
+    ///
+    /// On a real rack this is at most one dataset, but simulated sleds run
+    /// all of their Crucible datasets on the same address.
+    fn datasets_with_ip(&self, ip: &Ipv6Addr) -> Vec<&Dataset> {
+        self.datasets
+            .values()
+            .filter(|dataset| dataset.address().ip() == ip)
+            .collect()
+    }
+
+    /// Describes where a dataset lives, noting any part of its backing storage
+    /// that is missing, deleted, or expunged.
+    fn describe_dataset(
+        &self,
+        dataset: &Dataset,
+        problems: &mut Vec<String>,
+    ) -> String {
+        if dataset.time_deleted().is_some() {
+            problems.push(format!("dataset {} is deleted", dataset.id()));
+        }
+
+        let Some(zpool) = self.zpools.get(&dataset.pool_id) else {
+            problems.push(format!("zpool {} not found", dataset.pool_id));
+            return format!(
+                "dataset {}, zpool {} (missing)",
+                dataset.id(),
+                dataset.pool_id
+            );
+        };
+        if zpool.time_deleted().is_some() {
+            problems.push(format!("zpool {} is deleted", zpool.id()));
+        }
+
+        match self.physical_disks.get(&zpool.physical_disk_id) {
+            None => problems.push(format!(
+                "physical disk {} not found",
+                zpool.physical_disk_id
+            )),
+            Some(disk) => {
+                if disk.time_deleted().is_some() {
+                    problems.push(format!(
+                        "physical disk {} is deleted",
+                        disk.id()
+                    ));
+                }
+                if disk.disk_policy == PhysicalDiskPolicy::Expunged {
+                    problems.push(format!(
+                        "physical disk {} is expunged",
+                        disk.id()
+                    ));
+                }
+            }
+        }
+
+        let sled_serial = match self.sleds.get(&zpool.sled_id) {
+            None => {
+                problems.push(format!("sled {} not found", zpool.sled_id));
+                "<unknown sled>".to_string()
+            }
+            Some(sled) => {
+                if sled.policy() == SledPolicy::Expunged {
+                    problems.push(format!(
+                        "sled {} ({}) is expunged",
+                        sled.id(),
+                        sled.serial_number()
+                    ));
+                }
+                sled.serial_number().to_string()
+            }
+        };
+
+        format!(
+            "dataset {}, zpool {}, physical disk {}, sled {} ({})",
+            dataset.id(),
+            zpool.id(),
+            zpool.physical_disk_id,
+            zpool.sled_id,
+            sled_serial,
+        )
+    }
+}
+
+/// A Crucible target found in a volume construction request
+struct VolumeTarget {
+    target: String,
+    read_only: bool,
+    /// id of the volume record, followed by the ids of the volumes enclosing
+    /// this target, innermost last
+    volume_ids: Vec<Uuid>,
+}
+
+fn volume_targets(
+    vcr: &VolumeConstructionRequest,
+    enclosing: &mut Vec<Uuid>,
+    targets: &mut Vec<VolumeTarget>,
+) {
+    match vcr {
+        VolumeConstructionRequest::Volume {
+            id,
+            sub_volumes,
+            read_only_parent,
+            ..
+        } => {
+            enclosing.push(*id);
+            for sub_volume in sub_volumes {
+                volume_targets(sub_volume, enclosing, targets);
+            }
+            if let Some(read_only_parent) = read_only_parent {
+                volume_targets(read_only_parent, enclosing, targets);
+            }
+            enclosing.pop();
+        }
+        VolumeConstructionRequest::Region { opts, .. } => {
+            for target in &opts.target {
+                targets.push(VolumeTarget {
+                    target: target.clone(),
+                    read_only: opts.read_only,
+                    volume_ids: enclosing.clone(),
+                });
+            }
+        }
+        VolumeConstructionRequest::Url { .. }
+        | VolumeConstructionRequest::File { .. } => (),
+    }
+}
+
+/// The control plane's record of what backs a single Crucible target
+#[derive(Serialize)]
+struct TargetBacking {
+    record: String,
+    location: String,
+    problems: Vec<String>,
+}
+
+async fn resolve_volume_target(
+    conn: &DataStoreConnection<'_>,
+    storage: &CrucibleStorage,
+    target: &VolumeTarget,
+) -> Result<TargetBacking, anyhow::Error> {
+    let mut problems = Vec::new();
+
+    let Ok(addr) = target.target.parse::<SocketAddrV6>() else {
+        problems.push("target is not an IPv6 socket address".to_string());
+        return Ok(TargetBacking {
+            record: "-".to_string(),
+            location: "-".to_string(),
+            problems,
+        });
+    };
+    let candidates = storage.datasets_with_ip(addr.ip());
+    if candidates.is_empty() {
+        problems.push("no Crucible dataset has this address".to_string());
+        return Ok(TargetBacking {
+            record: "-".to_string(),
+            location: "-".to_string(),
+            problems,
+        });
+    }
+
+    // Read-only targets are usually running snapshots, which are recorded
+    // by their address.  Otherwise, look for a region on one of these
+    // datasets that belongs to the volume or one of the volumes enclosing the
+    // target.
+    use db::schema::region_snapshot::dsl as snapshot_dsl;
+    let region_snapshot = if target.read_only {
+        snapshot_dsl::region_snapshot
+            .filter(snapshot_dsl::snapshot_addr.eq(target.target.clone()))
+            .select(RegionSnapshot::as_select())
+            .first_async(&**conn)
+            .await
+            .optional()
+            .context("loading region snapshot")?
+    } else {
+        None
+    };
+
+    let (record, dataset_id) = if let Some(region_snapshot) = region_snapshot {
+        if region_snapshot.deleting {
+            problems.push(format!(
+                "region snapshot of region {} is being deleted",
+                region_snapshot.region_id
+            ));
+        }
+        let record = format!(
+            "snapshot {} of region {}",
+            region_snapshot.snapshot_id, region_snapshot.region_id
+        );
+        (record, Some(region_snapshot.dataset_id))
+    } else {
+        use db::schema::region::dsl as region_dsl;
+        let region = region_dsl::region
+            .filter(
+                region_dsl::dataset_id.eq_any(
+                    candidates
+                        .iter()
+                        .map(|dataset| dataset.id())
+                        .collect::<Vec<_>>(),
+                ),
+            )
+            .filter(region_dsl::volume_id.eq_any(target.volume_ids.clone()))
+            .select(Region::as_select())
+            .first_async(&**conn)
+            .await
+            .optional()
+            .context("loading region")?;
+        match region {
+            Some(region) => {
+                (format!("region {}", region.id()), Some(region.dataset_id()))
+            }
+            None => {
+                problems.push(
+                    "no region or region snapshot record for this target"
+                        .to_string(),
+                );
+                ("-".to_string(), None)
+            }
+        }
+    };
+
+    // Without a record, the dataset is only known if its address is unique.
+    let dataset = match dataset_id {
+        Some(id) => storage.datasets.get(&id),
+        None if candidates.len() == 1 => Some(candidates[0]),
+        None => None,
+    };
+    let location = match dataset {
+        Some(dataset) => storage.describe_dataset(dataset, &mut problems),
+        None => "-".to_string(),
+    };
+    Ok(TargetBacking { record, location, problems })
+}
+
+fn print_volume_construction_request(
+    vcr: &VolumeConstructionRequest,
+    depth: usize,
+    backings: &BTreeMap<String, TargetBacking>,
+) {
+    let indent = "    ".repeat(depth);
+    match vcr {
+        VolumeConstructionRequest::Volume {
+            id,
+            block_size,
+            sub_volumes,
+            read_only_parent,
+        } => {
+            println!("{indent}volume {id} (block size {block_size})");
+            for (i, sub_volume) in sub_volumes.iter().enumerate() {
+                println!("{indent}    sub-volume {i}:");
+                print_volume_construction_request(
+                    sub_volume,
+                    depth + 2,
+                    backings,
+                );
+            }
+            if let Some(read_only_parent) = read_only_parent {
+                println!("{indent}    read-only parent:");
+                print_volume_construction_request(
+                    read_only_parent,
+                    depth + 2,
+                    backings,
+                );
+            }
+        }
+        VolumeConstructionRequest::Region {
+            blocks_per_extent,
+            extent_count,
+            opts,
+            gen,
+            ..
+        } => {
+            println!(
+                "{indent}region set {} (generation {gen}, {}, \
+                {extent_count} extents of {blocks_per_extent} blocks)",
+                opts.id,
+                if opts.read_only { "read-only" } else { "read-write" },
+            );
+            for target in &opts.target {
+                println!("{indent}    target {target}");
+                let Some(backing) = backings.get(target) else {
+                    continue;
+                };
+                println!("{indent}        {}", backing.record);
+                println!("{indent}        {}", backing.location);
+                for problem in &backing.problems {
+                    println!("{indent}        WARNING: {problem}");
+                }
+            }
+        }
+        VolumeConstructionRequest::Url { id, url, .. } => {
+            println!("{indent}url {id}: {url}");
+        }
+        VolumeConstructionRequest::File { id, path, .. } => {
+            println!("{indent}file {id}: {path}");
+        }
+    }
+}
+
+/// Run `omdb db volume show <UUID>`.
+async fn cmd_db_volume_show(
+    datastore: &DataStore,
+    args: &VolumeShowArgs,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    ensure_not_csv(format, "omdb db volume show")?;
+
+    #[derive(Serialize)]
+    struct VolumeReport {
+        id: Uuid,
+        time_deleted: Option<DateTime<Utc>>,
+        volume_construction_request: VolumeConstructionRequest,
+        targets: Vec<VolumeTargetReport>,
+    }
+
+    #[derive(Serialize)]
+    struct VolumeTargetReport {
+        target: String,
+        read_only: bool,
+        volume_ids: Vec<Uuid>,
+        backing: TargetBacking,
+    }
+
+    let conn = datastore.pool_connection_for_tests().await?;
+
+    use db::schema::volume::dsl;
+    let volume = dsl::volume
+        .filter(dsl::id.eq(args.uuid))
+        .select(Volume::as_select())
+        .first_async(&*conn)
+        .await
+        .optional()
+        .context("loading volume")?
+        .ok_or_else(|| anyhow!("no volume with id {}", args.uuid))?;
+    if let Some(time_deleted) = volume.time_deleted {
+        if format != OutputFormat::Json {
+            println!("note: volume was deleted at {}", time_deleted);
+        }
+    }
+
+    let vcr: VolumeConstructionRequest = serde_json::from_str(volume.data())
+        .context("parsing volume construction request")?;
+
+    let storage = CrucibleStorage::load(datastore).await?;
+    let mut targets = Vec::new();
+    // Regions are allocated for the volume record, whose id need not match
+    // the id in its construction request.
+    volume_targets(&vcr, &mut vec![args.uuid], &mut targets);
+    let mut backings = BTreeMap::new();
+    for target in &targets {
+        let backing = resolve_volume_target(&conn, &storage, target).await?;
+        backings.insert(target.target.clone(), backing);
+    }
+
+    if format == OutputFormat::Json {
+        let targets = targets
+            .into_iter()
+            .filter_map(|target| {
+                let backing = backings.remove(&target.target)?;
+                Some(VolumeTargetReport {
+                    target: target.target,
+                    read_only: target.read_only,
+                    volume_ids: target.volume_ids,
+                    backing,
+                })
+            })
+            .collect();
+        return print_json(&VolumeReport {
+            id: args.uuid,
+            time_deleted: volume.time_deleted,
+            volume_construction_request: vcr,
+            targets,
+        });
+    }
+
+    print_volume_construction_request(&vcr, 0, &backings);
+
+    let nproblems = backings
+        .values()
+        .filter(|backing| !backing.problems.is_empty())
+        .count();
+    println!(
+        "\n{} target(s), {} with missing or expunged backing storage",
+        backings.len(),
+        nproblems
+    );
+
+    Ok(())
+}
 
-    let ctx = || "listing instances".to_string();
-    check_limit(&instances, limit, ctx);
+/// Run `omdb db volume affected`.
+async fn cmd_db_volume_affected(
+    datastore: &DataStore,
+    fetch_opts: &DbFetchOptions,
+    args: &VolumeAffectedArgs,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    #[derive(Serialize, Tabled)]
+    #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
+    struct AffectedVolumeRow {
+        volume_id: Uuid,
+        owner: String,
+        via: String,
+    }
 
-    let mut rows = Vec::new();
-    let mut h_to_s: HashMap<Uuid, String> = HashMap::new();
+    let limit = fetch_opts.fetch_limit;
+    let sled_id = args.sled;
+    let physical_disk_id = args.physical_disk;
+
+    // (volume id, how the volume uses the affected storage)
+    let uses: Vec<(Uuid, String)> = datastore
+        .pool_connection_for_tests()
+        .await?
+        .transaction_async(|conn| async move {
+            // Neither the zpools on a sled nor the volumes that reference a
+            // snapshot address are indexed.
+            conn.batch_execute_async(ALLOW_FULL_TABLE_SCAN_SQL).await?;
+
+            use db::schema::zpool::dsl as zpool_dsl;
+            let mut query = zpool_dsl::zpool.select(zpool_dsl::id).into_boxed();
+            if let Some(sled_id) = sled_id {
+                query = query.filter(zpool_dsl::sled_id.eq(sled_id));
+            }
+            if let Some(physical_disk_id) = physical_disk_id {
+                query = query
+                    .filter(zpool_dsl::physical_disk_id.eq(physical_disk_id));
+            }
+            let zpool_ids: Vec<Uuid> = query.load_async(&conn).await?;
+
+            use db::schema::dataset::dsl as dataset_dsl;
+            let dataset_ids: Vec<Uuid> = dataset_dsl::dataset
+                .filter(dataset_dsl::pool_id.eq_any(zpool_ids))
+                .filter(dataset_dsl::kind.eq(DatasetKind::Crucible))
+                .select(dataset_dsl::id)
+                .load_async(&conn)
+                .await?;
 
-    for i in instances {
-        let host_serial = if i.vmm().is_some() {
-            if let std::collections::hash_map::Entry::Vacant(e) =
-                h_to_s.entry(i.sled_id().unwrap())
-            {
-                let (_, my_sled) = LookupPath::new(opctx, datastore)
-                    .sled_id(i.sled_id().unwrap())
-                    .fetch()
-                    .await
-                    .context("failed to look up sled")?;
+            let mut uses = Vec::new();
 
-                let host_serial = my_sled.serial_number().to_string();
-                e.insert(host_serial.to_string());
-                host_serial.to_string()
-            } else {
-                h_to_s.get(&i.sled_id().unwrap()).unwrap().to_string()
+            use db::schema::region::dsl as region_dsl;
+            let regions = region_dsl::region
+                .filter(region_dsl::dataset_id.eq_any(dataset_ids.clone()))
+                .limit(i64::from(u32::from(limit)))
+                .select(Region::as_select())
+                .load_async(&conn)
+                .await?;
+            for region in regions {
+                uses.push((
+                    region.volume_id(),
+                    format!("region {}", region.id()),
+                ));
             }
-        } else {
-            "-".to_string()
-        };
 
-        if running && i.effective_state() != InstanceState::Running {
-            continue;
-        }
+            use db::schema::region_snapshot::dsl as snapshot_dsl;
+            let region_snapshots = snapshot_dsl::region_snapshot
+                .filter(snapshot_dsl::dataset_id.eq_any(dataset_ids))
+                .limit(i64::from(u32::from(limit)))
+                .select(RegionSnapshot::as_select())
+                .load_async(&conn)
+                .await?;
+            use db::schema::volume::dsl as volume_dsl;
+            for region_snapshot in region_snapshots {
+                let volume_ids: Vec<Uuid> =
+                    volume_dsl::volume
+                        .filter(volume_dsl::time_deleted.is_null())
+                        .filter(volume_dsl::data.like(format!(
+                            "%{}%",
+                            region_snapshot.snapshot_addr
+                        )))
+                        .select(volume_dsl::id)
+                        .load_async(&conn)
+                        .await?;
+                for volume_id in volume_ids {
+                    uses.push((
+                        volume_id,
+                        format!(
+                            "snapshot {} of region {}",
+                            region_snapshot.snapshot_id,
+                            region_snapshot.region_id
+                        ),
+                    ));
+                }
+            }
 
-        let cir = CustomerInstanceRow {
-            id: i.instance().id().to_string(),
-            name: i.instance().name().to_string(),
-            state: i.effective_state().to_string(),
-            propolis_id: (&i).into(),
-            sled_id: (&i).into(),
-            host_serial,
-        };
+            Ok(uses)
+        })
+        .await
+        .context("finding affected volumes")?;
+    check_limit(&uses, limit, || "listing affected volumes".to_string());
 
-        rows.push(cir);
-    }
+    let volume_ids: Vec<Uuid> = uses
+        .iter()
+        .map(|(volume_id, _)| *volume_id)
+        .collect::<BTreeSet<_>>()
+        .into_iter()
+        .collect();
+    let owners = volume_owners(datastore, volume_ids).await?;
 
-    let table = tabled::Table::new(rows)
-        .with(tabled::settings::Style::empty())
-        .with(tabled::settings::Padding::new(0, 1, 0, 0))
-        .to_string();
+    let rows = uses.into_iter().map(|(volume_id, via)| AffectedVolumeRow {
+        volume_id,
+        owner: owners
+            .get(&volume_id)
+            .cloned()
+            .unwrap_or_else(|| "-".to_string()),
+        via,
+    });
+    print_rows(format, rows)
+}
 
-    println!("{}", table);
+/// Returns a description of the disk, snapshot, or image that owns each of
+/// the given volumes.
+async fn volume_owners(
+    datastore: &DataStore,
+    volume_ids: Vec<Uuid>,
+) -> Result<BTreeMap<Uuid, String>, anyhow::Error> {
+    let (disks, snapshots, images) = datastore
+        .pool_connection_for_tests()
+        .await?
+        .transaction_async(|conn| async move {
+            // Snapshots and images are not indexed by volume.
+            conn.batch_execute_async(ALLOW_FULL_TABLE_SCAN_SQL).await?;
+
+            use db::schema::disk::dsl as disk_dsl;
+            use db::schema::image::dsl as image_dsl;
+            use db::schema::snapshot::dsl as snapshot_dsl;
+            let disks = disk_dsl::disk
+                .filter(disk_dsl::volume_id.eq_any(volume_ids.clone()))
+                .select(Disk::as_select())
+                .load_async(&conn)
+                .await?;
+            let snapshots = snapshot_dsl::snapshot
+                .filter(
+                    snapshot_dsl::volume_id
+                        .eq_any(volume_ids.clone())
+                        .or(snapshot_dsl::destination_volume_id
+                            .eq_any(volume_ids.clone())),
+                )
+                .select(Snapshot::as_select())
+                .load_async(&conn)
+                .await?;
+            let images = image_dsl::image
+                .filter(image_dsl::volume_id.eq_any(volume_ids))
+                .select(Image::as_select())
+                .load_async(&conn)
+                .await?;
+            Ok((disks, snapshots, images))
+        })
+        .await
+        .context("loading volume owners")?;
 
-    Ok(())
+    let mut owners = BTreeMap::new();
+    for disk in disks {
+        owners.insert(
+            disk.volume_id,
+            format!("disk {} ({})", disk.name(), disk.id()),
+        );
+    }
+    for snapshot in snapshots {
+        owners.insert(
+            snapshot.volume_id,
+            format!("snapshot {} ({})", snapshot.name(), snapshot.id()),
+        );
+        owners.insert(
+            snapshot.destination_volume_id,
+            format!(
+                "snapshot {} ({}) destination",
+                snapshot.name(),
+                snapshot.id()
+            ),
+        );
+    }
+    for image in images {
+        owners.insert(
+            image.volume_id,
+            format!("image {} ({})", image.name(), image.id()),
+        );
+    }
+    Ok(owners)
 }
 
 // DNS
@@ -1610,8 +3441,9 @@ async fn cmd_db_dns_show(
     opctx: &OpContext,
     datastore: &DataStore,
     fetch_opts: &DbFetchOptions,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
-    #[derive(Tabled)]
+    #[derive(Serialize, Tabled)]
     #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
     struct ZoneRow {
         group: String,
@@ -1649,12 +3481,7 @@ async fn cmd_db_dns_show(
         }));
     }
 
-    let table = tabled::Table::new(rows)
-        .with(tabled::settings::Style::empty())
-        .with(tabled::settings::Padding::new(0, 1, 0, 0))
-        .to_string();
-    println!("{}", table);
-    Ok(())
+    print_rows(format, rows)
 }
 
 async fn load_zones_version(
@@ -1691,30 +3518,59 @@ async fn load_zones_version(
     Ok((group_zones, version))
 }
 
+/// A DNS name and its records, for JSON output
+#[derive(Serialize)]
+struct DnsNameReport {
+    name: String,
+    records: Vec<DnsRecord>,
+    /// set if the name's records could not be parsed
+    error: Option<String>,
+}
+
+impl DnsNameReport {
+    fn new(
+        name: String,
+        maybe_records: Result<Vec<DnsRecord>, anyhow::Error>,
+    ) -> DnsNameReport {
+        match maybe_records {
+            Ok(records) => DnsNameReport { name, records, error: None },
+            Err(error) => DnsNameReport {
+                name,
+                records: Vec::new(),
+                error: Some(format!("{:#}", error)),
+            },
+        }
+    }
+}
+
 /// Run `omdb db dns diff`.
 async fn cmd_db_dns_diff(
     opctx: &OpContext,
     datastore: &DataStore,
     fetch_opts: &DbFetchOptions,
     args: &DnsVersionArgs,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
+    ensure_not_csv(format, "omdb db dns diff")?;
+
+    #[derive(Serialize)]
+    struct DnsZoneDiffReport {
+        zone_name: String,
+        group: String,
+        version: Generation,
+        time_created: DateTime<Utc>,
+        creator: String,
+        comment: String,
+        added: Vec<DnsNameReport>,
+        removed: Vec<DnsNameReport>,
+    }
+
     let limit = fetch_opts.fetch_limit;
     let (dns_zones, version) =
         load_zones_version(opctx, datastore, limit, args).await?;
 
+    let mut reports = Vec::new();
     for zone in dns_zones {
-        println!(
-            "DNS zone:                   {} ({:?})",
-            zone.zone_name, args.group
-        );
-        println!(
-            "requested version:          {} (created at {})",
-            *version.version,
-            version.time_created.to_rfc3339_opts(SecondsFormat::Secs, true)
-        );
-        println!("version created by Nexus:   {}", version.creator);
-        println!("version created because:    {}", version.comment);
-
         // Load the added and removed items.
         use nexus_db_queries::db::schema::dns_name::dsl;
 
@@ -1737,6 +3593,41 @@ async fn cmd_db_dns_diff(
             .await
             .context("loading added names")?;
         check_limit(&added, limit, || "loading removed names");
+
+        if format == OutputFormat::Json {
+            let names = |names: Vec<DnsName>| -> Vec<DnsNameReport> {
+                names
+                    .into_iter()
+                    .map(|n| {
+                        let records = n.records().context("parsing records");
+                        DnsNameReport::new(n.name, records)
+                    })
+                    .collect()
+            };
+            reports.push(DnsZoneDiffReport {
+                zone_name: zone.zone_name,
+                group: args.group.dns_group().to_string(),
+                version: *version.version,
+                time_created: version.time_created,
+                creator: version.creator.clone(),
+                comment: version.comment.clone(),
+                added: names(added),
+                removed: names(removed),
+            });
+            continue;
+        }
+
+        println!(
+            "DNS zone:                   {} ({:?})",
+            zone.zone_name, args.group
+        );
+        println!(
+            "requested version:          {} (created at {})",
+            *version.version,
+            version.time_created.to_rfc3339_opts(SecondsFormat::Secs, true)
+        );
+        println!("version created by Nexus:   {}", version.creator);
+        println!("version created because:    {}", version.comment);
         println!(
             "changes:                    names added: {}, names removed: {}",
             added.len(),
@@ -1753,6 +3644,10 @@ async fn cmd_db_dns_diff(
         }
     }
 
+    if format == OutputFormat::Json {
+        return print_json(&reports);
+    }
+
     Ok(())
 }
 
@@ -1762,21 +3657,30 @@ async fn cmd_db_dns_names(
     datastore: &DataStore,
     fetch_opts: &DbFetchOptions,
     args: &DnsVersionArgs,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
+    ensure_not_csv(format, "omdb db dns names")?;
+
+    #[derive(Serialize)]
+    struct DnsZoneNamesReport {
+        zone_name: String,
+        group: String,
+        names: Vec<DnsNameReport>,
+    }
+
     let limit = fetch_opts.fetch_limit;
     let (group_zones, version) =
         load_zones_version(opctx, datastore, limit, args).await?;
 
-    if group_zones.is_empty() {
+    if group_zones.is_empty() && format != OutputFormat::Json {
         println!("no DNS zones found for group {:?}", args.group);
         return Ok(());
     }
 
     // There will almost never be more than one zone.  But just in case, we'll
     // iterate over whatever we find and print all the names in each one.
+    let mut reports = Vec::new();
     for zone in group_zones {
-        println!("{:?} zone: {}", args.group, zone.zone_name);
-        println!("  {:50} {}", "NAME", "RECORDS");
         let ctx = || format!("listing names for zone {:?}", zone.zone_name);
         let mut names = datastore
             .dns_names_list(opctx, zone.id, version.version, &first_page(limit))
@@ -1799,11 +3703,31 @@ async fn cmd_db_dns_names(
             }
         });
 
+        if format == OutputFormat::Json {
+            reports.push(DnsZoneNamesReport {
+                zone_name: zone.zone_name,
+                group: args.group.dns_group().to_string(),
+                names: names
+                    .into_iter()
+                    .map(|(name, records)| {
+                        DnsNameReport::new(name, Ok(records))
+                    })
+                    .collect(),
+            });
+            continue;
+        }
+
+        println!("{:?} zone: {}", args.group, zone.zone_name);
+        println!("  {:50} {}", "NAME", "RECORDS");
         for (name, records) in names {
             print_name("", &name, Ok(records));
         }
     }
 
+    if format == OutputFormat::Json {
+        return print_json(&reports);
+    }
+
     Ok(())
 }
 
@@ -1812,6 +3736,7 @@ async fn cmd_db_eips(
     datastore: &DataStore,
     fetch_opts: &DbFetchOptions,
     verbose: bool,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
     use db::schema::external_ip::dsl;
     let mut query = dsl::external_ip.into_boxed();
@@ -1829,6 +3754,7 @@ async fn cmd_db_eips(
         String::from("listing external ips")
     });
 
+    #[derive(Serialize)]
     struct PortRange {
         first: u16,
         last: u16,
@@ -1896,7 +3822,7 @@ async fn cmd_db_eips(
         }
     }
 
-    #[derive(Tabled)]
+    #[derive(Serialize, Tabled)]
     #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
     struct IpRow {
         ip: ipnetwork::IpNetwork,
@@ -1910,16 +3836,13 @@ async fn cmd_db_eips(
         owner_disposition: Option<BlueprintZoneDisposition>,
     }
 
-    // Display an empty cell for an Option<T> if it's None.
-    fn display_option_blank<T: Display>(opt: &Option<T>) -> String {
-        opt.as_ref().map(|x| x.to_string()).unwrap_or_else(|| "".to_string())
-    }
-
     if verbose {
+        ensure_not_csv(format, "omdb db network list-eips --verbose")?;
+        if format == OutputFormat::Json {
+            return print_json(&ips);
+        }
         for ip in &ips {
-            if verbose {
-                println!("{ip:#?}");
-            }
+            println!("{ip:#?}");
         }
         return Ok(());
     }
@@ -2018,6 +3941,9 @@ async fn cmd_db_eips(
     }
 
     rows.sort_by(|a, b| a.ip.cmp(&b.ip));
+    if format != OutputFormat::Table {
+        return print_rows(format, rows);
+    }
     let table = tabled::Table::new(rows)
         .with(tabled::settings::Style::empty())
         .to_string();
@@ -2031,8 +3957,9 @@ async fn cmd_db_network_list_vnics(
     datastore: &DataStore,
     fetch_opts: &DbFetchOptions,
     verbose: bool,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
-    #[derive(Tabled)]
+    #[derive(Serialize, Tabled)]
     #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
     struct NicRow {
         ip: IpNetwork,
@@ -2061,10 +3988,50 @@ async fn cmd_db_network_list_vnics(
     });
 
     if verbose {
-        for nic in &nics {
-            if verbose {
-                println!("{nic:#?}");
+        ensure_not_csv(format, "omdb db network list-vnics --verbose")?;
+        if format == OutputFormat::Json {
+            // The database model isn't serializable, so this reproduces all
+            // of its fields.
+            #[derive(Serialize)]
+            struct NicDetail {
+                id: Uuid,
+                name: String,
+                description: String,
+                time_created: DateTime<Utc>,
+                time_modified: DateTime<Utc>,
+                time_deleted: Option<DateTime<Utc>>,
+                kind: String,
+                parent_id: Uuid,
+                vpc_id: Uuid,
+                subnet_id: Uuid,
+                mac: MacAddr,
+                ip: IpNetwork,
+                slot: u8,
+                primary: bool,
             }
+            let details: Vec<_> = nics
+                .iter()
+                .map(|nic| NicDetail {
+                    id: nic.id(),
+                    name: nic.name().to_string(),
+                    description: nic.description().to_string(),
+                    time_created: nic.time_created(),
+                    time_modified: nic.time_modified(),
+                    time_deleted: nic.time_deleted(),
+                    kind: format!("{:?}", nic.kind).to_lowercase(),
+                    parent_id: nic.parent_id,
+                    vpc_id: nic.vpc_id,
+                    subnet_id: nic.subnet_id,
+                    mac: nic.mac,
+                    ip: nic.ip,
+                    slot: *nic.slot,
+                    primary: nic.primary,
+                })
+                .collect();
+            return print_json(&details);
+        }
+        for nic in &nics {
+            println!("{nic:#?}");
         }
         return Ok(());
     }
@@ -2169,6 +4136,9 @@ async fn cmd_db_network_list_vnics(
     }
 
     rows.sort_by(|a, b| a.ip.cmp(&b.ip));
+    if format != OutputFormat::Table {
+        return print_rows(format, rows);
+    }
     let table = tabled::Table::new(rows)
         .with(tabled::settings::Style::empty())
         .to_string();
@@ -2181,6 +4151,7 @@ async fn cmd_db_network_list_vnics(
 /// Validate the `volume_references` column of the region snapshots table
 async fn cmd_db_validate_volume_references(
     datastore: &DataStore,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
     // First, get all region snapshot records
     let region_snapshots: Vec<RegionSnapshot> = {
@@ -2202,7 +4173,7 @@ async fn cmd_db_validate_volume_references(
         region_snapshots
     };
 
-    #[derive(Tabled)]
+    #[derive(Serialize, Tabled)]
     struct Row {
         dataset_id: Uuid,
         region_id: Uuid,
@@ -2295,6 +4266,9 @@ async fn cmd_db_validate_volume_references(
         }
     }
 
+    if format != OutputFormat::Table {
+        return print_rows(format, rows);
+    }
     let table = tabled::Table::new(rows)
         .with(tabled::settings::Style::empty())
         .to_string();
@@ -2306,6 +4280,7 @@ async fn cmd_db_validate_volume_references(
 
 async fn cmd_db_validate_region_snapshots(
     datastore: &DataStore,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
     let mut regions_to_snapshots_map: BTreeMap<Uuid, HashSet<Uuid>> =
         BTreeMap::default();
@@ -2340,7 +4315,7 @@ async fn cmd_db_validate_region_snapshots(
         datasets_region_snapshots
     };
 
-    #[derive(Tabled)]
+    #[derive(Serialize, Tabled)]
     struct Row {
         dataset_id: Uuid,
         region_id: Uuid,
@@ -2572,6 +4547,9 @@ async fn cmd_db_validate_region_snapshots(
         }
     }
 
+    if format != OutputFormat::Table {
+        return print_rows(format, rows);
+    }
     let table = tabled::Table::new(rows)
         .with(tabled::settings::Style::empty())
         .to_string();
@@ -2635,19 +4613,20 @@ async fn cmd_db_inventory(
     datastore: &DataStore,
     fetch_opts: &DbFetchOptions,
     inventory_args: &InventoryArgs,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
     let limit = fetch_opts.fetch_limit;
     let conn = datastore.pool_connection_for_tests().await?;
     match inventory_args.command {
         InventoryCommands::BaseboardIds => {
-            cmd_db_inventory_baseboard_ids(&conn, limit).await
+            cmd_db_inventory_baseboard_ids(&conn, limit, format).await
         }
         InventoryCommands::Cabooses => {
-            cmd_db_inventory_cabooses(&conn, limit).await
+            cmd_db_inventory_cabooses(&conn, limit, format).await
         }
         InventoryCommands::Collections(CollectionsArgs {
             command: CollectionsCommands::List,
-        }) => cmd_db_inventory_collections_list(&conn, limit).await,
+        }) => cmd_db_inventory_collections_list(&conn, limit, format).await,
         InventoryCommands::Collections(CollectionsArgs {
             command:
                 CollectionsCommands::Show(CollectionsShowArgs {
@@ -2662,14 +4641,15 @@ async fn cmd_db_inventory(
                 datastore,
                 id,
                 long_string_formatter,
+                format,
             )
             .await
         }
         InventoryCommands::PhysicalDisks(args) => {
-            cmd_db_inventory_physical_disks(&conn, limit, args).await
+            cmd_db_inventory_physical_disks(&conn, limit, args, format).await
         }
         InventoryCommands::RotPages => {
-            cmd_db_inventory_rot_pages(&conn, limit).await
+            cmd_db_inventory_rot_pages(&conn, limit, format).await
         }
     }
 }
@@ -2677,8 +4657,9 @@ async fn cmd_db_inventory(
 async fn cmd_db_inventory_baseboard_ids(
     conn: &DataStoreConnection<'_>,
     limit: NonZeroU32,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
-    #[derive(Tabled)]
+    #[derive(Serialize, Tabled)]
     #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
     struct BaseboardRow {
         id: Uuid,
@@ -2701,21 +4682,15 @@ async fn cmd_db_inventory_baseboard_ids(
         part_number: baseboard_id.part_number,
         serial_number: baseboard_id.serial_number,
     });
-    let table = tabled::Table::new(rows)
-        .with(tabled::settings::Style::empty())
-        .with(tabled::settings::Padding::new(0, 1, 0, 0))
-        .to_string();
-
-    println!("{}", table);
-
-    Ok(())
+    print_rows(format, rows)
 }
 
 async fn cmd_db_inventory_cabooses(
     conn: &DataStoreConnection<'_>,
     limit: NonZeroU32,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
-    #[derive(Tabled)]
+    #[derive(Serialize, Tabled)]
     #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
     struct CabooseRow {
         id: Uuid,
@@ -2742,22 +4717,16 @@ async fn cmd_db_inventory_cabooses(
         version: caboose.version,
         git_commit: caboose.git_commit,
     });
-    let table = tabled::Table::new(rows)
-        .with(tabled::settings::Style::empty())
-        .with(tabled::settings::Padding::new(0, 1, 0, 0))
-        .to_string();
-
-    println!("{}", table);
-
-    Ok(())
+    print_rows(format, rows)
 }
 
 async fn cmd_db_inventory_physical_disks(
     conn: &DataStoreConnection<'_>,
     limit: NonZeroU32,
     args: PhysicalDisksArgs,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
-    #[derive(Tabled)]
+    #[derive(Serialize, Tabled)]
     #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
     struct DiskRow {
         inv_collection_id: Uuid,
@@ -2799,21 +4768,15 @@ async fn cmd_db_inventory_physical_disks(
         variant: format!("{:?}", disk.variant),
     });
 
-    let table = tabled::Table::new(rows)
-        .with(tabled::settings::Style::empty())
-        .with(tabled::settings::Padding::new(0, 1, 0, 0))
-        .to_string();
-
-    println!("{}", table);
-
-    Ok(())
+    print_rows(format, rows)
 }
 
 async fn cmd_db_inventory_rot_pages(
     conn: &DataStoreConnection<'_>,
     limit: NonZeroU32,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
-    #[derive(Tabled)]
+    #[derive(Serialize, Tabled)]
     #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
     struct RotPageRow {
         id: Uuid,
@@ -2834,21 +4797,15 @@ async fn cmd_db_inventory_rot_pages(
         id: rot_page.id,
         data_base64: rot_page.data_base64,
     });
-    let table = tabled::Table::new(rows)
-        .with(tabled::settings::Style::empty())
-        .with(tabled::settings::Padding::new(0, 1, 0, 0))
-        .to_string();
-
-    println!("{}", table);
-
-    Ok(())
+    print_rows(format, rows)
 }
 
 async fn cmd_db_inventory_collections_list(
     conn: &DataStoreConnection<'_>,
     limit: NonZeroU32,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
-    #[derive(Tabled)]
+    #[derive(Serialize, Tabled)]
     #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
     struct CollectionRow {
         id: CollectionUuid,
@@ -2911,14 +4868,7 @@ async fn cmd_db_inventory_collections_list(
         });
     }
 
-    let table = tabled::Table::new(rows)
-        .with(tabled::settings::Style::empty())
-        .with(tabled::settings::Padding::new(0, 1, 0, 0))
-        .to_string();
-
-    println!("{}", table);
-
-    Ok(())
+    print_rows(format, rows)
 }
 
 async fn cmd_db_inventory_collections_show(
@@ -2926,16 +4876,22 @@ async fn cmd_db_inventory_collections_show(
     datastore: &DataStore,
     id: CollectionUuid,
     long_string_formatter: LongStringFormatter,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
     let collection = datastore
         .inventory_collection_read(opctx, id)
         .await
         .context("reading collection")?;
+    if format == OutputFormat::Json {
+        return print_json(&collection);
+    }
+    ensure_table_format(format, "omdb db inventory collections show")?;
 
     inv_collection_print(&collection).await?;
     let nerrors = inv_collection_print_errors(&collection).await?;
     inv_collection_print_devices(&collection, &long_string_formatter).await?;
     inv_collection_print_sleds(&collection);
+    inv_collection_print_switches(&collection);
 
     if nerrors > 0 {
         eprintln!(
@@ -3129,6 +5085,39 @@ async fn inv_collection_print_devices(
         } else {
             println!("    RoT: no information found");
         }
+
+        #[derive(Tabled)]
+        #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
+        struct SensorRow<'a> {
+            component: &'a str,
+            device: &'a str,
+            name: &'a str,
+            kind: String,
+            value: String,
+        }
+
+        println!("    sensors:");
+        let sensor_rows: Vec<_> = collection
+            .sensor_readings
+            .get(baseboard_id)
+            .into_iter()
+            .flatten()
+            .map(|reading| SensorRow {
+                component: &reading.component,
+                device: &reading.device,
+                name: &reading.name,
+                kind: reading.kind.to_string(),
+                value: match &reading.value {
+                    Ok(value) => value.to_string(),
+                    Err(error) => format!("error: {}", error),
+                },
+            })
+            .collect();
+        let table = tabled::Table::new(sensor_rows)
+            .with(tabled::settings::Style::empty())
+            .with(tabled::settings::Padding::new(0, 1, 0, 0))
+            .to_string();
+        println!("{}", textwrap::indent(&table.to_string(), "        "));
     }
 
     println!("");
@@ -3219,6 +5208,59 @@ fn inv_collection_print_sleds(collection: &Collection) {
     }
 }
 
+fn inv_collection_print_switches(collection: &Collection) {
+    println!("\nSWITCHES");
+    for switch in collection.switches.values() {
+        println!("\nswitch {}", switch.switch_location);
+        println!(
+            "    found at:    {} from {}",
+            switch.time_collected, switch.source
+        );
+        println!("    dpd version: {}", switch.dpd_version);
+
+        #[derive(Tabled)]
+        #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
+        struct PortRow<'a> {
+            port: &'a str,
+            link: String,
+            vendor: &'a str,
+            part: &'a str,
+            revision: &'a str,
+            serial: &'a str,
+        }
+
+        let rows: Vec<_> = switch
+            .ports
+            .iter()
+            .map(|(port_id, port)| {
+                let transceiver = port.transceiver.as_ref();
+                PortRow {
+                    port: port_id,
+                    link: port
+                        .link_state
+                        .map(|l| l.to_string())
+                        .unwrap_or_else(|| String::from("-")),
+                    vendor: transceiver
+                        .map(|t| t.vendor.as_str())
+                        .unwrap_or("-"),
+                    part: transceiver.map(|t| t.part.as_str()).unwrap_or("-"),
+                    revision: transceiver
+                        .map(|t| t.revision.as_str())
+                        .unwrap_or("-"),
+                    serial: transceiver
+                        .map(|t| t.serial.as_str())
+                        .unwrap_or("-"),
+                }
+            })
+            .collect();
+        let table = tabled::Table::new(rows)
+            .with(tabled::settings::Style::empty())
+            .with(tabled::settings::Padding::new(0, 1, 0, 0))
+            .to_string();
+        println!("{}", textwrap::indent(&table.to_string(), "    "));
+    }
+}
+
 #[derive(Debug)]
 struct LongStringFormatter {
     show_long_strings: bool,
@@ -4,6 +4,128 @@
 
 //! Utility helpers for the omdb CLI.
 
+use anyhow::Context;
+
 pub(crate) const CONNECTION_OPTIONS_HEADING: &str = "Connection Options";
 pub(crate) const DATABASE_OPTIONS_HEADING: &str = "Database Options";
 pub(crate) const SAFETY_OPTIONS_HEADING: &str = "Safety Options";
+pub(crate) const OUTPUT_OPTIONS_HEADING: &str = "Output Options";
+
+/// Formats in which omdb can print its results
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, clap::ValueEnum)]
+pub(crate) enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Csv,
+}
+
+impl std::fmt::Display for OutputFormat {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            OutputFormat::Table => "table",
+            OutputFormat::Json => "json",
+            OutputFormat::Csv => "csv",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Prints `rows` in the requested format
+///
+/// Tables use the style shared by all omdb commands.  JSON is an array of
+/// the serialized rows.  CSV uses the same columns (and rendering of each
+/// field) as the table, so that scripts can rely on either one.
+pub(crate) fn print_rows<R, I>(
+    format: OutputFormat,
+    rows: I,
+) -> Result<(), anyhow::Error>
+where
+    R: tabled::Tabled + serde::Serialize,
+    I: IntoIterator<Item = R>,
+{
+    match format {
+        OutputFormat::Table => {
+            let table = tabled::Table::new(rows)
+                .with(tabled::settings::Style::empty())
+                .with(tabled::settings::Padding::new(0, 1, 0, 0))
+                .to_string();
+            println!("{}", table);
+        }
+        OutputFormat::Json => {
+            let rows: Vec<R> = rows.into_iter().collect();
+            print_json(&rows)?;
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(std::io::stdout());
+            let headers = R::headers();
+            writer.write_record(headers.iter().map(|h| h.as_bytes()))?;
+            for row in rows {
+                let fields = row.fields();
+                writer.write_record(fields.iter().map(|f| f.as_bytes()))?;
+            }
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Prints `value` as pretty-printed JSON
+pub(crate) fn print_json<T: serde::Serialize + ?Sized>(
+    value: &T,
+) -> Result<(), anyhow::Error> {
+    let json = serde_json::to_string_pretty(value)
+        .context("serializing output as JSON")?;
+    println!("{}", json);
+    Ok(())
+}
+
+/// Fails unless the requested format is the default table format
+///
+/// This is for commands whose output is a report (multiple tables, or
+/// free-form text) rather than a list of rows.
+pub(crate) fn ensure_table_format(
+    format: OutputFormat,
+    command: &str,
+) -> Result<(), anyhow::Error> {
+    anyhow::ensure!(
+        format == OutputFormat::Table,
+        "\"{}\" does not support --format {}",
+        command,
+        format
+    );
+    Ok(())
+}
+
+/// Fails unless the requested format is the default table format, saying
+/// why the command has no machine-readable form
+pub(crate) fn ensure_table_format_because(
+    format: OutputFormat,
+    command: &str,
+    reason: &str,
+) -> Result<(), anyhow::Error> {
+    anyhow::ensure!(
+        format == OutputFormat::Table,
+        "\"{}\" does not support --format {} because {}",
+        command,
+        format,
+        reason
+    );
+    Ok(())
+}
+
+/// Fails if the requested format is CSV
+///
+/// This is for commands whose output is a report that can be printed either
+/// as tables or as a single JSON object, but has no one set of CSV columns.
+pub(crate) fn ensure_not_csv(
+    format: OutputFormat,
+    command: &str,
+) -> Result<(), anyhow::Error> {
+    anyhow::ensure!(
+        format != OutputFormat::Csv,
+        "\"{}\" does not support --format csv",
+        command
+    );
+    Ok(())
+}
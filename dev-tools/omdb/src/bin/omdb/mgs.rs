@@ -4,6 +4,10 @@
 
 //! Prototype code for collecting information from systems in the rack
 
+use crate::helpers::ensure_table_format;
+use crate::helpers::ensure_table_format_because;
+use crate::helpers::print_json;
+use crate::helpers::OutputFormat;
 use crate::helpers::CONNECTION_OPTIONS_HEADING;
 use crate::Omdb;
 use anyhow::Context;
@@ -21,6 +25,8 @@ use gateway_client::types::SpIgnitionInfo;
 use gateway_client::types::SpIgnitionSystemType;
 use gateway_client::types::SpState;
 use gateway_client::types::SpType;
+use serde::Serialize;
+use std::io::Write;
 use tabled::Tabled;
 
 mod dashboard;
@@ -48,6 +54,9 @@ pub struct MgsArgs {
 #[derive(Debug, Subcommand)]
 enum MgsCommands {
     /// Dashboard of SPs
+    ///
+    /// The dashboard is an interactive, full-screen display with no
+    /// machine-readable form, so it supports only the default --format table.
     Dashboard(DashboardArgs),
 
     /// Show information about devices and components visible to MGS
@@ -55,11 +64,23 @@ enum MgsCommands {
 
     /// Show information about sensors, as gleaned by MGS
     Sensors(SensorsArgs),
+
+    /// Dump a sled's host serial console output, as recorded by MGS
+    ///
+    /// The output is the console's raw bytes, which aren't tabular and which
+    /// need no further encoding, so this supports only --format table.
+    SerialConsoleHistory(SerialConsoleHistoryArgs),
 }
 
 #[derive(Debug, Args)]
 struct InventoryArgs {}
 
+#[derive(Debug, Args)]
+struct SerialConsoleHistoryArgs {
+    /// slot number of the sled whose console to dump
+    sled: u32,
+}
+
 impl MgsArgs {
     async fn mgs_client(
         &self,
@@ -92,19 +113,71 @@ impl MgsArgs {
     ) -> Result<(), anyhow::Error> {
         match &self.command {
             MgsCommands::Dashboard(args) => {
+                ensure_table_format_because(
+                    omdb.format,
+                    "omdb mgs dashboard",
+                    "the dashboard is interactive",
+                )?;
                 dashboard::cmd_mgs_dashboard(omdb, log, self, args).await
             }
             MgsCommands::Inventory(args) => {
                 let mgs_client = self.mgs_client(omdb, log).await?;
-                cmd_mgs_inventory(&mgs_client, args).await
+                if omdb.format == OutputFormat::Json {
+                    cmd_mgs_inventory_json(&mgs_client, args).await
+                } else {
+                    ensure_table_format(omdb.format, "omdb mgs inventory")?;
+                    cmd_mgs_inventory(&mgs_client, args).await
+                }
             }
             MgsCommands::Sensors(args) => {
                 sensors::cmd_mgs_sensors(omdb, log, self, args).await
             }
+            MgsCommands::SerialConsoleHistory(args) => {
+                ensure_table_format_because(
+                    omdb.format,
+                    "omdb mgs serial-console-history",
+                    "it prints the console's raw bytes",
+                )?;
+                let mgs_client = self.mgs_client(omdb, log).await?;
+                cmd_mgs_serial_console_history(&mgs_client, args).await
+            }
         }
     }
 }
 
+/// Runs `omdb mgs serial-console-history`
+///
+/// Writes the recorded output to stdout as-is, so that it can be piped to a
+/// file or pager.
+async fn cmd_mgs_serial_console_history(
+    mgs_client: &gateway_client::Client,
+    args: &SerialConsoleHistoryArgs,
+) -> Result<(), anyhow::Error> {
+    let history = mgs_client
+        .sp_component_serial_console_history(
+            SpType::Sled,
+            args.sled,
+            "sp3-host-cpu",
+        )
+        .await
+        .with_context(|| {
+            format!("fetching serial console history for sled {}", args.sled)
+        })?
+        .into_inner();
+
+    if history.start_offset > 0 {
+        eprintln!(
+            "note: earlier output ({} bytes) is no longer available",
+            history.start_offset
+        );
+    }
+
+    let mut stdout = std::io::stdout().lock();
+    stdout.write_all(&history.data).context("writing to stdout")?;
+    stdout.flush().context("writing to stdout")?;
+    Ok(())
+}
+
 /// Runs `omdb mgs inventory`
 ///
 /// Shows devices and components that are visible to an MGS instance.
@@ -175,6 +248,110 @@ async fn cmd_mgs_inventory(
     Ok(())
 }
 
+/// JSON output of `omdb mgs inventory`
+#[derive(Serialize)]
+struct MgsInventory {
+    sp_ids: Vec<SpIdentifier>,
+    ignition: Vec<SpIgnitionInfo>,
+    sps: Vec<SpInventory>,
+}
+
+/// Everything `omdb mgs inventory` reports about one SP
+#[derive(Serialize)]
+struct SpInventory {
+    id: SpIdentifier,
+    state: SpState,
+    components: Vec<SpComponentInfo>,
+    cabooses: Vec<SpInventoryCaboose>,
+}
+
+#[derive(Serialize)]
+struct SpInventoryCaboose {
+    component: String,
+    caboose: SpComponentCaboose,
+}
+
+/// Runs `omdb mgs inventory --format json`
+///
+/// This collects the same information that `cmd_mgs_inventory()` prints, but
+/// reports it as the structures returned by MGS.
+async fn cmd_mgs_inventory_json(
+    mgs_client: &gateway_client::Client,
+    _args: &InventoryArgs,
+) -> Result<(), anyhow::Error> {
+    let mut sp_ids = mgs_client
+        .sp_all_ids()
+        .await
+        .context("listing SP identifiers")?
+        .into_inner();
+    sp_ids.sort();
+
+    let mut ignition = mgs_client
+        .ignition_list()
+        .await
+        .context("listing ignition")?
+        .into_inner();
+    ignition.sort_by(|a, b| a.id.cmp(&b.id));
+
+    let mut sps = Vec::new();
+    for sp_id in ignition.iter().filter_map(|ignition| {
+        matches!(ignition.details, SpIgnition::Yes { .. })
+            .then_some(ignition.id)
+    }) {
+        let state = match mgs_client.sp_get(sp_id.type_, sp_id.slot).await {
+            Ok(state) => state.into_inner(),
+            Err(error) => {
+                eprintln!(
+                    "error: fetching info about SP {:?}: {:#}",
+                    sp_id, error
+                );
+                continue;
+            }
+        };
+
+        let components =
+            match mgs_client.sp_component_list(sp_id.type_, sp_id.slot).await {
+                Ok(list) => list.into_inner().components,
+                Err(error) => {
+                    eprintln!(
+                        "error: fetching components for SP {:?}: {:#}",
+                        sp_id, error
+                    );
+                    Vec::new()
+                }
+            };
+
+        let mut cabooses = Vec::new();
+        for c in &components {
+            if !COMPONENTS_WITH_CABOOSES.contains(&c.component.as_str()) {
+                continue;
+            }
+            match mgs_client
+                .sp_component_caboose_get(
+                    sp_id.type_,
+                    sp_id.slot,
+                    &c.component,
+                    0,
+                )
+                .await
+            {
+                Ok(caboose) => cabooses.push(SpInventoryCaboose {
+                    component: c.component.clone(),
+                    caboose: caboose.into_inner(),
+                }),
+                Err(error) => eprintln!(
+                    "warn: get caboose for SP {:?} component {:?}: {:#}",
+                    sp_id, c.component, error
+                ),
+            }
+        }
+
+        sps.push(SpInventory { id: sp_id, state, components, cabooses });
+    }
+
+    print_json(&MgsInventory { sp_ids, ignition, sps })
+}
+
 fn sp_type_to_str(s: &SpType) -> &'static str {
     match s {
         SpType::Sled => "Sled",
@@ -4,6 +4,7 @@
 
 //! Implementation of the "mgs sensors" subcommand
 
+use crate::helpers::OutputFormat;
 use anyhow::{bail, Context};
 use clap::Args;
 use gateway_client::types::MeasurementErrorCode;
@@ -13,7 +14,8 @@ use gateway_client::types::SpIdentifier;
 use gateway_client::types::SpIgnition;
 use gateway_client::types::SpType;
 use multimap::MultiMap;
-use std::collections::{HashMap, HashSet};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::fs::File;
 use std::sync::Arc;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
@@ -809,15 +811,57 @@ pub(crate) async fn sensor_data<R: std::io::Read + std::io::Seek>(
     }
 }
 
+/// One sample of every sensor, as printed with `--format json`
+#[derive(Serialize)]
+struct SensorsSample {
+    time: u64,
+    readings: Vec<SensorReading>,
+    /// MGS latency for each SP, in milliseconds, if `--show-latencies`
+    latencies_ms: Option<BTreeMap<String, u128>>,
+}
+
+#[derive(Serialize)]
+struct SensorReading {
+    sensor: String,
+    kind: String,
+    sp: String,
+    /// `None` if the sensor reported an error or has no value in this sample
+    value: Option<f32>,
+}
+
+fn sp_label(sp: &SpIdentifier) -> String {
+    format!(
+        "{}-{}",
+        crate::mgs::sp_type_to_str(&sp.type_).to_uppercase(),
+        sp.slot
+    )
+}
+
 ///
 /// Runs `omdb mgs sensors`
 ///
+/// With `--format json`, each sample is printed as one line of JSON, so that
+/// `--sleep` produces a stream of them.
+///
 pub(crate) async fn cmd_mgs_sensors(
     omdb: &crate::Omdb,
     log: &slog::Logger,
     mgs_args: &crate::mgs::MgsArgs,
     args: &SensorsArgs,
 ) -> Result<(), anyhow::Error> {
+    match omdb.format {
+        OutputFormat::Table => (),
+        OutputFormat::Json if !args.parseable => (),
+        OutputFormat::Json => {
+            bail!("--parseable cannot be used with --format json")
+        }
+        OutputFormat::Csv => bail!(
+            "\"omdb mgs sensors\" does not support --format csv \
+             (use --parseable instead)"
+        ),
+    }
+    let json = omdb.format == OutputFormat::Json;
+
     let mut input = if let Some(ref input) = args.input {
         let file = File::open(input)
             .with_context(|| format!("failed to open {input}"))?;
@@ -853,11 +897,7 @@ pub(crate) async fn cmd_mgs_sensors(
         }
 
         for sp in &sps {
-            print_value(format!(
-                "{}-{}",
-                crate::mgs::sp_type_to_str(&sp.type_).to_uppercase(),
-                sp.slot
-            ));
+            print_value(sp_label(sp));
         }
 
         println!();
@@ -882,48 +922,89 @@ pub(crate) async fn cmd_mgs_sensors(
     let mut wakeup =
         tokio::time::Instant::now() + tokio::time::Duration::from_millis(1000);
 
-    print_header();
+    if !json {
+        print_header();
+    }
 
     loop {
-        for sensor in &sensors {
-            print_name(sensor, values.time);
+        if json {
+            let mut readings = Vec::new();
+            for sensor in &sensors {
+                let by_sp =
+                    metadata.sensors_by_sensor_and_sp.get(sensor).unwrap();
+                for sp in &sps {
+                    let Some(id) = by_sp.get(sp) else {
+                        continue;
+                    };
+                    readings.push(SensorReading {
+                        sensor: sensor.name.clone(),
+                        kind: sensor.to_kind_string().to_string(),
+                        sp: sp_label(sp),
+                        value: values.values.get(id).copied().flatten(),
+                    });
+                }
+            }
+            let latencies_ms = if args.show_latencies {
+                values.latencies.as_ref().map(|latencies| {
+                    latencies
+                        .iter()
+                        .map(|(sp, latency)| {
+                            (sp_label(sp), latency.as_millis())
+                        })
+                        .collect()
+                })
+            } else {
+                None
+            };
+            let sample =
+                SensorsSample { time: values.time, readings, latencies_ms };
+            println!(
+                "{}",
+                serde_json::to_string(&sample)
+                    .context("serializing sensor values")?
+            );
+        } else {
+            for sensor in &sensors {
+                print_name(sensor, values.time);
 
-            let by_sp = metadata.sensors_by_sensor_and_sp.get(sensor).unwrap();
+                let by_sp =
+                    metadata.sensors_by_sensor_and_sp.get(sensor).unwrap();
 
-            for sp in &sps {
-                print_value(if let Some(id) = by_sp.get(sp) {
-                    if let Some(value) = values.values.get(id) {
-                        match value {
-                            Some(value) => {
-                                sensor.format(*value, args.parseable)
+                for sp in &sps {
+                    print_value(if let Some(id) = by_sp.get(sp) {
+                        if let Some(value) = values.values.get(id) {
+                            match value {
+                                Some(value) => {
+                                    sensor.format(*value, args.parseable)
+                                }
+                                None => "X".to_string(),
                             }
-                            None => "X".to_string(),
+                        } else {
+                            "?".to_string()
                         }
                     } else {
-                        "?".to_string()
-                    }
-                } else {
-                    "-".to_string()
-                });
-            }
+                        "-".to_string()
+                    });
+                }
 
-            println!();
-        }
+                println!();
+            }
 
-        if args.show_latencies {
-            if let Some(latencies) = values.latencies {
-                print_latency(values.time);
+            if args.show_latencies {
+                if let Some(latencies) = values.latencies {
+                    print_latency(values.time);
 
-                for sp in &sps {
-                    print_value(if let Some(latency) = latencies.get(sp) {
-                        format!("{}ms", latency.as_millis())
-                    } else {
-                        "?".to_string()
-                    });
+                    for sp in &sps {
+                        print_value(if let Some(latency) = latencies.get(sp) {
+                            format!("{}ms", latency.as_millis())
+                        } else {
+                            "?".to_string()
+                        });
+                    }
                 }
-            }
 
-            println!();
+                println!();
+            }
         }
 
         if !args.sleep {
@@ -941,7 +1022,7 @@ pub(crate) async fn cmd_mgs_sensors(
             break;
         }
 
-        if !args.parseable {
+        if !args.parseable && !json {
             print_header();
         }
     }
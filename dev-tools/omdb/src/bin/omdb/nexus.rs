@@ -6,6 +6,11 @@
 
 use crate::check_allow_destructive::DestructiveOperationToken;
 use crate::db::DbUrlOptions;
+use crate::helpers::ensure_not_csv;
+use crate::helpers::ensure_table_format;
+use crate::helpers::print_json;
+use crate::helpers::print_rows;
+use crate::helpers::OutputFormat;
 use crate::helpers::CONNECTION_OPTIONS_HEADING;
 use crate::Omdb;
 use anyhow::bail;
@@ -24,20 +29,28 @@ use nexus_client::types::BackgroundTask;
 use nexus_client::types::BackgroundTasksActivateRequest;
 use nexus_client::types::CurrentStatus;
 use nexus_client::types::LastResult;
+use nexus_client::types::SagaState;
 use nexus_client::types::SledSelector;
 use nexus_client::types::UninitializedSledId;
 use nexus_db_queries::db::lookup::LookupPath;
 use nexus_types::deployment::Blueprint;
+use nexus_types::deployment::BlueprintDiff;
+use nexus_types::deployment::DiffBeforeMetadata;
+use nexus_types::deployment::PendingMgsUpdates;
 use nexus_types::inventory::BaseboardId;
 use omicron_uuid_kinds::CollectionUuid;
 use omicron_uuid_kinds::GenericUuid;
+use omicron_uuid_kinds::OmicronZoneUuid;
 use omicron_uuid_kinds::SledUuid;
 use reedline::DefaultPrompt;
 use reedline::DefaultPromptSegment;
 use reedline::Reedline;
 use serde::Deserialize;
+use serde::Serialize;
 use slog_error_chain::InlineErrorChain;
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::net::Ipv6Addr;
 use std::str::FromStr;
 use tabled::Tabled;
 use uuid::Uuid;
@@ -66,6 +79,8 @@ enum NexusCommands {
     BackgroundTasks(BackgroundTasksArgs),
     /// interact with blueprints
     Blueprints(BlueprintsArgs),
+    /// print information about sagas in this Nexus's saga executor
+    Sagas(SagasArgs),
     /// interact with sleds
     Sleds(SledsArgs),
 }
@@ -239,6 +254,26 @@ struct BlueprintImportArgs {
     input: Utf8PathBuf,
 }
 
+#[derive(Debug, Args)]
+struct SagasArgs {
+    #[command(subcommand)]
+    command: SagasCommands,
+}
+
+#[derive(Debug, Subcommand)]
+enum SagasCommands {
+    /// List sagas known to this Nexus
+    List,
+    /// Show the current state of a saga in this Nexus
+    Show(SagaIdArgs),
+}
+
+#[derive(Debug, Args)]
+struct SagaIdArgs {
+    /// id of the saga
+    saga_id: Uuid,
+}
+
 #[derive(Debug, Args)]
 struct SledsArgs {
     #[command(subcommand)]
@@ -299,17 +334,18 @@ impl NexusArgs {
         };
         eprintln!("note: using Nexus URL {}", &nexus_url);
         let client = nexus_client::Client::new(&nexus_url, log.clone());
+        let format = omdb.format;
 
         match &self.command {
             NexusCommands::BackgroundTasks(BackgroundTasksArgs {
                 command: BackgroundTasksCommands::Doc,
-            }) => cmd_nexus_background_tasks_doc(&client).await,
+            }) => cmd_nexus_background_tasks_doc(&client, format).await,
             NexusCommands::BackgroundTasks(BackgroundTasksArgs {
                 command: BackgroundTasksCommands::List,
-            }) => cmd_nexus_background_tasks_list(&client).await,
+            }) => cmd_nexus_background_tasks_list(&client, format).await,
             NexusCommands::BackgroundTasks(BackgroundTasksArgs {
                 command: BackgroundTasksCommands::Show,
-            }) => cmd_nexus_background_tasks_show(&client).await,
+            }) => cmd_nexus_background_tasks_show(&client, format).await,
             NexusCommands::BackgroundTasks(BackgroundTasksArgs {
                 command: BackgroundTasksCommands::Activate(args),
             }) => {
@@ -319,13 +355,13 @@ impl NexusArgs {
 
             NexusCommands::Blueprints(BlueprintsArgs {
                 command: BlueprintsCommands::List,
-            }) => cmd_nexus_blueprints_list(&client).await,
+            }) => cmd_nexus_blueprints_list(&client, format).await,
             NexusCommands::Blueprints(BlueprintsArgs {
                 command: BlueprintsCommands::Show(args),
-            }) => cmd_nexus_blueprints_show(&client, args).await,
+            }) => cmd_nexus_blueprints_show(&client, args, format).await,
             NexusCommands::Blueprints(BlueprintsArgs {
                 command: BlueprintsCommands::Diff(args),
-            }) => cmd_nexus_blueprints_diff(&client, args).await,
+            }) => cmd_nexus_blueprints_diff(&client, args, format).await,
             NexusCommands::Blueprints(BlueprintsArgs {
                 command: BlueprintsCommands::Delete(args),
             }) => {
@@ -337,7 +373,7 @@ impl NexusArgs {
                     BlueprintsCommands::Target(BlueprintsTargetArgs {
                         command: BlueprintTargetCommands::Show,
                     }),
-            }) => cmd_nexus_blueprints_target_show(&client).await,
+            }) => cmd_nexus_blueprints_target_show(&client, format).await,
             NexusCommands::Blueprints(BlueprintsArgs {
                 command:
                     BlueprintsCommands::Target(BlueprintsTargetArgs {
@@ -384,9 +420,16 @@ impl NexusArgs {
                 cmd_nexus_blueprints_import(&client, token, args).await
             }
 
+            NexusCommands::Sagas(SagasArgs {
+                command: SagasCommands::List,
+            }) => cmd_nexus_sagas_list(&client, format).await,
+            NexusCommands::Sagas(SagasArgs {
+                command: SagasCommands::Show(args),
+            }) => cmd_nexus_sagas_show(&client, args, format).await,
+
             NexusCommands::Sleds(SledsArgs {
                 command: SledsCommands::ListUninitialized,
-            }) => cmd_nexus_sleds_list_uninitialized(&client).await,
+            }) => cmd_nexus_sleds_list_uninitialized(&client, format).await,
             NexusCommands::Sleds(SledsArgs {
                 command: SledsCommands::Add(args),
             }) => {
@@ -406,11 +449,16 @@ impl NexusArgs {
 /// Runs `omdb nexus background-tasks doc`
 async fn cmd_nexus_background_tasks_doc(
     client: &nexus_client::Client,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
     let response =
         client.bgtask_list().await.context("listing background tasks")?;
     let tasks = response.into_inner();
     let tasks: BTreeMap<_, _> = tasks.into_iter().collect();
+    if format == OutputFormat::Json {
+        return print_json(&tasks);
+    }
+    ensure_table_format(format, "omdb nexus background-tasks doc")?;
     for (_, bgtask) in &tasks {
         println!("task: {:?}", bgtask.name);
         println!(
@@ -432,22 +480,19 @@ async fn cmd_nexus_background_tasks_doc(
 /// Runs `omdb nexus background-tasks list`
 async fn cmd_nexus_background_tasks_list(
     client: &nexus_client::Client,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
     let response =
         client.bgtask_list().await.context("listing background tasks")?;
     let tasks = response.into_inner();
     let table_rows = tasks.values().map(BackgroundTaskStatusRow::from);
-    let table = tabled::Table::new(table_rows)
-        .with(tabled::settings::Style::empty())
-        .with(tabled::settings::Padding::new(0, 1, 0, 0))
-        .to_string();
-    println!("{}", table);
-    Ok(())
+    print_rows(format, table_rows)
 }
 
 /// Runs `omdb nexus background-tasks show`
 async fn cmd_nexus_background_tasks_show(
     client: &nexus_client::Client,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
     let response =
         client.bgtask_list().await.context("listing background tasks")?;
@@ -455,6 +500,10 @@ async fn cmd_nexus_background_tasks_show(
     // order.
     let mut tasks =
         response.into_inner().into_iter().collect::<BTreeMap<_, _>>();
+    if format == OutputFormat::Json {
+        return print_json(&tasks);
+    }
+    ensure_table_format(format, "omdb nexus background-tasks show")?;
 
     // We want to pick the order that we print some tasks intentionally.  Then
     // we want to print anything else that we find.
@@ -843,6 +892,34 @@ fn print_task_details(bgtask: &BackgroundTask, details: &serde_json::Value) {
                 );
             }
         };
+    } else if name == "mgs_updates" {
+        #[derive(Deserialize)]
+        struct UpdateStatus {
+            update: String,
+            status: String,
+        }
+
+        #[derive(Deserialize)]
+        struct TaskSuccess {
+            /// blueprint whose pending updates were attempted
+            target_id: Uuid,
+            /// status of each pending update
+            updates: Vec<UpdateStatus>,
+        }
+
+        match serde_json::from_value::<TaskSuccess>(details.clone()) {
+            Err(error) => eprintln!(
+                "warning: failed to interpret task details: {:?}: {:?}",
+                error, details
+            ),
+            Ok(success) => {
+                println!("    target blueprint: {}", success.target_id);
+                println!("    pending MGS updates: {}", success.updates.len());
+                for update in &success.updates {
+                    println!("        {}: {}", update.update, update.status);
+                }
+            }
+        };
     } else if name == "phantom_disks" {
         #[derive(Deserialize)]
         struct TaskSuccess {
@@ -895,6 +972,33 @@ fn print_task_details(bgtask: &BackgroundTask, details: &serde_json::Value) {
                 );
             }
         };
+    } else if name == "instance_serial_console" {
+        #[derive(Deserialize)]
+        struct TaskSuccess {
+            /// number of running VMMs whose output we tried to capture
+            vmms_checked: usize,
+
+            /// total bytes of output captured from all VMMs
+            bytes_captured: usize,
+
+            /// number of VMMs whose output could not be captured
+            errors: usize,
+        }
+
+        match serde_json::from_value::<TaskSuccess>(details.clone()) {
+            Err(error) => eprintln!(
+                "warning: failed to interpret task details: {:?}: {:?}",
+                error, details
+            ),
+            Ok(success) => {
+                println!("    VMMs checked: {}", success.vmms_checked);
+                println!(
+                    "    bytes of output captured: {}",
+                    success.bytes_captured
+                );
+                println!("    errors: {}", success.errors);
+            }
+        };
     } else if name == "instance_watcher" {
         #[derive(Deserialize)]
         struct TaskSuccess {
@@ -1017,7 +1121,7 @@ fn reason_str(reason: &ActivationReason) -> &'static str {
 }
 
 /// Used for printing background task status as a table
-#[derive(Tabled)]
+#[derive(Serialize, Tabled)]
 struct BackgroundTaskStatusRow {
     task_name: String,
     #[tabled(rename = "PGEN#")]
@@ -1087,8 +1191,9 @@ fn reason_code(reason: ActivationReason) -> char {
 
 async fn cmd_nexus_blueprints_list(
     client: &nexus_client::Client,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
-    #[derive(Tabled)]
+    #[derive(Serialize, Tabled)]
     #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
     struct BlueprintRow {
         #[tabled(rename = "T")]
@@ -1144,20 +1249,19 @@ async fn cmd_nexus_blueprints_list(
         })
         .collect();
 
-    let table = tabled::Table::new(rows)
-        .with(tabled::settings::Style::empty())
-        .with(tabled::settings::Padding::new(0, 1, 0, 0))
-        .to_string();
-
-    println!("{}", table);
-    Ok(())
+    print_rows(format, rows)
 }
 
 async fn cmd_nexus_blueprints_show(
     client: &nexus_client::Client,
     args: &BlueprintIdArgs,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
     let blueprint = args.blueprint_id.resolve_to_blueprint(client).await?;
+    if format == OutputFormat::Json {
+        return print_json(&blueprint);
+    }
+    ensure_table_format(format, "omdb nexus blueprints show")?;
     println!("{}", blueprint.display());
     Ok(())
 }
@@ -1165,17 +1269,174 @@ async fn cmd_nexus_blueprints_show(
 async fn cmd_nexus_blueprints_diff(
     client: &nexus_client::Client,
     args: &BlueprintIdsArgs,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
+    ensure_not_csv(format, "omdb nexus blueprints diff")?;
     let (b1, b2) = try_join(
         args.blueprint1_id.resolve_to_blueprint(client),
         args.blueprint2_id.resolve_to_blueprint(client),
     )
     .await?;
     let diff = b2.diff_since_blueprint(&b1);
+    if format == OutputFormat::Json {
+        return print_json(&BlueprintDiffReport::new(diff));
+    }
     println!("{}", diff.display());
     Ok(())
 }
 
+/// `omdb nexus blueprints diff --format json`: the diff's contents, with one
+/// row per zone and physical disk rather than one table per sled
+#[derive(Serialize)]
+struct BlueprintDiffReport {
+    before_blueprint_id: Option<Uuid>,
+    before_collection_id: Option<CollectionUuid>,
+    after_blueprint_id: Uuid,
+    has_changes: bool,
+    sleds_added: BTreeSet<SledUuid>,
+    sleds_removed: BTreeSet<SledUuid>,
+    sleds_modified: BTreeSet<SledUuid>,
+    sleds_unchanged: BTreeSet<SledUuid>,
+    zones: Vec<BlueprintDiffZoneRow>,
+    physical_disks: Vec<BlueprintDiffDiskRow>,
+    pending_mgs_updates_before: PendingMgsUpdates,
+    pending_mgs_updates_after: PendingMgsUpdates,
+}
+
+#[derive(Serialize)]
+struct BlueprintDiffZoneRow {
+    sled_id: SledUuid,
+    change: &'static str,
+    zone_id: OmicronZoneUuid,
+    kind: String,
+    disposition_before: Option<String>,
+    disposition_after: Option<String>,
+    underlay_address: Ipv6Addr,
+    /// why a zone that changed in more than its disposition is an error
+    error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct BlueprintDiffDiskRow {
+    sled_id: SledUuid,
+    change: &'static str,
+    vendor: String,
+    model: String,
+    serial: String,
+}
+
+impl BlueprintDiffReport {
+    fn new(diff: BlueprintDiff) -> BlueprintDiffReport {
+        let (before_blueprint_id, before_collection_id) =
+            match &diff.before_meta {
+                DiffBeforeMetadata::Blueprint(meta) => (Some(meta.id), None),
+                DiffBeforeMetadata::Collection { id } => (None, Some(*id)),
+            };
+        let has_changes = diff.has_changes();
+
+        let mut zones = Vec::new();
+        for (change, details) in [
+            ("unchanged", &diff.zones.unchanged),
+            ("removed", &diff.zones.removed),
+            ("added", &diff.zones.added),
+        ] {
+            for (sled_id, details) in details {
+                for zone in &details.zones {
+                    let disposition = Some(zone.disposition().to_string());
+                    zones.push(BlueprintDiffZoneRow {
+                        sled_id: *sled_id,
+                        change,
+                        zone_id: zone.id(),
+                        kind: zone.kind().to_string(),
+                        disposition_before: if change == "added" {
+                            None
+                        } else {
+                            disposition.clone()
+                        },
+                        disposition_after: if change == "removed" {
+                            None
+                        } else {
+                            disposition
+                        },
+                        underlay_address: zone.underlay_address(),
+                        error: None,
+                    });
+                }
+            }
+        }
+        for (sled_id, modified) in &diff.zones.modified {
+            for zone in &modified.zones {
+                zones.push(BlueprintDiffZoneRow {
+                    sled_id: *sled_id,
+                    change: "modified",
+                    zone_id: zone.zone.id(),
+                    kind: zone.zone.kind().to_string(),
+                    disposition_before: Some(
+                        zone.prior_disposition.to_string(),
+                    ),
+                    disposition_after: Some(
+                        zone.zone.disposition().to_string(),
+                    ),
+                    underlay_address: zone.zone.underlay_address(),
+                    error: None,
+                });
+            }
+        }
+        for (sled_id, errors) in &diff.zones.errors {
+            for error in &errors.errors {
+                zones.push(BlueprintDiffZoneRow {
+                    sled_id: *sled_id,
+                    change: "error",
+                    zone_id: error.zone_after.id(),
+                    kind: error.zone_after.kind().to_string(),
+                    disposition_before: Some(
+                        error.zone_before.disposition().to_string(),
+                    ),
+                    disposition_after: Some(
+                        error.zone_after.disposition().to_string(),
+                    ),
+                    underlay_address: error.zone_after.underlay_address(),
+                    error: Some(error.reason.clone()),
+                });
+            }
+        }
+
+        let mut physical_disks = Vec::new();
+        for (change, details) in [
+            ("unchanged", &diff.physical_disks.unchanged),
+            ("removed", &diff.physical_disks.removed),
+            ("added", &diff.physical_disks.added),
+        ] {
+            for (sled_id, details) in details {
+                for disk in &details.disks {
+                    physical_disks.push(BlueprintDiffDiskRow {
+                        sled_id: *sled_id,
+                        change,
+                        vendor: disk.vendor.clone(),
+                        model: disk.model.clone(),
+                        serial: disk.serial.clone(),
+                    });
+                }
+            }
+        }
+
+        BlueprintDiffReport {
+            before_blueprint_id,
+            before_collection_id,
+            after_blueprint_id: diff.after_meta.id,
+            has_changes,
+            sleds_added: diff.sleds_added,
+            sleds_removed: diff.sleds_removed,
+            sleds_modified: diff.sleds_modified,
+            sleds_unchanged: diff.sleds_unchanged,
+            zones,
+            physical_disks,
+            pending_mgs_updates_before: diff.pending_mgs_updates_before,
+            pending_mgs_updates_after: diff.pending_mgs_updates_after,
+        }
+    }
+}
+
 async fn cmd_nexus_blueprints_delete(
     client: &nexus_client::Client,
     args: &BlueprintIdArgs,
@@ -1192,11 +1453,16 @@ async fn cmd_nexus_blueprints_delete(
 
 async fn cmd_nexus_blueprints_target_show(
     client: &nexus_client::Client,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
     let target = client
         .blueprint_target_view()
         .await
         .context("fetching target blueprint")?;
+    if format == OutputFormat::Json {
+        return print_json(&*target);
+    }
+    ensure_table_format(format, "omdb nexus blueprints target show")?;
     println!("target blueprint: {}", target.target_id);
     println!("made target at:   {}", target.time_made_target);
     println!("enabled:          {}", target.enabled);
@@ -1290,9 +1556,88 @@ async fn cmd_nexus_blueprints_import(
     Ok(())
 }
 
+/// Returns a short label for a saga's state as reported by Nexus
+fn saga_state_label(state: &SagaState) -> &'static str {
+    match state {
+        SagaState::Running => "running",
+        SagaState::Succeeded => "succeeded",
+        SagaState::Failed { .. } => "failed",
+        SagaState::Stuck { .. } => "stuck",
+    }
+}
+
+/// Runs `omdb nexus sagas list`
+async fn cmd_nexus_sagas_list(
+    client: &nexus_client::Client,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    #[derive(Serialize, Tabled)]
+    #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
+    struct SagaRow {
+        saga_id: Uuid,
+        state: &'static str,
+    }
+
+    // This only reports the sagas in this Nexus's in-memory saga executor.
+    // Sagas that were started by a different Nexus (or before this one last
+    // restarted) and were never recovered won't show up here.
+    let sagas = client
+        .saga_list_stream(None, None)
+        .try_collect::<Vec<_>>()
+        .await
+        .context("listing sagas")?;
+
+    let rows = sagas.into_iter().map(|saga| SagaRow {
+        saga_id: saga.id,
+        state: saga_state_label(&saga.state),
+    });
+    print_rows(format, rows)
+}
+
+/// Runs `omdb nexus sagas show`
+async fn cmd_nexus_sagas_show(
+    client: &nexus_client::Client,
+    args: &SagaIdArgs,
+    format: OutputFormat,
+) -> Result<(), anyhow::Error> {
+    let saga = client
+        .saga_view(&args.saga_id)
+        .await
+        .with_context(|| format!("fetching saga {}", args.saga_id))?
+        .into_inner();
+    if format == OutputFormat::Json {
+        return print_json(&saga);
+    }
+    ensure_table_format(format, "omdb nexus sagas show")?;
+
+    println!("saga {}", saga.id);
+    println!("    state: {}", saga_state_label(&saga.state));
+    match &saga.state {
+        SagaState::Running | SagaState::Succeeded => (),
+        SagaState::Failed { error_node_name, error_info } => {
+            println!("    failed node: {}", error_node_name.as_str());
+            println!("    error: {}", serde_json::to_string(error_info)?);
+        }
+        SagaState::Stuck {
+            error_node_name,
+            error_info,
+            undo_error_node_name,
+            undo_source_error,
+        } => {
+            println!("    failed node: {}", error_node_name.as_str());
+            println!("    error: {}", serde_json::to_string(error_info)?);
+            println!("    failed undo node: {}", undo_error_node_name.as_str());
+            println!("    undo error: {}", undo_source_error);
+        }
+    }
+
+    Ok(())
+}
+
 /// Runs `omdb nexus sleds list-uninitialized`
 async fn cmd_nexus_sleds_list_uninitialized(
     client: &nexus_client::Client,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
     let response = client
         .sled_list_uninitialized()
@@ -1308,7 +1653,7 @@ async fn cmd_nexus_sleds_list_uninitialized(
     let mut sleds = sleds.items;
     sleds.sort_by_key(|sled| sled.cubby);
 
-    #[derive(Tabled)]
+    #[derive(Serialize, Tabled)]
     #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
     struct UninitializedSledRow {
         rack_id: Uuid,
@@ -1324,12 +1669,7 @@ async fn cmd_nexus_sleds_list_uninitialized(
         part: sled.baseboard.part,
         revision: sled.baseboard.revision,
     });
-    let table = tabled::Table::new(rows)
-        .with(tabled::settings::Style::empty())
-        .with(tabled::settings::Padding::new(0, 1, 0, 0))
-        .to_string();
-    println!("{}", table);
-    Ok(())
+    print_rows(format, rows)
 }
 
 /// Runs `omdb nexus sleds add`
@@ -2,19 +2,38 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-//! omdb commands that query oximeter
+//! omdb commands that query oximeter and the metrics it has collected
 
+use crate::helpers::ensure_table_format;
+use crate::helpers::print_json;
+use crate::helpers::print_rows;
+use crate::helpers::OutputFormat;
 use crate::helpers::CONNECTION_OPTIONS_HEADING;
+use crate::Omdb;
+use anyhow::bail;
+use anyhow::ensure;
 use anyhow::Context;
+use chrono::DateTime;
+use chrono::SecondsFormat;
+use chrono::Utc;
 use clap::Args;
 use clap::Subcommand;
+use dropshot::EmptyScanParams;
+use dropshot::WhichPage;
 use futures::TryStreamExt;
 use oximeter_client::types::ProducerEndpoint;
 use oximeter_client::Client;
+use oximeter_db::oxql::point::Datum;
+use oximeter_db::oxql::Table;
+use oximeter_db::oxql::Timeseries;
+use oximeter_db::FieldSource;
+use oximeter_db::TimeseriesName;
+use oximeter_db::TimeseriesSchema;
+use serde::Serialize;
 use slog::Logger;
 use std::net::SocketAddr;
+use std::num::NonZeroU32;
 use std::time::Duration;
-use tabled::Table;
 use tabled::Tabled;
 use uuid::Uuid;
 
@@ -24,12 +43,19 @@ pub struct OximeterArgs {
     #[arg(
         long,
         env = "OMDB_OXIMETER_URL",
-        // This can't be global = true (i.e. passed in later in the
-        // command-line) because global options can't be required. If this
-        // changes to being optional, we should set global = true.
+        global = true,
         help_heading = CONNECTION_OPTIONS_HEADING,
     )]
-    oximeter_url: String,
+    oximeter_url: Option<String>,
+
+    /// Address of the ClickHouse HTTP interface holding collected metrics
+    #[arg(
+        long,
+        env = "OMDB_CLICKHOUSE_ADDR",
+        global = true,
+        help_heading = CONNECTION_OPTIONS_HEADING,
+    )]
+    clickhouse_addr: Option<SocketAddr>,
 
     #[command(subcommand)]
     command: OximeterCommands,
@@ -40,26 +66,126 @@ pub struct OximeterArgs {
 enum OximeterCommands {
     /// List the producers the collector is assigned to poll
     ListProducers,
+    /// Run an OxQL query against the metrics database
+    Query(QueryArgs),
+    /// Print information about timeseries schema
+    Schema(SchemaArgs),
+}
+
+#[derive(Debug, Args)]
+struct QueryArgs {
+    /// The OxQL query to run, e.g., 'get physical_data_link:bytes_received'
+    query: String,
+
+    /// Draw each timeseries as a chart rather than listing its points
+    #[arg(long)]
+    chart: bool,
+
+    /// Width of each chart, in columns
+    #[arg(long, default_value_t = 60, requires = "chart")]
+    width: usize,
+}
+
+#[derive(Debug, Args)]
+struct SchemaArgs {
+    #[command(subcommand)]
+    command: SchemaCommands,
+}
+
+#[derive(Debug, Subcommand)]
+enum SchemaCommands {
+    /// List all timeseries known to the database
+    List,
+    /// Show the fields and datum type of a timeseries
+    Show(SchemaShowArgs),
+}
+
+#[derive(Debug, Args)]
+struct SchemaShowArgs {
+    /// Name of the timeseries, e.g., 'physical_data_link:bytes_received'
+    timeseries_name: String,
 }
 
 impl OximeterArgs {
-    fn client(&self, log: &Logger) -> Client {
-        Client::new(
-            &self.oximeter_url,
+    fn client(&self, log: &Logger) -> anyhow::Result<Client> {
+        // This is a little goofy.  The oximeter URL is required for some
+        // subcommands, but can come from the environment, in which case it
+        // won't be on the command line.
+        let Some(oximeter_url) = &self.oximeter_url else {
+            bail!(
+                "oximeter URL must be specified with --oximeter-url or \
+                OMDB_OXIMETER_URL"
+            );
+        };
+        Ok(Client::new(
+            oximeter_url,
             log.new(slog::o!("component" => "oximeter-client")),
-        )
+        ))
+    }
+
+    /// Returns a client for the ClickHouse database that oximeter writes to
+    ///
+    /// This talks to ClickHouse directly, rather than through Nexus's external
+    /// API, so that metrics can be examined even when Nexus is unavailable.
+    async fn db_client(
+        &self,
+        omdb: &Omdb,
+        log: &Logger,
+    ) -> anyhow::Result<oximeter_db::Client> {
+        let addr = match &self.clickhouse_addr {
+            Some(addr) => *addr,
+            None => {
+                eprintln!(
+                    "note: ClickHouse address not specified.  \
+                    Will pick one from DNS."
+                );
+                let addr = omdb
+                    .dns_lookup_one(
+                        log.clone(),
+                        internal_dns::ServiceName::Clickhouse,
+                    )
+                    .await?;
+                SocketAddr::from(addr)
+            }
+        };
+        eprintln!("note: using ClickHouse address {}", addr);
+        Ok(oximeter_db::Client::new(addr, log))
     }
 
-    pub async fn run_cmd(&self, log: &Logger) -> anyhow::Result<()> {
-        let client = self.client(log);
-        match self.command {
+    pub async fn run_cmd(
+        &self,
+        omdb: &Omdb,
+        log: &Logger,
+    ) -> anyhow::Result<()> {
+        match &self.command {
             OximeterCommands::ListProducers => {
-                self.list_producers(client).await
+                let client = self.client(log)?;
+                self.list_producers(client, omdb.format).await
+            }
+            OximeterCommands::Query(args) => {
+                let client = self.db_client(omdb, log).await?;
+                cmd_oximeter_query(&client, args, omdb.format).await
+            }
+            OximeterCommands::Schema(SchemaArgs {
+                command: SchemaCommands::List,
+            }) => {
+                let client = self.db_client(omdb, log).await?;
+                cmd_oximeter_schema_list(&client, omdb.format).await
+            }
+            OximeterCommands::Schema(SchemaArgs {
+                command: SchemaCommands::Show(args),
+            }) => {
+                let client = self.db_client(omdb, log).await?;
+                cmd_oximeter_schema_show(&client, args, omdb.format).await
             }
         }
     }
 
-    async fn list_producers(&self, client: Client) -> anyhow::Result<()> {
+    async fn list_producers(
+        &self,
+        client: Client,
+        format: OutputFormat,
+    ) -> anyhow::Result<()> {
         let info = client
             .collector_info()
             .await
@@ -70,22 +196,35 @@ impl OximeterArgs {
             .try_collect()
             .await
             .context("failed to list producers")?;
-        let table = Table::new(producers)
-            .with(tabled::settings::Style::empty())
-            .with(tabled::settings::Padding::new(0, 1, 0, 0))
-            .to_string();
-        println!("Collector ID: {}\n", info.id);
-        let last_refresh = info
-            .last_refresh
-            .map(|r| r.to_string())
-            .unwrap_or(String::from("Never"));
-        println!("Last refresh: {}\n", last_refresh);
-        println!("{table}");
-        Ok(())
+        match format {
+            OutputFormat::Table => {
+                println!("Collector ID: {}\n", info.id);
+                let last_refresh = info
+                    .last_refresh
+                    .map(|r| r.to_string())
+                    .unwrap_or(String::from("Never"));
+                println!("Last refresh: {}\n", last_refresh);
+                print_rows(format, producers)
+            }
+            OutputFormat::Json => print_json(&CollectorProducers {
+                collector_id: info.id,
+                last_refresh: info.last_refresh,
+                producers,
+            }),
+            OutputFormat::Csv => print_rows(format, producers),
+        }
     }
 }
 
-#[derive(Tabled)]
+/// JSON output of `omdb oximeter list-producers`
+#[derive(Serialize)]
+struct CollectorProducers {
+    collector_id: Uuid,
+    last_refresh: Option<DateTime<Utc>>,
+    producers: Vec<Producer>,
+}
+
+#[derive(Serialize, Tabled)]
 #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
 struct Producer {
     id: Uuid,
@@ -103,3 +242,448 @@ impl From<ProducerEndpoint> for Producer {
         }
     }
 }
+
+/// Runs `omdb oximeter query`
+async fn cmd_oximeter_query(
+    client: &oximeter_db::Client,
+    args: &QueryArgs,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    let query = args.query.trim().trim_end_matches(';');
+    let result = client
+        .oxql_query(query)
+        .await
+        .with_context(|| format!("running OxQL query {:?}", query))?;
+    eprintln!(
+        "note: query {} took {:?}",
+        result.query_id, result.total_duration
+    );
+
+    match format {
+        OutputFormat::Json => return print_json(&result.tables),
+        OutputFormat::Csv => {
+            ensure!(
+                !args.chart,
+                "--chart cannot be used with --format {}",
+                format
+            );
+            let rows = result.tables.iter().flat_map(|table| {
+                table.iter().flat_map(move |timeseries| {
+                    point_rows(timeseries).into_iter().map(move |point| {
+                        QueryPointRow {
+                            table: table.name().to_string(),
+                            fields: timeseries_fields(timeseries),
+                            start_time: point.start_time,
+                            timestamp: point.timestamp,
+                            values: point.values,
+                        }
+                    })
+                })
+            });
+            return print_rows(format, rows);
+        }
+        OutputFormat::Table => (),
+    }
+
+    for table in &result.tables {
+        print_query_table(table, args);
+    }
+    Ok(())
+}
+
+/// One point of an OxQL query result, with the table and timeseries it came
+/// from
+#[derive(Serialize, Tabled)]
+#[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
+struct QueryPointRow {
+    table: String,
+    fields: String,
+    start_time: String,
+    timestamp: String,
+    values: String,
+}
+
+/// One point of a single timeseries
+#[derive(Serialize, Tabled)]
+#[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
+struct PointRow {
+    start_time: String,
+    timestamp: String,
+    values: String,
+}
+
+fn point_rows(timeseries: &Timeseries) -> Vec<PointRow> {
+    timeseries
+        .points
+        .iter_points()
+        .map(|point| PointRow {
+            start_time: point
+                .start_time
+                .map(|t| t.to_rfc3339_opts(SecondsFormat::Millis, true))
+                .unwrap_or_else(|| String::from("-")),
+            timestamp: point
+                .timestamp
+                .to_rfc3339_opts(SecondsFormat::Millis, true),
+            values: point
+                .values
+                .iter()
+                .map(|(datum, _)| datum.to_string())
+                .collect::<Vec<_>>()
+                .join(","),
+        })
+        .collect()
+}
+
+fn timeseries_fields(timeseries: &Timeseries) -> String {
+    timeseries
+        .fields
+        .iter()
+        .map(|(name, value)| format!("{}={}", name, value))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn print_query_table(table: &Table, args: &QueryArgs) {
+    println!("table: {}", table.name());
+    if table.n_timeseries() == 0 {
+        println!("    <no timeseries>");
+    }
+    for timeseries in table.iter() {
+        println!();
+        println!("    {}", timeseries_fields(timeseries));
+        if timeseries.points.is_empty() {
+            println!("        <no points>");
+            continue;
+        }
+
+        if args.chart {
+            if let Some(chart) = chart_timeseries(timeseries, args.width) {
+                println!("{}", textwrap::indent(&chart, "        "));
+                continue;
+            }
+            println!("        (values are not numeric and cannot be charted)");
+        }
+
+        let table = tabled::Table::new(point_rows(timeseries))
+            .with(tabled::settings::Style::empty())
+            .with(tabled::settings::Padding::new(0, 1, 0, 0))
+            .to_string();
+        println!("{}", textwrap::indent(&table, "        "));
+    }
+    println!();
+}
+
+/// Renders each dimension of a timeseries as a sparkline, with the range of
+/// values and of time it covers
+///
+/// Returns `None` if the timeseries has non-numeric data, such as strings or
+/// distributions.
+fn chart_timeseries(timeseries: &Timeseries, width: usize) -> Option<String> {
+    let points: Vec<_> = timeseries.points.iter_points().collect();
+    let first = points.first()?;
+    let last = points.last()?;
+    let ndims = first.dimensionality();
+
+    let mut chart = String::new();
+    for dim in 0..ndims {
+        let values = points
+            .iter()
+            .map(|point| match point.values[dim].0 {
+                Datum::Integer(value) => Some(value.map(|v| *v as f64)),
+                Datum::Double(value) => Some(value.copied()),
+                Datum::Boolean(value) => {
+                    Some(value.map(|v| if v { 1.0 } else { 0.0 }))
+                }
+                Datum::String(_)
+                | Datum::IntegerDistribution(_)
+                | Datum::DoubleDistribution(_) => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+
+        if ndims > 1 {
+            chart.push_str(&format!("dimension {}: ", dim));
+        }
+        chart.push_str(&chart_values(&values, width));
+        chart.push('\n');
+    }
+    chart.push_str(&format!(
+        "{} points from {} to {}",
+        points.len(),
+        first.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+        last.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+    ));
+    Some(chart)
+}
+
+/// Renders one dimension of a timeseries as a sparkline followed by the
+/// minimum, maximum, and latest of its values
+///
+/// Values that are not finite (e.g., NaN) are left out of the minimum and
+/// maximum, but may still be the latest value.
+fn chart_values(values: &[Option<f64>], width: usize) -> String {
+    let finite = values.iter().flatten().filter(|v| v.is_finite());
+    let min = finite.clone().copied().fold(f64::INFINITY, f64::min);
+    let max = finite.copied().fold(f64::NEG_INFINITY, f64::max);
+    let latest = values
+        .iter()
+        .rev()
+        .flatten()
+        .next()
+        .map(|v| v.to_string())
+        .unwrap_or_else(|| String::from("-"));
+    format!(
+        "{}  min {} max {} last {}",
+        sparkline(values, width),
+        if min.is_finite() { min.to_string() } else { String::from("-") },
+        if max.is_finite() { max.to_string() } else { String::from("-") },
+        latest,
+    )
+}
+
+/// Renders `values` as a line of block characters at most `width` columns
+/// wide
+///
+/// If there are more values than columns, adjacent values are averaged into
+/// one column.  Missing values, values that are not finite, and columns with
+/// no other values are left blank.
+fn sparkline(values: &[Option<f64>], width: usize) -> String {
+    const BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+    let width = width.max(1);
+    let ncolumns = values.len().min(width);
+    let columns: Vec<Option<f64>> = (0..ncolumns)
+        .map(|i| {
+            let start = i * values.len() / ncolumns;
+            let end = (i + 1) * values.len() / ncolumns;
+            let bucket: Vec<f64> = values[start..end]
+                .iter()
+                .flatten()
+                .copied()
+                .filter(|v| v.is_finite())
+                .collect();
+            if bucket.is_empty() {
+                None
+            } else {
+                Some(bucket.iter().sum::<f64>() / bucket.len() as f64)
+            }
+        })
+        .collect();
+
+    let present = columns.iter().flatten();
+    let min = present.clone().copied().fold(f64::INFINITY, f64::min);
+    let max = present.copied().fold(f64::NEG_INFINITY, f64::max);
+    columns
+        .iter()
+        .map(|column| match column {
+            None => ' ',
+            Some(_) if max <= min => BARS[BARS.len() / 2],
+            Some(v) => {
+                let scaled = (v - min) / (max - min) * (BARS.len() - 1) as f64;
+                BARS[scaled.round() as usize]
+            }
+        })
+        .collect()
+}
+
+/// Runs `omdb oximeter schema list`
+async fn cmd_oximeter_schema_list(
+    client: &oximeter_db::Client,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    #[derive(Serialize, Tabled)]
+    #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
+    struct SchemaRow {
+        timeseries_name: String,
+        datum_type: String,
+        nfields: usize,
+        created: String,
+    }
+
+    let limit = NonZeroU32::new(100).unwrap();
+    let mut page = WhichPage::First(EmptyScanParams {});
+    let mut schema = Vec::new();
+    loop {
+        let results = client
+            .timeseries_schema_list(&page, limit)
+            .await
+            .context("listing timeseries schema")?;
+        let next = match (&results.next_page, results.items.last()) {
+            (Some(_), Some(last)) => {
+                Some(WhichPage::Next(last.timeseries_name.clone()))
+            }
+            _ => None,
+        };
+        schema.extend(results.items);
+        match next {
+            Some(next) => page = next,
+            None => break,
+        }
+    }
+
+    let rows = schema.into_iter().map(|schema| SchemaRow {
+        timeseries_name: schema.timeseries_name.to_string(),
+        datum_type: schema.datum_type.to_string(),
+        nfields: schema.field_schema.len(),
+        created: schema.created.to_rfc3339_opts(SecondsFormat::Secs, true),
+    });
+    print_rows(format, rows)
+}
+
+/// Runs `omdb oximeter schema show`
+async fn cmd_oximeter_schema_show(
+    client: &oximeter_db::Client,
+    args: &SchemaShowArgs,
+    format: OutputFormat,
+) -> anyhow::Result<()> {
+    #[derive(Serialize, Tabled)]
+    #[tabled(rename_all = "SCREAMING_SNAKE_CASE")]
+    struct FieldRow {
+        name: String,
+        #[tabled(rename = "TYPE")]
+        field_type: String,
+        source: &'static str,
+    }
+
+    let name: TimeseriesName =
+        args.timeseries_name.parse().with_context(|| {
+            format!("invalid timeseries name {:?}", args.timeseries_name)
+        })?;
+    let schema: TimeseriesSchema = client
+        .schema_for_timeseries(&name)
+        .await
+        .context("fetching timeseries schema")?
+        .with_context(|| format!("no timeseries named {:?}", name))?;
+
+    if format == OutputFormat::Json {
+        return print_json(&schema);
+    }
+    ensure_table_format(format, "omdb oximeter schema show")?;
+
+    println!("timeseries: {}", schema.timeseries_name);
+    println!("datum type: {}", schema.datum_type);
+    println!("created:    {}", schema.created);
+    println!();
+
+    let rows = schema.field_schema.iter().map(|field| FieldRow {
+        name: field.name.clone(),
+        field_type: field.field_type.to_string(),
+        source: match field.source {
+            FieldSource::Target => "target",
+            FieldSource::Metric => "metric",
+        },
+    });
+    print_rows(format, rows)
+}
+
+#[cfg(test)]
+mod test {
+    use super::chart_timeseries;
+    use super::chart_values;
+    use super::sparkline;
+    use oximeter_db::oxql::Timeseries;
+
+    #[test]
+    fn test_sparkline_buckets() {
+        // Adjacent values are averaged when there are more than fit.
+        let values: Vec<_> = (0..8).map(|v| Some(f64::from(v))).collect();
+        assert_eq!(sparkline(&values, 4), "▁▃▆█");
+
+        // Otherwise there's one column per value.
+        let values = [Some(1.0), Some(2.0), Some(3.0)];
+        assert_eq!(sparkline(&values, 10), "▁▅█");
+        assert_eq!(sparkline(&values, 0), "▅");
+        assert_eq!(sparkline(&[], 10), "");
+    }
+
+    #[test]
+    fn test_sparkline_constant() {
+        assert_eq!(sparkline(&[Some(5.0); 3], 10), "▅▅▅");
+        assert_eq!(sparkline(&[Some(0.0)], 10), "▅");
+    }
+
+    #[test]
+    fn test_sparkline_missing() {
+        assert_eq!(sparkline(&[Some(0.0), None, Some(7.0)], 10), "▁ █");
+        assert_eq!(sparkline(&[None, None], 10), "  ");
+
+        // A column is only blank if all of its values are missing.
+        let values = [Some(0.0), None, None, None, Some(2.0), Some(4.0)];
+        assert_eq!(sparkline(&values, 3), "▁ █");
+        assert_eq!(sparkline(&values, 2), "▁█");
+    }
+
+    #[test]
+    fn test_sparkline_nan() {
+        let values = [Some(0.0), Some(f64::NAN), Some(7.0)];
+        assert_eq!(sparkline(&values, 10), "▁ █");
+
+        // NaN doesn't poison the average of the column it falls in.
+        let values = [Some(0.0), Some(f64::NAN), Some(2.0), Some(4.0)];
+        assert_eq!(sparkline(&values, 2), "▁█");
+
+        let values = [Some(1.0), Some(f64::INFINITY), Some(3.0)];
+        assert_eq!(sparkline(&values, 10), "▁ █");
+    }
+
+    #[test]
+    fn test_chart_values() {
+        assert_eq!(
+            chart_values(&[Some(1.0), Some(2.5), Some(3.0)], 10),
+            "▁▆█  min 1 max 3 last 3"
+        );
+        assert_eq!(
+            chart_values(&[Some(1.0), None, Some(f64::NAN)], 10),
+            "▅    min 1 max 1 last NaN"
+        );
+        assert_eq!(chart_values(&[None], 10), "   min - max - last -");
+    }
+
+    fn timeseries(values: serde_json::Value) -> Timeseries {
+        serde_json::from_value(serde_json::json!({
+            "fields": {
+                "name": { "type": "string", "value": "test" }
+            },
+            "points": {
+                "start_times": null,
+                "timestamps": [
+                    "2024-01-01T00:00:00Z",
+                    "2024-01-01T00:00:01Z",
+                    "2024-01-01T00:00:02Z",
+                ],
+                "values": [
+                    { "values": values, "metric_type": "gauge" }
+                ]
+            }
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_chart_timeseries() {
+        let doubles = timeseries(serde_json::json!({
+            "type": "double",
+            "values": [1.0, null, 3.0],
+        }));
+        assert_eq!(
+            chart_timeseries(&doubles, 60).unwrap(),
+            "▁ █  min 1 max 3 last 3\n\
+             3 points from 2024-01-01T00:00:00Z to 2024-01-01T00:00:02Z"
+        );
+
+        let integers = timeseries(serde_json::json!({
+            "type": "integer",
+            "values": [4, 4, 4],
+        }));
+        assert_eq!(
+            chart_timeseries(&integers, 2).unwrap(),
+            "▅▅  min 4 max 4 last 4\n\
+             3 points from 2024-01-01T00:00:00Z to 2024-01-01T00:00:02Z"
+        );
+
+        let strings = timeseries(serde_json::json!({
+            "type": "string",
+            "values": ["a", "b", "c"],
+        }));
+        assert_eq!(chart_timeseries(&strings, 60), None);
+    }
+}
@@ -4,6 +4,9 @@
 
 //! omdb commands that query or update specific Sleds
 
+use crate::helpers::ensure_table_format;
+use crate::helpers::print_json;
+use crate::helpers::OutputFormat;
 use crate::helpers::CONNECTION_OPTIONS_HEADING;
 use crate::Omdb;
 use anyhow::bail;
@@ -65,7 +68,7 @@ impl SledAgentArgs {
     /// Run a `omdb sled-agent` subcommand.
     pub(crate) async fn run_cmd(
         &self,
-        _omdb: &Omdb,
+        omdb: &Omdb,
         log: &slog::Logger,
     ) -> Result<(), anyhow::Error> {
         // This is a little goofy. The sled URL is required, but can come
@@ -81,13 +84,13 @@ impl SledAgentArgs {
 
         match &self.command {
             SledAgentCommands::Zones(ZoneCommands::List) => {
-                cmd_zones_list(&client).await
+                cmd_zones_list(&client, omdb.format).await
             }
             SledAgentCommands::Zpools(ZpoolCommands::List) => {
-                cmd_zpools_list(&client).await
+                cmd_zpools_list(&client, omdb.format).await
             }
             SledAgentCommands::Bootstore(BootstoreCommands::Status) => {
-                cmd_bootstore_status(&client).await
+                cmd_bootstore_status(&client, omdb.format).await
             }
         }
     }
@@ -96,10 +99,15 @@ impl SledAgentArgs {
 /// Runs `omdb sled-agent zones list`
 async fn cmd_zones_list(
     client: &sled_agent_client::Client,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
     let response = client.zones_list().await.context("listing zones")?;
     let zones = response.into_inner();
     let zones: Vec<_> = zones.into_iter().collect();
+    if format == OutputFormat::Json {
+        return print_json(&zones);
+    }
+    ensure_table_format(format, "omdb sled-agent zones list")?;
 
     println!("zones:");
     if zones.is_empty() {
@@ -115,9 +123,14 @@ async fn cmd_zones_list(
 /// Runs `omdb sled-agent zpools list`
 async fn cmd_zpools_list(
     client: &sled_agent_client::Client,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
     let response = client.zpools_get().await.context("listing zpools")?;
     let zpools = response.into_inner();
+    if format == OutputFormat::Json {
+        return print_json(&zpools);
+    }
+    ensure_table_format(format, "omdb sled-agent zpools list")?;
 
     println!("zpools:");
     if zpools.is_empty() {
@@ -133,8 +146,13 @@ async fn cmd_zpools_list(
 /// Runs `omdb sled-agent bootstore status`
 async fn cmd_bootstore_status(
     client: &sled_agent_client::Client,
+    format: OutputFormat,
 ) -> Result<(), anyhow::Error> {
     let status = client.bootstore_status().await.context("bootstore status")?;
+    if format == OutputFormat::Json {
+        return print_json(&*status);
+    }
+    ensure_table_format(format, "omdb sled-agent bootstore status")?;
     println!("fsm ledger generation: {}", status.fsm_ledger_generation);
     println!(
         "network config ledger generation: {:?}",
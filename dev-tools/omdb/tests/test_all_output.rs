@@ -8,9 +8,19 @@
 //! sure you're only breaking what you intend.
 
 use expectorate::assert_contents;
+use nexus_db_queries::context::OpContext;
+use nexus_db_queries::db::lookup::LookupPath;
+use nexus_test_utils::resource_helpers::create_default_ip_pool;
+use nexus_test_utils::resource_helpers::create_disk;
+use nexus_test_utils::resource_helpers::create_instance_with;
+use nexus_test_utils::resource_helpers::create_project;
+use nexus_test_utils::resource_helpers::object_delete;
+use nexus_test_utils::resource_helpers::DiskTest;
+use nexus_test_utils::SLED_AGENT_UUID;
 use nexus_test_utils_macros::nexus_test;
 use nexus_types::deployment::SledFilter;
 use nexus_types::deployment::UnstableReconfiguratorState;
+use nexus_types::external_api::params;
 use omicron_test_utils::dev::test_cmds::path_to_executable;
 use omicron_test_utils::dev::test_cmds::redact_extra;
 use omicron_test_utils::dev::test_cmds::run_command;
@@ -46,14 +56,23 @@ async fn test_omdb_usage_errors() {
         &["db", "dns"],
         &["db", "dns", "diff"],
         &["db", "dns", "names"],
+        &["db", "instance"],
+        &["db", "sagas"],
         &["db", "sleds", "--help"],
         &["db", "snapshots"],
         &["db", "network"],
+        &["db", "volume"],
         &["mgs"],
+        // These have no machine-readable output.
+        &["mgs", "dashboard", "--format", "json"],
+        &["mgs", "serial-console-history", "0", "--format", "json"],
         &["nexus"],
         &["nexus", "background-tasks"],
         &["nexus", "blueprints"],
+        &["nexus", "sagas"],
         &["nexus", "sleds"],
+        &["oximeter", "query", "--help"],
+        &["oximeter", "schema", "--help"],
         &["sled-agent"],
         &["sled-agent", "zones"],
         &["sled-agent", "zpools"],
@@ -87,13 +106,26 @@ async fn test_omdb_success_cases(cptestctx: &ControlPlaneTestContext) {
 
     let invocations: &[&[&str]] = &[
         &["db", "disks", "list"],
+        &["db", "disks", "list", "--format", "json"],
         &["db", "dns", "show"],
         &["db", "dns", "diff", "external", "2"],
         &["db", "dns", "names", "external", "2"],
+        &["db", "dns", "names", "external", "2", "--format", "json"],
         &["db", "instances"],
         &["db", "reconfigurator-save", tmppath.as_str()],
         &["db", "sleds"],
         &["db", "sleds", "-F", "discretionary"],
+        &["db", "sleds", "-F", "discretionary", "--format", "json"],
+        &["db", "sleds", "--format", "csv"],
+        // Reports that are not a single list of rows can't be printed as CSV.
+        &[
+            "db",
+            "sagas",
+            "show",
+            "00000000-0000-0000-0000-000000000000",
+            "--format",
+            "csv",
+        ],
         &["mgs", "inventory"],
         &["nexus", "background-tasks", "doc"],
         &["nexus", "background-tasks", "show"],
@@ -114,6 +146,15 @@ async fn test_omdb_success_cases(cptestctx: &ControlPlaneTestContext) {
             &initial_blueprint_id,
             "current-target",
         ],
+        &[
+            "nexus",
+            "blueprints",
+            "diff",
+            &initial_blueprint_id,
+            "current-target",
+            "--format",
+            "json",
+        ],
         // We can't easily test the sled agent output because that's only
         // provided by a real sled agent, which is not available in the
         // ControlPlaneTestContext.
@@ -140,6 +181,64 @@ async fn test_omdb_success_cases(cptestctx: &ControlPlaneTestContext) {
         .await;
     }
 
+    // These commands need resources that are created through the external
+    // API.  They run after the ones above so that those don't list them.
+    DiskTest::new(cptestctx).await;
+    create_project(&cptestctx.external_client, "omdb-project").await;
+    let disk =
+        create_disk(&cptestctx.external_client, "omdb-project", "omdb-disk")
+            .await;
+    let datastore = cptestctx.server.server_context().nexus.datastore();
+    let opctx =
+        OpContext::for_tests(cptestctx.logctx.log.clone(), datastore.clone());
+    let (.., db_disk) = LookupPath::new(&opctx, datastore)
+        .disk_id(disk.identity.id)
+        .fetch()
+        .await
+        .expect("failed to look up disk");
+    let volume_id = db_disk.volume_id.to_string();
+
+    // Creating and then deleting an instance leaves it with two sagas, which
+    // are shown newest first.
+    create_default_ip_pool(&cptestctx.external_client).await;
+    let instance = create_instance_with(
+        &cptestctx.external_client,
+        "omdb-project",
+        "omdb-instance",
+        &params::InstanceNetworkInterfaceAttachment::None,
+        Vec::new(),
+        Vec::new(),
+        false,
+    )
+    .await;
+    object_delete(
+        &cptestctx.external_client,
+        "/v1/instances/omdb-instance?project=omdb-project",
+    )
+    .await;
+    let instance_id = instance.identity.id.to_string();
+
+    let invocations: &[&[&str]] = &[
+        &["db", "volume", "show", &volume_id],
+        &["db", "volume", "affected", "--sled", SLED_AGENT_UUID],
+        &["db", "instance", "show", &instance_id],
+        &["db", "instance", "show", &instance_id, "--no-sagas"],
+        &["db", "instance", "show", &instance_id, "--format", "json"],
+    ];
+
+    for args in invocations {
+        println!("running commands with args: {:?}", args);
+        let p = postgres_url.to_string();
+        do_run_extra(
+            &mut output,
+            move |exec| exec.env("OMDB_DB_URL", &p),
+            &cmd_path,
+            args,
+            &ExtraRedactions::new(),
+        )
+        .await;
+    }
+
     assert_contents("tests/successes.out", &output);
 
     // The `reconfigurator-save` output is not easy to compare as a string.  But
@@ -13,6 +13,10 @@ use dropshot::test_util::LogContext;
 use futures::stream::StreamExt;
 use nexus_config::NexusConfig;
 use nexus_test_interface::NexusServer;
+use nexus_test_utils::sim_rack::SimRackConfig;
+use nexus_test_utils::sim_rack::SimSledConfig;
+use nexus_test_utils::ControlPlaneTestContext;
+use nexus_test_utils::SimMode;
 use omicron_common::cmd::fatal;
 use omicron_common::cmd::CmdError;
 use omicron_test_utils::dev;
@@ -21,6 +25,11 @@ use signal_hook_tokio::Signals;
 use std::io::Write;
 use std::os::unix::prelude::OpenOptionsExt;
 use std::path::PathBuf;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::BufReader;
+use tokio::io::Lines;
+use tokio::io::Stdin;
+use uuid::Uuid;
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
@@ -465,6 +474,16 @@ struct RunAllArgs {
     /// Nexus external API listen port.  Use `0` to request any available port.
     #[clap(long, action)]
     nexus_listen_port: Option<u16>,
+
+    /// TOML file describing the simulated rack: its sleds, their disks and
+    /// policies, and the simulated management network
+    #[clap(long, action)]
+    rack_config: Option<Utf8PathBuf>,
+
+    /// Read commands from stdin for adding and removing simulated sleds
+    /// while running (enter `help` for a list)
+    #[clap(long, action)]
+    interactive: bool,
 }
 
 async fn cmd_run_all(args: &RunAllArgs) -> Result<(), anyhow::Error> {
@@ -487,10 +506,20 @@ async fn cmd_run_all(args: &RunAllArgs) -> Result<(), anyhow::Error> {
         config.deployment.dropshot_external.dropshot.bind_address.set_port(p);
     }
 
+    let rack_config = match &args.rack_config {
+        Some(path) => {
+            let contents = std::fs::read_to_string(path)
+                .with_context(|| format!("reading {path}"))?;
+            toml::from_str(&contents)
+                .with_context(|| format!("parsing {path}"))?
+        }
+        None => SimRackConfig::default(),
+    };
+
     println!("omicron-dev: setting up all services ... ");
-    let cptestctx = nexus_test_utils::omicron_dev_setup_with_config::<
+    let mut cptestctx = nexus_test_utils::omicron_dev_setup_with_config::<
         omicron_nexus::Server,
-    >(&mut config)
+    >(&mut config, rack_config)
     .await
     .context("error setting up services")?;
     println!("omicron-dev: services are running.");
@@ -549,15 +578,49 @@ async fn cmd_run_all(args: &RunAllArgs) -> Result<(), anyhow::Error> {
             gateway.client.bind_address, location,
         );
     }
+    for sled_agent in cptestctx.sled_agents() {
+        println!(
+            "omicron-dev: simulated sled agent:  http://{} ({})",
+            sled_agent.http_server.local_addr(),
+            sled_agent.sled_agent.id,
+        );
+    }
     println!("omicron-dev: silo name:             {}", cptestctx.silo_name,);
     println!(
         "omicron-dev: privileged user name:  {}",
         cptestctx.user_name.as_ref(),
     );
 
-    // Wait for a signal.
-    let caught_signal = signal_stream.next().await;
-    assert_eq!(caught_signal.unwrap(), SIGINT);
+    // Wait for a signal, handling commands from stdin in the meantime if
+    // we've been asked to.
+    let mut stdin_lines = args.interactive.then(|| {
+        println!("omicron-dev: reading commands from stdin (try `help`)");
+        BufReader::new(tokio::io::stdin()).lines()
+    });
+    loop {
+        let line = tokio::select! {
+            caught_signal = signal_stream.next() => {
+                assert_eq!(caught_signal.unwrap(), SIGINT);
+                break;
+            }
+            line = next_stdin_line(&mut stdin_lines) => line,
+        };
+        match line {
+            Ok(Some(line)) => {
+                if let Err(error) = run_all_command(&mut cptestctx, &line).await
+                {
+                    eprintln!("omicron-dev: {:#}", error);
+                }
+            }
+            // Once stdin is closed, there's nothing left to do but wait for a
+            // signal.
+            Ok(None) => stdin_lines = None,
+            Err(error) => {
+                eprintln!("omicron-dev: error reading stdin: {}", error);
+                stdin_lines = None;
+            }
+        }
+    }
     eprintln!(
         "omicron-dev: caught signal, shutting down and removing \
         temporary directory"
@@ -567,6 +630,68 @@ async fn cmd_run_all(args: &RunAllArgs) -> Result<(), anyhow::Error> {
     Ok(())
 }
 
+async fn next_stdin_line(
+    lines: &mut Option<Lines<BufReader<Stdin>>>,
+) -> std::io::Result<Option<String>> {
+    match lines {
+        Some(lines) => lines.next_line().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Handles one command read from stdin by `run-all --interactive`
+async fn run_all_command(
+    cptestctx: &mut ControlPlaneTestContext<omicron_nexus::Server>,
+    line: &str,
+) -> Result<(), anyhow::Error> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    match words.as_slice() {
+        [] => (),
+        ["help"] => {
+            println!("omicron-dev: commands:");
+            println!("    sleds                 list simulated sled agents");
+            println!(
+                "    add-sled [DISKS]      start a new simulated sled \
+                 (default: 10 disks)"
+            );
+            println!(
+                "    remove-sled SLED_ID   stop a simulated sled and \
+                 expunge it"
+            );
+        }
+        ["sleds"] => {
+            for sled_agent in cptestctx.sled_agents() {
+                println!(
+                    "omicron-dev: simulated sled agent:  http://{} ({})",
+                    sled_agent.http_server.local_addr(),
+                    sled_agent.sled_agent.id,
+                );
+            }
+        }
+        ["add-sled", rest @ ..] => {
+            let mut sled = SimSledConfig::default();
+            match rest {
+                [] => (),
+                [disks] => {
+                    sled.disks =
+                        disks.parse().context("parsing number of disks")?;
+                }
+                _ => bail!("usage: add-sled [DISKS]"),
+            }
+            let sled_id =
+                cptestctx.add_sled_agent(&sled, SimMode::Auto).await?;
+            println!("omicron-dev: added sled {}", sled_id);
+        }
+        ["remove-sled", sled_id] => {
+            let sled_id: Uuid = sled_id.parse().context("parsing sled id")?;
+            cptestctx.remove_sled_agent(sled_id).await?;
+            println!("omicron-dev: removed sled {}", sled_id);
+        }
+        _ => bail!("unrecognized command: {:?} (try `help`)", line),
+    }
+    Ok(())
+}
+
 #[derive(Clone, Debug, Args)]
 struct CertCreateArgs {
     /// path to where the generated certificate and key files should go
@@ -4,7 +4,8 @@
 
 //! developer REPL for driving blueprint planning
 
-use anyhow::{anyhow, bail, Context};
+use anyhow::{anyhow, bail, ensure, Context};
+use camino::Utf8Path;
 use camino::Utf8PathBuf;
 use clap::CommandFactory;
 use clap::FromArgMatches;
@@ -16,18 +17,22 @@ use nexus_reconfigurator_execution::blueprint_external_dns_config;
 use nexus_reconfigurator_execution::blueprint_internal_dns_config;
 use nexus_reconfigurator_planning::blueprint_builder::BlueprintBuilder;
 use nexus_reconfigurator_planning::blueprint_builder::EnsureMultiple;
+use nexus_reconfigurator_planning::example::ExampleSystem;
 use nexus_reconfigurator_planning::planner::Planner;
 use nexus_reconfigurator_planning::system::{
     SledBuilder, SledHwInventory, SystemDescription,
 };
+use nexus_types::deployment::BlueprintDiff;
 use nexus_types::deployment::BlueprintZoneFilter;
 use nexus_types::deployment::OmicronZoneNic;
 use nexus_types::deployment::PlanningInput;
 use nexus_types::deployment::SledFilter;
 use nexus_types::deployment::{Blueprint, UnstableReconfiguratorState};
+use nexus_types::external_api::views::SledPolicy;
+use nexus_types::external_api::views::SledState;
 use nexus_types::internal_api::params::DnsConfigParams;
+use nexus_types::internal_api::params::DnsConfigZone;
 use nexus_types::inventory::Collection;
-use nexus_types::inventory::OmicronZonesConfig;
 use nexus_types::inventory::SledRole;
 use omicron_common::api::external::Generation;
 use omicron_common::api::external::Name;
@@ -37,8 +42,10 @@ use omicron_uuid_kinds::SledUuid;
 use omicron_uuid_kinds::VnicUuid;
 use reedline::{Reedline, Signal};
 use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use std::io::BufRead;
-use swrite::{swriteln, SWrite};
+use std::str::FromStr;
+use swrite::{swrite, swriteln, SWrite};
 use tabled::Tabled;
 use uuid::Uuid;
 
@@ -74,14 +81,60 @@ struct ReconfiguratorSim {
     /// Policy overrides
     num_nexus: Option<u16>,
 
+    /// Seed for the RNGs used to generate blueprints and collections
+    ///
+    /// When set, the ids generated by a given sequence of commands are the same
+    /// from one run to the next.
+    rng_seed: Option<String>,
+    /// number of RNGs seeded so far (see `next_rng_seed()`)
+    rng_count: u64,
+
     log: slog::Logger,
 }
 
 impl ReconfiguratorSim {
-    fn blueprint_lookup(&self, id: Uuid) -> Result<&Blueprint, anyhow::Error> {
-        self.blueprints
-            .get(&id)
-            .ok_or_else(|| anyhow!("no such blueprint: {}", id))
+    fn blueprint_lookup(
+        &self,
+        id: BlueprintIdOpt,
+    ) -> Result<&Blueprint, anyhow::Error> {
+        match id {
+            BlueprintIdOpt::Latest => self
+                .blueprints
+                .last()
+                .map(|(_, blueprint)| blueprint)
+                .ok_or_else(|| anyhow!("there are no blueprints")),
+            BlueprintIdOpt::Id(id) => self
+                .blueprints
+                .get(&id)
+                .ok_or_else(|| anyhow!("no such blueprint: {}", id)),
+        }
+    }
+
+    fn collection_lookup(
+        &self,
+        id: CollectionIdOpt,
+    ) -> Result<&Collection, anyhow::Error> {
+        match id {
+            CollectionIdOpt::Latest => self
+                .collections
+                .last()
+                .map(|(_, collection)| collection)
+                .ok_or_else(|| anyhow!("there are no inventory collections")),
+            CollectionIdOpt::Id(id) => self
+                .collections
+                .get(&id)
+                .ok_or_else(|| anyhow!("no such inventory collection: {}", id)),
+        }
+    }
+
+    /// Returns a seed for a new RNG, if the user has configured one
+    ///
+    /// Each call returns a different seed so that successive blueprints and
+    /// collections don't wind up with the same ids.
+    fn next_rng_seed(&mut self) -> Option<(String, u64)> {
+        let seed = self.rng_seed.clone()?;
+        self.rng_count += 1;
+        Some((seed, self.rng_count))
     }
 
     fn blueprint_insert_new(&mut self, blueprint: Blueprint) {
@@ -132,11 +185,25 @@ impl ReconfiguratorSim {
         //   executing and newer if other blueprints have changed DNS in the
         //   meantime).
         //
-        // But in this CLI, there's no execution at all.  As a result, there's
-        // no way to really choose between these -- and it doesn't really
-        // matter, either.  We'll just pick the parent blueprint's.
-        builder.set_internal_dns_version(parent_blueprint.internal_dns_version);
-        builder.set_external_dns_version(parent_blueprint.external_dns_version);
+        // In this CLI, execution only happens when the user asks for it (with
+        // `blueprint-execute`), and that updates the DNS configurations we
+        // keep here.  So we use the latest of those, which is exactly what a
+        // real system would find.  If we have none (e.g., nothing has been
+        // loaded), we fall back to the parent blueprint's.
+        builder.set_internal_dns_version(
+            self.internal_dns
+                .keys()
+                .last()
+                .copied()
+                .unwrap_or(parent_blueprint.internal_dns_version),
+        );
+        builder.set_external_dns_version(
+            self.external_dns
+                .keys()
+                .last()
+                .copied()
+                .unwrap_or(parent_blueprint.external_dns_version),
+        );
 
         for (_, zone) in
             parent_blueprint.all_omicron_zones(BlueprintZoneFilter::All)
@@ -166,8 +233,26 @@ impl ReconfiguratorSim {
 
 /// interactive REPL for exploring the planner
 #[derive(Parser, Debug)]
+#[command(args_conflicts_with_subcommands = true)]
 struct CmdReconfiguratorSim {
+    /// file of commands to run instead of starting the REPL
     input_file: Option<Utf8PathBuf>,
+
+    #[command(subcommand)]
+    command: Option<CliCommands>,
+}
+
+#[derive(Debug, Subcommand)]
+enum CliCommands {
+    /// run a scenario file, stopping at the first command that fails
+    ///
+    /// Scenario files contain the same commands as the REPL, usually along
+    /// with `assert` commands that check the results.  Lines starting with `#`
+    /// are comments.
+    Run {
+        /// scenario file to run
+        scenario_file: Utf8PathBuf,
+    },
 }
 
 // REPL implementation
@@ -191,22 +276,14 @@ fn main() -> anyhow::Result<()> {
         silo_names: vec!["example-silo".parse().unwrap()],
         external_dns_zone_name: String::from("oxide.example"),
         num_nexus: None,
+        rng_seed: None,
+        rng_count: 0,
     };
 
-    if let Some(input_file) = cmd.input_file {
-        let file = std::fs::File::open(&input_file)
-            .with_context(|| format!("open {:?}", &input_file))?;
-        let bufread = std::io::BufReader::new(file);
-        for maybe_buffer in bufread.lines() {
-            let buffer = maybe_buffer
-                .with_context(|| format!("read {:?}", &input_file))?;
-            println!("> {}", buffer);
-            match process_entry(&mut sim, buffer) {
-                LoopResult::Continue => (),
-                LoopResult::Bail(error) => return Err(error),
-            }
-            println!("");
-        }
+    if let Some(CliCommands::Run { scenario_file }) = cmd.command {
+        run_file(&mut sim, &scenario_file, true)?;
+    } else if let Some(input_file) = cmd.input_file {
+        run_file(&mut sim, &input_file, false)?;
     } else {
         let mut ed = Reedline::create();
         let prompt = reedline::DefaultPrompt::new(
@@ -217,7 +294,7 @@ fn main() -> anyhow::Result<()> {
             match ed.read_line(&prompt) {
                 Ok(Signal::Success(buffer)) => {
                     match process_entry(&mut sim, buffer) {
-                        LoopResult::Continue => (),
+                        LoopResult::Continue | LoopResult::CommandFailed => (),
                         LoopResult::Bail(error) => return Err(error),
                     }
                 }
@@ -232,6 +309,37 @@ fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Runs each command in `input_file`, printing it along with its output
+///
+/// If `stop_on_failure` is true, a command that fails (including a failed
+/// assertion) causes this function to return an error.  Otherwise, failures
+/// are reported and the next command is run, as in the REPL.
+fn run_file(
+    sim: &mut ReconfiguratorSim,
+    input_file: &Utf8Path,
+    stop_on_failure: bool,
+) -> anyhow::Result<()> {
+    let file = std::fs::File::open(input_file)
+        .with_context(|| format!("open {:?}", input_file))?;
+    let bufread = std::io::BufReader::new(file);
+    for (i, maybe_buffer) in bufread.lines().enumerate() {
+        let buffer =
+            maybe_buffer.with_context(|| format!("read {:?}", input_file))?;
+        println!("> {}", buffer);
+        match process_entry(sim, buffer) {
+            LoopResult::Continue => (),
+            LoopResult::CommandFailed if !stop_on_failure => (),
+            LoopResult::CommandFailed => {
+                bail!("{:?} line {}: command failed", input_file, i + 1);
+            }
+            LoopResult::Bail(error) => return Err(error),
+        }
+        println!("");
+    }
+
+    Ok(())
+}
+
 /// Describes next steps after evaluating one "line" of user input
 ///
 /// This could just be `Result`, but it's easy to misuse that here because
@@ -242,6 +350,11 @@ enum LoopResult {
     /// Show the prompt and accept another command
     Continue,
 
+    /// The command failed (and the failure has been reported)
+    ///
+    /// The REPL carries on as with `Continue`, but scenario files stop here.
+    CommandFailed,
+
     /// Exit the REPL with a fatal error
     Bail(anyhow::Error),
 }
@@ -251,8 +364,9 @@ fn process_entry(sim: &mut ReconfiguratorSim, entry: String) -> LoopResult {
     // If no input was provided, take another lap (print the prompt and accept
     // another line).  This gets handled specially because otherwise clap would
     // treat this as a usage error and print a help message, which isn't what we
-    // want here.
-    if entry.trim().is_empty() {
+    // want here.  Comments are skipped the same way.
+    let trimmed = entry.trim();
+    if trimmed.is_empty() || trimmed.starts_with('#') {
         return LoopResult::Continue;
     }
 
@@ -268,9 +382,12 @@ fn process_entry(sim: &mut ReconfiguratorSim, entry: String) -> LoopResult {
         .and_then(|matches| TopLevelArgs::from_arg_matches(&matches));
     let command = match parsed_command {
         Err(error) => {
-            // We failed to parse the command.  Print the error.
+            // We failed to parse the command.  Print the error.  (This is
+            // also how clap handles requests for help, which aren't failures.)
+            let failed = error.use_stderr();
             return match error.print() {
                 // Assuming that worked, just take another lap.
+                Ok(_) if failed => LoopResult::CommandFailed,
                 Ok(_) => LoopResult::Continue,
                 // If we failed to even print the error, that itself is a fatal
                 // error.
@@ -287,6 +404,7 @@ fn process_entry(sim: &mut ReconfiguratorSim, entry: String) -> LoopResult {
         Commands::SledList => cmd_sled_list(sim),
         Commands::SledAdd(args) => cmd_sled_add(sim, args),
         Commands::SledShow(args) => cmd_sled_show(sim, args),
+        Commands::SledExpunge(args) => cmd_sled_expunge(sim, args),
         Commands::SiloList => cmd_silo_list(sim),
         Commands::SiloAdd(args) => cmd_silo_add(sim, args),
         Commands::SiloRemove(args) => cmd_silo_remove(sim, args),
@@ -302,20 +420,27 @@ fn process_entry(sim: &mut ReconfiguratorSim, entry: String) -> LoopResult {
             cmd_blueprint_diff_inventory(sim, args)
         }
         Commands::BlueprintSave(args) => cmd_blueprint_save(sim, args),
+        Commands::BlueprintExecute(args) => cmd_blueprint_execute(sim, args),
         Commands::Show => cmd_show(sim),
         Commands::Set(args) => cmd_set(sim, args),
         Commands::Load(args) => cmd_load(sim, args),
+        Commands::LoadExample(args) => cmd_load_example(sim, args),
         Commands::FileContents(args) => cmd_file_contents(args),
         Commands::Save(args) => cmd_save(sim, args),
+        Commands::Assert(args) => cmd_assert(sim, args),
     };
 
     match cmd_result {
-        Err(error) => println!("error: {:#}", error),
-        Ok(Some(s)) => println!("{}", s),
-        Ok(None) => (),
+        Err(error) => {
+            println!("error: {:#}", error);
+            LoopResult::CommandFailed
+        }
+        Ok(Some(s)) => {
+            println!("{}", s);
+            LoopResult::Continue
+        }
+        Ok(None) => LoopResult::Continue,
     }
-
-    LoopResult::Continue
 }
 
 // clap configuration for the REPL commands
@@ -335,6 +460,8 @@ enum Commands {
     SledAdd(SledAddArgs),
     /// show details about one sled
     SledShow(SledArgs),
+    /// expunge a sled (mark it as permanently gone)
+    SledExpunge(SledArgs),
 
     /// list silos
     SiloList,
@@ -364,6 +491,12 @@ enum Commands {
     BlueprintDiffInventory(BlueprintDiffInventoryArgs),
     /// write one blueprint to a file
     BlueprintSave(BlueprintSaveArgs),
+    /// simulate executing a blueprint against the configured sleds
+    ///
+    /// This deploys the blueprint's zones and disks to each in-service sled,
+    /// updates internal and external DNS, decommissions sleds, and then
+    /// generates a new inventory collection.
+    BlueprintExecute(BlueprintExecuteArgs),
 
     /// show system properties
     Show,
@@ -375,8 +508,14 @@ enum Commands {
     Save(SaveArgs),
     /// load state from a file
     Load(LoadArgs),
+    /// load an example system (sleds, an inventory collection, and a blueprint)
+    LoadExample(LoadExampleArgs),
     /// show information about what's in a saved file
     FileContents(FileContentsArgs),
+
+    /// check a condition, failing the command if it does not hold
+    #[command(subcommand)]
+    Assert(AssertCommands),
 }
 
 #[derive(Debug, Args)]
@@ -403,18 +542,61 @@ struct InventoryArgs {
     collection_id: CollectionUuid,
 }
 
+/// Identifies a blueprint, either by id or as the most recently added one
+#[derive(Clone, Copy, Debug)]
+enum BlueprintIdOpt {
+    /// the blueprint most recently created or loaded
+    Latest,
+    /// a specific blueprint
+    Id(Uuid),
+}
+
+impl FromStr for BlueprintIdOpt {
+    type Err = uuid::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "latest" {
+            Ok(BlueprintIdOpt::Latest)
+        } else {
+            Ok(BlueprintIdOpt::Id(s.parse()?))
+        }
+    }
+}
+
+/// Identifies an inventory collection, either by id or as the most recently
+/// added one
+#[derive(Clone, Copy, Debug)]
+enum CollectionIdOpt {
+    /// the collection most recently generated or loaded
+    Latest,
+    /// a specific collection
+    Id(CollectionUuid),
+}
+
+impl FromStr for CollectionIdOpt {
+    type Err = <CollectionUuid as FromStr>::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "latest" {
+            Ok(CollectionIdOpt::Latest)
+        } else {
+            Ok(CollectionIdOpt::Id(s.parse()?))
+        }
+    }
+}
+
 #[derive(Debug, Args)]
 struct BlueprintPlanArgs {
-    /// id of the blueprint on which this one will be based
-    parent_blueprint_id: Uuid,
-    /// id of the inventory collection to use in planning
-    collection_id: CollectionUuid,
+    /// id of the blueprint on which this one will be based (or "latest")
+    parent_blueprint_id: BlueprintIdOpt,
+    /// id of the inventory collection to use in planning (or "latest")
+    collection_id: CollectionIdOpt,
 }
 
 #[derive(Debug, Args)]
 struct BlueprintEditArgs {
-    /// id of the blueprint to edit
-    blueprint_id: Uuid,
+    /// id of the blueprint to edit (or "latest")
+    blueprint_id: BlueprintIdOpt,
     /// "creator" field for the new blueprint
     #[arg(long)]
     creator: Option<String>,
@@ -436,8 +618,8 @@ enum BlueprintEditCommands {
 
 #[derive(Debug, Args)]
 struct BlueprintArgs {
-    /// id of the blueprint
-    blueprint_id: Uuid,
+    /// id of the blueprint (or "latest")
+    blueprint_id: BlueprintIdOpt,
 }
 
 #[derive(Debug, Args)]
@@ -446,8 +628,8 @@ struct BlueprintDiffDnsArgs {
     dns_group: CliDnsGroup,
     /// DNS version to diff against
     dns_version: u32,
-    /// id of the blueprint
-    blueprint_id: Uuid,
+    /// id of the blueprint (or "latest")
+    blueprint_id: BlueprintIdOpt,
 }
 
 #[derive(Clone, Copy, Debug, ValueEnum)]
@@ -458,26 +640,35 @@ enum CliDnsGroup {
 
 #[derive(Debug, Args)]
 struct BlueprintDiffInventoryArgs {
-    /// id of the inventory collection
-    collection_id: CollectionUuid,
-    /// id of the blueprint
-    blueprint_id: Uuid,
+    /// id of the inventory collection (or "latest")
+    collection_id: CollectionIdOpt,
+    /// id of the blueprint (or "latest")
+    blueprint_id: BlueprintIdOpt,
 }
 
 #[derive(Debug, Args)]
 struct BlueprintSaveArgs {
-    /// id of the blueprint
-    blueprint_id: Uuid,
+    /// id of the blueprint (or "latest")
+    blueprint_id: BlueprintIdOpt,
     /// output file
     filename: Utf8PathBuf,
 }
 
+#[derive(Debug, Args)]
+struct BlueprintExecuteArgs {
+    /// id of the blueprint (or "latest")
+    blueprint_id: BlueprintIdOpt,
+    /// simulate a failure to deploy to this sled (may be repeated)
+    #[arg(long)]
+    fail_sled: Vec<SledUuid>,
+}
+
 #[derive(Debug, Args)]
 struct BlueprintDiffArgs {
-    /// id of the first blueprint
-    blueprint1_id: Uuid,
-    /// id of the second blueprint
-    blueprint2_id: Uuid,
+    /// id of the first blueprint (or "latest")
+    blueprint1_id: BlueprintIdOpt,
+    /// id of the second blueprint (or "latest")
+    blueprint2_id: BlueprintIdOpt,
 }
 
 #[derive(Debug, Subcommand)]
@@ -486,6 +677,8 @@ enum SetArgs {
     NumNexus { num_nexus: u16 },
     /// system's external DNS zone name (suffix)
     ExternalDnsZoneName { zone_name: String },
+    /// seed for generating ids, making output reproducible
+    Seed { seed: String },
 }
 
 #[derive(Debug, Args)]
@@ -498,6 +691,20 @@ struct LoadArgs {
     collection_id: Option<CollectionUuid>,
 }
 
+#[derive(Debug, Args)]
+struct LoadExampleArgs {
+    /// number of sleds in the example system
+    #[arg(long, default_value_t = 3)]
+    nsleds: usize,
+
+    /// seed for generating ids
+    ///
+    /// This also becomes the seed for blueprints and collections generated
+    /// afterwards (see `set seed`).
+    #[arg(long, default_value = "reconfigurator-cli")]
+    seed: String,
+}
+
 #[derive(Debug, Args)]
 struct FileContentsArgs {
     /// input file
@@ -510,6 +717,81 @@ struct SaveArgs {
     filename: Utf8PathBuf,
 }
 
+#[derive(Debug, Subcommand)]
+enum AssertCommands {
+    /// check the number of zones in a blueprint that should be running
+    ZoneCount(AssertZoneCountArgs),
+    /// check the number of zones that should be running on each in-service sled
+    ZonesPerSled(AssertZonesPerSledArgs),
+    /// check that a blueprint makes no changes to its parent
+    NoChanges(BlueprintArgs),
+    /// check the number of changes a blueprint makes to its parent
+    Diff(AssertDiffArgs),
+    /// check that the sleds in an inventory collection are running the zones
+    /// described by a blueprint
+    Converged(AssertConvergedArgs),
+}
+
+#[derive(Debug, Args)]
+struct AssertZoneCountArgs {
+    /// id of the blueprint (or "latest")
+    blueprint_id: BlueprintIdOpt,
+    /// expected number of zones
+    count: usize,
+    /// only count zones on this sled
+    #[arg(long)]
+    sled: Option<SledUuid>,
+    /// only count zones of this kind (e.g., "nexus", "internal_ntp")
+    #[arg(long)]
+    kind: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct AssertZonesPerSledArgs {
+    /// id of the blueprint (or "latest")
+    blueprint_id: BlueprintIdOpt,
+    /// expected number of zones on each sled
+    count: usize,
+    /// only count zones of this kind (e.g., "nexus", "internal_ntp")
+    #[arg(long)]
+    kind: Option<String>,
+}
+
+#[derive(Debug, Args)]
+struct AssertDiffArgs {
+    /// id of the blueprint (or "latest")
+    blueprint_id: BlueprintIdOpt,
+    /// expected number of sleds added
+    #[arg(long)]
+    sleds_added: Option<usize>,
+    /// expected number of sleds removed
+    #[arg(long)]
+    sleds_removed: Option<usize>,
+    /// expected number of sleds modified
+    #[arg(long)]
+    sleds_modified: Option<usize>,
+    /// expected number of zones added
+    #[arg(long)]
+    zones_added: Option<usize>,
+    /// expected number of zones removed
+    #[arg(long)]
+    zones_removed: Option<usize>,
+    /// expected number of zones modified
+    #[arg(long)]
+    zones_modified: Option<usize>,
+}
+
+#[derive(Debug, Args)]
+struct AssertConvergedArgs {
+    /// id of the inventory collection (or "latest")
+    collection_id: CollectionIdOpt,
+    /// id of the blueprint (or "latest")
+    blueprint_id: BlueprintIdOpt,
+    /// sled that is expected *not* to have converged (may be repeated)
+    #[arg(long)]
+    except: Vec<SledUuid>,
+}
+
 // Command handlers
 
 fn cmd_silo_list(
@@ -613,6 +895,17 @@ fn cmd_sled_show(
     Ok(Some(s))
 }
 
+fn cmd_sled_expunge(
+    sim: &mut ReconfiguratorSim,
+    args: SledArgs,
+) -> anyhow::Result<Option<String>> {
+    let _ = sim
+        .system
+        .sled_set_policy(args.sled_id, SledPolicy::Expunged)
+        .context("expunging sled")?;
+    Ok(Some(format!("expunged sled {}", args.sled_id)))
+}
+
 fn cmd_inventory_list(
     sim: &mut ReconfiguratorSim,
 ) -> anyhow::Result<Option<String>> {
@@ -645,31 +938,27 @@ fn cmd_inventory_list(
 fn cmd_inventory_generate(
     sim: &mut ReconfiguratorSim,
 ) -> anyhow::Result<Option<String>> {
+    let collection_id = inventory_generate(sim)?;
+    Ok(Some(format!(
+        "generated inventory collection {} from configured sleds",
+        collection_id
+    )))
+}
+
+/// Generates an inventory collection describing the current state of the
+/// configured sleds (including any zones deployed by `blueprint-execute`)
+fn inventory_generate(
+    sim: &mut ReconfiguratorSim,
+) -> anyhow::Result<CollectionUuid> {
     let mut builder =
         sim.system.to_collection_builder().context("generating inventory")?;
-    // For an inventory we just generated from thin air, pretend like each sled
-    // has no zones on it.
-    let planning_input =
-        sim.system.to_planning_input_builder().unwrap().build();
-    for sled_id in planning_input.all_sled_ids(SledFilter::Commissioned) {
-        builder
-            .found_sled_omicron_zones(
-                "fake sled agent",
-                sled_id,
-                OmicronZonesConfig {
-                    generation: Generation::new(),
-                    zones: vec![],
-                },
-            )
-            .context("recording Omicron zones")?;
+    if let Some(seed) = sim.next_rng_seed() {
+        builder.set_rng_seed(seed);
     }
     let inventory = builder.build();
-    let rv = format!(
-        "generated inventory collection {} from configured sleds",
-        inventory.id
-    );
-    sim.collections.insert(inventory.id, inventory);
-    Ok(Some(rv))
+    let collection_id = inventory.id;
+    sim.collections.insert(collection_id, inventory);
+    Ok(collection_id)
 }
 
 fn cmd_blueprint_list(
@@ -696,16 +985,13 @@ fn cmd_blueprint_plan(
     sim: &mut ReconfiguratorSim,
     args: BlueprintPlanArgs,
 ) -> anyhow::Result<Option<String>> {
-    let parent_blueprint_id = args.parent_blueprint_id;
-    let collection_id = args.collection_id;
-    let parent_blueprint = sim.blueprint_lookup(parent_blueprint_id)?;
-    let collection = sim
-        .collections
-        .get(&collection_id)
-        .ok_or_else(|| anyhow!("no such collection: {}", collection_id))?;
+    let rng_seed = sim.next_rng_seed();
+    let parent_blueprint = sim.blueprint_lookup(args.parent_blueprint_id)?;
+    let parent_blueprint_id = parent_blueprint.id;
+    let collection = sim.collection_lookup(args.collection_id)?;
     let creator = "reconfigurator-sim";
     let planning_input = sim.planning_input(parent_blueprint)?;
-    let planner = Planner::new_based_on(
+    let mut planner = Planner::new_based_on(
         sim.log.clone(),
         parent_blueprint,
         &planning_input,
@@ -713,6 +999,9 @@ fn cmd_blueprint_plan(
         collection,
     )
     .context("creating planner")?;
+    if let Some(seed) = rng_seed {
+        planner = planner.with_rng_seed(seed);
+    }
     let blueprint = planner.plan().context("generating blueprint")?;
     let rv = format!(
         "generated blueprint {} based on parent blueprint {}",
@@ -726,8 +1015,9 @@ fn cmd_blueprint_edit(
     sim: &mut ReconfiguratorSim,
     args: BlueprintEditArgs,
 ) -> anyhow::Result<Option<String>> {
-    let blueprint_id = args.blueprint_id;
-    let blueprint = sim.blueprint_lookup(blueprint_id)?;
+    let rng_seed = sim.next_rng_seed();
+    let blueprint = sim.blueprint_lookup(args.blueprint_id)?;
+    let blueprint_id = blueprint.id;
     let creator = args.creator.as_deref().unwrap_or("reconfigurator-cli");
     let planning_input = sim.planning_input(blueprint)?;
     let mut builder = BlueprintBuilder::new_based_on(
@@ -737,6 +1027,9 @@ fn cmd_blueprint_edit(
         creator,
     )
     .context("creating blueprint builder")?;
+    if let Some(seed) = rng_seed {
+        builder.set_rng_seed(seed);
+    }
 
     if let Some(comment) = args.comment {
         builder.comment(comment);
@@ -775,10 +1068,8 @@ fn cmd_blueprint_diff(
     args: BlueprintDiffArgs,
 ) -> anyhow::Result<Option<String>> {
     let mut rv = String::new();
-    let blueprint1_id = args.blueprint1_id;
-    let blueprint2_id = args.blueprint2_id;
-    let blueprint1 = sim.blueprint_lookup(blueprint1_id)?;
-    let blueprint2 = sim.blueprint_lookup(blueprint2_id)?;
+    let blueprint1 = sim.blueprint_lookup(args.blueprint1_id)?;
+    let blueprint2 = sim.blueprint_lookup(args.blueprint2_id)?;
 
     let sled_diff = blueprint2.diff_since_blueprint(&blueprint1);
     swriteln!(rv, "{}", sled_diff.display());
@@ -852,8 +1143,7 @@ fn cmd_blueprint_diff_dns(
 ) -> anyhow::Result<Option<String>> {
     let dns_group = args.dns_group;
     let dns_version = Generation::from(args.dns_version);
-    let blueprint_id = args.blueprint_id;
-    let blueprint = sim.blueprint_lookup(blueprint_id)?;
+    let blueprint = sim.blueprint_lookup(args.blueprint_id)?;
 
     let existing_dns_config = match dns_group {
         CliDnsGroup::Internal => sim.internal_dns.get(&dns_version),
@@ -889,12 +1179,8 @@ fn cmd_blueprint_diff_inventory(
     sim: &mut ReconfiguratorSim,
     args: BlueprintDiffInventoryArgs,
 ) -> anyhow::Result<Option<String>> {
-    let collection_id = args.collection_id;
-    let blueprint_id = args.blueprint_id;
-    let collection = sim.collections.get(&collection_id).ok_or_else(|| {
-        anyhow!("no such inventory collection: {}", collection_id)
-    })?;
-    let blueprint = sim.blueprint_lookup(blueprint_id)?;
+    let collection = sim.collection_lookup(args.collection_id)?;
+    let blueprint = sim.blueprint_lookup(args.blueprint_id)?;
     let diff = blueprint.diff_since_collection(&collection);
     Ok(Some(diff.display().to_string()))
 }
@@ -903,15 +1189,221 @@ fn cmd_blueprint_save(
     sim: &mut ReconfiguratorSim,
     args: BlueprintSaveArgs,
 ) -> anyhow::Result<Option<String>> {
-    let blueprint_id = args.blueprint_id;
-    let blueprint = sim.blueprint_lookup(blueprint_id)?;
+    let blueprint = sim.blueprint_lookup(args.blueprint_id)?;
 
     let output_path = &args.filename;
     let output_str = serde_json::to_string_pretty(&blueprint)
         .context("serializing blueprint")?;
     std::fs::write(&output_path, &output_str)
         .with_context(|| format!("write {:?}", output_path))?;
-    Ok(Some(format!("saved blueprint {} to {:?}", blueprint_id, output_path)))
+    Ok(Some(format!("saved blueprint {} to {:?}", blueprint.id, output_path)))
+}
+
+fn cmd_blueprint_execute(
+    sim: &mut ReconfiguratorSim,
+    args: BlueprintExecuteArgs,
+) -> anyhow::Result<Option<String>> {
+    let blueprint = sim.blueprint_lookup(args.blueprint_id)?.clone();
+    let sled_ids: BTreeSet<SledUuid> = blueprint
+        .blueprint_zones
+        .keys()
+        .chain(blueprint.blueprint_disks.keys())
+        .copied()
+        .collect();
+    for sled_id in &args.fail_sled {
+        ensure!(
+            sled_ids.contains(sled_id),
+            "sled {} is not in blueprint {}",
+            sled_id,
+            blueprint.id
+        );
+    }
+
+    // As in a real system, we only deploy to sleds that are in service.
+    let planning_input = sim
+        .system
+        .to_planning_input_builder()
+        .context("generating planning input")?
+        .build();
+    let in_service: BTreeSet<SledUuid> =
+        planning_input.all_sled_ids(SledFilter::InService).collect();
+
+    let mut ndeployed = 0;
+    let mut nfailed = 0;
+    let mut notes = Vec::new();
+    let mut errors = Vec::new();
+    for sled_id in sled_ids {
+        let zones = blueprint.blueprint_zones.get(&sled_id);
+        if !in_service.contains(&sled_id) {
+            // That's fine as long as nothing is supposed to be running there.
+            match zones {
+                Some(zones) if !zones.are_all_zones_expunged() => {
+                    errors.push(format!(
+                        "sled {}: not in service, but blueprint has zones \
+                         that should be running on it",
+                        sled_id
+                    ))
+                }
+                _ => (),
+            }
+            continue;
+        }
+
+        if args.fail_sled.contains(&sled_id) {
+            notes.push(format!("sled {}: simulated failure", sled_id));
+            nfailed += 1;
+            continue;
+        }
+
+        let result = match blueprint.blueprint_disks.get(&sled_id) {
+            Some(disks) => {
+                sim.system.sled_set_omicron_physical_disks(sled_id, disks)
+            }
+            None => Ok(&mut sim.system),
+        }
+        .and_then(|system| match zones {
+            Some(zones) => system.sled_set_omicron_zones(
+                sled_id,
+                zones.to_omicron_zones_config(
+                    BlueprintZoneFilter::ShouldBeRunning,
+                ),
+            ),
+            None => Ok(system),
+        });
+        match result {
+            Ok(_) => ndeployed += 1,
+            Err(error) => errors.push(format!("sled {}: {:#}", sled_id, error)),
+        }
+    }
+
+    let mut s = String::new();
+    swriteln!(s, "deployed zones and disks to {} sleds", ndeployed);
+    for note in &notes {
+        swriteln!(s, "{}", note);
+    }
+
+    // A real execution stops here if any sled could not be updated, so DNS
+    // and sled state are only updated once every sled has converged.
+    if nfailed > 0 || !errors.is_empty() {
+        swriteln!(
+            s,
+            "DNS and sled decommissioning: skipped \
+             (not all sleds were updated)"
+        );
+    } else {
+        let sleds_by_id = make_sleds_by_id(sim)?;
+        let internal_dns_zone = blueprint_internal_dns_config(
+            &blueprint,
+            &sleds_by_id,
+            &Default::default(),
+        );
+        let external_dns_zone = blueprint_external_dns_config(
+            &blueprint,
+            &sim.silo_names,
+            sim.external_dns_zone_name.clone(),
+        );
+        let dns_results = [
+            (
+                "internal",
+                execute_dns_one(
+                    &mut sim.internal_dns,
+                    blueprint.internal_dns_version,
+                    internal_dns_zone,
+                ),
+            ),
+            (
+                "external",
+                execute_dns_one(
+                    &mut sim.external_dns,
+                    blueprint.external_dns_version,
+                    external_dns_zone,
+                ),
+            ),
+        ];
+        for (dns_group, result) in dns_results {
+            match result {
+                Ok(message) => swriteln!(s, "{} DNS: {}", dns_group, message),
+                Err(error) => {
+                    errors.push(format!("{} DNS: {:#}", dns_group, error))
+                }
+            }
+        }
+
+        let commissioned: BTreeSet<SledUuid> =
+            planning_input.all_sled_ids(SledFilter::Commissioned).collect();
+        for (sled_id, state) in &blueprint.sled_state {
+            if *state == SledState::Decommissioned
+                && commissioned.contains(sled_id)
+            {
+                match sim.system.sled_set_state(*sled_id, *state) {
+                    Ok(_) => swriteln!(s, "sled {}: decommissioned", sled_id),
+                    Err(error) => {
+                        errors.push(format!("sled {}: {:#}", sled_id, error))
+                    }
+                }
+            }
+        }
+    }
+
+    // Inventory is collected regardless of how execution went.  That's what
+    // lets the next round of planning see any partial progress.
+    let collection_id = inventory_generate(sim)?;
+    swriteln!(s, "generated inventory collection {}", collection_id);
+
+    if !errors.is_empty() {
+        bail!(
+            "failed to execute blueprint {}:\n{}{}",
+            blueprint.id,
+            s,
+            errors.join("\n")
+        );
+    }
+
+    Ok(Some(s))
+}
+
+/// Simulates updating one DNS group to match a blueprint, returning a
+/// description of what changed
+///
+/// Like real execution, the update is made only if DNS is still at the
+/// generation that the blueprint was planned against.
+fn execute_dns_one(
+    dns: &mut BTreeMap<Generation, DnsConfigParams>,
+    blueprint_generation: Generation,
+    dns_zone_blueprint: DnsConfigZone,
+) -> anyhow::Result<String> {
+    if let Some((current_generation, current_config)) = dns.last_key_value() {
+        let dns_zone_current = current_config.sole_zone()?;
+        let diff = DnsDiff::new(dns_zone_current, &dns_zone_blueprint)
+            .context("failed to assemble DNS diff")?;
+        if diff.is_empty() {
+            return Ok(format!(
+                "unchanged (generation {})",
+                current_generation
+            ));
+        }
+        ensure!(
+            *current_generation == blueprint_generation,
+            "blueprint was planned against generation {}, but current \
+             generation is {}",
+            blueprint_generation,
+            current_generation
+        );
+    }
+
+    let new_generation = blueprint_generation.next();
+    dns.insert(
+        new_generation,
+        DnsConfigParams {
+            generation: u64::from(new_generation),
+            time_created: chrono::Utc::now(),
+            zones: vec![dns_zone_blueprint],
+        },
+    );
+    Ok(format!(
+        "updated from generation {} to generation {}",
+        blueprint_generation, new_generation
+    ))
 }
 
 fn cmd_save(
@@ -955,6 +1447,11 @@ fn cmd_show(sim: &mut ReconfiguratorSim) -> anyhow::Result<Option<String>> {
             None => String::from("default"),
         }
     );
+    swriteln!(
+        s,
+        "RNG seed: {}",
+        sim.rng_seed.as_deref().unwrap_or("none (ids are random)")
+    );
     Ok(Some(s))
 }
 
@@ -1010,6 +1507,12 @@ fn cmd_set(
             sim.external_dns_zone_name = zone_name;
             rv
         }
+        SetArgs::Seed { seed } => {
+            let rv = format!("{:?} -> {:?}", sim.rng_seed, seed);
+            sim.rng_seed = Some(seed);
+            sim.rng_count = 0;
+            rv
+        }
     }))
 }
 
@@ -1117,13 +1620,24 @@ fn cmd_load(
             },
         );
 
-        let result = sim.system.sled_full(
-            sled_id,
-            sled_details.policy,
-            sled_details.resources.clone(),
-            inventory_sp,
-            inventory_sled_agent,
-        );
+        let result = sim
+            .system
+            .sled_full(
+                sled_id,
+                sled_details.policy,
+                sled_details.resources.clone(),
+                inventory_sp,
+                inventory_sled_agent,
+            )
+            .and_then(|system| {
+                // Start the sled off running whatever zones it was running
+                // when the collection was taken.
+                match primary_collection.omicron_zones.get(&sled_id) {
+                    Some(found) => system
+                        .sled_set_omicron_zones(sled_id, found.zones.clone()),
+                    None => Ok(system),
+                }
+            });
 
         match result {
             Ok(_) => swriteln!(s, "sled {} loaded", sled_id),
@@ -1195,6 +1709,71 @@ fn cmd_load(
     Ok(Some(s))
 }
 
+fn cmd_load_example(
+    sim: &mut ReconfiguratorSim,
+    args: LoadExampleArgs,
+) -> anyhow::Result<Option<String>> {
+    let planning_input = sim
+        .system
+        .to_planning_input_builder()
+        .context("generating planning input")?
+        .build();
+    if planning_input.all_sled_ids(SledFilter::Commissioned).next().is_some()
+        || !sim.collections.is_empty()
+        || !sim.blueprints.is_empty()
+    {
+        bail!(
+            "an example system can only be loaded into an empty state \
+             (no sleds, collections, or blueprints)"
+        );
+    }
+
+    let example = ExampleSystem::new(&sim.log, &args.seed, args.nsleds);
+    sim.system = example.system;
+    if let Some(num_nexus) = sim.num_nexus {
+        sim.system.target_nexus_zone_count(usize::from(num_nexus));
+    }
+    let collection_id = example.collection.id;
+    let blueprint_id = example.blueprint.id;
+    sim.collections.insert(collection_id, example.collection);
+
+    // Start DNS off with the contents described by the example blueprint, as
+    // though that blueprint had already been executed.
+    let sleds_by_id = make_sleds_by_id(sim)?;
+    let internal_dns_zone = blueprint_internal_dns_config(
+        &example.blueprint,
+        &sleds_by_id,
+        &Default::default(),
+    );
+    let external_dns_zone = blueprint_external_dns_config(
+        &example.blueprint,
+        &sim.silo_names,
+        sim.external_dns_zone_name.clone(),
+    );
+    for (dns, zone) in [
+        (&mut sim.internal_dns, internal_dns_zone),
+        (&mut sim.external_dns, external_dns_zone),
+    ] {
+        dns.insert(
+            Generation::new(),
+            DnsConfigParams {
+                generation: u64::from(Generation::new()),
+                time_created: chrono::Utc::now(),
+                zones: vec![zone],
+            },
+        );
+    }
+
+    sim.blueprint_insert_new(example.blueprint);
+    sim.rng_seed = Some(args.seed);
+    sim.rng_count = 0;
+
+    Ok(Some(format!(
+        "loaded example system with {} sleds (collection {}, blueprint {})",
+        args.nsleds, collection_id, blueprint_id
+    )))
+}
+
 fn cmd_file_contents(args: FileContentsArgs) -> anyhow::Result<Option<String>> {
     let loaded = read_file(&args.filename)?;
 
@@ -1243,3 +1822,267 @@ fn cmd_file_contents(args: FileContentsArgs) -> anyhow::Result<Option<String>> {
 
     Ok(Some(s))
 }
+
+fn cmd_assert(
+    sim: &mut ReconfiguratorSim,
+    args: AssertCommands,
+) -> anyhow::Result<Option<String>> {
+    match args {
+        AssertCommands::ZoneCount(args) => cmd_assert_zone_count(sim, args),
+        AssertCommands::ZonesPerSled(args) => {
+            cmd_assert_zones_per_sled(sim, args)
+        }
+        AssertCommands::NoChanges(args) => cmd_assert_no_changes(sim, args),
+        AssertCommands::Diff(args) => cmd_assert_diff(sim, args),
+        AssertCommands::Converged(args) => cmd_assert_converged(sim, args),
+    }
+}
+
+/// Counts the zones in `blueprint` that should be running on `sled_id`,
+/// optionally only those of the given kind
+fn sled_zone_count(
+    blueprint: &Blueprint,
+    sled_id: SledUuid,
+    kind: Option<&str>,
+) -> usize {
+    blueprint
+        .all_omicron_zones(BlueprintZoneFilter::ShouldBeRunning)
+        .filter(|(zone_sled_id, zone)| {
+            *zone_sled_id == sled_id
+                && match kind {
+                    Some(kind) => zone.zone_type.kind().to_string() == kind,
+                    None => true,
+                }
+        })
+        .count()
+}
+
+fn describe_zones(kind: Option<&str>) -> String {
+    match kind {
+        Some(kind) => format!("{} zones", kind),
+        None => String::from("zones"),
+    }
+}
+
+fn cmd_assert_zone_count(
+    sim: &mut ReconfiguratorSim,
+    args: AssertZoneCountArgs,
+) -> anyhow::Result<Option<String>> {
+    let blueprint = sim.blueprint_lookup(args.blueprint_id)?;
+    let kind = args.kind.as_deref();
+    let (found, what) = match args.sled {
+        Some(sled_id) => (
+            sled_zone_count(blueprint, sled_id, kind),
+            format!("{} on sled {}", describe_zones(kind), sled_id),
+        ),
+        None => (
+            blueprint
+                .sleds()
+                .map(|sled_id| sled_zone_count(blueprint, sled_id, kind))
+                .sum(),
+            describe_zones(kind),
+        ),
+    };
+
+    if found != args.count {
+        bail!(
+            "assertion failed: blueprint {}: expected {} {}, found {}",
+            blueprint.id,
+            args.count,
+            what,
+            found
+        );
+    }
+
+    Ok(Some(format!("ok: blueprint {}: {}: {}", blueprint.id, what, found)))
+}
+
+fn cmd_assert_zones_per_sled(
+    sim: &mut ReconfiguratorSim,
+    args: AssertZonesPerSledArgs,
+) -> anyhow::Result<Option<String>> {
+    let blueprint = sim.blueprint_lookup(args.blueprint_id)?;
+    let kind = args.kind.as_deref();
+    let what = describe_zones(kind);
+    let planning_input = sim
+        .system
+        .to_planning_input_builder()
+        .context("generating planning input")?
+        .build();
+
+    let mut nsleds = 0;
+    let mut mismatches = Vec::new();
+    for sled_id in planning_input.all_sled_ids(SledFilter::InService) {
+        nsleds += 1;
+        let found = sled_zone_count(blueprint, sled_id, kind);
+        if found != args.count {
+            mismatches.push(format!("sled {}: {}", sled_id, found));
+        }
+    }
+
+    if !mismatches.is_empty() {
+        bail!(
+            "assertion failed: blueprint {}: expected {} {} on each sled, \
+             found {}",
+            blueprint.id,
+            args.count,
+            what,
+            mismatches.join(", ")
+        );
+    }
+
+    Ok(Some(format!(
+        "ok: blueprint {}: {} on each of {} sleds: {}",
+        blueprint.id, what, nsleds, args.count
+    )))
+}
+
+/// Returns the parent of `blueprint` along with the diff from that parent
+fn diff_from_parent<'a>(
+    sim: &'a ReconfiguratorSim,
+    blueprint: &Blueprint,
+) -> anyhow::Result<(&'a Blueprint, BlueprintDiff)> {
+    let parent_id = blueprint
+        .parent_blueprint_id
+        .ok_or_else(|| anyhow!("blueprint {} has no parent", blueprint.id))?;
+    let parent = sim
+        .blueprint_lookup(BlueprintIdOpt::Id(parent_id))
+        .with_context(|| format!("parent of blueprint {}", blueprint.id))?;
+    Ok((parent, blueprint.diff_since_blueprint(parent)))
+}
+
+fn cmd_assert_no_changes(
+    sim: &mut ReconfiguratorSim,
+    args: BlueprintArgs,
+) -> anyhow::Result<Option<String>> {
+    let blueprint = sim.blueprint_lookup(args.blueprint_id)?;
+    let (parent, diff) = diff_from_parent(sim, blueprint)?;
+    if diff.has_changes() {
+        bail!(
+            "assertion failed: blueprint {}: expected no changes from parent \
+             blueprint {}, found:\n{}",
+            blueprint.id,
+            parent.id,
+            diff.display()
+        );
+    }
+
+    Ok(Some(format!(
+        "ok: blueprint {}: no changes from parent blueprint {}",
+        blueprint.id, parent.id
+    )))
+}
+
+fn cmd_assert_diff(
+    sim: &mut ReconfiguratorSim,
+    args: AssertDiffArgs,
+) -> anyhow::Result<Option<String>> {
+    let blueprint = sim.blueprint_lookup(args.blueprint_id)?;
+    let (parent, diff) = diff_from_parent(sim, blueprint)?;
+
+    let zones_added =
+        diff.zones.added.values().map(|z| z.zones.len()).sum::<usize>();
+    let zones_removed =
+        diff.zones.removed.values().map(|z| z.zones.len()).sum::<usize>();
+    let zones_modified =
+        diff.zones.modified.values().map(|z| z.zones.len()).sum::<usize>();
+    let checks = [
+        ("sleds added", args.sleds_added, diff.sleds_added.len()),
+        ("sleds removed", args.sleds_removed, diff.sleds_removed.len()),
+        ("sleds modified", args.sleds_modified, diff.sleds_modified.len()),
+        ("zones added", args.zones_added, zones_added),
+        ("zones removed", args.zones_removed, zones_removed),
+        ("zones modified", args.zones_modified, zones_modified),
+    ];
+
+    let mismatches: Vec<_> = checks
+        .into_iter()
+        .filter_map(|(what, expected, found)| {
+            let expected = expected?;
+            (expected != found).then(|| {
+                format!("{}: expected {}, found {}", what, expected, found)
+            })
+        })
+        .collect();
+    if !mismatches.is_empty() {
+        bail!(
+            "assertion failed: blueprint {}: unexpected changes from parent \
+             blueprint {} ({}):\n{}",
+            blueprint.id,
+            parent.id,
+            mismatches.join("; "),
+            diff.display()
+        );
+    }
+
+    Ok(Some(format!(
+        "ok: blueprint {}: changes from parent blueprint {} match",
+        blueprint.id, parent.id
+    )))
+}
+
+fn cmd_assert_converged(
+    sim: &mut ReconfiguratorSim,
+    args: AssertConvergedArgs,
+) -> anyhow::Result<Option<String>> {
+    let collection = sim.collection_lookup(args.collection_id)?;
+    let blueprint = sim.blueprint_lookup(args.blueprint_id)?;
+    for sled_id in &args.except {
+        ensure!(
+            blueprint.blueprint_zones.contains_key(sled_id),
+            "sled {} is not in blueprint {}",
+            sled_id,
+            blueprint.id
+        );
+    }
+
+    let mut mismatches = Vec::new();
+    for (sled_id, zones) in &blueprint.blueprint_zones {
+        // Sleds with nothing left to run are not expected to report anything.
+        if zones.are_all_zones_expunged() {
+            continue;
+        }
+
+        let expected =
+            zones.to_omicron_zones_config(BlueprintZoneFilter::ShouldBeRunning);
+        let found = collection.omicron_zones.get(sled_id);
+        let converged = found.is_some_and(|found| found.zones == expected);
+        match (converged, args.except.contains(sled_id)) {
+            (true, false) | (false, true) => (),
+            (true, true) => mismatches.push(format!(
+                "sled {}: converged, but expected it not to",
+                sled_id
+            )),
+            (false, false) => mismatches.push(format!(
+                "sled {}: expected zones generation {}, found {}",
+                sled_id,
+                expected.generation,
+                match found {
+                    Some(found) => {
+                        format!("generation {}", found.zones.generation)
+                    }
+                    None => String::from("no zones"),
+                }
+            )),
+        }
+    }
+
+    if !mismatches.is_empty() {
+        bail!(
+            "assertion failed: collection {}: not converged to blueprint {}: \
+             {}",
+            collection.id,
+            blueprint.id,
+            mismatches.join(", ")
+        );
+    }
+
+    let mut s = format!(
+        "ok: collection {}: converged to blueprint {}",
+        collection.id, blueprint.id
+    );
+    if !args.except.is_empty() {
+        swrite!(s, " (except {} sleds)", args.except.len());
+    }
+    Ok(Some(s))
+}
@@ -46,6 +46,35 @@ fn test_basic() {
     assert_contents("tests/output/cmd-stderr", &stderr_text);
 }
 
+// Run each scenario in tests/scenarios.  Scenarios check their own assertions,
+// so each one must succeed.  We also compare each scenario's output against
+// the corresponding file in tests/output/scenarios to catch changes in planner
+// behavior that the assertions don't cover.
+#[test]
+fn test_scenarios() {
+    let scenario_dir = Utf8Path::new("tests/scenarios");
+    let mut scenarios: Vec<_> = scenario_dir
+        .read_dir_utf8()
+        .with_context(|| format!("read {:?}", scenario_dir))
+        .unwrap()
+        .map(|entry| entry.expect("reading directory entry").into_path())
+        .filter(|path| path.extension() == Some("txt"))
+        .collect();
+    scenarios.sort();
+    assert!(!scenarios.is_empty(), "no scenarios found in {:?}", scenario_dir);
+
+    for scenario in scenarios {
+        println!("running scenario {:?}", scenario);
+        let exec = Exec::cmd(path_to_cli()).arg("run").arg(&scenario);
+        let (exit_status, stdout_text, stderr_text) = run_command(exec);
+        assert_exit_code(exit_status, EXIT_SUCCESS, &stderr_text);
+        let stdout_text = redact_variable(&stdout_text);
+        let output_path = Utf8Path::new("tests/output/scenarios")
+            .join(format!("{}-stdout", scenario.file_stem().unwrap()));
+        assert_contents(&output_path, &stdout_text);
+    }
+}
+
 type ControlPlaneTestContext =
     nexus_test_utils::ControlPlaneTestContext<omicron_nexus::Server>;
 
@@ -27,6 +27,7 @@ use slog::Level;
 use slog::Logger;
 use std::fs;
 use std::io;
+use std::io::Write;
 use std::net::SocketAddrV6;
 use std::path::PathBuf;
 use std::time::Duration;
@@ -231,6 +232,13 @@ enum Command {
         sp: SpIdentifier,
     },
 
+    /// Dump the host serial console output MGS has recorded for this SP.
+    UsartHistory {
+        /// Target SP (e.g., 'sled/7', 'switch/1', 'power/0')
+        #[clap(value_parser = sp_identifier_from_str, action)]
+        sp: SpIdentifier,
+    },
+
     /// Upload a host phase 2 recovery image to be served on request
     UploadRecoveryHostPhase2 { path: PathBuf },
 
@@ -552,6 +560,19 @@ async fn main() -> Result<()> {
                 )
                 .await?;
         }
+        Command::UsartHistory { sp } => {
+            let history = client
+                .sp_component_serial_console_history(
+                    sp.type_,
+                    sp.slot,
+                    SERIAL_CONSOLE_COMPONENT,
+                )
+                .await?
+                .into_inner();
+            let mut stdout = io::stdout().lock();
+            stdout.write_all(&history.data)?;
+            stdout.flush()?;
+        }
         Command::UploadRecoveryHostPhase2 { path } => {
             let image_stream =
                 tokio::fs::File::open(&path).await.with_context(|| {
@@ -58,6 +58,29 @@ pub fn load_test_config() -> (omicron_gateway::Config, sp_sim::Config) {
     (server_config, sp_sim_config)
 }
 
+/// Like [`load_test_config()`], but loads MGS and/or SP simulator
+/// configuration from the given files in place of the test suite's
+///
+/// The test suite's MGS logging configuration is kept, since
+/// [`test_setup_with_config()`] requires it.
+pub fn load_config_with_overrides(
+    server_config_file_path: Option<&Utf8Path>,
+    sp_sim_config_file_path: Option<&Utf8Path>,
+) -> (omicron_gateway::Config, sp_sim::Config) {
+    let (mut server_config, mut sp_sim_config) = load_test_config();
+    if let Some(path) = server_config_file_path {
+        let log = server_config.log;
+        server_config = omicron_gateway::Config::from_file(path)
+            .unwrap_or_else(|e| panic!("failed to load {path}: {e}"));
+        server_config.log = log;
+    }
+    if let Some(path) = sp_sim_config_file_path {
+        sp_sim_config = sp_sim::Config::from_file(path)
+            .unwrap_or_else(|e| panic!("failed to load {path}: {e}"));
+    }
+    (server_config, sp_sim_config)
+}
+
 pub async fn test_setup(
     test_name: &str,
     sp_port: SpPort,
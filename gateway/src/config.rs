@@ -25,6 +25,9 @@ pub struct Config {
     pub switch: SwitchConfig,
     /// Server-wide logging configuration.
     pub log: ConfigLogging,
+    /// Recording of SP host serial console output.
+    #[serde(default)]
+    pub serial_console_history: SerialConsoleHistoryConfig,
 }
 
 impl Config {
@@ -45,6 +48,37 @@ pub struct PartialDropshotConfig {
     pub request_body_max_bytes: usize,
 }
 
+/// Configuration for recording SP host serial console output
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+#[serde(deny_unknown_fields)]
+pub struct SerialConsoleHistoryConfig {
+    /// Number of bytes of the most recent output to keep for each SP.
+    #[serde(default = "default_serial_console_history_buffer_size")]
+    pub buffer_size: usize,
+    /// If true, keep each sled's host serial console attached (and recorded)
+    /// while no client is attached to it.
+    #[serde(default)]
+    pub record_while_detached: bool,
+    /// Directory in which to save recorded output so that it survives MGS
+    /// restarts. If unset, recorded output is only kept in memory.
+    #[serde(default)]
+    pub persist_dir: Option<Utf8PathBuf>,
+}
+
+fn default_serial_console_history_buffer_size() -> usize {
+    64 * 1024
+}
+
+impl Default for SerialConsoleHistoryConfig {
+    fn default() -> Self {
+        Self {
+            buffer_size: default_serial_console_history_buffer_size(),
+            record_while_detached: false,
+            persist_dir: None,
+        }
+    }
+}
+
 #[derive(Debug, Error, SlogInlineError)]
 pub enum LoadError {
     #[error("error reading \"{path}\"")]
@@ -2,9 +2,11 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use crate::config::SerialConsoleHistoryConfig;
 use crate::error::StartupError;
 use crate::management_switch::ManagementSwitch;
 use crate::management_switch::SwitchConfig;
+use crate::serial_console_history::SerialConsoleHistory;
 use gateway_sp_comms::InMemoryHostPhase2Provider;
 use slog::{info, Logger};
 use std::sync::Arc;
@@ -16,6 +18,7 @@ pub struct ServerContext {
     pub mgmt_switch: ManagementSwitch,
     pub host_phase2_provider: Arc<InMemoryHostPhase2Provider>,
     pub rack_id: OnceLock<Uuid>,
+    pub serial_console_history: SerialConsoleHistory,
     pub log: Logger,
 }
 
@@ -23,6 +26,7 @@ impl ServerContext {
     pub async fn new(
         host_phase2_provider: Arc<InMemoryHostPhase2Provider>,
         switch_config: SwitchConfig,
+        serial_console_history_config: SerialConsoleHistoryConfig,
         rack_id_config: Option<Uuid>,
         log: &Logger,
     ) -> Result<Arc<Self>, StartupError> {
@@ -41,6 +45,10 @@ impl ServerContext {
             mgmt_switch,
             host_phase2_provider,
             rack_id,
+            serial_console_history: SerialConsoleHistory::new(
+                serial_console_history_config,
+                log,
+            ),
             log: log.clone(),
         }))
     }
@@ -532,6 +532,17 @@ pub struct SpComponentCaboose {
     pub version: String,
 }
 
+/// Recent output recorded from an SP's host serial console
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct SerialConsoleHistory {
+    /// Offset of the first byte of `data` in everything recorded from this
+    /// console since MGS started (or, if MGS persists this history, since it
+    /// was first recorded).
+    pub start_offset: u64,
+    /// The recorded output, oldest first.
+    pub data: Vec<u8>,
+}
+
 /// Identity of a host phase2 recovery image.
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
 pub struct HostPhase2RecoveryImageId {
@@ -948,27 +959,64 @@ async fn sp_component_serial_console_attach(
     let PathSpComponent { sp, component } = path.into_inner();
     let sp_id = sp.into();
     let component = component_from_str(&component)?;
+    let sp = apictx.mgmt_switch.sp(sp_id)?;
+
+    // If MGS is recording this console on its own, take it over; we hold
+    // onto this until the websocket connection ends.
+    let recording_guard =
+        apictx.serial_console_history.client_attaching(sp_id).await;
 
     // Ensure we can attach to this SP's serial console.
-    let console = apictx
-        .mgmt_switch
-        .sp(sp_id)?
-        .serial_console_attach(component)
-        .await
-        .map_err(|err| SpCommsError::SpCommunicationFailed {
-            sp: sp_id,
-            err,
-        })?;
+    let console = sp.serial_console_attach(component).await.map_err(|err| {
+        SpCommsError::SpCommunicationFailed { sp: sp_id, err }
+    })?;
 
-    let log = apictx.log.new(slog::o!("sp" => format!("{sp:?}")));
+    let log = apictx.log.new(slog::o!("sp" => format!("{sp_id:?}")));
+    let apictx = Arc::clone(apictx);
 
     // We've successfully attached to the SP's serial console: upgrade the
     // websocket and run our side of that connection.
-    websocket.handle(move |conn| {
-        crate::serial_console::run(sp_id, console, conn, log)
+    websocket.handle(move |conn| async move {
+        let result =
+            crate::serial_console::run(apictx, sp_id, console, conn, log).await;
+        drop(recording_guard);
+        result
     })
 }
 
+/// Get the most recent output recorded from the given SP's host serial
+/// console.
+///
+/// MGS records everything it receives from the console, whether on behalf of
+/// an attached client or (if so configured) while no client is attached. This
+/// endpoint is only valid for the `sp3-host-cpu` component.
+#[endpoint {
+    method = GET,
+    path = "/sp/{type}/{slot}/component/{component}/serial-console/history",
+}]
+async fn sp_component_serial_console_history(
+    rqctx: RequestContext<Arc<ServerContext>>,
+    path: Path<PathSpComponent>,
+) -> Result<HttpResponseOk<SerialConsoleHistory>, HttpError> {
+    let apictx = rqctx.context();
+    let PathSpComponent { sp, component } = path.into_inner();
+    let sp_id = sp.into();
+
+    if component_from_str(&component)? != SpComponent::SP3_HOST_CPU {
+        return Err(http_err_with_message(
+            http::StatusCode::BAD_REQUEST,
+            "RequestUnsupportedForComponent",
+            "only the host CPU serial console is recorded".to_string(),
+        ));
+    }
+
+    // Ensure the SP exists.
+    let _ = apictx.mgmt_switch.sp(sp_id)?;
+
+    let (start_offset, data) = apictx.serial_console_history.get(sp_id);
+    Ok(HttpResponseOk(SerialConsoleHistory { start_offset, data }))
+}
+
 /// Detach the websocket connection attached to the given SP component's serial
 /// console, if such a connection exists.
 #[endpoint {
@@ -1595,6 +1643,7 @@ pub fn api() -> GatewayApiDescription {
         api.register(sp_component_active_slot_set)?;
         api.register(sp_component_serial_console_attach)?;
         api.register(sp_component_serial_console_detach)?;
+        api.register(sp_component_serial_console_history)?;
         api.register(sp_component_update)?;
         api.register(sp_component_update_status)?;
         api.register(sp_component_update_abort)?;
@@ -7,10 +7,12 @@ mod context;
 mod error;
 mod management_switch;
 mod serial_console;
+mod serial_console_history;
 
 pub mod http_entrypoints; // TODO pub only for testing - is this right?
 
 pub use config::Config;
+pub use config::SerialConsoleHistoryConfig;
 pub use context::ServerContext;
 pub use error::*;
 
@@ -153,11 +155,13 @@ impl Server {
         let apictx = ServerContext::new(
             host_phase2_provider,
             config.switch,
+            config.serial_console_history,
             args.rack_id,
             &log,
         )
         .await
         .map_err(|error| format!("initializing server context: {}", error))?;
+        serial_console_history::start(&apictx);
 
         let mut http_servers = HashMap::with_capacity(args.addresses.len());
         let all_servers_shutdown = FuturesUnordered::new();
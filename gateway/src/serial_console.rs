@@ -5,6 +5,7 @@
 // Copyright 2022 Oxide Computer Company
 
 use crate::error::SpCommsError;
+use crate::ServerContext;
 use crate::SpIdentifier;
 use dropshot::WebsocketChannelResult;
 use dropshot::WebsocketConnection;
@@ -24,6 +25,7 @@ use slog_error_chain::SlogInlineError;
 use std::borrow::Cow;
 use std::ops::Deref;
 use std::ops::DerefMut;
+use std::sync::Arc;
 use tokio::select;
 use tokio::sync::mpsc;
 use tokio::sync::mpsc::error::TrySendError;
@@ -45,6 +47,7 @@ enum SerialTaskError {
 }
 
 pub(crate) async fn run(
+    apictx: Arc<ServerContext>,
     sp: SpIdentifier,
     console: AttachedSerialConsole,
     conn: WebsocketConnection,
@@ -111,6 +114,7 @@ pub(crate) async fn run(
                             log, "received serial console data from SP";
                             "length" => data.len(),
                         );
+                        apictx.serial_console_history.record(sp, &data);
                         match ws_sink_tx.try_send(Message::Binary(data)) {
                             Ok(()) => (),
                             Err(TrySendError::Full(data)) => {
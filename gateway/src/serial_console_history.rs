@@ -0,0 +1,554 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Bounded history of recent host serial console output for each SP
+//!
+//! Everything MGS receives from an SP's host serial console is appended to a
+//! ring buffer for that SP, whether it arrived on behalf of a websocket client
+//! or while MGS was recording on its own.  If `record_while_detached` is
+//! configured, MGS keeps each sled's console attached whenever no client is,
+//! so that output like a panic during boot isn't lost.  A client attaching
+//! preempts this recorder; the recorder reattaches once the client is done.
+
+use crate::config::SerialConsoleHistoryConfig;
+use crate::error::SpCommsError;
+use crate::management_switch::SpIdentifier;
+use crate::management_switch::SpType;
+use crate::ServerContext;
+use camino::Utf8Path;
+use camino::Utf8PathBuf;
+use gateway_messages::SpComponent;
+use gateway_messages::SERIAL_CONSOLE_IDLE_TIMEOUT;
+use gateway_sp_comms::AttachedSerialConsoleSend;
+use slog::debug;
+use slog::info;
+use slog::o;
+use slog::warn;
+use slog::Logger;
+use slog_error_chain::InlineErrorChain;
+use std::collections::HashMap;
+use std::collections::VecDeque;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::sync::Notify;
+use tokio::sync::OwnedMutexGuard;
+use tokio::time;
+use tokio::time::MissedTickBehavior;
+
+/// How often we write recorded output to disk, if persistence is enabled
+const PERSIST_INTERVAL: Duration = Duration::from_secs(5);
+
+/// How long the recorder waits before retrying a failed attach
+const ATTACH_RETRY_INTERVAL: Duration = Duration::from_secs(10);
+
+/// How long we wait for discovery to complete before starting recorders
+const DISCOVERY_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// How long a client waits for the recorder to give up an SP's console
+const PREEMPT_TIMEOUT: Duration = Duration::from_secs(5);
+
+pub struct SerialConsoleHistory {
+    config: SerialConsoleHistoryConfig,
+    buffers: Mutex<HashMap<SpIdentifier, RingBuffer>>,
+    owners: Mutex<HashMap<SpIdentifier, Arc<ConsoleOwner>>>,
+    log: Logger,
+}
+
+impl SerialConsoleHistory {
+    pub(crate) fn new(
+        config: SerialConsoleHistoryConfig,
+        log: &Logger,
+    ) -> Self {
+        Self {
+            config,
+            buffers: Mutex::new(HashMap::new()),
+            owners: Mutex::new(HashMap::new()),
+            log: log.new(o!("component" => "SerialConsoleHistory")),
+        }
+    }
+
+    /// Append output received from `sp`'s host serial console.
+    pub(crate) fn record(&self, sp: SpIdentifier, data: &[u8]) {
+        let mut buffers = self.buffers.lock().unwrap();
+        buffers.entry(sp).or_default().push(data, self.config.buffer_size);
+    }
+
+    /// Returns the recorded output for `sp`, along with the offset (in bytes
+    /// since recording began) of its first byte.
+    pub(crate) fn get(&self, sp: SpIdentifier) -> (u64, Vec<u8>) {
+        let buffers = self.buffers.lock().unwrap();
+        buffers
+            .get(&sp)
+            .map(|buffer| {
+                (buffer.start_offset, buffer.data.iter().copied().collect())
+            })
+            .unwrap_or_default()
+    }
+
+    /// Loads the output persisted in `dir` by a previous MGS.
+    ///
+    /// Anything recorded since this MGS started is kept, after the persisted
+    /// output.
+    async fn load_persisted(&self, dir: &Utf8Path) {
+        let mut entries = match tokio::fs::read_dir(dir).await {
+            Ok(entries) => entries,
+            Err(err) => {
+                warn!(
+                    self.log,
+                    "failed to read serial console history directory";
+                    "path" => %dir,
+                    InlineErrorChain::new(&err),
+                );
+                return;
+            }
+        };
+
+        loop {
+            let path = match entries.next_entry().await {
+                Ok(Some(entry)) => entry.path(),
+                Ok(None) => break,
+                Err(err) => {
+                    warn!(
+                        self.log,
+                        "failed to read serial console history directory";
+                        "path" => %dir,
+                        InlineErrorChain::new(&err),
+                    );
+                    break;
+                }
+            };
+            let Ok(path) = Utf8PathBuf::try_from(path) else {
+                continue;
+            };
+            let Some(sp) = parse_persist_path(&path) else {
+                continue;
+            };
+
+            let contents = match tokio::fs::read(&path).await {
+                Ok(contents) => contents,
+                Err(err) => {
+                    warn!(
+                        self.log,
+                        "failed to read serial console history";
+                        "path" => %path,
+                        InlineErrorChain::new(&err),
+                    );
+                    continue;
+                }
+            };
+            let Some(mut buffer) =
+                RingBuffer::from_persisted(&contents, self.config.buffer_size)
+            else {
+                warn!(
+                    self.log,
+                    "ignoring malformed serial console history";
+                    "path" => %path,
+                );
+                continue;
+            };
+
+            let mut buffers = self.buffers.lock().unwrap();
+            if let Some(recorded) = buffers.remove(&sp) {
+                let (front, back) = recorded.data.as_slices();
+                buffer.push(front, self.config.buffer_size);
+                buffer.push(back, self.config.buffer_size);
+            }
+            buffers.insert(sp, buffer);
+        }
+    }
+
+    fn owner(&self, sp: SpIdentifier) -> Arc<ConsoleOwner> {
+        let mut owners = self.owners.lock().unwrap();
+        Arc::clone(owners.entry(sp).or_default())
+    }
+
+    /// Called before a client attaches to `sp`'s host serial console.
+    ///
+    /// If we're recording while detached, this preempts the recorder and
+    /// returns a guard that keeps it from reattaching until the guard is
+    /// dropped (i.e., until the client's session ends).  Returns `None` if
+    /// there's nothing to preempt, including when another client already has
+    /// the console; in that case the attach proceeds and the SP rejects it as
+    /// it would without recording.
+    pub(crate) async fn client_attaching(
+        &self,
+        sp: SpIdentifier,
+    ) -> Option<ClientConsoleGuard> {
+        if !self.config.record_while_detached {
+            return None;
+        }
+
+        let owner = self.owner(sp);
+        if owner
+            .client_attached
+            .compare_exchange(false, true, Ordering::SeqCst, Ordering::SeqCst)
+            .is_err()
+        {
+            return None;
+        }
+
+        owner.preempt.notify_one();
+        match time::timeout(
+            PREEMPT_TIMEOUT,
+            Arc::clone(&owner.lock).lock_owned(),
+        )
+        .await
+        {
+            Ok(lock) => Some(ClientConsoleGuard { owner, _lock: lock }),
+            Err(_) => {
+                warn!(
+                    self.log,
+                    "timed out waiting for serial console recorder to detach";
+                    "sp" => ?sp,
+                );
+                owner.client_attached.store(false, Ordering::SeqCst);
+                None
+            }
+        }
+    }
+}
+
+/// Start any background tasks needed for the configured history.
+pub(crate) fn start(apictx: &Arc<ServerContext>) {
+    let config = &apictx.serial_console_history.config;
+    if let Some(dir) = &config.persist_dir {
+        tokio::spawn(persist_task(Arc::clone(apictx), dir.clone()));
+    }
+    if config.record_while_detached {
+        tokio::spawn(start_recorders(Arc::clone(apictx)));
+    }
+}
+
+/// Keeps a client's claim on an SP's console; see
+/// [`SerialConsoleHistory::client_attaching()`].
+pub(crate) struct ClientConsoleGuard {
+    owner: Arc<ConsoleOwner>,
+    _lock: OwnedMutexGuard<()>,
+}
+
+impl Drop for ClientConsoleGuard {
+    fn drop(&mut self) {
+        self.owner.client_attached.store(false, Ordering::SeqCst);
+    }
+}
+
+/// Coordinates attachment to a single SP's console between clients and the
+/// recorder
+///
+/// Whoever holds `lock` owns the attachment.
+#[derive(Debug, Default)]
+struct ConsoleOwner {
+    lock: Arc<tokio::sync::Mutex<()>>,
+    preempt: Notify,
+    client_attached: AtomicBool,
+}
+
+#[derive(Debug, Default, PartialEq, Eq)]
+struct RingBuffer {
+    data: VecDeque<u8>,
+    /// offset (in bytes since recording began) of the first byte of `data`
+    start_offset: u64,
+    /// whether `data` has changed since it was last persisted
+    dirty: bool,
+}
+
+impl RingBuffer {
+    fn push(&mut self, bytes: &[u8], capacity: usize) {
+        // Every byte that doesn't end up in `data` (whether it's old data
+        // we're evicting or the head of an oversized write) advances
+        // `start_offset`.
+        let skipped = bytes.len().saturating_sub(capacity);
+        let bytes = &bytes[skipped..];
+        let overflow = (self.data.len() + bytes.len()).saturating_sub(capacity);
+        self.data.drain(..overflow);
+        self.data.extend(bytes);
+        self.start_offset += (overflow + skipped) as u64;
+        self.dirty = true;
+    }
+
+    /// Serialize as the little-endian start offset followed by the data.
+    fn to_persisted(&self) -> Vec<u8> {
+        let mut out = Vec::with_capacity(8 + self.data.len());
+        out.extend_from_slice(&self.start_offset.to_le_bytes());
+        out.extend(&self.data);
+        out
+    }
+
+    fn from_persisted(contents: &[u8], capacity: usize) -> Option<Self> {
+        let (offset, data) = contents.split_first_chunk::<8>()?;
+        let mut buffer = RingBuffer {
+            data: VecDeque::new(),
+            start_offset: u64::from_le_bytes(*offset),
+            dirty: false,
+        };
+        // The configured capacity may have shrunk since this was written.
+        buffer.push(data, capacity);
+        buffer.dirty = false;
+        Some(buffer)
+    }
+}
+
+fn persist_path(dir: &Utf8Path, sp: SpIdentifier) -> Utf8PathBuf {
+    let typ = match sp.typ {
+        SpType::Sled => "sled",
+        SpType::Switch => "switch",
+        SpType::Power => "power",
+    };
+    dir.join(format!("{typ}-{}.console", sp.slot))
+}
+
+/// Parses the SP from a path returned by [`persist_path()`].
+fn parse_persist_path(path: &Utf8Path) -> Option<SpIdentifier> {
+    let name = path.file_name()?.strip_suffix(".console")?;
+    let (typ, slot) = name.split_once('-')?;
+    let typ = match typ {
+        "sled" => SpType::Sled,
+        "switch" => SpType::Switch,
+        "power" => SpType::Power,
+        _ => return None,
+    };
+    Some(SpIdentifier::new(typ, slot.parse().ok()?))
+}
+
+async fn persist_task(apictx: Arc<ServerContext>, dir: Utf8PathBuf) {
+    let history = &apictx.serial_console_history;
+    if let Err(err) = tokio::fs::create_dir_all(&dir).await {
+        warn!(
+            history.log,
+            "failed to create serial console history directory";
+            "path" => %dir,
+            InlineErrorChain::new(&err),
+        );
+    }
+    history.load_persisted(&dir).await;
+
+    let mut interval = time::interval(PERSIST_INTERVAL);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+
+        let dirty: Vec<_> = {
+            let mut buffers = history.buffers.lock().unwrap();
+            buffers
+                .iter_mut()
+                .filter(|(_, buffer)| buffer.dirty)
+                .map(|(sp, buffer)| {
+                    buffer.dirty = false;
+                    (*sp, buffer.to_persisted())
+                })
+                .collect()
+        };
+
+        for (sp, contents) in dirty {
+            let path = persist_path(&dir, sp);
+            let tmp_path = path.with_extension("console.tmp");
+            let result = async {
+                tokio::fs::write(&tmp_path, &contents).await?;
+                tokio::fs::rename(&tmp_path, &path).await
+            }
+            .await;
+            if let Err(err) = result {
+                warn!(
+                    history.log,
+                    "failed to persist serial console history";
+                    "path" => %path,
+                    InlineErrorChain::new(&err),
+                );
+            }
+        }
+    }
+}
+
+async fn start_recorders(apictx: Arc<ServerContext>) {
+    let log = &apictx.serial_console_history.log;
+    let sleds = loop {
+        match apictx.mgmt_switch.all_sps() {
+            Ok(sps) => {
+                break sps
+                    .map(|(id, _)| id)
+                    .filter(|id| id.typ == SpType::Sled)
+                    .collect::<Vec<_>>();
+            }
+            Err(SpCommsError::DiscoveryNotYetComplete) => {
+                time::sleep(DISCOVERY_POLL_INTERVAL).await;
+            }
+            Err(err) => {
+                warn!(
+                    log,
+                    "not recording serial consoles: cannot enumerate SPs";
+                    InlineErrorChain::new(&err),
+                );
+                return;
+            }
+        }
+    };
+
+    info!(log, "recording host serial consoles"; "nsleds" => sleds.len());
+    for sp in sleds {
+        tokio::spawn(record_while_detached(Arc::clone(&apictx), sp));
+    }
+}
+
+/// Keep `sp`'s host serial console attached and recorded whenever no client
+/// is attached to it.
+async fn record_while_detached(
+    apictx: Arc<ServerContext>,
+    sp_id: SpIdentifier,
+) {
+    let history = &apictx.serial_console_history;
+    let log = history.log.new(o!("sp" => format!("{sp_id:?}")));
+    let owner = history.owner(sp_id);
+
+    loop {
+        let lock = Arc::clone(&owner.lock).lock_owned().await;
+
+        let attach_result = match apictx.mgmt_switch.sp(sp_id) {
+            Ok(sp) => sp
+                .serial_console_attach(SpComponent::SP3_HOST_CPU)
+                .await
+                .map_err(|err| SpCommsError::SpCommunicationFailed {
+                    sp: sp_id,
+                    err,
+                }),
+            Err(err) => Err(err),
+        };
+        let console = match attach_result {
+            Ok(console) => console,
+            Err(err) => {
+                debug!(
+                    log,
+                    "failed to attach to serial console for recording";
+                    InlineErrorChain::new(&err),
+                );
+                drop(lock);
+                time::sleep(ATTACH_RETRY_INTERVAL).await;
+                continue;
+            }
+        };
+        debug!(log, "attached to serial console for recording");
+
+        let (mut console_tx, mut console_rx) = console.split();
+        let mut keepalive = time::interval(SERIAL_CONSOLE_IDLE_TIMEOUT / 4);
+        keepalive.set_missed_tick_behavior(MissedTickBehavior::Delay);
+        loop {
+            tokio::select! {
+                packet = console_rx.recv() => {
+                    match packet {
+                        Some(data) => history.record(sp_id, &data),
+                        None => {
+                            // Someone else detached us (e.g., via the detach
+                            // endpoint); try again.
+                            debug!(log, "recording serial console detached");
+                            break;
+                        }
+                    }
+                }
+
+                _ = keepalive.tick() => {
+                    if let Err(err) = console_tx.keepalive().await {
+                        warn!(
+                            log,
+                            "serial console keepalive failed";
+                            InlineErrorChain::new(&err),
+                        );
+                        detach(&console_tx, &log).await;
+                        break;
+                    }
+                }
+
+                _ = owner.preempt.notified() => {
+                    debug!(log, "client attaching; pausing recording");
+                    detach(&console_tx, &log).await;
+                    break;
+                }
+            }
+        }
+
+        // Releasing the lock lets a waiting client attach.  We'll queue up
+        // behind it at the top of the loop.
+        drop(lock);
+    }
+}
+
+async fn detach(console_tx: &AttachedSerialConsoleSend, log: &Logger) {
+    if let Err(err) = console_tx.detach().await {
+        // If the SP is gone, there's nothing left to detach from.
+        debug!(
+            log,
+            "failed to detach recording serial console";
+            InlineErrorChain::new(&err),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contents(buffer: &RingBuffer) -> Vec<u8> {
+        buffer.data.iter().copied().collect()
+    }
+
+    #[test]
+    fn test_ring_buffer() {
+        let mut buffer = RingBuffer::default();
+
+        buffer.push(b"hello", 8);
+        assert_eq!(contents(&buffer), b"hello");
+        assert_eq!(buffer.start_offset, 0);
+        assert!(buffer.dirty);
+
+        // Filling past capacity drops the oldest bytes.
+        buffer.push(b" world", 8);
+        assert_eq!(contents(&buffer), b"lo world");
+        assert_eq!(buffer.start_offset, 3);
+
+        // A single write larger than the capacity keeps only its tail.
+        buffer.push(b"0123456789", 8);
+        assert_eq!(contents(&buffer), b"23456789");
+        assert_eq!(buffer.start_offset, 13);
+    }
+
+    #[test]
+    fn test_ring_buffer_persisted() {
+        let mut buffer = RingBuffer::default();
+        buffer.push(b"panic: boot failed", 12);
+        let persisted = buffer.to_persisted();
+
+        let mut loaded = RingBuffer::from_persisted(&persisted, 12).unwrap();
+        assert!(!loaded.dirty);
+        loaded.dirty = true;
+        assert_eq!(loaded, buffer);
+
+        // Loading into a smaller buffer keeps the most recent output.
+        let loaded = RingBuffer::from_persisted(&persisted, 6).unwrap();
+        assert_eq!(contents(&loaded), b"failed");
+        assert_eq!(loaded.start_offset, 12);
+
+        assert_eq!(RingBuffer::from_persisted(b"short", 12), None);
+    }
+
+    #[test]
+    fn test_persist_path() {
+        let dir = Utf8Path::new("/history");
+        for sp in [
+            SpIdentifier::new(SpType::Sled, 14),
+            SpIdentifier::new(SpType::Switch, 0),
+            SpIdentifier::new(SpType::Power, 1),
+        ] {
+            assert_eq!(parse_persist_path(&persist_path(dir, sp)), Some(sp));
+        }
+        assert_eq!(
+            parse_persist_path(Utf8Path::new("/history/sled-1.tmp")),
+            None
+        );
+        assert_eq!(
+            parse_persist_path(Utf8Path::new("/history/rack-1.console")),
+            None
+        );
+    }
+}
@@ -0,0 +1,48 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use dropshot::test_util;
+use gateway_messages::SpPort;
+use gateway_test_utils::setup;
+use serde_json::json;
+
+#[tokio::test]
+async fn component_details_measurements() {
+    let testctx =
+        setup::test_setup("component_details_measurements", SpPort::One).await;
+    let client = &testctx.client;
+
+    // A sensor that reports a reading.
+    let url = format!("{}", client.url("/sp/switch/0/component/dev-0"));
+    let resp: serde_json::Value = test_util::object_get(client, &url).await;
+    assert_eq!(
+        resp,
+        json!([{
+            "type": "measurement",
+            "name": "Southwest",
+            "kind": { "kind": "temperature" },
+            "value": 41.5,
+        }])
+    );
+
+    // A sensor that reports an error.
+    let url = format!("{}", client.url("/sp/switch/0/component/dev-1"));
+    let resp: serde_json::Value = test_util::object_get(client, &url).await;
+    assert_eq!(
+        resp,
+        json!([{
+            "type": "measurement_error",
+            "name": "South",
+            "kind": { "kind": "temperature" },
+            "error": { "code": "device_error" },
+        }])
+    );
+
+    // A component with no sensors.
+    let url = format!("{}", client.url("/sp/sled/0/component/sp3-host-cpu"));
+    let resp: serde_json::Value = test_util::object_get(client, &url).await;
+    assert_eq!(resp, json!([]));
+
+    testctx.teardown().await;
+}
@@ -5,6 +5,7 @@
 // Copyright 2022 Oxide Computer Company
 
 mod commands;
+mod component_details;
 mod component_list;
 mod location_discovery;
 mod serial_console;
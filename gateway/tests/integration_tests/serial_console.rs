@@ -4,6 +4,7 @@
 
 // Copyright 2022 Oxide Computer Company
 
+use dropshot::test_util;
 use dropshot::Method;
 use futures::prelude::*;
 use gateway_messages::SpPort;
@@ -13,7 +14,12 @@ use gateway_test_utils::sim_sp_serial_console;
 use http::uri::Scheme;
 use http::StatusCode;
 use http::Uri;
+use omicron_gateway::http_entrypoints::SerialConsoleHistory;
 use omicron_gateway::http_entrypoints::SpType;
+use omicron_test_utils::dev::poll;
+use omicron_test_utils::dev::poll::CondCheckError;
+use std::convert::Infallible;
+use std::time::Duration;
 use tokio_tungstenite::tungstenite;
 use tokio_tungstenite::tungstenite::protocol::Message;
 
@@ -149,3 +155,81 @@ async fn serial_console_detach() {
 
     testctx.teardown().await;
 }
+
+#[tokio::test]
+async fn serial_console_history() {
+    let testctx =
+        setup::test_setup("serial_console_history", SpPort::One).await;
+    let client = &testctx.client;
+    let simrack = &testctx.simrack;
+
+    let history_url =
+        "/sp/sled/0/component/sp3-host-cpu/serial-console/history";
+    let contains = |history: &SerialConsoleHistory, needle: &[u8]| {
+        history.data.windows(needle.len()).any(|w| w == needle)
+    };
+
+    // connect to sled 0's serial console
+    let (console_write, mut console_read) =
+        sim_sp_serial_console(&simrack.gimlets[0]).await;
+
+    // With no websocket attached, MGS should still record output from the
+    // console. The recorder attaches asynchronously after discovery and the
+    // SP discards output while nothing is attached, so keep writing until it
+    // shows up.
+    let boot_msg = b"booting host OS\n";
+    poll::wait_for_condition::<(), Infallible, _, _>(
+        || async {
+            console_write.send(boot_msg.to_vec()).await.unwrap();
+            let history: SerialConsoleHistory =
+                test_util::object_get(client, history_url).await;
+            if contains(&history, boot_msg) {
+                Ok(())
+            } else {
+                Err(CondCheckError::NotYet)
+            }
+        },
+        &Duration::from_millis(100),
+        &Duration::from_secs(30),
+    )
+    .await
+    .unwrap();
+
+    // Attaching a client takes over from the recorder; output the client sees
+    // is recorded too.
+    let attach_url = {
+        let mut parts = client
+            .url("/sp/sled/0/component/sp3-host-cpu/serial-console/attach")
+            .into_parts();
+        parts.scheme = Some(Scheme::try_from("ws").unwrap());
+        Uri::from_parts(parts).unwrap()
+    };
+    let (mut ws, _resp) =
+        tokio_tungstenite::connect_async(attach_url).await.unwrap();
+    ws.send(Message::Binary(b"hello".to_vec())).await.unwrap();
+    assert_eq!(console_read.recv().await.unwrap(), b"hello");
+    console_write.send(b"login: ".to_vec()).await.unwrap();
+    assert_eq!(
+        ws.next().await.unwrap().unwrap(),
+        Message::Binary(b"login: ".to_vec())
+    );
+    let history: SerialConsoleHistory =
+        test_util::object_get(client, history_url).await;
+    assert!(contains(&history, boot_msg));
+    assert!(history.data.ends_with(b"login: "));
+
+    // Only the host CPU's console is recorded.
+    let url = client
+        .url("/sp/sled/0/component/sp/serial-console/history")
+        .to_string();
+    let err = client
+        .make_request_no_body(Method::GET, &url, StatusCode::BAD_REQUEST)
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err.error_code.as_deref(),
+        Some("RequestUnsupportedForComponent")
+    );
+
+    testctx.teardown().await;
+}
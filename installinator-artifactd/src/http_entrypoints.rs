@@ -4,12 +4,14 @@
 
 // Copyright 2022 Oxide Computer Company
 
+use std::ops::Range;
+
 use dropshot::{
-    endpoint, ApiDescription, FreeformBody, HttpError, HttpResponseHeaders,
-    HttpResponseOk, HttpResponseUpdatedNoContent, Path, RequestContext,
-    TypedBody,
+    endpoint, ApiDescription, ApiEndpointResponse, FreeformBody, HttpError,
+    HttpResponse, HttpResponseHeaders, HttpResponseOk,
+    HttpResponseUpdatedNoContent, Path, RequestContext, TypedBody,
 };
-use hyper::{header, Body, StatusCode};
+use hyper::{header, header::HeaderValue, Body, Response, StatusCode};
 use installinator_common::EventReport;
 use omicron_common::update::ArtifactHashId;
 use schemars::JsonSchema;
@@ -38,6 +40,12 @@ pub fn api() -> ArtifactServerApiDesc {
 }
 
 /// Fetch an artifact by hash.
+///
+/// A single byte range of the artifact may be requested with a `Range` header
+/// of the form `bytes=<first>-` or `bytes=<first>-<last>`, in which case only
+/// those bytes are returned (with status 206 Partial Content).  This allows
+/// interrupted downloads to be resumed, and large artifacts to be fetched in
+/// pieces from several servers.
 #[endpoint {
     method = GET,
     path = "/artifacts/by-hash/{kind}/{hash}",
@@ -45,14 +53,16 @@ pub fn api() -> ArtifactServerApiDesc {
 async fn get_artifact_by_hash(
     rqctx: RequestContext<ServerContext>,
     path: Path<ArtifactHashId>,
-) -> Result<HttpResponseHeaders<HttpResponseOk<FreeformBody>>, HttpError> {
+) -> Result<ArtifactResponse, HttpError> {
+    let range =
+        rqctx.request.headers().get(header::RANGE).and_then(parse_range_header);
     match rqctx
         .context()
         .artifact_store
-        .get_artifact_by_hash(&path.into_inner())
+        .get_artifact_by_hash(&path.into_inner(), range.clone())
         .await
     {
-        Some((size, body)) => Ok(body_to_artifact_response(size, body)),
+        Some((size, body)) => body_to_artifact_response(size, range, body),
         None => {
             Err(HttpError::for_not_found(None, "Artifact not found".into()))
         }
@@ -103,13 +113,86 @@ async fn report_progress(
     }
 }
 
+/// The response to a request for an artifact
+///
+/// This is a 200 OK with the whole artifact or a 206 Partial Content with the
+/// requested range of it.  Dropshot's typed responses have a single success
+/// status, so the response is built by hand, but it's described in the API
+/// spec as the typed 200 response it would otherwise be (which also documents
+/// the error responses).
+struct ArtifactResponse(Response<Body>);
+
+impl HttpResponse for ArtifactResponse {
+    fn to_result(self) -> Result<Response<Body>, HttpError> {
+        Ok(self.0)
+    }
+
+    fn response_metadata() -> ApiEndpointResponse {
+        HttpResponseHeaders::<HttpResponseOk<FreeformBody>>::response_metadata()
+    }
+}
+
 fn body_to_artifact_response(
     size: u64,
+    range: Option<Range<u64>>,
     body: Body,
-) -> HttpResponseHeaders<HttpResponseOk<FreeformBody>> {
-    let mut response =
-        HttpResponseHeaders::new_unnamed(HttpResponseOk(body.into()));
-    let headers = response.headers_mut();
-    headers.append(header::CONTENT_LENGTH, size.into());
-    response
+) -> Result<ArtifactResponse, HttpError> {
+    let builder = Response::builder().header(header::ACCEPT_RANGES, "bytes");
+    let response = match range {
+        // No range is satisfiable for an empty artifact, so ignore the range
+        // and return the (empty) artifact instead.
+        Some(range) if size > 0 => {
+            if range.start >= size {
+                return Err(HttpError::for_client_error(
+                    None,
+                    StatusCode::RANGE_NOT_SATISFIABLE,
+                    format!(
+                        "requested range starts at byte {}, but the artifact \
+                         is only {size} bytes long",
+                        range.start,
+                    ),
+                ));
+            }
+            let end = range.end.min(size);
+            builder
+                .status(StatusCode::PARTIAL_CONTENT)
+                .header(
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{size}", range.start, end - 1),
+                )
+                .header(header::CONTENT_LENGTH, end - range.start)
+                .body(body)
+        }
+        _ => builder
+            .status(StatusCode::OK)
+            .header(header::CONTENT_LENGTH, size)
+            .body(body),
+    };
+    response.map(ArtifactResponse).map_err(|error| {
+        HttpError::for_internal_error(format!(
+            "failed to build artifact response: {error}"
+        ))
+    })
+}
+
+/// Parses the value of a `Range` header into the range of bytes it requests.
+///
+/// Only a single range of the form `bytes=<first>-` or `bytes=<first>-<last>`
+/// is supported.  Anything else results in `None`, in which case the header is
+/// ignored and the whole artifact is returned, as RFC 9110 allows.
+fn parse_range_header(value: &HeaderValue) -> Option<Range<u64>> {
+    let spec = value.to_str().ok()?.trim().strip_prefix("bytes=")?;
+    let (first, last) = spec.split_once('-')?;
+    let first = first.trim().parse::<u64>().ok()?;
+    let end = match last.trim() {
+        "" => u64::MAX,
+        last => {
+            let last = last.parse::<u64>().ok()?;
+            if last < first {
+                return None;
+            }
+            last.saturating_add(1)
+        }
+    };
+    Some(first..end)
 }
@@ -5,6 +5,7 @@
 // Copyright 2023 Oxide Computer Company
 
 use std::fmt;
+use std::ops::Range;
 
 use async_trait::async_trait;
 use dropshot::HttpError;
@@ -17,8 +18,17 @@ use uuid::Uuid;
 /// Represents a way to fetch artifacts.
 #[async_trait]
 pub trait ArtifactGetter: fmt::Debug + Send + Sync + 'static {
-    /// Gets an artifact by hash, returning it as a [`Body`].
-    async fn get_by_hash(&self, id: &ArtifactHashId) -> Option<(u64, Body)>;
+    /// Gets an artifact by hash, returning its total size along with a
+    /// [`Body`].
+    ///
+    /// If `range` is specified, the body only contains the bytes of the
+    /// artifact within that range; any part of `range` past the end of the
+    /// artifact is ignored.  Otherwise, the body contains the whole artifact.
+    async fn get_by_hash(
+        &self,
+        id: &ArtifactHashId,
+        range: Option<Range<u64>>,
+    ) -> Option<(u64, Body)>;
 
     /// Reports update progress events from the installinator.
     async fn report_progress(
@@ -63,9 +73,15 @@ impl ArtifactStore {
     pub(crate) async fn get_artifact_by_hash(
         &self,
         id: &ArtifactHashId,
+        range: Option<Range<u64>>,
     ) -> Option<(u64, Body)> {
-        slog::debug!(self.log, "Artifact requested by hash: {:?}", id);
-        self.getter.get_by_hash(id).await
+        slog::debug!(
+            self.log,
+            "Artifact requested by hash: {:?} (range: {:?})",
+            id,
+            range,
+        );
+        self.getter.get_by_hash(id, range).await
     }
 
     pub(crate) async fn report_progress(
@@ -2,7 +2,7 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use std::net::SocketAddr;
+use std::{net::SocketAddr, ops::Range};
 
 use anyhow::{Context, Result};
 use clap::Args;
@@ -11,6 +11,7 @@ use installinator_artifact_client::ClientError;
 use installinator_common::EventReport;
 use ipcc::{InstallinatorImageId, Ipcc};
 use omicron_common::update::{ArtifactHash, ArtifactHashId};
+use reqwest::{header::HeaderMap, StatusCode};
 use tokio::sync::mpsc;
 use uuid::Uuid;
 
@@ -86,40 +87,77 @@ impl ArtifactClient {
         Self { log, client }
     }
 
+    /// Fetches the bytes of an artifact within `range`, returning the total
+    /// size of the artifact along with a receiver for those bytes.
+    ///
+    /// The bytes sent over the receiver always start at `range.start`, but
+    /// there may be fewer of them than requested (if `range` extends past the
+    /// end of the artifact) or more (if the server ignored the range and sent
+    /// the whole artifact, which is only accepted if `range.start` is 0).
     pub(crate) async fn fetch(
         &self,
         artifact_hash_id: ArtifactHashId,
+        range: Range<u64>,
     ) -> Result<(u64, FetchReceiver), HttpError> {
-        let artifact_bytes = self
+        assert!(!range.is_empty(), "requested range {range:?} is empty");
+
+        // The generated client doesn't know about ranges, so make this
+        // request by hand.
+        let url = format!(
+            "{}/artifacts/by-hash/{}/{}",
+            self.client.baseurl(),
+            artifact_hash_id.kind.as_str(),
+            artifact_hash_id.hash,
+        );
+        let response = self
             .client
-            .get_artifact_by_hash(
-                artifact_hash_id.kind.as_str(),
-                &artifact_hash_id.hash.to_string(),
+            .client()
+            .get(url)
+            .header(
+                reqwest::header::RANGE,
+                format!("bytes={}-{}", range.start, range.end - 1),
             )
-            .await?;
+            .send()
+            .await
+            .map_err(ClientError::from)?;
+
+        let artifact_size = match response.status() {
+            StatusCode::PARTIAL_CONTENT => {
+                let (start, artifact_size) =
+                    parse_content_range(response.headers())?;
+                if start != range.start {
+                    return Err(HttpError::RangeMismatch {
+                        requested: range.start,
+                        returned: start,
+                    });
+                }
+                artifact_size
+            }
+            StatusCode::OK => {
+                if range.start != 0 {
+                    return Err(HttpError::RangeMismatch {
+                        requested: range.start,
+                        returned: 0,
+                    });
+                }
+                // We expect servers to set a Content-Length header.
+                parse_content_length(response.headers())?
+            }
+            _ => return Err(ClientError::UnexpectedResponse(response).into()),
+        };
 
         slog::debug!(
             &self.log,
             "preparing to receive {:?} bytes from artifact",
-            artifact_bytes.content_length(),
+            response.content_length();
+            "range" => ?range,
+            "artifact_size" => artifact_size,
         );
 
-        // We expect servers to set a Content-Length header.
-        let content_length =
-            match artifact_bytes.headers().get(http::header::CONTENT_LENGTH) {
-                Some(v) => {
-                    let s = v
-                        .to_str()
-                        .map_err(|_| HttpError::InvalidContentLength)?;
-                    s.parse().map_err(|_| HttpError::InvalidContentLength)?
-                }
-                None => return Err(HttpError::MissingContentLength),
-            };
-
         let (fetch_sender, fetch_receiver) = mpsc::channel(8);
 
         tokio::spawn(async move {
-            let mut bytes = artifact_bytes.into_inner_stream();
+            let mut bytes = response.bytes_stream();
             while let Some(item) = bytes.next().await {
                 if let Err(_) =
                     fetch_sender.send(item.map_err(Into::into)).await
@@ -130,7 +168,7 @@ impl ArtifactClient {
             }
         });
 
-        Ok((content_length, fetch_receiver))
+        Ok((artifact_size, fetch_receiver))
     }
 
     pub(crate) async fn report_progress(
@@ -144,3 +182,26 @@ impl ArtifactClient {
             .map(|resp| resp.into_inner())
     }
 }
+
+fn parse_content_length(headers: &HeaderMap) -> Result<u64, HttpError> {
+    let value = headers
+        .get(reqwest::header::CONTENT_LENGTH)
+        .ok_or(HttpError::MissingContentLength)?;
+    let s = value.to_str().map_err(|_| HttpError::InvalidContentLength)?;
+    s.parse().map_err(|_| HttpError::InvalidContentLength)
+}
+
+/// Parses a `Content-Range` header of the form `bytes <first>-<last>/<size>`,
+/// returning the first byte and the total size of the artifact.
+fn parse_content_range(headers: &HeaderMap) -> Result<(u64, u64), HttpError> {
+    let value = headers
+        .get(reqwest::header::CONTENT_RANGE)
+        .ok_or(HttpError::MissingContentRange)?;
+    let parse = || {
+        let s = value.to_str().ok()?;
+        let (range, size) = s.strip_prefix("bytes ")?.split_once('/')?;
+        let (first, _last) = range.split_once('-')?;
+        Some((first.parse().ok()?, size.parse().ok()?))
+    };
+    parse().ok_or(HttpError::InvalidContentRange)
+}
@@ -4,8 +4,8 @@
 
 use std::time::Duration;
 
-use anyhow::{bail, Context, Result};
-use buf_list::{BufList, Cursor};
+use anyhow::{Context, Result};
+use buf_list::Cursor;
 use camino::{Utf8Path, Utf8PathBuf};
 use clap::{Args, Parser, Subcommand};
 use installinator_common::{
@@ -16,9 +16,8 @@ use installinator_common::{
 use omicron_common::FileKv;
 use omicron_common::{
     api::internal::nexus::KnownArtifactKind,
-    update::{ArtifactHash, ArtifactHashId, ArtifactKind},
+    update::{ArtifactHashId, ArtifactKind},
 };
-use sha2::{Digest, Sha256};
 use slog::{error, warn, Drain};
 use tufaceous_lib::ControlPlaneZoneImages;
 use update_engine::StepResult;
@@ -218,23 +217,6 @@ impl InstallOpts {
                         fetch_artifact(&cx, &host_phase_2_id, discovery, log)
                             .await?;
 
-                    // Check that the sha256 of the data we got from wicket
-                    // matches the data we asked for. If this fails, we fail the
-                    // entire installation rather than trying to fetch the
-                    // artifact again, because we're fetching data from wicketd
-                    // (in memory) over TCP to ourselves (in memory), so the
-                    // only cases where this could fail are disturbing enough
-                    // (memory corruption, corruption under TCP, or wicketd gave
-                    // us something other than what we requested) we want to
-                    // know immediately and not retry: it's likely an operator
-                    // could miss any warnings we emit if a retry succeeds.
-                    check_downloaded_artifact_hash(
-                        "host phase 2",
-                        host_phase_2_artifact.artifact.clone(),
-                        host_phase_2_id.hash,
-                    )
-                    .await?;
-
                     let address = host_phase_2_artifact.addr;
 
                     StepSuccess::new(host_phase_2_artifact)
@@ -263,17 +245,6 @@ impl InstallOpts {
                         fetch_artifact(&cx, &control_plane_id, discovery, log)
                             .await?;
 
-                    // Check that the sha256 of the data we got from wicket
-                    // matches the data we asked for. We do not retry this for
-                    // the same reasons described above when checking the
-                    // downloaded host phase 2 artifact.
-                    check_downloaded_artifact_hash(
-                        "control plane",
-                        control_plane_artifact.artifact.clone(),
-                        control_plane_id.hash,
-                    )
-                    .await?;
-
                     let address = control_plane_artifact.addr;
 
                     StepSuccess::new(control_plane_artifact)
@@ -403,31 +374,6 @@ impl InstallOpts {
     }
 }
 
-async fn check_downloaded_artifact_hash(
-    name: &'static str,
-    data: BufList,
-    expected_hash: ArtifactHash,
-) -> Result<()> {
-    tokio::task::spawn_blocking(move || {
-        let mut hasher = Sha256::new();
-        for chunk in data.iter() {
-            hasher.update(chunk);
-        }
-        let computed_hash = ArtifactHash(hasher.finalize().into());
-        if expected_hash != computed_hash {
-            bail!(
-                "downloaded {name} checksum failure: \
-                 expected {} but calculated {}",
-                hex::encode(&expected_hash),
-                hex::encode(&computed_hash)
-            );
-        }
-        Ok(())
-    })
-    .await
-    .unwrap()
-}
-
 async fn scan_hardware_with_retries(
     cx: &StepContext,
     log: &slog::Logger,
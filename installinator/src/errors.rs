@@ -21,6 +21,13 @@ pub(crate) enum ArtifactFetchError {
 
     #[error("artifact size in Content-Length header ({artifact_size}) did not match downloaded size ({downloaded_bytes})")]
     SizeMismatch { artifact_size: u64, downloaded_bytes: u64 },
+
+    #[error("peer {peer} reported an artifact size of {artifact_size} bytes, but earlier peers reported {expected_size} bytes")]
+    PeerSizeMismatch {
+        peer: SocketAddr,
+        artifact_size: u64,
+        expected_size: u64,
+    },
 }
 
 #[derive(Debug, Error)]
@@ -44,4 +51,13 @@ pub(crate) enum HttpError {
 
     #[error("Content-Length header could not be parsed into an integer")]
     InvalidContentLength,
+
+    #[error("missing Content-Range header in partial response")]
+    MissingContentRange,
+
+    #[error("Content-Range header could not be parsed")]
+    InvalidContentRange,
+
+    #[error("requested bytes starting at offset {requested}, but server returned bytes starting at offset {returned}")]
+    RangeMismatch { requested: u64, returned: u64 },
 }
@@ -10,6 +10,7 @@ use std::{
     collections::BTreeMap,
     fmt,
     net::{IpAddr, Ipv6Addr, SocketAddr},
+    ops::Range,
     time::Duration,
 };
 
@@ -27,7 +28,7 @@ use uuid::Uuid;
 
 use crate::{
     errors::HttpError,
-    peers::{FetchReceiver, PeersImpl},
+    peers::{FetchReceiver, PeersImpl, MAX_CHUNK_PASSES},
 };
 
 struct MockPeersUniverse {
@@ -101,29 +102,81 @@ impl MockPeersUniverse {
         )
     }
 
-    /// On success this returns (successful attempt, peer).
+    /// On success this returns (successful attempt, peer, strict), where
+    /// `strict` is true if the artifact was fetched as a single chunk on the
+    /// first pass over the peers in the successful attempt.
     ///
     /// On failure this returns the number of attempts that failed.
     fn expected_result(
         &self,
         timeout: Duration,
-    ) -> Result<(usize, SocketAddr), usize> {
-        self.attempts()
-            .enumerate()
-            .filter_map(|(attempt, peers)| {
-                peers
-                    .ok()?
-                    .successful_peer(timeout)
-                    // attempt is zero-indexed here, but the attempt returned by FetchedArtifact is
-                    // 1-indexed.
-                    .map(|addr| (attempt + 1, addr))
-            })
-            .next()
-            .ok_or_else(|| {
-                // We're going to try one last time after the attempt bitmaps
-                // run out, then abort. Hence + 1.
-                self.attempt_bitmaps.len() + 1
-            })
+        chunk_size: u64,
+    ) -> Result<(usize, SocketAddr, bool), usize> {
+        // Like `Peers`, keep track of what's been fetched across attempts.
+        let artifact_len = self.artifact.len();
+        let chunk_size = usize::try_from(chunk_size).unwrap_or(usize::MAX);
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        loop {
+            // There's always at least one chunk, even for an empty artifact.
+            let end = artifact_len.min(start.saturating_add(chunk_size));
+            chunks.push(MockChunk { remaining: end - start, peer: None });
+            if end == artifact_len {
+                break;
+            }
+            start = end;
+        }
+        let mut size_known = false;
+
+        for (attempt, peers) in self.attempts().enumerate() {
+            let Ok(peers) = peers else {
+                continue;
+            };
+
+            // Until the size of the artifact is known, the first chunk is
+            // fetched on its own.  Any peer that responds at all tells us the
+            // size.
+            let mut first_pass = true;
+            if !size_known {
+                if peers.selected_peers.is_empty() {
+                    continue;
+                }
+                size_known = true;
+                match peers.fetch_chunk(0, &mut chunks[0], timeout) {
+                    Some(pass) => first_pass &= pass == 0,
+                    None => continue,
+                }
+            }
+
+            let mut complete = true;
+            for (index, chunk) in chunks.iter_mut().enumerate() {
+                if chunk.peer.is_some() {
+                    continue;
+                }
+                match peers.fetch_chunk(index, chunk, timeout) {
+                    Some(pass) => first_pass &= pass == 0,
+                    None => complete = false,
+                }
+            }
+
+            if complete {
+                let addr = chunks
+                    .last()
+                    .and_then(|chunk| chunk.peer)
+                    .expect("last chunk is complete");
+                // attempt is zero-indexed here, but the attempt returned by
+                // FetchedArtifact is 1-indexed.
+                return Ok((
+                    attempt + 1,
+                    addr,
+                    first_pass && chunks.len() == 1,
+                ));
+            }
+        }
+
+        // We're going to try one last time after the attempt bitmaps run out,
+        // then abort. Hence + 1.
+        Err(self.attempt_bitmaps.len() + 1)
     }
 
     fn attempts(&self) -> impl Iterator<Item = Result<MockPeers>> + '_ {
@@ -186,10 +239,23 @@ impl MockPeers {
         self.selected_peers.iter()
     }
 
-    /// Returns the peer that can return the entire dataset within the timeout.
-    fn successful_peer(&self, timeout: Duration) -> Option<SocketAddr> {
-        self.peers()
-            .filter_map(|(addr, peer)| {
+    /// Fetches the rest of the `index`th chunk, returning the (zero-indexed)
+    /// pass over the peers during which it was completed, if any.
+    ///
+    /// Like `Peers`, this makes up to `MAX_CHUNK_PASSES` passes over the
+    /// peers, each starting with the `index`th one, and each peer picks up
+    /// where the previous one left off.
+    fn fetch_chunk(
+        &self,
+        index: usize,
+        chunk: &mut MockChunk,
+        timeout: Duration,
+    ) -> Option<usize> {
+        let peers: Vec<_> = self.peers().collect();
+
+        for pass in 0..MAX_CHUNK_PASSES {
+            for i in 0..peers.len() {
+                let (addr, peer) = peers[(index + i) % peers.len()];
                 if peer.artifact != self.artifact {
                     // We don't handle the case where the peer returns the wrong artifact yet.
                     panic!("peer artifact not the same as self.artifact -- can't happen in normal use");
@@ -211,26 +277,42 @@ impl MockPeers {
                                     // Since Peers creates the timeout BEFORE MockPeersUniverse sets its
                                     // delay, action.after must be less than timeout.
                                     if *after >= timeout {
-                                        return None;
+                                        break;
                                     }
 
                                     total_count += count;
-                                    if total_count >= peer.artifact.len() {
-                                        return Some(*addr);
+                                    if total_count >= chunk.remaining {
+                                        chunk.peer = Some(*addr);
+                                        return Some(pass);
                                     }
                                 }
-                                ResponseAction::Error => return None,
+                                ResponseAction::Error => break,
                             }
                         }
-                        None
+
+                        // Whatever this peer returned before failing is kept, and
+                        // the next peer is asked for the rest.
+                        chunk.remaining -= total_count;
                     }
-                    MockResponse::Forbidden { .. } | MockResponse::NotFound { .. } => None,
+                    MockResponse::Forbidden { .. }
+                    | MockResponse::NotFound { .. } => {}
                 }
-            })
-            .next()
+            }
+        }
+
+        None
     }
 }
 
+/// The model's view of what's been fetched of one chunk of the artifact
+#[derive(Debug)]
+struct MockChunk {
+    /// The number of bytes of the chunk not yet fetched.
+    remaining: usize,
+    /// The peer that delivered the end of the chunk, once it's complete.
+    peer: Option<SocketAddr>,
+}
+
 #[async_trait]
 impl PeersImpl for MockPeers {
     fn peers(&self) -> Box<dyn Iterator<Item = SocketAddr> + Send + '_> {
@@ -246,16 +328,22 @@ impl PeersImpl for MockPeers {
         peer: SocketAddr,
         // We don't (yet) use the artifact ID in MockPeers
         _artifact_hash_id: ArtifactHashId,
+        range: Range<u64>,
     ) -> Result<(u64, FetchReceiver), HttpError> {
-        let peer_data = self
+        let mut peer_data = self
             .get(peer)
             .unwrap_or_else(|| panic!("peer {peer} not found in selection"))
             .clone();
         let artifact_size = peer_data.artifact.len() as u64;
 
+        // Only return the requested part of the artifact, like a real server
+        // would.
+        let start = range.start.min(artifact_size) as usize;
+        let end = range.end.min(artifact_size) as usize;
+        peer_data.artifact = peer_data.artifact.slice(start..end);
+
         let (sender, receiver) = mpsc::channel(8);
         tokio::spawn(async move { peer_data.send_response(sender).await });
-        // TODO: add tests to ensure an invalid artifact size is correctly detected
         Ok((artifact_size, receiver))
     }
 
@@ -507,6 +595,7 @@ impl PeersImpl for MockReportPeers {
         &self,
         _peer: SocketAddr,
         _artifact_hash_id: ArtifactHashId,
+        _range: Range<u64>,
     ) -> Result<(u64, FetchReceiver), HttpError> {
         unimplemented!(
             "this should never be called -- \
@@ -550,7 +639,7 @@ mod tests {
         errors::DiscoverPeersError,
         peers::{FetchedArtifact, Peers},
         reporter::ProgressReporter,
-        test_helpers::{dummy_artifact_hash_id, with_test_runtime},
+        test_helpers::with_test_runtime,
     };
 
     use bytes::Buf;
@@ -560,8 +649,11 @@ mod tests {
         InstallinatorProgressMetadata, InstallinatorStepId, StepContext,
         StepEvent, StepEventKind, StepOutcome, StepSuccess, UpdateEngine,
     };
-    use omicron_common::api::internal::nexus::KnownArtifactKind;
+    use omicron_common::{
+        api::internal::nexus::KnownArtifactKind, update::ArtifactHash,
+    };
     use omicron_test_utils::dev::test_setup_log;
+    use sha2::{Digest, Sha256};
     use test_strategy::proptest;
     use tokio_stream::wrappers::ReceiverStream;
 
@@ -572,13 +664,17 @@ mod tests {
         universe: MockPeersUniverse,
         #[strategy((0..2000u64).prop_map(Duration::from_millis))]
         timeout: Duration,
+        // Artifacts are up to 4096 bytes long, so this covers both artifacts
+        // fetched in a single chunk and ones split into up to 16 chunks.
+        #[strategy(256..8192u64)] chunk_size: u64,
         #[strategy(any::<[u8; 16]>().prop_map(Uuid::from_bytes))]
         update_id: Uuid,
     ) {
         with_test_runtime(move || async move {
             let logctx = test_setup_log("proptest_fetch_artifact");
-            let expected_result = universe.expected_result(timeout);
+            let expected_result = universe.expected_result(timeout, chunk_size);
             let expected_artifact = universe.artifact.clone();
+            let artifact_hash_id = hash_id_for(&expected_artifact);
 
             let attempts = universe.attempts();
 
@@ -617,9 +713,15 @@ mod tests {
                     InstallinatorStepId::Download,
                     "Downloading artifact",
                     |cx| async move {
-                        let artifact =
-                            fetch_artifact(&cx, &log, attempts, timeout)
-                                .await?;
+                        let artifact = fetch_artifact(
+                            &cx,
+                            &log,
+                            attempts,
+                            timeout,
+                            chunk_size,
+                            &artifact_hash_id,
+                        )
+                        .await?;
                         let address = artifact.addr;
                         StepSuccess::new(artifact)
                             .with_metadata(
@@ -651,7 +753,7 @@ mod tests {
 
             match (expected_result, fetched_artifact) {
                 (
-                    Ok((expected_attempt, expected_addr)),
+                    Ok((expected_attempt, expected_addr, _)),
                     Ok(FetchedArtifact { attempt, addr, mut artifact }),
                 ) => {
                     assert_eq!(
@@ -673,7 +775,7 @@ mod tests {
                 (Err(_), Ok(fetched_artifact)) => {
                     panic!("expected failure to fetch but found success: {fetched_artifact:?}");
                 }
-                (Ok((attempt, addr)), Err(err)) => {
+                (Ok((attempt, addr, _)), Err(err)) => {
                     panic!("expected success at attempt `{attempt}` from `{addr}`, but found failure: {err}");
                 }
             }
@@ -684,20 +786,136 @@ mod tests {
         });
     }
 
+    #[test]
+    fn fetch_artifact_with_lying_peer() {
+        with_test_runtime(|| async {
+            let logctx = test_setup_log("fetch_artifact_with_lying_peer");
+
+            let artifact: Bytes = (0..4096).map(|i| i as u8).collect();
+            let artifact_hash_id = hash_id_for(&artifact);
+            let honest_peer: SocketAddr = "[fd00::2]:8000".parse().unwrap();
+            let honest = MockPeer {
+                artifact: artifact.clone(),
+                response: MockResponse::Response(vec![
+                    ResponseAction::Response {
+                        after: Duration::ZERO,
+                        count: usize::MAX,
+                    },
+                ]),
+            };
+
+            // The lying peer sorts first, so it's asked for the first chunk
+            // (and so decides the size of the artifact) on the first attempt.
+            // It either sends its own (wrong) artifact, which fails the hash
+            // check once assembled, or fails right after telling us the size,
+            // after which no other peer agrees with that size.
+            let lying_peer: SocketAddr = "[fd00::1]:8000".parse().unwrap();
+            let lying_artifact = Bytes::from(vec![0xff; 5000]);
+            let lying_responses = [
+                MockResponse::Response(vec![ResponseAction::Response {
+                    after: Duration::ZERO,
+                    count: usize::MAX,
+                }]),
+                MockResponse::Response(vec![ResponseAction::Error]),
+            ];
+
+            for lying_response in lying_responses {
+                println!("lying peer response: {lying_response:?}");
+                let liar = MockPeer {
+                    artifact: lying_artifact.clone(),
+                    response: lying_response,
+                };
+                let attempts = (0..2).map(|_| {
+                    Ok(MockPeers {
+                        artifact: artifact.clone(),
+                        selected_peers: [
+                            (lying_peer, liar.clone()),
+                            (honest_peer, honest.clone()),
+                        ]
+                        .into_iter()
+                        .collect(),
+                    })
+                });
+
+                let (event_sender, event_receiver) = mpsc::channel(512);
+                let receiver_handle = tokio::spawn(async move {
+                    ReceiverStream::new(event_receiver)
+                        .collect::<Vec<_>>()
+                        .await
+                });
+                let engine = UpdateEngine::new(&logctx.log, event_sender);
+                let log = logctx.log.clone();
+                let artifact_hash_id = &artifact_hash_id;
+                let artifact_handle = engine
+                    .new_step(
+                        InstallinatorComponent::ControlPlane,
+                        InstallinatorStepId::Download,
+                        "Downloading artifact",
+                        |cx| async move {
+                            let artifact = fetch_artifact(
+                                &cx,
+                                &log,
+                                attempts,
+                                Duration::from_secs(10),
+                                1024,
+                                artifact_hash_id,
+                            )
+                            .await?;
+                            let address = artifact.addr;
+                            StepSuccess::new(artifact)
+                                .with_metadata(
+                                    InstallinatorCompletionMetadata::Download {
+                                        address,
+                                    },
+                                )
+                                .into()
+                        },
+                    )
+                    .register();
+
+                let completion_cx =
+                    engine.execute().await.expect("artifact fetched");
+                let FetchedArtifact { attempt, addr, artifact: mut fetched } =
+                    artifact_handle.into_value(completion_cx.token()).await;
+                receiver_handle.await.expect("event receiver task succeeded");
+
+                // Everything fetched on the first attempt was thrown away, and
+                // the second attempt started with the honest peer.
+                assert_eq!(attempt, 2, "fetched on the second attempt");
+                assert_eq!(addr, honest_peer, "last chunk from honest peer");
+                let fetched = fetched.copy_to_bytes(fetched.num_bytes());
+                assert_eq!(fetched, artifact, "correct artifact fetched");
+            }
+
+            logctx.cleanup_successful();
+        });
+    }
+
+    /// Returns the ID of a control plane artifact with these contents.
+    fn hash_id_for(artifact: &[u8]) -> ArtifactHashId {
+        ArtifactHashId {
+            kind: KnownArtifactKind::ControlPlane.into(),
+            hash: ArtifactHash(Sha256::digest(artifact).into()),
+        }
+    }
+
     async fn fetch_artifact(
         cx: &StepContext,
         log: &slog::Logger,
         attempts: impl IntoIterator<Item = Result<MockPeers>>,
         timeout: Duration,
+        chunk_size: u64,
+        artifact_hash_id: &ArtifactHashId,
     ) -> Result<FetchedArtifact> {
         let mut attempts = attempts.into_iter();
         FetchedArtifact::loop_fetch_from_peers(
             cx,
             log,
             || match attempts.next() {
-                Some(Ok(peers)) => {
-                    future::ok(Peers::new(&log, Box::new(peers), timeout))
-                }
+                Some(Ok(peers)) => future::ok(
+                    Peers::new(&log, Box::new(peers), timeout)
+                        .with_chunk_size(chunk_size),
+                ),
                 Some(Err(error)) => {
                     future::err(DiscoverPeersError::Retry(error))
                 }
@@ -705,14 +923,14 @@ mod tests {
                     anyhow::anyhow!("ran out of attempts"),
                 )),
             },
-            &dummy_artifact_hash_id(KnownArtifactKind::ControlPlane),
+            artifact_hash_id,
         )
         .await
     }
 
     fn assert_reports(
         reports: &[EventReport],
-        expected_result: Result<(usize, SocketAddr), usize>,
+        expected_result: Result<(usize, SocketAddr, bool), usize>,
     ) {
         let all_step_events: Vec<_> =
             reports.iter().flat_map(|report| &report.step_events).collect();
@@ -720,11 +938,12 @@ mod tests {
         // Assert that we received failure events for all prior attempts and
         // a success event for the current attempt.
         match expected_result {
-            Ok((expected_attempt, expected_addr)) => {
+            Ok((expected_attempt, expected_addr, strict)) => {
                 assert_success_events(
                     all_step_events,
                     expected_attempt,
                     expected_addr,
+                    strict,
                 );
             }
             Err(expected_total_attempts) => {
@@ -737,13 +956,17 @@ mod tests {
         all_step_events: Vec<&StepEvent>,
         expected_attempt: usize,
         expected_addr: SocketAddr,
+        strict: bool,
     ) {
         let mut saw_success = false;
 
         for event in all_step_events {
             match &event.kind {
                 StepEventKind::ProgressReset { attempt, metadata, .. } => {
-                    if *attempt == expected_attempt {
+                    // If the artifact was fetched in several chunks, or over
+                    // several passes, the peer that delivered the end of it
+                    // may well have failed before.
+                    if *attempt == expected_attempt && strict {
                         match metadata {
                             InstallinatorProgressMetadata::Download {
                                 peer,
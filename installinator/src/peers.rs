@@ -3,10 +3,13 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use std::{
+    collections::BTreeMap,
     fmt,
     future::Future,
     net::{IpAddr, SocketAddr},
+    ops::Range,
     str::FromStr,
+    sync::atomic::{AtomicU64, Ordering},
     time::Duration,
 };
 
@@ -22,9 +25,10 @@ use installinator_common::{
 };
 use itertools::Itertools;
 use omicron_common::address::BOOTSTRAP_ARTIFACT_PORT;
-use omicron_common::update::ArtifactHashId;
+use omicron_common::update::{ArtifactHash, ArtifactHashId};
 use omicron_ddm_admin_client::Client as DdmAdminClient;
 use reqwest::StatusCode;
+use sha2::{Digest, Sha256};
 use sled_hardware_types::underlay::BootstrapInterface;
 use tokio::{sync::mpsc, time::Instant};
 use update_engine::events::ProgressUnits;
@@ -115,9 +119,26 @@ impl FromStr for DiscoveryMechanism {
     }
 }
 
+/// Artifacts are fetched in chunks of this size.  Each chunk is fetched from a
+/// single peer at a time, but different chunks may be fetched from different
+/// peers in parallel.
+const FETCH_CHUNK_SIZE: u64 = 64 * 1024 * 1024;
+
+/// The maximum number of chunks of an artifact fetched at the same time.
+const MAX_CONCURRENT_CHUNK_FETCHES: usize = 8;
+
+/// The number of passes made over the peers for each chunk, per attempt.
+///
+/// Bytes received during a pass are kept, so each pass only asks for what's
+/// still missing.
+pub(crate) const MAX_CHUNK_PASSES: usize = 3;
+
 /// A fetched artifact.
 pub(crate) struct FetchedArtifact {
     pub(crate) attempt: usize,
+    /// The peer that delivered the end of the artifact.
+    ///
+    /// Other parts of the artifact may have come from other peers.
     pub(crate) addr: SocketAddr,
     pub(crate) artifact: BufList,
 }
@@ -127,6 +148,10 @@ impl FetchedArtifact {
     ///
     /// If `discover_fn` returns [`DiscoverPeersError::Retry`], this function will retry. If it
     /// returns `DiscoverPeersError::Abort`, this function will exit with the underlying error.
+    ///
+    /// The fetched artifact is checked against `artifact_hash_id`'s hash.  If
+    /// it doesn't match, or if peers disagree about the size of the artifact,
+    /// everything fetched so far is thrown away and the fetch starts over.
     pub(crate) async fn loop_fetch_from_peers<F, Fut>(
         cx: &StepContext,
         log: &slog::Logger,
@@ -141,6 +166,10 @@ impl FetchedArtifact {
         // to fetch an artifact from a found peer.
         const RETRY_DELAY: Duration = Duration::from_secs(5);
 
+        // Bytes fetched during one attempt are kept for the next, so each
+        // attempt picks up where the last one left off -- unless they turn
+        // out to be wrong, in which case they're discarded.
+        let mut progress = FetchProgress::default();
         let mut attempt = 0;
         loop {
             attempt += 1;
@@ -170,14 +199,60 @@ impl FetchedArtifact {
                 peers.peer_count(),
                 peers.display(),
             );
-            match peers.fetch_artifact(&cx, artifact_hash_id).await {
-                Some((addr, artifact)) => {
-                    return Ok(Self { attempt, addr, artifact })
+            match peers
+                .fetch_artifact(&cx, artifact_hash_id, &mut progress)
+                .await
+            {
+                Ok((addr, artifact)) => {
+                    let computed_hash = artifact_hash(artifact.clone()).await;
+                    if computed_hash == artifact_hash_id.hash {
+                        return Ok(Self { attempt, addr, artifact });
+                    }
+
+                    // The artifact may have been pieced together from chunks
+                    // fetched from several peers, and we can't tell which
+                    // one was bad, so start over from scratch.
+                    slog::warn!(
+                        log,
+                        "(attempt {attempt}) fetched artifact has the wrong \
+                         hash, discarding it and retrying";
+                        "expected_hash" => %artifact_hash_id.hash,
+                        "computed_hash" => %computed_hash,
+                        "last_peer" => %addr,
+                    );
+                    cx.send_progress(StepProgress::retry(format!(
+                        "fetched artifact checksum failure: expected {} but \
+                         calculated {}",
+                        artifact_hash_id.hash, computed_hash,
+                    )))
+                    .await;
+                    progress.reset();
+                    tokio::time::sleep(RETRY_DELAY).await;
+                }
+                Err(FetchArtifactError::SizeMismatch) => {
+                    // The size we learned may have come from a peer that's
+                    // wrong, in which case no other peer will ever agree with
+                    // it.  Forget it (and the bytes fetched based on it), and
+                    // start over.
+                    slog::warn!(
+                        log,
+                        "(attempt {attempt}) peers disagree about the size of \
+                         the artifact, discarding fetched bytes and retrying";
+                        "fetched_bytes" => progress.fetched_bytes(),
+                    );
+                    cx.send_progress(StepProgress::retry(
+                        "peers disagree about the size of the artifact, \
+                         starting over",
+                    ))
+                    .await;
+                    progress.reset();
+                    tokio::time::sleep(RETRY_DELAY).await;
                 }
-                None => {
+                Err(FetchArtifactError::Incomplete) => {
                     slog::warn!(
                         log,
-                        "unable to fetch artifact from peers, retrying discovery",
+                        "unable to fetch artifact from peers, retrying discovery";
+                        "fetched_bytes" => progress.fetched_bytes(),
                     );
                     cx.send_progress(StepProgress::retry(format!(
                         "unable to fetch artifact from any of {} peers, retrying",
@@ -213,6 +288,7 @@ pub(crate) struct Peers {
     log: slog::Logger,
     imp: Box<dyn PeersImpl>,
     timeout: Duration,
+    chunk_size: u64,
 }
 
 impl Peers {
@@ -222,87 +298,306 @@ impl Peers {
         timeout: Duration,
     ) -> Self {
         let log = log.new(slog::o!("component" => "Peers"));
-        Self { log, imp, timeout }
+        Self { log, imp, timeout, chunk_size: FETCH_CHUNK_SIZE }
+    }
+
+    /// Overrides the size of the chunks that artifacts are fetched in.
+    #[cfg(test)]
+    pub(crate) fn with_chunk_size(mut self, chunk_size: u64) -> Self {
+        assert!(chunk_size > 0, "chunk size must be non-zero");
+        self.chunk_size = chunk_size;
+        self
     }
 
+    /// Fetches an artifact from the peers, returning the peer that delivered
+    /// the end of it along with its contents.
+    ///
+    /// The artifact is fetched in chunks.  Until the size of the artifact is
+    /// known, the first chunk is fetched on its own; the remaining chunks are
+    /// then fetched from several peers in parallel.  Each chunk is retried
+    /// independently, and if a peer fails partway through a chunk, the next
+    /// peer picks up where it left off.
+    ///
+    /// Everything fetched is recorded in `progress`.  Returns an error if some
+    /// chunk couldn't be fetched, in which case calling this again with the
+    /// same `progress` only fetches what's still missing.
+    ///
+    /// This doesn't check the artifact's hash: that's up to the caller, once
+    /// the whole artifact has been assembled.
     pub(crate) async fn fetch_artifact(
         &self,
         cx: &StepContext,
         artifact_hash_id: &ArtifactHashId,
-    ) -> Option<(SocketAddr, BufList)> {
+        progress: &mut FetchProgress,
+    ) -> Result<(SocketAddr, BufList), FetchArtifactError> {
         // TODO: do we want a check phase that happens before the download?
-        let peers = self.peers();
-        let mut remaining_peers = self.peer_count();
+        let peers: Vec<_> = self.peers().collect();
 
         let log = self.log.new(
             slog::o!("artifact_hash_id" => format!("{artifact_hash_id:?}")),
         );
 
-        slog::debug!(log, "start fetch from peers"; "remaining_peers" => remaining_peers);
+        slog::debug!(
+            log,
+            "start fetch from peers";
+            "peer_count" => peers.len(),
+            "fetched_bytes" => progress.fetched_bytes(),
+        );
+
+        let start = Instant::now();
+        let downloaded_bytes = AtomicU64::new(progress.fetched_bytes());
+        let artifact_size = match progress.artifact_size {
+            Some(artifact_size) => artifact_size,
+            None => {
+                // Until some peer tells us, we don't know how big the artifact
+                // is, so we don't know what the other chunks are.
+                //
+                // Each time progress is reset, start with a different peer,
+                // so that a peer that's wrong about the size doesn't get to
+                // decide it every time.
+                let first_peer = progress.resets;
+                let chunk = progress.chunks.entry(0).or_default();
+                self.fetch_range(
+                    cx,
+                    &log,
+                    &peers,
+                    first_peer,
+                    artifact_hash_id,
+                    0..self.chunk_size,
+                    &mut progress.artifact_size,
+                    chunk,
+                    &downloaded_bytes,
+                )
+                .await?;
+                progress
+                    .artifact_size
+                    .expect("artifact size known after fetching")
+            }
+        };
+
+        // There's always at least one chunk, even for an empty artifact.
+        let mut offset = 0;
+        loop {
+            progress.chunks.entry(offset).or_default();
+            offset = offset.saturating_add(self.chunk_size);
+            if offset >= artifact_size {
+                break;
+            }
+        }
+
+        // Spread the remaining chunks across peers, starting each one with a
+        // different peer.  A chunk that can't be fetched doesn't stop the
+        // others.
+        let concurrency = peers.len().clamp(1, MAX_CONCURRENT_CHUNK_FETCHES);
+        let (log, peers, downloaded_bytes) = (&log, &peers, &downloaded_bytes);
+        let chunk_size = self.chunk_size;
+        let results = futures::stream::iter(
+            progress
+                .chunks
+                .iter_mut()
+                .enumerate()
+                .filter(|(_, (_, chunk))| !chunk.is_complete()),
+        )
+        .map(|(index, (&offset, chunk))| async move {
+            let mut artifact_size = Some(artifact_size);
+            self.fetch_range(
+                cx,
+                log,
+                peers,
+                index,
+                artifact_hash_id,
+                offset..offset.saturating_add(chunk_size),
+                &mut artifact_size,
+                chunk,
+                downloaded_bytes,
+            )
+            .await
+        })
+        .buffer_unordered(concurrency)
+        .collect::<Vec<_>>()
+        .await;
+        if results.contains(&Err(FetchArtifactError::SizeMismatch)) {
+            return Err(FetchArtifactError::SizeMismatch);
+        }
+
+        let mut addr = None;
+        let mut artifact = BufList::new();
+        for (offset, chunk) in &progress.chunks {
+            let Some(peer) = chunk.peer else {
+                slog::warn!(
+                    log,
+                    "unable to fetch chunk from any peer";
+                    "offset" => offset,
+                    "fetched_bytes" => chunk.data.num_bytes(),
+                );
+                return Err(FetchArtifactError::Incomplete);
+            };
+            addr = Some(peer);
+            for bytes in chunk.data.iter() {
+                artifact.push_chunk(bytes.clone());
+            }
+        }
+        let addr = addr.expect("artifact has at least one chunk");
+
+        slog::info!(
+            log,
+            "fetched artifact ({artifact_size} bytes) in {:?}",
+            start.elapsed();
+            "last_peer" => %addr,
+        );
+        Ok((addr, artifact))
+    }
+
+    pub(crate) fn peers(&self) -> impl Iterator<Item = SocketAddr> + '_ {
+        self.imp.peers()
+    }
+
+    pub(crate) fn peer_count(&self) -> usize {
+        self.imp.peer_count()
+    }
+
+    pub(crate) fn display(&self) -> impl fmt::Display {
+        self.peers().join(", ")
+    }
 
-        for peer in peers {
-            remaining_peers -= 1;
+    /// Fetches the bytes of the artifact within `range` (clamped to the end of
+    /// the artifact) into `chunk`, resuming after any bytes already there.
+    ///
+    /// Each pass over the peers starts with `peers[first_peer]` (mod the
+    /// number of peers), and up to [`MAX_CHUNK_PASSES`] passes are made.  If a
+    /// peer fails partway through, the next peer is asked for the rest of the
+    /// range.  On success, `chunk.peer` is set to the peer that delivered the
+    /// end of the range.
+    ///
+    /// `artifact_size` is the size of the artifact if it's already known;
+    /// peers that disagree with it are skipped.  Otherwise, it's filled in
+    /// from the first peer that responds.
+    ///
+    /// If no peer delivers the range, returns
+    /// [`FetchArtifactError::SizeMismatch`] if any of them disagreed with
+    /// `artifact_size`.
+    #[allow(clippy::too_many_arguments)]
+    async fn fetch_range(
+        &self,
+        cx: &StepContext,
+        log: &slog::Logger,
+        peers: &[SocketAddr],
+        first_peer: usize,
+        artifact_hash_id: &ArtifactHashId,
+        range: Range<u64>,
+        artifact_size: &mut Option<u64>,
+        chunk: &mut ChunkProgress,
+        downloaded_bytes: &AtomicU64,
+    ) -> Result<(), FetchArtifactError> {
+        let attempts = (0..MAX_CHUNK_PASSES)
+            .flat_map(|_| 0..peers.len())
+            .map(|i| peers[(first_peer + i) % peers.len()]);
+        let mut remaining_attempts = MAX_CHUNK_PASSES * peers.len();
+        let mut size_mismatch = false;
+
+        for peer in attempts {
+            remaining_attempts -= 1;
+            let offset = range.start + chunk.data.num_bytes() as u64;
 
             slog::debug!(
                 log,
-                "start fetch from peer {peer:?}"; "remaining_peers" => remaining_peers,
+                "start fetch from peer {peer:?}";
+                "offset" => offset,
+                "end" => range.end,
+                "remaining_attempts" => remaining_attempts,
             );
 
-            // Attempt to download data from this peer.
+            // Attempt to download the rest of the range from this peer.
             let start = Instant::now();
-            match self.fetch_from_peer(cx, peer, artifact_hash_id).await {
-                Ok(artifact_bytes) => {
+            match self
+                .fetch_from_peer(
+                    cx,
+                    peer,
+                    artifact_hash_id,
+                    offset..range.end,
+                    artifact_size,
+                    &mut chunk.data,
+                    downloaded_bytes,
+                )
+                .await
+            {
+                Ok(()) => {
                     let elapsed = start.elapsed();
-                    slog::info!(
+                    slog::debug!(
                         log,
-                        "fetched artifact from peer {peer} in {elapsed:?}"
+                        "fetched bytes {offset}..{} from peer {peer} \
+                         in {elapsed:?}",
+                        range.start + chunk.data.num_bytes() as u64,
                     );
-                    return Some((peer, artifact_bytes));
                 }
                 Err(error) => {
+                    size_mismatch |= matches!(
+                        error,
+                        ArtifactFetchError::PeerSizeMismatch { .. }
+                    );
                     let elapsed = start.elapsed();
                     slog::warn!(
                         log,
                         "error after {elapsed:?}: {}",
                         DisplayErrorChain::new(&error);
-                        "remaining_peers" => remaining_peers,
+                        "remaining_attempts" => remaining_attempts,
                     );
+
+                    // The peer might have failed after sending the last bytes
+                    // we needed (e.g., while finishing up the response).
+                    // That's fine: the hash check on the whole artifact will
+                    // catch any problems with the data.
+                    let end = range.start + chunk.data.num_bytes() as u64;
+                    let done = end > offset
+                        && artifact_size.is_some_and(|artifact_size| {
+                            end >= range.end.min(artifact_size)
+                        });
+                    if !done {
+                        continue;
+                    }
                 }
             }
-        }
-
-        None
-    }
 
-    pub(crate) fn peers(&self) -> impl Iterator<Item = SocketAddr> + '_ {
-        self.imp.peers()
-    }
-
-    pub(crate) fn peer_count(&self) -> usize {
-        self.imp.peer_count()
-    }
+            chunk.peer = Some(peer);
+            return Ok(());
+        }
 
-    pub(crate) fn display(&self) -> impl fmt::Display {
-        self.peers().join(", ")
+        if size_mismatch {
+            Err(FetchArtifactError::SizeMismatch)
+        } else {
+            Err(FetchArtifactError::Incomplete)
+        }
     }
 
+    /// Fetches the bytes of the artifact within `range` from a single peer,
+    /// appending them to `data`.
+    ///
+    /// `artifact_size` is filled in from the peer's response if it isn't
+    /// already known.  Bytes received before an error are kept in `data`, so
+    /// that the fetch can be resumed from another peer.
+    #[allow(clippy::too_many_arguments)]
     async fn fetch_from_peer(
         &self,
         cx: &StepContext,
         peer: SocketAddr,
         artifact_hash_id: &ArtifactHashId,
-    ) -> Result<BufList, ArtifactFetchError> {
+        range: Range<u64>,
+        artifact_size: &mut Option<u64>,
+        data: &mut BufList,
+        downloaded_bytes: &AtomicU64,
+    ) -> Result<(), ArtifactFetchError> {
         let log = self.log.new(slog::o!("peer" => peer.to_string()));
+        let metadata = InstallinatorProgressMetadata::Download { peer };
 
         let (total_bytes, mut receiver) = match self
             .imp
-            .fetch_from_peer_impl(peer, artifact_hash_id.clone())
+            .fetch_from_peer_impl(peer, artifact_hash_id.clone(), range.clone())
             .await
         {
             Ok(x) => x,
             Err(error) => {
                 cx.send_progress(StepProgress::Reset {
-                    metadata: InstallinatorProgressMetadata::Download { peer },
+                    metadata,
                     message: error.to_string().into(),
                 })
                 .await;
@@ -310,22 +605,72 @@ impl Peers {
             }
         };
 
-        let mut artifact_bytes = BufList::new();
-        let mut downloaded_bytes = 0u64;
-        let metadata = InstallinatorProgressMetadata::Download { peer };
+        // All peers must agree on the size of the artifact.
+        match *artifact_size {
+            Some(expected_size) if expected_size != total_bytes => {
+                let error = ArtifactFetchError::PeerSizeMismatch {
+                    peer,
+                    artifact_size: total_bytes,
+                    expected_size,
+                };
+                cx.send_progress(StepProgress::reset(
+                    metadata,
+                    error.to_string(),
+                ))
+                .await;
+                return Err(error);
+            }
+            Some(_) => {}
+            None => *artifact_size = Some(total_bytes),
+        }
+
+        // If this range includes the end of the artifact, keep reading until
+        // the peer finishes its response, to make sure it doesn't have more
+        // data than it said it did.  Otherwise, stop as soon as we have the
+        // whole range.
+        let end = range.end.min(total_bytes);
+        let includes_end = end == total_bytes;
+        let mut offset = range.start;
 
         loop {
+            if offset >= end && !includes_end {
+                break;
+            }
+
             match tokio::time::timeout(self.timeout, receiver.recv()).await {
-                Ok(Some(Ok(bytes))) => {
+                Ok(Some(Ok(mut bytes))) => {
                     slog::debug!(
                         &log,
                         "received chunk of {} bytes from peer",
                         bytes.len()
                     );
-                    downloaded_bytes += bytes.len() as u64;
-                    artifact_bytes.push_chunk(bytes);
+                    let wanted = end.saturating_sub(offset);
+                    if bytes.len() as u64 > wanted {
+                        if includes_end {
+                            let error = ArtifactFetchError::SizeMismatch {
+                                artifact_size: total_bytes,
+                                downloaded_bytes: offset + bytes.len() as u64,
+                            };
+                            cx.send_progress(StepProgress::reset(
+                                metadata,
+                                error.to_string(),
+                            ))
+                            .await;
+                            return Err(error);
+                        }
+                        // The peer sent more than we asked for (e.g., it
+                        // returned the whole artifact); only keep the bytes in
+                        // our range.
+                        bytes.truncate(wanted as usize);
+                    }
+
+                    offset += bytes.len() as u64;
+                    let current = downloaded_bytes
+                        .fetch_add(bytes.len() as u64, Ordering::Relaxed)
+                        + bytes.len() as u64;
+                    data.push_chunk(bytes);
                     cx.send_progress(StepProgress::with_current_and_total(
-                        downloaded_bytes,
+                        current,
                         total_bytes,
                         ProgressUnits::BYTES,
                         metadata.clone(),
@@ -349,7 +694,7 @@ impl Peers {
                     });
                 }
                 Ok(None) => {
-                    // The entire artifact has been downloaded.
+                    // The peer has finished its response.
                     break;
                 }
                 Err(_) => {
@@ -366,24 +711,24 @@ impl Peers {
                     return Err(ArtifactFetchError::Timeout {
                         peer,
                         timeout: self.timeout,
-                        bytes_fetched: artifact_bytes.num_bytes(),
+                        bytes_fetched: (offset - range.start) as usize,
                     });
                 }
             }
         }
 
-        // Check that the artifact size matches the returned size.
-        if total_bytes != artifact_bytes.num_bytes() as u64 {
+        // Check that the peer returned everything we asked for.
+        if offset != end {
             let error = ArtifactFetchError::SizeMismatch {
                 artifact_size: total_bytes,
-                downloaded_bytes,
+                downloaded_bytes: offset,
             };
             cx.send_progress(StepProgress::reset(metadata, error.to_string()))
                 .await;
             return Err(error);
         }
 
-        Ok(artifact_bytes)
+        Ok(())
     }
 
     pub(crate) fn broadcast_report(
@@ -446,11 +791,15 @@ pub(crate) trait PeersImpl: fmt::Debug + Send + Sync {
     fn peers(&self) -> Box<dyn Iterator<Item = SocketAddr> + Send + '_>;
     fn peer_count(&self) -> usize;
 
-    /// Returns (size, receiver) on success, and an error on failure.
+    /// Fetches the bytes of an artifact within `range`.
+    ///
+    /// Returns (total artifact size, receiver) on success, and an error on
+    /// failure.  The bytes sent over the receiver start at `range.start`.
     async fn fetch_from_peer_impl(
         &self,
         peer: SocketAddr,
         artifact_hash_id: ArtifactHashId,
+        range: Range<u64>,
     ) -> Result<(u64, FetchReceiver), HttpError>;
 
     async fn report_progress_impl(
@@ -461,6 +810,68 @@ pub(crate) trait PeersImpl: fmt::Debug + Send + Sync {
     ) -> Result<(), ClientError>;
 }
 
+/// What's been fetched of an artifact so far, kept across attempts to fetch
+/// it
+#[derive(Debug, Default)]
+pub(crate) struct FetchProgress {
+    /// The size of the artifact, once a peer has told us.
+    artifact_size: Option<u64>,
+    /// The chunks of the artifact, by offset.
+    chunks: BTreeMap<u64, ChunkProgress>,
+    /// The number of times everything fetched has been thrown away.
+    resets: usize,
+}
+
+impl FetchProgress {
+    /// Returns the number of bytes of the artifact fetched so far.
+    pub(crate) fn fetched_bytes(&self) -> u64 {
+        self.chunks.values().map(|chunk| chunk.data.num_bytes() as u64).sum()
+    }
+
+    /// Throws away the size of the artifact and everything fetched so far.
+    fn reset(&mut self) {
+        *self = Self { resets: self.resets + 1, ..Default::default() };
+    }
+}
+
+/// Why [`Peers::fetch_artifact`] couldn't fetch an artifact
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum FetchArtifactError {
+    /// Some chunk couldn't be fetched from any peer.
+    Incomplete,
+    /// Some chunk couldn't be fetched from any peer, and at least one of them
+    /// disagreed with the size of the artifact we'd learned from another.
+    SizeMismatch,
+}
+
+/// Computes the SHA-256 hash of an artifact.
+async fn artifact_hash(artifact: BufList) -> ArtifactHash {
+    tokio::task::spawn_blocking(move || {
+        let mut hasher = Sha256::new();
+        for chunk in artifact.iter() {
+            hasher.update(chunk);
+        }
+        ArtifactHash(hasher.finalize().into())
+    })
+    .await
+    .unwrap()
+}
+
+/// What's been fetched of one chunk of an artifact so far
+#[derive(Debug, Default)]
+struct ChunkProgress {
+    /// The peer that delivered the end of the chunk, once it's complete.
+    peer: Option<SocketAddr>,
+    /// The bytes of the chunk received so far.
+    data: BufList,
+}
+
+impl ChunkProgress {
+    fn is_complete(&self) -> bool {
+        self.peer.is_some()
+    }
+}
+
 /// The send side of the channel over which data is sent.
 pub(crate) type FetchReceiver = mpsc::Receiver<Result<Bytes, ClientError>>;
 
@@ -492,10 +903,11 @@ impl PeersImpl for HttpPeers {
         &self,
         peer: SocketAddr,
         artifact_hash_id: ArtifactHashId,
+        range: Range<u64>,
     ) -> Result<(u64, FetchReceiver), HttpError> {
         // TODO: be able to fetch from sled-agent clients as well
         let artifact_client = ArtifactClient::new(peer, &self.log);
-        artifact_client.fetch(artifact_hash_id).await
+        artifact_client.fetch(artifact_hash_id, range).await
     }
 
     async fn report_progress_impl(
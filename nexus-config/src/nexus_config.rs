@@ -242,6 +242,13 @@ pub struct UpdatesConfig {
     pub trusted_root: Utf8PathBuf,
 }
 
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct ArtifactStoreConfig {
+    /// Directory in which to keep the artifacts extracted from uploaded TUF
+    /// repositories, so that they survive a restart of Nexus
+    pub dir: Utf8PathBuf,
+}
+
 /// Options to tweak database schema changes.
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
 pub struct SchemaConfig {
@@ -377,6 +384,8 @@ pub struct BackgroundTaskConfig {
     pub region_replacement: RegionReplacementConfig,
     /// configuration for instance watcher task
     pub instance_watcher: InstanceWatcherConfig,
+    /// configuration for instance serial console capture task
+    pub instance_serial_console: InstanceSerialConsoleConfig,
     /// configuration for service VPC firewall propagation task
     pub service_firewall_propagation: ServiceFirewallPropagationConfig,
 }
@@ -531,6 +540,14 @@ pub struct InstanceWatcherConfig {
     pub period_secs: Duration,
 }
 
+#[serde_as]
+#[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
+pub struct InstanceSerialConsoleConfig {
+    /// period (in seconds) for periodic activations of this background task
+    #[serde_as(as = "DurationSeconds<u64>")]
+    pub period_secs: Duration,
+}
+
 #[serde_as]
 #[derive(Clone, Debug, Deserialize, Eq, PartialEq, Serialize)]
 pub struct ServiceFirewallPropagationConfig {
@@ -555,6 +572,11 @@ pub struct PackageConfig {
     /// this is unconfigured.
     #[serde(default)]
     pub updates: Option<UpdatesConfig>,
+    /// Where to keep the artifacts of uploaded TUF repositories. When this is
+    /// unconfigured, they're kept in a temporary directory and lost when
+    /// Nexus restarts.
+    #[serde(default)]
+    pub artifact_store: Option<ArtifactStoreConfig>,
     /// Describes how to handle and perform schema changes.
     #[serde(default)]
     pub schema: Option<SchemaConfig>,
@@ -731,6 +753,8 @@ mod test {
             address = "[::1]:8123"
             [updates]
             trusted_root = "/path/to/root.json"
+            [artifact_store]
+            dir = "/path/to/artifacts"
             [tunables]
             max_vpc_ipv4_subnet_prefix = 27
             [deployment]
@@ -776,6 +800,7 @@ mod test {
             switch_port_settings_manager.period_secs = 30
             region_replacement.period_secs = 30
             instance_watcher.period_secs = 30
+            instance_serial_console.period_secs = 10
             service_firewall_propagation.period_secs = 300
             [default_region_allocation_strategy]
             type = "random"
@@ -838,6 +863,9 @@ mod test {
                     updates: Some(UpdatesConfig {
                         trusted_root: Utf8PathBuf::from("/path/to/root.json"),
                     }),
+                    artifact_store: Some(ArtifactStoreConfig {
+                        dir: Utf8PathBuf::from("/path/to/artifacts"),
+                    }),
                     schema: None,
                     tunables: Tunables { max_vpc_ipv4_subnet_prefix: 27 },
                     dendrite: HashMap::from([(
@@ -908,6 +936,9 @@ mod test {
                         instance_watcher: InstanceWatcherConfig {
                             period_secs: Duration::from_secs(30),
                         },
+                        instance_serial_console: InstanceSerialConsoleConfig {
+                            period_secs: Duration::from_secs(10),
+                        },
                         service_firewall_propagation:
                             ServiceFirewallPropagationConfig {
                                 period_secs: Duration::from_secs(300),
@@ -979,6 +1010,7 @@ mod test {
             switch_port_settings_manager.period_secs = 30
             region_replacement.period_secs = 30
             instance_watcher.period_secs = 30
+            instance_serial_console.period_secs = 10
             service_firewall_propagation.period_secs = 300
             [default_region_allocation_strategy]
             type = "random"
@@ -0,0 +1,112 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::device_auth::generate_token;
+use crate::schema::api_token;
+use chrono::{DateTime, Duration, Utc};
+use db_macros::Resource;
+use nexus_types::external_api::params;
+use nexus_types::external_api::shared;
+use nexus_types::external_api::views;
+use nexus_types::identity::Resource;
+use uuid::Uuid;
+
+/// How stale a token's `time_last_used` may get before we bother updating it
+///
+/// Tokens are used on every request, so we avoid writing to the database each
+/// time.
+pub const API_TOKEN_LAST_USED_GRANULARITY_SECS: i64 = 60;
+
+/// A long-lived, user-managed API token.
+///
+/// The scope columns (`read_only` and `project_id`) restrict what the token
+/// may be used for on top of whatever roles its owner holds.
+// TODO-security: wrap token in an opaque struct to avoid accidental leaks.
+#[derive(Clone, Debug, Insertable, Queryable, Resource, Selectable)]
+#[diesel(table_name = api_token)]
+pub struct ApiToken {
+    #[diesel(embed)]
+    identity: ApiTokenIdentity,
+
+    pub silo_user_id: Uuid,
+    pub token: String,
+    pub time_expires: Option<DateTime<Utc>>,
+    pub time_last_used: Option<DateTime<Utc>>,
+    pub read_only: bool,
+    pub project_id: Option<Uuid>,
+}
+
+impl ApiToken {
+    /// Create a new token for `silo_user_id`.
+    ///
+    /// `project_id` is the resolved project from any `Project` scope in
+    /// `params`; the caller is responsible for looking it up.
+    pub fn new(
+        silo_user_id: Uuid,
+        params: params::ApiTokenCreate,
+        project_id: Option<Uuid>,
+    ) -> Self {
+        let read_only = params
+            .scopes
+            .iter()
+            .any(|s| matches!(s, shared::ApiTokenScope::ReadOnly));
+        Self {
+            identity: ApiTokenIdentity::new(Uuid::new_v4(), params.identity),
+            silo_user_id,
+            token: generate_token(),
+            time_expires: params.time_expires,
+            time_last_used: None,
+            read_only,
+            project_id,
+        }
+    }
+
+    /// Returns whether the token is past its expiration time
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.time_expires.map_or(false, |t| t <= now)
+    }
+
+    /// Returns whether `time_last_used` is out of date enough, as of `now`,
+    /// that it should be updated
+    pub fn last_used_is_stale(&self, now: DateTime<Utc>) -> bool {
+        let stale =
+            now - Duration::seconds(API_TOKEN_LAST_USED_GRANULARITY_SECS);
+        !matches!(self.time_last_used, Some(t) if t >= stale)
+    }
+
+    fn scopes(&self) -> Vec<shared::ApiTokenScope> {
+        let mut scopes = Vec::new();
+        if self.read_only {
+            scopes.push(shared::ApiTokenScope::ReadOnly);
+        }
+        if let Some(project_id) = self.project_id {
+            scopes.push(shared::ApiTokenScope::Project {
+                project: project_id.into(),
+            });
+        }
+        scopes
+    }
+}
+
+impl From<ApiToken> for views::ApiToken {
+    fn from(token: ApiToken) -> Self {
+        Self {
+            identity: token.identity(),
+            time_expires: token.time_expires,
+            time_last_used: token.time_last_used,
+            scopes: token.scopes(),
+        }
+    }
+}
+
+impl From<ApiToken> for views::ApiTokenValue {
+    fn from(token: ApiToken) -> Self {
+        Self {
+            identity: token.identity(),
+            time_expires: token.time_expires,
+            scopes: token.scopes(),
+            bearer_token: format!("oxide-token-{}", token.token),
+        }
+    }
+}
@@ -66,6 +66,10 @@ impl Dataset {
         }
     }
 
+    pub fn time_deleted(&self) -> Option<DateTime<Utc>> {
+        self.time_deleted
+    }
+
     pub fn address(&self) -> SocketAddrV6 {
         self.address_with_port(self.port.into())
     }
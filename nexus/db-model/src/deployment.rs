@@ -5,18 +5,19 @@
 //! Types for representing the deployed software and configuration in the
 //! database
 
-use crate::inventory::ZoneType;
+use crate::inventory::{HwRotSlot, SpType, ZoneType};
 use crate::omicron_zone_config::{OmicronZone, OmicronZoneNic};
 use crate::schema::{
     blueprint, bp_omicron_physical_disk, bp_omicron_zone, bp_omicron_zone_nic,
-    bp_sled_omicron_physical_disks, bp_sled_omicron_zones, bp_sled_state,
-    bp_target,
+    bp_pending_mgs_update, bp_sled_omicron_physical_disks,
+    bp_sled_omicron_zones, bp_sled_state, bp_target,
 };
 use crate::typed_uuid::DbTypedUuid;
 use crate::{
-    impl_enum_type, ipv6, Generation, MacAddr, Name, SledState, SqlU16, SqlU32,
-    SqlU8,
+    impl_enum_type, ipv6, ArtifactHash, Generation, MacAddr, Name,
+    SemverVersion, SledState, SqlU16, SqlU32, SqlU8,
 };
+use anyhow::anyhow;
 use chrono::{DateTime, Utc};
 use ipnetwork::IpNetwork;
 use nexus_types::deployment::BlueprintPhysicalDiskConfig;
@@ -25,6 +26,9 @@ use nexus_types::deployment::BlueprintTarget;
 use nexus_types::deployment::BlueprintZoneConfig;
 use nexus_types::deployment::BlueprintZoneDisposition;
 use nexus_types::deployment::BlueprintZonesConfig;
+use nexus_types::deployment::PendingMgsUpdate;
+use nexus_types::deployment::PendingMgsUpdateDetails;
+use nexus_types::inventory::BaseboardId;
 use omicron_common::api::internal::shared::NetworkInterface;
 use omicron_common::disk::DiskIdentity;
 use omicron_uuid_kinds::GenericUuid;
@@ -420,6 +424,135 @@ impl BpOmicronZoneNic {
     }
 }
 
+impl_enum_type!(
+    #[derive(Clone, SqlType, Debug, QueryId)]
+    #[diesel(postgres_type(name = "mgs_update_component", schema = "public"))]
+    pub struct MgsUpdateComponentEnum;
+
+    #[derive(Clone, Copy, Debug, AsExpression, FromSqlRow, PartialEq)]
+    #[diesel(sql_type = MgsUpdateComponentEnum)]
+    pub enum MgsUpdateComponent;
+
+    // Enum values
+    Sp => b"sp"
+    Rot => b"rot"
+    HostPhase1 => b"host_phase_1"
+);
+
+/// See [`nexus_types::deployment::PendingMgsUpdate`].
+#[derive(Queryable, Clone, Debug, Selectable, Insertable)]
+#[diesel(table_name = bp_pending_mgs_update)]
+pub struct BpPendingMgsUpdate {
+    pub blueprint_id: Uuid,
+    pub part_number: String,
+    pub serial_number: String,
+    pub sp_type: SpType,
+    pub sp_slot: SqlU16,
+    pub component: MgsUpdateComponent,
+    pub artifact_sha256: ArtifactHash,
+    pub artifact_version: SemverVersion,
+    pub expected_active_version: Option<String>,
+    pub expected_inactive_version: Option<String>,
+    pub expected_active_rot_slot: Option<HwRotSlot>,
+    pub expected_host_phase_1_sha256: Option<ArtifactHash>,
+}
+
+impl BpPendingMgsUpdate {
+    pub fn new(blueprint_id: Uuid, update: &PendingMgsUpdate) -> Self {
+        let mut row = Self {
+            blueprint_id,
+            part_number: update.baseboard_id.part_number.clone(),
+            serial_number: update.baseboard_id.serial_number.clone(),
+            sp_type: update.sp_type.into(),
+            sp_slot: update.slot_id.into(),
+            component: MgsUpdateComponent::Sp,
+            artifact_sha256: update.artifact_hash.into(),
+            artifact_version: update.artifact_version.clone().into(),
+            expected_active_version: None,
+            expected_inactive_version: None,
+            expected_active_rot_slot: None,
+            expected_host_phase_1_sha256: None,
+        };
+        match &update.details {
+            PendingMgsUpdateDetails::Sp {
+                expected_active_version,
+                expected_inactive_version,
+            } => {
+                row.expected_active_version =
+                    Some(expected_active_version.clone());
+                row.expected_inactive_version =
+                    expected_inactive_version.clone();
+            }
+            PendingMgsUpdateDetails::Rot {
+                expected_active_slot,
+                expected_active_version,
+                expected_inactive_version,
+            } => {
+                row.component = MgsUpdateComponent::Rot;
+                row.expected_active_rot_slot =
+                    Some(expected_active_slot.clone().into());
+                row.expected_active_version =
+                    Some(expected_active_version.clone());
+                row.expected_inactive_version =
+                    expected_inactive_version.clone();
+            }
+            PendingMgsUpdateDetails::HostPhase1 { expected_artifact } => {
+                row.component = MgsUpdateComponent::HostPhase1;
+                row.expected_host_phase_1_sha256 =
+                    expected_artifact.map(ArtifactHash::from);
+            }
+        }
+        row
+    }
+
+    pub fn into_pending_mgs_update(
+        self,
+    ) -> Result<PendingMgsUpdate, anyhow::Error> {
+        let details = match self.component {
+            MgsUpdateComponent::Sp => PendingMgsUpdateDetails::Sp {
+                expected_active_version: self
+                    .expected_active_version
+                    .ok_or_else(|| {
+                        anyhow!("SP update is missing expected active version")
+                    })?,
+                expected_inactive_version: self.expected_inactive_version,
+            },
+            MgsUpdateComponent::Rot => PendingMgsUpdateDetails::Rot {
+                expected_active_slot: self
+                    .expected_active_rot_slot
+                    .ok_or_else(|| {
+                        anyhow!("RoT update is missing expected active slot")
+                    })?
+                    .into(),
+                expected_active_version: self
+                    .expected_active_version
+                    .ok_or_else(|| {
+                        anyhow!("RoT update is missing expected active version")
+                    })?,
+                expected_inactive_version: self.expected_inactive_version,
+            },
+            MgsUpdateComponent::HostPhase1 => {
+                PendingMgsUpdateDetails::HostPhase1 {
+                    expected_artifact: self
+                        .expected_host_phase_1_sha256
+                        .map(|h| h.0),
+                }
+            }
+        };
+        Ok(PendingMgsUpdate {
+            baseboard_id: BaseboardId {
+                part_number: self.part_number,
+                serial_number: self.serial_number,
+            },
+            sp_type: self.sp_type.into(),
+            slot_id: *self.sp_slot,
+            details,
+            artifact_hash: self.artifact_sha256.0,
+            artifact_version: self.artifact_version.0,
+        })
+    }
+}
+
 mod diesel_util {
     use crate::{
         schema::bp_omicron_zone::disposition, to_db_bp_zone_disposition,
@@ -3,9 +3,14 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use crate::impl_enum_type;
-use crate::schema::{identity_provider, saml_identity_provider};
+use crate::schema::{
+    identity_provider, oidc_identity_provider, oidc_login_request,
+    saml_identity_provider,
+};
+use chrono::{DateTime, Duration, Utc};
 use db_macros::Resource;
 use nexus_types::identity::Resource;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
 
 use nexus_types::external_api::views;
 use serde::{Deserialize, Serialize};
@@ -22,12 +27,14 @@ impl_enum_type!(
 
     // Enum values
     Saml => b"saml"
+    Oidc => b"oidc"
 );
 
 impl From<IdentityProviderType> for views::IdentityProviderType {
     fn from(idp_type: IdentityProviderType) -> Self {
         match idp_type {
             IdentityProviderType::Saml => views::IdentityProviderType::Saml,
+            IdentityProviderType::Oidc => views::IdentityProviderType::Oidc,
         }
     }
 }
@@ -101,3 +108,112 @@ impl From<SamlIdentityProvider> for views::SamlIdentityProvider {
         }
     }
 }
+
+#[derive(Queryable, Insertable, Clone, Debug, Selectable, Resource)]
+#[diesel(table_name = oidc_identity_provider)]
+pub struct OidcIdentityProvider {
+    #[diesel(embed)]
+    pub identity: OidcIdentityProviderIdentity,
+
+    pub silo_id: Uuid,
+
+    /// the provider's issuer identifier, as it appears in the "iss" claim
+    pub issuer: String,
+
+    /// client id assigned to us by the provider
+    pub client_id: String,
+
+    /// client secret assigned to us by the provider
+    pub client_secret: String,
+
+    /// endpoints from the provider's discovery document
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+
+    /// our endpoint to which the provider redirects users after they log in
+    pub redirect_uri: String,
+
+    /// space-separated list of scopes to request
+    pub scopes: String,
+
+    /// name of the ID token claim used as the user's external id
+    pub user_claim: String,
+
+    /// if set, the ID token claim with this name will be considered to denote
+    /// a user's group membership, where the values will be the group names.
+    pub group_claim: Option<String>,
+}
+
+impl From<OidcIdentityProvider> for views::OidcIdentityProvider {
+    fn from(oidc_idp: OidcIdentityProvider) -> Self {
+        Self {
+            identity: oidc_idp.identity(),
+            issuer: oidc_idp.issuer,
+            client_id: oidc_idp.client_id,
+            authorization_endpoint: oidc_idp.authorization_endpoint,
+            token_endpoint: oidc_idp.token_endpoint,
+            jwks_uri: oidc_idp.jwks_uri,
+            redirect_uri: oidc_idp.redirect_uri,
+            scopes: oidc_idp
+                .scopes
+                .split_whitespace()
+                .map(String::from)
+                .collect(),
+            user_claim: oidc_idp.user_claim,
+            group_claim: oidc_idp.group_claim,
+        }
+    }
+}
+
+/// How long a user has to complete an OIDC login at the identity provider
+const OIDC_LOGIN_TIMEOUT_SECONDS: i64 = 600;
+
+/// Maximum length of the relay state saved with an OIDC login (the size of the
+/// `relay_state` column)
+pub const OIDC_RELAY_STATE_MAX_BYTES: usize = 2048;
+
+/// Maximum number of unexpired OIDC logins in progress for one identity
+/// provider
+///
+/// Logins are started by unauthenticated users, so this bounds how much an
+/// attacker can grow the table.
+pub const OIDC_MAX_PENDING_LOGINS_PER_PROVIDER: i64 = 10_000;
+
+/// Record of an OIDC login that's in progress at the identity provider
+///
+/// All of the random values here are 32 random bytes, hex-encoded.  That's
+/// well within the length limits that RFC 7636 places on the PKCE code
+/// verifier.
+#[derive(Clone, Debug, Insertable, Queryable, Selectable)]
+#[diesel(table_name = oidc_login_request)]
+pub struct OidcLoginRequest {
+    pub state: String,
+    pub provider_id: Uuid,
+    pub code_verifier: String,
+    pub nonce: String,
+    pub relay_state: Option<String>,
+    pub time_created: DateTime<Utc>,
+    pub time_expires: DateTime<Utc>,
+}
+
+impl OidcLoginRequest {
+    pub fn new(provider_id: Uuid, relay_state: Option<String>) -> Self {
+        let now = Utc::now();
+        Self {
+            state: generate_oidc_secret(),
+            provider_id,
+            code_verifier: generate_oidc_secret(),
+            nonce: generate_oidc_secret(),
+            relay_state,
+            time_created: now,
+            time_expires: now + Duration::seconds(OIDC_LOGIN_TIMEOUT_SECONDS),
+        }
+    }
+}
+
+fn generate_oidc_secret() -> String {
+    let mut bytes = [0u8; 32];
+    StdRng::from_entropy().fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
@@ -0,0 +1,40 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Defines database model types for the InstanceSerialConsoleLog table.
+//!
+//! Each row is an immutable chunk of the serial console output Nexus has
+//! captured for an instance, across all of the VMMs that have incarnated it.
+//! Offsets count all of the output the instance has produced since it was
+//! created.
+
+use crate::schema::instance_serial_console_log;
+use chrono::{DateTime, Utc};
+use uuid::Uuid;
+
+/// A chunk of serial console output captured for an instance.
+#[derive(Clone, Debug, Queryable, Insertable, Selectable)]
+#[diesel(table_name = instance_serial_console_log)]
+pub struct InstanceSerialConsoleChunk {
+    pub instance_id: Uuid,
+
+    /// Offset of the first byte of this chunk.
+    pub start_offset: i64,
+
+    /// Offset just past the last byte of this chunk.
+    pub end_offset: i64,
+
+    pub time_created: DateTime<Utc>,
+
+    /// The output in this chunk, or `None` if it was lost before Nexus could
+    /// capture it.
+    pub data: Option<Vec<u8>>,
+
+    /// The VMM that produced this output.
+    pub vmm_id: Uuid,
+
+    /// Offset in the output of `vmm_id` just past the last byte of this
+    /// chunk.
+    pub vmm_offset: i64,
+}
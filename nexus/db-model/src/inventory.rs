@@ -9,8 +9,8 @@ use crate::schema::{
     hw_baseboard_id, inv_caboose, inv_collection, inv_collection_error,
     inv_omicron_zone, inv_omicron_zone_nic, inv_physical_disk,
     inv_root_of_trust, inv_root_of_trust_page, inv_service_processor,
-    inv_sled_agent, inv_sled_omicron_zones, inv_zpool, sw_caboose,
-    sw_root_of_trust_page,
+    inv_sled_agent, inv_sled_omicron_zones, inv_sp_sensor, inv_switch,
+    inv_switch_port, inv_zpool, sw_caboose, sw_root_of_trust_page,
 };
 use crate::typed_uuid::DbTypedUuid;
 use crate::PhysicalDiskKind;
@@ -29,7 +29,8 @@ use diesel::serialize::ToSql;
 use diesel::{serialize, sql_types};
 use ipnetwork::IpNetwork;
 use nexus_types::inventory::{
-    BaseboardId, Caboose, Collection, PowerState, RotPage, RotSlot,
+    BaseboardId, Caboose, Collection, PowerState, RotPage, RotSlot, SensorKind,
+    SensorReading, SwitchLocation,
 };
 use omicron_common::api::internal::shared::NetworkInterface;
 use omicron_uuid_kinds::CollectionKind;
@@ -581,6 +582,111 @@ pub struct InvRotPage {
     pub sw_root_of_trust_page_id: Uuid,
 }
 
+// See [`nexus_types::inventory::SensorKind`].
+impl_enum_type!(
+    #[derive(SqlType, Debug, QueryId)]
+    #[diesel(postgres_type(name = "sp_sensor_kind", schema = "public"))]
+    pub struct SpSensorKindEnum;
+
+    #[derive(
+        Copy,
+        Clone,
+        Debug,
+        AsExpression,
+        FromSqlRow,
+        PartialOrd,
+        Ord,
+        PartialEq,
+        Eq
+    )]
+    #[diesel(sql_type = SpSensorKindEnum)]
+    pub enum SpSensorKind;
+
+    // Enum values
+    Temperature => b"temperature"
+    Power => b"power"
+    Current => b"current"
+    Voltage => b"voltage"
+    InputCurrent => b"input_current"
+    InputVoltage => b"input_voltage"
+    Speed => b"speed"
+);
+
+impl From<SensorKind> for SpSensorKind {
+    fn from(value: SensorKind) -> Self {
+        match value {
+            SensorKind::Temperature => SpSensorKind::Temperature,
+            SensorKind::Power => SpSensorKind::Power,
+            SensorKind::Current => SpSensorKind::Current,
+            SensorKind::Voltage => SpSensorKind::Voltage,
+            SensorKind::InputCurrent => SpSensorKind::InputCurrent,
+            SensorKind::InputVoltage => SpSensorKind::InputVoltage,
+            SensorKind::Speed => SpSensorKind::Speed,
+        }
+    }
+}
+
+impl From<SpSensorKind> for SensorKind {
+    fn from(value: SpSensorKind) -> Self {
+        match value {
+            SpSensorKind::Temperature => SensorKind::Temperature,
+            SpSensorKind::Power => SensorKind::Power,
+            SpSensorKind::Current => SensorKind::Current,
+            SpSensorKind::Voltage => SensorKind::Voltage,
+            SpSensorKind::InputCurrent => SensorKind::InputCurrent,
+            SpSensorKind::InputVoltage => SensorKind::InputVoltage,
+            SpSensorKind::Speed => SensorKind::Speed,
+        }
+    }
+}
+
+/// See [`nexus_types::inventory::SensorReading`].
+#[derive(Queryable, Clone, Debug, Selectable)]
+#[diesel(table_name = inv_sp_sensor)]
+pub struct InvSpSensor {
+    pub inv_collection_id: Uuid,
+    pub hw_baseboard_id: Uuid,
+    pub idx: i32,
+    pub time_collected: DateTime<Utc>,
+    pub source: String,
+
+    pub component: String,
+    pub device: String,
+    pub name: String,
+    pub kind: SpSensorKind,
+    pub value: Option<f32>,
+    pub error: Option<String>,
+}
+
+impl TryFrom<InvSpSensor> for SensorReading {
+    type Error = anyhow::Error;
+
+    fn try_from(row: InvSpSensor) -> Result<Self, Self::Error> {
+        let value = match (row.value, row.error) {
+            (Some(value), None) => Ok(value),
+            (None, Some(error)) => Err(error),
+            _ => {
+                return Err(anyhow!(
+                    "sensor {:?} of component {:?} (baseboard {}): expected \
+                     exactly one of value or error",
+                    row.name,
+                    row.component,
+                    row.hw_baseboard_id,
+                ));
+            }
+        };
+        Ok(SensorReading {
+            time_collected: row.time_collected,
+            source: row.source,
+            component: row.component,
+            device: row.device,
+            name: row.name,
+            kind: row.kind.into(),
+            value,
+        })
+    }
+}
+
 // See [`nexus_types::inventory::SledRole`].
 impl_enum_type!(
     #[derive(SqlType, Debug, QueryId)]
@@ -1008,3 +1114,167 @@ impl InvOmicronZoneNic {
         zone_nic.into_network_interface_for_zone(zone_id)
     }
 }
+
+// See [`nexus_types::inventory::PortLinkState`].
+impl_enum_type!(
+    #[derive(SqlType, Debug, QueryId)]
+    #[diesel(postgres_type(name = "port_link_state", schema = "public"))]
+    pub struct PortLinkStateEnum;
+
+    #[derive(Copy, Clone, Debug, AsExpression, FromSqlRow, PartialEq, Eq)]
+    #[diesel(sql_type = PortLinkStateEnum)]
+    pub enum PortLinkState;
+
+    // Enum values
+    Up => b"up"
+    Down => b"down"
+    Unknown => b"unknown"
+    Faulted => b"faulted"
+);
+
+impl From<nexus_types::inventory::PortLinkState> for PortLinkState {
+    fn from(value: nexus_types::inventory::PortLinkState) -> Self {
+        match value {
+            nexus_types::inventory::PortLinkState::Up => PortLinkState::Up,
+            nexus_types::inventory::PortLinkState::Down => PortLinkState::Down,
+            nexus_types::inventory::PortLinkState::Unknown => {
+                PortLinkState::Unknown
+            }
+            nexus_types::inventory::PortLinkState::Faulted => {
+                PortLinkState::Faulted
+            }
+        }
+    }
+}
+
+impl From<PortLinkState> for nexus_types::inventory::PortLinkState {
+    fn from(value: PortLinkState) -> Self {
+        match value {
+            PortLinkState::Up => nexus_types::inventory::PortLinkState::Up,
+            PortLinkState::Down => nexus_types::inventory::PortLinkState::Down,
+            PortLinkState::Unknown => {
+                nexus_types::inventory::PortLinkState::Unknown
+            }
+            PortLinkState::Faulted => {
+                nexus_types::inventory::PortLinkState::Faulted
+            }
+        }
+    }
+}
+
+/// See [`nexus_types::inventory::Switch`].
+#[derive(Queryable, Clone, Debug, Selectable, Insertable)]
+#[diesel(table_name = inv_switch)]
+pub struct InvSwitch {
+    pub inv_collection_id: DbTypedUuid<CollectionKind>,
+    pub time_collected: DateTime<Utc>,
+    pub source: String,
+    pub switch_location: String,
+    pub dpd_version: String,
+}
+
+impl InvSwitch {
+    pub fn new(
+        inv_collection_id: CollectionUuid,
+        switch: &nexus_types::inventory::Switch,
+    ) -> InvSwitch {
+        InvSwitch {
+            inv_collection_id: inv_collection_id.into(),
+            time_collected: switch.time_collected,
+            source: switch.source.clone(),
+            switch_location: switch.switch_location.to_string(),
+            dpd_version: switch.dpd_version.clone(),
+        }
+    }
+
+    /// Returns the switch described by this row, without any of its ports
+    pub fn into_uninit_switch(
+        self,
+    ) -> Result<nexus_types::inventory::Switch, anyhow::Error> {
+        let switch_location =
+            self.switch_location.parse::<SwitchLocation>().map_err(|e| {
+                anyhow!(
+                    "unrecognized switch location {:?}: {}",
+                    self.switch_location,
+                    e
+                )
+            })?;
+        Ok(nexus_types::inventory::Switch {
+            time_collected: self.time_collected,
+            source: self.source,
+            switch_location,
+            dpd_version: self.dpd_version,
+            ports: std::collections::BTreeMap::new(),
+        })
+    }
+}
+
+/// See [`nexus_types::inventory::SwitchPortState`].
+#[derive(Queryable, Clone, Debug, Selectable, Insertable)]
+#[diesel(table_name = inv_switch_port)]
+pub struct InvSwitchPort {
+    pub inv_collection_id: DbTypedUuid<CollectionKind>,
+    pub switch_location: String,
+    pub port_id: String,
+    pub link_state: Option<PortLinkState>,
+    pub transceiver_vendor: Option<String>,
+    pub transceiver_part: Option<String>,
+    pub transceiver_revision: Option<String>,
+    pub transceiver_serial: Option<String>,
+}
+
+impl InvSwitchPort {
+    pub fn new(
+        inv_collection_id: CollectionUuid,
+        switch_location: SwitchLocation,
+        port_id: &str,
+        port: &nexus_types::inventory::SwitchPortState,
+    ) -> InvSwitchPort {
+        let transceiver = port.transceiver.as_ref();
+        InvSwitchPort {
+            inv_collection_id: inv_collection_id.into(),
+            switch_location: switch_location.to_string(),
+            port_id: port_id.to_string(),
+            link_state: port.link_state.map(PortLinkState::from),
+            transceiver_vendor: transceiver.map(|t| t.vendor.clone()),
+            transceiver_part: transceiver.map(|t| t.part.clone()),
+            transceiver_revision: transceiver.map(|t| t.revision.clone()),
+            transceiver_serial: transceiver.map(|t| t.serial.clone()),
+        }
+    }
+}
+
+impl TryFrom<InvSwitchPort> for nexus_types::inventory::SwitchPortState {
+    type Error = anyhow::Error;
+
+    fn try_from(value: InvSwitchPort) -> Result<Self, Self::Error> {
+        let transceiver = match (
+            value.transceiver_vendor,
+            value.transceiver_part,
+            value.transceiver_revision,
+            value.transceiver_serial,
+        ) {
+            (None, None, None, None) => None,
+            (Some(vendor), Some(part), Some(revision), Some(serial)) => {
+                Some(nexus_types::inventory::Transceiver {
+                    vendor,
+                    part,
+                    revision,
+                    serial,
+                })
+            }
+            _ => {
+                return Err(anyhow!(
+                    "switch {} port {:?}: transceiver columns must be all \
+                     present or all absent",
+                    value.switch_location,
+                    value.port_id,
+                ))
+            }
+        };
+        Ok(nexus_types::inventory::SwitchPortState {
+            link_state: value.link_state.map(Into::into),
+            transceiver,
+        })
+    }
+}
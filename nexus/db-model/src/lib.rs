@@ -11,6 +11,7 @@ extern crate newtype_derive;
 
 mod address_lot;
 mod allow_list;
+mod api_token;
 mod bfd;
 mod bgp;
 mod block_size;
@@ -34,6 +35,7 @@ mod identity_provider;
 mod image;
 mod instance;
 mod instance_cpu_count;
+mod instance_serial_console_log;
 mod instance_state;
 mod inventory;
 mod ip_pool;
@@ -71,6 +73,8 @@ mod role_builtin;
 pub mod saga_types;
 pub mod schema;
 mod schema_versions;
+mod scim_client_bearer_token;
+mod service_account;
 mod service_kind;
 mod silo;
 mod silo_group;
@@ -86,6 +90,7 @@ mod sled_underlay_subnet_allocation;
 mod snapshot;
 mod ssh_key;
 mod switch;
+mod target_release;
 mod tuf_repo;
 mod typed_uuid;
 mod unsigned;
@@ -116,6 +121,7 @@ pub use self::macaddr::*;
 pub use self::unsigned::*;
 pub use address_lot::*;
 pub use allow_list::*;
+pub use api_token::*;
 pub use bfd::*;
 pub use bgp::*;
 pub use block_size::*;
@@ -140,6 +146,7 @@ pub use identity_provider::*;
 pub use image::*;
 pub use instance::*;
 pub use instance_cpu_count::*;
+pub use instance_serial_console_log::*;
 pub use instance_state::*;
 pub use inventory::*;
 pub use ip_pool::*;
@@ -165,7 +172,9 @@ pub use region_snapshot::*;
 pub use role_assignment::*;
 pub use role_builtin::*;
 pub use schema_versions::*;
+pub use scim_client_bearer_token::*;
 pub use semver_version::*;
+pub use service_account::*;
 pub use service_kind::*;
 pub use silo::*;
 pub use silo_group::*;
@@ -183,6 +192,7 @@ pub use ssh_key::*;
 pub use switch::*;
 pub use switch_interface::*;
 pub use switch_port::*;
+pub use target_release::*;
 pub use tuf_repo::*;
 pub use typed_uuid::to_db_typed_uuid;
 pub use upstairs_repair::*;
@@ -7,6 +7,7 @@
 //! Refer to <nexus/src/db/queries/virtual_provisioning_collection_update.rs>
 //! for the construction of this query.
 
+use crate::schema::project_quotas;
 use crate::schema::silo;
 use crate::schema::silo_quotas;
 use crate::schema::virtual_provisioning_collection;
@@ -38,6 +39,24 @@ table! {
     }
 }
 
+table! {
+    project_limits (project_id) {
+        project_id -> Uuid,
+        cpus -> Nullable<Int8>,
+        memory -> Nullable<Int8>,
+        storage -> Nullable<Int8>,
+    }
+}
+
+table! {
+    project_provisioned {
+        id -> Uuid,
+        virtual_disk_bytes_provisioned -> Int8,
+        cpus_provisioned -> Int8,
+        ram_provisioned -> Int8,
+    }
+}
+
 table! {
     silo_provisioned {
         id -> Uuid,
@@ -52,9 +71,12 @@ diesel::allow_tables_to_appear_in_same_query!(silo, parent_silo,);
 diesel::allow_tables_to_appear_in_same_query!(
     virtual_provisioning_collection,
     silo_quotas,
+    project_quotas,
     parent_silo,
     all_collections,
     do_update,
     quotas,
-    silo_provisioned
+    silo_provisioned,
+    project_limits,
+    project_provisioned
 );
@@ -1,5 +1,5 @@
 use super::ByteCount;
-use crate::schema::silo_quotas;
+use crate::schema::{project_quotas, silo_quotas};
 use chrono::{DateTime, Utc};
 use nexus_types::external_api::{params, views};
 use serde::{Deserialize, Serialize};
@@ -96,3 +96,57 @@ impl From<params::SiloQuotasUpdate> for SiloQuotasUpdate {
         }
     }
 }
+
+/// Optional resource limits on a single project, on top of its silo's quota
+#[derive(
+    Queryable, Insertable, Debug, Clone, Selectable, Serialize, Deserialize,
+)]
+#[diesel(table_name = project_quotas)]
+pub struct ProjectQuotas {
+    pub project_id: Uuid,
+    pub time_created: DateTime<Utc>,
+    pub time_modified: DateTime<Utc>,
+
+    /// The number of CPUs that this project is allowed to use, if limited
+    pub cpus: Option<i64>,
+
+    /// The amount of memory (in bytes) that this project is allowed to use,
+    /// if limited
+    #[diesel(column_name = memory_bytes)]
+    pub memory: Option<ByteCount>,
+
+    /// The amount of storage (in bytes) that this project is allowed to use,
+    /// if limited
+    #[diesel(column_name = storage_bytes)]
+    pub storage: Option<ByteCount>,
+}
+
+impl ProjectQuotas {
+    pub fn new(project_id: Uuid, params: params::ProjectQuotasUpdate) -> Self {
+        let now = Utc::now();
+        Self {
+            project_id,
+            time_created: now,
+            time_modified: now,
+            cpus: params.cpus,
+            memory: params.memory.map(|m| m.into()),
+            storage: params.storage.map(|s| s.into()),
+        }
+    }
+
+    /// Returns the limits for a project that has never had any set
+    pub fn unlimited(project_id: Uuid) -> Self {
+        Self::new(project_id, params::ProjectQuotasUpdate::default())
+    }
+}
+
+impl From<ProjectQuotas> for views::ProjectQuotas {
+    fn from(quotas: ProjectQuotas) -> Self {
+        Self {
+            project_id: quotas.project_id,
+            cpus: quotas.cpus,
+            memory: quotas.memory.map(|m| m.into()),
+            storage: quotas.storage.map(|s| s.into()),
+        }
+    }
+}
@@ -31,6 +31,7 @@ impl_enum_type!(
     UserBuiltin => b"user_builtin"
     SiloUser => b"silo_user"
     SiloGroup => b"silo_group"
+    ServiceAccount => b"service_account"
 );
 
 impl From<shared::IdentityType> for IdentityType {
@@ -38,6 +39,9 @@ impl From<shared::IdentityType> for IdentityType {
         match other {
             shared::IdentityType::SiloUser => IdentityType::SiloUser,
             shared::IdentityType::SiloGroup => IdentityType::SiloGroup,
+            shared::IdentityType::ServiceAccount => {
+                IdentityType::ServiceAccount
+            }
         }
     }
 }
@@ -52,6 +56,9 @@ impl TryFrom<IdentityType> for shared::IdentityType {
             }
             IdentityType::SiloUser => Ok(shared::IdentityType::SiloUser),
             IdentityType::SiloGroup => Ok(shared::IdentityType::SiloGroup),
+            IdentityType::ServiceAccount => {
+                Ok(shared::IdentityType::ServiceAccount)
+            }
         }
     }
 }
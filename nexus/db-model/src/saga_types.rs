@@ -12,6 +12,7 @@
 //! actually serialize them to and from SQL take care of the necessary
 //! conversions.
 
+use super::impl_enum_type;
 use super::schema::{saga, saga_node_event};
 use diesel::backend::Backend;
 use diesel::deserialize::{self, FromSql};
@@ -20,8 +21,9 @@ use diesel::serialize::{self, ToSql};
 use diesel::sql_types;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::Generation;
+use serde::{Deserialize, Serialize};
 use std::convert::TryFrom;
-use std::io::Write;
+use std::fmt;
 use std::sync::Arc;
 use uuid::Uuid;
 
@@ -139,44 +141,46 @@ where
     }
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, SqlType)]
-#[diesel(postgres_type(name = "saga_state", schema = "public"))]
-pub struct SagaCachedStateEnum;
+impl_enum_type!(
+    #[derive(Clone, SqlType, Debug, QueryId)]
+    #[diesel(postgres_type(name = "saga_state", schema = "public"))]
+    pub struct SagaStateEnum;
 
-/// Newtype wrapper around [`steno::SagaCachedState`] which implements
-/// Diesel traits.
-///
-/// This exists because Omicron cannot implement foreign traits
-/// for foreign types.
-#[derive(AsExpression, FromSqlRow, Clone, Copy, Debug, PartialEq)]
-#[diesel(sql_type = SagaCachedStateEnum)]
-pub struct SagaCachedState(pub steno::SagaCachedState);
+    /// The state of a saga as recorded in the database
+    ///
+    /// This is a superset of [`steno::SagaCachedState`]: in addition to the
+    /// states that Steno itself reports, a saga may be marked `Abandoned` by an
+    /// operator.  Abandoned sagas are never recovered or resumed.
+    #[derive(Clone, Copy, Debug, AsExpression, FromSqlRow, Serialize, Deserialize, PartialEq)]
+    #[diesel(sql_type = SagaStateEnum)]
+    pub enum SagaState;
 
-NewtypeFrom! { () pub struct SagaCachedState(steno::SagaCachedState); }
+    // Enum values
+    Running => b"running"
+    Unwinding => b"unwinding"
+    Done => b"done"
+    Abandoned => b"abandoned"
+);
 
-impl ToSql<SagaCachedStateEnum, Pg> for SagaCachedState {
-    fn to_sql<'a>(
-        &'a self,
-        out: &mut serialize::Output<'a, '_, Pg>,
-    ) -> serialize::Result {
-        use steno::SagaCachedState;
-        out.write_all(match self.0 {
-            SagaCachedState::Running => b"running",
-            SagaCachedState::Unwinding => b"unwinding",
-            SagaCachedState::Done => b"done",
-        })?;
-        Ok(serialize::IsNull::No)
+impl From<steno::SagaCachedState> for SagaState {
+    fn from(state: steno::SagaCachedState) -> Self {
+        match state {
+            steno::SagaCachedState::Running => SagaState::Running,
+            steno::SagaCachedState::Unwinding => SagaState::Unwinding,
+            steno::SagaCachedState::Done => SagaState::Done,
+        }
     }
 }
 
-impl FromSql<SagaCachedStateEnum, Pg> for SagaCachedState {
-    fn from_sql(
-        bytes: <Pg as Backend>::RawValue<'_>,
-    ) -> deserialize::Result<Self> {
-        let bytes = <Pg as Backend>::RawValue::as_bytes(&bytes);
-        let s = std::str::from_utf8(bytes)?;
-        let state = steno::SagaCachedState::try_from(s)?;
-        Ok(Self(state))
+impl fmt::Display for SagaState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            SagaState::Running => "running",
+            SagaState::Unwinding => "unwinding",
+            SagaState::Done => "done",
+            SagaState::Abandoned => "abandoned",
+        };
+        write!(f, "{}", s)
     }
 }
 
@@ -189,7 +193,7 @@ pub struct Saga {
     pub time_created: chrono::DateTime<chrono::Utc>,
     pub name: String,
     pub saga_dag: serde_json::Value,
-    pub saga_state: SagaCachedState,
+    pub saga_state: SagaState,
     pub current_sec: Option<SecId>,
     pub adopt_generation: super::Generation,
     pub adopt_time: chrono::DateTime<chrono::Utc>,
@@ -438,6 +438,18 @@ table! {
 }
 joinable!(vmm -> sled (sled_id));
 
+table! {
+    instance_serial_console_log (instance_id, start_offset) {
+        instance_id -> Uuid,
+        start_offset -> Int8,
+        end_offset -> Int8,
+        time_created -> Timestamptz,
+        data -> Nullable<Binary>,
+        vmm_id -> Uuid,
+        vmm_offset -> Int8,
+    }
+}
+
 table! {
     sled_instance (id) {
         id -> Uuid,
@@ -478,6 +490,17 @@ table! {
     }
 }
 
+table! {
+    project_quotas(project_id) {
+        project_id -> Uuid,
+        time_created -> Timestamptz,
+        time_modified -> Timestamptz,
+        cpus -> Nullable<Int8>,
+        memory_bytes -> Nullable<Int8>,
+        storage_bytes -> Nullable<Int8>,
+    }
+}
+
 table! {
     silo_utilization(silo_id) {
         silo_id -> Uuid,
@@ -692,6 +715,7 @@ table! {
 
         silo_id -> Uuid,
         external_id -> Text,
+        active -> Bool,
     }
 }
 
@@ -715,6 +739,31 @@ table! {
     }
 }
 
+table! {
+    service_account (id) {
+        id -> Uuid,
+        name -> Text,
+        description -> Text,
+        time_created -> Timestamptz,
+        time_modified -> Timestamptz,
+        time_deleted -> Nullable<Timestamptz>,
+
+        silo_id -> Uuid,
+    }
+}
+
+table! {
+    service_account_token (id) {
+        id -> Uuid,
+        time_created -> Timestamptz,
+        time_deleted -> Nullable<Timestamptz>,
+        service_account_id -> Uuid,
+        token -> Text,
+        time_expires -> Nullable<Timestamptz>,
+        time_last_used -> Nullable<Timestamptz>,
+    }
+}
+
 table! {
     silo_group_membership (silo_group_id, silo_user_id) {
         silo_group_id -> Uuid,
@@ -768,6 +817,44 @@ table! {
     }
 }
 
+table! {
+    oidc_identity_provider (id) {
+        id -> Uuid,
+        name -> Text,
+        description -> Text,
+        time_created -> Timestamptz,
+        time_modified -> Timestamptz,
+        time_deleted -> Nullable<Timestamptz>,
+
+        silo_id -> Uuid,
+
+        issuer -> Text,
+        client_id -> Text,
+        client_secret -> Text,
+
+        authorization_endpoint -> Text,
+        token_endpoint -> Text,
+        jwks_uri -> Text,
+        redirect_uri -> Text,
+
+        scopes -> Text,
+        user_claim -> Text,
+        group_claim -> Nullable<Text>,
+    }
+}
+
+table! {
+    oidc_login_request (state) {
+        state -> Text,
+        provider_id -> Uuid,
+        code_verifier -> Text,
+        nonce -> Text,
+        relay_state -> Nullable<Text>,
+        time_created -> Timestamptz,
+        time_expires -> Timestamptz,
+    }
+}
+
 table! {
     ssh_key (id) {
         id -> Uuid,
@@ -781,6 +868,23 @@ table! {
     }
 }
 
+table! {
+    api_token (id) {
+        id -> Uuid,
+        name -> Text,
+        description -> Text,
+        time_created -> Timestamptz,
+        time_modified -> Timestamptz,
+        time_deleted -> Nullable<Timestamptz>,
+        silo_user_id -> Uuid,
+        token -> Text,
+        time_expires -> Nullable<Timestamptz>,
+        time_last_used -> Nullable<Timestamptz>,
+        read_only -> Bool,
+        project_id -> Nullable<Uuid>,
+    }
+}
+
 table! {
     instance_ssh_key (instance_id, ssh_key_id) {
         instance_id -> Uuid,
@@ -818,7 +922,7 @@ table! {
         time_created -> Timestamptz,
         name -> Text,
         saga_dag -> Jsonb,
-        saga_state -> crate::saga_types::SagaCachedStateEnum,
+        saga_state -> crate::saga_types::SagaStateEnum,
         current_sec -> Nullable<Uuid>,
         adopt_generation -> Int8,
         adopt_time -> Timestamptz,
@@ -1215,6 +1319,17 @@ table! {
     }
 }
 
+table! {
+    scim_client_bearer_token (id) {
+        id -> Uuid,
+        time_created -> Timestamptz,
+        time_deleted -> Nullable<Timestamptz>,
+        time_expires -> Nullable<Timestamptz>,
+        silo_id -> Uuid,
+        bearer_token -> Text,
+    }
+}
+
 table! {
     role_builtin (resource_type, role_name) {
         resource_type -> Text,
@@ -1277,6 +1392,34 @@ allow_tables_to_appear_in_same_query!(
     tuf_artifact
 );
 joinable!(tuf_repo_artifact -> tuf_repo (tuf_repo_id));
+
+table! {
+    target_release (generation) {
+        generation -> Int8,
+        time_requested -> Timestamptz,
+        system_version -> Nullable<Text>,
+    }
+}
+
+table! {
+    host_phase_1_delivery (part_number, serial_number) {
+        part_number -> Text,
+        serial_number -> Text,
+        artifact_sha256 -> Text,
+        time_delivered -> Timestamptz,
+    }
+}
+
+table! {
+    mgs_update_delivery (part_number, serial_number) {
+        part_number -> Text,
+        serial_number -> Text,
+        id -> Uuid,
+        artifact_sha256 -> Text,
+        time_started -> Timestamptz,
+        time_delivered -> Nullable<Timestamptz>,
+    }
+}
 // Can't specify joinable for a composite primary key (tuf_repo_artifact ->
 // tuf_artifact).
 
@@ -1380,6 +1523,23 @@ table! {
     }
 }
 
+table! {
+    inv_sp_sensor (inv_collection_id, hw_baseboard_id, idx) {
+        inv_collection_id -> Uuid,
+        hw_baseboard_id -> Uuid,
+        idx -> Int4,
+        time_collected -> Timestamptz,
+        source -> Text,
+
+        component -> Text,
+        device -> Text,
+        name -> Text,
+        kind -> crate::SpSensorKindEnum,
+        value -> Nullable<Float4>,
+        error -> Nullable<Text>,
+    }
+}
+
 table! {
     inv_sled_agent (inv_collection_id, sled_id) {
         inv_collection_id -> Uuid,
@@ -1475,6 +1635,31 @@ table! {
     }
 }
 
+table! {
+    inv_switch (inv_collection_id, switch_location) {
+        inv_collection_id -> Uuid,
+        time_collected -> Timestamptz,
+        source -> Text,
+        switch_location -> Text,
+
+        dpd_version -> Text,
+    }
+}
+
+table! {
+    inv_switch_port (inv_collection_id, switch_location, port_id) {
+        inv_collection_id -> Uuid,
+        switch_location -> Text,
+        port_id -> Text,
+
+        link_state -> Nullable<crate::PortLinkStateEnum>,
+        transceiver_vendor -> Nullable<Text>,
+        transceiver_part -> Nullable<Text>,
+        transceiver_revision -> Nullable<Text>,
+        transceiver_serial -> Nullable<Text>,
+    }
+}
+
 /* blueprints */
 
 table! {
@@ -1588,6 +1773,45 @@ table! {
     }
 }
 
+table! {
+    bp_pending_mgs_update (blueprint_id, part_number, serial_number) {
+        blueprint_id -> Uuid,
+
+        part_number -> Text,
+        serial_number -> Text,
+
+        sp_type -> crate::SpTypeEnum,
+        sp_slot -> Int4,
+
+        component -> crate::MgsUpdateComponentEnum,
+
+        artifact_sha256 -> Text,
+        artifact_version -> Text,
+
+        expected_active_version -> Nullable<Text>,
+        expected_inactive_version -> Nullable<Text>,
+        expected_active_rot_slot -> Nullable<crate::HwRotSlotEnum>,
+        expected_host_phase_1_sha256 -> Nullable<Text>,
+    }
+}
+
+table! {
+    target_release_artifact (
+        target_release_generation,
+        component,
+        sp_type,
+        artifact_sha256
+    ) {
+        target_release_generation -> Int8,
+        component -> crate::MgsUpdateComponentEnum,
+        sp_type -> crate::SpTypeEnum,
+        board -> Nullable<Text>,
+        rot_slot -> Nullable<crate::HwRotSlotEnum>,
+        artifact_version -> Text,
+        artifact_sha256 -> Text,
+    }
+}
+
 table! {
     bootstore_keys (key, generation) {
         key -> Text,
@@ -1714,6 +1938,7 @@ allow_tables_to_appear_in_same_query!(
     inv_root_of_trust_page
 );
 allow_tables_to_appear_in_same_query!(hw_baseboard_id, inv_sled_agent,);
+allow_tables_to_appear_in_same_query!(hw_baseboard_id, inv_sp_sensor);
 
 allow_tables_to_appear_in_same_query!(
     bp_omicron_zone,
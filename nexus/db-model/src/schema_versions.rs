@@ -17,7 +17,7 @@ use std::collections::BTreeMap;
 ///
 /// This must be updated when you change the database schema.  Refer to
 /// schema/crdb/README.adoc in the root of this repository for details.
-pub const SCHEMA_VERSION: SemverVersion = SemverVersion::new(63, 0, 0);
+pub const SCHEMA_VERSION: SemverVersion = SemverVersion::new(74, 0, 0);
 
 /// List of all past database schema versions, in *reverse* order
 ///
@@ -29,6 +29,17 @@ static KNOWN_VERSIONS: Lazy<Vec<KnownVersion>> = Lazy::new(|| {
         // |  leaving the first copy as an example for the next person.
         // v
         // KnownVersion::new(next_int, "unique-dirname-with-the-sql-files"),
+        KnownVersion::new(74, "silo-user-active"),
+        KnownVersion::new(73, "saga-abandoned-state"),
+        KnownVersion::new(72, "instance-serial-console-log"),
+        KnownVersion::new(71, "inv-sp-sensor"),
+        KnownVersion::new(70, "inv-switch"),
+        KnownVersion::new(69, "mgs-updates"),
+        KnownVersion::new(68, "project-quotas"),
+        KnownVersion::new(67, "service-accounts"),
+        KnownVersion::new(66, "api-tokens"),
+        KnownVersion::new(65, "oidc-identity-provider"),
+        KnownVersion::new(64, "silo-scim-provisioning"),
         KnownVersion::new(63, "remove-producer-base-route-column"),
         KnownVersion::new(62, "allocate-subnet-decommissioned-sleds"),
         KnownVersion::new(61, "blueprint-add-sled-state"),
@@ -0,0 +1,80 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Bearer tokens used by identity providers to provision Silo users and
+//! groups via SCIM 2.0.
+
+use crate::schema::scim_client_bearer_token;
+use chrono::{DateTime, Utc};
+use nexus_types::external_api::views;
+use rand::{rngs::StdRng, RngCore, SeedableRng};
+use uuid::Uuid;
+
+/// Bearer tokens are not meant to be human-readable, so we use 20 random
+/// bytes (160 bits), hex-encoded.
+const TOKEN_LENGTH: usize = 20;
+
+fn generate_token() -> String {
+    let mut bytes: [u8; TOKEN_LENGTH] = [0; TOKEN_LENGTH];
+    let mut rng = StdRng::from_entropy();
+    rng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// A bearer token that grants an identity provider permission to manage the
+/// users and groups of exactly one Silo.
+// TODO-security: wrap token in an opaque struct to avoid accidental leaks.
+#[derive(Clone, Debug, Insertable, Queryable, Selectable)]
+#[diesel(table_name = scim_client_bearer_token)]
+pub struct ScimClientBearerToken {
+    pub id: Uuid,
+    pub time_created: DateTime<Utc>,
+    pub time_deleted: Option<DateTime<Utc>>,
+    pub time_expires: Option<DateTime<Utc>>,
+    pub silo_id: Uuid,
+    pub bearer_token: String,
+}
+
+impl ScimClientBearerToken {
+    pub fn new(silo_id: Uuid, time_expires: Option<DateTime<Utc>>) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            time_created: Utc::now(),
+            time_deleted: None,
+            time_expires,
+            silo_id,
+            bearer_token: generate_token(),
+        }
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Returns whether this token has expired as of `now`
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.time_expires.map(|t| t <= now).unwrap_or(false)
+    }
+}
+
+impl From<ScimClientBearerToken> for views::ScimClientBearerToken {
+    fn from(t: ScimClientBearerToken) -> Self {
+        Self {
+            id: t.id,
+            time_created: t.time_created,
+            time_expires: t.time_expires,
+        }
+    }
+}
+
+impl From<ScimClientBearerToken> for views::ScimClientBearerTokenValue {
+    fn from(t: ScimClientBearerToken) -> Self {
+        Self {
+            id: t.id,
+            time_created: t.time_created,
+            time_expires: t.time_expires,
+            bearer_token: format!("oxide-scim-{}", t.bearer_token),
+        }
+    }
+}
@@ -0,0 +1,108 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Service accounts: non-human identities within a Silo, and the bearer
+//! tokens they authenticate with.
+
+use crate::device_auth::generate_token;
+use crate::schema::{service_account, service_account_token};
+use chrono::{DateTime, Utc};
+use db_macros::Resource;
+use nexus_types::external_api::params;
+use nexus_types::external_api::views;
+use nexus_types::identity::Resource;
+use uuid::Uuid;
+
+/// Describes a service account within the database.
+#[derive(Clone, Debug, Insertable, Queryable, Resource, Selectable)]
+#[diesel(table_name = service_account)]
+pub struct ServiceAccount {
+    #[diesel(embed)]
+    identity: ServiceAccountIdentity,
+
+    pub silo_id: Uuid,
+}
+
+impl ServiceAccount {
+    pub fn new(silo_id: Uuid, params: params::ServiceAccountCreate) -> Self {
+        Self {
+            identity: ServiceAccountIdentity::new(
+                Uuid::new_v4(),
+                params.identity,
+            ),
+            silo_id,
+        }
+    }
+}
+
+impl From<ServiceAccount> for views::ServiceAccount {
+    fn from(service_account: ServiceAccount) -> Self {
+        Self {
+            identity: service_account.identity(),
+            silo_id: service_account.silo_id,
+        }
+    }
+}
+
+/// A bearer token that authenticates as a service account.
+// TODO-security: wrap token in an opaque struct to avoid accidental leaks.
+#[derive(Clone, Debug, Insertable, Queryable, Selectable)]
+#[diesel(table_name = service_account_token)]
+pub struct ServiceAccountToken {
+    pub id: Uuid,
+    pub time_created: DateTime<Utc>,
+    pub time_deleted: Option<DateTime<Utc>>,
+    pub service_account_id: Uuid,
+    pub token: String,
+    pub time_expires: Option<DateTime<Utc>>,
+    pub time_last_used: Option<DateTime<Utc>>,
+}
+
+impl ServiceAccountToken {
+    pub fn new(
+        service_account_id: Uuid,
+        time_expires: Option<DateTime<Utc>>,
+    ) -> Self {
+        Self {
+            id: Uuid::new_v4(),
+            time_created: Utc::now(),
+            time_deleted: None,
+            service_account_id,
+            token: generate_token(),
+            time_expires,
+            time_last_used: None,
+        }
+    }
+
+    pub fn id(&self) -> Uuid {
+        self.id
+    }
+
+    /// Returns whether this token has expired as of `now`
+    pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.time_expires.map_or(false, |t| t <= now)
+    }
+}
+
+impl From<ServiceAccountToken> for views::ServiceAccountToken {
+    fn from(t: ServiceAccountToken) -> Self {
+        Self {
+            id: t.id,
+            time_created: t.time_created,
+            time_expires: t.time_expires,
+            time_last_used: t.time_last_used,
+        }
+    }
+}
+
+impl From<ServiceAccountToken> for views::ServiceAccountTokenValue {
+    fn from(t: ServiceAccountToken) -> Self {
+        Self {
+            id: t.id,
+            time_created: t.time_created,
+            time_expires: t.time_expires,
+            bearer_token: format!("oxide-token-{}", t.token),
+        }
+    }
+}
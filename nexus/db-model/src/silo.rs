@@ -62,6 +62,7 @@ impl_enum_type!(
     // Enum values
     ApiOnly => b"api_only"
     Jit => b"jit"
+    Scim => b"scim"
 );
 
 impl From<shared::UserProvisionType> for UserProvisionType {
@@ -69,6 +70,7 @@ impl From<shared::UserProvisionType> for UserProvisionType {
         match params {
             shared::UserProvisionType::ApiOnly => UserProvisionType::ApiOnly,
             shared::UserProvisionType::Jit => UserProvisionType::Jit,
+            shared::UserProvisionType::Scim => UserProvisionType::Scim,
         }
     }
 }
@@ -78,6 +80,7 @@ impl From<UserProvisionType> for shared::UserProvisionType {
         match model {
             UserProvisionType::ApiOnly => Self::ApiOnly,
             UserProvisionType::Jit => Self::Jit,
+            UserProvisionType::Scim => Self::Scim,
         }
     }
 }
@@ -210,11 +213,15 @@ impl TryFrom<Silo> for views::Silo {
             (AuthenticationMode::Saml, UserProvisionType::Jit) => {
                 Some(SiloIdentityMode::SamlJit)
             }
+            (AuthenticationMode::Saml, UserProvisionType::Scim) => {
+                Some(SiloIdentityMode::SamlScim)
+            }
             (AuthenticationMode::Saml, UserProvisionType::ApiOnly) => None,
             (AuthenticationMode::Local, UserProvisionType::ApiOnly) => {
                 Some(SiloIdentityMode::LocalOnly)
             }
             (AuthenticationMode::Local, UserProvisionType::Jit) => None,
+            (AuthenticationMode::Local, UserProvisionType::Scim) => None,
         }
         .ok_or_else(|| {
             Error::internal_error(&format!(
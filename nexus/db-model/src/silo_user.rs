@@ -20,6 +20,12 @@ pub struct SiloUser {
 
     /// The identity provider's ID for this user.
     pub external_id: String,
+
+    /// Whether the user may log in
+    ///
+    /// The identity provider of a SCIM Silo deactivates users rather than
+    /// deleting them when it may want to reactivate them later.
+    pub active: bool,
 }
 
 impl SiloUser {
@@ -29,6 +35,7 @@ impl SiloUser {
             time_deleted: None,
             silo_id,
             external_id,
+            active: true,
         }
     }
 }
@@ -0,0 +1,193 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::schema::{
+    host_phase_1_delivery, mgs_update_delivery, target_release,
+    target_release_artifact,
+};
+use crate::{
+    ArtifactHash, Generation, HwRotSlot, MgsUpdateComponent, SemverVersion,
+    SpType,
+};
+use anyhow::anyhow;
+use chrono::{DateTime, Utc};
+use nexus_types::deployment;
+use nexus_types::deployment::TargetReleaseArtifactKind;
+use nexus_types::external_api::views;
+use nexus_types::inventory::BaseboardId;
+use uuid::Uuid;
+
+/// The release that the operator has asked the system to run
+///
+/// Only the row with the highest generation is meaningful.  Older rows are
+/// kept as a history of what was requested.
+#[derive(Queryable, Insertable, Clone, Debug, Selectable)]
+#[diesel(table_name = target_release)]
+pub struct TargetRelease {
+    pub generation: Generation,
+    pub time_requested: DateTime<Utc>,
+    pub system_version: Option<SemverVersion>,
+}
+
+impl TargetRelease {
+    /// Returns the release that would follow `self` if the operator asked for
+    /// `system_version`
+    pub fn new_next(&self, system_version: Option<SemverVersion>) -> Self {
+        Self {
+            generation: Generation(self.generation.next()),
+            time_requested: Utc::now(),
+            system_version,
+        }
+    }
+}
+
+impl From<TargetRelease> for views::TargetRelease {
+    fn from(release: TargetRelease) -> Self {
+        Self {
+            generation: i64::from(&*release.generation),
+            time_requested: release.time_requested,
+            system_version: release.system_version.map(|v| v.0),
+        }
+    }
+}
+
+/// See [`nexus_types::deployment::TargetReleaseArtifact`].
+#[derive(Queryable, Insertable, Clone, Debug, Selectable)]
+#[diesel(table_name = target_release_artifact)]
+pub struct TargetReleaseArtifact {
+    pub target_release_generation: Generation,
+    pub component: MgsUpdateComponent,
+    pub sp_type: SpType,
+    pub board: Option<String>,
+    pub rot_slot: Option<HwRotSlot>,
+    pub artifact_version: SemverVersion,
+    pub artifact_sha256: ArtifactHash,
+}
+
+impl TargetReleaseArtifact {
+    pub fn new(
+        target_release_generation: Generation,
+        artifact: &deployment::TargetReleaseArtifact,
+    ) -> Self {
+        let (component, board, rot_slot) = match &artifact.kind {
+            TargetReleaseArtifactKind::Sp { board } => {
+                (MgsUpdateComponent::Sp, Some(board.clone()), None)
+            }
+            TargetReleaseArtifactKind::Rot { slot } => {
+                (MgsUpdateComponent::Rot, None, Some(slot.clone().into()))
+            }
+            TargetReleaseArtifactKind::HostPhase1 => {
+                (MgsUpdateComponent::HostPhase1, None, None)
+            }
+        };
+        Self {
+            target_release_generation,
+            component,
+            sp_type: artifact.sp_type.into(),
+            board,
+            rot_slot,
+            artifact_version: artifact.version.clone().into(),
+            artifact_sha256: artifact.hash.into(),
+        }
+    }
+
+    pub fn into_target_release_artifact(
+        self,
+    ) -> Result<deployment::TargetReleaseArtifact, anyhow::Error> {
+        let kind = match self.component {
+            MgsUpdateComponent::Sp => TargetReleaseArtifactKind::Sp {
+                board: self
+                    .board
+                    .ok_or_else(|| anyhow!("SP artifact is missing board"))?,
+            },
+            MgsUpdateComponent::Rot => TargetReleaseArtifactKind::Rot {
+                slot: self
+                    .rot_slot
+                    .ok_or_else(|| anyhow!("RoT artifact is missing slot"))?
+                    .into(),
+            },
+            MgsUpdateComponent::HostPhase1 => {
+                TargetReleaseArtifactKind::HostPhase1
+            }
+        };
+        Ok(deployment::TargetReleaseArtifact {
+            kind,
+            sp_type: self.sp_type.into(),
+            version: self.artifact_version.0,
+            hash: self.artifact_sha256.0,
+        })
+    }
+}
+
+/// The host phase 1 artifact that the control plane most recently delivered
+/// to a baseboard
+#[derive(Queryable, Insertable, Clone, Debug, Selectable)]
+#[diesel(table_name = host_phase_1_delivery)]
+pub struct HostPhase1Delivery {
+    pub part_number: String,
+    pub serial_number: String,
+    pub artifact_sha256: ArtifactHash,
+    pub time_delivered: DateTime<Utc>,
+}
+
+impl HostPhase1Delivery {
+    pub fn new(
+        baseboard_id: &BaseboardId,
+        artifact_hash: ArtifactHash,
+    ) -> Self {
+        Self {
+            part_number: baseboard_id.part_number.clone(),
+            serial_number: baseboard_id.serial_number.clone(),
+            artifact_sha256: artifact_hash,
+            time_delivered: Utc::now(),
+        }
+    }
+
+    pub fn baseboard_id(&self) -> BaseboardId {
+        BaseboardId {
+            part_number: self.part_number.clone(),
+            serial_number: self.serial_number.clone(),
+        }
+    }
+}
+
+/// The artifact that a Nexus most recently claimed for delivery to a
+/// baseboard through MGS
+///
+/// `time_delivered` is `None` while the delivery is in progress.
+#[derive(Queryable, Insertable, Clone, Debug, Selectable)]
+#[diesel(table_name = mgs_update_delivery)]
+pub struct MgsUpdateDelivery {
+    pub part_number: String,
+    pub serial_number: String,
+    pub id: Uuid,
+    pub artifact_sha256: ArtifactHash,
+    pub time_started: DateTime<Utc>,
+    pub time_delivered: Option<DateTime<Utc>>,
+}
+
+impl MgsUpdateDelivery {
+    /// Returns a new, in-progress delivery of `artifact_hash` to
+    /// `baseboard_id`
+    pub fn new(
+        baseboard_id: &BaseboardId,
+        artifact_hash: ArtifactHash,
+    ) -> Self {
+        Self {
+            part_number: baseboard_id.part_number.clone(),
+            serial_number: baseboard_id.serial_number.clone(),
+            id: Uuid::new_v4(),
+            artifact_sha256: artifact_hash,
+            time_started: Utc::now(),
+            time_delivered: None,
+        }
+    }
+
+    pub fn baseboard_id(&self) -> BaseboardId {
+        BaseboardId {
+            part_number: self.part_number.clone(),
+            serial_number: self.serial_number.clone(),
+        }
+    }
+}
@@ -38,6 +38,10 @@ impl Zpool {
             physical_disk_id,
         }
     }
+
+    pub fn time_deleted(&self) -> Option<DateTime<Utc>> {
+        self.time_deleted
+    }
 }
 
 impl DatastoreCollectionConfig<Dataset> for Zpool {
@@ -215,9 +215,9 @@ mod test {
             self.nattempts.fetch_add(1, Ordering::SeqCst);
             match self.next.load(Ordering::SeqCst) {
                 SKIP => SchemeResult::NotRequested,
-                OK => SchemeResult::Authenticated(authn::Details {
-                    actor: self.actor,
-                }),
+                OK => {
+                    SchemeResult::Authenticated(authn::Details::new(self.actor))
+                }
                 FAIL => SchemeResult::Failed(Reason::BadCredentials {
                     actor: self.actor,
                     source: anyhow!("grunt error"),
@@ -65,7 +65,9 @@ where
             Ok(None) => SchemeResult::NotRequested,
             Ok(Some(token)) => match ctx.token_actor(token).await {
                 Err(error) => SchemeResult::Failed(error),
-                Ok(actor) => SchemeResult::Authenticated(Details { actor }),
+                Ok((actor, scope)) => SchemeResult::Authenticated(
+                    Details::new_scoped(actor, scope),
+                ),
             },
         }
     }
@@ -91,7 +93,12 @@ fn parse_token(
 /// A context that can look up a Silo user and client ID from a token.
 #[async_trait]
 pub trait TokenContext {
-    async fn token_actor(&self, token: String) -> Result<authn::Actor, Reason>;
+    /// Returns the actor identified by `token`, along with any restrictions
+    /// attached to the token itself
+    async fn token_actor(
+        &self,
+        token: String,
+    ) -> Result<(authn::Actor, authn::Scope), Reason>;
 }
 
 #[cfg(test)]
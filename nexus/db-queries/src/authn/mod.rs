@@ -25,6 +25,7 @@
 //! authentication, but they'd all produce the same [`Context`] struct.
 
 pub mod external;
+pub mod oidc;
 pub mod saga;
 pub mod silos;
 
@@ -82,7 +83,7 @@ impl Context {
         &self,
     ) -> Result<&Actor, omicron_common::api::external::Error> {
         match &self.kind {
-            Kind::Authenticated(Details { actor }, ..) => Ok(actor),
+            Kind::Authenticated(Details { actor, .. }, ..) => Ok(actor),
             Kind::Unauthenticated => {
                 Err(omicron_common::api::external::Error::Unauthenticated {
                     internal_message: "Actor required".to_string(),
@@ -91,6 +92,17 @@ impl Context {
         }
     }
 
+    /// Returns the restrictions that apply to this context beyond the
+    /// actor's roles
+    ///
+    /// Unauthenticated contexts and most authenticated ones are unrestricted.
+    pub fn scope(&self) -> Scope {
+        match &self.kind {
+            Kind::Authenticated(Details { scope, .. }, ..) => *scope,
+            Kind::Unauthenticated => Scope::default(),
+        }
+    }
+
     /// Returns the current actor's Silo if they have one or an appropriate
     /// error otherwise
     ///
@@ -192,7 +204,10 @@ impl Context {
     fn context_for_builtin_user(user_builtin_id: Uuid) -> Context {
         Context {
             kind: Kind::Authenticated(
-                Details { actor: Actor::UserBuiltin { user_builtin_id } },
+                Details {
+                    actor: Actor::UserBuiltin { user_builtin_id },
+                    scope: Scope::default(),
+                },
                 None,
             ),
             schemes_tried: Vec::new(),
@@ -210,6 +225,7 @@ impl Context {
                         silo_user_id: USER_TEST_PRIVILEGED.id(),
                         silo_id: USER_TEST_PRIVILEGED.silo_id,
                     },
+                    scope: Scope::default(),
                 },
                 Some(SiloAuthnPolicy::try_from(&*DEFAULT_SILO).unwrap()),
             ),
@@ -237,7 +253,10 @@ impl Context {
     ) -> Context {
         Context {
             kind: Kind::Authenticated(
-                Details { actor: Actor::SiloUser { silo_user_id, silo_id } },
+                Details {
+                    actor: Actor::SiloUser { silo_user_id, silo_id },
+                    scope: Scope::default(),
+                },
                 Some(silo_authn_policy),
             ),
             schemes_tried: Vec::new(),
@@ -354,6 +373,45 @@ enum Kind {
 pub struct Details {
     /// the actor performing the request
     actor: Actor,
+    /// restrictions attached to the credential the actor presented
+    #[serde(default)]
+    scope: Scope,
+}
+
+impl Details {
+    /// Describes an actor whose credentials carry no restrictions
+    pub fn new(actor: Actor) -> Details {
+        Details { actor, scope: Scope::default() }
+    }
+
+    /// Describes an actor whose credentials are restricted by `scope`
+    pub fn new_scoped(actor: Actor, scope: Scope) -> Details {
+        Details { actor, scope }
+    }
+}
+
+/// Restrictions on what an actor may do, on top of what its roles allow
+///
+/// Sessions and device tokens are unrestricted.  API tokens may be created
+/// with scopes that limit them to reads, or to modifying resources within a
+/// single project.  `authz` enforces these only after the role-based policy
+/// check has already allowed the action, so a scope can never grant access.
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Eq, PartialEq, Serialize,
+)]
+pub struct Scope {
+    /// If true, only actions that read state are permitted
+    pub read_only: bool,
+    /// If set, resources inside a project may only be accessed if they
+    /// belong to this project, and resources outside any project may only
+    /// be read
+    pub project_id: Option<Uuid>,
+}
+
+impl Scope {
+    pub fn is_unrestricted(&self) -> bool {
+        !self.read_only && self.project_id.is_none()
+    }
 }
 
 /// Who is performing an operation
@@ -361,6 +419,7 @@ pub struct Details {
 pub enum Actor {
     UserBuiltin { user_builtin_id: Uuid },
     SiloUser { silo_user_id: Uuid, silo_id: Uuid },
+    ServiceAccount { service_account_id: Uuid, silo_id: Uuid },
 }
 
 impl Actor {
@@ -368,6 +427,9 @@ impl Actor {
         match self {
             Actor::UserBuiltin { user_builtin_id, .. } => *user_builtin_id,
             Actor::SiloUser { silo_user_id, .. } => *silo_user_id,
+            Actor::ServiceAccount { service_account_id, .. } => {
+                *service_account_id
+            }
         }
     }
 
@@ -375,6 +437,7 @@ impl Actor {
         match self {
             Actor::UserBuiltin { .. } => None,
             Actor::SiloUser { silo_id, .. } => Some(*silo_id),
+            Actor::ServiceAccount { silo_id, .. } => Some(*silo_id),
         }
     }
 
@@ -382,6 +445,7 @@ impl Actor {
         match self {
             Actor::UserBuiltin { .. } => None,
             Actor::SiloUser { silo_user_id, .. } => Some(*silo_user_id),
+            Actor::ServiceAccount { .. } => None,
         }
     }
 }
@@ -391,6 +455,9 @@ impl From<&Actor> for db::model::IdentityType {
         match actor {
             Actor::UserBuiltin { .. } => db::model::IdentityType::UserBuiltin,
             Actor::SiloUser { .. } => db::model::IdentityType::SiloUser,
+            Actor::ServiceAccount { .. } => {
+                db::model::IdentityType::ServiceAccount
+            }
         }
     }
 }
@@ -414,6 +481,11 @@ impl std::fmt::Debug for Actor {
                 .field("silo_user_id", &silo_user_id)
                 .field("silo_id", &silo_id)
                 .finish_non_exhaustive(),
+            Actor::ServiceAccount { service_account_id, silo_id } => f
+                .debug_struct("Actor::ServiceAccount")
+                .field("service_account_id", &service_account_id)
+                .field("silo_id", &silo_id)
+                .finish_non_exhaustive(),
         }
     }
 }
@@ -0,0 +1,661 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! OpenID Connect authentication types and functions
+//!
+//! Nexus acts as an OIDC relying party using the authorization code flow with
+//! PKCE (RFC 7636).  This module contains the parts of that flow that don't
+//! involve talking to the identity provider: building the URL that users are
+//! sent to and verifying the ID token that comes back.  Fetching tokens and
+//! signing keys from the provider happens in Nexus proper.
+
+use super::silos::AuthenticatedSubject;
+use crate::db::model;
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use chrono::{DateTime, TimeZone, Utc};
+use openssl::bn::BigNum;
+use openssl::ec::{EcGroup, EcKey};
+use openssl::ecdsa::EcdsaSig;
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Public};
+use openssl::rsa::Rsa;
+use openssl::sign::Verifier;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use uuid::Uuid;
+
+/// How much clock skew between us and the identity provider we tolerate when
+/// checking the timestamps in an ID token
+const CLOCK_SKEW_LEEWAY_SECONDS: i64 = 60;
+
+pub struct OidcIdentityProvider {
+    pub id: Uuid,
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+    pub jwks_uri: String,
+    pub redirect_uri: String,
+    pub scopes: Vec<String>,
+    pub user_claim: String,
+    pub group_claim: Option<String>,
+}
+
+impl From<model::OidcIdentityProvider> for OidcIdentityProvider {
+    fn from(model: model::OidcIdentityProvider) -> Self {
+        use crate::db::identity::Resource;
+        OidcIdentityProvider {
+            id: model.id(),
+            scopes: model.scopes.split_whitespace().map(String::from).collect(),
+            issuer: model.issuer,
+            client_id: model.client_id,
+            client_secret: model.client_secret,
+            authorization_endpoint: model.authorization_endpoint,
+            token_endpoint: model.token_endpoint,
+            jwks_uri: model.jwks_uri,
+            redirect_uri: model.redirect_uri,
+            user_claim: model.user_claim,
+            group_claim: model.group_claim,
+        }
+    }
+}
+
+impl OidcIdentityProvider {
+    /// Returns the URL of the provider's authorization endpoint that a user
+    /// should be sent to in order to carry out the given login request
+    pub fn authorization_url(
+        &self,
+        login_request: &model::OidcLoginRequest,
+    ) -> Result<String> {
+        // "openid" is what makes this an OpenID Connect request (rather than
+        // plain OAuth 2.0), so always ask for it.
+        let mut scopes = vec!["openid"];
+        scopes.extend(
+            self.scopes.iter().map(|s| s.as_str()).filter(|s| *s != "openid"),
+        );
+        let scope = scopes.join(" ");
+        let code_challenge = pkce_challenge(&login_request.code_verifier);
+
+        let query = serde_urlencoded::to_string([
+            ("response_type", "code"),
+            ("client_id", self.client_id.as_str()),
+            ("redirect_uri", self.redirect_uri.as_str()),
+            ("scope", scope.as_str()),
+            ("state", login_request.state.as_str()),
+            ("nonce", login_request.nonce.as_str()),
+            ("code_challenge", code_challenge.as_str()),
+            ("code_challenge_method", "S256"),
+        ])
+        .context("encoding authorization request")?;
+
+        // The authorization endpoint is allowed to have its own query
+        // parameters, which we must preserve.
+        let separator =
+            if self.authorization_endpoint.contains('?') { '&' } else { '?' };
+        Ok(format!("{}{}{}", self.authorization_endpoint, separator, query))
+    }
+
+    /// Returns the form parameters used to exchange an authorization code for
+    /// tokens at the provider's token endpoint
+    ///
+    /// The client authenticates itself separately, using HTTP Basic
+    /// authentication with its client id and secret.
+    pub fn token_request_params<'a>(
+        &'a self,
+        code: &'a str,
+        login_request: &'a model::OidcLoginRequest,
+    ) -> [(&'static str, &'a str); 4] {
+        [
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", self.redirect_uri.as_str()),
+            ("code_verifier", login_request.code_verifier.as_str()),
+        ]
+    }
+
+    /// Verify an ID token issued by this provider and extract the subject
+    /// that it authenticates
+    ///
+    /// `jwks` is the provider's current set of signing keys and
+    /// `expected_nonce` is the nonce from the login request that this token is
+    /// a response to.
+    pub fn verify_id_token(
+        &self,
+        token: &UnverifiedIdToken,
+        jwks: &Jwks,
+        expected_nonce: &str,
+        now: DateTime<Utc>,
+    ) -> Result<AuthenticatedSubject> {
+        // Check the signature against an explicit list of allowed algorithms.
+        // In particular, this rejects "none" and the HMAC-based algorithms.
+        let (digest, ec_component_len) = match token.header.alg.as_str() {
+            "RS256" => (MessageDigest::sha256(), None),
+            "RS384" => (MessageDigest::sha384(), None),
+            "RS512" => (MessageDigest::sha512(), None),
+            "ES256" => (MessageDigest::sha256(), Some(32)),
+            "ES384" => (MessageDigest::sha384(), Some(48)),
+            alg => bail!("ID token signature algorithm {} is not allowed", alg),
+        };
+
+        let key = jwks.key_for(token).ok_or_else(|| {
+            anyhow!(
+                "no key found to verify ID token (key id {:?})",
+                token.header.kid
+            )
+        })?;
+        let public_key = key.public_key()?;
+
+        let signature = match ec_component_len {
+            None => token.signature.clone(),
+            Some(len) => {
+                // JWS encodes ECDSA signatures as the concatenation of R and S
+                // (RFC 7518 §3.4), but OpenSSL expects DER.
+                if token.signature.len() != 2 * len {
+                    bail!("ID token ECDSA signature has the wrong length");
+                }
+                let r = BigNum::from_slice(&token.signature[..len])?;
+                let s = BigNum::from_slice(&token.signature[len..])?;
+                EcdsaSig::from_private_components(r, s)?.to_der()?
+            }
+        };
+
+        let mut verifier = Verifier::new(digest, &public_key)?;
+        verifier.update(token.signing_input.as_bytes())?;
+        if !verifier.verify(&signature)? {
+            bail!("ID token signature is not valid");
+        }
+
+        // The signature is good.  Now check that the token was issued by this
+        // provider, for us, recently, and in response to this login request
+        // (OpenID Connect Core 1.0 §3.1.3.7).
+        let claims = &token.claims;
+        let issuer = string_claim(claims, "iss")?;
+        if issuer != self.issuer {
+            bail!(
+                "ID token issuer {} does not match configured issuer {}",
+                issuer,
+                self.issuer
+            );
+        }
+
+        let audience_ok = match claims.get("aud") {
+            Some(Value::String(aud)) => *aud == self.client_id,
+            Some(Value::Array(auds)) => auds
+                .iter()
+                .any(|aud| aud.as_str() == Some(self.client_id.as_str())),
+            _ => false,
+        };
+        if !audience_ok {
+            bail!("ID token audience does not include our client id");
+        }
+
+        let leeway = chrono::Duration::seconds(CLOCK_SKEW_LEEWAY_SECONDS);
+        let expires = date_claim(claims, "exp")?
+            .ok_or_else(|| anyhow!("ID token has no expiration time"))?;
+        if expires + leeway < now {
+            bail!("ID token expired at {}", expires);
+        }
+        if let Some(issued) = date_claim(claims, "iat")? {
+            if issued - leeway > now {
+                bail!("ID token was issued in the future ({})", issued);
+            }
+        }
+
+        let nonce = string_claim(claims, "nonce")?;
+        if nonce != expected_nonce {
+            bail!("ID token nonce does not match login request");
+        }
+
+        // Map the claims onto a Silo user and groups.
+        let external_id = string_claim(claims, &self.user_claim)?.to_string();
+        let mut groups = vec![];
+        if let Some(group_claim) = &self.group_claim {
+            let names: Vec<&str> = match claims.get(group_claim) {
+                None | Some(Value::Null) => vec![],
+                Some(Value::Array(values)) => {
+                    values.iter().filter_map(|v| v.as_str()).collect()
+                }
+                // Accept comma-separated group names, like we do for SAML.
+                Some(Value::String(value)) => value.split(',').collect(),
+                Some(_) => bail!(
+                    "ID token claim {:?} is not a list of group names",
+                    group_claim
+                ),
+            };
+            groups.extend(
+                names
+                    .into_iter()
+                    .map(|g| g.trim())
+                    .filter(|g| !g.is_empty())
+                    .map(String::from),
+            );
+        }
+
+        Ok(AuthenticatedSubject { external_id, groups })
+    }
+}
+
+/// Returns the PKCE code challenge for the given code verifier using the
+/// "S256" method (RFC 7636 §4.2)
+pub fn pkce_challenge(code_verifier: &str) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD
+        .encode(openssl::sha::sha256(code_verifier.as_bytes()))
+}
+
+fn base64url_decode(value: &str) -> Result<Vec<u8>> {
+    Ok(base64::engine::general_purpose::URL_SAFE_NO_PAD.decode(value)?)
+}
+
+fn string_claim<'a>(
+    claims: &'a serde_json::Map<String, Value>,
+    name: &str,
+) -> Result<&'a str> {
+    match claims.get(name) {
+        Some(Value::String(value)) => Ok(value),
+        Some(_) => bail!("ID token claim {:?} is not a string", name),
+        None => bail!("ID token has no {:?} claim", name),
+    }
+}
+
+fn date_claim(
+    claims: &serde_json::Map<String, Value>,
+    name: &str,
+) -> Result<Option<DateTime<Utc>>> {
+    let Some(value) = claims.get(name) else {
+        return Ok(None);
+    };
+    // NumericDate values are seconds since the epoch, and may be fractional.
+    let seconds = value
+        .as_i64()
+        .or_else(|| value.as_f64().map(|f| f as i64))
+        .ok_or_else(|| {
+        anyhow!("ID token claim {:?} is not a date", name)
+    })?;
+    Utc.timestamp_opt(seconds, 0)
+        .single()
+        .map(Some)
+        .ok_or_else(|| anyhow!("ID token claim {:?} is out of range", name))
+}
+
+#[derive(Debug, Deserialize)]
+struct JwsHeader {
+    alg: String,
+    #[serde(default)]
+    kid: Option<String>,
+}
+
+/// An ID token (a JWS in compact serialization) whose signature has not yet
+/// been verified
+///
+/// Nothing in here should be trusted until it's been checked with
+/// [`OidcIdentityProvider::verify_id_token()`].
+#[derive(Debug)]
+pub struct UnverifiedIdToken {
+    header: JwsHeader,
+    claims: serde_json::Map<String, Value>,
+    signing_input: String,
+    signature: Vec<u8>,
+}
+
+impl UnverifiedIdToken {
+    pub fn parse(token: &str) -> Result<Self> {
+        let (signing_input, signature) = token
+            .rsplit_once('.')
+            .ok_or_else(|| anyhow!("ID token is not a JWS"))?;
+        let (header, claims) = signing_input
+            .split_once('.')
+            .ok_or_else(|| anyhow!("ID token is not a JWS"))?;
+
+        let header = serde_json::from_slice(
+            &base64url_decode(header).context("decoding ID token header")?,
+        )
+        .context("parsing ID token header")?;
+        let claims = serde_json::from_slice(
+            &base64url_decode(claims).context("decoding ID token claims")?,
+        )
+        .context("parsing ID token claims")?;
+        let signature = base64url_decode(signature)
+            .context("decoding ID token signature")?;
+
+        Ok(UnverifiedIdToken {
+            header,
+            claims,
+            signing_input: signing_input.to_string(),
+            signature,
+        })
+    }
+
+    /// Returns the id of the key that the token claims to be signed with
+    pub fn key_id(&self) -> Option<&str> {
+        self.header.kid.as_deref()
+    }
+}
+
+/// A JSON Web Key Set (RFC 7517 §5), as published at a provider's `jwks_uri`
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Jwks {
+    pub keys: Vec<Jwk>,
+}
+
+impl Jwks {
+    /// Returns the key that should be used to verify `token`, if this set
+    /// contains one
+    pub fn key_for(&self, token: &UnverifiedIdToken) -> Option<&Jwk> {
+        let kty = if token.header.alg.starts_with("RS") {
+            "RSA"
+        } else if token.header.alg.starts_with("ES") {
+            "EC"
+        } else {
+            return None;
+        };
+
+        self.keys
+            .iter()
+            .filter(|k| k.kty == kty)
+            .filter(|k| k.key_use.as_deref().map_or(true, |u| u == "sig"))
+            .find(|k| match (token.key_id(), k.kid.as_deref()) {
+                (Some(want), Some(have)) => want == have,
+                (Some(_), None) => false,
+                (None, _) => true,
+            })
+    }
+}
+
+/// A single JSON Web Key (RFC 7517 §4)
+///
+/// Only the public parts of RSA and elliptic curve keys are supported.
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct Jwk {
+    pub kty: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kid: Option<String>,
+    #[serde(default, rename = "use", skip_serializing_if = "Option::is_none")]
+    pub key_use: Option<String>,
+
+    // RSA public key parameters (RFC 7518 §6.3.1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub n: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub e: Option<String>,
+
+    // Elliptic curve public key parameters (RFC 7518 §6.2.1)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub crv: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub x: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub y: Option<String>,
+}
+
+impl Jwk {
+    fn param(&self, name: &str, value: &Option<String>) -> Result<BigNum> {
+        let value = value.as_deref().ok_or_else(|| {
+            anyhow!("{} key is missing parameter {:?}", self.kty, name)
+        })?;
+        Ok(BigNum::from_slice(&base64url_decode(value)?)?)
+    }
+
+    fn public_key(&self) -> Result<PKey<Public>> {
+        match self.kty.as_str() {
+            "RSA" => {
+                let n = self.param("n", &self.n)?;
+                let e = self.param("e", &self.e)?;
+                Ok(PKey::from_rsa(Rsa::from_public_components(n, e)?)?)
+            }
+            "EC" => {
+                let nid = match self.crv.as_deref() {
+                    Some("P-256") => Nid::X9_62_PRIME256V1,
+                    Some("P-384") => Nid::SECP384R1,
+                    crv => bail!("unsupported elliptic curve {:?}", crv),
+                };
+                let group = EcGroup::from_curve_name(nid)?;
+                let x = self.param("x", &self.x)?;
+                let y = self.param("y", &self.y)?;
+                let key =
+                    EcKey::from_public_key_affine_coordinates(&group, &x, &y)?;
+                Ok(PKey::from_ec_key(key)?)
+            }
+            kty => bail!("unsupported key type {:?}", kty),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use openssl::pkey::Private;
+    use openssl::sign::Signer;
+    use serde_json::json;
+
+    const ISSUER: &str = "https://idp.example.com";
+    const CLIENT_ID: &str = "oxide";
+    const NONCE: &str = "the-nonce";
+
+    fn b64(data: &[u8]) -> String {
+        base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(data)
+    }
+
+    fn provider(group_claim: Option<&str>) -> OidcIdentityProvider {
+        OidcIdentityProvider {
+            id: Uuid::new_v4(),
+            issuer: ISSUER.to_string(),
+            client_id: CLIENT_ID.to_string(),
+            client_secret: "secret".to_string(),
+            authorization_endpoint: format!("{}/authorize", ISSUER),
+            token_endpoint: format!("{}/token", ISSUER),
+            jwks_uri: format!("{}/jwks", ISSUER),
+            redirect_uri: "https://oxide.example.com/callback".to_string(),
+            scopes: vec!["email".to_string()],
+            user_claim: "email".to_string(),
+            group_claim: group_claim.map(String::from),
+        }
+    }
+
+    fn rsa_key() -> (PKey<Private>, Jwks) {
+        let rsa = Rsa::generate(2048).unwrap();
+        let jwk = Jwk {
+            kty: "RSA".to_string(),
+            kid: Some("key1".to_string()),
+            key_use: Some("sig".to_string()),
+            n: Some(b64(&rsa.n().to_vec())),
+            e: Some(b64(&rsa.e().to_vec())),
+            crv: None,
+            x: None,
+            y: None,
+        };
+        (PKey::from_rsa(rsa).unwrap(), Jwks { keys: vec![jwk] })
+    }
+
+    fn sign(key: &PKey<Private>, header: Value, claims: Value) -> String {
+        let signing_input = format!(
+            "{}.{}",
+            b64(header.to_string().as_bytes()),
+            b64(claims.to_string().as_bytes())
+        );
+        let mut signer = Signer::new(MessageDigest::sha256(), key).unwrap();
+        signer.update(signing_input.as_bytes()).unwrap();
+        let mut signature = signer.sign_to_vec().unwrap();
+        if let Ok(ec) = key.ec_key() {
+            // Convert OpenSSL's DER signature into the JWS format.
+            let sig = EcdsaSig::from_der(&signature).unwrap();
+            let len = (ec.group().degree() as usize + 7) / 8;
+            signature = sig.r().to_vec_padded(len as i32).unwrap();
+            signature.extend(sig.s().to_vec_padded(len as i32).unwrap());
+        }
+        format!("{}.{}", signing_input, b64(&signature))
+    }
+
+    fn claims() -> Value {
+        let now = Utc::now().timestamp();
+        json!({
+            "iss": ISSUER,
+            "aud": CLIENT_ID,
+            "sub": "248289761001",
+            "email": "jane@example.com",
+            "groups": ["engineering", " ops "],
+            "nonce": NONCE,
+            "iat": now,
+            "exp": now + 300,
+        })
+    }
+
+    fn verify(
+        provider: &OidcIdentityProvider,
+        token: &str,
+        jwks: &Jwks,
+    ) -> Result<AuthenticatedSubject> {
+        let token = UnverifiedIdToken::parse(token)?;
+        provider.verify_id_token(&token, jwks, NONCE, Utc::now())
+    }
+
+    #[test]
+    fn test_pkce_challenge() {
+        // Example from RFC 7636 Appendix B
+        assert_eq!(
+            pkce_challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"),
+            "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM"
+        );
+    }
+
+    #[test]
+    fn test_authorization_url() {
+        let provider = provider(None);
+        let login_request =
+            model::OidcLoginRequest::new(provider.id, Some("relay".into()));
+        let url = provider.authorization_url(&login_request).unwrap();
+        let (base, query) = url.split_once('?').unwrap();
+        assert_eq!(base, provider.authorization_endpoint);
+        let params: std::collections::BTreeMap<String, String> =
+            serde_urlencoded::from_str(query).unwrap();
+        assert_eq!(params["response_type"], "code");
+        assert_eq!(params["client_id"], CLIENT_ID);
+        assert_eq!(params["redirect_uri"], provider.redirect_uri);
+        assert_eq!(params["scope"], "openid email");
+        assert_eq!(params["state"], login_request.state);
+        assert_eq!(params["nonce"], login_request.nonce);
+        assert_eq!(params["code_challenge_method"], "S256");
+        assert_eq!(
+            params["code_challenge"],
+            pkce_challenge(&login_request.code_verifier)
+        );
+    }
+
+    #[test]
+    fn test_verify_id_token_rsa() {
+        let (key, jwks) = rsa_key();
+        let header = json!({ "alg": "RS256", "kid": "key1" });
+
+        let provider = provider(Some("groups"));
+        let token = sign(&key, header.clone(), claims());
+        let subject = verify(&provider, &token, &jwks).unwrap();
+        assert_eq!(subject.external_id, "jane@example.com");
+        assert_eq!(subject.groups, vec!["engineering", "ops"]);
+
+        // Without a group claim configured, no groups are reported.
+        let subject = verify(&self::provider(None), &token, &jwks).unwrap();
+        assert!(subject.groups.is_empty());
+
+        // Comma-separated groups are also accepted.
+        let mut c = claims();
+        c["groups"] = json!("a, b,,c");
+        let token = sign(&key, header.clone(), c);
+        let subject = verify(&provider, &token, &jwks).unwrap();
+        assert_eq!(subject.groups, vec!["a", "b", "c"]);
+    }
+
+    #[test]
+    fn test_verify_id_token_ec() {
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let ec = EcKey::generate(&group).unwrap();
+        let mut x = BigNum::new().unwrap();
+        let mut y = BigNum::new().unwrap();
+        let mut ctx = openssl::bn::BigNumContext::new().unwrap();
+        ec.public_key()
+            .affine_coordinates(&group, &mut x, &mut y, &mut ctx)
+            .unwrap();
+        let jwks = Jwks {
+            keys: vec![Jwk {
+                kty: "EC".to_string(),
+                kid: None,
+                key_use: None,
+                n: None,
+                e: None,
+                crv: Some("P-256".to_string()),
+                x: Some(b64(&x.to_vec_padded(32).unwrap())),
+                y: Some(b64(&y.to_vec_padded(32).unwrap())),
+            }],
+        };
+        let key = PKey::from_ec_key(ec).unwrap();
+        let token = sign(&key, json!({ "alg": "ES256" }), claims());
+        let subject = verify(&provider(None), &token, &jwks).unwrap();
+        assert_eq!(subject.external_id, "jane@example.com");
+    }
+
+    #[test]
+    fn test_verify_id_token_rejects_bad_tokens() {
+        let (key, jwks) = rsa_key();
+        let header = json!({ "alg": "RS256", "kid": "key1" });
+        let provider = provider(None);
+
+        let cases: Vec<(&str, Box<dyn Fn(&mut Value)>)> = vec![
+            ("wrong issuer", Box::new(|c| c["iss"] = json!("https://evil"))),
+            ("wrong audience", Box::new(|c| c["aud"] = json!("someone-else"))),
+            ("wrong nonce", Box::new(|c| c["nonce"] = json!("replayed"))),
+            ("no nonce", Box::new(|c| c["nonce"] = Value::Null)),
+            (
+                "expired",
+                Box::new(|c| {
+                    c["exp"] = json!(Utc::now().timestamp() - 3600);
+                }),
+            ),
+            (
+                "issued in the future",
+                Box::new(|c| {
+                    c["iat"] = json!(Utc::now().timestamp() + 3600);
+                }),
+            ),
+            ("no user claim", Box::new(|c| c["email"] = Value::Null)),
+        ];
+        for (label, modify) in cases {
+            let mut c = claims();
+            modify(&mut c);
+            let token = sign(&key, header.clone(), c);
+            verify(&provider, &token, &jwks).expect_err(label);
+        }
+
+        // An audience list that includes us is fine.
+        let mut c = claims();
+        c["aud"] = json!(["someone-else", CLIENT_ID]);
+        let token = sign(&key, header.clone(), c);
+        verify(&provider, &token, &jwks).unwrap();
+
+        // Tampering with the claims invalidates the signature.
+        let token = sign(&key, header.clone(), claims());
+        let mut parts: Vec<&str> = token.split('.').collect();
+        let mut c = claims();
+        c["email"] = json!("admin@example.com");
+        let tampered_claims = b64(c.to_string().as_bytes());
+        parts[1] = &tampered_claims;
+        verify(&provider, &parts.join("."), &jwks).expect_err("tampered token");
+
+        // Unsigned tokens are never accepted.
+        let unsigned = format!(
+            "{}.{}.",
+            b64(json!({ "alg": "none" }).to_string().as_bytes()),
+            b64(claims().to_string().as_bytes())
+        );
+        verify(&provider, &unsigned, &jwks).expect_err("unsigned token");
+
+        // Tokens signed by a key we don't know about are rejected.
+        let (other_key, _) = rsa_key();
+        let token = sign(&other_key, header, claims());
+        verify(&provider, &token, &jwks).expect_err("unknown key");
+        let token =
+            sign(&key, json!({ "alg": "RS256", "kid": "key2" }), claims());
+        verify(&provider, &token, &jwks).expect_err("unknown key id");
+    }
+}
@@ -4,6 +4,7 @@
 
 //! Silo related authentication types and functions
 
+use super::oidc::OidcIdentityProvider;
 use crate::authz;
 use crate::context::OpContext;
 use crate::db::lookup::LookupPath;
@@ -66,6 +67,7 @@ impl TryFrom<model::SamlIdentityProvider> for SamlIdentityProvider {
 
 pub enum IdentityProviderType {
     Saml(SamlIdentityProvider),
+    Oidc(OidcIdentityProvider),
 }
 
 impl IdentityProviderType {
@@ -115,6 +117,21 @@ impl IdentityProviderType {
 
                 Ok((authz_silo, db_silo, saml_identity_provider))
             }
+
+            model::IdentityProviderType::Oidc => {
+                let (.., oidc_identity_provider) =
+                    LookupPath::new(opctx, datastore)
+                        .silo_name(silo_name)
+                        .oidc_identity_provider_name(provider_name)
+                        .fetch()
+                        .await?;
+
+                Ok((
+                    authz_silo,
+                    db_silo,
+                    IdentityProviderType::Oidc(oidc_identity_provider.into()),
+                ))
+            }
         }
     }
 }
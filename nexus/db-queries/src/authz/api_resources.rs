@@ -153,6 +153,14 @@ impl<T: ApiResource + oso::PolarClass + Clone> AuthorizedResource for T {
     fn polar_class(&self) -> oso::Class {
         Self::get_polar_class()
     }
+
+    fn containing_project(&self) -> Option<Uuid> {
+        if self.resource_type() == ResourceType::Project {
+            self.as_resource_with_roles().map(|p| p.resource_id())
+        } else {
+            self.parent().and_then(|p| p.containing_project())
+        }
+    }
 }
 
 /// Represents the Oxide fleet for authz purposes
@@ -754,6 +762,65 @@ impl AuthorizedResource for SiloUserList {
     }
 }
 
+/// Synthetic resource describing the list of service accounts in a Silo
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct ServiceAccountList(Silo);
+
+impl ServiceAccountList {
+    pub fn new(silo: Silo) -> ServiceAccountList {
+        ServiceAccountList(silo)
+    }
+
+    pub fn silo(&self) -> &Silo {
+        &self.0
+    }
+}
+
+impl oso::PolarClass for ServiceAccountList {
+    fn get_polar_class_builder() -> oso::ClassBuilder<Self> {
+        oso::Class::builder()
+            .with_equality_check()
+            .add_attribute_getter("silo", |list: &ServiceAccountList| {
+                list.0.clone()
+            })
+    }
+}
+
+impl AuthorizedResource for ServiceAccountList {
+    fn load_roles<'a, 'b, 'c, 'd, 'e, 'f>(
+        &'a self,
+        opctx: &'b OpContext,
+        datastore: &'c DataStore,
+        authn: &'d authn::Context,
+        roleset: &'e mut RoleSet,
+    ) -> futures::future::BoxFuture<'f, Result<(), Error>>
+    where
+        'a: 'f,
+        'b: 'f,
+        'c: 'f,
+        'd: 'f,
+        'e: 'f,
+    {
+        // There are no roles on this resource, but we still need to load the
+        // Silo-related roles.
+        self.silo().load_roles(opctx, datastore, authn, roleset)
+    }
+
+    fn on_unauthorized(
+        &self,
+        _: &Authz,
+        error: Error,
+        _: AnyActor,
+        _: Action,
+    ) -> Error {
+        error
+    }
+
+    fn polar_class(&self) -> oso::Class {
+        Self::get_polar_class()
+    }
+}
+
 // Main resource hierarchy: Projects and their resources
 
 authz_resource! {
@@ -976,6 +1043,14 @@ authz_resource! {
     polar_snippet = Custom,
 }
 
+authz_resource! {
+    name = "ServiceAccount",
+    parent = "Silo",
+    primary_key = Uuid,
+    roles_allowed = false,
+    polar_snippet = Custom,
+}
+
 authz_resource! {
     name = "SiloImage",
     parent = "Silo",
@@ -1009,6 +1084,14 @@ authz_resource! {
     polar_snippet = Custom,
 }
 
+authz_resource! {
+    name = "OidcIdentityProvider",
+    parent = "Silo",
+    primary_key = Uuid,
+    roles_allowed = false,
+    polar_snippet = Custom,
+}
+
 authz_resource! {
     name = "SshKey",
     parent = "SiloUser",
@@ -1017,6 +1100,14 @@ authz_resource! {
     polar_snippet = Custom,
 }
 
+authz_resource! {
+    name = "ApiToken",
+    parent = "SiloUser",
+    primary_key = Uuid,
+    roles_allowed = false,
+    polar_snippet = Custom,
+}
+
 authz_resource! {
     name = "Sled",
     parent = "Fleet",
@@ -18,6 +18,7 @@ use oso::Oso;
 use oso::OsoError;
 use std::collections::BTreeSet;
 use std::sync::Arc;
+use uuid::Uuid;
 
 /// Server-wide authorization context
 pub struct Authz {
@@ -118,7 +119,7 @@ impl Context {
         let actor = AnyActor::new(&self.authn, roles);
         let is_authn = self.authn.actor().is_some();
         match self.authz.is_allowed(&actor, action, &resource) {
-            Ok(true) => Ok(()),
+            Ok(true) => self.check_scope(action, &resource),
             Err(error) => Err(Error::internal_error(&format!(
                 "failed to compute authorization: {:#}",
                 error
@@ -147,6 +148,53 @@ impl Context {
             }
         }
     }
+
+    /// Check that `action` on `resource` is within the scope of the
+    /// credentials the actor presented
+    ///
+    /// This is only called once the policy has already allowed the action.
+    /// Scopes can only take away access, never grant it.
+    fn check_scope<Resource>(
+        &self,
+        action: Action,
+        resource: &Resource,
+    ) -> Result<(), Error>
+    where
+        Resource: AuthorizedResource,
+    {
+        let scope = self.authn.scope();
+        if scope.is_unrestricted() {
+            return Ok(());
+        }
+
+        let is_read = matches!(
+            action,
+            Action::Query
+                | Action::Read
+                | Action::ListChildren
+                | Action::ReadPolicy
+        );
+        if scope.read_only && !is_read {
+            return Err(Error::Forbidden);
+        }
+
+        let Some(scope_project_id) = scope.project_id else {
+            return Ok(());
+        };
+        match resource.containing_project() {
+            Some(project_id) if project_id == scope_project_id => Ok(()),
+            Some(_) => Err(Error::Forbidden),
+            None if is_read => Ok(()),
+            // Creating an instance or floating IP in the scoped project
+            // allocates an address from a (silo-wide) IP pool.
+            None if action == Action::CreateChild
+                && resource.polar_class().name == "IpPool" =>
+            {
+                Ok(())
+            }
+            None => Err(Error::Forbidden),
+        }
+    }
 }
 
 pub trait AuthorizedResource: oso::ToPolar + Send + Sync + 'static {
@@ -190,6 +238,14 @@ pub trait AuthorizedResource: oso::ToPolar + Send + Sync + 'static {
 
     /// Returns the Polar class that implements this resource
     fn polar_class(&self) -> oso::Class;
+
+    /// Returns the id of the Project that contains this resource (or that
+    /// this resource is), if any
+    ///
+    /// This is used to enforce project-scoped credentials.
+    fn containing_project(&self) -> Option<Uuid> {
+        None
+    }
 }
 
 #[cfg(test)]
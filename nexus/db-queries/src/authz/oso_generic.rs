@@ -114,6 +114,7 @@ pub fn make_omicron_oso(log: &slog::Logger) -> Result<OsoInit, anyhow::Error> {
         SiloCertificateList::get_polar_class(),
         SiloIdentityProviderList::get_polar_class(),
         SiloUserList::get_polar_class(),
+        ServiceAccountList::get_polar_class(),
     ];
     for c in classes {
         oso_builder = oso_builder.register_class(c)?;
@@ -148,11 +149,14 @@ pub fn make_omicron_oso(log: &slog::Logger) -> Result<OsoInit, anyhow::Error> {
         Rack::init(),
         RoleBuiltin::init(),
         SshKey::init(),
+        ApiToken::init(),
         Silo::init(),
         SiloUser::init(),
         SiloGroup::init(),
+        ServiceAccount::init(),
         IdentityProvider::init(),
         SamlIdentityProvider::init(),
+        OidcIdentityProvider::init(),
         Sled::init(),
         TufRepo::init(),
         TufArtifact::init(),
@@ -226,6 +226,11 @@ async fn make_silo(
         idp_id,
         LookupType::ByName(format!("{}-saml-identity-provider", silo_name)),
     ));
+    builder.new_resource(authz::OidcIdentityProvider::new(
+        silo.clone(),
+        idp_id,
+        LookupType::ByName(format!("{}-oidc-identity-provider", silo_name)),
+    ));
 
     builder.new_resource(authz::SiloUserList::new(silo.clone()));
     let silo_user_id = Uuid::new_v4();
@@ -237,16 +242,29 @@ async fn make_silo(
     builder.new_resource(silo_user.clone());
     let ssh_key_id = Uuid::new_v4();
     builder.new_resource(authz::SshKey::new(
-        silo_user,
+        silo_user.clone(),
         ssh_key_id,
         LookupType::ByName(format!("{}-user-ssh-key", silo_name)),
     ));
+    let api_token_id = Uuid::new_v4();
+    builder.new_resource(authz::ApiToken::new(
+        silo_user,
+        api_token_id,
+        LookupType::ByName(format!("{}-user-api-token", silo_name)),
+    ));
     let silo_group_id = Uuid::new_v4();
     builder.new_resource(authz::SiloGroup::new(
         silo.clone(),
         silo_group_id,
         LookupType::ByName(format!("{}-group", silo_name)),
     ));
+    builder.new_resource(authz::ServiceAccountList::new(silo.clone()));
+    let service_account_id = Uuid::new_v4();
+    builder.new_resource(authz::ServiceAccount::new(
+        silo.clone(),
+        service_account_id,
+        LookupType::ByName(format!("{}-service-account", silo_name)),
+    ));
     let silo_image_id = Uuid::new_v4();
     builder.new_resource(authz::SiloImage::new(
         silo.clone(),
@@ -0,0 +1,166 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! [`DataStore`] methods related to [`ApiToken`]s.
+
+use super::DataStore;
+use crate::authz;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::error::public_error_from_diesel;
+use crate::db::error::ErrorHandler;
+use crate::db::identity::Resource;
+use crate::db::model::ApiToken;
+use crate::db::model::Name;
+use crate::db::model::API_TOKEN_LAST_USED_GRANULARITY_SECS;
+use crate::db::pagination::paginated;
+use crate::db::update_and_check::UpdateAndCheck;
+use async_bb8_diesel::AsyncRunQueryDsl;
+use chrono::{DateTime, Duration, Utc};
+use diesel::prelude::*;
+use omicron_common::api::external::http_pagination::PaginatedBy;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::LookupType;
+use omicron_common::api::external::ResourceType;
+use ref_cast::RefCast;
+use uuid::Uuid;
+
+impl DataStore {
+    pub async fn api_tokens_list(
+        &self,
+        opctx: &OpContext,
+        authz_user: &authz::SiloUser,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<ApiToken> {
+        opctx.authorize(authz::Action::ListChildren, authz_user).await?;
+
+        use db::schema::api_token::dsl;
+        match pagparams {
+            PaginatedBy::Id(pagparams) => {
+                paginated(dsl::api_token, dsl::id, &pagparams)
+            }
+            PaginatedBy::Name(pagparams) => paginated(
+                dsl::api_token,
+                dsl::name,
+                &pagparams.map_name(|n| Name::ref_cast(n)),
+            ),
+        }
+        .filter(dsl::silo_user_id.eq(authz_user.id()))
+        .filter(dsl::time_deleted.is_null())
+        .select(ApiToken::as_select())
+        .load_async(&*self.pool_connection_authorized(opctx).await?)
+        .await
+        .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))
+    }
+
+    /// Create a new API token for a user.
+    pub async fn api_token_create(
+        &self,
+        opctx: &OpContext,
+        authz_user: &authz::SiloUser,
+        api_token: ApiToken,
+    ) -> CreateResult<ApiToken> {
+        assert_eq!(authz_user.id(), api_token.silo_user_id);
+        opctx.authorize(authz::Action::CreateChild, authz_user).await?;
+        let name = api_token.name().to_string();
+
+        use db::schema::api_token::dsl;
+        diesel::insert_into(dsl::api_token)
+            .values(api_token)
+            .returning(ApiToken::as_returning())
+            .get_result_async(&*self.pool_connection_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel(
+                    e,
+                    ErrorHandler::Conflict(ResourceType::ApiToken, &name),
+                )
+            })
+    }
+
+    /// Revoke an existing API token.
+    pub async fn api_token_delete(
+        &self,
+        opctx: &OpContext,
+        authz_api_token: &authz::ApiToken,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Delete, authz_api_token).await?;
+
+        use db::schema::api_token::dsl;
+        diesel::update(dsl::api_token)
+            .filter(dsl::id.eq(authz_api_token.id()))
+            .filter(dsl::time_deleted.is_null())
+            .set(dsl::time_deleted.eq(Utc::now()))
+            .check_if_exists::<ApiToken>(authz_api_token.id())
+            .execute_and_check(&*self.pool_connection_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_api_token),
+                )
+            })?;
+        Ok(())
+    }
+
+    /// Fetch a live API token by its bearer token value.
+    ///
+    /// This is used during authentication, before there is an actor to
+    /// authorize, so it performs no authz check of its own.  Callers must
+    /// check expiration themselves.
+    pub async fn api_token_fetch_for_authn(
+        &self,
+        opctx: &OpContext,
+        token: &str,
+    ) -> LookupResult<ApiToken> {
+        use db::schema::api_token::dsl;
+        dsl::api_token
+            .filter(dsl::token.eq(token.to_string()))
+            .filter(dsl::time_deleted.is_null())
+            .select(ApiToken::as_select())
+            .get_result_async(&*self.pool_connection_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel(
+                    e,
+                    ErrorHandler::NotFoundByLookup(
+                        ResourceType::ApiToken,
+                        LookupType::ByOther("bearer token".to_string()),
+                    ),
+                )
+            })
+    }
+
+    /// Record that the given API token was used at `now`.
+    ///
+    /// Callers should skip this when the token they already have in hand
+    /// shows a recent enough last-used time (see
+    /// [`ApiToken::last_used_is_stale()`]).  The same check is repeated here
+    /// so that concurrent requests using the same token don't all write it.
+    pub async fn api_token_touch(
+        &self,
+        opctx: &OpContext,
+        api_token_id: Uuid,
+        now: DateTime<Utc>,
+    ) -> Result<(), Error> {
+        use db::schema::api_token::dsl;
+        let stale =
+            now - Duration::seconds(API_TOKEN_LAST_USED_GRANULARITY_SECS);
+        diesel::update(dsl::api_token)
+            .filter(dsl::id.eq(api_token_id))
+            .filter(dsl::time_deleted.is_null())
+            .filter(
+                dsl::time_last_used.is_null().or(dsl::time_last_used.lt(stale)),
+            )
+            .set(dsl::time_last_used.eq(now))
+            .execute_async(&*self.pool_connection_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))?;
+        Ok(())
+    }
+}
@@ -11,6 +11,7 @@ use crate::db::datastore::SQL_BATCH_SIZE;
 use crate::db::error::public_error_from_diesel;
 use crate::db::error::ErrorHandler;
 use crate::db::pagination::paginated;
+use crate::db::pagination::paginated_multicolumn;
 use crate::db::pagination::Paginator;
 use crate::db::DbConnection;
 use crate::db::TransactionError;
@@ -38,6 +39,7 @@ use nexus_db_model::Blueprint as DbBlueprint;
 use nexus_db_model::BpOmicronPhysicalDisk;
 use nexus_db_model::BpOmicronZone;
 use nexus_db_model::BpOmicronZoneNic;
+use nexus_db_model::BpPendingMgsUpdate;
 use nexus_db_model::BpSledOmicronPhysicalDisks;
 use nexus_db_model::BpSledOmicronZones;
 use nexus_db_model::BpSledState;
@@ -47,6 +49,7 @@ use nexus_types::deployment::BlueprintMetadata;
 use nexus_types::deployment::BlueprintPhysicalDisksConfig;
 use nexus_types::deployment::BlueprintTarget;
 use nexus_types::deployment::BlueprintZonesConfig;
+use nexus_types::deployment::PendingMgsUpdates;
 use nexus_types::external_api::views::SledState;
 use omicron_common::api::external::DataPageParams;
 use omicron_common::api::external::Error;
@@ -174,6 +177,11 @@ impl DataStore {
                 })
             })
             .collect::<Result<Vec<BpOmicronZoneNic>, _>>()?;
+        let pending_mgs_updates = blueprint
+            .pending_mgs_updates
+            .iter()
+            .map(|update| BpPendingMgsUpdate::new(blueprint_id, update))
+            .collect::<Vec<_>>();
 
         // This implementation inserts all records associated with the
         // blueprint in one transaction.  This is required: we don't want
@@ -253,6 +261,17 @@ impl DataStore {
                         .await?;
             }
 
+            // Insert the pending MGS updates for this blueprint.
+            {
+                use db::schema::bp_pending_mgs_update::dsl as pending_update;
+                let _ = diesel::insert_into(
+                    pending_update::bp_pending_mgs_update,
+                )
+                .values(pending_mgs_updates)
+                .execute_async(&conn)
+                .await?;
+            }
+
             Ok(())
         })
         .await
@@ -603,10 +622,55 @@ impl DataStore {
             disks_config.disks.sort_unstable_by_key(|d| d.id);
         }
 
+        // Load the pending MGS updates.
+        let pending_mgs_updates = {
+            use db::schema::bp_pending_mgs_update::dsl;
+
+            let mut pending_mgs_updates = PendingMgsUpdates::new();
+            let mut paginator = Paginator::new(SQL_BATCH_SIZE);
+            while let Some(p) = paginator.next() {
+                let batch = paginated_multicolumn(
+                    dsl::bp_pending_mgs_update,
+                    (dsl::part_number, dsl::serial_number),
+                    &p.current_pagparams(),
+                )
+                .filter(dsl::blueprint_id.eq(blueprint_id))
+                .select(BpPendingMgsUpdate::as_select())
+                .load_async(&*conn)
+                .await
+                .map_err(|e| {
+                    public_error_from_diesel(e, ErrorHandler::Server)
+                })?;
+
+                paginator = p.found_batch(&batch, &|u| {
+                    (u.part_number.clone(), u.serial_number.clone())
+                });
+
+                for u in batch {
+                    let update = u
+                        .into_pending_mgs_update()
+                        .context("pending MGS update: parse from database")
+                        .map_err(|e| {
+                            Error::internal_error(&format!("{:#}", e))
+                        })?;
+                    let old = pending_mgs_updates.insert(update);
+                    bail_unless!(
+                        old.is_none(),
+                        "found duplicate baseboard in bp_pending_mgs_update: \
+                         {:?}",
+                        old.map(|u| u.baseboard_id),
+                    );
+                }
+            }
+
+            pending_mgs_updates
+        };
+
         Ok(Blueprint {
             id: blueprint_id,
             blueprint_zones,
             blueprint_disks,
+            pending_mgs_updates,
             sled_state,
             parent_blueprint_id,
             internal_dns_version,
@@ -642,6 +706,7 @@ impl DataStore {
             nsled_agent_zones,
             nzones,
             nnics,
+            npending_mgs_updates,
         ) = conn
             .transaction_async(|conn| async move {
                 // Ensure that blueprint we're about to delete is not the
@@ -738,6 +803,16 @@ impl DataStore {
                     .await?
                 };
 
+                let npending_mgs_updates = {
+                    use db::schema::bp_pending_mgs_update::dsl;
+                    diesel::delete(
+                        dsl::bp_pending_mgs_update
+                            .filter(dsl::blueprint_id.eq(blueprint_id)),
+                    )
+                    .execute_async(&conn)
+                    .await?
+                };
+
                 Ok((
                     nblueprints,
                     nsled_states,
@@ -746,6 +821,7 @@ impl DataStore {
                     nsled_agent_zones,
                     nzones,
                     nnics,
+                    npending_mgs_updates,
                 ))
             })
             .await
@@ -765,6 +841,7 @@ impl DataStore {
             "nsled_agent_zones" => nsled_agent_zones,
             "nzones" => nzones,
             "nnics" => nnics,
+            "npending_mgs_updates" => npending_mgs_updates,
         );
 
         Ok(())
@@ -1330,6 +1407,8 @@ mod tests {
     use nexus_test_utils::db::test_setup_database;
     use nexus_types::deployment::BlueprintZoneDisposition;
     use nexus_types::deployment::BlueprintZoneFilter;
+    use nexus_types::deployment::PendingMgsUpdate;
+    use nexus_types::deployment::PendingMgsUpdateDetails;
     use nexus_types::deployment::PlanningInput;
     use nexus_types::deployment::PlanningInputBuilder;
     use nexus_types::deployment::SledDetails;
@@ -1340,8 +1419,10 @@ mod tests {
     use nexus_types::external_api::views::PhysicalDiskState;
     use nexus_types::external_api::views::SledPolicy;
     use nexus_types::inventory::Collection;
+    use nexus_types::inventory::RotSlot;
     use omicron_common::address::Ipv6Subnet;
     use omicron_common::disk::DiskIdentity;
+    use omicron_common::update::ArtifactHash;
     use omicron_test_utils::dev;
     use omicron_uuid_kinds::PhysicalDiskUuid;
     use omicron_uuid_kinds::SledUuid;
@@ -1383,6 +1464,7 @@ mod tests {
             query_count!(blueprint, id),
             query_count!(bp_omicron_zone, blueprint_id),
             query_count!(bp_omicron_zone_nic, blueprint_id),
+            query_count!(bp_pending_mgs_update, blueprint_id),
         ] {
             let count: i64 = result.unwrap();
             assert_eq!(
@@ -1449,6 +1531,35 @@ mod tests {
         // Treat this blueprint as the initial blueprint for the system.
         blueprint.parent_blueprint_id = None;
 
+        // Include one pending MGS update of each kind so that they're written
+        // to and read back from the database, too.
+        let details = [
+            PendingMgsUpdateDetails::Sp {
+                expected_active_version: String::from("1.0.0"),
+                expected_inactive_version: None,
+            },
+            PendingMgsUpdateDetails::Rot {
+                expected_active_slot: RotSlot::B,
+                expected_active_version: String::from("1.0.0"),
+                expected_inactive_version: Some(String::from("0.9.0")),
+            },
+            PendingMgsUpdateDetails::HostPhase1 {
+                expected_artifact: Some(ArtifactHash([1; 32])),
+            },
+        ];
+        for ((baseboard_id, sp), details) in collection.sps.iter().zip(details)
+        {
+            blueprint.pending_mgs_updates.insert(PendingMgsUpdate {
+                baseboard_id: (**baseboard_id).clone(),
+                sp_type: sp.sp_type,
+                slot_id: sp.sp_slot,
+                details,
+                artifact_hash: ArtifactHash([2; 32]),
+                artifact_version: "1.1.0".parse().unwrap(),
+            });
+        }
+        assert_eq!(blueprint.pending_mgs_updates.len(), 3);
+
         (collection, planning_input, blueprint)
     }
 
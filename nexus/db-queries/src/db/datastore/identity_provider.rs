@@ -13,12 +13,18 @@ use crate::db::error::ErrorHandler;
 use crate::db::identity::Resource;
 use crate::db::model::IdentityProvider;
 use crate::db::model::Name;
+use crate::db::model::OidcLoginRequest;
+use crate::db::model::OIDC_MAX_PENDING_LOGINS_PER_PROVIDER;
+use crate::db::model::OIDC_RELAY_STATE_MAX_BYTES;
 use crate::db::pagination::paginated;
 use async_bb8_diesel::AsyncRunQueryDsl;
+use chrono::Utc;
 use diesel::prelude::*;
 use omicron_common::api::external::http_pagination::PaginatedBy;
 use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::Error;
 use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
 use omicron_common::api::external::ResourceType;
 use ref_cast::RefCast;
 
@@ -115,4 +121,150 @@ impl DataStore {
                 )
             })
     }
+
+    pub async fn oidc_identity_provider_create(
+        &self,
+        opctx: &OpContext,
+        authz_idp_list: &authz::SiloIdentityProviderList,
+        provider: db::model::OidcIdentityProvider,
+    ) -> CreateResult<db::model::OidcIdentityProvider> {
+        opctx.authorize(authz::Action::CreateChild, authz_idp_list).await?;
+        assert_eq!(provider.silo_id, authz_idp_list.silo().id());
+
+        let name = provider.identity().name.to_string();
+        let conn = self.pool_connection_authorized(opctx).await?;
+
+        self.transaction_retry_wrapper("oidc_identity_provider_create")
+            .transaction(&conn, |conn| {
+                let provider = provider.clone();
+                async move {
+                    // insert silo identity provider record with type Oidc
+                    use db::schema::identity_provider::dsl as idp_dsl;
+                    diesel::insert_into(idp_dsl::identity_provider)
+                        .values(db::model::IdentityProvider {
+                            identity: db::model::IdentityProviderIdentity {
+                                id: provider.identity.id,
+                                name: provider.identity.name.clone(),
+                                description: provider
+                                    .identity
+                                    .description
+                                    .clone(),
+                                time_created: provider.identity.time_created,
+                                time_modified: provider.identity.time_modified,
+                                time_deleted: provider.identity.time_deleted,
+                            },
+                            silo_id: provider.silo_id,
+                            provider_type:
+                                db::model::IdentityProviderType::Oidc,
+                        })
+                        .execute_async(&conn)
+                        .await?;
+
+                    // insert silo oidc identity provider record
+                    use db::schema::oidc_identity_provider::dsl;
+                    let result =
+                        diesel::insert_into(dsl::oidc_identity_provider)
+                            .values(provider)
+                            .returning(
+                                db::model::OidcIdentityProvider::as_returning(),
+                            )
+                            .get_result_async(&conn)
+                            .await?;
+
+                    Ok(result)
+                }
+            })
+            .await
+            .map_err(|e| {
+                public_error_from_diesel(
+                    e,
+                    ErrorHandler::Conflict(
+                        ResourceType::OidcIdentityProvider,
+                        &name,
+                    ),
+                )
+            })
+    }
+
+    /// Record the start of an OIDC login with the given identity provider
+    ///
+    /// This happens on behalf of a user that is not yet authenticated, so the
+    /// only check is that the caller can see the identity provider.  To keep
+    /// the table from growing without bound, this first deletes any expired
+    /// login requests (for all providers) and then refuses to create more than
+    /// [`OIDC_MAX_PENDING_LOGINS_PER_PROVIDER`] unexpired requests for any one
+    /// provider.
+    pub async fn oidc_login_request_create(
+        &self,
+        opctx: &OpContext,
+        authz_idp: &authz::OidcIdentityProvider,
+        login_request: OidcLoginRequest,
+    ) -> CreateResult<OidcLoginRequest> {
+        opctx.authorize(authz::Action::Read, authz_idp).await?;
+        assert_eq!(login_request.provider_id, authz_idp.id());
+        if let Some(relay_state) = &login_request.relay_state {
+            if relay_state.len() > OIDC_RELAY_STATE_MAX_BYTES {
+                return Err(Error::invalid_request(format!(
+                    "relay state is too long (max {} bytes)",
+                    OIDC_RELAY_STATE_MAX_BYTES
+                )));
+            }
+        }
+
+        use db::schema::oidc_login_request::dsl;
+        let conn = self.pool_connection_authorized(opctx).await?;
+        let now = Utc::now();
+        diesel::delete(dsl::oidc_login_request)
+            .filter(dsl::time_expires.lt(now))
+            .execute_async(&*conn)
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))?;
+
+        // This check and the insert below aren't atomic, so concurrent logins
+        // can overshoot the limit slightly.  That's fine: the point is to keep
+        // the table bounded, not to enforce an exact count.
+        let npending: i64 = dsl::oidc_login_request
+            .filter(dsl::provider_id.eq(authz_idp.id()))
+            .filter(dsl::time_expires.ge(now))
+            .count()
+            .get_result_async(&*conn)
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))?;
+        if npending >= OIDC_MAX_PENDING_LOGINS_PER_PROVIDER {
+            return Err(Error::unavail(
+                "too many logins in progress for this identity provider",
+            ));
+        }
+
+        diesel::insert_into(dsl::oidc_login_request)
+            .values(login_request)
+            .returning(OidcLoginRequest::as_returning())
+            .get_result_async(&*conn)
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))
+    }
+
+    /// Remove and return the in-progress OIDC login with the given state
+    ///
+    /// Each login request can be used at most once.  Returns `None` if there's
+    /// no such request for this identity provider.  The request may already be
+    /// expired; it's up to the caller to check.
+    pub async fn oidc_login_request_consume(
+        &self,
+        opctx: &OpContext,
+        authz_idp: &authz::OidcIdentityProvider,
+        state: &str,
+    ) -> LookupResult<Option<OidcLoginRequest>> {
+        opctx.authorize(authz::Action::Read, authz_idp).await?;
+
+        use db::schema::oidc_login_request::dsl;
+        diesel::delete(dsl::oidc_login_request)
+            .filter(dsl::state.eq(state.to_string()))
+            .filter(dsl::provider_id.eq(authz_idp.id()))
+            .returning(OidcLoginRequest::as_returning())
+            .get_result_async(&*self.pool_connection_authorized(opctx).await?)
+            .await
+            .optional()
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))
+    }
 }
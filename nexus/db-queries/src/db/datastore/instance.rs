@@ -526,6 +526,8 @@ impl DataStore {
             })?;
 
         self.instance_ssh_keys_delete(opctx, authz_instance.id()).await?;
+        self.instance_serial_console_log_delete(opctx, authz_instance.id())
+            .await?;
 
         Ok(())
     }
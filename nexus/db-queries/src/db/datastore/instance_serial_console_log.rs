@@ -0,0 +1,412 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! [`DataStore`] methods on [`InstanceSerialConsoleChunk`]s.
+
+use super::DataStore;
+use crate::authz;
+use crate::context::OpContext;
+use crate::db::error::public_error_from_diesel;
+use crate::db::error::ErrorHandler;
+use crate::db::model::InstanceSerialConsoleChunk;
+use crate::db::schema::instance_serial_console_log::dsl;
+use crate::db::schema::vmm::dsl as vmm_dsl;
+use async_bb8_diesel::AsyncRunQueryDsl;
+use chrono::DateTime;
+use chrono::Utc;
+use diesel::prelude::*;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::UpdateResult;
+use uuid::Uuid;
+
+/// Maximum number of bytes of serial console output kept for each instance
+///
+/// The oldest chunks are deleted as new output is captured.
+pub const INSTANCE_SERIAL_CONSOLE_LOG_MAX_BYTES: usize = 256 * 1024;
+
+/// Maximum number of chunks of serial console output kept for each instance
+///
+/// This bounds the number of rows for an instance that produces output a few
+/// bytes at a time.
+pub const INSTANCE_SERIAL_CONSOLE_LOG_MAX_CHUNKS: usize = 1024;
+
+/// Identifies how far into an instance's serial console output we've captured
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SerialConsoleCursor {
+    /// Offset, in all of the instance's output, just past the last chunk
+    pub end_offset: u64,
+    /// The VMM that produced the last chunk
+    pub vmm_id: Uuid,
+    /// Offset, in the output of `vmm_id`, just past the last chunk
+    pub vmm_offset: u64,
+}
+
+impl From<&InstanceSerialConsoleChunk> for SerialConsoleCursor {
+    fn from(chunk: &InstanceSerialConsoleChunk) -> Self {
+        SerialConsoleCursor {
+            end_offset: chunk.end_offset as u64,
+            vmm_id: chunk.vmm_id,
+            vmm_offset: chunk.vmm_offset as u64,
+        }
+    }
+}
+
+/// Output captured from a VMM's serial console, to be appended to its
+/// instance's log
+#[derive(Clone, Debug)]
+pub struct SerialConsoleCapture {
+    pub vmm_id: Uuid,
+    /// Offset, in the output of `vmm_id`, just past the end of `data`
+    pub vmm_offset: u64,
+    /// Number of bytes of output produced before `data` that were lost before
+    /// they could be captured
+    pub lost: u64,
+    pub data: Vec<u8>,
+}
+
+impl SerialConsoleCapture {
+    /// Returns the chunks that appending this output after `prev` produces:
+    /// one recording the lost output, if any, followed by one holding the
+    /// captured data, if any.
+    pub fn chunks(
+        &self,
+        instance_id: Uuid,
+        prev: Option<SerialConsoleCursor>,
+    ) -> Vec<InstanceSerialConsoleChunk> {
+        let now = Utc::now();
+        let data_len = self.data.len() as u64;
+        let mut offset = prev.map_or(0, |cursor| cursor.end_offset);
+        let mut chunks = Vec::new();
+        if self.lost > 0 {
+            chunks.push(InstanceSerialConsoleChunk {
+                instance_id,
+                start_offset: offset as i64,
+                end_offset: (offset + self.lost) as i64,
+                time_created: now,
+                data: None,
+                vmm_id: self.vmm_id,
+                vmm_offset: (self.vmm_offset - data_len) as i64,
+            });
+            offset += self.lost;
+        }
+        if data_len > 0 {
+            chunks.push(InstanceSerialConsoleChunk {
+                instance_id,
+                start_offset: offset as i64,
+                end_offset: (offset + data_len) as i64,
+                time_created: now,
+                data: Some(self.data.clone()),
+                vmm_id: self.vmm_id,
+                vmm_offset: self.vmm_offset as i64,
+            });
+        }
+        chunks
+    }
+}
+
+impl DataStore {
+    /// Lists the chunks of serial console output captured for an instance,
+    /// oldest first
+    pub async fn instance_serial_console_log_list(
+        &self,
+        opctx: &OpContext,
+        authz_instance: &authz::Instance,
+    ) -> ListResultVec<InstanceSerialConsoleChunk> {
+        opctx.authorize(authz::Action::Read, authz_instance).await?;
+
+        // Concurrent appends may briefly leave more than the maximum number of
+        // chunks; the newest ones are the ones we want.
+        let mut chunks = dsl::instance_serial_console_log
+            .filter(dsl::instance_id.eq(authz_instance.id()))
+            .order(dsl::start_offset.desc())
+            .limit(INSTANCE_SERIAL_CONSOLE_LOG_MAX_CHUNKS as i64)
+            .select(InstanceSerialConsoleChunk::as_select())
+            .load_async(&*self.pool_connection_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))?;
+        chunks.reverse();
+        Ok(chunks)
+    }
+
+    /// Returns how far we've captured an instance's serial console output, or
+    /// `None` if we haven't captured any yet.
+    pub async fn instance_serial_console_log_cursor(
+        &self,
+        opctx: &OpContext,
+        instance_id: Uuid,
+    ) -> LookupResult<Option<SerialConsoleCursor>> {
+        opctx.authorize(authz::Action::Read, &authz::FLEET).await?;
+
+        let cursor = dsl::instance_serial_console_log
+            .filter(dsl::instance_id.eq(instance_id))
+            .order(dsl::start_offset.desc())
+            .select((dsl::end_offset, dsl::vmm_id, dsl::vmm_offset))
+            .first_async::<(i64, Uuid, i64)>(
+                &*self.pool_connection_authorized(opctx).await?,
+            )
+            .await
+            .optional()
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))?;
+
+        Ok(cursor.map(|(end_offset, vmm_id, vmm_offset)| SerialConsoleCursor {
+            end_offset: end_offset as u64,
+            vmm_id,
+            vmm_offset: vmm_offset as u64,
+        }))
+    }
+
+    /// Append output captured from a VMM to its instance's serial console log
+    ///
+    /// `prev` must be the cursor returned by
+    /// [`DataStore::instance_serial_console_log_cursor()`] before the output
+    /// was fetched.  If `capture.lost` is nonzero, a chunk recording the lost
+    /// output is appended before the captured data.
+    ///
+    /// Chunks are never modified once written.  If another chunk has been
+    /// appended since `prev` was read (e.g., because another Nexus captured
+    /// the same output concurrently), or if `prev` was produced by a newer VMM
+    /// than `capture` was, nothing is appended and this returns `false`.
+    pub async fn instance_serial_console_log_append(
+        &self,
+        opctx: &OpContext,
+        instance_id: Uuid,
+        prev: Option<SerialConsoleCursor>,
+        capture: &SerialConsoleCapture,
+    ) -> UpdateResult<bool> {
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+
+        let vmm_id = capture.vmm_id;
+        let chunks = capture.chunks(instance_id, prev);
+        let Some(end_offset) = chunks.last().map(|c| c.end_offset as u64)
+        else {
+            return Ok(false);
+        };
+
+        let conn = self.pool_connection_authorized(opctx).await?;
+        self.transaction_retry_wrapper("instance_serial_console_log_append")
+            .transaction(&conn, |conn| {
+                let chunks = chunks.clone();
+                async move {
+                    // Output from an older VMM (e.g., the source of a
+                    // migration) can't follow output from a newer one, since
+                    // that would put it after output it preceded.
+                    if let Some(prev) = prev.filter(|p| p.vmm_id != vmm_id) {
+                        let prev_created = vmm_dsl::vmm
+                            .filter(vmm_dsl::id.eq(prev.vmm_id))
+                            .select(vmm_dsl::time_created)
+                            .get_result_async::<DateTime<Utc>>(&conn)
+                            .await
+                            .optional()?;
+                        let created = vmm_dsl::vmm
+                            .filter(vmm_dsl::id.eq(vmm_id))
+                            .select(vmm_dsl::time_created)
+                            .get_result_async::<DateTime<Utc>>(&conn)
+                            .await
+                            .optional()?;
+                        if let (Some(prev_created), Some(created)) =
+                            (prev_created, created)
+                        {
+                            if prev_created > created {
+                                return Ok(false);
+                            }
+                        }
+                    }
+
+                    let mut chunks = chunks.into_iter();
+
+                    // The first chunk starts where `prev` ended, so it
+                    // conflicts with whatever has been appended since.
+                    let first = chunks.next().unwrap();
+                    let inserted =
+                        diesel::insert_into(dsl::instance_serial_console_log)
+                            .values(first)
+                            .on_conflict_do_nothing()
+                            .execute_async(&conn)
+                            .await?;
+                    if inserted == 0 {
+                        return Ok(false);
+                    }
+                    for chunk in chunks {
+                        diesel::insert_into(dsl::instance_serial_console_log)
+                            .values(chunk)
+                            .execute_async(&conn)
+                            .await?;
+                    }
+
+                    // Discard the oldest output, by size and by number of
+                    // chunks.
+                    let min_end_offset = end_offset.saturating_sub(
+                        INSTANCE_SERIAL_CONSOLE_LOG_MAX_BYTES as u64,
+                    );
+                    diesel::delete(dsl::instance_serial_console_log)
+                        .filter(dsl::instance_id.eq(instance_id))
+                        .filter(dsl::end_offset.le(min_end_offset as i64))
+                        .execute_async(&conn)
+                        .await?;
+                    let oldest_kept = dsl::instance_serial_console_log
+                        .filter(dsl::instance_id.eq(instance_id))
+                        .order(dsl::start_offset.desc())
+                        .offset(
+                            INSTANCE_SERIAL_CONSOLE_LOG_MAX_CHUNKS as i64 - 1,
+                        )
+                        .select(dsl::start_offset)
+                        .first_async::<i64>(&conn)
+                        .await
+                        .optional()?;
+                    if let Some(oldest_kept) = oldest_kept {
+                        diesel::delete(dsl::instance_serial_console_log)
+                            .filter(dsl::instance_id.eq(instance_id))
+                            .filter(dsl::start_offset.lt(oldest_kept))
+                            .execute_async(&conn)
+                            .await?;
+                    }
+
+                    Ok(true)
+                }
+            })
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))
+    }
+
+    /// Delete the serial console output captured for an instance
+    pub(super) async fn instance_serial_console_log_delete(
+        &self,
+        opctx: &OpContext,
+        instance_id: Uuid,
+    ) -> DeleteResult {
+        diesel::delete(dsl::instance_serial_console_log)
+            .filter(dsl::instance_id.eq(instance_id))
+            .execute_async(&*self.pool_connection_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::datastore::test_utils::datastore_test;
+    use nexus_test_utils::db::test_setup_database;
+    use omicron_test_utils::dev;
+
+    #[tokio::test]
+    async fn test_instance_serial_console_log_append() {
+        let logctx =
+            dev::test_setup_log("test_instance_serial_console_log_append");
+        let mut db = test_setup_database(&logctx.log).await;
+        let (opctx, datastore) = datastore_test(&logctx, &db).await;
+
+        let instance_id = Uuid::new_v4();
+        let vmm1 = Uuid::new_v4();
+        let vmm2 = Uuid::new_v4();
+        let cursor = || async {
+            datastore
+                .instance_serial_console_log_cursor(&opctx, instance_id)
+                .await
+                .unwrap()
+        };
+        let append = |prev, vmm_id, vmm_offset, lost, data: &[u8]| {
+            let capture = SerialConsoleCapture {
+                vmm_id,
+                vmm_offset,
+                lost,
+                data: data.to_vec(),
+            };
+            let datastore = &datastore;
+            let opctx = &opctx;
+            async move {
+                datastore
+                    .instance_serial_console_log_append(
+                        opctx,
+                        instance_id,
+                        prev,
+                        &capture,
+                    )
+                    .await
+                    .unwrap()
+            }
+        };
+        let chunks = || async {
+            dsl::instance_serial_console_log
+                .filter(dsl::instance_id.eq(instance_id))
+                .order(dsl::start_offset.asc())
+                .select(InstanceSerialConsoleChunk::as_select())
+                .load_async(
+                    &*datastore.pool_connection_for_tests().await.unwrap(),
+                )
+                .await
+                .unwrap()
+                .into_iter()
+                .map(|chunk| (chunk.start_offset, chunk.end_offset, chunk.data))
+                .collect::<Vec<_>>()
+        };
+
+        // Nothing has been captured yet.
+        assert_eq!(cursor().await, None);
+
+        // Capture some output from the first VMM.
+        assert!(append(None, vmm1, 6, 0, b"hello\n").await);
+        let cursor1 =
+            SerialConsoleCursor { end_offset: 6, vmm_id: vmm1, vmm_offset: 6 };
+        assert_eq!(cursor().await, Some(cursor1));
+
+        // An append based on a stale cursor is ignored, even if it carries
+        // more output than what was appended.
+        assert!(!append(None, vmm1, 9, 0, b"hello\nhi\n").await);
+        assert_eq!(cursor().await, Some(cursor1));
+
+        // Output from a new VMM (e.g., after a migration) is appended to the
+        // output from the old one.
+        assert!(append(Some(cursor1), vmm2, 3, 0, b"hi\n").await);
+        let cursor2 =
+            SerialConsoleCursor { end_offset: 9, vmm_id: vmm2, vmm_offset: 3 };
+        assert_eq!(cursor().await, Some(cursor2));
+
+        // Lost output is recorded as a gap before the captured output.
+        assert!(append(Some(cursor2), vmm2, 10, 5, b"ok").await);
+        let cursor3 = SerialConsoleCursor {
+            end_offset: 16,
+            vmm_id: vmm2,
+            vmm_offset: 10,
+        };
+        assert_eq!(cursor().await, Some(cursor3));
+        assert_eq!(
+            chunks().await,
+            vec![
+                (0, 6, Some(b"hello\n".to_vec())),
+                (6, 9, Some(b"hi\n".to_vec())),
+                (9, 14, None),
+                (14, 16, Some(b"ok".to_vec())),
+            ]
+        );
+
+        // Once the log is full, the oldest chunks are discarded.
+        let big = vec![b'x'; INSTANCE_SERIAL_CONSOLE_LOG_MAX_BYTES - 10];
+        assert!(
+            append(Some(cursor3), vmm2, 10 + big.len() as u64, 0, &big).await
+        );
+        let end = 16 + big.len() as i64;
+        assert_eq!(
+            chunks().await,
+            vec![
+                (6, 9, Some(b"hi\n".to_vec())),
+                (9, 14, None),
+                (14, 16, Some(b"ok".to_vec())),
+                (16, end, Some(big.clone())),
+            ]
+        );
+
+        datastore
+            .instance_serial_console_log_delete(&opctx, instance_id)
+            .await
+            .unwrap();
+        assert_eq!(cursor().await, None);
+
+        db.cleanup().await.unwrap();
+        logctx.cleanup_successful();
+    }
+}
@@ -46,10 +46,15 @@ use nexus_db_model::InvRotPage;
 use nexus_db_model::InvServiceProcessor;
 use nexus_db_model::InvSledAgent;
 use nexus_db_model::InvSledOmicronZones;
+use nexus_db_model::InvSpSensor;
+use nexus_db_model::InvSwitch;
+use nexus_db_model::InvSwitchPort;
 use nexus_db_model::InvZpool;
 use nexus_db_model::RotPageWhichEnum;
 use nexus_db_model::SledRole;
 use nexus_db_model::SledRoleEnum;
+use nexus_db_model::SpSensorKind;
+use nexus_db_model::SpSensorKindEnum;
 use nexus_db_model::SpType;
 use nexus_db_model::SpTypeEnum;
 use nexus_db_model::SqlU16;
@@ -113,6 +118,22 @@ impl DataStore {
             .iter()
             .map(|p| SwRotPage::from((**p).clone()))
             .collect::<Vec<_>>();
+        let sensor_readings = collection
+            .sensor_readings
+            .iter()
+            .flat_map(|(baseboard_id, readings)| {
+                readings.iter().enumerate().map(move |(i, reading)| {
+                    let idx = i32::try_from(i).map_err(|e| {
+                        Error::internal_error(&format!(
+                            "failed to convert sensor reading index to i32 \
+                            (too many sensor readings for baseboard {:?}?): {}",
+                            baseboard_id, e
+                        ))
+                    })?;
+                    Ok((baseboard_id, idx, reading))
+                })
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
         let error_values = collection
             .errors
             .iter()
@@ -204,6 +225,25 @@ impl DataStore {
                 })
             })
             .collect::<Result<Vec<InvOmicronZoneNic>, _>>()?;
+        let switches = collection
+            .switches
+            .values()
+            .map(|switch| InvSwitch::new(collection_id, switch))
+            .collect::<Vec<_>>();
+        let switch_ports = collection
+            .switches
+            .values()
+            .flat_map(|switch| {
+                switch.ports.iter().map(|(port_id, port)| {
+                    InvSwitchPort::new(
+                        collection_id,
+                        switch.switch_location,
+                        port_id,
+                        port,
+                    )
+                })
+            })
+            .collect::<Vec<_>>();
 
         // This implementation inserts all records associated with the
         // collection in one transaction.  This is primarily for simplicity.  It
@@ -673,6 +713,100 @@ impl DataStore {
                 }
             }
 
+            // Insert rows for the SP sensor readings that we found.  Like
+            // service processors, we do this using INSERT INTO ... SELECT.
+            // Each reading's position in the list reported by its SP becomes
+            // its "idx" so that we can reconstruct the list in order.
+            {
+                use db::schema::hw_baseboard_id::dsl as baseboard_dsl;
+                use db::schema::inv_sp_sensor::dsl as sensor_dsl;
+
+                for (baseboard_id, idx, reading) in &sensor_readings {
+                    let (value, error) = match &reading.value {
+                        Ok(value) => (Some(*value), None),
+                        Err(error) => (None, Some(error.clone())),
+                    };
+                    let selection = db::schema::hw_baseboard_id::table
+                        .select((
+                            db_collection_id
+                                .into_sql::<diesel::sql_types::Uuid>(),
+                            baseboard_dsl::id,
+                            (*idx).into_sql::<diesel::sql_types::Int4>(),
+                            reading
+                                .time_collected
+                                .into_sql::<diesel::sql_types::Timestamptz>(),
+                            reading
+                                .source
+                                .clone()
+                                .into_sql::<diesel::sql_types::Text>(),
+                            reading
+                                .component
+                                .clone()
+                                .into_sql::<diesel::sql_types::Text>(),
+                            reading
+                                .device
+                                .clone()
+                                .into_sql::<diesel::sql_types::Text>(),
+                            reading
+                                .name
+                                .clone()
+                                .into_sql::<diesel::sql_types::Text>(),
+                            SpSensorKind::from(reading.kind)
+                                .into_sql::<SpSensorKindEnum>(),
+                            value.into_sql::<Nullable<diesel::sql_types::Float4>>(
+                            ),
+                            error.into_sql::<Nullable<diesel::sql_types::Text>>(
+                            ),
+                        ))
+                        .filter(
+                            baseboard_dsl::part_number
+                                .eq(baseboard_id.part_number.clone()),
+                        )
+                        .filter(
+                            baseboard_dsl::serial_number
+                                .eq(baseboard_id.serial_number.clone()),
+                        );
+
+                    let _ = diesel::insert_into(
+                        db::schema::inv_sp_sensor::table,
+                    )
+                    .values(selection)
+                    .into_columns((
+                        sensor_dsl::inv_collection_id,
+                        sensor_dsl::hw_baseboard_id,
+                        sensor_dsl::idx,
+                        sensor_dsl::time_collected,
+                        sensor_dsl::source,
+                        sensor_dsl::component,
+                        sensor_dsl::device,
+                        sensor_dsl::name,
+                        sensor_dsl::kind,
+                        sensor_dsl::value,
+                        sensor_dsl::error,
+                    ))
+                    .execute_async(&conn)
+                    .await?;
+
+                    // See the comment above about `all_columns()`.  If you
+                    // update the statement below because the schema for
+                    // `inv_sp_sensor` has changed, be sure to update the code
+                    // above, too!
+                    let (
+                        _inv_collection_id,
+                        _hw_baseboard_id,
+                        _idx,
+                        _time_collected,
+                        _source,
+                        _component,
+                        _device,
+                        _name,
+                        _kind,
+                        _value,
+                        _error,
+                    ) = sensor_dsl::inv_sp_sensor::all_columns();
+                }
+            }
+
             // Insert rows for all the physical disks we found.
             {
                 use db::schema::inv_physical_disk::dsl;
@@ -842,6 +976,22 @@ impl DataStore {
                         .await?;
             }
 
+            {
+                use db::schema::inv_switch::dsl as switch_dsl;
+                let _ = diesel::insert_into(switch_dsl::inv_switch)
+                    .values(switches)
+                    .execute_async(&conn)
+                    .await?;
+            }
+
+            {
+                use db::schema::inv_switch_port::dsl as switch_port_dsl;
+                let _ = diesel::insert_into(switch_port_dsl::inv_switch_port)
+                    .values(switch_ports)
+                    .execute_async(&conn)
+                    .await?;
+            }
+
             // Finally, insert the list of errors.
             {
                 use db::schema::inv_collection_error::dsl as errors_dsl;
@@ -1101,12 +1251,15 @@ impl DataStore {
             nrots,
             ncabooses,
             nrot_pages,
+            nsensors,
             nsled_agents,
             nphysical_disks,
             nsled_agent_zones,
             nzones,
             nnics,
             nzpools,
+            nswitches,
+            nswitch_ports,
             nerrors,
         ) = conn
             .transaction_async(|conn| async move {
@@ -1165,6 +1318,17 @@ impl DataStore {
                         .await?
                     };
 
+                // Remove rows for SP sensor readings found.
+                let nsensors =
+                    {
+                        use db::schema::inv_sp_sensor::dsl;
+                        diesel::delete(dsl::inv_sp_sensor.filter(
+                            dsl::inv_collection_id.eq(db_collection_id),
+                        ))
+                        .execute_async(&conn)
+                        .await?
+                    };
+
                 // Remove rows for sled agents found.
                 let nsled_agents =
                     {
@@ -1228,6 +1392,27 @@ impl DataStore {
                         .await?
                     };
 
+                // Remove rows associated with switches.
+                let nswitches =
+                    {
+                        use db::schema::inv_switch::dsl;
+                        diesel::delete(dsl::inv_switch.filter(
+                            dsl::inv_collection_id.eq(db_collection_id),
+                        ))
+                        .execute_async(&conn)
+                        .await?
+                    };
+
+                let nswitch_ports =
+                    {
+                        use db::schema::inv_switch_port::dsl;
+                        diesel::delete(dsl::inv_switch_port.filter(
+                            dsl::inv_collection_id.eq(db_collection_id),
+                        ))
+                        .execute_async(&conn)
+                        .await?
+                    };
+
                 // Remove rows for errors encountered.
                 let nerrors =
                     {
@@ -1245,12 +1430,15 @@ impl DataStore {
                     nrots,
                     ncabooses,
                     nrot_pages,
+                    nsensors,
                     nsled_agents,
                     nphysical_disks,
                     nsled_agent_zones,
                     nzones,
                     nnics,
                     nzpools,
+                    nswitches,
+                    nswitch_ports,
                     nerrors,
                 ))
             })
@@ -1269,12 +1457,15 @@ impl DataStore {
             "nrots" => nrots,
             "ncabooses" => ncabooses,
             "nrot_pages" => nrot_pages,
+            "nsensors" => nsensors,
             "nsled_agents" => nsled_agents,
             "nphysical_disks" => nphysical_disks,
             "nsled_agent_zones" => nsled_agent_zones,
             "nzones" => nzones,
             "nnics" => nnics,
             "nzpools" => nzpools,
+            "nswitches" => nswitches,
+            "nswitch_ports" => nswitch_ports,
             "nerrors" => nerrors,
         );
 
@@ -1312,6 +1503,23 @@ impl DataStore {
         &self,
         opctx: &OpContext,
     ) -> Result<Option<Collection>, Error> {
+        let Some(collection_id) =
+            self.inventory_get_latest_collection_id(opctx).await?
+        else {
+            return Ok(None);
+        };
+
+        Ok(Some(self.inventory_collection_read(opctx, collection_id).await?))
+    }
+
+    /// Attempt to read the id of the latest collection, without reading the
+    /// collection itself.
+    ///
+    /// If there aren't any collections, return `Ok(None)`.
+    pub async fn inventory_get_latest_collection_id(
+        &self,
+        opctx: &OpContext,
+    ) -> Result<Option<CollectionUuid>, Error> {
         opctx.authorize(authz::Action::Read, &authz::INVENTORY).await?;
         let conn = self.pool_connection_authorized(opctx).await?;
         use db::schema::inv_collection::dsl;
@@ -1323,17 +1531,7 @@ impl DataStore {
             .optional()
             .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))?;
 
-        let Some(collection_id) = collection_id else {
-            return Ok(None);
-        };
-
-        Ok(Some(
-            self.inventory_collection_read(
-                opctx,
-                CollectionUuid::from_untyped_uuid(collection_id),
-            )
-            .await?,
-        ))
+        Ok(collection_id.map(CollectionUuid::from_untyped_uuid))
     }
 
     /// Attempt to read the current collection
@@ -1892,6 +2090,50 @@ impl DataStore {
             );
         }
 
+        // Fetch records of SP sensor readings found.  These come back ordered
+        // by baseboard and then by the order in which the SP reported them.
+        let mut sensor_readings: BTreeMap<_, Vec<_>> = BTreeMap::new();
+        {
+            use db::schema::inv_sp_sensor::dsl;
+
+            let mut paginator = Paginator::new(batch_size);
+            while let Some(p) = paginator.next() {
+                let batch = paginated_multicolumn(
+                    dsl::inv_sp_sensor,
+                    (dsl::hw_baseboard_id, dsl::idx),
+                    &p.current_pagparams(),
+                )
+                .filter(dsl::inv_collection_id.eq(db_id))
+                .select(InvSpSensor::as_select())
+                .load_async(&*conn)
+                .await
+                .map_err(|e| {
+                    public_error_from_diesel(e, ErrorHandler::Server)
+                })?;
+                paginator = p
+                    .found_batch(&batch, &|row| (row.hw_baseboard_id, row.idx));
+                for row in batch {
+                    let Some(bb) = baseboards_by_id.get(&row.hw_baseboard_id)
+                    else {
+                        let msg = format!(
+                            "unknown baseboard found in inv_sp_sensor: {}",
+                            row.hw_baseboard_id
+                        );
+                        return Err(Error::internal_error(&msg));
+                    };
+                    let reading =
+                        nexus_types::inventory::SensorReading::try_from(row)
+                            .map_err(|e| {
+                                Error::internal_error(&format!("{:#}", e))
+                            })?;
+                    sensor_readings
+                        .entry(bb.clone())
+                        .or_default()
+                        .push(reading);
+                }
+            }
+        }
+
         // Now read the Omicron zones.
         //
         // In the first pass, we'll load the "inv_sled_omicron_zones" records.
@@ -2040,6 +2282,87 @@ impl DataStore {
             omicron_zone_nics.keys()
         );
 
+        // Load the switches found, keyed by location.  These get filled in
+        // with their ports below.
+        let mut switches: BTreeMap<_, _> = {
+            use db::schema::inv_switch::dsl;
+
+            let mut switches = BTreeMap::new();
+
+            let mut paginator = Paginator::new(batch_size);
+            while let Some(p) = paginator.next() {
+                let batch = paginated(
+                    dsl::inv_switch,
+                    dsl::switch_location,
+                    &p.current_pagparams(),
+                )
+                .filter(dsl::inv_collection_id.eq(db_id))
+                .select(InvSwitch::as_select())
+                .load_async(&*conn)
+                .await
+                .map_err(|e| {
+                    public_error_from_diesel(e, ErrorHandler::Server)
+                })?;
+                paginator =
+                    p.found_batch(&batch, &|row| row.switch_location.clone());
+                for row in batch {
+                    let switch = row.into_uninit_switch().map_err(|e| {
+                        Error::internal_error(&format!("{:#}", e))
+                    })?;
+                    switches.insert(switch.switch_location.to_string(), switch);
+                }
+            }
+
+            switches
+        };
+
+        {
+            use db::schema::inv_switch_port::dsl;
+
+            let mut paginator = Paginator::new(batch_size);
+            while let Some(p) = paginator.next() {
+                let batch = paginated_multicolumn(
+                    dsl::inv_switch_port,
+                    (dsl::switch_location, dsl::port_id),
+                    &p.current_pagparams(),
+                )
+                .filter(dsl::inv_collection_id.eq(db_id))
+                .select(InvSwitchPort::as_select())
+                .load_async(&*conn)
+                .await
+                .map_err(|e| {
+                    public_error_from_diesel(e, ErrorHandler::Server)
+                })?;
+                paginator = p.found_batch(&batch, &|row| {
+                    (row.switch_location.clone(), row.port_id.clone())
+                });
+                for row in batch {
+                    // This error means that we found a row in inv_switch_port
+                    // with no associated record in inv_switch.  This should
+                    // be impossible and reflects either a bug or database
+                    // corruption.
+                    let switch = switches
+                        .get_mut(&row.switch_location)
+                        .ok_or_else(|| {
+                            Error::internal_error(&format!(
+                                "switch port {:?}: unknown switch: {:?}",
+                                row.port_id, row.switch_location
+                            ))
+                        })?;
+                    let port_id = row.port_id.clone();
+                    let port = row.try_into().map_err(|e: anyhow::Error| {
+                        Error::internal_error(&format!("{:#}", e))
+                    })?;
+                    switch.ports.insert(port_id, port);
+                }
+            }
+        }
+
+        let switches = switches
+            .into_values()
+            .map(|switch| (switch.switch_location, switch))
+            .collect();
+
         Ok(Collection {
             id,
             errors,
@@ -2053,8 +2376,10 @@ impl DataStore {
             rots,
             cabooses_found,
             rot_pages_found,
+            sensor_readings,
             sled_agents,
             omicron_zones,
+            switches,
         })
     }
 }
@@ -2653,6 +2978,24 @@ mod test {
                 .await
                 .unwrap();
             assert_eq!(0, count);
+            let count = schema::inv_switch::dsl::inv_switch
+                .select(diesel::dsl::count_star())
+                .first_async::<i64>(&conn)
+                .await
+                .unwrap();
+            assert_eq!(0, count);
+            let count = schema::inv_switch_port::dsl::inv_switch_port
+                .select(diesel::dsl::count_star())
+                .first_async::<i64>(&conn)
+                .await
+                .unwrap();
+            assert_eq!(0, count);
+            let count = schema::inv_sp_sensor::dsl::inv_sp_sensor
+                .select(diesel::dsl::count_star())
+                .first_async::<i64>(&conn)
+                .await
+                .unwrap();
+            assert_eq!(0, count);
 
             Ok::<(), anyhow::Error>(())
         })
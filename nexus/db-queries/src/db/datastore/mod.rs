@@ -49,6 +49,7 @@ use uuid::Uuid;
 
 mod address_lot;
 mod allow_list;
+mod api_token;
 mod bfd;
 mod bgp;
 mod bootstore;
@@ -64,6 +65,7 @@ mod external_ip;
 mod identity_provider;
 mod image;
 mod instance;
+mod instance_serial_console_log;
 mod inventory;
 mod ip_pool;
 mod ipv4_nat_entry;
@@ -80,6 +82,8 @@ mod region;
 mod region_snapshot;
 mod role;
 mod saga;
+mod scim_client_bearer_token;
+mod service_account;
 mod silo;
 mod silo_group;
 mod silo_user;
@@ -104,15 +108,21 @@ pub use address_lot::AddressLotCreateResult;
 pub use dns::DataStoreDnsTest;
 pub use dns::DnsVersionUpdateBuilder;
 pub use instance::InstanceAndActiveVmm;
+pub use instance_serial_console_log::SerialConsoleCapture;
+pub use instance_serial_console_log::SerialConsoleCursor;
+pub use instance_serial_console_log::INSTANCE_SERIAL_CONSOLE_LOG_MAX_BYTES;
 pub use inventory::DataStoreInventoryTest;
 use nexus_db_model::AllSchemaVersions;
 pub use probe::ProbeInfo;
 pub use rack::RackInit;
 pub use rack::SledUnderlayAllocationResult;
+pub use saga::SagaAbandonPlan;
+pub use saga::SagaAbandonVmm;
 pub use silo::Discoverability;
 pub use sled::SledTransition;
 pub use sled::TransitionError;
 pub use switch_port::SwitchPortSettingsCombinedResult;
+pub use update::MGS_UPDATE_DELIVERY_TIMEOUT;
 pub use virtual_provisioning_collection::StorageType;
 pub use volume::read_only_resources_associated_with_volume;
 pub use volume::CrucibleResources;
@@ -607,6 +617,89 @@ mod test {
         logctx.cleanup_successful();
     }
 
+    #[tokio::test]
+    async fn test_silo_user_set_active() {
+        let logctx = dev::test_setup_log("test_silo_user_set_active");
+        let mut db = test_setup_database(&logctx.log).await;
+        let (opctx, datastore) = datastore_test(&logctx, &db).await;
+        let authn_opctx = OpContext::for_background(
+            logctx.log.new(o!("component" => "TestExternalAuthn")),
+            Arc::new(authz::Authz::new(&logctx.log)),
+            authn::Context::external_authn(),
+            Arc::clone(&datastore),
+        );
+
+        let authz_silo = authz::Silo::new(
+            authz::FLEET,
+            *DEFAULT_SILO_ID,
+            LookupType::ById(*DEFAULT_SILO_ID),
+        );
+        let silo_user_id = Uuid::new_v4();
+        let (authz_silo_user, db_silo_user) = datastore
+            .silo_user_create(
+                &authz_silo,
+                SiloUser::new(
+                    authz_silo.id(),
+                    silo_user_id,
+                    "external_id".into(),
+                ),
+            )
+            .await
+            .unwrap();
+        assert!(db_silo_user.active);
+
+        let token = "a_token".to_string();
+        datastore
+            .session_create(
+                &authn_opctx,
+                ConsoleSession {
+                    token: token.clone(),
+                    time_created: Utc::now(),
+                    time_last_used: Utc::now(),
+                    silo_user_id,
+                },
+            )
+            .await
+            .unwrap();
+
+        // Deactivating the user revokes their session but keeps the user.
+        let deactivated = datastore
+            .silo_user_set_active(&opctx, &authz_silo_user, false)
+            .await
+            .unwrap();
+        assert!(!deactivated.active);
+        let fetched = LookupPath::new(&opctx, &datastore)
+            .console_session_token(&token)
+            .fetch()
+            .await;
+        assert!(matches!(fetched, Err(Error::ObjectNotFound { .. })));
+        let (.., fetched) = LookupPath::new(&opctx, &datastore)
+            .silo_user_id(silo_user_id)
+            .fetch()
+            .await
+            .unwrap();
+        assert!(!fetched.active);
+
+        // Reactivating them keeps their id.
+        let reactivated = datastore
+            .silo_user_set_active(&opctx, &authz_silo_user, true)
+            .await
+            .unwrap();
+        assert!(reactivated.active);
+        assert_eq!(reactivated.id(), silo_user_id);
+
+        // A deleted user can't be reactivated.
+        datastore.silo_user_delete(&opctx, &authz_silo_user).await.unwrap();
+        let error = datastore
+            .silo_user_set_active(&opctx, &authz_silo_user, true)
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::ObjectNotFound { .. }), "{error:?}");
+
+        db.cleanup().await.unwrap();
+        logctx.cleanup_successful();
+    }
+
     // Creates a test sled, returns its UUID.
     async fn create_test_sled(datastore: &DataStore) -> SledUuid {
         let bogus_addr = SocketAddrV6::new(
@@ -284,6 +284,14 @@ impl DataStore {
                         db_project.id(),
                     )
                     .await?;
+
+                    {
+                        use db::schema::project_quotas::dsl;
+                        diesel::delete(dsl::project_quotas)
+                            .filter(dsl::project_id.eq(db_project.id()))
+                            .execute_async(&conn)
+                            .await?;
+                    }
                     Ok(())
                 }
             })
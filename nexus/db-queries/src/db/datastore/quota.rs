@@ -7,7 +7,9 @@ use crate::db::error::ErrorHandler;
 use crate::db::pagination::paginated;
 use crate::db::pool::DbConnection;
 use async_bb8_diesel::AsyncRunQueryDsl;
+use chrono::Utc;
 use diesel::prelude::*;
+use nexus_db_model::ProjectQuotas;
 use nexus_db_model::SiloQuotas;
 use nexus_db_model::SiloQuotasUpdate;
 use omicron_common::api::external::DataPageParams;
@@ -124,4 +126,65 @@ impl DataStore {
             .await
             .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))
     }
+
+    /// Fetches the limits set on a project.  A project that has never had any
+    /// limits set is reported as unlimited.
+    pub async fn project_quotas_view(
+        &self,
+        opctx: &OpContext,
+        authz_project: &authz::Project,
+    ) -> Result<ProjectQuotas, Error> {
+        opctx.authorize(authz::Action::Read, authz_project).await?;
+        use db::schema::project_quotas::dsl;
+        let quotas = dsl::project_quotas
+            .filter(dsl::project_id.eq(authz_project.id()))
+            .select(ProjectQuotas::as_select())
+            .first_async(&*self.pool_connection_authorized(opctx).await?)
+            .await
+            .optional()
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))?;
+        Ok(quotas
+            .unwrap_or_else(|| ProjectQuotas::unlimited(authz_project.id())))
+    }
+
+    /// Replaces the limits set on a project.
+    ///
+    /// Project quotas constrain the project's members, so changing them
+    /// requires permission to modify the project's Silo rather than the
+    /// project itself.
+    pub async fn project_quotas_update(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        authz_project: &authz::Project,
+        quotas: ProjectQuotas,
+    ) -> UpdateResult<ProjectQuotas> {
+        assert_eq!(authz_project.id(), quotas.project_id);
+        opctx.authorize(authz::Action::Modify, authz_silo).await?;
+
+        use db::schema::project_quotas::dsl;
+        let project_id = authz_project.id();
+        diesel::insert_into(dsl::project_quotas)
+            .values(quotas.clone())
+            .on_conflict(dsl::project_id)
+            .do_update()
+            .set((
+                dsl::time_modified.eq(Utc::now()),
+                dsl::cpus.eq(quotas.cpus),
+                dsl::memory_bytes.eq(quotas.memory),
+                dsl::storage_bytes.eq(quotas.storage),
+            ))
+            .returning(ProjectQuotas::as_returning())
+            .get_result_async(&*self.pool_connection_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel(
+                    e,
+                    ErrorHandler::Conflict(
+                        ResourceType::ProjectQuotas,
+                        &project_id.to_string(),
+                    ),
+                )
+            })
+    }
 }
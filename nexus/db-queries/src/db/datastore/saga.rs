@@ -5,22 +5,89 @@
 //! [`DataStore`] methods on [`db::saga_types::Saga`]s.
 
 use super::DataStore;
+use crate::authz;
+use crate::context::OpContext;
 use crate::db;
 use crate::db::error::public_error_from_diesel;
 use crate::db::error::ErrorHandler;
 use crate::db::model::Generation;
+use crate::db::model::InstanceState;
 use crate::db::pagination::paginated;
+use crate::db::saga_types::SagaState;
 use crate::db::update_and_check::UpdateAndCheck;
 use crate::db::update_and_check::UpdateStatus;
+use crate::db::DbConnection;
+use crate::transaction_retry::OptionalError;
 use async_bb8_diesel::AsyncRunQueryDsl;
+use chrono::Utc;
 use diesel::prelude::*;
+use diesel::result::Error as DieselError;
+use omicron_common::api::external;
 use omicron_common::api::external::DataPageParams;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupType;
 use omicron_common::api::external::ResourceType;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
 use uuid::Uuid;
 
+/// What [`DataStore::saga_abandon()`] will do to abandon a saga
+///
+/// This is computed by [`DataStore::saga_abandon_plan()`] without modifying
+/// anything, so that it can be reviewed before it's carried out.
+#[derive(Clone, Debug, PartialEq)]
+pub struct SagaAbandonPlan {
+    pub saga_id: steno::SagaId,
+    pub saga_name: String,
+    pub saga_state: SagaState,
+    pub current_sec: Option<db::SecId>,
+    pub adopt_generation: Generation,
+    /// instances whose migration, started by the saga, will be cleared
+    pub instances_to_unlock: Vec<Uuid>,
+    /// VMM records created by the saga that will be marked destroyed
+    pub vmms_to_delete: Vec<SagaAbandonVmm>,
+    /// sled resource reservations made by the saga that will be deleted
+    pub sled_reservations_to_delete: Vec<Uuid>,
+}
+
+/// A VMM record that abandoning a saga will mark destroyed
+#[derive(Clone, Debug, PartialEq)]
+pub struct SagaAbandonVmm {
+    pub id: Uuid,
+    pub instance_id: Uuid,
+    pub sled_id: Uuid,
+}
+
+/// The kind of database record a saga node's output identifies
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum SagaRecordedId {
+    /// a migration id set on an instance
+    Migration,
+    /// the id of a VMM record and of the sled reservation made for it
+    Vmm,
+}
+
+/// Saga nodes whose outputs identify records a saga creates, by saga name and
+/// node name
+///
+/// Only these outputs are considered by [`DataStore::saga_abandon()`]; other
+/// nodes' outputs are left alone even if they look like ids.  This must be
+/// kept in sync with the sagas in `nexus/src/app/sagas`.
+const SAGA_RECORDED_IDS: &[(&str, &str, SagaRecordedId)] = &[
+    ("instance-start", "propolis_id", SagaRecordedId::Vmm),
+    ("instance-migrate", "migrate_id", SagaRecordedId::Migration),
+    ("instance-migrate", "dst_propolis_id", SagaRecordedId::Vmm),
+];
+
+#[derive(Debug)]
+enum SagaAbandonError {
+    NotFound,
+    NotInProgress(SagaState),
+    BadDag(String),
+    PlanChanged,
+}
+
 impl DataStore {
     pub async fn saga_create(
         &self,
@@ -36,27 +103,56 @@ impl DataStore {
         Ok(())
     }
 
+    /// Records an event for a saga node
+    ///
+    /// Returns [`Error::Conflict`] without recording anything if the saga has
+    /// been abandoned.
     pub async fn saga_create_event(
         &self,
         event: &db::saga_types::SagaNodeEvent,
     ) -> Result<(), Error> {
+        use db::schema::saga::dsl as saga_dsl;
         use db::schema::saga_node_event::dsl;
 
         // TODO-robustness This INSERT ought to be conditional on this SEC still
         // owning this saga.
-        diesel::insert_into(dsl::saga_node_event)
-            .values(event.clone())
-            .execute_async(&*self.pool_connection_unauthorized().await?)
+        let err = OptionalError::new();
+        let conn = self.pool_connection_unauthorized().await?;
+        self.transaction_retry_wrapper("saga_create_event")
+            .transaction(&conn, |conn| {
+                let err = err.clone();
+                async move {
+                    let saga_state = saga_dsl::saga
+                        .filter(saga_dsl::id.eq(event.saga_id))
+                        .select(saga_dsl::saga_state)
+                        .first_async::<SagaState>(&conn)
+                        .await
+                        .optional()?;
+                    if saga_state == Some(SagaState::Abandoned) {
+                        return Err(err.bail(()));
+                    }
+
+                    diesel::insert_into(dsl::saga_node_event)
+                        .values(event.clone())
+                        .execute_async(&conn)
+                        .await?;
+                    Ok(())
+                }
+            })
             .await
-            .map_err(|e| {
-                public_error_from_diesel(
+            .map_err(|e| match err.take() {
+                Some(()) => saga_abandoned_error(event.saga_id),
+                None => public_error_from_diesel(
                     e,
                     ErrorHandler::Conflict(ResourceType::SagaDbg, "Saga Event"),
-                )
-            })?;
-        Ok(())
+                ),
+            })
     }
 
+    /// Sets the state of a saga owned by `current_sec`
+    ///
+    /// Returns [`Error::Conflict`] without changing anything if the saga has
+    /// been abandoned.
     pub async fn saga_update_state(
         &self,
         saga_id: steno::SagaId,
@@ -71,7 +167,8 @@ impl DataStore {
             .filter(dsl::id.eq(saga_id))
             .filter(dsl::current_sec.eq(current_sec))
             .filter(dsl::adopt_generation.eq(current_adopt_generation))
-            .set(dsl::saga_state.eq(db::saga_types::SagaCachedState(new_state)))
+            .filter(dsl::saga_state.ne(db::saga_types::SagaState::Abandoned))
+            .set(dsl::saga_state.eq(db::saga_types::SagaState::from(new_state)))
             .check_if_exists::<db::saga_types::Saga>(saga_id)
             .execute_and_check(&*self.pool_connection_unauthorized().await?)
             .await
@@ -87,6 +184,12 @@ impl DataStore {
 
         match result.status {
             UpdateStatus::Updated => Ok(()),
+            UpdateStatus::NotUpdatedButExists
+                if result.found.saga_state
+                    == db::saga_types::SagaState::Abandoned =>
+            {
+                Err(saga_abandoned_error(saga_id))
+            }
             UpdateStatus::NotUpdatedButExists => Err(Error::invalid_request(
                 format!(
                     "failed to update saga {:?} with state {:?}: preconditions not met: \
@@ -111,9 +214,8 @@ impl DataStore {
     ) -> ListResultVec<db::saga_types::Saga> {
         use db::schema::saga::dsl;
         paginated(dsl::saga, dsl::id, &pagparams)
-            .filter(dsl::saga_state.ne(db::saga_types::SagaCachedState(
-                steno::SagaCachedState::Done,
-            )))
+            .filter(dsl::saga_state.ne(db::saga_types::SagaState::Done))
+            .filter(dsl::saga_state.ne(db::saga_types::SagaState::Abandoned))
             .filter(dsl::current_sec.eq(*sec_id))
             .load_async(&*self.pool_connection_unauthorized().await?)
             .await
@@ -153,4 +255,683 @@ impl DataStore {
             .map(|db_event| steno::SagaNodeEvent::try_from(db_event))
             .collect::<Result<_, Error>>()
     }
+
+    /// Determines what [`DataStore::saga_abandon()`] would do to abandon a
+    /// saga that is running or unwinding, without changing anything
+    pub async fn saga_abandon_plan(
+        &self,
+        opctx: &OpContext,
+        saga_id: steno::SagaId,
+    ) -> Result<SagaAbandonPlan, Error> {
+        opctx.authorize(authz::Action::Read, &authz::FLEET).await?;
+
+        let saga_id: db::saga_types::SagaId = saga_id.into();
+        let err = OptionalError::new();
+        let conn = self.pool_connection_authorized(opctx).await?;
+        self.transaction_retry_wrapper("saga_abandon_plan")
+            .transaction(&conn, |conn| {
+                let err = err.clone();
+                async move {
+                    Self::saga_abandon_plan_on_connection(&conn, &err, saga_id)
+                        .await
+                }
+            })
+            .await
+            .map_err(|e| saga_abandon_error(saga_id, err.take(), e))
+    }
+
+    /// Marks a saga that is running or unwinding as abandoned and releases
+    /// the database records it left behind, as described by `plan`
+    ///
+    /// `plan` must come from [`DataStore::saga_abandon_plan()`].  If anything
+    /// it describes has changed since then (e.g., because the saga has made
+    /// progress or been adopted by another Nexus), nothing is changed and an
+    /// error is returned.
+    ///
+    /// An abandoned saga is never recovered by any Nexus, and no SEC may
+    /// record events for it or change its state.  A Nexus that is still
+    /// executing the saga in memory is not stopped: it keeps running the
+    /// saga's actions, but its SEC store discards everything it tries to
+    /// record.  This is intended for sagas whose SEC is gone, or that are
+    /// stuck and will never make progress on their own.
+    pub async fn saga_abandon(
+        &self,
+        opctx: &OpContext,
+        plan: &SagaAbandonPlan,
+    ) -> Result<(), Error> {
+        use db::schema::instance::dsl as instance_dsl;
+        use db::schema::saga::dsl as saga_dsl;
+        use db::schema::sled_resource::dsl as resource_dsl;
+        use db::schema::vmm::dsl as vmm_dsl;
+
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+
+        let saga_id: db::saga_types::SagaId = plan.saga_id.into();
+        let err = OptionalError::new();
+        let conn = self.pool_connection_authorized(opctx).await?;
+        self.transaction_retry_wrapper("saga_abandon")
+            .transaction(&conn, |conn| {
+                let err = err.clone();
+                async move {
+                    let current = Self::saga_abandon_plan_on_connection(
+                        &conn, &err, saga_id,
+                    )
+                    .await?;
+                    if current != *plan {
+                        return Err(err.bail(SagaAbandonError::PlanChanged));
+                    }
+
+                    diesel::update(saga_dsl::saga)
+                        .filter(saga_dsl::id.eq(saga_id))
+                        .set(saga_dsl::saga_state.eq(SagaState::Abandoned))
+                        .execute_async(&conn)
+                        .await?;
+
+                    let now = Utc::now();
+                    diesel::update(instance_dsl::instance)
+                        .filter(
+                            instance_dsl::id
+                                .eq_any(plan.instances_to_unlock.clone()),
+                        )
+                        .set((
+                            instance_dsl::migration_id.eq(None::<Uuid>),
+                            instance_dsl::target_propolis_id.eq(None::<Uuid>),
+                            instance_dsl::state_generation
+                                .eq(instance_dsl::state_generation + 1),
+                            instance_dsl::time_state_updated.eq(now),
+                        ))
+                        .execute_async(&conn)
+                        .await?;
+
+                    diesel::update(vmm_dsl::vmm)
+                        .filter(
+                            vmm_dsl::id.eq_any(
+                                plan.vmms_to_delete
+                                    .iter()
+                                    .map(|vmm| vmm.id)
+                                    .collect::<Vec<_>>(),
+                            ),
+                        )
+                        .set((
+                            vmm_dsl::state.eq(InstanceState::new(
+                                external::InstanceState::Destroyed,
+                            )),
+                            vmm_dsl::state_generation
+                                .eq(vmm_dsl::state_generation + 1),
+                            vmm_dsl::time_state_updated.eq(now),
+                            vmm_dsl::time_deleted.eq(now),
+                        ))
+                        .execute_async(&conn)
+                        .await?;
+
+                    diesel::delete(resource_dsl::sled_resource)
+                        .filter(
+                            resource_dsl::id.eq_any(
+                                plan.sled_reservations_to_delete.clone(),
+                            ),
+                        )
+                        .execute_async(&conn)
+                        .await?;
+
+                    Ok(())
+                }
+            })
+            .await
+            .map_err(|e| saga_abandon_error(saga_id, err.take(), e))
+    }
+
+    async fn saga_abandon_plan_on_connection(
+        conn: &async_bb8_diesel::Connection<DbConnection>,
+        err: &OptionalError<SagaAbandonError>,
+        saga_id: db::saga_types::SagaId,
+    ) -> Result<SagaAbandonPlan, DieselError> {
+        use db::schema::instance::dsl as instance_dsl;
+        use db::schema::saga::dsl as saga_dsl;
+        use db::schema::saga_node_event::dsl as event_dsl;
+        use db::schema::sled_resource::dsl as resource_dsl;
+        use db::schema::vmm::dsl as vmm_dsl;
+
+        let saga = saga_dsl::saga
+            .filter(saga_dsl::id.eq(saga_id))
+            .select(db::saga_types::Saga::as_select())
+            .first_async(conn)
+            .await
+            .optional()?
+            .ok_or_else(|| err.bail(SagaAbandonError::NotFound))?;
+        match saga.saga_state {
+            SagaState::Running | SagaState::Unwinding => (),
+            state @ (SagaState::Done | SagaState::Abandoned) => {
+                return Err(err.bail(SagaAbandonError::NotInProgress(state)));
+            }
+        }
+
+        let events = event_dsl::saga_node_event
+            .filter(event_dsl::saga_id.eq(saga_id))
+            .select(db::saga_types::SagaNodeEvent::as_select())
+            .load_async(conn)
+            .await?;
+        let recorded = saga_recorded_ids(&saga, &events)
+            .map_err(|message| err.bail(SagaAbandonError::BadDag(message)))?;
+        let recorded_ids = |kind| {
+            recorded
+                .iter()
+                .filter(|(k, _)| *k == kind)
+                .map(|(_, id)| *id)
+                .collect::<Vec<Uuid>>()
+        };
+        let migration_ids = recorded_ids(SagaRecordedId::Migration);
+        let vmm_ids = recorded_ids(SagaRecordedId::Vmm);
+
+        let instances_to_unlock: Vec<Uuid> = instance_dsl::instance
+            .filter(instance_dsl::time_deleted.is_null())
+            .filter(instance_dsl::migration_id.eq_any(migration_ids))
+            .order(instance_dsl::id)
+            .select(instance_dsl::id)
+            .load_async(conn)
+            .await?;
+
+        // VMM records and reservations that an instance still points at are
+        // in use and must be left alone, except for the migration targets of
+        // the instances being unlocked.
+        let referenced: BTreeSet<Uuid> = instance_dsl::instance
+            .filter(instance_dsl::time_deleted.is_null())
+            .filter(
+                instance_dsl::active_propolis_id
+                    .eq_any(vmm_ids.clone())
+                    .or(instance_dsl::target_propolis_id
+                        .eq_any(vmm_ids.clone())),
+            )
+            .select((
+                instance_dsl::id,
+                instance_dsl::active_propolis_id,
+                instance_dsl::target_propolis_id,
+            ))
+            .load_async::<(Uuid, Option<Uuid>, Option<Uuid>)>(conn)
+            .await?
+            .into_iter()
+            .flat_map(|(id, active, target)| {
+                let target =
+                    target.filter(|_| !instances_to_unlock.contains(&id));
+                [active, target]
+            })
+            .flatten()
+            .collect();
+        let unreferenced: Vec<Uuid> =
+            vmm_ids.into_iter().filter(|id| !referenced.contains(id)).collect();
+
+        let vmms_to_delete = vmm_dsl::vmm
+            .filter(vmm_dsl::time_deleted.is_null())
+            .filter(vmm_dsl::id.eq_any(unreferenced.clone()))
+            .order(vmm_dsl::id)
+            .select((vmm_dsl::id, vmm_dsl::instance_id, vmm_dsl::sled_id))
+            .load_async::<(Uuid, Uuid, Uuid)>(conn)
+            .await?
+            .into_iter()
+            .map(|(id, instance_id, sled_id)| SagaAbandonVmm {
+                id,
+                instance_id,
+                sled_id,
+            })
+            .collect();
+
+        let sled_reservations_to_delete = resource_dsl::sled_resource
+            .filter(resource_dsl::id.eq_any(unreferenced))
+            .order(resource_dsl::id)
+            .select(resource_dsl::id)
+            .load_async(conn)
+            .await?;
+
+        Ok(SagaAbandonPlan {
+            saga_id: saga_id.0,
+            saga_name: saga.name,
+            saga_state: saga.saga_state,
+            current_sec: saga.current_sec,
+            adopt_generation: saga.adopt_generation,
+            instances_to_unlock,
+            vmms_to_delete,
+            sled_reservations_to_delete,
+        })
+    }
+}
+
+/// The error returned when an SEC tries to record progress for a saga that
+/// has been abandoned
+fn saga_abandoned_error(saga_id: db::saga_types::SagaId) -> Error {
+    Error::conflict(format!("saga {} has been abandoned", saga_id.0))
+}
+
+fn saga_abandon_error(
+    saga_id: db::saga_types::SagaId,
+    error: Option<SagaAbandonError>,
+    diesel_error: DieselError,
+) -> Error {
+    match error {
+        Some(SagaAbandonError::NotFound) => {
+            Error::not_found_by_id(ResourceType::SagaDbg, &saga_id.0.into())
+        }
+        Some(SagaAbandonError::NotInProgress(state)) => Error::invalid_request(
+            format!("saga {} is {} and cannot be abandoned", saga_id.0, state),
+        ),
+        Some(SagaAbandonError::BadDag(message)) => Error::internal_error(
+            &format!("saga {}: failed to parse DAG: {}", saga_id.0, message),
+        ),
+        Some(SagaAbandonError::PlanChanged) => Error::conflict(format!(
+            "saga {} or the records it left behind changed while it was \
+             being abandoned",
+            saga_id.0
+        )),
+        None => public_error_from_diesel(diesel_error, ErrorHandler::Server),
+    }
+}
+
+/// Returns the ids that a saga recorded as the outputs of the nodes listed in
+/// [`SAGA_RECORDED_IDS`] and has not undone
+fn saga_recorded_ids(
+    saga: &db::saga_types::Saga,
+    events: &[db::saga_types::SagaNodeEvent],
+) -> Result<Vec<(SagaRecordedId, Uuid)>, String> {
+    let dag: steno::SagaDag = serde_json::from_value(saga.saga_dag.clone())
+        .map_err(|e| e.to_string())?;
+    let node_kinds: BTreeMap<u32, SagaRecordedId> = dag
+        .get_nodes()
+        .filter_map(|node| {
+            let (_, _, kind) = SAGA_RECORDED_IDS.iter().find(
+                |(saga_name, node_name, _)| {
+                    *saga_name == saga.name
+                        && *node_name == node.name().as_ref()
+                },
+            )?;
+            Some((u32::try_from(node.index().index()).ok()?, *kind))
+        })
+        .collect();
+
+    let undone: BTreeSet<u32> = events
+        .iter()
+        .filter(|event| event.event_type == "undo_finished")
+        .map(|event| u32::from(event.node_id.0))
+        .collect();
+    Ok(events
+        .iter()
+        .filter(|event| event.event_type == "succeeded")
+        .filter(|event| !undone.contains(&u32::from(event.node_id.0)))
+        .filter_map(|event| {
+            let kind = node_kinds.get(&u32::from(event.node_id.0))?;
+            let id = event.data.as_ref()?.as_str()?.parse().ok()?;
+            Some((*kind, id))
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::db::datastore::test_utils::datastore_test;
+    use crate::db::model::ByteCount;
+    use crate::db::model::Resources;
+    use crate::db::model::SledResource;
+    use crate::db::model::SledResourceKind;
+    use crate::db::model::Vmm;
+    use crate::db::model::VmmInitialState;
+    use crate::db::saga_types::SagaNodeEvent;
+    use crate::db::saga_types::SecId;
+    use nexus_test_utils::db::test_setup_database;
+    use omicron_test_utils::dev;
+    use once_cell::sync::Lazy;
+    use std::sync::Arc;
+    use steno::{
+        new_action_noop_undo, Action, ActionContext, ActionError, DagBuilder,
+        Node, SagaDag, SagaName, SagaType,
+    };
+
+    #[derive(Debug)]
+    struct TestOp;
+    impl SagaType for TestOp {
+        type ExecContextType = ();
+    }
+
+    static ACTION_ID: Lazy<Arc<dyn Action<TestOp>>> =
+        Lazy::new(|| new_action_noop_undo("test_id", node_id));
+
+    async fn node_id(_: ActionContext<TestOp>) -> Result<Uuid, ActionError> {
+        Ok(Uuid::new_v4())
+    }
+
+    /// Makes a saga record with one node for each of `node_names`, returning
+    /// it along with each node's id
+    fn test_saga(
+        saga_name: &str,
+        node_names: &[&str],
+    ) -> (db::saga_types::Saga, BTreeMap<String, u32>) {
+        let mut builder = DagBuilder::new(SagaName::new(saga_name));
+        for name in node_names {
+            builder.append(Node::action(*name, "TestNode", ACTION_ID.as_ref()));
+        }
+        let dag =
+            SagaDag::new(builder.build().unwrap(), serde_json::Value::Null);
+        let node_ids = dag
+            .get_nodes()
+            .map(|node| {
+                (
+                    node.name().as_ref().to_string(),
+                    u32::try_from(node.index().index()).unwrap(),
+                )
+            })
+            .collect();
+        let now = Utc::now();
+        let sec = SecId(Uuid::new_v4());
+        let saga = db::saga_types::Saga {
+            id: steno::SagaId(Uuid::new_v4()).into(),
+            creator: sec,
+            time_created: now,
+            name: saga_name.to_string(),
+            saga_dag: serde_json::to_value(&dag).unwrap(),
+            saga_state: SagaState::Running,
+            current_sec: Some(sec),
+            adopt_generation: Generation::new(),
+            adopt_time: now,
+        };
+        (saga, node_ids)
+    }
+
+    fn event(
+        saga: &db::saga_types::Saga,
+        node_id: u32,
+        event_type: &str,
+        data: Option<serde_json::Value>,
+    ) -> SagaNodeEvent {
+        SagaNodeEvent {
+            saga_id: saga.id,
+            node_id: steno::SagaNodeId::from(node_id).into(),
+            event_type: event_type.to_string(),
+            data,
+            event_time: Utc::now(),
+            creator: saga.creator,
+        }
+    }
+
+    #[test]
+    fn test_saga_recorded_ids() {
+        let (saga, nodes) = test_saga(
+            "instance-migrate",
+            &["migrate_id", "dst_propolis_id", "src_propolis_id", "label"],
+        );
+        let migration_id = Uuid::new_v4();
+        let vmm_id = Uuid::new_v4();
+        let events = vec![
+            event(&saga, nodes["migrate_id"], "started", None),
+            event(
+                &saga,
+                nodes["migrate_id"],
+                "succeeded",
+                Some(serde_json::json!(migration_id.to_string())),
+            ),
+            event(&saga, nodes["dst_propolis_id"], "started", None),
+            event(
+                &saga,
+                nodes["dst_propolis_id"],
+                "succeeded",
+                Some(serde_json::json!(vmm_id.to_string())),
+            ),
+            // Ids produced by nodes that don't create the records are ignored,
+            // even if they identify something.
+            event(&saga, nodes["src_propolis_id"], "started", None),
+            event(
+                &saga,
+                nodes["src_propolis_id"],
+                "succeeded",
+                Some(serde_json::json!(Uuid::new_v4().to_string())),
+            ),
+            event(&saga, nodes["label"], "started", None),
+            event(
+                &saga,
+                nodes["label"],
+                "succeeded",
+                Some(serde_json::json!("not-a-uuid")),
+            ),
+        ];
+        assert_eq!(
+            saga_recorded_ids(&saga, &events).unwrap(),
+            vec![
+                (SagaRecordedId::Migration, migration_id),
+                (SagaRecordedId::Vmm, vmm_id),
+            ]
+        );
+
+        // Ids whose nodes have been undone are ignored.
+        let mut undone = events.clone();
+        undone.push(event(&saga, nodes["migrate_id"], "undo_started", None));
+        undone.push(event(&saga, nodes["migrate_id"], "undo_finished", None));
+        assert_eq!(
+            saga_recorded_ids(&saga, &undone).unwrap(),
+            vec![(SagaRecordedId::Vmm, vmm_id)]
+        );
+
+        // The same node names in some other saga mean nothing.
+        let other = db::saga_types::Saga {
+            name: String::from("instance-delete"),
+            ..saga.clone()
+        };
+        assert_eq!(saga_recorded_ids(&other, &events).unwrap(), vec![]);
+
+        // A DAG that can't be parsed is an error rather than an empty list.
+        let bad = db::saga_types::Saga {
+            saga_dag: serde_json::json!({}),
+            ..saga.clone()
+        };
+        assert!(saga_recorded_ids(&bad, &events).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_saga_abandon() {
+        use db::schema::sled_resource::dsl as resource_dsl;
+        use db::schema::vmm::dsl as vmm_dsl;
+
+        let logctx = dev::test_setup_log("test_saga_abandon");
+        let mut db = test_setup_database(&logctx.log).await;
+        let (opctx, datastore) = datastore_test(&logctx, &db).await;
+
+        // Record an instance-start saga that has created a VMM and reserved
+        // resources for it, and has also produced a sled id along the way.
+        let (saga, nodes) =
+            test_saga("instance-start", &["propolis_id", "sled_id"]);
+        datastore.saga_create(&saga).await.unwrap();
+        let instance_id = Uuid::new_v4();
+        let sled_id = Uuid::new_v4();
+        let vmm_id = Uuid::new_v4();
+        for (node, id) in [("propolis_id", vmm_id), ("sled_id", sled_id)] {
+            for (event_type, data) in [
+                ("started", None),
+                ("succeeded", Some(serde_json::json!(id.to_string()))),
+            ] {
+                datastore
+                    .saga_create_event(&event(
+                        &saga,
+                        nodes[node],
+                        event_type,
+                        data,
+                    ))
+                    .await
+                    .unwrap();
+            }
+        }
+        datastore
+            .vmm_insert(
+                &opctx,
+                Vmm::new(
+                    vmm_id,
+                    instance_id,
+                    sled_id,
+                    "::1".parse().unwrap(),
+                    12400,
+                    VmmInitialState::Starting,
+                ),
+            )
+            .await
+            .unwrap();
+        let conn = datastore.pool_connection_for_tests().await.unwrap();
+        diesel::insert_into(resource_dsl::sled_resource)
+            .values(SledResource::new(
+                vmm_id,
+                sled_id,
+                SledResourceKind::Instance,
+                Resources::new(
+                    1,
+                    ByteCount::try_from(1024_i64 * 1024).unwrap(),
+                    ByteCount::try_from(1024_i64 * 1024).unwrap(),
+                ),
+            ))
+            .execute_async(&*conn)
+            .await
+            .unwrap();
+
+        // Only the VMM and its reservation are released.
+        let saga_id = saga.id.0;
+        let plan = datastore.saga_abandon_plan(&opctx, saga_id).await.unwrap();
+        assert_eq!(plan.saga_state, SagaState::Running);
+        assert_eq!(plan.current_sec, saga.current_sec);
+        assert!(plan.instances_to_unlock.is_empty());
+        assert_eq!(
+            plan.vmms_to_delete,
+            vec![SagaAbandonVmm { id: vmm_id, instance_id, sled_id }]
+        );
+        assert_eq!(plan.sled_reservations_to_delete, vec![vmm_id]);
+
+        // If the saga makes progress after the plan was made, the plan is
+        // rejected and nothing changes.
+        datastore
+            .saga_update_state(
+                saga_id,
+                steno::SagaCachedState::Unwinding,
+                saga.creator,
+                saga.adopt_generation,
+            )
+            .await
+            .unwrap();
+        let error = datastore.saga_abandon(&opctx, &plan).await.unwrap_err();
+        assert!(matches!(error, Error::Conflict { .. }), "{error:?}");
+        let plan = datastore.saga_abandon_plan(&opctx, saga_id).await.unwrap();
+        assert_eq!(plan.saga_state, SagaState::Unwinding);
+
+        datastore.saga_abandon(&opctx, &plan).await.unwrap();
+        let vmm_deleted: Option<chrono::DateTime<Utc>> = vmm_dsl::vmm
+            .filter(vmm_dsl::id.eq(vmm_id))
+            .select(vmm_dsl::time_deleted)
+            .first_async(&*conn)
+            .await
+            .unwrap();
+        assert!(vmm_deleted.is_some());
+        let reservations: Vec<Uuid> = resource_dsl::sled_resource
+            .filter(resource_dsl::id.eq(vmm_id))
+            .select(resource_dsl::id)
+            .load_async(&*conn)
+            .await
+            .unwrap();
+        assert!(reservations.is_empty());
+
+        // The SEC can no longer change the saga's state, it's not recovered,
+        // and it can't be abandoned again.
+        let error = datastore
+            .saga_update_state(
+                saga_id,
+                steno::SagaCachedState::Done,
+                saga.creator,
+                saga.adopt_generation,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::Conflict { .. }), "{error:?}");
+        let unfinished = datastore
+            .saga_list_unfinished_by_id(
+                &saga.creator,
+                &DataPageParams::<Uuid>::max_page(),
+            )
+            .await
+            .unwrap();
+        assert!(unfinished.iter().all(|s| s.id != saga.id));
+        let error =
+            datastore.saga_abandon_plan(&opctx, saga_id).await.unwrap_err();
+        assert!(matches!(error, Error::InvalidRequest { .. }), "{error:?}");
+
+        db.cleanup().await.unwrap();
+        logctx.cleanup_successful();
+    }
+
+    #[tokio::test]
+    async fn test_saga_abandon_running() {
+        use db::schema::saga::dsl as saga_dsl;
+        use db::schema::saga_node_event::dsl as event_dsl;
+        use steno::SecStore;
+
+        let logctx = dev::test_setup_log("test_saga_abandon_running");
+        let mut db = test_setup_database(&logctx.log).await;
+        let (opctx, datastore) = datastore_test(&logctx, &db).await;
+
+        // Abandon a saga while the Nexus that created it is still running it.
+        let (saga, nodes) = test_saga("instance-start", &["a", "b"]);
+        datastore.saga_create(&saga).await.unwrap();
+        let sec_store = db::CockroachDbSecStore::new(
+            saga.creator,
+            datastore.clone(),
+            logctx.log.clone(),
+        );
+        sec_store
+            .record_event(steno::SagaNodeEvent {
+                saga_id: saga.id.0,
+                node_id: steno::SagaNodeId::from(nodes["a"]),
+                event_type: steno::SagaNodeEventType::Started,
+            })
+            .await;
+        let plan =
+            datastore.saga_abandon_plan(&opctx, saga.id.0).await.unwrap();
+        datastore.saga_abandon(&opctx, &plan).await.unwrap();
+
+        // The datastore refuses to record the saga's progress.
+        let error = datastore
+            .saga_create_event(&event(&saga, nodes["b"], "started", None))
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::Conflict { .. }), "{error:?}");
+        let error = datastore
+            .saga_update_state(
+                saga.id.0,
+                steno::SagaCachedState::Unwinding,
+                saga.creator,
+                saga.adopt_generation,
+            )
+            .await
+            .unwrap_err();
+        assert!(matches!(error, Error::Conflict { .. }), "{error:?}");
+
+        // The running Nexus's SEC store discards its progress rather than
+        // retrying forever or panicking.
+        sec_store
+            .record_event(steno::SagaNodeEvent {
+                saga_id: saga.id.0,
+                node_id: steno::SagaNodeId::from(nodes["a"]),
+                event_type: steno::SagaNodeEventType::Succeeded(Arc::new(
+                    serde_json::json!(Uuid::new_v4().to_string()),
+                )),
+            })
+            .await;
+        sec_store.saga_update(saga.id.0, steno::SagaCachedState::Done).await;
+
+        let conn = datastore.pool_connection_for_tests().await.unwrap();
+        let event_types: Vec<String> = event_dsl::saga_node_event
+            .filter(event_dsl::saga_id.eq(saga.id))
+            .select(event_dsl::event_type)
+            .load_async(&*conn)
+            .await
+            .unwrap();
+        assert_eq!(event_types, vec![String::from("started")]);
+        let saga_state: SagaState = saga_dsl::saga
+            .filter(saga_dsl::id.eq(saga.id))
+            .select(saga_dsl::saga_state)
+            .first_async(&*conn)
+            .await
+            .unwrap();
+        assert_eq!(saga_state, SagaState::Abandoned);
+
+        db.cleanup().await.unwrap();
+        logctx.cleanup_successful();
+    }
 }
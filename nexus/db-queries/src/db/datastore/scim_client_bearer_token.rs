@@ -0,0 +1,126 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! [`DataStore`] methods related to SCIM client bearer tokens.
+
+use super::DataStore;
+use crate::authz;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::error::public_error_from_diesel;
+use crate::db::error::ErrorHandler;
+use crate::db::model::ScimClientBearerToken;
+use crate::db::pagination::paginated;
+use async_bb8_diesel::AsyncRunQueryDsl;
+use chrono::DateTime;
+use chrono::Utc;
+use diesel::prelude::*;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::ResourceType;
+use uuid::Uuid;
+
+impl DataStore {
+    /// Create a new bearer token that an identity provider can use to manage
+    /// the users and groups of a Silo via SCIM
+    pub async fn scim_client_bearer_token_create(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        time_expires: Option<DateTime<Utc>>,
+    ) -> CreateResult<ScimClientBearerToken> {
+        // Anybody who can hold one of these tokens can create and delete any
+        // user in the Silo, so we require the ability to modify the Silo
+        // itself in order to create one.
+        opctx.authorize(authz::Action::Modify, authz_silo).await?;
+
+        let token = ScimClientBearerToken::new(authz_silo.id(), time_expires);
+
+        use db::schema::scim_client_bearer_token::dsl;
+        diesel::insert_into(dsl::scim_client_bearer_token)
+            .values(token)
+            .returning(ScimClientBearerToken::as_returning())
+            .get_result_async(&*self.pool_connection_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))
+    }
+
+    /// List the SCIM client bearer tokens for a Silo
+    pub async fn scim_client_bearer_token_list(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        pagparams: &DataPageParams<'_, Uuid>,
+    ) -> ListResultVec<ScimClientBearerToken> {
+        opctx.authorize(authz::Action::Modify, authz_silo).await?;
+
+        use db::schema::scim_client_bearer_token::dsl;
+        paginated(dsl::scim_client_bearer_token, dsl::id, pagparams)
+            .filter(dsl::silo_id.eq(authz_silo.id()))
+            .filter(dsl::time_deleted.is_null())
+            .select(ScimClientBearerToken::as_select())
+            .load_async(&*self.pool_connection_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))
+    }
+
+    /// Revoke one of a Silo's SCIM client bearer tokens
+    pub async fn scim_client_bearer_token_delete(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        token_id: Uuid,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Modify, authz_silo).await?;
+
+        use db::schema::scim_client_bearer_token::dsl;
+        let updated = diesel::update(dsl::scim_client_bearer_token)
+            .filter(dsl::silo_id.eq(authz_silo.id()))
+            .filter(dsl::id.eq(token_id))
+            .filter(dsl::time_deleted.is_null())
+            .set(dsl::time_deleted.eq(Utc::now()))
+            .execute_async(&*self.pool_connection_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))?;
+
+        if updated == 0 {
+            return Err(Error::not_found_by_id(
+                ResourceType::ScimClientBearerToken,
+                &token_id,
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Look up a SCIM client bearer token by its value
+    ///
+    /// Like `device_access_token_fetch()`, this lookup is not done by primary
+    /// key or name, so it does not fit the usual lookup machinery and does not
+    /// do any authz check.  The caller is in the middle of authenticating the
+    /// request and has nothing to check against.  The token is a high-entropy
+    /// random value and should not be guessable by an attacker.
+    ///
+    /// Expired tokens are returned as well.  It's up to the caller to check
+    /// the expiration time.
+    pub async fn scim_client_bearer_token_lookup(
+        &self,
+        opctx: &OpContext,
+        bearer_token: &str,
+    ) -> LookupResult<Option<ScimClientBearerToken>> {
+        use db::schema::scim_client_bearer_token::dsl;
+        dsl::scim_client_bearer_token
+            .filter(dsl::bearer_token.eq(bearer_token.to_string()))
+            .filter(dsl::time_deleted.is_null())
+            .select(ScimClientBearerToken::as_select())
+            .first_async(&*self.pool_connection_authorized(opctx).await?)
+            .await
+            .optional()
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))
+    }
+}
@@ -0,0 +1,280 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! [`DataStore`] methods related to [`ServiceAccount`]s and their tokens.
+
+use super::DataStore;
+use crate::authz;
+use crate::context::OpContext;
+use crate::db;
+use crate::db::error::public_error_from_diesel;
+use crate::db::error::ErrorHandler;
+use crate::db::identity::Resource;
+use crate::db::model::Name;
+use crate::db::model::ServiceAccount;
+use crate::db::model::ServiceAccountToken;
+use crate::db::pagination::paginated;
+use crate::db::update_and_check::UpdateAndCheck;
+use async_bb8_diesel::AsyncConnection;
+use async_bb8_diesel::AsyncRunQueryDsl;
+use chrono::{DateTime, Duration, Utc};
+use diesel::prelude::*;
+use omicron_common::api::external::http_pagination::PaginatedBy;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::InternalContext;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::LookupType;
+use omicron_common::api::external::ResourceType;
+use ref_cast::RefCast;
+use uuid::Uuid;
+
+/// How stale a token's `time_last_used` may get before we bother updating it
+const LAST_USED_GRANULARITY_SECS: i64 = 60;
+
+impl DataStore {
+    pub async fn service_accounts_list(
+        &self,
+        opctx: &OpContext,
+        authz_service_account_list: &authz::ServiceAccountList,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<ServiceAccount> {
+        opctx
+            .authorize(authz::Action::ListChildren, authz_service_account_list)
+            .await?;
+
+        use db::schema::service_account::dsl;
+        match pagparams {
+            PaginatedBy::Id(pagparams) => {
+                paginated(dsl::service_account, dsl::id, &pagparams)
+            }
+            PaginatedBy::Name(pagparams) => paginated(
+                dsl::service_account,
+                dsl::name,
+                &pagparams.map_name(|n| Name::ref_cast(n)),
+            ),
+        }
+        .filter(dsl::silo_id.eq(authz_service_account_list.silo().id()))
+        .filter(dsl::time_deleted.is_null())
+        .select(ServiceAccount::as_select())
+        .load_async(&*self.pool_connection_authorized(opctx).await?)
+        .await
+        .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))
+    }
+
+    pub async fn service_account_create(
+        &self,
+        opctx: &OpContext,
+        authz_service_account_list: &authz::ServiceAccountList,
+        service_account: ServiceAccount,
+    ) -> CreateResult<ServiceAccount> {
+        assert_eq!(
+            authz_service_account_list.silo().id(),
+            service_account.silo_id
+        );
+        opctx
+            .authorize(authz::Action::CreateChild, authz_service_account_list)
+            .await?;
+        let name = service_account.name().to_string();
+
+        use db::schema::service_account::dsl;
+        diesel::insert_into(dsl::service_account)
+            .values(service_account)
+            .returning(ServiceAccount::as_returning())
+            .get_result_async(&*self.pool_connection_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel(
+                    e,
+                    ErrorHandler::Conflict(ResourceType::ServiceAccount, &name),
+                )
+            })
+    }
+
+    /// Delete a service account, revoking all of its tokens and removing any
+    /// roles it has been granted.
+    pub async fn service_account_delete(
+        &self,
+        opctx: &OpContext,
+        authz_service_account: &authz::ServiceAccount,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Delete, authz_service_account).await?;
+
+        let service_account_id = authz_service_account.id();
+        self.pool_connection_authorized(opctx)
+            .await?
+            .transaction_async(|mut conn| async move {
+                let now = Utc::now();
+
+                {
+                    use db::schema::service_account::dsl;
+                    diesel::update(dsl::service_account)
+                        .filter(dsl::id.eq(service_account_id))
+                        .filter(dsl::time_deleted.is_null())
+                        .set(dsl::time_deleted.eq(now))
+                        .check_if_exists::<ServiceAccount>(service_account_id)
+                        .execute_and_check(&mut conn)
+                        .await?;
+                }
+
+                {
+                    use db::schema::service_account_token::dsl;
+                    diesel::update(dsl::service_account_token)
+                        .filter(dsl::service_account_id.eq(service_account_id))
+                        .filter(dsl::time_deleted.is_null())
+                        .set(dsl::time_deleted.eq(now))
+                        .execute_async(&mut conn)
+                        .await?;
+                }
+
+                // Role assignments are not soft-deleted.  Leaving them behind
+                // would show a deleted identity in every policy that
+                // referenced it.
+                {
+                    use db::schema::role_assignment::dsl;
+                    diesel::delete(dsl::role_assignment)
+                        .filter(
+                            dsl::identity_type
+                                .eq(db::model::IdentityType::ServiceAccount),
+                        )
+                        .filter(dsl::identity_id.eq(service_account_id))
+                        .execute_async(&mut conn)
+                        .await?;
+                }
+
+                Ok(())
+            })
+            .await
+            .map_err(|e| {
+                public_error_from_diesel(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_service_account),
+                )
+                .internal_context("deleting service account")
+            })
+    }
+
+    pub async fn service_account_tokens_list(
+        &self,
+        opctx: &OpContext,
+        authz_service_account: &authz::ServiceAccount,
+        pagparams: &DataPageParams<'_, Uuid>,
+    ) -> ListResultVec<ServiceAccountToken> {
+        opctx
+            .authorize(authz::Action::ListChildren, authz_service_account)
+            .await?;
+
+        use db::schema::service_account_token::dsl;
+        paginated(dsl::service_account_token, dsl::id, pagparams)
+            .filter(dsl::service_account_id.eq(authz_service_account.id()))
+            .filter(dsl::time_deleted.is_null())
+            .select(ServiceAccountToken::as_select())
+            .load_async(&*self.pool_connection_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))
+    }
+
+    pub async fn service_account_token_create(
+        &self,
+        opctx: &OpContext,
+        authz_service_account: &authz::ServiceAccount,
+        token: ServiceAccountToken,
+    ) -> CreateResult<ServiceAccountToken> {
+        assert_eq!(authz_service_account.id(), token.service_account_id);
+        opctx
+            .authorize(authz::Action::CreateChild, authz_service_account)
+            .await?;
+
+        use db::schema::service_account_token::dsl;
+        diesel::insert_into(dsl::service_account_token)
+            .values(token)
+            .returning(ServiceAccountToken::as_returning())
+            .get_result_async(&*self.pool_connection_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))
+    }
+
+    /// Revoke one of a service account's tokens.
+    ///
+    /// Tokens are not resources in their own right; deleting one is a
+    /// modification of the service account that owns it.
+    pub async fn service_account_token_delete(
+        &self,
+        opctx: &OpContext,
+        authz_service_account: &authz::ServiceAccount,
+        token_id: Uuid,
+    ) -> DeleteResult {
+        opctx.authorize(authz::Action::Modify, authz_service_account).await?;
+
+        use db::schema::service_account_token::dsl;
+        let updated = diesel::update(dsl::service_account_token)
+            .filter(dsl::id.eq(token_id))
+            .filter(dsl::service_account_id.eq(authz_service_account.id()))
+            .filter(dsl::time_deleted.is_null())
+            .set(dsl::time_deleted.eq(Utc::now()))
+            .execute_async(&*self.pool_connection_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))?;
+        if updated == 0 {
+            return Err(Error::not_found_by_id(
+                ResourceType::ServiceAccountToken,
+                &token_id,
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fetch a live service account token by its bearer token value.
+    ///
+    /// Like [`DataStore::api_token_fetch_for_authn`], this performs no authz
+    /// check and does not check expiration.
+    pub async fn service_account_token_fetch_for_authn(
+        &self,
+        opctx: &OpContext,
+        token: &str,
+    ) -> LookupResult<ServiceAccountToken> {
+        use db::schema::service_account_token::dsl;
+        dsl::service_account_token
+            .filter(dsl::token.eq(token.to_string()))
+            .filter(dsl::time_deleted.is_null())
+            .select(ServiceAccountToken::as_select())
+            .get_result_async(&*self.pool_connection_authorized(opctx).await?)
+            .await
+            .map_err(|e| {
+                public_error_from_diesel(
+                    e,
+                    ErrorHandler::NotFoundByLookup(
+                        ResourceType::ServiceAccountToken,
+                        LookupType::ByOther("bearer token".to_string()),
+                    ),
+                )
+            })
+    }
+
+    /// Record that the given service account token was used at `now`, at
+    /// most once a minute.
+    pub async fn service_account_token_touch(
+        &self,
+        opctx: &OpContext,
+        token_id: Uuid,
+        now: DateTime<Utc>,
+    ) -> Result<(), Error> {
+        use db::schema::service_account_token::dsl;
+        let stale = now - Duration::seconds(LAST_USED_GRANULARITY_SECS);
+        diesel::update(dsl::service_account_token)
+            .filter(dsl::id.eq(token_id))
+            .filter(dsl::time_deleted.is_null())
+            .filter(
+                dsl::time_last_used.is_null().or(dsl::time_last_used.lt(stale)),
+            )
+            .set(dsl::time_last_used.eq(now))
+            .execute_async(&*self.pool_connection_authorized(opctx).await?)
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))?;
+        Ok(())
+    }
+}
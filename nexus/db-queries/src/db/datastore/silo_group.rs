@@ -183,6 +183,43 @@ impl DataStore {
             .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))
     }
 
+    /// Adds a user to, or removes them from, a single group
+    ///
+    /// Unlike [`DataStore::silo_group_membership_replace_for_user`], this
+    /// touches only the one membership row, so concurrent changes to the
+    /// user's other memberships are not lost.
+    pub async fn silo_group_membership_set_for_user(
+        &self,
+        opctx: &OpContext,
+        authz_silo_user: &authz::SiloUser,
+        silo_group_id: Uuid,
+        is_member: bool,
+    ) -> UpdateResult<()> {
+        use db::schema::silo_group_membership::dsl;
+
+        opctx.authorize(authz::Action::Modify, authz_silo_user).await?;
+
+        let conn = self.pool_connection_authorized(opctx).await?;
+        let silo_user_id = authz_silo_user.id();
+        if is_member {
+            diesel::insert_into(dsl::silo_group_membership)
+                .values(SiloGroupMembership { silo_group_id, silo_user_id })
+                .on_conflict((dsl::silo_group_id, dsl::silo_user_id))
+                .do_nothing()
+                .execute_async(&*conn)
+                .await
+        } else {
+            diesel::delete(dsl::silo_group_membership)
+                .filter(dsl::silo_group_id.eq(silo_group_id))
+                .filter(dsl::silo_user_id.eq(silo_user_id))
+                .execute_async(&*conn)
+                .await
+        }
+        .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))?;
+
+        Ok(())
+    }
+
     pub async fn silo_group_delete(
         &self,
         opctx: &OpContext,
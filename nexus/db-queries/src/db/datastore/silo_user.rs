@@ -21,6 +21,7 @@ use crate::db::model::UserBuiltin;
 use crate::db::model::UserProvisionType;
 use crate::db::pagination::paginated;
 use crate::db::update_and_check::UpdateAndCheck;
+use crate::db::DbConnection;
 use async_bb8_diesel::AsyncConnection;
 use async_bb8_diesel::AsyncRunQueryDsl;
 use chrono::Utc;
@@ -107,23 +108,9 @@ impl DataStore {
                         .await?;
                 }
 
-                // Delete console sessions.
-                {
-                    use db::schema::console_session::dsl;
-                    diesel::delete(dsl::console_session)
-                        .filter(dsl::silo_user_id.eq(authz_silo_user_id))
-                        .execute_async(&mut conn)
-                        .await?;
-                }
-
-                // Delete device authentication tokens.
-                {
-                    use db::schema::device_access_token::dsl;
-                    diesel::delete(dsl::device_access_token)
-                        .filter(dsl::silo_user_id.eq(authz_silo_user_id))
-                        .execute_async(&mut conn)
-                        .await?;
-                }
+                // Delete console sessions and tokens.
+                Self::silo_user_credentials_delete(&conn, authz_silo_user_id)
+                    .await?;
 
                 // Delete group memberships.
                 {
@@ -157,6 +144,95 @@ impl DataStore {
             })
     }
 
+    /// Deactivate a Silo User so that they can no longer log in, or
+    /// reactivate them
+    ///
+    /// Deactivating a user also revokes their console sessions and tokens, but
+    /// the user keeps their id, group memberships, and role assignments so
+    /// that all of that is still in place if they're reactivated.
+    pub async fn silo_user_set_active(
+        &self,
+        opctx: &OpContext,
+        authz_silo_user: &authz::SiloUser,
+        active: bool,
+    ) -> UpdateResult<SiloUser> {
+        opctx.authorize(authz::Action::Modify, authz_silo_user).await?;
+
+        let authz_silo_user_id = authz_silo_user.id();
+        self.pool_connection_authorized(opctx)
+            .await?
+            .transaction_async(|conn| async move {
+                let db_silo_user = {
+                    use db::schema::silo_user::dsl;
+                    diesel::update(dsl::silo_user)
+                        .filter(dsl::id.eq(authz_silo_user_id))
+                        .filter(dsl::time_deleted.is_null())
+                        .set((
+                            dsl::active.eq(active),
+                            dsl::time_modified.eq(Utc::now()),
+                        ))
+                        .returning(SiloUser::as_returning())
+                        .get_result_async(&conn)
+                        .await?
+                };
+
+                if !active {
+                    Self::silo_user_credentials_delete(
+                        &conn,
+                        authz_silo_user_id,
+                    )
+                    .await?;
+                }
+
+                Ok(db_silo_user)
+            })
+            .await
+            .map_err(|e| {
+                public_error_from_diesel(
+                    e,
+                    ErrorHandler::NotFoundByResource(authz_silo_user),
+                )
+            })
+    }
+
+    /// Delete the console sessions and tokens that a Silo User could use to
+    /// authenticate
+    async fn silo_user_credentials_delete(
+        conn: &async_bb8_diesel::Connection<DbConnection>,
+        silo_user_id: Uuid,
+    ) -> Result<(), diesel::result::Error> {
+        // Delete console sessions.
+        {
+            use db::schema::console_session::dsl;
+            diesel::delete(dsl::console_session)
+                .filter(dsl::silo_user_id.eq(silo_user_id))
+                .execute_async(conn)
+                .await?;
+        }
+
+        // Delete device authentication tokens.
+        {
+            use db::schema::device_access_token::dsl;
+            diesel::delete(dsl::device_access_token)
+                .filter(dsl::silo_user_id.eq(silo_user_id))
+                .execute_async(conn)
+                .await?;
+        }
+
+        // Delete API tokens.
+        {
+            use db::schema::api_token::dsl;
+            diesel::update(dsl::api_token)
+                .filter(dsl::silo_user_id.eq(silo_user_id))
+                .filter(dsl::time_deleted.is_null())
+                .set(dsl::time_deleted.eq(Utc::now()))
+                .execute_async(conn)
+                .await?;
+        }
+
+        Ok(())
+    }
+
     /// Given an external ID, return
     /// - Ok(Some((authz::SiloUser, SiloUser))) if that external id refers to an
     ///   existing silo user
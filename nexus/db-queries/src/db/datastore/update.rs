@@ -5,25 +5,42 @@
 //! [`DataStore`] methods related to updates and artifacts.
 
 use std::collections::HashMap;
+use std::time::Duration;
 
 use super::DataStore;
+use super::SQL_BATCH_SIZE;
 use crate::authz;
 use crate::context::OpContext;
 use crate::db;
 use crate::db::error::{public_error_from_diesel, ErrorHandler};
 use crate::db::model::SemverVersion;
+use crate::db::pagination::{paginated_multicolumn, Paginator};
 use crate::transaction_retry::OptionalError;
 use async_bb8_diesel::AsyncRunQueryDsl;
+use chrono::{DateTime, Utc};
 use diesel::prelude::*;
 use diesel::result::Error as DieselError;
-use nexus_db_model::{ArtifactHash, TufArtifact, TufRepo, TufRepoDescription};
+use nexus_db_model::{
+    ArtifactHash, Generation, HostPhase1Delivery, MgsUpdateDelivery,
+    TargetRelease, TargetReleaseArtifact, TufArtifact, TufRepo,
+    TufRepoDescription,
+};
 use omicron_common::api::external::{
-    self, CreateResult, LookupResult, LookupType, ResourceType,
-    TufRepoInsertStatus,
+    self, CreateResult, Error, ListResultVec, LookupResult, LookupType,
+    ResourceType, TufRepoInsertStatus,
 };
 use omicron_uuid_kinds::TufRepoKind;
 use omicron_uuid_kinds::TypedUuid;
 use swrite::{swrite, SWrite};
+use uuid::Uuid;
+
+/// How long an MGS update delivery may remain in progress before another
+/// Nexus may claim it
+///
+/// Writing an image through the SP takes minutes, so a delivery that's been
+/// in progress this long was almost certainly abandoned by a Nexus that went
+/// away partway through.
+pub const MGS_UPDATE_DELIVERY_TIMEOUT: Duration = Duration::from_secs(60 * 60);
 
 /// The return value of [`DataStore::update_tuf_repo_description_insert`].
 ///
@@ -147,6 +164,287 @@ impl DataStore {
             .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))?;
         Ok(TufRepoDescription { repo, artifacts })
     }
+
+    /// Returns the most recently requested target release
+    pub async fn target_release_get_current(
+        &self,
+        opctx: &OpContext,
+    ) -> LookupResult<TargetRelease> {
+        opctx.authorize(authz::Action::Read, &authz::FLEET).await?;
+
+        use db::schema::target_release::dsl;
+
+        // The database is initialized with a target release (with no system
+        // version), so there's always at least one row here.
+        let conn = self.pool_connection_authorized(opctx).await?;
+        dsl::target_release
+            .order_by(dsl::generation.desc())
+            .select(TargetRelease::as_select())
+            .first_async(&*conn)
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))
+    }
+
+    /// Records a new target release along with the artifacts from its
+    /// repository that are delivered through MGS
+    ///
+    /// Callers are expected to construct `release` with
+    /// [`TargetRelease::new_next()`] from the current target release.  This
+    /// fails with a conflict error if some other caller recorded a new target
+    /// release in the meantime.
+    pub async fn target_release_insert(
+        &self,
+        opctx: &OpContext,
+        release: TargetRelease,
+        artifacts: Vec<TargetReleaseArtifact>,
+    ) -> CreateResult<TargetRelease> {
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+
+        use db::schema::target_release::dsl;
+        use db::schema::target_release_artifact::dsl as artifact_dsl;
+
+        if let Some(artifact) = artifacts
+            .iter()
+            .find(|a| a.target_release_generation != release.generation)
+        {
+            return Err(Error::internal_error(&format!(
+                "target release artifact has generation {}, but the release \
+                 has generation {}",
+                *artifact.target_release_generation, *release.generation,
+            )));
+        }
+
+        let err = OptionalError::new();
+        let conn = self.pool_connection_authorized(opctx).await?;
+        self.transaction_retry_wrapper("target_release_insert")
+            .transaction(&conn, |conn| {
+                let err = err.clone();
+                let release = release.clone();
+                let artifacts = artifacts.clone();
+                async move {
+                    let inserted = diesel::insert_into(dsl::target_release)
+                        .values(release)
+                        .on_conflict(dsl::generation)
+                        .do_nothing()
+                        .execute_async(&conn)
+                        .await?;
+                    if inserted == 0 {
+                        return Err(err.bail(Error::conflict(
+                            "target release was changed concurrently",
+                        )));
+                    }
+                    if !artifacts.is_empty() {
+                        diesel::insert_into(
+                            artifact_dsl::target_release_artifact,
+                        )
+                        .values(artifacts)
+                        .execute_async(&conn)
+                        .await?;
+                    }
+                    Ok(())
+                }
+            })
+            .await
+            .map_err(|e| match err.take() {
+                Some(err) => err,
+                None => public_error_from_diesel(e, ErrorHandler::Server),
+            })?;
+
+        Ok(release)
+    }
+
+    /// Returns the artifacts recorded with the target release having the
+    /// given generation
+    pub async fn target_release_artifacts_list(
+        &self,
+        opctx: &OpContext,
+        generation: Generation,
+    ) -> ListResultVec<TargetReleaseArtifact> {
+        opctx.authorize(authz::Action::Read, &authz::FLEET).await?;
+
+        use db::schema::target_release_artifact::dsl;
+
+        // A release has at most a few dozen such artifacts, so there's no
+        // need to paginate.
+        let conn = self.pool_connection_authorized(opctx).await?;
+        dsl::target_release_artifact
+            .filter(dsl::target_release_generation.eq(generation))
+            .select(TargetReleaseArtifact::as_select())
+            .load_async(&*conn)
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))
+    }
+
+    /// Records that the control plane delivered a host phase 1 artifact to a
+    /// baseboard, replacing any previous record for that baseboard
+    pub async fn host_phase_1_delivery_record(
+        &self,
+        opctx: &OpContext,
+        delivery: HostPhase1Delivery,
+    ) -> Result<(), Error> {
+        opctx
+            .authorize(authz::Action::Modify, &authz::BLUEPRINT_CONFIG)
+            .await?;
+
+        use db::schema::host_phase_1_delivery::dsl;
+
+        let conn = self.pool_connection_authorized(opctx).await?;
+        diesel::insert_into(dsl::host_phase_1_delivery)
+            .values(delivery.clone())
+            .on_conflict((dsl::part_number, dsl::serial_number))
+            .do_update()
+            .set((
+                dsl::artifact_sha256.eq(delivery.artifact_sha256),
+                dsl::time_delivered.eq(delivery.time_delivered),
+            ))
+            .execute_async(&*conn)
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))?;
+        Ok(())
+    }
+
+    /// Lists the host phase 1 artifacts most recently delivered to every
+    /// baseboard, making as many queries as needed to get them all
+    pub async fn host_phase_1_delivery_list_all_batched(
+        &self,
+        opctx: &OpContext,
+    ) -> ListResultVec<HostPhase1Delivery> {
+        opctx.authorize(authz::Action::Read, &authz::BLUEPRINT_CONFIG).await?;
+        opctx.check_complex_operations_allowed()?;
+
+        use db::schema::host_phase_1_delivery::dsl;
+
+        let conn = self.pool_connection_authorized(opctx).await?;
+        let mut all = Vec::new();
+        let mut paginator = Paginator::new(SQL_BATCH_SIZE);
+        while let Some(p) = paginator.next() {
+            let batch = paginated_multicolumn(
+                dsl::host_phase_1_delivery,
+                (dsl::part_number, dsl::serial_number),
+                &p.current_pagparams(),
+            )
+            .select(HostPhase1Delivery::as_select())
+            .load_async(&*conn)
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))?;
+            paginator = p.found_batch(&batch, &|row| {
+                (row.part_number.clone(), row.serial_number.clone())
+            });
+            all.extend(batch);
+        }
+        Ok(all)
+    }
+
+    /// Claims the delivery of an artifact to a baseboard through MGS,
+    /// returning whether the claim succeeded
+    ///
+    /// Only one delivery per baseboard may be claimed at a time.  The claim
+    /// succeeds if there's no previous delivery for the baseboard, or if the
+    /// previous one:
+    ///
+    /// * finished, and either was of a different artifact or finished before
+    ///   `inventory_started` (so that inventory now reflects it), or
+    /// * never finished, and was started more than
+    ///   [`MGS_UPDATE_DELIVERY_TIMEOUT`] ago (so that the Nexus delivering it
+    ///   has presumably gone away).
+    ///
+    /// The caller must finish or release a successful claim using
+    /// [`DataStore::mgs_update_delivery_finish`] or
+    /// [`DataStore::mgs_update_delivery_release`].
+    pub async fn mgs_update_delivery_claim(
+        &self,
+        opctx: &OpContext,
+        delivery: MgsUpdateDelivery,
+        inventory_started: DateTime<Utc>,
+    ) -> Result<bool, Error> {
+        opctx
+            .authorize(authz::Action::Modify, &authz::BLUEPRINT_CONFIG)
+            .await?;
+
+        use db::schema::mgs_update_delivery::dsl;
+
+        let abandoned_before = delivery.time_started
+            - chrono::Duration::from_std(MGS_UPDATE_DELIVERY_TIMEOUT)
+                .expect("timeout fits in a chrono::Duration");
+        let conn = self.pool_connection_authorized(opctx).await?;
+        let nclaimed = diesel::insert_into(dsl::mgs_update_delivery)
+            .values(delivery.clone())
+            .on_conflict((dsl::part_number, dsl::serial_number))
+            .do_update()
+            .set((
+                dsl::id.eq(delivery.id),
+                dsl::artifact_sha256.eq(delivery.artifact_sha256),
+                dsl::time_started.eq(delivery.time_started),
+                dsl::time_delivered.eq(delivery.time_delivered),
+            ))
+            .filter(
+                dsl::time_delivered
+                    .is_not_null()
+                    .and(
+                        dsl::artifact_sha256
+                            .ne(delivery.artifact_sha256)
+                            .or(dsl::time_delivered.lt(inventory_started)),
+                    )
+                    .or(dsl::time_delivered
+                        .is_null()
+                        .and(dsl::time_started.lt(abandoned_before))),
+            )
+            .execute_async(&*conn)
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))?;
+        Ok(nclaimed == 1)
+    }
+
+    /// Records that the delivery claimed by
+    /// [`DataStore::mgs_update_delivery_claim`] finished
+    ///
+    /// This does nothing if the claim has since been taken over by another
+    /// delivery.
+    pub async fn mgs_update_delivery_finish(
+        &self,
+        opctx: &OpContext,
+        delivery_id: Uuid,
+    ) -> Result<(), Error> {
+        opctx
+            .authorize(authz::Action::Modify, &authz::BLUEPRINT_CONFIG)
+            .await?;
+
+        use db::schema::mgs_update_delivery::dsl;
+
+        let conn = self.pool_connection_authorized(opctx).await?;
+        diesel::update(dsl::mgs_update_delivery)
+            .filter(dsl::id.eq(delivery_id))
+            .filter(dsl::time_delivered.is_null())
+            .set(dsl::time_delivered.eq(Utc::now()))
+            .execute_async(&*conn)
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))?;
+        Ok(())
+    }
+
+    /// Gives up the delivery claimed by
+    /// [`DataStore::mgs_update_delivery_claim`] without recording it as
+    /// finished, so that it may be attempted again
+    pub async fn mgs_update_delivery_release(
+        &self,
+        opctx: &OpContext,
+        delivery_id: Uuid,
+    ) -> Result<(), Error> {
+        opctx
+            .authorize(authz::Action::Modify, &authz::BLUEPRINT_CONFIG)
+            .await?;
+
+        use db::schema::mgs_update_delivery::dsl;
+
+        let conn = self.pool_connection_authorized(opctx).await?;
+        diesel::delete(dsl::mgs_update_delivery)
+            .filter(dsl::id.eq(delivery_id))
+            .filter(dsl::time_delivered.is_null())
+            .execute_async(&*conn)
+            .await
+            .map_err(|e| public_error_from_diesel(e, ErrorHandler::Server))?;
+        Ok(())
+    }
 }
 
 // This is a separate method mostly to make rustfmt not bail out on long lines
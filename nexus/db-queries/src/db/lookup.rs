@@ -338,11 +338,49 @@ impl<'a> LookupPath<'a> {
         SiloGroup::PrimaryKey(Root { lookup_root: self }, id)
     }
 
+    /// Select a resource of type ServiceAccount, identified by its owned name
+    /// within the current Silo
+    pub fn service_account_name_owned<'b, 'c>(
+        self,
+        name: Name,
+    ) -> ServiceAccount<'c>
+    where
+        'a: 'c,
+        'b: 'c,
+    {
+        match self
+            .opctx
+            .authn
+            .silo_required()
+            .internal_context("looking up ServiceAccount by name")
+        {
+            Ok(authz_silo) => {
+                let root = Root { lookup_root: self };
+                let silo_key = Silo::PrimaryKey(root, authz_silo.id());
+                ServiceAccount::OwnedName(silo_key, name)
+            }
+            Err(error) => {
+                let root = Root { lookup_root: self };
+                ServiceAccount::Error(root, error)
+            }
+        }
+    }
+
+    /// Select a resource of type ServiceAccount, identified by its id
+    pub fn service_account_id(self, id: Uuid) -> ServiceAccount<'a> {
+        ServiceAccount::PrimaryKey(Root { lookup_root: self }, id)
+    }
+
     /// Select a resource of type SshKey, identified by its id
     pub fn ssh_key_id(self, id: Uuid) -> SshKey<'a> {
         SshKey::PrimaryKey(Root { lookup_root: self }, id)
     }
 
+    /// Select a resource of type ApiToken, identified by its id
+    pub fn api_token_id(self, id: Uuid) -> ApiToken<'a> {
+        ApiToken::PrimaryKey(Root { lookup_root: self }, id)
+    }
+
     /// Select a resource of type Rack, identified by its id
     pub fn rack_id(self, id: Uuid) -> Rack<'a> {
         Rack::PrimaryKey(Root { lookup_root: self }, id)
@@ -521,6 +559,17 @@ impl<'a> LookupPath<'a> {
     {
         SamlIdentityProvider::PrimaryKey(Root { lookup_root: self }, id)
     }
+
+    /// Select a resource of type OidcIdentityProvider, identified by its id
+    pub fn oidc_identity_provider_id<'b>(
+        self,
+        id: Uuid,
+    ) -> OidcIdentityProvider<'b>
+    where
+        'a: 'b,
+    {
+        OidcIdentityProvider::PrimaryKey(Root { lookup_root: self }, id)
+    }
 }
 
 /// Represents the head of the selection path for a resource
@@ -544,7 +593,7 @@ impl<'a> Root<'a> {
 lookup_resource! {
     name = "Silo",
     ancestors = [],
-    children = [ "IdentityProvider", "SamlIdentityProvider", "Project", "SiloImage", "Certificate" ],
+    children = [ "IdentityProvider", "SamlIdentityProvider", "OidcIdentityProvider", "Project", "SiloImage", "Certificate", "ServiceAccount" ],
     lookup_by_name = true,
     soft_deletes = true,
     primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
@@ -553,7 +602,7 @@ lookup_resource! {
 lookup_resource! {
     name = "SiloUser",
     ancestors = [ "Silo" ],
-    children = [ "SshKey" ],
+    children = [ "SshKey", "ApiToken" ],
     lookup_by_name = false,
     soft_deletes = true,
     primary_key_columns = [ { column_name = "id", rust_type = Uuid } ],
@@ -569,6 +618,15 @@ lookup_resource! {
     primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
 }
 
+lookup_resource! {
+    name = "ServiceAccount",
+    ancestors = [ "Silo" ],
+    children = [],
+    lookup_by_name = true,
+    soft_deletes = true,
+    primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
+}
+
 lookup_resource! {
     name = "SiloImage",
     ancestors = [ "Silo" ],
@@ -611,6 +669,18 @@ lookup_resource! {
     visible_outside_silo = true
 }
 
+lookup_resource! {
+    name = "OidcIdentityProvider",
+    ancestors = [ "Silo" ],
+    children = [],
+    lookup_by_name = true,
+    soft_deletes = true,
+    primary_key_columns = [
+        { column_name = "id", rust_type = Uuid },
+    ],
+    visible_outside_silo = true
+}
+
 lookup_resource! {
     name = "SshKey",
     ancestors = [ "Silo", "SiloUser" ],
@@ -620,6 +690,15 @@ lookup_resource! {
     primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
 }
 
+lookup_resource! {
+    name = "ApiToken",
+    ancestors = [ "Silo", "SiloUser" ],
+    children = [],
+    lookup_by_name = true,
+    soft_deletes = true,
+    primary_key_columns = [ { column_name = "id", rust_type = Uuid } ]
+}
+
 lookup_resource! {
     name = "Project",
     ancestors = [ "Silo" ],
@@ -25,7 +25,8 @@ use diesel::{
     SelectableHelper,
 };
 use nexus_db_model::queries::virtual_provisioning_collection_update::{
-    all_collections, do_update, parent_silo, quotas, silo_provisioned,
+    all_collections, do_update, parent_silo, project_limits,
+    project_provisioned, quotas, silo_provisioned,
 };
 use omicron_common::api::external;
 use omicron_common::api::external::MessagePair;
@@ -33,6 +34,12 @@ use omicron_common::api::external::MessagePair;
 const NOT_ENOUGH_CPUS_SENTINEL: &'static str = "Not enough cpus";
 const NOT_ENOUGH_MEMORY_SENTINEL: &'static str = "Not enough memory";
 const NOT_ENOUGH_STORAGE_SENTINEL: &'static str = "Not enough storage";
+const NOT_ENOUGH_PROJECT_CPUS_SENTINEL: &'static str =
+    "Not enough project cpus";
+const NOT_ENOUGH_PROJECT_MEMORY_SENTINEL: &'static str =
+    "Not enough project memory";
+const NOT_ENOUGH_PROJECT_STORAGE_SENTINEL: &'static str =
+    "Not enough project storage";
 
 /// Translates a generic pool error to an external error based
 /// on messages which may be emitted when provisioning virtual resources
@@ -44,6 +51,9 @@ pub fn from_diesel(e: DieselError) -> external::Error {
         NOT_ENOUGH_CPUS_SENTINEL,
         NOT_ENOUGH_MEMORY_SENTINEL,
         NOT_ENOUGH_STORAGE_SENTINEL,
+        NOT_ENOUGH_PROJECT_CPUS_SENTINEL,
+        NOT_ENOUGH_PROJECT_MEMORY_SENTINEL,
+        NOT_ENOUGH_PROJECT_STORAGE_SENTINEL,
     ];
     if let Some(sentinel) = matches_sentinel(&e, &sentinels) {
         match sentinel {
@@ -71,6 +81,30 @@ pub fn from_diesel(e: DieselError) -> external::Error {
                     )
                 }
             }
+            NOT_ENOUGH_PROJECT_CPUS_SENTINEL => {
+                return external::Error::InsufficientCapacity {
+                    message: MessagePair::new_full(
+                         "Project vCPU Limit Exceeded: Not enough vCPUs left in this project's quota to complete request. Either stop unused instances in the project to free up resources or ask a silo administrator to raise the project's quota.".to_string(),
+                         "User tried to allocate an instance but the project's quota did not have enough CPUs available to satisfy the request.".to_string(),
+                    )
+                }
+            }
+            NOT_ENOUGH_PROJECT_MEMORY_SENTINEL => {
+                return external::Error::InsufficientCapacity {
+                    message: MessagePair::new_full(
+                         "Project Memory Limit Exceeded: Not enough memory left in this project's quota to complete request. Either stop unused instances in the project to free up resources or ask a silo administrator to raise the project's quota.".to_string(),
+                         "User tried to allocate an instance but the project's quota did not have enough RAM available to satisfy the request.".to_string(),
+                    )
+                }
+            }
+            NOT_ENOUGH_PROJECT_STORAGE_SENTINEL => {
+                return external::Error::InsufficientCapacity {
+                    message: MessagePair::new_full(
+                         "Project Storage Limit Exceeded: Not enough storage left in this project's quota to complete request. Either remove unneeded disks and snapshots in the project to free up resources or ask a silo administrator to raise the project's quota.".to_string(),
+                         "User tried to allocate a disk or snapshot but the project's quota did not have enough storage available to satisfy the request.".to_string(),
+                    )
+                }
+            }
             _ => {}
         }
     }
@@ -138,6 +172,8 @@ impl DoUpdate {
     fn new_for_insert(
         silo_provisioned: &SiloProvisioned,
         quotas: &Quotas,
+        project_provisioned: &ProjectProvisioned,
+        project_limits: &ProjectLimits,
         resource: VirtualProvisioningResource,
     ) -> Self {
         use virtual_provisioning_resource::dsl;
@@ -193,6 +229,63 @@ impl DoUpdate {
                 .assume_not_null()
                 + storage_provisioned_delta);
 
+        // A project may have no limits at all (no row), or no limit on a
+        // particular resource (a NULL column).  Either way, the limit's
+        // subquery evaluates to NULL, and only the silo's quota applies.
+        let project_has_sufficient_cpus = project_limits
+            .query_source()
+            .select(project_limits::cpus)
+            .single_value()
+            .is_null()
+            .or(project_limits
+                .query_source()
+                .select(project_limits::cpus)
+                .single_value()
+                .ge((project_provisioned
+                    .query_source()
+                    .select(project_provisioned::cpus_provisioned)
+                    .single_value()
+                    .assume_not_null()
+                    + cpus_provisioned_delta)
+                    .nullable()))
+            .assume_not_null();
+
+        let project_has_sufficient_memory = project_limits
+            .query_source()
+            .select(project_limits::memory)
+            .single_value()
+            .is_null()
+            .or(project_limits
+                .query_source()
+                .select(project_limits::memory)
+                .single_value()
+                .ge((project_provisioned
+                    .query_source()
+                    .select(project_provisioned::ram_provisioned)
+                    .single_value()
+                    .assume_not_null()
+                    + memory_provisioned_delta)
+                    .nullable()))
+            .assume_not_null();
+
+        let project_has_sufficient_storage = project_limits
+            .query_source()
+            .select(project_limits::storage)
+            .single_value()
+            .is_null()
+            .or(project_limits
+                .query_source()
+                .select(project_limits::storage)
+                .single_value()
+                .ge((project_provisioned
+                    .query_source()
+                    .select(project_provisioned::virtual_disk_bytes_provisioned)
+                    .single_value()
+                    .assume_not_null()
+                    + storage_provisioned_delta)
+                    .nullable()))
+            .assume_not_null();
+
         Self {
             query: Box::new(diesel::select((ExpressionAlias::new::<
                 do_update::update,
@@ -213,6 +306,24 @@ impl DoUpdate {
                             .eq(0)
                             .or(has_sufficient_storage),
                         NOT_ENOUGH_STORAGE_SENTINEL,
+                    ))
+                    .and(TrueOrCastError::new(
+                        cpus_provisioned_delta
+                            .eq(0)
+                            .or(project_has_sufficient_cpus),
+                        NOT_ENOUGH_PROJECT_CPUS_SENTINEL,
+                    ))
+                    .and(TrueOrCastError::new(
+                        memory_provisioned_delta
+                            .eq(0)
+                            .or(project_has_sufficient_memory),
+                        NOT_ENOUGH_PROJECT_MEMORY_SENTINEL,
+                    ))
+                    .and(TrueOrCastError::new(
+                        storage_provisioned_delta
+                            .eq(0)
+                            .or(project_has_sufficient_storage),
+                        NOT_ENOUGH_PROJECT_STORAGE_SENTINEL,
                     )),
             ),))),
         }
@@ -341,6 +452,58 @@ impl SiloProvisioned {
     }
 }
 
+#[derive(Subquery, QueryId)]
+#[subquery(name = project_limits)]
+struct ProjectLimits {
+    query: Box<dyn CteQuery<SqlType = project_limits::SqlType>>,
+}
+
+impl ProjectLimits {
+    fn new(project_id: uuid::Uuid) -> Self {
+        use crate::db::schema::project_quotas::dsl;
+        Self {
+            query: Box::new(
+                dsl::project_quotas
+                    .filter(dsl::project_id.eq(project_id))
+                    .select((
+                        dsl::project_id,
+                        dsl::cpus,
+                        ExpressionAlias::new::<project_limits::dsl::memory>(
+                            dsl::memory_bytes,
+                        ),
+                        ExpressionAlias::new::<project_limits::dsl::storage>(
+                            dsl::storage_bytes,
+                        ),
+                    )),
+            ),
+        }
+    }
+}
+
+#[derive(Subquery, QueryId)]
+#[subquery(name = project_provisioned)]
+struct ProjectProvisioned {
+    query: Box<dyn CteQuery<SqlType = project_provisioned::SqlType>>,
+}
+
+impl ProjectProvisioned {
+    fn new(project_id: uuid::Uuid) -> Self {
+        use virtual_provisioning_collection::dsl;
+        Self {
+            query: Box::new(
+                dsl::virtual_provisioning_collection
+                    .filter(dsl::id.eq(project_id))
+                    .select((
+                        dsl::id,
+                        dsl::virtual_disk_bytes_provisioned,
+                        dsl::cpus_provisioned,
+                        dsl::ram_provisioned,
+                    )),
+            ),
+        }
+    }
+}
+
 // This structure wraps a query, such that it can be used within a CTE.
 //
 // It generates a name that can be used by the "CteBuilder", but does not
@@ -429,11 +592,17 @@ impl VirtualProvisioningCollectionUpdate {
 
         let quotas = Quotas::new(&parent_silo);
         let silo_provisioned = SiloProvisioned::new(&parent_silo);
+        let project_limits = ProjectLimits::new(project_id);
+        let project_provisioned = ProjectProvisioned::new(project_id);
 
         let do_update = match update_kind {
-            UpdateKind::Insert(resource) => {
-                DoUpdate::new_for_insert(&silo_provisioned, &quotas, resource)
-            }
+            UpdateKind::Insert(resource) => DoUpdate::new_for_insert(
+                &silo_provisioned,
+                &quotas,
+                &project_provisioned,
+                &project_limits,
+                resource,
+            ),
             UpdateKind::Delete(id) => DoUpdate::new_for_delete(id),
         };
 
@@ -453,6 +622,8 @@ impl VirtualProvisioningCollectionUpdate {
             .add_subquery(all_collections)
             .add_subquery(quotas)
             .add_subquery(silo_provisioned)
+            .add_subquery(project_limits)
+            .add_subquery(project_provisioned)
             .add_subquery(do_update)
             .add_subquery(update)
             .add_subquery(updated_collections)
@@ -9,6 +9,7 @@ use anyhow::Context;
 use async_trait::async_trait;
 use dropshot::HttpError;
 use futures::TryFutureExt;
+use omicron_common::api::external::Error;
 use omicron_common::backoff;
 use slog::Logger;
 use std::fmt;
@@ -89,9 +90,19 @@ impl steno::SecStore for CockroachDbSecStore {
                 // https://github.com/oxidecomputer/omicron/issues/5406 and the
                 // note in `nexus/src/app/saga.rs`'s `execute_saga` for more
                 // details.
-                self.datastore
-                    .saga_create_event(&our_event)
-                    .map_err(backoff::BackoffError::transient)
+                //
+                // The one permanent error we don't retry is a conflict, which
+                // means the saga has been abandoned.  Nothing more will ever be
+                // recorded for it, and an abandoned saga is never recovered,
+                // so the event can be dropped.
+                self.datastore.saga_create_event(&our_event).map_err(|error| {
+                    match error {
+                        Error::Conflict { .. } => {
+                            backoff::BackoffError::permanent(error)
+                        }
+                        _ => backoff::BackoffError::transient(error),
+                    }
+                })
             },
             move |error, call_count, total_duration| {
                 let http_error = HttpError::from(error.clone());
@@ -124,7 +135,14 @@ impl steno::SecStore for CockroachDbSecStore {
             },
         )
         .await
-        .expect("the above backoff retries forever")
+        .unwrap_or_else(|error| {
+            warn!(
+                &self.log,
+                "not recording event for abandoned saga";
+                "saga_id" => our_event.saga_id.0.to_string(),
+                "error" => &error,
+            );
+        })
     }
 
     async fn saga_update(&self, id: SagaId, update: steno::SagaCachedState) {
@@ -138,9 +156,23 @@ impl steno::SecStore for CockroachDbSecStore {
 
         // TODO-robustness This should be wrapped with a retry loop rather than
         // unwrapping the result.  See omicron#2416.
-        self.datastore
+        //
+        // A conflict means the saga has been abandoned (e.g., by an operator
+        // using omdb) while this Nexus was still executing it.  Its state
+        // can no longer change, so there's nothing to do.
+        match self
+            .datastore
             .saga_update_state(id, update, self.sec_id, Generation::new())
             .await
-            .unwrap();
+        {
+            Ok(()) => (),
+            Err(error @ Error::Conflict { .. }) => {
+                warn!(&self.log, "not updating state of abandoned saga";
+                    "saga_id" => id.to_string(),
+                    "error" => &error,
+                );
+            }
+            Err(error) => panic!("failed to update saga state: {error:#}"),
+        }
     }
 }
@@ -12,6 +12,8 @@ use anyhow::anyhow;
 use chrono::DateTime;
 use chrono::Utc;
 use gateway_client::types::SpComponentCaboose;
+use gateway_client::types::SpComponentDetails;
+use gateway_client::types::SpComponentInfo;
 use gateway_client::types::SpState;
 use gateway_client::types::SpType;
 use nexus_types::inventory::BaseboardId;
@@ -24,8 +26,12 @@ use nexus_types::inventory::RotPage;
 use nexus_types::inventory::RotPageFound;
 use nexus_types::inventory::RotPageWhich;
 use nexus_types::inventory::RotState;
+use nexus_types::inventory::SensorReading;
 use nexus_types::inventory::ServiceProcessor;
 use nexus_types::inventory::SledAgent;
+use nexus_types::inventory::Switch;
+use nexus_types::inventory::SwitchLocation;
+use nexus_types::inventory::SwitchPortState;
 use nexus_types::inventory::Zpool;
 use omicron_uuid_kinds::CollectionKind;
 use omicron_uuid_kinds::GenericUuid;
@@ -88,8 +94,10 @@ pub struct CollectionBuilder {
         BTreeMap<CabooseWhich, BTreeMap<Arc<BaseboardId>, CabooseFound>>,
     rot_pages_found:
         BTreeMap<RotPageWhich, BTreeMap<Arc<BaseboardId>, RotPageFound>>,
+    sensor_readings: BTreeMap<Arc<BaseboardId>, Vec<SensorReading>>,
     sleds: BTreeMap<SledUuid, SledAgent>,
     omicron_zones: BTreeMap<SledUuid, OmicronZonesFound>,
+    switches: BTreeMap<SwitchLocation, Switch>,
     // We just generate one UUID for each collection.
     id_rng: TypedUuidRng<CollectionKind>,
 }
@@ -115,8 +123,10 @@ impl CollectionBuilder {
             rots: BTreeMap::new(),
             cabooses_found: BTreeMap::new(),
             rot_pages_found: BTreeMap::new(),
+            sensor_readings: BTreeMap::new(),
             sleds: BTreeMap::new(),
             omicron_zones: BTreeMap::new(),
+            switches: BTreeMap::new(),
             id_rng: TypedUuidRng::from_entropy(),
         }
     }
@@ -142,8 +152,10 @@ impl CollectionBuilder {
             rots: self.rots,
             cabooses_found: self.cabooses_found,
             rot_pages_found: self.rot_pages_found,
+            sensor_readings: self.sensor_readings,
             sled_agents: self.sleds,
             omicron_zones: self.omicron_zones,
+            switches: self.switches,
         }
     }
 
@@ -395,6 +407,83 @@ impl CollectionBuilder {
         }
     }
 
+    /// Returns true if we already found sensor readings for component
+    /// `component` of baseboard `baseboard`
+    ///
+    /// This is used to avoid requesting them multiple times (from multiple MGS
+    /// instances).
+    pub fn found_sensor_readings_already(
+        &self,
+        baseboard: &BaseboardId,
+        component: &str,
+    ) -> bool {
+        self.sensor_readings
+            .get(baseboard)
+            .map(|readings| readings.iter().any(|r| r.component == component))
+            .unwrap_or(false)
+    }
+
+    /// Record the sensor readings reported in the details of the given SP
+    /// component
+    ///
+    /// The baseboard must previously have been reported using
+    /// `found_sp_state()`.  Details that are not sensor readings (e.g., port
+    /// status) are ignored.
+    ///
+    /// `source` is an arbitrary string for debugging that describes the MGS
+    /// that reported this data (generally a URL string).
+    pub fn found_sensor_readings(
+        &mut self,
+        baseboard: &BaseboardId,
+        source: &str,
+        component: &SpComponentInfo,
+        details: Vec<SpComponentDetails>,
+    ) -> Result<(), CollectorBug> {
+        let (baseboard, _) =
+            self.sps.get_key_value(baseboard).ok_or_else(|| {
+                anyhow!(
+                    "reporting sensor readings for unknown baseboard: {:?} \
+                     (component {:?})",
+                    baseboard,
+                    component.component,
+                )
+            })?;
+        if self.found_sensor_readings_already(baseboard, &component.component) {
+            return Err(CollectorBug::from(anyhow!(
+                "baseboard {:?} component {:?}: reported sensor readings \
+                 multiple times",
+                baseboard,
+                component.component,
+            )));
+        }
+
+        let time_collected = now_db_precision();
+        let new_readings = details.into_iter().filter_map(|d| {
+            let (name, kind, value) = match d {
+                SpComponentDetails::Measurement { name, kind, value } => {
+                    (name, kind, Ok(value))
+                }
+                SpComponentDetails::MeasurementError { name, kind, error } => {
+                    (name, kind, Err(format!("{:?}", error)))
+                }
+                SpComponentDetails::PortStatus { .. }
+                | SpComponentDetails::PortStatusError { .. } => return None,
+            };
+            Some(SensorReading {
+                time_collected,
+                source: source.to_owned(),
+                component: component.component.clone(),
+                device: component.device.clone(),
+                name,
+                kind: kind.into(),
+                value,
+            })
+        });
+        let baseboard = baseboard.clone();
+        self.sensor_readings.entry(baseboard).or_default().extend(new_readings);
+        Ok(())
+    }
+
     /// Helper function for normalizing items
     ///
     /// If `item` (or its equivalent) is not already in `items`, insert it.
@@ -524,6 +613,37 @@ impl CollectionBuilder {
             Ok(())
         }
     }
+
+    /// Record the state of a switch reported by its dataplane daemon (dpd)
+    ///
+    /// `source` is an arbitrary string for debugging that describes the dpd
+    /// that reported this data (generally a URL string).
+    pub fn found_switch(
+        &mut self,
+        source: &str,
+        switch_location: SwitchLocation,
+        dpd_version: String,
+        ports: BTreeMap<String, SwitchPortState>,
+    ) -> Result<(), anyhow::Error> {
+        if let Some(previous) = self.switches.get(&switch_location) {
+            Err(anyhow!(
+                "switch {switch_location}: reported multiple times \
+                (previously {previous:?})",
+            ))
+        } else {
+            self.switches.insert(
+                switch_location,
+                Switch {
+                    time_collected: now_db_precision(),
+                    source: source.to_string(),
+                    switch_location,
+                    dpd_version,
+                    ports,
+                },
+            );
+            Ok(())
+        }
+    }
 }
 
 /// Returns the current time, truncated to the previous microsecond.
@@ -543,11 +663,14 @@ pub fn now_db_precision() -> DateTime<Utc> {
 mod test {
     use super::now_db_precision;
     use super::CollectionBuilder;
+    use crate::examples::measurement;
     use crate::examples::representative;
+    use crate::examples::sensor_component;
     use crate::examples::sp_state;
     use crate::examples::Representative;
     use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
     use base64::Engine;
+    use gateway_client::types::MeasurementKind;
     use gateway_client::types::PowerState;
     use gateway_client::types::RotSlot;
     use gateway_client::types::RotState;
@@ -557,10 +680,14 @@ mod test {
     use nexus_types::inventory::BaseboardId;
     use nexus_types::inventory::Caboose;
     use nexus_types::inventory::CabooseWhich;
+    use nexus_types::inventory::PortLinkState;
     use nexus_types::inventory::RotPage;
     use nexus_types::inventory::RotPageWhich;
+    use nexus_types::inventory::SensorKind;
     use nexus_types::inventory::SledRole;
+    use nexus_types::inventory::SwitchLocation;
     use omicron_common::api::external::ByteCount;
+    use std::collections::BTreeMap;
 
     // Verify the contents of an empty collection.
     #[test]
@@ -582,6 +709,8 @@ mod test {
         assert!(collection.rots.is_empty());
         assert!(collection.cabooses_found.is_empty());
         assert!(collection.rot_pages_found.is_empty());
+        assert!(collection.switches.is_empty());
+        assert!(collection.sensor_readings.is_empty());
     }
 
     // Simple test of a single, fairly typical collection that contains just
@@ -596,6 +725,7 @@ mod test {
     // - serial number reused across different model numbers
     // - sled agent inventory
     // - omicron zone inventory
+    // - switch inventory
     //
     // This test is admittedly pretty tedious and maybe not worthwhile but it's
     // a useful quick check.
@@ -945,6 +1075,84 @@ mod test {
         assert!(collection.sled_agents[&sled_agent_id_unknown]
             .baseboard_id
             .is_none());
+
+        // Verify the switches.
+        assert_eq!(collection.switches.len(), 2);
+        let switch0 = &collection.switches[&SwitchLocation::Switch0];
+        assert_eq!(switch0.switch_location, SwitchLocation::Switch0);
+        assert_eq!(switch0.source, "fake dpd 0");
+        assert_eq!(switch0.dpd_version, "1.0.0");
+        assert!(collection.time_started <= switch0.time_collected);
+        assert!(switch0.time_collected <= collection.time_done);
+        assert_eq!(
+            switch0.ports.keys().collect::<Vec<_>>(),
+            ["qsfp0", "qsfp1", "qsfp2", "rear0"]
+        );
+        assert_eq!(switch0.ports["qsfp0"].link_state, Some(PortLinkState::Up));
+        assert_eq!(
+            switch0.ports["qsfp0"].transceiver.as_ref().unwrap().serial,
+            "serial_sw0-0"
+        );
+        assert_eq!(switch0.ports["qsfp1"].link_state, None);
+        assert_eq!(
+            switch0.ports["qsfp2"].link_state,
+            Some(PortLinkState::Faulted)
+        );
+        assert_eq!(switch0.ports["qsfp2"].transceiver, None);
+        let switch1 = &collection.switches[&SwitchLocation::Switch1];
+        assert_eq!(switch1.dpd_version, "1.0.1");
+        assert_eq!(switch1.ports.len(), 1);
+
+        // Verify the sensor readings.  The port status in the middle of sled1's
+        // fan readings should have been ignored.
+        assert_eq!(collection.sensor_readings.len(), 2);
+        let readings = &collection.sensor_readings[sled1_bb];
+        assert_eq!(
+            readings
+                .iter()
+                .map(|r| (
+                    r.component.as_str(),
+                    r.device.as_str(),
+                    r.name.as_str(),
+                    r.kind,
+                    r.value.clone()
+                ))
+                .collect::<Vec<_>>(),
+            [
+                (
+                    "dev-0",
+                    "tmp117",
+                    "Southwest",
+                    SensorKind::Temperature,
+                    Ok(41.5)
+                ),
+                (
+                    "dev-1",
+                    "max31790",
+                    "Southeast",
+                    SensorKind::Speed,
+                    Ok(6000.0)
+                ),
+                (
+                    "dev-1",
+                    "max31790",
+                    "Northeast",
+                    SensorKind::Speed,
+                    Ok(6300.0)
+                ),
+            ]
+        );
+        for r in readings {
+            assert_eq!(r.source, "test suite");
+            assert!(collection.time_started <= r.time_collected);
+            assert!(r.time_collected <= collection.time_done);
+        }
+        let readings = &collection.sensor_readings[&switch];
+        assert_eq!(readings.len(), 1);
+        assert_eq!(readings[0].name, "Southwest");
+        assert_eq!(readings[0].value, Err(String::from("DeviceUnavailable")));
+        assert!(!collection.sensor_readings.contains_key(&psc));
+        assert!(!collection.sensor_readings.contains_key(sled4_bb));
     }
 
     // Exercises all the failure cases that shouldn't happen in real systems.
@@ -1186,6 +1394,82 @@ mod test {
         ));
         assert!(message.contains(", now RotPage { data_base64: \"page2\" }"));
 
+        // report sensor readings for an unknown baseboard
+        let error = builder
+            .found_sensor_readings(
+                &bogus_baseboard,
+                "dummy",
+                &sensor_component("dev-0", "tmp117"),
+                vec![measurement(
+                    "Southwest",
+                    MeasurementKind::Temperature,
+                    1.0,
+                )],
+            )
+            .unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "reporting sensor readings for unknown baseboard: \
+            BaseboardId { part_number: \"p1\", serial_number: \"bogus\" } \
+            (component \"dev-0\")"
+        );
+        assert!(
+            !builder.found_sensor_readings_already(&bogus_baseboard, "dev-0")
+        );
+
+        // report sensor readings for the same component twice
+        builder
+            .found_sensor_readings(
+                &sled1_bb,
+                "dummy",
+                &sensor_component("dev-0", "tmp117"),
+                vec![measurement(
+                    "Southwest",
+                    MeasurementKind::Temperature,
+                    1.0,
+                )],
+            )
+            .unwrap();
+        assert!(builder.found_sensor_readings_already(&sled1_bb, "dev-0"));
+        let error = builder
+            .found_sensor_readings(
+                &sled1_bb,
+                "dummy",
+                &sensor_component("dev-0", "tmp117"),
+                vec![measurement(
+                    "Southwest",
+                    MeasurementKind::Temperature,
+                    2.0,
+                )],
+            )
+            .unwrap_err();
+        let message = format!("{:#}", error);
+        println!("found error: {}", message);
+        assert!(message.ends_with(
+            "component \"dev-0\": reported sensor readings multiple times"
+        ));
+
+        // report the same switch twice
+        builder
+            .found_switch(
+                "dpd",
+                SwitchLocation::Switch0,
+                String::from("1.0.0"),
+                BTreeMap::new(),
+            )
+            .unwrap();
+        let error = builder
+            .found_switch(
+                "dpd",
+                SwitchLocation::Switch0,
+                String::from("1.0.1"),
+                BTreeMap::new(),
+            )
+            .unwrap_err();
+        let message = format!("{:#}", error);
+        println!("found error: {}", message);
+        assert!(message.starts_with("switch switch0: reported multiple times"));
+
         // We should still get a valid collection.
         let collection = builder.build();
         println!("{:#?}", collection);
@@ -1232,6 +1516,17 @@ mod test {
             .rot_page_for(RotPageWhich::CfpaScratch, &sled1_bb)
             .is_none());
 
+        // The first report of the switch should have been kept.
+        assert_eq!(
+            collection.switches[&SwitchLocation::Switch0].dpd_version,
+            "1.0.0"
+        );
+
+        // The first sensor reading should have been kept.
+        let readings = &collection.sensor_readings[&sled1_bb];
+        assert_eq!(readings.len(), 1);
+        assert_eq!(readings[0].value, Ok(1.0));
+
         // We should see an error.
         assert_eq!(
             collection
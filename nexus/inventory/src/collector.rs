@@ -6,19 +6,24 @@
 
 use crate::builder::CollectionBuilder;
 use crate::builder::InventoryError;
+use crate::DpdInventory;
 use crate::SledAgentEnumerator;
 use anyhow::Context;
 use gateway_client::types::GetCfpaParams;
 use gateway_client::types::RotCfpaSlot;
+use gateway_messages::DeviceCapabilities;
 use gateway_messages::SpComponent;
 use nexus_types::inventory::CabooseWhich;
 use nexus_types::inventory::Collection;
 use nexus_types::inventory::RotPage;
 use nexus_types::inventory::RotPageWhich;
+use nexus_types::inventory::SwitchLocation;
+use nexus_types::inventory::SwitchPortState;
 use omicron_uuid_kinds::GenericUuid;
 use omicron_uuid_kinds::SledUuid;
 use slog::o;
 use slog::{debug, error};
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use std::time::Duration;
 use strum::IntoEnumIterator;
@@ -30,6 +35,7 @@ const SLED_AGENT_TIMEOUT: Duration = Duration::from_secs(60);
 pub struct Collector<'a> {
     log: slog::Logger,
     mgs_clients: Vec<Arc<gateway_client::Client>>,
+    dpd_clients: Vec<(SwitchLocation, Arc<dyn DpdInventory>)>,
     sled_agent_lister: &'a (dyn SledAgentEnumerator + Send + Sync),
     in_progress: CollectionBuilder,
 }
@@ -38,12 +44,14 @@ impl<'a> Collector<'a> {
     pub fn new(
         creator: &str,
         mgs_clients: &[Arc<gateway_client::Client>],
+        dpd_clients: &[(SwitchLocation, Arc<dyn DpdInventory>)],
         sled_agent_lister: &'a (dyn SledAgentEnumerator + Send + Sync),
         log: slog::Logger,
     ) -> Self {
         Collector {
             log,
             mgs_clients: mgs_clients.to_vec(),
+            dpd_clients: dpd_clients.to_vec(),
             sled_agent_lister,
             in_progress: CollectionBuilder::new(creator),
         }
@@ -68,6 +76,7 @@ impl<'a> Collector<'a> {
 
         self.collect_all_mgs().await;
         self.collect_all_sled_agents().await;
+        self.collect_all_switches().await;
 
         debug!(&self.log, "finished collection");
 
@@ -292,6 +301,80 @@ impl<'a> Collector<'a> {
                     );
                 }
             }
+
+            // Fetch the readings of any environmental sensors on this SP.
+            // These are reported as the details of components that have
+            // measurement channels.  As with the RoT pages, we'd generally only
+            // fetch these from the first MGS client.
+            let result = client
+                .sp_component_list(sp.type_, sp.slot)
+                .await
+                .with_context(|| {
+                    format!(
+                        "MGS {:?}: SP {:?}: listing components",
+                        client.baseurl(),
+                        sp
+                    )
+                });
+            let components = match result {
+                Err(error) => {
+                    self.in_progress.found_error(InventoryError::from(error));
+                    continue;
+                }
+                Ok(response) => response.into_inner().components,
+            };
+
+            for component in components {
+                let capabilities = DeviceCapabilities::from_bits_truncate(
+                    component.capabilities,
+                );
+                if !capabilities
+                    .contains(DeviceCapabilities::HAS_MEASUREMENT_CHANNELS)
+                {
+                    continue;
+                }
+                if self.in_progress.found_sensor_readings_already(
+                    &baseboard_id,
+                    &component.component,
+                ) {
+                    continue;
+                }
+
+                let result = client
+                    .sp_component_get(sp.type_, sp.slot, &component.component)
+                    .await
+                    .with_context(|| {
+                        format!(
+                            "MGS {:?}: SP {:?}: sensors of component {:?}",
+                            client.baseurl(),
+                            sp,
+                            component.component
+                        )
+                    });
+                let details = match result {
+                    Err(error) => {
+                        self.in_progress
+                            .found_error(InventoryError::from(error));
+                        continue;
+                    }
+                    Ok(response) => response.into_inner(),
+                };
+                if let Err(error) = self.in_progress.found_sensor_readings(
+                    &baseboard_id,
+                    client.baseurl(),
+                    &component,
+                    details,
+                ) {
+                    error!(
+                        &self.log,
+                        "error reporting sensor readings: {:?} {:?} {:?}: {:#}",
+                        baseboard_id,
+                        component.component,
+                        client.baseurl(),
+                        error
+                    );
+                }
+            }
         }
     }
 
@@ -368,14 +451,116 @@ impl<'a> Collector<'a> {
             ),
         }
     }
+
+    /// Collect inventory from the dataplane daemon (dpd) in each switch zone
+    async fn collect_all_switches(&mut self) {
+        let clients = self.dpd_clients.clone();
+        for (switch_location, client) in &clients {
+            self.collect_one_switch(*switch_location, client.as_ref()).await;
+        }
+    }
+
+    async fn collect_one_switch(
+        &mut self,
+        switch_location: SwitchLocation,
+        client: &dyn DpdInventory,
+    ) {
+        debug!(&self.log, "begin collection from dpd";
+            "dpd_url" => client.baseurl(),
+            "switch_location" => %switch_location,
+        );
+
+        let result = client
+            .dpd_version()
+            .await
+            .with_context(|| format!("dpd {:?}: version", client.baseurl()));
+        let dpd_version = match result {
+            Err(error) => {
+                self.in_progress.found_error(InventoryError::from(error));
+                return;
+            }
+            Ok(version) => version,
+        };
+
+        let result = client.port_ids().await.with_context(|| {
+            format!("dpd {:?}: listing ports", client.baseurl())
+        });
+        let port_ids = match result {
+            Err(error) => {
+                self.in_progress.found_error(InventoryError::from(error));
+                return;
+            }
+            Ok(port_ids) => port_ids,
+        };
+
+        // Failing to fetch the link or transceiver of one port doesn't stop us
+        // from reporting the rest of the switch.  The port is still recorded,
+        // with whatever we couldn't fetch left empty.
+        let mut ports = BTreeMap::new();
+        for port_id in port_ids {
+            let result = client.link_state(&port_id).await.with_context(|| {
+                format!(
+                    "dpd {:?}: port {:?}: link state",
+                    client.baseurl(),
+                    port_id
+                )
+            });
+            let link_state = match result {
+                Err(error) => {
+                    self.in_progress.found_error(InventoryError::from(error));
+                    None
+                }
+                Ok(link_state) => link_state,
+            };
+
+            let result =
+                client.transceiver(&port_id).await.with_context(|| {
+                    format!(
+                        "dpd {:?}: port {:?}: transceiver",
+                        client.baseurl(),
+                        port_id
+                    )
+                });
+            let transceiver = match result {
+                Err(error) => {
+                    self.in_progress.found_error(InventoryError::from(error));
+                    None
+                }
+                Ok(transceiver) => transceiver,
+            };
+
+            ports.insert(port_id, SwitchPortState { link_state, transceiver });
+        }
+
+        if let Err(error) = self.in_progress.found_switch(
+            client.baseurl(),
+            switch_location,
+            dpd_version,
+            ports,
+        ) {
+            error!(
+                &self.log,
+                "error reporting switch: {} {:?}: {:#}",
+                switch_location,
+                client.baseurl(),
+                error
+            );
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
     use super::Collector;
+    use crate::DpdInventory;
+    use crate::FakeDpd;
     use crate::StaticSledAgentEnumerator;
     use gateway_messages::SpPort;
     use nexus_types::inventory::Collection;
+    use nexus_types::inventory::PortLinkState;
+    use nexus_types::inventory::SwitchLocation;
+    use nexus_types::inventory::SwitchPortState;
+    use nexus_types::inventory::Transceiver;
     use omicron_common::api::external::Generation;
     use omicron_sled_agent::sim;
     use std::fmt::Write;
@@ -470,6 +655,25 @@ mod test {
             }
         }
 
+        write!(&mut s, "\nsensor readings found:\n").unwrap();
+        for (bb, readings) in &collection.sensor_readings {
+            for r in readings {
+                write!(
+                    &mut s,
+                    "    baseboard part {:?} serial {:?}: component {:?} \
+                     ({:?}) sensor {:?} {}: {:?}\n",
+                    bb.part_number,
+                    bb.serial_number,
+                    r.component,
+                    r.device,
+                    r.name,
+                    r.kind,
+                    r.value,
+                )
+                .unwrap();
+            }
+        }
+
         write!(&mut s, "\nsled agents found:\n").unwrap();
         for (sled_id, sled_info) in &collection.sled_agents {
             assert_eq!(*sled_id, sled_info.sled_id);
@@ -499,6 +703,25 @@ mod test {
             }
         }
 
+        write!(&mut s, "\nswitches:\n").unwrap();
+        for (location, switch) in &collection.switches {
+            assert_eq!(*location, switch.switch_location);
+            write!(
+                &mut s,
+                "  {} source {:?} dpd version {:?}\n",
+                location, switch.source, switch.dpd_version
+            )
+            .unwrap();
+            for (port_id, port) in &switch.ports {
+                write!(
+                    &mut s,
+                    "    port {:?} link {:?} transceiver {:?}\n",
+                    port_id, port.link_state, port.transceiver,
+                )
+                .unwrap();
+            }
+        }
+
         write!(&mut s, "\nerrors:\n").unwrap();
         for e in &collection.errors {
             // Some error strings have OS error numbers in them.  We want to
@@ -564,6 +787,60 @@ mod test {
         agent
     }
 
+    fn fake_dpds() -> Vec<(SwitchLocation, Arc<dyn DpdInventory>)> {
+        let transceiver = |serial: &str| Transceiver {
+            vendor: String::from("FAKE VENDOR"),
+            part: String::from("FAKE-QSFP"),
+            revision: String::from("A"),
+            serial: String::from(serial),
+        };
+        let switch0 = FakeDpd::new(
+            "fake dpd 0",
+            "1.0.0",
+            [
+                (
+                    String::from("qsfp0"),
+                    SwitchPortState {
+                        link_state: Some(PortLinkState::Up),
+                        transceiver: Some(transceiver("FAKE0")),
+                    },
+                ),
+                (
+                    String::from("qsfp1"),
+                    SwitchPortState { link_state: None, transceiver: None },
+                ),
+                (
+                    String::from("rear0"),
+                    SwitchPortState {
+                        link_state: Some(PortLinkState::Down),
+                        transceiver: None,
+                    },
+                ),
+            ],
+        );
+        let switch1 = FakeDpd::new(
+            "fake dpd 1",
+            "1.0.0",
+            [(
+                String::from("qsfp0"),
+                SwitchPortState {
+                    link_state: Some(PortLinkState::Faulted),
+                    transceiver: Some(transceiver("FAKE1")),
+                },
+            )],
+        );
+        vec![
+            (
+                SwitchLocation::Switch0,
+                Arc::new(switch0) as Arc<dyn DpdInventory>,
+            ),
+            (
+                SwitchLocation::Switch1,
+                Arc::new(switch1) as Arc<dyn DpdInventory>,
+            ),
+        ]
+    }
+
     #[tokio::test]
     async fn test_basic() {
         // Set up the stock MGS test setup (which includes a couple of fake SPs)
@@ -593,6 +870,7 @@ mod test {
         let collector = Collector::new(
             "test-suite",
             &[mgs_client],
+            &fake_dpds(),
             &sled_enum,
             log.clone(),
         );
@@ -649,8 +927,13 @@ mod test {
             })
             .collect::<Vec<_>>();
         let sled_enum = StaticSledAgentEnumerator::new([sled1_url, sled2_url]);
-        let collector =
-            Collector::new("test-suite", &mgs_clients, &sled_enum, log.clone());
+        let collector = Collector::new(
+            "test-suite",
+            &mgs_clients,
+            &fake_dpds(),
+            &sled_enum,
+            log.clone(),
+        );
         let collection = collector
             .collect_all()
             .await
@@ -690,8 +973,13 @@ mod test {
         };
         let mgs_clients = &[bad_client, real_client];
         let sled_enum = StaticSledAgentEnumerator::empty();
-        let collector =
-            Collector::new("test-suite", mgs_clients, &sled_enum, log.clone());
+        let collector = Collector::new(
+            "test-suite",
+            mgs_clients,
+            &[],
+            &sled_enum,
+            log.clone(),
+        );
         let collection = collector
             .collect_all()
             .await
@@ -730,6 +1018,7 @@ mod test {
         let collector = Collector::new(
             "test-suite",
             &[mgs_client],
+            &[],
             &sled_enum,
             log.clone(),
         );
@@ -749,4 +1038,52 @@ mod test {
         sled1.http_server.close().await.unwrap();
         gwtestctx.teardown().await;
     }
+
+    #[tokio::test]
+    async fn test_dpd_failure() {
+        // Collect from two switches, one of which has a dpd that's offline.
+        // We should still get the state of the other switch.
+        let logctx =
+            omicron_test_utils::dev::test_setup_log("test_dpd_failure");
+        let log = &logctx.log;
+        let real_dpd: Arc<dyn DpdInventory> = fake_dpds()
+            .into_iter()
+            .find(|(location, _)| *location == SwitchLocation::Switch0)
+            .unwrap()
+            .1;
+        let bad_dpd: Arc<dyn DpdInventory> = {
+            // This IP range is guaranteed by RFC 6666 to discard traffic.
+            let url = "http://[100::1]:12345";
+            let client_state = dpd_client::ClientState {
+                tag: String::from("test-suite"),
+                log: log.clone(),
+            };
+            Arc::new(dpd_client::Client::new(url, client_state))
+        };
+        let dpd_clients = [
+            (SwitchLocation::Switch0, real_dpd),
+            (SwitchLocation::Switch1, bad_dpd),
+        ];
+        let sled_enum = StaticSledAgentEnumerator::empty();
+        let collector = Collector::new(
+            "test-suite",
+            &[],
+            &dpd_clients,
+            &sled_enum,
+            log.clone(),
+        );
+        let collection = collector
+            .collect_all()
+            .await
+            .expect("failed to carry out collection");
+        assert_eq!(collection.collector, "test-suite");
+
+        let s = dump_collection(&collection);
+        expectorate::assert_contents(
+            "tests/output/collector_dpd_errors.txt",
+            &s,
+        );
+
+        logctx.cleanup_successful();
+    }
 }
@@ -0,0 +1,196 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use anyhow::anyhow;
+use dpd_client::types::LinkId;
+use dpd_client::types::PortId;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use nexus_types::inventory::PortLinkState;
+use nexus_types::inventory::SwitchPortState;
+use nexus_types::inventory::Transceiver;
+use std::collections::BTreeMap;
+
+/// Describes how to fetch switch state from a dataplane daemon (dpd)
+///
+/// In a real system, this is a `dpd_client::Client`.  For testing, the
+/// `FakeDpd` below can be used to avoid depending on a running dpd.
+pub trait DpdInventory: Send + Sync {
+    /// Returns a string describing this dpd (generally a URL)
+    fn baseurl(&self) -> &str;
+
+    /// Returns the version of dpd that's running
+    fn dpd_version(&self) -> BoxFuture<'_, Result<String, anyhow::Error>>;
+
+    /// Returns the ids of all of the switch's ports (e.g., `qsfp0`)
+    fn port_ids(&self) -> BoxFuture<'_, Result<Vec<String>, anyhow::Error>>;
+
+    /// Returns the state of the link on port `port_id`, or `None` if no link
+    /// has been created on it
+    fn link_state<'a>(
+        &'a self,
+        port_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<PortLinkState>, anyhow::Error>>;
+
+    /// Returns vendor data for the transceiver in port `port_id`, or `None` if
+    /// there's no transceiver there
+    fn transceiver<'a>(
+        &'a self,
+        port_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<Transceiver>, anyhow::Error>>;
+}
+
+impl DpdInventory for dpd_client::Client {
+    fn baseurl(&self) -> &str {
+        dpd_client::Client::baseurl(self)
+    }
+
+    fn dpd_version(&self) -> BoxFuture<'_, Result<String, anyhow::Error>> {
+        async move {
+            Ok(dpd_client::Client::dpd_version(self).await?.into_inner())
+        }
+        .boxed()
+    }
+
+    fn port_ids(&self) -> BoxFuture<'_, Result<Vec<String>, anyhow::Error>> {
+        async move {
+            let ports = self.port_list().await?.into_inner();
+            Ok(ports.iter().map(|port_id| port_id.to_string()).collect())
+        }
+        .boxed()
+    }
+
+    fn link_state<'a>(
+        &'a self,
+        port_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<PortLinkState>, anyhow::Error>> {
+        async move {
+            let port_id = parse_port_id(port_id)?;
+            // There's no breakout support yet, so each port has at most one
+            // link, and its id is always 0.
+            let link = match self.link_get(&port_id, &LinkId(0)).await {
+                Ok(link) => link.into_inner(),
+                Err(error)
+                    if error.status()
+                        == Some(reqwest::StatusCode::NOT_FOUND) =>
+                {
+                    return Ok(None);
+                }
+                Err(error) => return Err(error.into()),
+            };
+            use dpd_client::types::LinkState;
+            Ok(Some(match link.link_state {
+                LinkState::Up => PortLinkState::Up,
+                LinkState::Down => PortLinkState::Down,
+                LinkState::Unknown => PortLinkState::Unknown,
+                LinkState::Faulted(_) => PortLinkState::Faulted,
+            }))
+        }
+        .boxed()
+    }
+
+    fn transceiver<'a>(
+        &'a self,
+        port_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<Transceiver>, anyhow::Error>> {
+        async move {
+            // Only QSFP ports hold transceivers.
+            let port_id = parse_port_id(port_id)?;
+            if !matches!(port_id, PortId::Qsfp(_)) {
+                return Ok(None);
+            }
+            let transceiver = match self.transceiver_get(&port_id).await {
+                Ok(transceiver) => transceiver.into_inner(),
+                Err(error)
+                    if error.status()
+                        == Some(reqwest::StatusCode::NOT_FOUND) =>
+                {
+                    return Ok(None);
+                }
+                Err(error) => return Err(error.into()),
+            };
+            use dpd_client::types::Transceiver as DpdTransceiver;
+            match transceiver {
+                DpdTransceiver::Supported(info) => {
+                    Ok(info.vendor_info.map(|info| Transceiver {
+                        vendor: info.vendor.name,
+                        part: info.vendor.part,
+                        revision: info.vendor.revision,
+                        serial: info.vendor.serial,
+                    }))
+                }
+                DpdTransceiver::Faulted(reason) => {
+                    Err(anyhow!("transceiver is faulted: {:?}", reason))
+                }
+                DpdTransceiver::Unsupported => Ok(None),
+            }
+        }
+        .boxed()
+    }
+}
+
+fn parse_port_id(port_id: &str) -> Result<PortId, anyhow::Error> {
+    port_id
+        .parse()
+        .map_err(|error| anyhow!("invalid port id {:?}: {}", port_id, error))
+}
+
+/// Used to provide a fixed set of switch state to a `Collector`
+///
+/// This is mainly used for testing.
+pub struct FakeDpd {
+    baseurl: String,
+    dpd_version: String,
+    ports: BTreeMap<String, SwitchPortState>,
+}
+
+impl FakeDpd {
+    pub fn new(
+        baseurl: &str,
+        dpd_version: &str,
+        ports: impl IntoIterator<Item = (String, SwitchPortState)>,
+    ) -> Self {
+        FakeDpd {
+            baseurl: baseurl.to_string(),
+            dpd_version: dpd_version.to_string(),
+            ports: ports.into_iter().collect(),
+        }
+    }
+
+    fn port(&self, port_id: &str) -> Result<&SwitchPortState, anyhow::Error> {
+        self.ports
+            .get(port_id)
+            .ok_or_else(|| anyhow!("no such port: {:?}", port_id))
+    }
+}
+
+impl DpdInventory for FakeDpd {
+    fn baseurl(&self) -> &str {
+        &self.baseurl
+    }
+
+    fn dpd_version(&self) -> BoxFuture<'_, Result<String, anyhow::Error>> {
+        futures::future::ready(Ok(self.dpd_version.clone())).boxed()
+    }
+
+    fn port_ids(&self) -> BoxFuture<'_, Result<Vec<String>, anyhow::Error>> {
+        futures::future::ready(Ok(self.ports.keys().cloned().collect())).boxed()
+    }
+
+    fn link_state<'a>(
+        &'a self,
+        port_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<PortLinkState>, anyhow::Error>> {
+        let result = self.port(port_id).map(|port| port.link_state);
+        futures::future::ready(result).boxed()
+    }
+
+    fn transceiver<'a>(
+        &'a self,
+        port_id: &'a str,
+    ) -> BoxFuture<'a, Result<Option<Transceiver>, anyhow::Error>> {
+        let result = self.port(port_id).map(|port| port.transceiver.clone());
+        futures::future::ready(result).boxed()
+    }
+}
@@ -5,20 +5,31 @@
 //! Example collections used for testing
 
 use crate::CollectionBuilder;
+use gateway_client::types::MeasurementErrorCode;
+use gateway_client::types::MeasurementKind;
+use gateway_client::types::PortStatusErrorCode;
 use gateway_client::types::PowerState;
 use gateway_client::types::RotSlot;
 use gateway_client::types::RotState;
 use gateway_client::types::SpComponentCaboose;
+use gateway_client::types::SpComponentDetails;
+use gateway_client::types::SpComponentInfo;
+use gateway_client::types::SpComponentPresence;
 use gateway_client::types::SpState;
 use gateway_client::types::SpType;
 use nexus_types::inventory::BaseboardId;
 use nexus_types::inventory::CabooseWhich;
 use nexus_types::inventory::OmicronZonesConfig;
+use nexus_types::inventory::PortLinkState;
 use nexus_types::inventory::RotPage;
 use nexus_types::inventory::RotPageWhich;
+use nexus_types::inventory::SwitchLocation;
+use nexus_types::inventory::SwitchPortState;
+use nexus_types::inventory::Transceiver;
 use omicron_common::api::external::ByteCount;
 use omicron_uuid_kinds::GenericUuid;
 use omicron_uuid_kinds::SledUuid;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use strum::IntoEnumIterator;
 
@@ -34,6 +45,8 @@ use strum::IntoEnumIterator;
 /// - some missing cabooses
 /// - some cabooses common to multiple baseboards; others not
 /// - serial number reused across different model numbers
+/// - sensor readings, including failed readings
+/// - switch ports with and without links and transceivers
 pub fn representative() -> Representative {
     let mut builder = CollectionBuilder::new("example");
 
@@ -268,6 +281,47 @@ pub fn representative() -> Representative {
 
     // We deliberately provide no RoT pages for sled2.
 
+    // Report some sensor readings: a temperature sensor and two fans on
+    // sled1, and a temperature sensor that couldn't be read on the switch.
+    // The fan controller also reports a port status detail, which should be
+    // ignored.  We deliberately provide no sensor readings for the other
+    // baseboards.
+    builder
+        .found_sensor_readings(
+            &sled1_bb,
+            "test suite",
+            &sensor_component("dev-0", "tmp117"),
+            vec![measurement("Southwest", MeasurementKind::Temperature, 41.5)],
+        )
+        .unwrap();
+    builder
+        .found_sensor_readings(
+            &sled1_bb,
+            "test suite",
+            &sensor_component("dev-1", "max31790"),
+            vec![
+                measurement("Southeast", MeasurementKind::Speed, 6000.0),
+                SpComponentDetails::PortStatusError {
+                    port: 0,
+                    code: PortStatusErrorCode::Unconfigured,
+                },
+                measurement("Northeast", MeasurementKind::Speed, 6300.0),
+            ],
+        )
+        .unwrap();
+    builder
+        .found_sensor_readings(
+            &switch1_bb,
+            "test suite",
+            &sensor_component("dev-0", "tmp117"),
+            vec![SpComponentDetails::MeasurementError {
+                name: String::from("Southwest"),
+                kind: MeasurementKind::Temperature,
+                error: MeasurementErrorCode::DeviceUnavailable,
+            }],
+        )
+        .unwrap();
+
     // Report some sled agents.
     //
     // This first one will match "sled1_bb"'s baseboard information.
@@ -429,6 +483,43 @@ pub fn representative() -> Representative {
         .found_sled_omicron_zones("fake sled 15 agent", sled17_id, sled17)
         .unwrap();
 
+    // Report switch state from both switches, covering ports with and without
+    // links and transceivers.  Both switches have the same part in qsfp0, but
+    // with different serial numbers.
+    builder
+        .found_switch(
+            "fake dpd 0",
+            SwitchLocation::Switch0,
+            String::from("1.0.0"),
+            BTreeMap::from([
+                (
+                    String::from("qsfp0"),
+                    switch_port(Some(PortLinkState::Up), Some("sw0-0")),
+                ),
+                (String::from("qsfp1"), switch_port(None, Some("sw0-1"))),
+                (
+                    String::from("qsfp2"),
+                    switch_port(Some(PortLinkState::Faulted), None),
+                ),
+                (
+                    String::from("rear0"),
+                    switch_port(Some(PortLinkState::Up), None),
+                ),
+            ]),
+        )
+        .unwrap();
+    builder
+        .found_switch(
+            "fake dpd 1",
+            SwitchLocation::Switch1,
+            String::from("1.0.1"),
+            BTreeMap::from([(
+                String::from("qsfp0"),
+                switch_port(Some(PortLinkState::Down), Some("sw1-0")),
+            )]),
+        )
+        .unwrap();
+
     Representative {
         builder,
         sleds: [sled1_bb, sled2_bb, sled3_bb, sled4_bb],
@@ -499,6 +590,44 @@ pub fn rot_page(unique: &str) -> RotPage {
     }
 }
 
+pub fn sensor_component(component: &str, device: &str) -> SpComponentInfo {
+    SpComponentInfo {
+        capabilities: 0x2,
+        component: String::from(component),
+        description: format!("fake {}", device),
+        device: String::from(device),
+        presence: SpComponentPresence::Present,
+        serial_number: None,
+    }
+}
+
+pub fn measurement(
+    name: &str,
+    kind: MeasurementKind,
+    value: f32,
+) -> SpComponentDetails {
+    SpComponentDetails::Measurement { name: String::from(name), kind, value }
+}
+
+pub fn transceiver(unique: &str) -> Transceiver {
+    Transceiver {
+        vendor: String::from("vendor"),
+        part: String::from("part"),
+        revision: String::from("rev"),
+        serial: format!("serial_{}", unique),
+    }
+}
+
+pub fn switch_port(
+    link_state: Option<PortLinkState>,
+    transceiver_unique: Option<&str>,
+) -> SwitchPortState {
+    SwitchPortState {
+        link_state,
+        transceiver: transceiver_unique.map(transceiver),
+    }
+}
+
 pub fn sled_agent(
     sled_id: SledUuid,
     baseboard: sled_agent_client::types::Baseboard,
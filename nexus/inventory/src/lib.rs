@@ -19,6 +19,7 @@
 
 mod builder;
 mod collector;
+mod dpd;
 pub mod examples;
 mod sled_agent_enumerator;
 
@@ -31,5 +32,8 @@ pub use builder::now_db_precision;
 
 pub use collector::Collector;
 
+pub use dpd::DpdInventory;
+pub use dpd::FakeDpd;
+
 pub use sled_agent_enumerator::SledAgentEnumerator;
 pub use sled_agent_enumerator::StaticSledAgentEnumerator;
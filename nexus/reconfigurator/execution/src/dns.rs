@@ -477,6 +477,7 @@ mod test {
     use nexus_types::deployment::BlueprintZoneConfig;
     use nexus_types::deployment::BlueprintZoneDisposition;
     use nexus_types::deployment::BlueprintZonesConfig;
+    use nexus_types::deployment::PendingMgsUpdates;
     use nexus_types::deployment::SledFilter;
     use nexus_types::external_api::params;
     use nexus_types::external_api::shared;
@@ -595,6 +596,7 @@ mod test {
             id: Uuid::new_v4(),
             blueprint_zones,
             blueprint_disks: BTreeMap::new(),
+            pending_mgs_updates: PendingMgsUpdates::new(),
             sled_state,
             parent_blueprint_id: None,
             internal_dns_version: initial_dns_generation,
@@ -1207,6 +1209,8 @@ mod test {
                 .await
                 .unwrap()
         };
+        let target_release_row =
+            datastore.target_release_get_current(&opctx).await.unwrap();
         let planning_input = {
             let mut builder = PlanningInputFromDb {
                 sled_rows: &sled_rows,
@@ -1224,7 +1228,10 @@ mod test {
                 // the planner.
                 external_ip_rows: &[],
                 service_nic_rows: &[],
+                host_phase_1_delivery_rows: &[],
                 target_nexus_zone_count: NEXUS_REDUNDANCY,
+                target_release_row: &target_release_row,
+                target_release_artifact_rows: &[],
                 log,
             }
             .build()
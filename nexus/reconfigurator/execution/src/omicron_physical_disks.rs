@@ -109,7 +109,7 @@ mod test {
     use nexus_test_utils_macros::nexus_test;
     use nexus_types::deployment::{
         Blueprint, BlueprintPhysicalDiskConfig, BlueprintPhysicalDisksConfig,
-        BlueprintTarget,
+        BlueprintTarget, PendingMgsUpdates,
     };
     use omicron_common::api::external::Generation;
     use omicron_common::disk::DiskIdentity;
@@ -136,6 +136,7 @@ mod test {
                 id,
                 blueprint_zones: BTreeMap::new(),
                 blueprint_disks,
+                pending_mgs_updates: PendingMgsUpdates::new(),
                 sled_state: BTreeMap::new(),
                 parent_blueprint_id: None,
                 internal_dns_version: Generation::new(),
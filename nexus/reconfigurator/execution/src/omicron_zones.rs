@@ -99,7 +99,7 @@ mod test {
     };
     use nexus_types::deployment::{
         Blueprint, BlueprintTarget, BlueprintZoneConfig,
-        BlueprintZoneDisposition, BlueprintZonesConfig,
+        BlueprintZoneDisposition, BlueprintZonesConfig, PendingMgsUpdates,
     };
     use nexus_types::inventory::OmicronZoneDataset;
     use omicron_common::api::external::Generation;
@@ -126,6 +126,7 @@ mod test {
                 id,
                 blueprint_zones,
                 blueprint_disks: BTreeMap::new(),
+                pending_mgs_updates: PendingMgsUpdates::new(),
                 sled_state: BTreeMap::new(),
                 parent_blueprint_id: None,
                 internal_dns_version: Generation::new(),
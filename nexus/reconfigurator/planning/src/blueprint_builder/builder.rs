@@ -23,6 +23,7 @@ use nexus_types::deployment::BlueprintZonesConfig;
 use nexus_types::deployment::DiskFilter;
 use nexus_types::deployment::OmicronZoneDataset;
 use nexus_types::deployment::OmicronZoneExternalFloatingIp;
+use nexus_types::deployment::PendingMgsUpdates;
 use nexus_types::deployment::PlanningInput;
 use nexus_types::deployment::SledFilter;
 use nexus_types::deployment::SledResources;
@@ -146,6 +147,7 @@ pub struct BlueprintBuilder<'a> {
     pub(super) zones: BlueprintZonesBuilder<'a>,
     disks: BlueprintDisksBuilder<'a>,
     sled_state: BTreeMap<SledUuid, SledState>,
+    pending_mgs_updates: PendingMgsUpdates,
 
     creator: String,
     comments: Vec<String>,
@@ -205,6 +207,7 @@ impl<'a> BlueprintBuilder<'a> {
             blueprint_zones,
             blueprint_disks: BTreeMap::new(),
             sled_state,
+            pending_mgs_updates: PendingMgsUpdates::new(),
             parent_blueprint_id: None,
             internal_dns_version: Generation::new(),
             external_dns_version: Generation::new(),
@@ -264,6 +267,7 @@ impl<'a> BlueprintBuilder<'a> {
             zones: BlueprintZonesBuilder::new(parent_blueprint),
             disks: BlueprintDisksBuilder::new(parent_blueprint),
             sled_state,
+            pending_mgs_updates: parent_blueprint.pending_mgs_updates.clone(),
             creator: creator.to_owned(),
             comments: Vec::new(),
             rng: BlueprintBuilderRng::new(),
@@ -284,6 +288,12 @@ impl<'a> BlueprintBuilder<'a> {
         self.zones.current_sled_zones(sled_id).map(|(config, _)| config)
     }
 
+    /// Returns whether any sled's zones have been changed relative to the
+    /// parent blueprint
+    pub fn has_zone_changes(&self) -> bool {
+        self.zones.has_changes()
+    }
+
     /// Assemble a final [`Blueprint`] based on the contents of the builder
     pub fn build(mut self) -> Blueprint {
         // Collect the Omicron zones config for all sleds, including sleds that
@@ -299,6 +309,7 @@ impl<'a> BlueprintBuilder<'a> {
             blueprint_zones,
             blueprint_disks,
             sled_state: self.sled_state,
+            pending_mgs_updates: self.pending_mgs_updates,
             parent_blueprint_id: Some(self.parent_blueprint.id),
             internal_dns_version: self.input.internal_dns_version(),
             external_dns_version: self.input.external_dns_version(),
@@ -317,6 +328,16 @@ impl<'a> BlueprintBuilder<'a> {
         self.sled_state.insert(sled_id, desired_state);
     }
 
+    /// Returns the MGS-managed updates that are currently pending
+    pub fn pending_mgs_updates(&self) -> &PendingMgsUpdates {
+        &self.pending_mgs_updates
+    }
+
+    /// Replaces the set of pending MGS-managed updates
+    pub fn set_pending_mgs_updates(&mut self, updates: PendingMgsUpdates) {
+        self.pending_mgs_updates = updates;
+    }
+
     /// Within tests, set a seeded RNG for deterministic results.
     ///
     /// This will ensure that tests that use this builder will produce the same
@@ -885,6 +906,11 @@ impl<'a> BlueprintZonesBuilder<'a> {
         })
     }
 
+    /// Returns whether we've changed any sled's zones
+    pub fn has_changes(&self) -> bool {
+        !self.changed_zones.is_empty()
+    }
+
     /// Iterates over the list of sled IDs for which we have zones.
     ///
     /// This may include decommissioned sleds.
@@ -88,10 +88,6 @@ impl ExampleSystem {
         }
 
         let blueprint = builder.build();
-        let mut builder =
-            system.to_collection_builder().expect("failed to build collection");
-        builder.set_rng_seed((test_name, "ExampleSystem collection"));
-
         for sled_id in blueprint.sleds() {
             let Some(zones) = blueprint.blueprint_zones.get(&sled_id) else {
                 continue;
@@ -119,9 +115,8 @@ impl ExampleSystem {
                         .expect("failed to add Omicron zone NIC");
                 }
             }
-            builder
-                .found_sled_omicron_zones(
-                    "fake sled agent",
+            system
+                .sled_set_omicron_zones(
                     sled_id,
                     zones.to_omicron_zones_config(
                         BlueprintZoneFilter::ShouldBeRunning,
@@ -130,6 +125,10 @@ impl ExampleSystem {
                 .unwrap();
         }
 
+        let mut builder =
+            system.to_collection_builder().expect("failed to build collection");
+        builder.set_rng_seed((test_name, "ExampleSystem collection"));
+
         ExampleSystem {
             system,
             input: input_builder.build(),
@@ -118,5 +118,6 @@
 pub mod blueprint_builder;
 pub mod example;
 mod ip_allocator;
+mod mgs_updates;
 pub mod planner;
 pub mod system;
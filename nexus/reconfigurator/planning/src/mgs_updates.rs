@@ -0,0 +1,690 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Planning updates to software delivered through MGS: SP firmware, RoT
+//! firmware, and host phase 1 images
+
+use nexus_types::deployment::BlueprintZonesConfig;
+use nexus_types::deployment::PendingMgsUpdate;
+use nexus_types::deployment::PendingMgsUpdateDetails;
+use nexus_types::deployment::PendingMgsUpdates;
+use nexus_types::deployment::PlanningInput;
+use nexus_types::deployment::SledFilter;
+use nexus_types::deployment::TargetRelease;
+use nexus_types::deployment::TargetReleaseArtifact;
+use nexus_types::inventory::BaseboardId;
+use nexus_types::inventory::CabooseWhich;
+use nexus_types::inventory::Collection;
+use nexus_types::inventory::RotSlot;
+use nexus_types::inventory::SpType;
+use omicron_uuid_kinds::SledUuid;
+use slog::debug;
+use slog::info;
+use slog::warn;
+use slog::Logger;
+use std::collections::BTreeMap;
+
+/// Determines which MGS-managed updates should be pending in the next
+/// blueprint, given the ones pending in the current one
+///
+/// We only ever have one update in flight at a time, across the whole system.
+/// An update stays pending as long as its precondition holds (i.e., inventory
+/// still shows the component in the state it was in when we planned the
+/// update) and it still describes the target release.  Once it's been carried
+/// out, its precondition no longer holds, so it's dropped, and we look for the
+/// next thing to update.
+///
+/// A new update is only started while the rest of the system is settled:
+/// every commissioned sled is in service, every sled agent is running the
+/// zones that `blueprint_zones` (the parent blueprint's) asks for, and
+/// `zones_changing` (whether this round of planning changed any zones) is
+/// false.  Host phase 1 updates in particular power-cycle a sled, so we don't
+/// want to start one while anything else is going on.
+pub(crate) fn plan_mgs_updates(
+    log: &Logger,
+    input: &PlanningInput,
+    inventory: &Collection,
+    blueprint_zones: &BTreeMap<SledUuid, BlueprintZonesConfig>,
+    zones_changing: bool,
+    current: &PendingMgsUpdates,
+) -> PendingMgsUpdates {
+    let Some(target_release) = input.target_release() else {
+        if !current.is_empty() {
+            info!(log, "dropping pending MGS updates: no target release");
+        }
+        return PendingMgsUpdates::new();
+    };
+
+    let mut next = PendingMgsUpdates::new();
+    for update in current.iter() {
+        let last_host_phase_1 =
+            input.host_phase_1_artifact(&update.baseboard_id).copied();
+        if !update.precondition_holds(inventory, last_host_phase_1) {
+            info!(
+                log,
+                "dropping pending MGS update: precondition no longer holds \
+                 (it's probably done)";
+                "update" => %update,
+            );
+        } else if !target_release
+            .artifacts
+            .iter()
+            .any(|a| a.hash == update.artifact_hash)
+        {
+            info!(
+                log,
+                "dropping pending MGS update: artifact is not part of the \
+                 target release";
+                "update" => %update,
+                "system_version" => %target_release.system_version,
+            );
+        } else {
+            next.insert(update.clone());
+        }
+    }
+
+    if !next.is_empty() {
+        debug!(log, "MGS update still pending; not starting another one");
+        return next;
+    }
+
+    if let Err(reason) = check_inventory_complete(input, inventory) {
+        warn!(
+            log,
+            "not planning MGS updates: inventory is incomplete";
+            "reason" => reason,
+        );
+        return next;
+    }
+
+    if zones_changing {
+        info!(log, "not planning MGS updates: zones are being changed");
+        return next;
+    }
+
+    if let Err(reason) = check_system_settled(input, inventory, blueprint_zones)
+    {
+        info!(
+            log,
+            "not planning MGS updates: system is not settled";
+            "reason" => reason,
+        );
+        return next;
+    }
+
+    for (baseboard_id, sp) in &inventory.sps {
+        if let Some(update) = next_update_for_baseboard(
+            log,
+            input,
+            inventory,
+            target_release,
+            baseboard_id,
+            sp.sp_type,
+            sp.sp_slot,
+        ) {
+            info!(log, "planning MGS update"; "update" => %update);
+            next.insert(update);
+            break;
+        }
+    }
+
+    next
+}
+
+/// Checks that inventory has everything we need to decide what to update
+///
+/// We don't want to start updating anything while some part of the system
+/// isn't accounted for.  An SP or sled agent that's missing from inventory
+/// might be in the middle of an update that we didn't see finish.
+fn check_inventory_complete(
+    input: &PlanningInput,
+    inventory: &Collection,
+) -> Result<(), String> {
+    for sled_id in input.all_sled_ids(SledFilter::InService) {
+        if !inventory.sled_agents.contains_key(&sled_id) {
+            return Err(format!("sled agent for sled {sled_id} not found"));
+        }
+    }
+
+    for baseboard_id in inventory.sps.keys() {
+        let Some(rot) = inventory.rots.get(baseboard_id) else {
+            return Err(format!("RoT state for {baseboard_id:?} not found"));
+        };
+        let active_rot = rot_caboose_which(&rot.active_slot);
+        for which in [CabooseWhich::SpSlot0, active_rot] {
+            if inventory.caboose_for(which, baseboard_id).is_none() {
+                return Err(format!(
+                    "caboose {which:?} for {baseboard_id:?} not found"
+                ));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that no other change to the system is underway
+///
+/// Only in-service sleds are checked.  A sled that has been expunged but not
+/// yet decommissioned has nothing left to converge to, so it shouldn't hold up
+/// updates to the rest of the system.
+fn check_system_settled(
+    input: &PlanningInput,
+    inventory: &Collection,
+    blueprint_zones: &BTreeMap<SledUuid, BlueprintZonesConfig>,
+) -> Result<(), String> {
+    for sled_id in input.all_sled_ids(SledFilter::InService) {
+        let Some(blueprint_zones) = blueprint_zones.get(&sled_id) else {
+            return Err(format!("sled {sled_id} has no zones in blueprint"));
+        };
+        let Some(found) = inventory.omicron_zones.get(&sled_id) else {
+            return Err(format!("zones for sled {sled_id} not found"));
+        };
+        if found.zones.generation != blueprint_zones.generation {
+            return Err(format!(
+                "sled {sled_id} is running zones generation {}, but the \
+                 blueprint has generation {}",
+                found.zones.generation, blueprint_zones.generation,
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+/// Returns the first update that this baseboard needs, if any
+///
+/// Components are updated in the order SP, RoT, host phase 1.
+fn next_update_for_baseboard(
+    log: &Logger,
+    input: &PlanningInput,
+    inventory: &Collection,
+    target_release: &TargetRelease,
+    baseboard_id: &BaseboardId,
+    sp_type: SpType,
+    sp_slot: u16,
+) -> Option<PendingMgsUpdate> {
+    let caboose_version = |which| {
+        inventory
+            .caboose_for(which, baseboard_id)
+            .map(|found| found.caboose.version.clone())
+    };
+    let make_update =
+        |details, artifact: &TargetReleaseArtifact| PendingMgsUpdate {
+            baseboard_id: baseboard_id.clone(),
+            sp_type,
+            slot_id: sp_slot,
+            details,
+            artifact_hash: artifact.hash,
+            artifact_version: artifact.version.clone(),
+        };
+
+    // SP
+    let sp_caboose =
+        inventory.caboose_for(CabooseWhich::SpSlot0, baseboard_id)?;
+    match target_release.sp_artifact(sp_type, &sp_caboose.caboose.board) {
+        Some(artifact) => {
+            if sp_caboose.caboose.version != artifact.version.to_string() {
+                return Some(make_update(
+                    PendingMgsUpdateDetails::Sp {
+                        expected_active_version: sp_caboose
+                            .caboose
+                            .version
+                            .clone(),
+                        expected_inactive_version: caboose_version(
+                            CabooseWhich::SpSlot1,
+                        ),
+                    },
+                    artifact,
+                ));
+            }
+        }
+        None => {
+            // We can't update anything else on this baseboard until we know
+            // its SP is running the target release.
+            warn!(
+                log,
+                "no SP artifact in target release for baseboard";
+                "baseboard_id" => ?baseboard_id,
+                "board" => &sp_caboose.caboose.board,
+            );
+            return None;
+        }
+    }
+
+    // RoT
+    let rot = inventory.rots.get(baseboard_id)?;
+    let active_slot = rot.active_slot.clone();
+    let inactive_slot = match active_slot {
+        RotSlot::A => RotSlot::B,
+        RotSlot::B => RotSlot::A,
+    };
+    let active_version = caboose_version(rot_caboose_which(&active_slot))?;
+    if let Some(artifact) = target_release.rot_artifact(sp_type, &inactive_slot)
+    {
+        if active_version != artifact.version.to_string() {
+            return Some(make_update(
+                PendingMgsUpdateDetails::Rot {
+                    expected_active_slot: active_slot,
+                    expected_active_version: active_version,
+                    expected_inactive_version: caboose_version(
+                        rot_caboose_which(&inactive_slot),
+                    ),
+                },
+                artifact,
+            ));
+        }
+    }
+
+    // Host phase 1 (only sleds have hosts)
+    if sp_type == SpType::Sled {
+        if let Some(artifact) = target_release.host_phase_1_artifact() {
+            let last_delivered =
+                input.host_phase_1_artifact(baseboard_id).copied();
+            if last_delivered != Some(artifact.hash) {
+                return Some(make_update(
+                    PendingMgsUpdateDetails::HostPhase1 {
+                        expected_artifact: last_delivered,
+                    },
+                    artifact,
+                ));
+            }
+        }
+    }
+
+    None
+}
+
+fn rot_caboose_which(slot: &RotSlot) -> CabooseWhich {
+    match slot {
+        RotSlot::A => CabooseWhich::RotSlotA,
+        RotSlot::B => CabooseWhich::RotSlotB,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::plan_mgs_updates;
+    use gateway_client::types::PowerState;
+    use gateway_client::types::RotState;
+    use gateway_client::types::SpComponentCaboose;
+    use gateway_client::types::SpState;
+    use nexus_inventory::CollectionBuilder;
+    use nexus_types::deployment::PendingMgsUpdateDetails;
+    use nexus_types::deployment::PendingMgsUpdates;
+    use nexus_types::deployment::PlanningInput;
+    use nexus_types::deployment::PlanningInputBuilder;
+    use nexus_types::deployment::SledDetails;
+    use nexus_types::deployment::SledResources;
+    use nexus_types::deployment::TargetRelease;
+    use nexus_types::deployment::TargetReleaseArtifact;
+    use nexus_types::deployment::TargetReleaseArtifactKind;
+    use nexus_types::external_api::views::SledPolicy;
+    use nexus_types::external_api::views::SledState;
+    use nexus_types::inventory::BaseboardId;
+    use nexus_types::inventory::CabooseWhich;
+    use nexus_types::inventory::Collection;
+    use nexus_types::inventory::RotSlot;
+    use nexus_types::inventory::SpType;
+    use omicron_common::address::Ipv6Subnet;
+    use omicron_common::api::external::SemverVersion;
+    use omicron_common::update::ArtifactHash;
+    use omicron_test_utils::dev::test_setup_log;
+    use omicron_uuid_kinds::SledUuid;
+    use std::collections::BTreeMap;
+    use std::net::Ipv6Addr;
+
+    const BOARD: &str = "gimlet-d";
+
+    fn hash(n: u8) -> ArtifactHash {
+        ArtifactHash([n; 32])
+    }
+
+    fn target_release(version: &str) -> TargetRelease {
+        let version: SemverVersion = version.parse().unwrap();
+        let artifact = |kind, n| TargetReleaseArtifact {
+            kind,
+            sp_type: SpType::Sled,
+            version: version.clone(),
+            hash: hash(n),
+        };
+        TargetRelease {
+            system_version: version.clone(),
+            artifacts: vec![
+                artifact(
+                    TargetReleaseArtifactKind::Sp { board: BOARD.to_string() },
+                    1,
+                ),
+                artifact(
+                    TargetReleaseArtifactKind::Rot { slot: RotSlot::A },
+                    2,
+                ),
+                artifact(
+                    TargetReleaseArtifactKind::Rot { slot: RotSlot::B },
+                    3,
+                ),
+                artifact(TargetReleaseArtifactKind::HostPhase1, 4),
+            ],
+        }
+    }
+
+    fn planning_input(
+        target_release: Option<TargetRelease>,
+        host_phase_1: Option<(&BaseboardId, ArtifactHash)>,
+    ) -> PlanningInput {
+        let mut builder = PlanningInputBuilder::empty_input().into_builder();
+        builder.policy_mut().target_release = target_release;
+        if let Some((baseboard_id, hash)) = host_phase_1 {
+            builder.set_host_phase_1_artifact(baseboard_id.clone(), hash);
+        }
+        builder.build()
+    }
+
+    /// Returns a collection with one sled whose SP and RoT are running the
+    /// given versions
+    fn collection(
+        sp_version: &str,
+        rot_active: RotSlot,
+        rot_version: &str,
+    ) -> (Collection, BaseboardId) {
+        let mut builder = CollectionBuilder::new("test");
+        let baseboard_id = builder
+            .found_sp_state(
+                "fake MGS",
+                SpType::Sled,
+                3,
+                SpState {
+                    base_mac_address: [0; 6],
+                    hubris_archive_id: String::from("hubris1"),
+                    model: String::from("model1"),
+                    power_state: PowerState::A0,
+                    revision: 0,
+                    rot: RotState::Enabled {
+                        active: rot_active.clone(),
+                        pending_persistent_boot_preference: None,
+                        persistent_boot_preference: rot_active.clone(),
+                        slot_a_sha3_256_digest: None,
+                        slot_b_sha3_256_digest: None,
+                        transient_boot_preference: None,
+                    },
+                    serial_number: String::from("s1"),
+                },
+            )
+            .unwrap();
+        let caboose = |version: &str| SpComponentCaboose {
+            board: BOARD.to_string(),
+            git_commit: String::from("deadbeef"),
+            name: String::from("test"),
+            version: version.to_string(),
+        };
+        let rot_which = match rot_active {
+            RotSlot::A => CabooseWhich::RotSlotA,
+            RotSlot::B => CabooseWhich::RotSlotB,
+        };
+        builder
+            .found_caboose(
+                &baseboard_id,
+                CabooseWhich::SpSlot0,
+                "test",
+                caboose(sp_version),
+            )
+            .unwrap();
+        builder
+            .found_caboose(
+                &baseboard_id,
+                rot_which,
+                "test",
+                caboose(rot_version),
+            )
+            .unwrap();
+        (builder.build(), (*baseboard_id).clone())
+    }
+
+    #[test]
+    fn test_mgs_update_sequence() {
+        let logctx = test_setup_log("test_mgs_update_sequence");
+        let log = &logctx.log;
+        let release = target_release("2.0.0");
+
+        // Without a target release, nothing gets updated.
+        let (inventory, baseboard_id) =
+            collection("1.0.0", RotSlot::A, "1.0.0");
+        let input = planning_input(None, None);
+        let updates = plan_mgs_updates(
+            log,
+            &input,
+            &inventory,
+            &BTreeMap::new(),
+            false,
+            &PendingMgsUpdates::new(),
+        );
+        assert!(updates.is_empty());
+
+        // With one, the SP is updated first.
+        let input = planning_input(Some(release.clone()), None);
+        let updates = plan_mgs_updates(
+            log,
+            &input,
+            &inventory,
+            &BTreeMap::new(),
+            false,
+            &PendingMgsUpdates::new(),
+        );
+        assert_eq!(updates.len(), 1);
+        let update = updates.get(&baseboard_id).unwrap();
+        assert_eq!(update.artifact_hash, hash(1));
+        assert_eq!(
+            update.details,
+            PendingMgsUpdateDetails::Sp {
+                expected_active_version: String::from("1.0.0"),
+                expected_inactive_version: None,
+            }
+        );
+
+        // While inventory hasn't changed, the update stays pending.
+        let next = plan_mgs_updates(
+            log,
+            &input,
+            &inventory,
+            &BTreeMap::new(),
+            false,
+            &updates,
+        );
+        assert_eq!(next, updates);
+
+        // Once the SP is updated, the RoT is next.  Its inactive slot gets the
+        // image built for that slot.
+        let (inventory, _) = collection("2.0.0", RotSlot::A, "1.0.0");
+        let updates = plan_mgs_updates(
+            log,
+            &input,
+            &inventory,
+            &BTreeMap::new(),
+            false,
+            &updates,
+        );
+        assert_eq!(updates.len(), 1);
+        let update = updates.get(&baseboard_id).unwrap();
+        assert_eq!(update.artifact_hash, hash(3));
+        assert_eq!(
+            update.details,
+            PendingMgsUpdateDetails::Rot {
+                expected_active_slot: RotSlot::A,
+                expected_active_version: String::from("1.0.0"),
+                expected_inactive_version: None,
+            }
+        );
+
+        // Then host phase 1.
+        let (inventory, _) = collection("2.0.0", RotSlot::B, "2.0.0");
+        let updates = plan_mgs_updates(
+            log,
+            &input,
+            &inventory,
+            &BTreeMap::new(),
+            false,
+            &updates,
+        );
+        assert_eq!(updates.len(), 1);
+        let update = updates.get(&baseboard_id).unwrap();
+        assert_eq!(update.artifact_hash, hash(4));
+        assert_eq!(
+            update.details,
+            PendingMgsUpdateDetails::HostPhase1 { expected_artifact: None }
+        );
+
+        // Once that's been delivered, there's nothing left to do.
+        let input = planning_input(
+            Some(release.clone()),
+            Some((&baseboard_id, hash(4))),
+        );
+        let updates = plan_mgs_updates(
+            log,
+            &input,
+            &inventory,
+            &BTreeMap::new(),
+            false,
+            &updates,
+        );
+        assert!(updates.is_empty());
+
+        logctx.cleanup_successful();
+    }
+
+    #[test]
+    fn test_mgs_update_dropped_without_target_release() {
+        let logctx =
+            test_setup_log("test_mgs_update_dropped_without_target_release");
+        let log = &logctx.log;
+
+        let (inventory, _) = collection("1.0.0", RotSlot::A, "1.0.0");
+        let input = planning_input(Some(target_release("2.0.0")), None);
+        let updates = plan_mgs_updates(
+            log,
+            &input,
+            &inventory,
+            &BTreeMap::new(),
+            false,
+            &PendingMgsUpdates::new(),
+        );
+        assert_eq!(updates.len(), 1);
+
+        // If the target release goes away, so does the pending update.
+        let input = planning_input(None, None);
+        let next = plan_mgs_updates(
+            log,
+            &input,
+            &inventory,
+            &BTreeMap::new(),
+            false,
+            &updates,
+        );
+        assert!(next.is_empty());
+
+        // Likewise if the target release changes to one that doesn't include
+        // the pending update's artifact.  In that case, a new update is
+        // planned for the new release.
+        let mut release = target_release("3.0.0");
+        for artifact in &mut release.artifacts {
+            artifact.hash = ArtifactHash([artifact.hash.0[0] + 10; 32]);
+        }
+        let input = planning_input(Some(release), None);
+        let next = plan_mgs_updates(
+            log,
+            &input,
+            &inventory,
+            &BTreeMap::new(),
+            false,
+            &updates,
+        );
+        assert_eq!(next.len(), 1);
+        assert_eq!(next.iter().next().unwrap().artifact_hash, hash(11));
+
+        logctx.cleanup_successful();
+    }
+
+    #[test]
+    fn test_mgs_update_waits_for_settled_system() {
+        let logctx = test_setup_log("test_mgs_update_waits_for_settled_system");
+        let log = &logctx.log;
+        let no_zones = BTreeMap::new();
+
+        let (inventory, _) = collection("1.0.0", RotSlot::A, "1.0.0");
+        let input = planning_input(Some(target_release("2.0.0")), None);
+
+        // Nothing new is started while this round of planning is changing
+        // zones.
+        let updates = plan_mgs_updates(
+            log,
+            &input,
+            &inventory,
+            &no_zones,
+            true,
+            &PendingMgsUpdates::new(),
+        );
+        assert!(updates.is_empty());
+
+        // An update that's already pending is kept, though.
+        let updates = plan_mgs_updates(
+            log,
+            &input,
+            &inventory,
+            &no_zones,
+            false,
+            &PendingMgsUpdates::new(),
+        );
+        assert_eq!(updates.len(), 1);
+        let next = plan_mgs_updates(
+            log, &input, &inventory, &no_zones, true, &updates,
+        );
+        assert_eq!(next, updates);
+
+        // A sled that has been expunged, but not yet decommissioned, doesn't
+        // hold anything up.
+        let sled_details = |policy| SledDetails {
+            policy,
+            state: SledState::Active,
+            resources: SledResources {
+                zpools: BTreeMap::new(),
+                subnet: Ipv6Subnet::new(Ipv6Addr::LOCALHOST),
+            },
+        };
+        let mut builder = input.into_builder();
+        builder
+            .add_sled(SledUuid::new_v4(), sled_details(SledPolicy::Expunged))
+            .unwrap();
+        let input = builder.build();
+        let updates = plan_mgs_updates(
+            log,
+            &input,
+            &inventory,
+            &no_zones,
+            false,
+            &PendingMgsUpdates::new(),
+        );
+        assert_eq!(updates.len(), 1);
+
+        // But nothing new is started while an in-service sled has yet to
+        // converge to the blueprint (here, because it has no zones in it).
+        let mut builder = input.into_builder();
+        builder
+            .add_sled(
+                SledUuid::new_v4(),
+                sled_details(SledPolicy::provisionable()),
+            )
+            .unwrap();
+        let input = builder.build();
+        let updates = plan_mgs_updates(
+            log,
+            &input,
+            &inventory,
+            &no_zones,
+            false,
+            &PendingMgsUpdates::new(),
+        );
+        assert!(updates.is_empty());
+
+        logctx.cleanup_successful();
+    }
+}
@@ -28,6 +28,7 @@ use std::hash::Hash;
 pub struct Planner<'a> {
     log: Logger,
     input: &'a PlanningInput,
+    parent_blueprint: &'a Blueprint,
     blueprint: BlueprintBuilder<'a>,
     // latest inventory collection
     //
@@ -57,7 +58,7 @@ impl<'a> Planner<'a> {
             input,
             creator,
         )?;
-        Ok(Planner { log, input, blueprint, inventory })
+        Ok(Planner { log, input, parent_blueprint, blueprint, inventory })
     }
 
     /// Within tests, set a seeded RNG for deterministic results.
@@ -83,10 +84,37 @@ impl<'a> Planner<'a> {
         self.do_plan_expunge()?;
         self.do_plan_add()?;
         self.do_plan_decommission()?;
+        self.do_plan_mgs_updates();
 
         Ok(())
     }
 
+    fn do_plan_mgs_updates(&mut self) {
+        let current = self.blueprint.pending_mgs_updates();
+        let next = crate::mgs_updates::plan_mgs_updates(
+            &self.log,
+            self.input,
+            self.inventory,
+            &self.parent_blueprint.blueprint_zones,
+            self.blueprint.has_zone_changes(),
+            current,
+        );
+        if next != *current {
+            self.blueprint.comment(format!(
+                "pending MGS updates: {}",
+                if next.is_empty() {
+                    String::from("none")
+                } else {
+                    next.iter()
+                        .map(|u| u.to_string())
+                        .collect::<Vec<_>>()
+                        .join(", ")
+                }
+            ));
+            self.blueprint.set_pending_mgs_updates(next);
+        }
+    }
+
     fn do_plan_decommission(&mut self) -> Result<(), Error> {
         // Check for any sleds that are currently commissioned but can be
         // decommissioned. Our gates for decommissioning are:
@@ -10,10 +10,12 @@ use gateway_client::types::RotState;
 use gateway_client::types::SpState;
 use indexmap::IndexMap;
 use nexus_inventory::CollectionBuilder;
+use nexus_types::deployment::BlueprintPhysicalDisksConfig;
 use nexus_types::deployment::PlanningInputBuilder;
 use nexus_types::deployment::Policy;
 use nexus_types::deployment::SledDetails;
 use nexus_types::deployment::SledDisk;
+use nexus_types::deployment::SledFilter;
 use nexus_types::deployment::SledResources;
 use nexus_types::external_api::views::PhysicalDiskPolicy;
 use nexus_types::external_api::views::PhysicalDiskState;
@@ -21,6 +23,7 @@ use nexus_types::external_api::views::SledPolicy;
 use nexus_types::external_api::views::SledProvisionPolicy;
 use nexus_types::external_api::views::SledState;
 use nexus_types::inventory::BaseboardId;
+use nexus_types::inventory::OmicronZonesConfig;
 use nexus_types::inventory::PowerState;
 use nexus_types::inventory::RotSlot;
 use nexus_types::inventory::SledRole;
@@ -260,6 +263,83 @@ impl SystemDescription {
         Ok(self)
     }
 
+    fn sled_mut(&mut self, sled_id: SledUuid) -> anyhow::Result<&mut Sled> {
+        self.sleds
+            .get_mut(&sled_id)
+            .ok_or_else(|| anyhow!("no sled with id {}", sled_id))
+    }
+
+    /// Set the policy of an existing sled (e.g., to expunge it)
+    pub fn sled_set_policy(
+        &mut self,
+        sled_id: SledUuid,
+        policy: SledPolicy,
+    ) -> anyhow::Result<&mut Self> {
+        self.sled_mut(sled_id)?.policy = policy;
+        Ok(self)
+    }
+
+    /// Set the state of an existing sled (e.g., to decommission it)
+    pub fn sled_set_state(
+        &mut self,
+        sled_id: SledUuid,
+        state: SledState,
+    ) -> anyhow::Result<&mut Self> {
+        self.sled_mut(sled_id)?.state = state;
+        Ok(self)
+    }
+
+    /// Returns the Omicron zones that a sled is currently running
+    pub fn sled_omicron_zones(
+        &self,
+        sled_id: SledUuid,
+    ) -> Option<&OmicronZonesConfig> {
+        self.sleds.get(&sled_id).map(|sled| &sled.omicron_zones)
+    }
+
+    /// Set the Omicron zones that a sled is running (and reports in
+    /// inventory)
+    ///
+    /// Like a real sled agent, this refuses a configuration older than the one
+    /// the sled already has.
+    pub fn sled_set_omicron_zones(
+        &mut self,
+        sled_id: SledUuid,
+        omicron_zones: OmicronZonesConfig,
+    ) -> anyhow::Result<&mut Self> {
+        let sled = self.sled_mut(sled_id)?;
+        ensure!(
+            omicron_zones.generation >= sled.omicron_zones.generation,
+            "sled {}: refusing Omicron zones generation {} (currently at \
+             generation {})",
+            sled_id,
+            omicron_zones.generation,
+            sled.omicron_zones.generation,
+        );
+        sled.omicron_zones = omicron_zones;
+        Ok(self)
+    }
+
+    /// Set the physical disks that a sled is managing
+    ///
+    /// The zpools on these disks then show up in the sled's inventory.
+    pub fn sled_set_omicron_physical_disks(
+        &mut self,
+        sled_id: SledUuid,
+        disks: &BlueprintPhysicalDisksConfig,
+    ) -> anyhow::Result<&mut Self> {
+        let sled = self.sled_mut(sled_id)?;
+        sled.inventory_sled_agent.zpools = disks
+            .disks
+            .iter()
+            .map(|disk| sled_agent_client::types::InventoryZpool {
+                id: disk.pool_id,
+                total_size: ByteCount::from_gibibytes_u32(100),
+            })
+            .collect();
+        Ok(self)
+    }
+
     pub fn to_collection_builder(&self) -> anyhow::Result<CollectionBuilder> {
         let collector_label = self
             .collector
@@ -280,12 +360,27 @@ impl SystemDescription {
                     .context("recording SP state")?;
             }
 
+            // Sled agents are not asked for inventory once their sled has
+            // been expunged.
+            if !SledFilter::QueryDuringInventory
+                .matches_policy_and_state(s.policy, s.state)
+            {
+                continue;
+            }
+
             builder
                 .found_sled_inventory(
                     "fake sled agent",
                     s.sled_agent_inventory().clone(),
                 )
                 .context("recording sled agent")?;
+            builder
+                .found_sled_omicron_zones(
+                    "fake sled agent",
+                    s.sled_id,
+                    s.omicron_zones.clone(),
+                )
+                .context("recording Omicron zones")?;
         }
 
         Ok(builder)
@@ -301,6 +396,7 @@ impl SystemDescription {
         let policy = Policy {
             service_ip_pool_ranges: self.service_ip_pool_ranges.clone(),
             target_nexus_zone_count: self.target_nexus_zone_count,
+            target_release: None,
         };
         let mut builder = PlanningInputBuilder::new(
             policy,
@@ -311,7 +407,7 @@ impl SystemDescription {
         for sled in self.sleds.values() {
             let sled_details = SledDetails {
                 policy: sled.policy,
-                state: SledState::Active,
+                state: sled.state,
                 resources: SledResources {
                     zpools: sled.zpools.clone(),
                     subnet: sled.sled_subnet,
@@ -427,6 +523,8 @@ struct Sled {
     inventory_sled_agent: sled_agent_client::types::Inventory,
     zpools: BTreeMap<ZpoolUuid, SledDisk>,
     policy: SledPolicy,
+    state: SledState,
+    omicron_zones: OmicronZonesConfig,
 }
 
 impl Sled {
@@ -543,6 +641,11 @@ impl Sled {
             policy: SledPolicy::InService {
                 provision_policy: SledProvisionPolicy::Provisionable,
             },
+            state: SledState::Active,
+            omicron_zones: OmicronZonesConfig {
+                generation: Generation::new(),
+                zones: vec![],
+            },
         }
     }
 
@@ -624,6 +727,11 @@ impl Sled {
             inventory_sp,
             inventory_sled_agent,
             policy: sled_policy,
+            state: SledState::Active,
+            omicron_zones: OmicronZonesConfig {
+                generation: Generation::new(),
+                zones: vec![],
+            },
         }
     }
 
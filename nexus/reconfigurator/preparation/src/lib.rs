@@ -25,6 +25,7 @@ use nexus_types::deployment::SledDetails;
 use nexus_types::deployment::SledDisk;
 use nexus_types::deployment::SledFilter;
 use nexus_types::deployment::SledResources;
+use nexus_types::deployment::TargetRelease;
 use nexus_types::deployment::UnstableReconfiguratorState;
 use nexus_types::identity::Asset;
 use nexus_types::identity::Resource;
@@ -57,7 +58,11 @@ pub struct PlanningInputFromDb<'a> {
     pub ip_pool_range_rows: &'a [nexus_db_model::IpPoolRange],
     pub external_ip_rows: &'a [nexus_db_model::ExternalIp],
     pub service_nic_rows: &'a [nexus_db_model::ServiceNetworkInterface],
+    pub host_phase_1_delivery_rows: &'a [nexus_db_model::HostPhase1Delivery],
     pub target_nexus_zone_count: usize,
+    pub target_release_row: &'a nexus_db_model::TargetRelease,
+    pub target_release_artifact_rows:
+        &'a [nexus_db_model::TargetReleaseArtifact],
     pub internal_dns_version: nexus_db_model::Generation,
     pub external_dns_version: nexus_db_model::Generation,
     pub log: &'a Logger,
@@ -67,9 +72,11 @@ impl PlanningInputFromDb<'_> {
     pub fn build(&self) -> Result<PlanningInput, Error> {
         let service_ip_pool_ranges =
             self.ip_pool_range_rows.iter().map(IpRange::from).collect();
+        let target_release = self.target_release()?;
         let policy = Policy {
             service_ip_pool_ranges,
             target_nexus_zone_count: self.target_nexus_zone_count,
+            target_release,
         };
         let mut builder = PlanningInputBuilder::new(
             policy,
@@ -169,8 +176,38 @@ impl PlanningInputFromDb<'_> {
             })?;
         }
 
+        for delivery in self.host_phase_1_delivery_rows {
+            builder.set_host_phase_1_artifact(
+                delivery.baseboard_id(),
+                *delivery.artifact_sha256,
+            );
+        }
+
         Ok(builder.build())
     }
+
+    fn target_release(&self) -> Result<Option<TargetRelease>, Error> {
+        let Some(system_version) = &self.target_release_row.system_version
+        else {
+            return Ok(None);
+        };
+        let artifacts = self
+            .target_release_artifact_rows
+            .iter()
+            .map(|row| {
+                row.clone().into_target_release_artifact().map_err(|e| {
+                    Error::internal_error(&format!(
+                        "invalid target release artifact read from \
+                         database: {e:#}"
+                    ))
+                })
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Some(TargetRelease {
+            system_version: system_version.0.clone(),
+            artifacts,
+        }))
+    }
 }
 
 /// Loads state for import into `reconfigurator-cli`
@@ -207,6 +244,18 @@ pub async fn reconfigurator_state_load(
         .service_network_interfaces_all_list_batched(opctx)
         .await
         .context("fetching service NICs")?;
+    let host_phase_1_delivery_rows = datastore
+        .host_phase_1_delivery_list_all_batched(opctx)
+        .await
+        .context("fetching host phase 1 deliveries")?;
+    let target_release_row = datastore
+        .target_release_get_current(opctx)
+        .await
+        .context("fetching target release")?;
+    let target_release_artifact_rows = datastore
+        .target_release_artifacts_list(opctx, target_release_row.generation)
+        .await
+        .context("fetching target release artifacts")?;
     let internal_dns_version = datastore
         .dns_group_latest_version(opctx, DnsGroup::Internal)
         .await
@@ -223,8 +272,11 @@ pub async fn reconfigurator_state_load(
         zpool_rows: &zpool_rows,
         ip_pool_range_rows: &ip_pool_range_rows,
         target_nexus_zone_count: NEXUS_REDUNDANCY,
+        target_release_row: &target_release_row,
+        target_release_artifact_rows: &target_release_artifact_rows,
         external_ip_rows: &external_ip_rows,
         service_nic_rows: &service_nic_rows,
+        host_phase_1_delivery_rows: &host_phase_1_delivery_rows,
         log: &opctx.log,
         internal_dns_version,
         external_dns_version,
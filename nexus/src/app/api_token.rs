@@ -0,0 +1,209 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Long-lived, user-managed API tokens
+//!
+//! Unlike the tokens granted by the device authorization flow (see
+//! [`device_auth`](super::device_auth)), API tokens are named, listable and
+//! revocable by their owner, may expire, and may carry scopes that further
+//! restrict what they can be used for.  Both kinds of token are presented the
+//! same way (`Authorization: Bearer oxide-token-...`).
+
+use crate::external_api::params;
+use crate::external_api::shared;
+use anyhow::anyhow;
+use chrono::Utc;
+use nexus_db_queries::authn::{Actor, Reason, Scope};
+use nexus_db_queries::authz;
+use nexus_db_queries::authz::ApiResource;
+use nexus_db_queries::context::OpContext;
+use nexus_db_queries::db::identity::Resource;
+use nexus_db_queries::db::lookup;
+use nexus_db_queries::db::lookup::LookupPath;
+use nexus_db_queries::db::model::ApiToken;
+use nexus_db_queries::db::model::Name;
+use omicron_common::api::external::http_pagination::PaginatedBy;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::NameOrId;
+use ref_cast::RefCast;
+use uuid::Uuid;
+
+impl super::Nexus {
+    pub fn api_token_lookup<'a>(
+        &'a self,
+        opctx: &'a OpContext,
+        api_token_selector: &'a params::ApiTokenSelector,
+    ) -> LookupResult<lookup::ApiToken<'a>> {
+        match api_token_selector {
+            params::ApiTokenSelector {
+                silo_user_id: _,
+                token: NameOrId::Id(id),
+            } => {
+                Ok(LookupPath::new(opctx, &self.db_datastore).api_token_id(*id))
+            }
+            params::ApiTokenSelector {
+                silo_user_id,
+                token: NameOrId::Name(name),
+            } => Ok(LookupPath::new(opctx, &self.db_datastore)
+                .silo_user_id(*silo_user_id)
+                .api_token_name(Name::ref_cast(name))),
+        }
+    }
+
+    pub(crate) async fn api_token_create(
+        &self,
+        opctx: &OpContext,
+        silo_user_id: Uuid,
+        params: params::ApiTokenCreate,
+    ) -> CreateResult<ApiToken> {
+        if let Some(time_expires) = params.time_expires {
+            if time_expires <= Utc::now() {
+                return Err(Error::invalid_value(
+                    "time_expires",
+                    "expiration time must be in the future",
+                ));
+            }
+        }
+
+        let mut projects =
+            params.scopes.iter().filter_map(|scope| match scope {
+                shared::ApiTokenScope::Project { project } => Some(project),
+                shared::ApiTokenScope::ReadOnly => None,
+            });
+        let project = projects.next().cloned();
+        if projects.next().is_some() {
+            return Err(Error::invalid_value(
+                "scopes",
+                "a token may be scoped to at most one project",
+            ));
+        }
+
+        // The caller must be able to see the project in order to scope a
+        // token to it.  We don't require more than that: the token can never
+        // do more than its owner anyway.
+        let project_id = match project {
+            None => None,
+            Some(project) => {
+                let (.., authz_project) = self
+                    .project_lookup(opctx, params::ProjectSelector { project })?
+                    .lookup_for(authz::Action::Read)
+                    .await?;
+                Some(authz_project.id())
+            }
+        };
+
+        let (.., authz_user) = LookupPath::new(opctx, &self.datastore())
+            .silo_user_id(silo_user_id)
+            .lookup_for(authz::Action::CreateChild)
+            .await?;
+        assert_eq!(authz_user.id(), silo_user_id);
+        let api_token = ApiToken::new(silo_user_id, params, project_id);
+        self.db_datastore.api_token_create(opctx, &authz_user, api_token).await
+    }
+
+    pub(crate) async fn api_tokens_list(
+        &self,
+        opctx: &OpContext,
+        silo_user_id: Uuid,
+        page_params: &PaginatedBy<'_>,
+    ) -> ListResultVec<ApiToken> {
+        let (.., authz_user) = LookupPath::new(opctx, &self.datastore())
+            .silo_user_id(silo_user_id)
+            .lookup_for(authz::Action::ListChildren)
+            .await?;
+        assert_eq!(authz_user.id(), silo_user_id);
+        self.db_datastore.api_tokens_list(opctx, &authz_user, page_params).await
+    }
+
+    pub(crate) async fn api_token_delete(
+        &self,
+        opctx: &OpContext,
+        silo_user_id: Uuid,
+        api_token_lookup: &lookup::ApiToken<'_>,
+    ) -> DeleteResult {
+        let (.., authz_silo_user, authz_api_token) =
+            api_token_lookup.lookup_for(authz::Action::Delete).await?;
+        if authz_silo_user.id() != silo_user_id {
+            return Err(authz_api_token.not_found());
+        }
+        self.db_datastore.api_token_delete(opctx, &authz_api_token).await
+    }
+
+    /// Look up the actor (and scope) for an API token presented as a bearer
+    /// token, recording that the token was used.
+    pub(crate) async fn api_token_actor(
+        &self,
+        opctx: &OpContext,
+        token: String,
+    ) -> Result<(Actor, Scope), Reason> {
+        let db_api_token = self
+            .db_datastore
+            .api_token_fetch_for_authn(opctx, &token)
+            .await
+            .map_err(|e| match e {
+                Error::ObjectNotFound { .. } => {
+                    Reason::UnknownActor { actor: "from API token".to_string() }
+                }
+                e => Reason::UnknownError { source: e },
+            })?;
+
+        let silo_user_id = db_api_token.silo_user_id;
+        let (.., db_silo_user) = LookupPath::new(opctx, &self.db_datastore)
+            .silo_user_id(silo_user_id)
+            .fetch()
+            .await
+            .map_err(|e| match e {
+                Error::ObjectNotFound { .. } => {
+                    Reason::UnknownActor { actor: silo_user_id.to_string() }
+                }
+                e => Reason::UnknownError { source: e },
+            })?;
+        let actor =
+            Actor::SiloUser { silo_user_id, silo_id: db_silo_user.silo_id };
+        if !db_silo_user.active {
+            return Err(Reason::BadCredentials {
+                actor,
+                source: anyhow!("user has been deactivated"),
+            });
+        }
+
+        let now = Utc::now();
+        if db_api_token.is_expired(now) {
+            return Err(Reason::BadCredentials {
+                actor,
+                source: anyhow!("API token has expired"),
+            });
+        }
+
+        // Record the last-used time, but only once in a while: this happens on
+        // every request made with the token.  Failing to record it is not a
+        // reason to fail the request.
+        if db_api_token.last_used_is_stale(now) {
+            if let Err(error) = self
+                .db_datastore
+                .api_token_touch(opctx, db_api_token.id(), now)
+                .await
+            {
+                warn!(
+                    opctx.log,
+                    "failed to update API token last-used time";
+                    "api_token_id" => %db_api_token.id(),
+                    "error" => #%error,
+                );
+            }
+        }
+
+        Ok((
+            actor,
+            Scope {
+                read_only: db_api_token.read_only,
+                project_id: db_api_token.project_id,
+            },
+        ))
+    }
+}
@@ -123,7 +123,7 @@ mod test {
     use nexus_types::deployment::{
         blueprint_zone_type, Blueprint, BlueprintPhysicalDisksConfig,
         BlueprintTarget, BlueprintZoneConfig, BlueprintZoneDisposition,
-        BlueprintZoneType, BlueprintZonesConfig,
+        BlueprintZoneType, BlueprintZonesConfig, PendingMgsUpdates,
     };
     use nexus_types::external_api::views::SledState;
     use nexus_types::inventory::OmicronZoneDataset;
@@ -164,6 +164,7 @@ mod test {
                 id,
                 blueprint_zones,
                 blueprint_disks,
+                pending_mgs_updates: PendingMgsUpdates::new(),
                 sled_state,
                 parent_blueprint_id: None,
                 internal_dns_version: dns_version,
@@ -188,7 +188,9 @@ mod test {
     use crate::app::background::common::BackgroundTask;
     use nexus_inventory::now_db_precision;
     use nexus_test_utils_macros::nexus_test;
-    use nexus_types::deployment::{Blueprint, BlueprintTarget};
+    use nexus_types::deployment::{
+        Blueprint, BlueprintTarget, PendingMgsUpdates,
+    };
     use omicron_common::api::external::Generation;
     use serde::Deserialize;
     use std::collections::BTreeMap;
@@ -211,6 +213,7 @@ mod test {
                 id,
                 blueprint_zones: BTreeMap::new(),
                 blueprint_disks: BTreeMap::new(),
+                pending_mgs_updates: PendingMgsUpdates::new(),
                 sled_state: BTreeMap::new(),
                 parent_blueprint_id: Some(parent_blueprint_id),
                 internal_dns_version: Generation::new(),
@@ -12,9 +12,11 @@ use super::dns_config;
 use super::dns_propagation;
 use super::dns_servers;
 use super::external_endpoints;
+use super::instance_serial_console;
 use super::instance_watcher;
 use super::inventory_collection;
 use super::metrics_producer_gc;
+use super::mgs_updates;
 use super::nat_cleanup;
 use super::phantom_disks;
 use super::physical_disk_adoption;
@@ -24,6 +26,7 @@ use super::sync_service_zone_nat::ServiceZoneNatTracker;
 use super::sync_switch_configuration::SwitchPortSettingsManager;
 use crate::app::oximeter::PRODUCER_LEASE_DURATION;
 use crate::app::sagas::SagaRequest;
+use crate::app::update::UploadedRepos;
 use nexus_config::BackgroundTaskConfig;
 use nexus_config::DnsTasksConfig;
 use nexus_db_model::DnsGroup;
@@ -84,6 +87,10 @@ pub struct BackgroundTasks {
     /// task handle for blueprint execution background task
     pub task_blueprint_executor: common::TaskHandle,
 
+    /// task handle for delivering the blueprint's SP, RoT, and host phase 1
+    /// updates
+    pub task_mgs_updates: common::TaskHandle,
+
     /// task handle for the service zone nat tracker
     pub task_service_zone_nat_tracker: common::TaskHandle,
 
@@ -97,6 +104,10 @@ pub struct BackgroundTasks {
     /// task handle for the task that polls sled agents for instance states.
     pub task_instance_watcher: common::TaskHandle,
 
+    /// task handle for the task that captures instances' serial console
+    /// output
+    pub task_instance_serial_console: common::TaskHandle,
+
     /// task handle for propagation of VPC firewall rules for Omicron services
     /// with external network connectivity,
     pub task_service_firewall_propagation: common::TaskHandle,
@@ -114,6 +125,7 @@ impl BackgroundTasks {
         resolver: internal_dns::resolver::Resolver,
         saga_request: Sender<SagaRequest>,
         producer_registry: &ProducerRegistry,
+        uploaded_repos: UploadedRepos,
     ) -> BackgroundTasks {
         let mut driver = common::Driver::new();
 
@@ -250,6 +262,28 @@ impl BackgroundTasks {
             config.blueprints.period_secs_execute,
             Box::new(blueprint_executor),
             opctx.child(BTreeMap::new()),
+            vec![Box::new(rx_blueprint.clone())],
+        );
+
+        // Background task: MGS-managed updates
+        //
+        // This is separate from blueprint execution because delivering these
+        // updates can take a long time, and it shouldn't hold up the rest of
+        // execution.
+        let task_mgs_updates = driver.register(
+            String::from("mgs_updates"),
+            String::from(
+                "delivers the target blueprint's SP, RoT, and host phase 1 \
+                updates",
+            ),
+            config.blueprints.period_secs_execute,
+            Box::new(mgs_updates::MgsUpdateDriver::new(
+                datastore.clone(),
+                resolver.clone(),
+                rx_blueprint.clone(),
+                uploaded_repos,
+            )),
+            opctx.child(BTreeMap::new()),
             vec![Box::new(rx_blueprint)],
         );
 
@@ -268,6 +302,8 @@ impl BackgroundTasks {
                 &nexus_id.to_string(),
                 config.inventory.nkeep,
                 config.inventory.disable,
+                producer_registry,
+                rack_id,
             );
             let inventory_watcher = collector.watcher();
             let task = driver.register(
@@ -368,6 +404,23 @@ impl BackgroundTasks {
                 vec![],
             )
         };
+
+        // Background task: instance serial console capture
+        let task_instance_serial_console = driver.register(
+            String::from("instance_serial_console"),
+            String::from(
+                "captures serial console output from running instances",
+            ),
+            config.instance_serial_console.period_secs,
+            Box::new(
+                instance_serial_console::InstanceSerialConsoleCapture::new(
+                    datastore.clone(),
+                ),
+            ),
+            opctx.child(BTreeMap::new()),
+            vec![],
+        );
+
         // Background task: service firewall rule propagation
         let task_service_firewall_propagation = driver.register(
             String::from("service_firewall_rule_propagation"),
@@ -399,10 +452,12 @@ impl BackgroundTasks {
             task_phantom_disks,
             task_blueprint_loader,
             task_blueprint_executor,
+            task_mgs_updates,
             task_service_zone_nat_tracker,
             task_switch_port_settings_manager,
             task_region_replacement,
             task_instance_watcher,
+            task_instance_serial_console,
             task_service_firewall_propagation,
         }
     }
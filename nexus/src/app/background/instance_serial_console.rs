@@ -0,0 +1,110 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Background task for capturing instances' serial console output
+//!
+//! Propolis only keeps a VMM's serial console output in memory, and only for
+//! as long as the VMM exists.  This task periodically copies new output from
+//! each running VMM into the database so that it's still available after the
+//! instance stops or migrates (e.g., to debug a guest that failed to boot).
+
+use super::common::BackgroundTask;
+use crate::app::instance::capture_instance_serial_console;
+use crate::app::instance::vmm_has_serial_console;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use futures::StreamExt;
+use nexus_db_queries::context::OpContext;
+use nexus_db_queries::db::pagination::Paginator;
+use nexus_db_queries::db::DataStore;
+use serde_json::json;
+use std::num::NonZeroU32;
+use std::sync::Arc;
+
+/// How many VMMs we fetch from the database at a time
+const VMM_BATCH_SIZE: NonZeroU32 = unsafe {
+    // Safety: 100 is not zero.
+    NonZeroU32::new_unchecked(100)
+};
+
+/// How many VMMs we capture output from concurrently
+const MAX_CONCURRENT_CAPTURES: usize = 16;
+
+pub struct InstanceSerialConsoleCapture {
+    datastore: Arc<DataStore>,
+}
+
+impl InstanceSerialConsoleCapture {
+    pub fn new(datastore: Arc<DataStore>) -> Self {
+        InstanceSerialConsoleCapture { datastore }
+    }
+}
+
+impl BackgroundTask for InstanceSerialConsoleCapture {
+    fn activate<'a>(
+        &'a mut self,
+        opctx: &'a OpContext,
+    ) -> BoxFuture<'a, serde_json::Value> {
+        async {
+            let log = &opctx.log;
+
+            let mut vmms = Vec::new();
+            let mut paginator = Paginator::new(VMM_BATCH_SIZE);
+            while let Some(p) = paginator.next() {
+                let batch = match self
+                    .datastore
+                    .vmm_list_active(opctx, &p.current_pagparams())
+                    .await
+                {
+                    Ok(batch) => batch,
+                    Err(e) => {
+                        warn!(log, "failed to list active VMMs"; "error" => %e);
+                        return json!({
+                            "error": format!("failed to list active VMMs: {e}")
+                        });
+                    }
+                };
+                paginator = p.found_batch(&batch, &|vmm| vmm.id);
+                vmms.extend(batch.into_iter().filter(vmm_has_serial_console));
+            }
+
+            let datastore = &self.datastore;
+            let results: Vec<_> = futures::stream::iter(&vmms)
+                .map(|vmm| async move {
+                    let result =
+                        capture_instance_serial_console(datastore, opctx, vmm)
+                            .await;
+                    if let Err(e) = &result {
+                        warn!(
+                            log,
+                            "failed to capture instance serial console output";
+                            "instance_id" => %vmm.instance_id,
+                            "vmm_id" => %vmm.id,
+                            "error" => %e,
+                        );
+                    }
+                    result
+                })
+                .buffer_unordered(MAX_CONCURRENT_CAPTURES)
+                .collect()
+                .await;
+
+            let mut bytes_captured = 0;
+            let mut errors = 0;
+            for result in results {
+                match result {
+                    Ok(nbytes) => bytes_captured += nbytes,
+                    Err(_) => errors += 1,
+                }
+            }
+
+            json!({
+                "vmms_checked": vmms.len(),
+                "bytes_captured": bytes_captured,
+                "errors": errors,
+            })
+        }
+        .boxed()
+    }
+}
@@ -12,13 +12,21 @@ use futures::FutureExt;
 use internal_dns::ServiceName;
 use nexus_db_queries::context::OpContext;
 use nexus_db_queries::db::DataStore;
+use nexus_inventory::DpdInventory;
 use nexus_inventory::InventoryError;
 use nexus_types::deployment::SledFilter;
 use nexus_types::inventory::Collection;
 use omicron_uuid_kinds::CollectionUuid;
+use oximeter::types::ProducerRegistry;
 use serde_json::json;
 use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
 use tokio::sync::watch;
+use uuid::Uuid;
+
+/// how long to wait to find the dpd instances to collect switch state from
+const DPD_LOOKUP_TIMEOUT: Duration = Duration::from_secs(30);
 
 /// Background task that reads inventory for the rack
 pub struct InventoryCollector {
@@ -28,6 +36,7 @@ pub struct InventoryCollector {
     nkeep: u32,
     disable: bool,
     tx: watch::Sender<Option<CollectionUuid>>,
+    metrics: Arc<Mutex<metrics::Metrics>>,
 }
 
 impl InventoryCollector {
@@ -37,8 +46,14 @@ impl InventoryCollector {
         creator: &str,
         nkeep: u32,
         disable: bool,
+        producer_registry: &ProducerRegistry,
+        rack_id: Uuid,
     ) -> InventoryCollector {
         let (tx, _) = watch::channel(None);
+        let metrics = Arc::new(Mutex::new(metrics::Metrics::new(rack_id)));
+        producer_registry
+            .register_producer(metrics::Producer(metrics.clone()))
+            .unwrap();
         InventoryCollector {
             datastore,
             resolver,
@@ -46,12 +61,44 @@ impl InventoryCollector {
             nkeep,
             disable,
             tx,
+            metrics,
         }
     }
 
     pub fn watcher(&self) -> watch::Receiver<Option<CollectionUuid>> {
         self.tx.subscribe()
     }
+
+    /// Queue the sensor readings in `collection` to be reported to oximeter,
+    /// if it's the latest collection
+    ///
+    /// Every Nexus collects inventory, but only one of them should report
+    /// each set of readings.  The Nexus that collected the latest collection
+    /// reports it; one whose collection was superseded before it finished
+    /// reports nothing.
+    async fn record_sensor_readings(
+        &self,
+        opctx: &OpContext,
+        collection: &Collection,
+    ) {
+        match self.datastore.inventory_get_latest_collection_id(opctx).await {
+            Ok(Some(latest_id)) if latest_id == collection.id => {
+                self.metrics.lock().unwrap().record_sensor_readings(collection);
+            }
+            Ok(latest_id) => {
+                debug!(opctx.log, "not reporting superseded sensor readings";
+                    "collection_id" => %collection.id,
+                    "latest_collection_id" => ?latest_id,
+                );
+            }
+            Err(error) => {
+                warn!(opctx.log, "failed to find latest inventory collection";
+                    "collection_id" => %collection.id,
+                    "error" => %error,
+                );
+            }
+        }
+    }
 }
 
 impl BackgroundTask for InventoryCollector {
@@ -87,6 +134,7 @@ impl BackgroundTask for InventoryCollector {
                         "time_started": collection.time_started.to_string(),
                         "time_done": collection.time_done.to_string()
                     });
+                    self.record_sensor_readings(opctx, &collection).await;
                     self.tx.send_replace(Some(collection.id));
                     json
                 }
@@ -132,6 +180,32 @@ async fn inventory_activate(
         })
         .collect::<Vec<_>>();
 
+    // Find dpd clients.  Mapping switch zones to switch slots retries until
+    // each zone's MGS answers, so bound how long we wait: failing to find dpd
+    // shouldn't stop us from collecting everything else.
+    let dpd_clients = match tokio::time::timeout(
+        DPD_LOOKUP_TIMEOUT,
+        crate::app::dpd_clients(resolver, &opctx.log),
+    )
+    .await
+    {
+        Ok(Ok(clients)) => clients
+            .into_iter()
+            .map(|(location, client)| {
+                (location, Arc::new(client) as Arc<dyn DpdInventory>)
+            })
+            .collect::<Vec<_>>(),
+        Ok(Err(error)) => {
+            warn!(opctx.log, "inventory: failed to find dpd clients";
+                "error" => error);
+            Vec::new()
+        }
+        Err(_) => {
+            warn!(opctx.log, "inventory: timed out finding dpd clients");
+            Vec::new()
+        }
+    };
+
     // Create an enumerator to find sled agents.
     let sled_enum = DbSledAgentEnumerator { opctx, datastore };
 
@@ -139,6 +213,7 @@ async fn inventory_activate(
     let inventory = nexus_inventory::Collector::new(
         creator,
         &mgs_clients,
+        &dpd_clients,
         &sled_enum,
         opctx.log.clone(),
     );
@@ -184,10 +259,220 @@ impl<'a> nexus_inventory::SledAgentEnumerator for DbSledAgentEnumerator<'a> {
     }
 }
 
+mod metrics {
+    use nexus_types::inventory::Collection;
+    use nexus_types::inventory::SensorKind;
+    use nexus_types::inventory::SensorReading;
+    use nexus_types::inventory::SpType;
+    use omicron_uuid_kinds::GenericUuid;
+    use oximeter::Metric;
+    use oximeter::MetricsError;
+    use oximeter::Sample;
+    use std::borrow::Cow;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+    use uuid::Uuid;
+
+    /// SP sensor readings from the latest inventory collection that have not
+    /// yet been produced
+    ///
+    /// Each reading's target is tagged with the id of the collection it came
+    /// from.
+    #[derive(Debug)]
+    pub(super) struct Metrics {
+        rack_id: Uuid,
+        readings: Vec<(HardwareComponent, SensorReading)>,
+    }
+
+    #[derive(Debug)]
+    pub(super) struct Producer(pub(super) Arc<Mutex<Metrics>>);
+
+    impl Metrics {
+        pub(super) fn new(rack_id: Uuid) -> Metrics {
+            Metrics { rack_id, readings: Vec::new() }
+        }
+
+        /// Replace any readings not yet produced with those in `collection`
+        ///
+        /// Readings are only produced once.  Each sample is timestamped with
+        /// the time the reading was collected, so a reading that oximeter
+        /// never picked up is superseded by the next collection's.  Readings
+        /// that the SP failed to take are not reported.
+        pub(super) fn record_sensor_readings(
+            &mut self,
+            collection: &Collection,
+        ) {
+            self.readings.clear();
+            for (baseboard_id, readings) in &collection.sensor_readings {
+                // The builder only accepts readings for baseboards whose SP
+                // it's seen, so this should always be found.
+                let Some(sp) = collection.sps.get(baseboard_id) else {
+                    continue;
+                };
+                for reading in readings.iter().filter(|r| r.value.is_ok()) {
+                    let target = HardwareComponent {
+                        rack_id: self.rack_id,
+                        collection_id: collection.id.into_untyped_uuid(),
+                        sp_type: match sp.sp_type {
+                            SpType::Sled => Cow::Borrowed("sled"),
+                            SpType::Switch => Cow::Borrowed("switch"),
+                            SpType::Power => Cow::Borrowed("power"),
+                        },
+                        sp_slot: sp.sp_slot,
+                        part_number: baseboard_id.part_number.clone(),
+                        serial_number: baseboard_id.serial_number.clone(),
+                        component: reading.component.clone(),
+                        device: reading.device.clone(),
+                    };
+                    self.readings.push((target, reading.clone()));
+                }
+            }
+        }
+    }
+
+    impl oximeter::Producer for Producer {
+        fn produce(
+            &mut self,
+        ) -> Result<Box<dyn Iterator<Item = Sample>>, MetricsError> {
+            let mut metrics = self.0.lock().unwrap();
+            let readings = std::mem::take(&mut metrics.readings);
+            let mut v = Vec::with_capacity(readings.len());
+            for (target, reading) in &readings {
+                let Ok(datum) = reading.value else {
+                    continue;
+                };
+                let sensor = reading.name.clone();
+                let timestamp = reading.time_collected;
+                let sample = match reading.kind {
+                    SensorKind::Temperature => Sample::new_with_timestamp(
+                        timestamp,
+                        target,
+                        &Temperature { sensor, datum },
+                    ),
+                    SensorKind::Power => Sample::new_with_timestamp(
+                        timestamp,
+                        target,
+                        &Power { sensor, datum },
+                    ),
+                    SensorKind::Current => Sample::new_with_timestamp(
+                        timestamp,
+                        target,
+                        &Current { sensor, datum },
+                    ),
+                    SensorKind::Voltage => Sample::new_with_timestamp(
+                        timestamp,
+                        target,
+                        &Voltage { sensor, datum },
+                    ),
+                    SensorKind::InputCurrent => Sample::new_with_timestamp(
+                        timestamp,
+                        target,
+                        &InputCurrent { sensor, datum },
+                    ),
+                    SensorKind::InputVoltage => Sample::new_with_timestamp(
+                        timestamp,
+                        target,
+                        &InputVoltage { sensor, datum },
+                    ),
+                    SensorKind::Speed => Sample::new_with_timestamp(
+                        timestamp,
+                        target,
+                        &FanSpeed { sensor, datum },
+                    ),
+                };
+                v.push(sample?);
+            }
+            Ok(Box::new(v.into_iter()))
+        }
+    }
+
+    /// A component of a sled, switch, or power shelf with sensors that are
+    /// read by its service processor
+    #[derive(
+        Clone, Debug, PartialEq, Eq, PartialOrd, Ord, oximeter::Target,
+    )]
+    struct HardwareComponent {
+        /// The ID of the rack containing the component.
+        rack_id: Uuid,
+        /// The ID of the inventory collection that took the reading.
+        collection_id: Uuid,
+        /// The type of the SP that reported the reading ("sled", "switch", or
+        /// "power").
+        sp_type: Cow<'static, str>,
+        /// The slot of the SP that reported the reading.
+        sp_slot: u16,
+        /// The part number of the baseboard containing the component.
+        part_number: String,
+        /// The serial number of the baseboard containing the component.
+        serial_number: String,
+        /// The SP's name for the component (e.g., "dev-0").
+        component: String,
+        /// The kind of device (e.g., "tmp117").
+        device: String,
+    }
+
+    /// A temperature reading, in degrees Celsius.
+    #[derive(Clone, Debug, Metric)]
+    struct Temperature {
+        /// The name of the sensor.
+        sensor: String,
+        datum: f32,
+    }
+
+    /// A power reading, in watts.
+    #[derive(Clone, Debug, Metric)]
+    struct Power {
+        /// The name of the sensor.
+        sensor: String,
+        datum: f32,
+    }
+
+    /// A current reading, in amperes.
+    #[derive(Clone, Debug, Metric)]
+    struct Current {
+        /// The name of the sensor.
+        sensor: String,
+        datum: f32,
+    }
+
+    /// A voltage reading, in volts.
+    #[derive(Clone, Debug, Metric)]
+    struct Voltage {
+        /// The name of the sensor.
+        sensor: String,
+        datum: f32,
+    }
+
+    /// An input current reading, in amperes.
+    #[derive(Clone, Debug, Metric)]
+    struct InputCurrent {
+        /// The name of the sensor.
+        sensor: String,
+        datum: f32,
+    }
+
+    /// An input voltage reading, in volts.
+    #[derive(Clone, Debug, Metric)]
+    struct InputVoltage {
+        /// The name of the sensor.
+        sensor: String,
+        datum: f32,
+    }
+
+    /// A fan speed reading, in revolutions per minute.
+    #[derive(Clone, Debug, Metric)]
+    struct FanSpeed {
+        /// The name of the sensor.
+        sensor: String,
+        datum: f32,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use crate::app::authz;
     use crate::app::background::common::BackgroundTask;
+    use crate::app::background::inventory_collection::metrics;
     use crate::app::background::inventory_collection::DbSledAgentEnumerator;
     use crate::app::background::inventory_collection::InventoryCollector;
     use nexus_db_model::Generation;
@@ -202,9 +487,15 @@ mod test {
     use omicron_common::api::external::ByteCount;
     use omicron_common::api::external::LookupType;
     use omicron_uuid_kinds::CollectionUuid;
+    use omicron_uuid_kinds::GenericUuid;
+    use oximeter::types::FieldValue;
+    use oximeter::types::ProducerRegistry;
+    use oximeter::Producer;
     use std::collections::BTreeSet;
     use std::net::Ipv6Addr;
     use std::net::SocketAddrV6;
+    use std::sync::Arc;
+    use std::sync::Mutex;
     use uuid::Uuid;
 
     type ControlPlaneTestContext =
@@ -237,6 +528,8 @@ mod test {
             "me",
             nkeep,
             false,
+            &ProducerRegistry::new(),
+            Uuid::new_v4(),
         );
         let nkeep = usize::try_from(nkeep).unwrap();
         let mut all_our_collection_ids = Vec::new();
@@ -302,6 +595,8 @@ mod test {
             "disabled",
             3,
             true,
+            &ProducerRegistry::new(),
+            Uuid::new_v4(),
         );
         let _ = task.activate(&opctx).await;
 
@@ -405,4 +700,42 @@ mod test {
         found_urls.sort();
         assert_eq!(remaining_urls, found_urls);
     }
+
+    // Test that sensor readings are produced as oximeter samples exactly once.
+    #[test]
+    fn test_sensor_metrics() {
+        let collection =
+            nexus_inventory::examples::representative().builder.build();
+        let metrics =
+            Arc::new(Mutex::new(metrics::Metrics::new(Uuid::new_v4())));
+        let mut producer = metrics::Producer(metrics.clone());
+
+        // The representative collection has three successful readings on one
+        // sled, plus a failed one on the switch that should not be reported.
+        metrics.lock().unwrap().record_sensor_readings(&collection);
+        let samples: Vec<_> = producer.produce().unwrap().collect();
+        assert_eq!(
+            samples
+                .iter()
+                .map(|s| s.timeseries_name.as_str())
+                .collect::<Vec<_>>(),
+            [
+                "hardware_component:temperature",
+                "hardware_component:fan_speed",
+                "hardware_component:fan_speed",
+            ]
+        );
+        for sample in &samples {
+            let timestamp = sample.measurement.timestamp();
+            assert!(collection.time_started <= timestamp);
+            assert!(timestamp <= collection.time_done);
+            assert_eq!(
+                sample.sorted_target_fields()["collection_id"].value,
+                FieldValue::Uuid(collection.id.into_untyped_uuid()),
+            );
+        }
+
+        // Nothing new has been collected, so nothing more should be produced.
+        assert_eq!(producer.produce().unwrap().count(), 0);
+    }
 }
@@ -0,0 +1,473 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Background task for delivering the SP, RoT, and host phase 1 updates
+//! requested by the target blueprint
+
+use super::common::BackgroundTask;
+use crate::app::update::HostPhase1Updater;
+use crate::app::update::MgsClients;
+use crate::app::update::RotUpdater;
+use crate::app::update::SpUpdater;
+use crate::app::update::UploadedRepos;
+use anyhow::anyhow;
+use anyhow::Context;
+use chrono::DateTime;
+use chrono::Utc;
+use futures::future::BoxFuture;
+use futures::FutureExt;
+use futures::TryStreamExt;
+use gateway_client::types::PowerState;
+use gateway_client::types::SpComponentFirmwareSlot;
+use gateway_client::SpComponent;
+use internal_dns::resolver::Resolver;
+use internal_dns::ServiceName;
+use nexus_db_model::HostPhase1Delivery;
+use nexus_db_model::MgsUpdateDelivery;
+use nexus_db_queries::context::OpContext;
+use nexus_db_queries::db::DataStore;
+use nexus_types::deployment::{
+    Blueprint, BlueprintTarget, PendingMgsUpdate, PendingMgsUpdateDetails,
+};
+use nexus_types::inventory::RotSlot;
+use nexus_types::inventory::SpType;
+use serde_json::json;
+use std::sync::Arc;
+use tokio::sync::watch;
+use uuid::Uuid;
+
+/// Background task that delivers the pending MGS-managed updates in the
+/// target blueprint
+///
+/// Each update is only delivered if the component is still in the state that
+/// the planner expected (according to the latest inventory collection).
+/// Otherwise, it's skipped and the planner is left to notice the change.
+///
+/// Until an inventory collection that started after a delivery comes along,
+/// inventory still shows the old state and so the update's precondition still
+/// appears to hold.  Each delivery is claimed in the database before anything
+/// is written so that no two Nexus instances write to the same component at
+/// once, and no Nexus delivers the same artifact again in the meantime.
+pub struct MgsUpdateDriver {
+    datastore: Arc<DataStore>,
+    resolver: Resolver,
+    rx_blueprint: watch::Receiver<Option<Arc<(BlueprintTarget, Blueprint)>>>,
+    uploaded_repos: UploadedRepos,
+}
+
+impl MgsUpdateDriver {
+    pub fn new(
+        datastore: Arc<DataStore>,
+        resolver: Resolver,
+        rx_blueprint: watch::Receiver<
+            Option<Arc<(BlueprintTarget, Blueprint)>>,
+        >,
+        uploaded_repos: UploadedRepos,
+    ) -> MgsUpdateDriver {
+        MgsUpdateDriver { datastore, resolver, rx_blueprint, uploaded_repos }
+    }
+
+    async fn activate_impl(&mut self, opctx: &OpContext) -> serde_json::Value {
+        let update = self.rx_blueprint.borrow_and_update().clone();
+        let Some(update) = update else {
+            return json!({ "error": "no blueprint" });
+        };
+
+        let (bp_target, blueprint) = &*update;
+        if !bp_target.enabled {
+            return json!({
+                "target_id": blueprint.id.to_string(),
+                "error": "blueprint disabled",
+            });
+        }
+
+        if blueprint.pending_mgs_updates.is_empty() {
+            return json!({
+                "target_id": blueprint.id.to_string(),
+                "updates": [],
+            });
+        }
+
+        let updates = match self.deliver_all(opctx, blueprint).await {
+            Ok(updates) => updates,
+            Err(error) => {
+                let error = format!("{error:#}");
+                warn!(
+                    &opctx.log,
+                    "failed to deliver MGS updates";
+                    "target_id" => %blueprint.id,
+                    "error" => &error,
+                );
+                return json!({
+                    "target_id": blueprint.id.to_string(),
+                    "error": error,
+                });
+            }
+        };
+
+        json!({
+            "target_id": blueprint.id.to_string(),
+            "updates": updates,
+        })
+    }
+
+    /// Attempts each of the blueprint's pending updates, returning a status
+    /// for each one
+    async fn deliver_all(
+        &self,
+        opctx: &OpContext,
+        blueprint: &Blueprint,
+    ) -> Result<Vec<serde_json::Value>, anyhow::Error> {
+        let log = &opctx.log;
+        let collection = self
+            .datastore
+            .inventory_get_latest_collection(opctx)
+            .await
+            .context("loading latest inventory collection")?
+            .ok_or_else(|| anyhow!("no inventory collection available"))?;
+        let host_phase_1_deliveries = self
+            .datastore
+            .host_phase_1_delivery_list_all_batched(opctx)
+            .await
+            .context("listing host phase 1 deliveries")?;
+
+        let mgs_clients = self
+            .resolver
+            .lookup_all_socket_v6(ServiceName::ManagementGatewayService)
+            .await
+            .context("looking up MGS addresses")?
+            .into_iter()
+            .map(|sockaddr| {
+                let url = format!("http://{}", sockaddr);
+                let log = log.new(o!("gateway_url" => url.clone()));
+                gateway_client::Client::new(&url, log)
+            })
+            .collect::<Vec<_>>();
+        if mgs_clients.is_empty() {
+            return Err(anyhow!("no MGS instances found"));
+        }
+        let mut mgs_clients = MgsClients::from_clients(mgs_clients);
+
+        let mut statuses = Vec::new();
+        for update in blueprint.pending_mgs_updates.iter() {
+            let last_host_phase_1 = host_phase_1_deliveries
+                .iter()
+                .find(|d| d.baseboard_id() == update.baseboard_id)
+                .map(|d| *d.artifact_sha256);
+            let status =
+                if !update.precondition_holds(&collection, last_host_phase_1) {
+                    info!(
+                        log,
+                        "skipping MGS update: precondition does not hold";
+                        "update" => %update,
+                        "collection_id" => %collection.id,
+                    );
+                    json!("skipped: precondition does not hold")
+                } else {
+                    match self
+                        .deliver(
+                            opctx,
+                            update,
+                            collection.time_started,
+                            &mut mgs_clients,
+                        )
+                        .await
+                    {
+                        Ok(true) => json!("delivered"),
+                        Ok(false) => json!(
+                        "skipped: delivery in progress or not yet reflected \
+                         in inventory"
+                    ),
+                        Err(error) => {
+                            let error = format!("{error:#}");
+                            warn!(
+                                log,
+                                "failed to deliver MGS update";
+                                "update" => %update,
+                                "error" => &error,
+                            );
+                            json!(format!("failed: {error}"))
+                        }
+                    }
+                };
+            statuses.push(json!({
+                "update": update.to_string(),
+                "status": status,
+            }));
+        }
+
+        Ok(statuses)
+    }
+
+    /// Delivers one update, assuming its precondition has been checked
+    /// against an inventory collection started at `inventory_started`
+    ///
+    /// Returns `false` without writing anything if the delivery can't be
+    /// claimed, either because some Nexus is already delivering to this
+    /// baseboard or because the same artifact was delivered after
+    /// `inventory_started`.
+    async fn deliver(
+        &self,
+        opctx: &OpContext,
+        update: &PendingMgsUpdate,
+        inventory_started: DateTime<Utc>,
+        mgs_clients: &mut MgsClients,
+    ) -> Result<bool, anyhow::Error> {
+        let log = &opctx.log;
+        let artifact = self
+            .uploaded_repos
+            .mgs_artifact(&update.artifact_hash)
+            .ok_or_else(|| {
+                anyhow!(
+                    "artifact {} is not available on this Nexus",
+                    update.artifact_hash
+                )
+            })?;
+        let data: Vec<u8> = artifact
+            .reader_stream()
+            .await
+            .context("opening artifact")?
+            .map_ok(|chunk| chunk.to_vec())
+            .try_concat()
+            .await
+            .context("reading artifact")?;
+
+        let delivery = MgsUpdateDelivery::new(
+            &update.baseboard_id,
+            update.artifact_hash.into(),
+        );
+        let delivery_id = delivery.id;
+        let claimed = self
+            .datastore
+            .mgs_update_delivery_claim(opctx, delivery, inventory_started)
+            .await
+            .context("claiming MGS update delivery")?;
+        if !claimed {
+            info!(
+                log,
+                "skipping MGS update: delivery already claimed";
+                "update" => %update,
+            );
+            return Ok(false);
+        }
+
+        if let Err(error) = self.write(opctx, update, data, mgs_clients).await {
+            // Let this or another Nexus try again.  If this fails, the claim
+            // expires on its own eventually.
+            if let Err(release_error) = self
+                .datastore
+                .mgs_update_delivery_release(opctx, delivery_id)
+                .await
+            {
+                warn!(
+                    log,
+                    "failed to release claim on failed MGS update delivery";
+                    "update" => %update,
+                    "delivery_id" => %delivery_id,
+                    "error" => %release_error,
+                );
+            }
+            return Err(error);
+        }
+
+        self.datastore
+            .mgs_update_delivery_finish(opctx, delivery_id)
+            .await
+            .context("recording MGS update delivery")?;
+        info!(log, "delivered MGS update"; "update" => %update);
+
+        Ok(true)
+    }
+
+    /// Writes an update's artifact to its component through MGS
+    async fn write(
+        &self,
+        opctx: &OpContext,
+        update: &PendingMgsUpdate,
+        data: Vec<u8>,
+        mgs_clients: &mut MgsClients,
+    ) -> Result<(), anyhow::Error> {
+        let log = &opctx.log;
+        let sp_type = update.sp_type;
+        let sp_slot = u32::from(update.slot_id);
+
+        info!(log, "delivering MGS update"; "update" => %update);
+        match &update.details {
+            PendingMgsUpdateDetails::Sp { .. } => {
+                SpUpdater::new(sp_type, sp_slot, Uuid::new_v4(), data, log)
+                    .update(mgs_clients)
+                    .await
+                    .map_err(anyhow::Error::new)?;
+            }
+            PendingMgsUpdateDetails::Rot { expected_active_slot, .. } => {
+                let target_slot = match expected_active_slot {
+                    RotSlot::A => RotSlot::B,
+                    RotSlot::B => RotSlot::A,
+                };
+                RotUpdater::new(
+                    sp_type,
+                    sp_slot,
+                    target_slot,
+                    Uuid::new_v4(),
+                    data,
+                    log,
+                )
+                .update(mgs_clients)
+                .await
+                .map_err(anyhow::Error::new)?;
+            }
+            PendingMgsUpdateDetails::HostPhase1 { .. } => {
+                self.deliver_host_phase_1(opctx, update, data, mgs_clients)
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Writes a host phase 1 image to the host's inactive boot flash slot
+    /// and boots the host from it
+    ///
+    /// The SP can only write the boot flash while the host is powered off, so
+    /// this moves the host to A2 for the duration.  The planner only asks for
+    /// this while the rest of the system is otherwise settled.
+    async fn deliver_host_phase_1(
+        &self,
+        opctx: &OpContext,
+        update: &PendingMgsUpdate,
+        data: Vec<u8>,
+        mgs_clients: &mut MgsClients,
+    ) -> Result<(), anyhow::Error> {
+        let log = &opctx.log;
+        let sp_type = update.sp_type;
+        let sp_slot = u32::from(update.slot_id);
+        let component = SpComponent::HOST_CPU_BOOT_FLASH.const_as_str();
+
+        let active_slot = mgs_clients
+            .try_all_serially(log, |client| async move {
+                client
+                    .sp_component_active_slot_get(sp_type, sp_slot, component)
+                    .await
+                    .map(|response| response.into_inner().slot)
+            })
+            .await
+            .context("fetching active host boot flash slot")?;
+        let target_slot = match active_slot {
+            0 => 1,
+            1 => 0,
+            other => {
+                return Err(anyhow!(
+                    "unexpected active host boot flash slot {other}"
+                ));
+            }
+        };
+
+        set_host_power_state(
+            log,
+            mgs_clients,
+            sp_type,
+            sp_slot,
+            PowerState::A2,
+        )
+        .await?;
+
+        // The updater makes the target slot active before writing it.  If the
+        // write fails, put back the slot we started with so that the host
+        // boots from an image we know is intact.
+        let result = HostPhase1Updater::new(
+            sp_type,
+            sp_slot,
+            target_slot,
+            Uuid::new_v4(),
+            data,
+            log,
+        )
+        .update(mgs_clients)
+        .await
+        .map_err(anyhow::Error::new);
+        if let Err(error) = result {
+            let restored = mgs_clients
+                .try_all_serially(log, |client| async move {
+                    client
+                        .sp_component_active_slot_set(
+                            sp_type,
+                            sp_slot,
+                            component,
+                            true,
+                            &SpComponentFirmwareSlot { slot: active_slot },
+                        )
+                        .await
+                })
+                .await;
+            if let Err(restore_error) = restored {
+                // Leave the host in A2 rather than boot a partial image.
+                error!(
+                    log,
+                    "failed to restore active host boot flash slot after \
+                     failed host phase 1 update; leaving host in A2";
+                    "update" => %update,
+                    "error" => %restore_error,
+                );
+                return Err(error);
+            }
+            set_host_power_state(
+                log,
+                mgs_clients,
+                sp_type,
+                sp_slot,
+                PowerState::A0,
+            )
+            .await?;
+            return Err(error);
+        }
+
+        // Inventory can't tell what's in the host's boot flash, so remember
+        // what we put there.
+        self.datastore
+            .host_phase_1_delivery_record(
+                opctx,
+                HostPhase1Delivery::new(
+                    &update.baseboard_id,
+                    update.artifact_hash.into(),
+                ),
+            )
+            .await
+            .context("recording host phase 1 delivery")?;
+
+        set_host_power_state(log, mgs_clients, sp_type, sp_slot, PowerState::A0)
+            .await
+    }
+}
+
+async fn set_host_power_state(
+    log: &slog::Logger,
+    mgs_clients: &mut MgsClients,
+    sp_type: SpType,
+    sp_slot: u32,
+    power_state: PowerState,
+) -> Result<(), anyhow::Error> {
+    info!(
+        log,
+        "setting host power state";
+        "sp_type" => ?sp_type,
+        "sp_slot" => sp_slot,
+        "power_state" => ?power_state,
+    );
+    mgs_clients
+        .try_all_serially(log, |client| async move {
+            client.sp_power_state_set(sp_type, sp_slot, power_state).await
+        })
+        .await
+        .with_context(|| format!("moving host to {power_state:?}"))?;
+    Ok(())
+}
+
+impl BackgroundTask for MgsUpdateDriver {
+    fn activate<'a>(
+        &'a mut self,
+        opctx: &'a OpContext,
+    ) -> BoxFuture<'a, serde_json::Value> {
+        self.activate_impl(opctx).boxed()
+    }
+}
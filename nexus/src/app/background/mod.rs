@@ -13,9 +13,11 @@ mod dns_propagation;
 mod dns_servers;
 mod external_endpoints;
 mod init;
+mod instance_serial_console;
 mod instance_watcher;
 mod inventory_collection;
 mod metrics_producer_gc;
+mod mgs_updates;
 mod nat_cleanup;
 mod networking;
 mod phantom_disks;
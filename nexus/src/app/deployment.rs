@@ -147,6 +147,13 @@ impl super::Nexus {
         let service_nic_rows = datastore
             .service_network_interfaces_all_list_batched(opctx)
             .await?;
+        let host_phase_1_delivery_rows =
+            datastore.host_phase_1_delivery_list_all_batched(opctx).await?;
+        let target_release_row =
+            datastore.target_release_get_current(opctx).await?;
+        let target_release_artifact_rows = datastore
+            .target_release_artifacts_list(opctx, target_release_row.generation)
+            .await?;
 
         let internal_dns_version = datastore
             .dns_group_latest_version(opctx, DnsGroup::Internal)
@@ -169,7 +176,10 @@ impl super::Nexus {
             ip_pool_range_rows: &ip_pool_range_rows,
             external_ip_rows: &external_ip_rows,
             service_nic_rows: &service_nic_rows,
+            host_phase_1_delivery_rows: &host_phase_1_delivery_rows,
             target_nexus_zone_count: NEXUS_REDUNDANCY,
+            target_release_row: &target_release_row,
+            target_release_artifact_rows: &target_release_artifact_rows,
             log: &opctx.log,
             internal_dns_version,
             external_dns_version,
@@ -177,7 +177,14 @@ impl super::Nexus {
                 e => Reason::UnknownError { source: e },
             })?;
         let silo_id = db_silo_user.silo_id;
+        let actor = Actor::SiloUser { silo_user_id, silo_id };
+        if !db_silo_user.active {
+            return Err(Reason::BadCredentials {
+                actor,
+                source: anyhow::anyhow!("user has been deactivated"),
+            });
+        }
 
-        Ok(Actor::SiloUser { silo_user_id, silo_id })
+        Ok(actor)
     }
 }
@@ -25,6 +25,9 @@ use nexus_db_queries::authz;
 use nexus_db_queries::context::OpContext;
 use nexus_db_queries::db;
 use nexus_db_queries::db::datastore::InstanceAndActiveVmm;
+use nexus_db_queries::db::datastore::SerialConsoleCapture;
+use nexus_db_queries::db::datastore::SerialConsoleCursor;
+use nexus_db_queries::db::datastore::INSTANCE_SERIAL_CONSOLE_LOG_MAX_BYTES;
 use nexus_db_queries::db::identity::Resource;
 use nexus_db_queries::db::lookup;
 use nexus_db_queries::db::lookup::LookupPath;
@@ -333,21 +336,36 @@ impl super::Nexus {
         let actor = opctx.authn.actor_required().internal_context(
             "loading current user's ssh keys for new Instance",
         )?;
-        let (.., authz_user) = LookupPath::new(opctx, &self.db_datastore)
-            .silo_user_id(actor.actor_id())
-            .lookup_for(authz::Action::ListChildren)
-            .await?;
-
-        let ssh_keys = match &params.ssh_public_keys {
-            Some(keys) => Some(
-                self.db_datastore
-                    .ssh_keys_batch_lookup(opctx, &authz_user, keys)
-                    .await?
-                    .iter()
-                    .map(|id| NameOrId::Id(*id))
-                    .collect::<Vec<NameOrId>>(),
-            ),
-            None => None,
+        let ssh_keys = match actor.silo_user_id() {
+            Some(silo_user_id) => {
+                let (.., authz_user) =
+                    LookupPath::new(opctx, &self.db_datastore)
+                        .silo_user_id(silo_user_id)
+                        .lookup_for(authz::Action::ListChildren)
+                        .await?;
+                match &params.ssh_public_keys {
+                    Some(keys) => Some(
+                        self.db_datastore
+                            .ssh_keys_batch_lookup(opctx, &authz_user, keys)
+                            .await?
+                            .iter()
+                            .map(|id| NameOrId::Id(*id))
+                            .collect::<Vec<NameOrId>>(),
+                    ),
+                    None => None,
+                }
+            }
+            // Service accounts don't have SSH keys of their own.
+            None => match &params.ssh_public_keys {
+                Some(keys) if !keys.is_empty() => {
+                    return Err(Error::invalid_value(
+                        "ssh_public_keys",
+                        "only silo users can attach their SSH keys to an \
+                        instance",
+                    ));
+                }
+                _ => Some(Vec::new()),
+            },
         };
         if let Some(ssh_keys) = &ssh_keys {
             if ssh_keys.len() > MAX_SSH_KEYS_PER_INSTANCE.try_into().unwrap() {
@@ -978,6 +996,15 @@ impl super::Nexus {
         )? {
             InstanceStateChangeRequestAction::AlreadyDone => Ok(()),
             InstanceStateChangeRequestAction::SendToSled(sled_id) => {
+                // Have the background task capture the instance's serial
+                // console output before its VMM goes away.  Anything it prints
+                // while stopping is captured when the sled agent reports that.
+                if let InstanceStateChangeRequest::Stop = requested {
+                    self.background_tasks.activate(
+                        &self.background_tasks.task_instance_serial_console,
+                    );
+                }
+
                 let sa = self.sled_client(&sled_id).await?;
                 let instance_put_result = sa
                     .instance_put_state(
@@ -1524,49 +1551,67 @@ impl super::Nexus {
             new_runtime_state,
         )
         .await?;
+
+        // A VMM's serial console output goes away with its Propolis server,
+        // so have the background task capture whatever it hasn't yet while
+        // the server is still there.
+        if matches!(
+            new_runtime_state.vmm_state.state,
+            InstanceState::Stopping | InstanceState::Migrating
+        ) {
+            self.background_tasks
+                .activate(&self.background_tasks.task_instance_serial_console);
+        }
         Ok(())
     }
 
-    /// Returns the requested range of serial console output bytes,
-    /// provided they are still in the propolis-server's cache.
+    /// Returns the requested range of the instance's serial console output.
+    ///
+    /// This is served from the output Nexus has captured from the instance's
+    /// VMMs, so it remains available after the instance stops or migrates,
+    /// followed by whatever the running VMM has produced since it was last
+    /// captured.
     pub(crate) async fn instance_serial_console_data(
         &self,
         opctx: &OpContext,
         instance_lookup: &lookup::Instance<'_>,
         params: &params::InstanceSerialConsoleRequest,
     ) -> Result<params::InstanceSerialConsoleData, Error> {
-        let client = self
-            .propolis_client_for_instance(
-                opctx,
-                instance_lookup,
-                authz::Action::Read,
-            )
+        let (.., authz_instance) =
+            instance_lookup.lookup_for(authz::Action::Read).await?;
+
+        let state = self
+            .db_datastore
+            .instance_fetch_with_vmm(opctx, &authz_instance)
             .await?;
-        let mut request = client.instance_serial_history_get();
-        if let Some(max_bytes) = params.max_bytes {
-            request = request.max_bytes(max_bytes);
-        }
-        if let Some(from_start) = params.from_start {
-            request = request.from_start(from_start);
-        }
-        if let Some(most_recent) = params.most_recent {
-            request = request.most_recent(most_recent);
+        let mut chunks = self
+            .db_datastore
+            .instance_serial_console_log_list(opctx, &authz_instance)
+            .await?;
+
+        if let Some(vmm) =
+            state.vmm().as_ref().filter(|vmm| vmm_has_serial_console(vmm))
+        {
+            // This doesn't record what it fetches; that's left to the
+            // background task, so that reading the console never writes to
+            // the database.  If it fails, we can still return whatever we
+            // captured previously.
+            let cursor = chunks.last().map(SerialConsoleCursor::from);
+            match fetch_vmm_serial_console(vmm, cursor).await {
+                Ok(capture) => {
+                    chunks.extend(capture.chunks(authz_instance.id(), cursor))
+                }
+                Err(e) => warn!(
+                    opctx.log,
+                    "failed to fetch instance serial console output";
+                    "instance_id" => %authz_instance.id(),
+                    "vmm_id" => %vmm.id,
+                    "error" => %e,
+                ),
+            }
         }
-        let data = request
-            .send()
-            .await
-            .map_err(|e| {
-                Error::internal_error(&format!(
-                    "websocket connection to instance's serial port failed: \
-                        {:?}",
-                    e,
-                ))
-            })?
-            .into_inner();
-        Ok(params::InstanceSerialConsoleData {
-            data: data.data,
-            last_byte_offset: data.last_byte_offset,
-        })
+
+        serial_console_log_range(&chunks, params)
     }
 
     pub(crate) async fn instance_serial_console_stream(
@@ -1576,12 +1621,8 @@ impl super::Nexus {
         instance_lookup: &lookup::Instance<'_>,
         params: &params::InstanceSerialConsoleStreamRequest,
     ) -> Result<(), Error> {
-        let client_addr = match self
-            .propolis_addr_for_instance(
-                opctx,
-                instance_lookup,
-                authz::Action::Modify,
-            )
+        let (client_addr, offset, replay) = match self
+            .serial_console_stream_start(opctx, instance_lookup, params)
             .await
         {
             Ok(x) => x,
@@ -1597,11 +1638,6 @@ impl super::Nexus {
             }
         };
 
-        let offset = match params.most_recent {
-            Some(most_recent) => WSClientOffset::MostRecent(most_recent),
-            None => WSClientOffset::FromStart(0),
-        };
-
         match propolis_client::support::InstanceSerialConsoleHelper::new(
             client_addr,
             offset,
@@ -1610,6 +1646,14 @@ impl super::Nexus {
         .await
         {
             Ok(propolis_conn) => {
+                if !replay.is_empty() {
+                    client_stream
+                        .send(WebSocketMessage::Binary(replay))
+                        .await
+                        .map_err(|e| {
+                            Error::internal_error(&format!("{}", e))
+                        })?;
+                }
                 Self::proxy_instance_serial_ws(client_stream, propolis_conn)
                     .await
                     .map_err(|e| Error::internal_error(&format!("{}", e)))
@@ -1631,12 +1675,82 @@ impl super::Nexus {
         }
     }
 
-    async fn propolis_addr_for_instance(
+    /// Determines where to start streaming an instance's serial console.
+    ///
+    /// Returns the address of the Propolis server to connect to, the offset in
+    /// its output to start from, and any captured output from the instance's
+    /// earlier VMMs to send before it.  Like the offsets used by
+    /// [`Self::instance_serial_console_data`], `params.from_start` counts all
+    /// of the output the instance has produced since it was created.
+    async fn serial_console_stream_start(
+        &self,
+        opctx: &OpContext,
+        instance_lookup: &lookup::Instance<'_>,
+        params: &params::InstanceSerialConsoleStreamRequest,
+    ) -> Result<(SocketAddr, WSClientOffset, Vec<u8>), Error> {
+        let (authz_instance, vmm) = self
+            .serial_console_vmm_for_instance(
+                opctx,
+                instance_lookup,
+                authz::Action::Modify,
+            )
+            .await?;
+        let client_addr =
+            SocketAddr::new(vmm.propolis_ip.ip(), vmm.propolis_port.into());
+
+        match (params.from_start, params.most_recent) {
+            (None, None) => {
+                Ok((client_addr, WSClientOffset::FromStart(0), vec![]))
+            }
+            (None, Some(most_recent)) => Ok((
+                client_addr,
+                WSClientOffset::MostRecent(most_recent),
+                vec![],
+            )),
+            (Some(from_start), None) => {
+                let chunks = self
+                    .db_datastore
+                    .instance_serial_console_log_list(opctx, &authz_instance)
+                    .await?;
+                let vmm_start = vmm_serial_console_start(&chunks, vmm.id);
+
+                // Output the instance produced before this VMM started comes
+                // from what we've captured; Propolis has the rest.
+                let replay = chunks
+                    .iter()
+                    .filter_map(|chunk| {
+                        let data = chunk.data.as_deref()?;
+                        let start = chunk.start_offset as u64;
+                        let end = (chunk.end_offset as u64).min(vmm_start);
+                        let begin = from_start.max(start);
+                        (begin < end).then(|| {
+                            &data[(begin - start) as usize
+                                ..(end - start) as usize]
+                        })
+                    })
+                    .flatten()
+                    .copied()
+                    .collect();
+                Ok((
+                    client_addr,
+                    WSClientOffset::FromStart(
+                        from_start.saturating_sub(vmm_start),
+                    ),
+                    replay,
+                ))
+            }
+            (Some(_), Some(_)) => Err(Error::invalid_request(
+                "at most one of from_start and most_recent may be provided",
+            )),
+        }
+    }
+
+    async fn serial_console_vmm_for_instance(
         &self,
         opctx: &OpContext,
         instance_lookup: &lookup::Instance<'_>,
         action: authz::Action,
-    ) -> Result<SocketAddr, Error> {
+    ) -> Result<(authz::Instance, db::model::Vmm), Error> {
         let (.., authz_instance) = instance_lookup.lookup_for(action).await?;
 
         let state = self
@@ -1650,9 +1764,7 @@ impl super::Nexus {
                 InstanceState::Running
                 | InstanceState::Rebooting
                 | InstanceState::Migrating
-                | InstanceState::Repairing => {
-                    Ok(SocketAddr::new(vmm.propolis_ip.ip(), vmm.propolis_port.into()))
-                }
+                | InstanceState::Repairing => Ok((authz_instance, vmm.clone())),
                 InstanceState::Creating
                 | InstanceState::Starting
                 | InstanceState::Stopping
@@ -1676,18 +1788,6 @@ impl super::Nexus {
         }
     }
 
-    async fn propolis_client_for_instance(
-        &self,
-        opctx: &OpContext,
-        instance_lookup: &lookup::Instance<'_>,
-        action: authz::Action,
-    ) -> Result<propolis_client::Client, Error> {
-        let client_addr = self
-            .propolis_addr_for_instance(opctx, instance_lookup, action)
-            .await?;
-        Ok(propolis_client::Client::new(&format!("http://{}", client_addr)))
-    }
-
     async fn proxy_instance_serial_ws(
         client_stream: WebSocketStream<impl AsyncRead + AsyncWrite + Unpin>,
         mut propolis_conn: InstanceSerialConsoleHelper,
@@ -1963,6 +2063,187 @@ pub(crate) struct InstanceUpdated {
     pub vmm_updated: bool,
 }
 
+/// Returns true if `vmm` is in a state in which its Propolis server may be
+/// producing serial console output.
+pub(crate) fn vmm_has_serial_console(vmm: &db::model::Vmm) -> bool {
+    match vmm.runtime.state.0 {
+        InstanceState::Running
+        | InstanceState::Rebooting
+        | InstanceState::Migrating
+        | InstanceState::Repairing
+        | InstanceState::Stopping => true,
+        InstanceState::Creating
+        | InstanceState::Starting
+        | InstanceState::Stopped
+        | InstanceState::Failed
+        | InstanceState::Destroyed => false,
+    }
+}
+
+/// Copies any serial console output from `vmm` that Nexus hasn't yet
+/// captured into its instance's serial console log.
+///
+/// Returns the number of bytes captured.
+pub(crate) async fn capture_instance_serial_console(
+    datastore: &DataStore,
+    opctx: &OpContext,
+    vmm: &db::model::Vmm,
+) -> Result<usize, Error> {
+    let prev = datastore
+        .instance_serial_console_log_cursor(opctx, vmm.instance_id)
+        .await?;
+    let capture = fetch_vmm_serial_console(vmm, prev).await?;
+    if capture.lost == 0 && capture.data.is_empty() {
+        return Ok(0);
+    }
+
+    let appended = datastore
+        .instance_serial_console_log_append(
+            opctx,
+            vmm.instance_id,
+            prev,
+            &capture,
+        )
+        .await?;
+    Ok(if appended { capture.data.len() } else { 0 })
+}
+
+/// Fetches the serial console output `vmm` has produced since `cursor` from
+/// its Propolis server.
+async fn fetch_vmm_serial_console(
+    vmm: &db::model::Vmm,
+    cursor: Option<SerialConsoleCursor>,
+) -> Result<SerialConsoleCapture, Error> {
+    let max_bytes = INSTANCE_SERIAL_CONSOLE_LOG_MAX_BYTES as u64;
+
+    // Pick up where we left off in this VMM's output.  If this is a new VMM
+    // (because the instance was restarted or migrated), its output starts
+    // over from 0.
+    let from_start = match cursor {
+        Some(cursor) if cursor.vmm_id == vmm.id => cursor.vmm_offset,
+        _ => 0,
+    };
+
+    let client = propolis_client::Client::new(&format!(
+        "http://{}",
+        SocketAddr::new(vmm.propolis_ip.ip(), vmm.propolis_port.into())
+    ));
+    let history = match client
+        .instance_serial_history_get()
+        .from_start(from_start)
+        .max_bytes(max_bytes)
+        .send()
+        .await
+    {
+        Ok(history) => history.into_inner(),
+        // Propolis only buffers so much output; if what we want is gone,
+        // start again from the oldest output it still has.
+        Err(e) if e.status().is_some_and(|s| s.is_client_error()) => client
+            .instance_serial_history_get()
+            .most_recent(max_bytes)
+            .max_bytes(max_bytes)
+            .send()
+            .await
+            .map_err(|e| {
+                Error::internal_error(&format!(
+                    "fetching serial console history from propolis: {e}"
+                ))
+            })?
+            .into_inner(),
+        Err(e) => {
+            return Err(Error::internal_error(&format!(
+                "fetching serial console history from propolis: {e}"
+            )));
+        }
+    };
+
+    // If we fell back to the most recent output, there may be a gap between
+    // where we left off and where it starts, or some overlap with what we
+    // already have.
+    let mut data = history.data;
+    let data_start = history.last_byte_offset.saturating_sub(data.len() as u64);
+    let overlap = from_start.saturating_sub(data_start).min(data.len() as u64);
+    data.drain(..overlap as usize);
+    Ok(SerialConsoleCapture {
+        vmm_id: vmm.id,
+        vmm_offset: history.last_byte_offset,
+        lost: data_start.saturating_sub(from_start),
+        data,
+    })
+}
+
+/// Returns the offset, in all of an instance's serial console output, of the
+/// first byte produced by its VMM `vmm_id`, given the chunks captured so far.
+fn vmm_serial_console_start(
+    chunks: &[db::model::InstanceSerialConsoleChunk],
+    vmm_id: Uuid,
+) -> u64 {
+    match chunks.last() {
+        Some(last) if last.vmm_id == vmm_id => {
+            (last.end_offset - last.vmm_offset).max(0) as u64
+        }
+        Some(last) => last.end_offset as u64,
+        None => 0,
+    }
+}
+
+/// Selects the range of an instance's serial console output described by
+/// `params` from `chunks`, which must be contiguous and in order.
+///
+/// The returned data is always contiguous.  Output that was lost before it
+/// could be captured is skipped: a request that starts in a gap gets the
+/// output after it, and a response stops at the start of a gap, so that
+/// reading from `last_byte_offset` continues past it.
+fn serial_console_log_range(
+    chunks: &[db::model::InstanceSerialConsoleChunk],
+    params: &params::InstanceSerialConsoleRequest,
+) -> Result<params::InstanceSerialConsoleData, Error> {
+    let start_offset = chunks.first().map_or(0, |c| c.start_offset as u64);
+    let end_offset = chunks.last().map_or(0, |c| c.end_offset as u64);
+
+    // Output before `start_offset` has been discarded, so a request for it
+    // gets the oldest output that's still available.
+    let mut begin = match (params.from_start, params.most_recent) {
+        (Some(from_start), None) => from_start.clamp(start_offset, end_offset),
+        (None, Some(most_recent)) => {
+            end_offset.saturating_sub(most_recent).max(start_offset)
+        }
+        _ => {
+            return Err(Error::invalid_request(
+                "exactly one of from_start and most_recent must be provided",
+            ));
+        }
+    };
+    let max_bytes = params.max_bytes.unwrap_or(u64::MAX);
+
+    let mut data = Vec::new();
+    for chunk in chunks {
+        let pos = begin + data.len() as u64;
+        let (start, end) = (chunk.start_offset as u64, chunk.end_offset as u64);
+        if end <= pos {
+            continue;
+        }
+        let Some(chunk_data) = &chunk.data else {
+            if data.is_empty() {
+                begin = end;
+                continue;
+            }
+            break;
+        };
+        let take = (end - pos).min(max_bytes - data.len() as u64);
+        let from = (pos - start) as usize;
+        data.extend_from_slice(&chunk_data[from..from + take as usize]);
+        if data.len() as u64 == max_bytes {
+            break;
+        }
+    }
+
+    Ok(params::InstanceSerialConsoleData {
+        last_byte_offset: begin + data.len() as u64,
+        data,
+    })
+}
+
 /// Invoked by a sled agent to publish an updated runtime state for an
 /// Instance.
 pub(crate) async fn notify_instance_updated(
@@ -2145,15 +2426,18 @@ pub(crate) async fn notify_instance_updated(
 #[cfg(test)]
 mod tests {
     use super::super::Nexus;
+    use super::{params, serial_console_log_range, vmm_serial_console_start};
     use super::{CloseCode, CloseFrame, WebSocketMessage, WebSocketStream};
     use core::time::Duration;
     use futures::{SinkExt, StreamExt};
+    use nexus_db_model::InstanceSerialConsoleChunk;
     use omicron_test_utils::dev::test_setup_log;
     use propolis_client::support::tungstenite::protocol::Role;
     use propolis_client::support::{
         InstanceSerialConsoleHelper, WSClientOffset,
     };
     use std::net::{Ipv4Addr, SocketAddr, SocketAddrV4};
+    use uuid::Uuid;
 
     #[tokio::test]
     async fn test_serial_console_stream_proxying() {
@@ -2246,4 +2530,93 @@ mod tests {
             .expect("proxy task exited successfully");
         logctx.cleanup_successful();
     }
+
+    #[test]
+    fn test_serial_console_log_range() {
+        let instance_id = Uuid::new_v4();
+        let vmm_id = Uuid::new_v4();
+        let chunk =
+            |start_offset: i64, end_offset: i64, data: Option<&[u8]>| {
+                InstanceSerialConsoleChunk {
+                    instance_id,
+                    start_offset,
+                    end_offset,
+                    time_created: chrono::Utc::now(),
+                    data: data.map(|data| data.to_vec()),
+                    vmm_id,
+                    vmm_offset: end_offset,
+                }
+            };
+        let chunks = vec![
+            chunk(10, 15, Some(b"01234")),
+            chunk(15, 20, Some(b"56789")),
+            chunk(20, 25, None),
+            chunk(25, 28, Some(b"abc")),
+        ];
+        let range = |from_start, most_recent, max_bytes| {
+            let params = params::InstanceSerialConsoleRequest {
+                project: None,
+                from_start,
+                most_recent,
+                max_bytes,
+            };
+            serial_console_log_range(&chunks, &params)
+                .map(|data| (data.data, data.last_byte_offset))
+        };
+
+        // Reads span chunks, and stop at a gap.
+        assert_eq!(
+            range(Some(12), None, None).unwrap(),
+            (b"23456789".to_vec(), 20)
+        );
+        assert_eq!(
+            range(Some(12), None, Some(5)).unwrap(),
+            (b"23456".to_vec(), 17)
+        );
+
+        // A read that starts in a gap gets the output after it.
+        assert_eq!(range(Some(20), None, None).unwrap(), (b"abc".to_vec(), 28));
+        assert_eq!(
+            range(Some(22), None, Some(2)).unwrap(),
+            (b"ab".to_vec(), 27)
+        );
+        assert_eq!(range(None, Some(5), None).unwrap(), (b"abc".to_vec(), 28));
+        assert_eq!(range(None, Some(2), None).unwrap(), (b"bc".to_vec(), 28));
+
+        // Output that has been discarded, or hasn't been produced yet, can't
+        // be returned.
+        assert_eq!(
+            range(Some(0), None, Some(2)).unwrap(),
+            (b"01".to_vec(), 12)
+        );
+        assert_eq!(
+            range(None, Some(100), Some(2)).unwrap(),
+            (b"01".to_vec(), 12)
+        );
+        assert_eq!(range(Some(30), None, None).unwrap(), (Vec::new(), 28));
+
+        // Exactly one of `from_start` and `most_recent` is required.
+        assert!(range(None, None, None).is_err());
+        assert!(range(Some(0), Some(0), None).is_err());
+
+        // Nothing has been captured for an instance that has never run.
+        let params = params::InstanceSerialConsoleRequest {
+            project: None,
+            from_start: Some(0),
+            most_recent: None,
+            max_bytes: None,
+        };
+        let data = serial_console_log_range(&[], &params).unwrap();
+        assert!(data.data.is_empty());
+        assert_eq!(data.last_byte_offset, 0);
+
+        // A VMM's output starts where the last chunk it produced leaves off
+        // in its own output, or after all of the captured output if it
+        // hasn't produced any yet.
+        let mut chunks = chunks.clone();
+        chunks.last_mut().unwrap().vmm_offset = 8;
+        assert_eq!(vmm_serial_console_start(&chunks, vmm_id), 20);
+        assert_eq!(vmm_serial_console_start(&chunks, Uuid::new_v4()), 28);
+        assert_eq!(vmm_serial_console_start(&[], vmm_id), 0);
+    }
 }
@@ -34,12 +34,14 @@ use std::collections::HashMap;
 use std::net::SocketAddrV6;
 use std::net::{IpAddr, Ipv6Addr};
 use std::sync::Arc;
+use update_common::artifacts::ArtifactStore;
 use uuid::Uuid;
 
 // The implementation of Nexus is large, and split into a number of submodules
 // by resource.
 mod address_lot;
 mod allow_list;
+mod api_token;
 pub(crate) mod background;
 mod bfd;
 mod bgp;
@@ -57,12 +59,15 @@ mod instance_network;
 mod ip_pool;
 mod metrics;
 mod network_interface;
+mod oidc;
 pub(crate) mod oximeter;
 mod probe;
 mod project;
 mod quota;
 mod rack;
 pub(crate) mod saga;
+mod scim;
+mod service_account;
 mod session;
 mod silo;
 mod sled;
@@ -166,6 +171,12 @@ pub struct Nexus {
     #[allow(dead_code)]
     updates_config: Option<UpdatesConfig>,
 
+    /// Contents of the TUF repositories uploaded to this Nexus
+    uploaded_repos: update::UploadedRepos,
+
+    /// Store for the artifacts extracted from uploaded TUF repositories
+    artifact_store: ArtifactStore,
+
     /// The tunable parameters from a configuration file
     tunables: Tunables,
 
@@ -183,6 +194,9 @@ pub struct Nexus {
     // Nexus to not all fail.
     samael_max_issue_delay: std::sync::Mutex<Option<chrono::Duration>>,
 
+    /// Signing keys published by OIDC identity providers
+    oidc_jwks_cache: oidc::OidcJwksCache,
+
     /// DNS resolver for internal services
     internal_resolver: internal_dns::resolver::Resolver,
 
@@ -392,6 +406,17 @@ impl Nexus {
 
         let (saga_request, mut saga_request_recv) = SagaRequest::channel();
 
+        let uploaded_repos = update::UploadedRepos::new();
+        let artifact_store = match &config.pkg.artifact_store {
+            Some(artifact_store) => {
+                ArtifactStore::open(&log, artifact_store.dir.clone()).await
+            }
+            None => ArtifactStore::temporary(&log),
+        }
+        .map_err(|error| {
+            format!("failed to open artifact store: {:#}", anyhow!(error))
+        })?;
+
         let background_tasks = background::BackgroundTasks::start(
             &background_ctx,
             Arc::clone(&db_datastore),
@@ -401,6 +426,7 @@ impl Nexus {
             resolver.clone(),
             saga_request,
             producer_registry,
+            uploaded_repos.clone(),
         );
 
         let external_resolver = {
@@ -428,6 +454,8 @@ impl Nexus {
             reqwest_client,
             timeseries_client,
             updates_config: config.pkg.updates.clone(),
+            uploaded_repos,
+            artifact_store,
             tunables: config.pkg.tunables.clone(),
             opctx_alloc: OpContext::for_background(
                 log.new(o!("component" => "InstanceAllocator")),
@@ -442,6 +470,7 @@ impl Nexus {
                 Arc::clone(&db_datastore),
             ),
             samael_max_issue_delay: std::sync::Mutex::new(None),
+            oidc_jwks_cache: oidc::OidcJwksCache::default(),
             internal_resolver: resolver,
             external_resolver,
             external_dns_servers: config
@@ -503,6 +532,13 @@ impl Nexus {
             }
         });
 
+        // Restore the contents of the repositories uploaded before this Nexus
+        // started.  This can take a while, so don't hold up startup for it.
+        {
+            let nexus = nexus.clone();
+            tokio::spawn(async move { nexus.uploaded_repos_restore().await });
+        }
+
         // Spawn a task to receive SagaRequests from RPWs, and execute them
         {
             let nexus = nexus.clone();
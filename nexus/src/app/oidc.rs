@@ -0,0 +1,470 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! OpenID Connect identity providers
+//!
+//! OIDC providers are an alternative to SAML for Silos that use federated
+//! authentication.  Users log in with the authorization code flow, protected
+//! with PKCE: the console login routes send the user to the provider along
+//! with a random `state`, `nonce`, and code challenge that we record in the
+//! database.  When the provider sends the user back, we look up (and consume)
+//! that record, exchange the authorization code for an ID token, verify the
+//! token against the provider's published signing keys, and map its claims
+//! onto a Silo user and groups just as we do for SAML assertions.
+
+use crate::external_api::params;
+use nexus_db_queries::authn;
+use nexus_db_queries::authn::oidc::{Jwks, UnverifiedIdToken};
+use nexus_db_queries::authz;
+use nexus_db_queries::context::OpContext;
+use nexus_db_queries::db;
+use nexus_db_queries::db::identity::Resource;
+use nexus_db_queries::db::lookup;
+use nexus_db_queries::db::lookup::LookupPath;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::LookupType;
+use omicron_common::api::external::NameOrId;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::time::Duration;
+use std::time::Instant;
+use uuid::Uuid;
+
+/// How long we use a provider's signing keys before fetching them again
+const JWKS_CACHE_TTL: Duration = Duration::from_secs(15 * 60);
+
+/// Minimum time between fetches of a provider's signing keys
+///
+/// Providers rotate keys by publishing the new one before they start using it,
+/// so when we see a token signed with a key we don't know about, we refetch
+/// the key set early.  This limit keeps a stream of bogus tokens from turning
+/// into a stream of requests to the provider.
+const JWKS_MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Timeout for requests made to an identity provider
+const OIDC_REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Cache of signing key sets published by OIDC identity providers, keyed by
+/// their `jwks_uri`
+#[derive(Default)]
+pub(crate) struct OidcJwksCache {
+    entries: Mutex<HashMap<String, JwksCacheEntry>>,
+}
+
+struct JwksCacheEntry {
+    fetched: Instant,
+    jwks: Arc<Jwks>,
+}
+
+impl OidcJwksCache {
+    /// Returns the cached key set for `jwks_uri` if it can be used to verify
+    /// `token`, or `None` if it should be fetched again first
+    fn get(
+        &self,
+        jwks_uri: &str,
+        token: &UnverifiedIdToken,
+    ) -> Option<Arc<Jwks>> {
+        let entries = self.entries.lock().unwrap();
+        let entry = entries.get(jwks_uri)?;
+        let age = entry.fetched.elapsed();
+        let has_key = entry.jwks.key_for(token).is_some();
+        if (has_key && age < JWKS_CACHE_TTL)
+            || (!has_key && age < JWKS_MIN_REFRESH_INTERVAL)
+        {
+            Some(Arc::clone(&entry.jwks))
+        } else {
+            None
+        }
+    }
+
+    fn insert(&self, jwks_uri: &str, jwks: Arc<Jwks>) {
+        self.entries.lock().unwrap().insert(
+            jwks_uri.to_string(),
+            JwksCacheEntry { fetched: Instant::now(), jwks },
+        );
+    }
+}
+
+/// The parts of a provider's discovery document (OpenID Connect Discovery 1.0
+/// §3) that we use
+#[derive(Deserialize)]
+struct OidcProviderMetadata {
+    issuer: String,
+    authorization_endpoint: String,
+    token_endpoint: String,
+    jwks_uri: String,
+    #[serde(default)]
+    code_challenge_methods_supported: Option<Vec<String>>,
+}
+
+/// The parts of a successful token endpoint response that we use
+#[derive(Deserialize)]
+struct OidcTokenResponse {
+    id_token: String,
+}
+
+/// Returns the application/x-www-form-urlencoded encoding of `value`, which
+/// RFC 6749 §2.3.1 requires for client credentials sent with HTTP Basic
+/// authentication
+fn form_urlencode(value: &str) -> String {
+    serde_urlencoded::to_string([("", value)])
+        .map(|encoded| encoded[1..].to_string())
+        .unwrap_or_else(|_| value.to_string())
+}
+
+impl super::Nexus {
+    fn oidc_http_client(&self) -> Result<reqwest::Client, Error> {
+        reqwest::ClientBuilder::new()
+            .connect_timeout(OIDC_REQUEST_TIMEOUT)
+            .timeout(OIDC_REQUEST_TIMEOUT)
+            .dns_resolver(self.external_resolver.clone())
+            .build()
+            .map_err(|e| {
+                Error::internal_error(&format!(
+                    "failed to build reqwest client: {}",
+                    e
+                ))
+            })
+    }
+
+    pub fn oidc_identity_provider_lookup<'a>(
+        &'a self,
+        opctx: &'a OpContext,
+        oidc_identity_provider_selector: params::OidcIdentityProviderSelector,
+    ) -> LookupResult<lookup::OidcIdentityProvider<'a>> {
+        match oidc_identity_provider_selector {
+            params::OidcIdentityProviderSelector {
+                oidc_identity_provider: NameOrId::Id(id),
+                silo: None,
+            } => {
+                let oidc_provider = LookupPath::new(opctx, &self.db_datastore)
+                    .oidc_identity_provider_id(id);
+                Ok(oidc_provider)
+            }
+            params::OidcIdentityProviderSelector {
+                oidc_identity_provider: NameOrId::Name(name),
+                silo: Some(silo),
+            } => {
+                let oidc_provider = self
+                    .silo_lookup(opctx, silo)?
+                    .oidc_identity_provider_name_owned(name.into());
+                Ok(oidc_provider)
+            }
+            params::OidcIdentityProviderSelector {
+                oidc_identity_provider: NameOrId::Id(_),
+                silo: _,
+            } => Err(Error::invalid_request(
+                "when providing provider as an ID, silo should not be specified",
+            )),
+            _ => Err(Error::invalid_request(
+                "provider should either be a UUID or silo should be specified",
+            )),
+        }
+    }
+
+    pub(crate) async fn oidc_identity_provider_create(
+        &self,
+        opctx: &OpContext,
+        silo_lookup: &lookup::Silo<'_>,
+        params: params::OidcIdentityProviderCreate,
+    ) -> CreateResult<db::model::OidcIdentityProvider> {
+        let (authz_silo, db_silo) = silo_lookup.fetch().await?;
+        let authz_idp_list = authz::SiloIdentityProviderList::new(authz_silo);
+
+        if !matches!(
+            db_silo.user_provision_type,
+            db::model::UserProvisionType::Jit
+                | db::model::UserProvisionType::Scim
+        ) {
+            return Err(Error::invalid_request(
+                "cannot create identity providers in this kind of Silo",
+            ));
+        }
+
+        // As with SAML, check this now to protect the code that fetches the
+        // provider's configuration from an external source.
+        opctx.authorize(authz::Action::CreateChild, &authz_idp_list).await?;
+
+        if db_silo.authentication_mode
+            != nexus_db_model::AuthenticationMode::Saml
+        {
+            return Err(Error::invalid_request(&format!(
+                "cannot create OIDC identity provider for this Silo type \
+                (expected authentication mode {:?}, found {:?})",
+                nexus_db_model::AuthenticationMode::Saml,
+                &db_silo.authentication_mode,
+            )));
+        }
+
+        if !params
+            .scopes
+            .iter()
+            .all(|s| !s.is_empty() && !s.contains(|c: char| c.is_whitespace()))
+        {
+            return Err(Error::invalid_value(
+                "scopes",
+                "scopes must be non-empty and cannot contain whitespace",
+            ));
+        }
+
+        // Fetch the provider's configuration once, now, and store the parts we
+        // need.  Like the SAML metadata document, it would add attack surface
+        // to fetch this every time it's needed.
+        let issuer = params.issuer.trim_end_matches('/').to_string();
+        let discovery_url =
+            format!("{}/.well-known/openid-configuration", issuer);
+        let response =
+            self.oidc_http_client()?.get(&discovery_url).send().await.map_err(
+                |e| {
+                    Error::invalid_value(
+                        "issuer",
+                        format!("error querying {}: {}", discovery_url, e),
+                    )
+                },
+            )?;
+        if !response.status().is_success() {
+            return Err(Error::invalid_value(
+                "issuer",
+                format!(
+                    "querying {} returned: {}",
+                    discovery_url,
+                    response.status()
+                ),
+            ));
+        }
+        let metadata: OidcProviderMetadata =
+            response.json().await.map_err(|e| {
+                Error::invalid_value(
+                    "issuer",
+                    format!("error parsing provider configuration: {}", e),
+                )
+            })?;
+
+        // The issuer in the discovery document must exactly match the one we
+        // used to find it (OpenID Connect Discovery 1.0 §4.3).
+        if metadata.issuer.trim_end_matches('/') != issuer {
+            return Err(Error::invalid_value(
+                "issuer",
+                format!(
+                    "provider configuration is for issuer {:?}",
+                    metadata.issuer
+                ),
+            ));
+        }
+        if let Some(methods) = &metadata.code_challenge_methods_supported {
+            if !methods.iter().any(|m| m == "S256") {
+                return Err(Error::invalid_value(
+                    "issuer",
+                    "provider does not support PKCE with S256",
+                ));
+            }
+        }
+
+        let provider = db::model::OidcIdentityProvider {
+            identity: db::model::OidcIdentityProviderIdentity::new(
+                Uuid::new_v4(),
+                params.identity,
+            ),
+            silo_id: db_silo.id(),
+            // Store the issuer exactly as the provider reports it, since
+            // that's what will appear in the ID tokens it issues.
+            issuer: metadata.issuer,
+            client_id: params.client_id,
+            client_secret: params.client_secret,
+            authorization_endpoint: metadata.authorization_endpoint,
+            token_endpoint: metadata.token_endpoint,
+            jwks_uri: metadata.jwks_uri,
+            redirect_uri: params.redirect_uri,
+            scopes: params.scopes.join(" "),
+            user_claim: params.user_claim,
+            group_claim: params.group_claim,
+        };
+
+        self.db_datastore
+            .oidc_identity_provider_create(opctx, &authz_idp_list, provider)
+            .await
+    }
+
+    /// Begin logging a user in with an OIDC identity provider
+    ///
+    /// Returns the URL of the provider's authorization endpoint that the user
+    /// should be sent to.  `relay_state` is handed back when the login
+    /// completes.
+    pub(crate) async fn oidc_login_begin(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        provider: &authn::oidc::OidcIdentityProvider,
+        relay_state: Option<String>,
+    ) -> Result<String, Error> {
+        let authz_idp = authz::OidcIdentityProvider::new(
+            authz_silo.clone(),
+            provider.id,
+            LookupType::ById(provider.id),
+        );
+        let login_request = self
+            .db_datastore
+            .oidc_login_request_create(
+                opctx,
+                &authz_idp,
+                db::model::OidcLoginRequest::new(provider.id, relay_state),
+            )
+            .await?;
+        provider.authorization_url(&login_request).map_err(|e| {
+            Error::internal_error(&format!(
+                "building authorization URL: {:#}",
+                e
+            ))
+        })
+    }
+
+    /// Finish logging a user in with an OIDC identity provider
+    ///
+    /// `code` and `state` are the query parameters with which the provider
+    /// sent the user back to us.  Returns the Silo user (if there is one) and
+    /// the relay state from the start of the login.
+    pub(crate) async fn oidc_login_complete(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        db_silo: &db::model::Silo,
+        provider: &authn::oidc::OidcIdentityProvider,
+        code: &str,
+        state: &str,
+    ) -> Result<(Option<db::model::SiloUser>, Option<String>), Error> {
+        let authz_idp = authz::OidcIdentityProvider::new(
+            authz_silo.clone(),
+            provider.id,
+            LookupType::ById(provider.id),
+        );
+        let login_request = self
+            .db_datastore
+            .oidc_login_request_consume(opctx, &authz_idp, state)
+            .await?
+            .ok_or_else(|| Error::Unauthenticated {
+                internal_message: String::from(
+                    "OIDC login state does not match any login request",
+                ),
+            })?;
+        if login_request.time_expires < chrono::Utc::now() {
+            return Err(Error::Unauthenticated {
+                internal_message: String::from("OIDC login request expired"),
+            });
+        }
+
+        let id_token =
+            self.oidc_token_exchange(provider, code, &login_request).await?;
+        let id_token = UnverifiedIdToken::parse(&id_token).map_err(|e| {
+            Error::Unauthenticated {
+                internal_message: format!("parsing ID token: {:#}", e),
+            }
+        })?;
+        let jwks = self.oidc_jwks(provider, &id_token).await?;
+        let authenticated_subject = provider
+            .verify_id_token(
+                &id_token,
+                &jwks,
+                &login_request.nonce,
+                chrono::Utc::now(),
+            )
+            .map_err(|e| Error::Unauthenticated {
+                internal_message: format!("verifying ID token: {:#}", e),
+            })?;
+
+        let user = self
+            .silo_user_from_authenticated_subject(
+                opctx,
+                authz_silo,
+                db_silo,
+                &authenticated_subject,
+            )
+            .await?;
+        Ok((user, login_request.relay_state))
+    }
+
+    /// Exchange an authorization code for an ID token
+    async fn oidc_token_exchange(
+        &self,
+        provider: &authn::oidc::OidcIdentityProvider,
+        code: &str,
+        login_request: &db::model::OidcLoginRequest,
+    ) -> Result<String, Error> {
+        let response = self
+            .oidc_http_client()?
+            .post(&provider.token_endpoint)
+            .basic_auth(
+                form_urlencode(&provider.client_id),
+                Some(form_urlencode(&provider.client_secret)),
+            )
+            .form(&provider.token_request_params(code, login_request))
+            .send()
+            .await
+            .map_err(|e| {
+                Error::unavail(&format!(
+                    "error querying OIDC token endpoint: {}",
+                    e
+                ))
+            })?;
+
+        // The provider rejects the code if the user took too long, reused it,
+        // or the code verifier doesn't match.  None of those are our problem.
+        if !response.status().is_success() {
+            return Err(Error::Unauthenticated {
+                internal_message: format!(
+                    "OIDC token endpoint returned: {}",
+                    response.status()
+                ),
+            });
+        }
+
+        let token_response: OidcTokenResponse =
+            response.json().await.map_err(|e| Error::Unauthenticated {
+                internal_message: format!(
+                    "error parsing OIDC token response: {}",
+                    e
+                ),
+            })?;
+        Ok(token_response.id_token)
+    }
+
+    /// Returns the provider's signing keys, suitable for verifying `token`
+    async fn oidc_jwks(
+        &self,
+        provider: &authn::oidc::OidcIdentityProvider,
+        token: &UnverifiedIdToken,
+    ) -> Result<Arc<Jwks>, Error> {
+        if let Some(jwks) = self.oidc_jwks_cache.get(&provider.jwks_uri, token)
+        {
+            return Ok(jwks);
+        }
+
+        let response = self
+            .oidc_http_client()?
+            .get(&provider.jwks_uri)
+            .send()
+            .await
+            .map_err(|e| {
+                Error::unavail(&format!(
+                    "error querying OIDC signing keys: {}",
+                    e
+                ))
+            })?;
+        if !response.status().is_success() {
+            return Err(Error::unavail(&format!(
+                "querying OIDC signing keys returned: {}",
+                response.status()
+            )));
+        }
+        let jwks: Arc<Jwks> = Arc::new(response.json().await.map_err(|e| {
+            Error::unavail(&format!("error parsing OIDC signing keys: {}", e))
+        })?);
+
+        self.oidc_jwks_cache.insert(&provider.jwks_uri, Arc::clone(&jwks));
+        Ok(jwks)
+    }
+}
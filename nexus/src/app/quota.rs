@@ -46,4 +46,37 @@ impl super::Nexus {
             .silo_update_quota(opctx, &authz_silo, updates.clone().into())
             .await
     }
+
+    pub(crate) async fn project_quotas_view(
+        &self,
+        opctx: &OpContext,
+        project_lookup: &lookup::Project<'_>,
+    ) -> Result<db::model::ProjectQuotas, Error> {
+        let (.., authz_project) =
+            project_lookup.lookup_for(authz::Action::Read).await?;
+        self.db_datastore.project_quotas_view(opctx, &authz_project).await
+    }
+
+    pub(crate) async fn project_quotas_update(
+        &self,
+        opctx: &OpContext,
+        project_lookup: &lookup::Project<'_>,
+        updates: &params::ProjectQuotasUpdate,
+    ) -> UpdateResult<db::model::ProjectQuotas> {
+        if let Some(cpus) = updates.cpus {
+            if cpus < 0 {
+                return Err(Error::invalid_value(
+                    "cpus",
+                    "project CPU quota must not be negative",
+                ));
+            }
+        }
+        let (authz_silo, authz_project) =
+            project_lookup.lookup_for(authz::Action::Read).await?;
+        let quotas =
+            db::model::ProjectQuotas::new(authz_project.id(), updates.clone());
+        self.db_datastore
+            .project_quotas_update(opctx, &authz_silo, &authz_project, quotas)
+            .await
+    }
 }
@@ -320,8 +320,14 @@ async fn sic_associate_ssh_keys(
         .internal_context("loading current user's ssh keys for new Instance")
         .map_err(ActionError::action_failed)?;
 
+    // Only silo users have SSH keys.  For anyone else (e.g., a service
+    // account), `ssh_public_keys` was already checked to be empty.
+    let Some(silo_user_id) = actor.silo_user_id() else {
+        return Ok(());
+    };
+
     let (.., authz_user) = LookupPath::new(&opctx, &datastore)
-        .silo_user_id(actor.actor_id())
+        .silo_user_id(silo_user_id)
         .lookup_for(authz::Action::ListChildren)
         .await
         .map_err(ActionError::action_failed)?;
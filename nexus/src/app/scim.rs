@@ -0,0 +1,478 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! SCIM 2.0 provisioning of Silo users and groups
+//!
+//! In Silos whose identity mode is `SamlScim`, users still authenticate with
+//! SAML, but they are not created during login.  Instead, the Silo's identity
+//! provider creates, updates, and deletes users and groups using the SCIM 2.0
+//! protocol (RFC 7643 and RFC 7644).  That way, users removed from the
+//! identity provider are removed here right away, along with their console
+//! sessions and device tokens, rather than lingering until somebody notices.
+//! Users that the identity provider deactivates stay in place, but they can't
+//! log in until they're reactivated.
+//!
+//! The identity provider authenticates with a bearer token that a Silo
+//! administrator creates using the external API.  Each token is scoped to one
+//! Silo.  Requests made with these tokens are carried out with the same
+//! built-in "external authenticator" identity that we use to create users and
+//! groups during SAML login.
+
+use nexus_db_model::UserProvisionType;
+use nexus_db_queries::authz;
+use nexus_db_queries::authz::ApiResource;
+use nexus_db_queries::context::OpContext;
+use nexus_db_queries::db;
+use nexus_db_queries::db::identity::Asset;
+use nexus_db_queries::db::lookup;
+use nexus_db_queries::db::lookup::LookupPath;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::ResourceType;
+use omicron_common::api::external::UpdateResult;
+use std::num::NonZeroU32;
+use uuid::Uuid;
+
+/// Number of records to fetch from the database at a time when a SCIM client
+/// lists all of the users or groups in a Silo
+const SCIM_LIST_BATCH_SIZE: u32 = 100;
+
+impl super::Nexus {
+    // SCIM client bearer tokens (managed through the external API)
+
+    /// Create a bearer token for a Silo's SCIM client
+    pub(crate) async fn scim_client_bearer_token_create(
+        &self,
+        opctx: &OpContext,
+        silo_lookup: &lookup::Silo<'_>,
+    ) -> CreateResult<db::model::ScimClientBearerToken> {
+        let (authz_silo, db_silo) =
+            silo_lookup.fetch_for(authz::Action::Modify).await?;
+        if db_silo.user_provision_type != UserProvisionType::Scim {
+            return Err(Error::invalid_request(
+                "cannot create SCIM client bearer tokens in this kind of Silo",
+            ));
+        }
+
+        self.db_datastore
+            .scim_client_bearer_token_create(opctx, &authz_silo, None)
+            .await
+    }
+
+    /// List the bearer tokens for a Silo's SCIM clients
+    pub(crate) async fn scim_client_bearer_token_list(
+        &self,
+        opctx: &OpContext,
+        silo_lookup: &lookup::Silo<'_>,
+        pagparams: &DataPageParams<'_, Uuid>,
+    ) -> ListResultVec<db::model::ScimClientBearerToken> {
+        let (authz_silo,) =
+            silo_lookup.lookup_for(authz::Action::Modify).await?;
+        self.db_datastore
+            .scim_client_bearer_token_list(opctx, &authz_silo, pagparams)
+            .await
+    }
+
+    /// Revoke a bearer token for a Silo's SCIM client
+    pub(crate) async fn scim_client_bearer_token_delete(
+        &self,
+        opctx: &OpContext,
+        silo_lookup: &lookup::Silo<'_>,
+        token_id: Uuid,
+    ) -> DeleteResult {
+        let (authz_silo,) =
+            silo_lookup.lookup_for(authz::Action::Modify).await?;
+        self.db_datastore
+            .scim_client_bearer_token_delete(opctx, &authz_silo, token_id)
+            .await
+    }
+
+    // SCIM protocol operations
+
+    /// Authenticate a SCIM client based on the bearer token that it provided
+    ///
+    /// On success, returns the Silo whose users and groups the client may
+    /// manage, along with the context that should be used to manage them.
+    pub(crate) async fn scim_authenticate(
+        &self,
+        bearer_token: &str,
+    ) -> Result<(&OpContext, authz::Silo), Error> {
+        let opctx = self.opctx_external_authn();
+        let unauthenticated = |message: &str| Error::Unauthenticated {
+            internal_message: message.to_string(),
+        };
+
+        let token = self
+            .db_datastore
+            .scim_client_bearer_token_lookup(opctx, bearer_token)
+            .await?
+            .ok_or_else(|| unauthenticated("unknown SCIM bearer token"))?;
+        if token.is_expired(chrono::Utc::now()) {
+            return Err(unauthenticated("expired SCIM bearer token"));
+        }
+
+        let (authz_silo, db_silo) = LookupPath::new(opctx, &self.db_datastore)
+            .silo_id(token.silo_id)
+            .fetch()
+            .await
+            .map_err(|e| match e {
+                Error::ObjectNotFound { .. } => {
+                    unauthenticated("SCIM bearer token for deleted Silo")
+                }
+                e => e,
+            })?;
+
+        // Tokens can only be created in SCIM Silos and a Silo's provision type
+        // cannot be changed, but be defensive here anyway.
+        if db_silo.user_provision_type != UserProvisionType::Scim {
+            return Err(unauthenticated("SCIM bearer token for non-SCIM Silo"));
+        }
+
+        Ok((opctx, authz_silo))
+    }
+
+    /// List all of the users in a Silo, optionally filtered by user name
+    // TODO-scalability SCIM uses offset-based pagination, which doesn't map
+    // onto our marker-based pagination.  We load the whole list and let the
+    // caller slice it up.
+    pub(crate) async fn scim_user_list(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        user_name: Option<&str>,
+    ) -> ListResultVec<db::model::SiloUser> {
+        if let Some(user_name) = user_name {
+            return Ok(self
+                .db_datastore
+                .silo_user_fetch_by_external_id(opctx, authz_silo, user_name)
+                .await?
+                .into_iter()
+                .map(|(_, db_silo_user)| db_silo_user)
+                .collect());
+        }
+
+        let authz_silo_user_list = authz::SiloUserList::new(authz_silo.clone());
+        let mut users = Vec::new();
+        let mut marker = None;
+        loop {
+            let pagparams = scim_pagparams(marker.as_ref());
+            let batch = self
+                .db_datastore
+                .silo_users_list(opctx, &authz_silo_user_list, &pagparams)
+                .await?;
+            let done = batch.len() < SCIM_LIST_BATCH_SIZE as usize;
+            marker = batch.last().map(|u| u.id());
+            users.extend(batch);
+            if done {
+                return Ok(users);
+            }
+        }
+    }
+
+    /// Create a user in a Silo on behalf of its SCIM client
+    pub(crate) async fn scim_user_create(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        user_name: String,
+        active: bool,
+    ) -> CreateResult<db::model::SiloUser> {
+        if self
+            .db_datastore
+            .silo_user_fetch_by_external_id(opctx, authz_silo, &user_name)
+            .await?
+            .is_some()
+        {
+            return Err(Error::ObjectAlreadyExists {
+                type_name: ResourceType::SiloUser,
+                object_name: user_name,
+            });
+        }
+
+        let mut silo_user = db::model::SiloUser::new(
+            authz_silo.id(),
+            Uuid::new_v4(),
+            user_name,
+        );
+        silo_user.active = active;
+        let (_, db_silo_user) =
+            self.db_datastore.silo_user_create(authz_silo, silo_user).await?;
+        Ok(db_silo_user)
+    }
+
+    /// Fetch a user in a Silo on behalf of its SCIM client
+    pub(crate) async fn scim_user_fetch(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        silo_user_id: Uuid,
+    ) -> LookupResult<db::model::SiloUser> {
+        let (_, db_silo_user) = self
+            .silo_user_lookup_by_id(
+                opctx,
+                authz_silo,
+                silo_user_id,
+                authz::Action::Read,
+            )
+            .await?;
+        Ok(db_silo_user)
+    }
+
+    /// Deactivate (or reactivate) a user in a Silo on behalf of its SCIM
+    /// client
+    ///
+    /// A deactivated user cannot log in, and their console sessions and tokens
+    /// are revoked.  Unlike deleting the user, this keeps their id, group
+    /// memberships, and role assignments, so identity providers can reactivate
+    /// them later.
+    pub(crate) async fn scim_user_set_active(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        silo_user_id: Uuid,
+        active: bool,
+    ) -> UpdateResult<db::model::SiloUser> {
+        let (authz_silo_user, db_silo_user) = self
+            .silo_user_lookup_by_id(
+                opctx,
+                authz_silo,
+                silo_user_id,
+                authz::Action::Modify,
+            )
+            .await?;
+        if db_silo_user.active == active {
+            return Ok(db_silo_user);
+        }
+        self.db_datastore
+            .silo_user_set_active(opctx, &authz_silo_user, active)
+            .await
+    }
+
+    /// Deprovision a user in a Silo on behalf of its SCIM client
+    ///
+    /// This deletes the user along with their console sessions, device access
+    /// tokens, and group memberships.
+    pub(crate) async fn scim_user_delete(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        silo_user_id: Uuid,
+    ) -> DeleteResult {
+        let (authz_silo_user, _) = self
+            .silo_user_lookup_by_id(
+                opctx,
+                authz_silo,
+                silo_user_id,
+                authz::Action::Delete,
+            )
+            .await?;
+        self.db_datastore.silo_user_delete(opctx, &authz_silo_user).await
+    }
+
+    /// List all of the groups in a Silo, optionally filtered by display name
+    // TODO-scalability See `scim_user_list()`.
+    pub(crate) async fn scim_group_list(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        display_name: Option<&str>,
+    ) -> ListResultVec<db::model::SiloGroup> {
+        if let Some(display_name) = display_name {
+            return Ok(self
+                .db_datastore
+                .silo_group_optional_lookup(
+                    opctx,
+                    authz_silo,
+                    display_name.to_string(),
+                )
+                .await?
+                .into_iter()
+                .collect());
+        }
+
+        let mut groups = Vec::new();
+        let mut marker = None;
+        loop {
+            let pagparams = scim_pagparams(marker.as_ref());
+            let batch = self
+                .db_datastore
+                .silo_groups_list_by_id(opctx, authz_silo, &pagparams)
+                .await?;
+            let done = batch.len() < SCIM_LIST_BATCH_SIZE as usize;
+            marker = batch.last().map(|g| g.id());
+            groups.extend(batch);
+            if done {
+                return Ok(groups);
+            }
+        }
+    }
+
+    /// Create a group in a Silo on behalf of its SCIM client
+    pub(crate) async fn scim_group_create(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        display_name: String,
+        member_ids: &[Uuid],
+    ) -> CreateResult<db::model::SiloGroup> {
+        if self
+            .db_datastore
+            .silo_group_optional_lookup(opctx, authz_silo, display_name.clone())
+            .await?
+            .is_some()
+        {
+            return Err(Error::ObjectAlreadyExists {
+                type_name: ResourceType::SiloGroup,
+                object_name: display_name,
+            });
+        }
+
+        let db_silo_group = self
+            .db_datastore
+            .silo_group_ensure(
+                opctx,
+                authz_silo,
+                db::model::SiloGroup::new(
+                    Uuid::new_v4(),
+                    authz_silo.id(),
+                    display_name,
+                ),
+            )
+            .await?;
+
+        for silo_user_id in member_ids {
+            self.scim_group_membership_set(
+                opctx,
+                authz_silo,
+                db_silo_group.id(),
+                *silo_user_id,
+                true,
+            )
+            .await?;
+        }
+
+        Ok(db_silo_group)
+    }
+
+    /// Fetch a group in a Silo on behalf of its SCIM client
+    pub(crate) async fn scim_group_fetch(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        silo_group_id: Uuid,
+    ) -> LookupResult<(authz::SiloGroup, db::model::SiloGroup)> {
+        let (_, authz_silo_group, db_silo_group) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .silo_group_id(silo_group_id)
+                .fetch()
+                .await?;
+        if db_silo_group.silo_id != authz_silo.id() {
+            return Err(authz_silo_group.not_found());
+        }
+        Ok((authz_silo_group, db_silo_group))
+    }
+
+    /// List the members of a Silo group on behalf of its SCIM client
+    pub(crate) async fn scim_group_members(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        authz_silo_group: &authz::SiloGroup,
+    ) -> ListResultVec<db::model::SiloUser> {
+        let authz_silo_user_list = authz::SiloUserList::new(authz_silo.clone());
+        let mut users = Vec::new();
+        let mut marker = None;
+        loop {
+            let pagparams = scim_pagparams(marker.as_ref());
+            let batch = self
+                .db_datastore
+                .silo_group_users_list(
+                    opctx,
+                    &authz_silo_user_list,
+                    &pagparams,
+                    authz_silo_group,
+                )
+                .await?;
+            let done = batch.len() < SCIM_LIST_BATCH_SIZE as usize;
+            marker = batch.last().map(|u| u.id());
+            users.extend(batch);
+            if done {
+                return Ok(users);
+            }
+        }
+    }
+
+    /// Delete a group in a Silo on behalf of its SCIM client
+    ///
+    /// Unlike the SAML login path, SCIM clients expect to be able to delete
+    /// groups that still have members, so we remove the memberships first.
+    pub(crate) async fn scim_group_delete(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        silo_group_id: Uuid,
+    ) -> DeleteResult {
+        let (authz_silo_group, _) =
+            self.scim_group_fetch(opctx, authz_silo, silo_group_id).await?;
+        let members = self
+            .scim_group_members(opctx, authz_silo, &authz_silo_group)
+            .await?;
+        for member in members {
+            self.scim_group_membership_set(
+                opctx,
+                authz_silo,
+                silo_group_id,
+                member.id(),
+                false,
+            )
+            .await?;
+        }
+        self.db_datastore.silo_group_delete(opctx, &authz_silo_group).await
+    }
+
+    /// Add a user to (or remove them from) a Silo group on behalf of the
+    /// Silo's SCIM client
+    pub(crate) async fn scim_group_membership_set(
+        &self,
+        opctx: &OpContext,
+        authz_silo: &authz::Silo,
+        silo_group_id: Uuid,
+        silo_user_id: Uuid,
+        is_member: bool,
+    ) -> UpdateResult<()> {
+        let (authz_silo_user, _) = self
+            .silo_user_lookup_by_id(
+                opctx,
+                authz_silo,
+                silo_user_id,
+                authz::Action::Modify,
+            )
+            .await?;
+
+        // Only this one membership is changed, in a single statement, so
+        // concurrent PATCH requests for the same user can't undo each other.
+        self.db_datastore
+            .silo_group_membership_set_for_user(
+                opctx,
+                &authz_silo_user,
+                silo_group_id,
+                is_member,
+            )
+            .await
+    }
+}
+
+/// Returns the parameters for fetching the next batch of records when listing
+/// everything on behalf of a SCIM client
+fn scim_pagparams(marker: Option<&Uuid>) -> DataPageParams<'_, Uuid> {
+    DataPageParams {
+        marker,
+        direction: dropshot::PaginationOrder::Ascending,
+        limit: NonZeroU32::new(SCIM_LIST_BATCH_SIZE).unwrap(),
+    }
+}
@@ -0,0 +1,218 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Service accounts
+//!
+//! A service account is an identity within a Silo for automation (CI
+//! pipelines, controllers, and the like) rather than for a person.  It has no
+//! credentials other than the tokens a Silo administrator creates for it, and
+//! it has no access except what it's granted through role assignments.
+
+use crate::external_api::params;
+use anyhow::anyhow;
+use chrono::Utc;
+use nexus_db_queries::authn::{Actor, Reason, Scope};
+use nexus_db_queries::authz;
+use nexus_db_queries::context::OpContext;
+use nexus_db_queries::db::lookup;
+use nexus_db_queries::db::lookup::LookupPath;
+use nexus_db_queries::db::model::ServiceAccount;
+use nexus_db_queries::db::model::ServiceAccountToken;
+use omicron_common::api::external::http_pagination::PaginatedBy;
+use omicron_common::api::external::CreateResult;
+use omicron_common::api::external::DataPageParams;
+use omicron_common::api::external::DeleteResult;
+use omicron_common::api::external::Error;
+use omicron_common::api::external::InternalContext;
+use omicron_common::api::external::ListResultVec;
+use omicron_common::api::external::LookupResult;
+use omicron_common::api::external::NameOrId;
+use uuid::Uuid;
+
+impl super::Nexus {
+    pub fn service_account_lookup<'a>(
+        &'a self,
+        opctx: &'a OpContext,
+        service_account: NameOrId,
+    ) -> LookupResult<lookup::ServiceAccount<'a>> {
+        let lookup_path = LookupPath::new(opctx, &self.db_datastore);
+        Ok(match service_account {
+            NameOrId::Id(id) => lookup_path.service_account_id(id),
+            NameOrId::Name(name) => {
+                lookup_path.service_account_name_owned(name.into())
+            }
+        })
+    }
+
+    fn service_account_list_authz(
+        &self,
+        opctx: &OpContext,
+    ) -> LookupResult<authz::ServiceAccountList> {
+        let authz_silo = opctx
+            .authn
+            .silo_required()
+            .internal_context("listing service accounts")?;
+        Ok(authz::ServiceAccountList::new(authz_silo))
+    }
+
+    pub(crate) async fn service_account_create(
+        &self,
+        opctx: &OpContext,
+        params: params::ServiceAccountCreate,
+    ) -> CreateResult<ServiceAccount> {
+        let authz_list = self.service_account_list_authz(opctx)?;
+        let service_account =
+            ServiceAccount::new(authz_list.silo().id(), params);
+        self.db_datastore
+            .service_account_create(opctx, &authz_list, service_account)
+            .await
+    }
+
+    pub(crate) async fn service_accounts_list(
+        &self,
+        opctx: &OpContext,
+        pagparams: &PaginatedBy<'_>,
+    ) -> ListResultVec<ServiceAccount> {
+        let authz_list = self.service_account_list_authz(opctx)?;
+        self.db_datastore
+            .service_accounts_list(opctx, &authz_list, pagparams)
+            .await
+    }
+
+    pub(crate) async fn service_account_delete(
+        &self,
+        opctx: &OpContext,
+        service_account_lookup: &lookup::ServiceAccount<'_>,
+    ) -> DeleteResult {
+        let (.., authz_service_account) =
+            service_account_lookup.lookup_for(authz::Action::Delete).await?;
+        self.db_datastore
+            .service_account_delete(opctx, &authz_service_account)
+            .await
+    }
+
+    pub(crate) async fn service_account_token_create(
+        &self,
+        opctx: &OpContext,
+        service_account_lookup: &lookup::ServiceAccount<'_>,
+        params: params::ServiceAccountTokenCreate,
+    ) -> CreateResult<ServiceAccountToken> {
+        if let Some(time_expires) = params.time_expires {
+            if time_expires <= Utc::now() {
+                return Err(Error::invalid_value(
+                    "time_expires",
+                    "expiration time must be in the future",
+                ));
+            }
+        }
+
+        let (.., authz_service_account) = service_account_lookup
+            .lookup_for(authz::Action::CreateChild)
+            .await?;
+        let token = ServiceAccountToken::new(
+            authz_service_account.id(),
+            params.time_expires,
+        );
+        self.db_datastore
+            .service_account_token_create(opctx, &authz_service_account, token)
+            .await
+    }
+
+    pub(crate) async fn service_account_tokens_list(
+        &self,
+        opctx: &OpContext,
+        service_account_lookup: &lookup::ServiceAccount<'_>,
+        pagparams: &DataPageParams<'_, Uuid>,
+    ) -> ListResultVec<ServiceAccountToken> {
+        let (.., authz_service_account) = service_account_lookup
+            .lookup_for(authz::Action::ListChildren)
+            .await?;
+        self.db_datastore
+            .service_account_tokens_list(
+                opctx,
+                &authz_service_account,
+                pagparams,
+            )
+            .await
+    }
+
+    pub(crate) async fn service_account_token_delete(
+        &self,
+        opctx: &OpContext,
+        service_account_lookup: &lookup::ServiceAccount<'_>,
+        token_id: Uuid,
+    ) -> DeleteResult {
+        let (.., authz_service_account) =
+            service_account_lookup.lookup_for(authz::Action::Modify).await?;
+        self.db_datastore
+            .service_account_token_delete(
+                opctx,
+                &authz_service_account,
+                token_id,
+            )
+            .await
+    }
+
+    /// Look up the actor for a service account token presented as a bearer
+    /// token, recording that the token was used.
+    ///
+    /// Service account tokens are never scoped: a service account's access is
+    /// already limited to what it's been explicitly granted.
+    pub(crate) async fn service_account_token_actor(
+        &self,
+        opctx: &OpContext,
+        token: String,
+    ) -> Result<(Actor, Scope), Reason> {
+        let db_token = self
+            .db_datastore
+            .service_account_token_fetch_for_authn(opctx, &token)
+            .await
+            .map_err(|e| match e {
+                Error::ObjectNotFound { .. } => Reason::UnknownActor {
+                    actor: "from service account token".to_string(),
+                },
+                e => Reason::UnknownError { source: e },
+            })?;
+
+        let service_account_id = db_token.service_account_id;
+        let (.., db_service_account) =
+            LookupPath::new(opctx, &self.db_datastore)
+                .service_account_id(service_account_id)
+                .fetch()
+                .await
+                .map_err(|e| match e {
+                    Error::ObjectNotFound { .. } => Reason::UnknownActor {
+                        actor: service_account_id.to_string(),
+                    },
+                    e => Reason::UnknownError { source: e },
+                })?;
+        let actor = Actor::ServiceAccount {
+            service_account_id,
+            silo_id: db_service_account.silo_id,
+        };
+
+        let now = Utc::now();
+        if db_token.is_expired(now) {
+            return Err(Reason::BadCredentials {
+                actor,
+                source: anyhow!("service account token has expired"),
+            });
+        }
+
+        if let Err(error) = self
+            .db_datastore
+            .service_account_token_touch(opctx, db_token.id(), now)
+            .await
+        {
+            warn!(
+                opctx.log,
+                "failed to update service account token last-used time";
+                "token_id" => %db_token.id(),
+                "error" => #%error,
+            );
+        }
+
+        Ok((actor, Scope::default()))
+    }
+}
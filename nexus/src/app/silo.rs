@@ -230,7 +230,7 @@ impl super::Nexus {
     ///
     /// `LookupPath` lets you look up users directly, regardless of what Silo
     /// they're in.  This helper validates that they're in the expected Silo.
-    async fn silo_user_lookup_by_id(
+    pub(crate) async fn silo_user_lookup_by_id(
         &self,
         opctx: &OpContext,
         authz_silo: &authz::Silo,
@@ -385,15 +385,23 @@ impl super::Nexus {
 
         let (authz_silo_user, db_silo_user) =
             if let Some(existing_silo_user) = fetch_result {
+                // Users that the identity provider has deactivated using SCIM
+                // may not log in until it reactivates them.
+                if !existing_silo_user.1.active {
+                    return Ok(None);
+                }
                 existing_silo_user
             } else {
                 // In this branch, no user exists for the authenticated subject
                 // external id. The next action depends on the silo's user
                 // provision type.
                 match db_silo.user_provision_type {
-                    // If the user provision type is ApiOnly, do not create a
-                    // new user if one does not exist.
-                    db::model::UserProvisionType::ApiOnly => {
+                    // If the user provision type is ApiOnly or Scim, do not
+                    // create a new user if one does not exist.  In the Scim
+                    // case, the identity provider is responsible for creating
+                    // users ahead of time.
+                    db::model::UserProvisionType::ApiOnly
+                    | db::model::UserProvisionType::Scim => {
                         return Ok(None);
                     }
 
@@ -413,6 +421,13 @@ impl super::Nexus {
                 }
             };
 
+        // In Scim Silos, the identity provider manages group memberships
+        // using SCIM.  Those take precedence over whatever groups it sent us
+        // during login.
+        if db_silo.user_provision_type == db::model::UserProvisionType::Scim {
+            return Ok(Some(db_silo_user));
+        }
+
         // Gather a list of groups that the user is part of based on what the
         // IdP sent us. Also, if the silo user provision type is Jit, create
         // silo groups if new groups from the IdP are seen.
@@ -422,7 +437,8 @@ impl super::Nexus {
 
         for group in &authenticated_subject.groups {
             let silo_group = match db_silo.user_provision_type {
-                db::model::UserProvisionType::ApiOnly => {
+                db::model::UserProvisionType::ApiOnly
+                | db::model::UserProvisionType::Scim => {
                     self.db_datastore
                         .silo_group_optional_lookup(
                             opctx,
@@ -714,7 +730,10 @@ impl super::Nexus {
         let (authz_silo, db_silo) = silo_lookup.fetch().await?;
         let authz_idp_list = authz::SiloIdentityProviderList::new(authz_silo);
 
-        if db_silo.user_provision_type != UserProvisionType::Jit {
+        if !matches!(
+            db_silo.user_provision_type,
+            UserProvisionType::Jit | UserProvisionType::Scim
+        ) {
             return Err(Error::invalid_request(
                 "cannot create identity providers in this kind of Silo",
             ));
@@ -4,15 +4,22 @@
 
 //! Sleds, and the hardware and services within them.
 
+use crate::app::update::GatewayClientError;
+use crate::app::update::MgsClients;
 use crate::external_api::params;
 use crate::internal_api::params::{
     PhysicalDiskPutRequest, SledAgentInfo, SledRole, ZpoolPutRequest,
 };
+use gateway_client::types::IgnitionCommand;
+use gateway_client::types::SpType;
+use internal_dns::ServiceName;
 use nexus_db_queries::authz;
 use nexus_db_queries::context::OpContext;
 use nexus_db_queries::db;
+use nexus_db_queries::db::identity::Asset;
 use nexus_db_queries::db::lookup;
 use nexus_db_queries::db::model::DatasetKind;
+use nexus_types::deployment::BlueprintZoneFilter;
 use nexus_types::deployment::SledFilter;
 use nexus_types::external_api::views::SledPolicy;
 use nexus_types::external_api::views::SledProvisionPolicy;
@@ -20,7 +27,10 @@ use omicron_common::api::external::DataPageParams;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::ListResultVec;
 use omicron_common::api::external::LookupResult;
+use omicron_uuid_kinds::GenericUuid;
+use omicron_uuid_kinds::SledUuid;
 use sled_agent_client::Client as SledAgentClient;
+use slog_error_chain::InlineErrorChain;
 use std::net::SocketAddrV6;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -183,6 +193,194 @@ impl super::Nexus {
             .await
     }
 
+    // Sled power control
+
+    /// Power cycle a sled by asking its SP's ignition controller to reset its
+    /// power
+    pub(crate) async fn sled_power_cycle(
+        &self,
+        opctx: &OpContext,
+        sled_lookup: &lookup::Sled<'_>,
+    ) -> Result<(), Error> {
+        let (authz_sled, sled) =
+            sled_lookup.fetch_for(authz::Action::Modify).await?;
+        let (sp_type, sp_slot) = self.sled_sp_lookup(opctx, &sled).await?;
+        info!(
+            opctx.log, "power cycling sled";
+            "sled_id" => %authz_sled.id(),
+            "sp_type" => ?sp_type,
+            "sp_slot" => sp_slot,
+        );
+        self.sled_ignition_command(
+            opctx,
+            sp_type,
+            sp_slot,
+            IgnitionCommand::PowerReset,
+        )
+        .await
+    }
+
+    /// Power off a sled through its SP's ignition controller
+    ///
+    /// Unless `force` is set, this refuses to power off a sled on which the
+    /// current target blueprint still has control plane zones that have not
+    /// been expunged.
+    pub(crate) async fn sled_power_off(
+        &self,
+        opctx: &OpContext,
+        sled_lookup: &lookup::Sled<'_>,
+        force: bool,
+    ) -> Result<(), Error> {
+        let (authz_sled, sled) =
+            sled_lookup.fetch_for(authz::Action::Modify).await?;
+
+        if !force {
+            let sled_id = SledUuid::from_untyped_uuid(authz_sled.id());
+            let (_, blueprint) = self
+                .db_datastore
+                .blueprint_target_get_current_full(opctx)
+                .await?;
+            let nzones = blueprint
+                .all_omicron_zones(
+                    BlueprintZoneFilter::ShouldPreventSledPowerOff,
+                )
+                .filter(|(zone_sled_id, _)| *zone_sled_id == sled_id)
+                .count();
+            if nzones > 0 {
+                return Err(Error::conflict(format!(
+                    "sled {} still has {} control plane zone(s) that have \
+                     not been expunged (use \"force\" to power it off anyway)",
+                    sled_id, nzones,
+                )));
+            }
+        }
+
+        let (sp_type, sp_slot) = self.sled_sp_lookup(opctx, &sled).await?;
+        info!(
+            opctx.log, "powering off sled";
+            "sled_id" => %authz_sled.id(),
+            "sp_type" => ?sp_type,
+            "sp_slot" => sp_slot,
+            "force" => force,
+        );
+        self.sled_ignition_command(
+            opctx,
+            sp_type,
+            sp_slot,
+            IgnitionCommand::PowerOff,
+        )
+        .await
+    }
+
+    /// Reset the service processor of a sled
+    ///
+    /// This does not affect the power state of the sled itself.
+    pub(crate) async fn sled_reset_sp(
+        &self,
+        opctx: &OpContext,
+        sled_lookup: &lookup::Sled<'_>,
+    ) -> Result<(), Error> {
+        let (authz_sled, sled) =
+            sled_lookup.fetch_for(authz::Action::Modify).await?;
+        let (sp_type, sp_slot) = self.sled_sp_lookup(opctx, &sled).await?;
+        info!(
+            opctx.log, "resetting sled SP";
+            "sled_id" => %authz_sled.id(),
+            "sp_type" => ?sp_type,
+            "sp_slot" => sp_slot,
+        );
+        let mut mgs_clients = self.mgs_clients().await?;
+        mgs_clients
+            .try_all_serially(&opctx.log, |client| async move {
+                client
+                    .sp_component_reset(
+                        sp_type,
+                        sp_slot,
+                        gateway_client::SpComponent::SP_ITSELF.const_as_str(),
+                    )
+                    .await
+            })
+            .await
+            .map_err(|error| mgs_error("reset SP", error))?;
+        Ok(())
+    }
+
+    /// Finds the SP that manages a sled, based on the sled's baseboard in the
+    /// latest inventory collection
+    async fn sled_sp_lookup(
+        &self,
+        opctx: &OpContext,
+        sled: &db::model::Sled,
+    ) -> Result<(SpType, u32), Error> {
+        let collection = self
+            .db_datastore
+            .inventory_get_latest_collection(opctx)
+            .await?
+            .ok_or_else(|| {
+                Error::unavail("no inventory collection is available yet")
+            })?;
+        let sp = collection
+            .sps
+            .iter()
+            .find(|(baseboard_id, _)| {
+                baseboard_id.serial_number == sled.serial_number()
+                    && baseboard_id.part_number == sled.part_number()
+            })
+            .map(|(_, sp)| sp)
+            .ok_or_else(|| {
+                Error::unavail(&format!(
+                    "no SP found in the latest inventory collection for \
+                     sled {} (serial {:?}, part {:?})",
+                    sled.id(),
+                    sled.serial_number(),
+                    sled.part_number(),
+                ))
+            })?;
+        Ok((sp.sp_type, u32::from(sp.sp_slot)))
+    }
+
+    async fn sled_ignition_command(
+        &self,
+        opctx: &OpContext,
+        sp_type: SpType,
+        sp_slot: u32,
+        command: IgnitionCommand,
+    ) -> Result<(), Error> {
+        let mut mgs_clients = self.mgs_clients().await?;
+        mgs_clients
+            .try_all_serially(&opctx.log, |client| async move {
+                client.ignition_command(sp_type, sp_slot, command).await
+            })
+            .await
+            .map_err(|error| mgs_error("send ignition command", error))?;
+        Ok(())
+    }
+
+    async fn mgs_clients(&self) -> Result<MgsClients, Error> {
+        let clients = self
+            .resolver()
+            .await
+            .lookup_all_socket_v6(ServiceName::ManagementGatewayService)
+            .await
+            .map_err(|error| {
+                Error::unavail(&format!(
+                    "failed to look up MGS addresses: {}",
+                    InlineErrorChain::new(&error)
+                ))
+            })?
+            .into_iter()
+            .map(|sockaddr| {
+                let url = format!("http://{}", sockaddr);
+                let log = self.log.new(o!("gateway_url" => url.clone()));
+                gateway_client::Client::new(&url, log)
+            })
+            .collect::<Vec<_>>();
+        if clients.is_empty() {
+            return Err(Error::unavail("no MGS instances found"));
+        }
+        Ok(MgsClients::from_clients(clients))
+    }
+
     // Physical disks
 
     pub async fn physical_disk_lookup<'a>(
@@ -301,3 +499,26 @@ impl super::Nexus {
         .await
     }
 }
+
+/// Converts an error from MGS into an external error
+///
+/// Client errors reported by MGS (e.g., an SP that isn't present) are passed
+/// through as such; anything else is an internal error.
+fn mgs_error(what: &str, error: GatewayClientError) -> Error {
+    match error {
+        gateway_client::Error::ErrorResponse(rv)
+            if rv.status() == http::StatusCode::SERVICE_UNAVAILABLE =>
+        {
+            Error::unavail(&format!("failed to {what}: {}", rv.message))
+        }
+        gateway_client::Error::ErrorResponse(rv)
+            if rv.status().is_client_error() =>
+        {
+            Error::invalid_request(format!("failed to {what}: {}", rv.message))
+        }
+        error => Error::internal_error(&format!(
+            "failed to {what}: {}",
+            InlineErrorChain::new(&error)
+        )),
+    }
+}
@@ -10,7 +10,7 @@ use slog::Logger;
 use std::collections::VecDeque;
 use std::sync::Arc;
 
-pub(super) type GatewayClientError =
+pub(crate) type GatewayClientError =
     gateway_client::Error<gateway_client::types::Error>;
 
 #[derive(Debug, Clone)]
@@ -51,7 +51,7 @@ impl MgsClients {
     ///
     /// On a successful return, the internal client list will be reordered so
     /// any future accesses will attempt the most-recently-successful client.
-    pub(super) async fn try_all_serially<T, F, Fut>(
+    pub(crate) async fn try_all_serially<T, F, Fut>(
         &mut self,
         log: &Logger,
         op: F,
@@ -7,26 +7,34 @@
 use bytes::Bytes;
 use dropshot::HttpError;
 use futures::Stream;
+use nexus_db_model::TargetReleaseArtifact;
 use nexus_db_model::TufRepoDescription;
 use nexus_db_queries::authz;
 use nexus_db_queries::context::OpContext;
+use nexus_types::external_api::{params, views};
 use omicron_common::api::external::{
     Error, SemverVersion, TufRepoInsertResponse,
 };
 use omicron_common::update::ArtifactId;
+use slog_error_chain::InlineErrorChain;
 use update_common::artifacts::ArtifactsWithPlan;
+use update_common::artifacts::RepoArchive;
 
 mod common_sp_update;
 mod host_phase1_updater;
 mod mgs_clients;
 mod rot_updater;
 mod sp_updater;
+mod uploaded_repos;
 
 pub use common_sp_update::SpComponentUpdateError;
 pub use host_phase1_updater::HostPhase1Updater;
+pub(crate) use mgs_clients::GatewayClientError;
 pub use mgs_clients::MgsClients;
 pub use rot_updater::RotUpdater;
 pub use sp_updater::SpUpdater;
+pub use uploaded_repos::target_release_from_plan;
+pub use uploaded_repos::UploadedRepos;
 
 #[derive(Debug, PartialEq, Clone)]
 pub enum UpdateProgress {
@@ -52,10 +60,17 @@ impl super::Nexus {
                 Error::internal_error("updates system not initialized")
             })?;
 
-        let artifacts_with_plan =
-            ArtifactsWithPlan::from_stream(body, Some(file_name), &self.log)
-                .await
-                .map_err(|error| error.to_http_error())?;
+        let artifacts_with_plan = ArtifactsWithPlan::from_stream(
+            body,
+            Some(file_name),
+            // Keep the archive so that the repository can be restored if this
+            // Nexus restarts.
+            RepoArchive::Keep,
+            &self.artifact_store,
+            &self.log,
+        )
+        .await
+        .map_err(|error| error.to_http_error())?;
 
         // Now store the artifacts in the database.
         let tuf_repo_description = TufRepoDescription::from_external(
@@ -67,9 +82,75 @@ impl super::Nexus {
             .update_tuf_repo_insert(opctx, tuf_repo_description)
             .await
             .map_err(HttpError::from)?;
+
+        // Keep the artifacts around so that they can be delivered to the
+        // system if this release becomes the target release.  This may replace
+        // a repository with the same system version, whose artifacts are then
+        // no longer needed.
+        self.uploaded_repos.insert(artifacts_with_plan);
+        self.artifact_store_gc();
+
         Ok(response.into_external())
     }
 
+    /// Restores the contents of the repositories uploaded to this Nexus
+    /// before it last started from the archives kept in the artifact store
+    ///
+    /// This unpacks every archive again, so it can take a while.
+    pub(crate) async fn uploaded_repos_restore(&self) {
+        let log = self.log.new(o!("component" => "UploadedReposRestore"));
+        for (repo_hash, contents) in self.artifact_store.repos() {
+            if contents.archive.is_none() {
+                continue;
+            }
+            match ArtifactsWithPlan::from_store(
+                repo_hash,
+                None,
+                &self.artifact_store,
+                &log,
+            )
+            .await
+            {
+                Ok(artifacts_with_plan) => {
+                    let system_version =
+                        artifacts_with_plan.plan().system_version.clone();
+                    let restored = self
+                        .uploaded_repos
+                        .insert_restored(artifacts_with_plan);
+                    info!(
+                        log,
+                        "restored uploaded repository";
+                        "repo_hash" => %repo_hash,
+                        "system_version" => %system_version,
+                        "superseded" => !restored,
+                    );
+                }
+                Err(error) => {
+                    warn!(
+                        log,
+                        "failed to restore uploaded repository";
+                        "repo_hash" => %repo_hash,
+                        InlineErrorChain::new(&error),
+                    );
+                }
+            }
+        }
+
+        self.uploaded_repos.finish_restore();
+        self.artifact_store_gc();
+    }
+
+    /// Forgets the repositories recorded in the artifact store that aren't
+    /// among the uploaded repositories, removing the artifacts that only they
+    /// referred to
+    fn artifact_store_gc(&self) {
+        // Until the repositories from before this Nexus started have been
+        // restored, we can't tell which records are still in use.
+        if let Some(live) = self.uploaded_repos.repo_hashes() {
+            self.artifact_store.retain_repos(|hash| live.contains(hash));
+        }
+    }
+
     pub(crate) async fn updates_get_repository(
         &self,
         opctx: &OpContext,
@@ -91,6 +172,60 @@ impl super::Nexus {
         Ok(tuf_repo_description)
     }
 
+    pub(crate) async fn target_release_view(
+        &self,
+        opctx: &OpContext,
+    ) -> Result<views::TargetRelease, Error> {
+        let release =
+            self.db_datastore.target_release_get_current(opctx).await?;
+        Ok(release.into())
+    }
+
+    /// Sets the release that the system should be running
+    ///
+    /// The release's TUF repository must have been uploaded to this Nexus.
+    /// The artifacts from it that are delivered through MGS are recorded in
+    /// the database along with the new target release so that every Nexus
+    /// can plan their delivery.
+    pub(crate) async fn target_release_update(
+        &self,
+        opctx: &OpContext,
+        params: params::SetTargetReleaseParams,
+    ) -> Result<views::TargetRelease, Error> {
+        opctx.authorize(authz::Action::Modify, &authz::FLEET).await?;
+
+        let system_version = params.system_version;
+
+        // Make sure the repository exists before pointing the system at it.
+        // It's not enough that its description is in the database: this
+        // Nexus needs its contents in order to deliver its artifacts.
+        self.db_datastore
+            .update_tuf_repo_get(opctx, system_version.clone().into())
+            .await?;
+        let Some(repo) = self.uploaded_repos.get(&system_version) else {
+            return Err(Error::invalid_request(format!(
+                "contents of TUF repository for system version \
+                 {system_version} are not available (the repository must be \
+                 uploaded again)"
+            )));
+        };
+        let target_release = target_release_from_plan(&opctx.log, repo.plan());
+
+        let current =
+            self.db_datastore.target_release_get_current(opctx).await?;
+        let next = current.new_next(Some(system_version.into()));
+        let artifacts = target_release
+            .artifacts
+            .iter()
+            .map(|a| TargetReleaseArtifact::new(next.generation, a))
+            .collect();
+        let release = self
+            .db_datastore
+            .target_release_insert(opctx, next, artifacts)
+            .await?;
+        Ok(release.into())
+    }
+
     /// Downloads a file (currently not implemented).
     pub(crate) async fn updates_download_artifact(
         &self,
@@ -0,0 +1,201 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Contents of TUF repositories uploaded to this Nexus
+
+use nexus_types::deployment::TargetRelease;
+use nexus_types::deployment::TargetReleaseArtifact;
+use nexus_types::deployment::TargetReleaseArtifactKind;
+use nexus_types::inventory::RotSlot;
+use nexus_types::inventory::SpType;
+use omicron_common::api::external::SemverVersion;
+use omicron_common::update::ArtifactHash;
+use slog::warn;
+use slog::Logger;
+use std::collections::btree_map::Entry;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::sync::Mutex;
+use update_common::artifacts::ArtifactIdData;
+use update_common::artifacts::ArtifactsWithPlan;
+use update_common::artifacts::ExtractedArtifactDataHandle;
+use update_common::artifacts::UpdatePlan;
+
+/// Keeps the contents of the TUF repositories uploaded to this Nexus so that
+/// their artifacts can be delivered to the rest of the system
+///
+/// The descriptions of uploaded repositories are stored in the database, but
+/// their contents are only kept here and in this Nexus's artifact store, from
+/// which they're restored when Nexus restarts.  Other Nexus instances can't
+/// see them.
+#[derive(Clone, Debug, Default)]
+pub struct UploadedRepos {
+    inner: Arc<Mutex<UploadedReposInner>>,
+}
+
+#[derive(Debug, Default)]
+struct UploadedReposInner {
+    repos: BTreeMap<SemverVersion, Arc<ArtifactsWithPlan>>,
+    // Whether the repositories uploaded before this Nexus started have been
+    // restored from the artifact store
+    restored: bool,
+}
+
+impl UploadedRepos {
+    pub fn new() -> UploadedRepos {
+        UploadedRepos::default()
+    }
+
+    /// Stores the contents of an uploaded repository, replacing any
+    /// previously-uploaded repository with the same system version
+    pub fn insert(&self, artifacts: ArtifactsWithPlan) {
+        let system_version = artifacts.plan().system_version.clone();
+        self.inner
+            .lock()
+            .unwrap()
+            .repos
+            .insert(system_version, Arc::new(artifacts));
+    }
+
+    /// Stores the contents of a repository restored from the artifact store,
+    /// unless a repository with the same system version has been uploaded
+    /// since this Nexus started
+    ///
+    /// Returns whether the repository was stored.
+    pub fn insert_restored(&self, artifacts: ArtifactsWithPlan) -> bool {
+        let system_version = artifacts.plan().system_version.clone();
+        let mut inner = self.inner.lock().unwrap();
+        match inner.repos.entry(system_version) {
+            Entry::Vacant(entry) => {
+                entry.insert(Arc::new(artifacts));
+                true
+            }
+            Entry::Occupied(_) => false,
+        }
+    }
+
+    /// Records that every repository that could be restored from the
+    /// artifact store has been
+    pub fn finish_restore(&self) {
+        self.inner.lock().unwrap().restored = true;
+    }
+
+    /// Returns the hashes of the repositories stored here, or `None` if
+    /// repositories are still being restored from the artifact store (in
+    /// which case we can't yet tell which ones are in use)
+    pub fn repo_hashes(&self) -> Option<BTreeSet<ArtifactHash>> {
+        let inner = self.inner.lock().unwrap();
+        inner.restored.then(|| {
+            inner
+                .repos
+                .values()
+                .map(|repo| repo.description().repo.hash)
+                .collect()
+        })
+    }
+
+    /// Returns the contents of the repository with the given system version,
+    /// if it was uploaded to this Nexus
+    pub fn get(
+        &self,
+        system_version: &SemverVersion,
+    ) -> Option<Arc<ArtifactsWithPlan>> {
+        self.inner.lock().unwrap().repos.get(system_version).cloned()
+    }
+
+    /// Returns the artifact with the given hash that's delivered through MGS
+    /// (an SP, RoT, or host phase 1 image) from any uploaded repository
+    pub fn mgs_artifact(
+        &self,
+        hash: &ArtifactHash,
+    ) -> Option<ExtractedArtifactDataHandle> {
+        let inner = self.inner.lock().unwrap();
+        inner.repos.values().find_map(|repo| {
+            mgs_artifacts(repo.plan())
+                .find(|artifact| artifact.data.hash() == *hash)
+                .map(|artifact| artifact.data.clone())
+        })
+    }
+}
+
+/// Iterates over the artifacts in `plan` that are delivered through MGS
+fn mgs_artifacts(plan: &UpdatePlan) -> impl Iterator<Item = &ArtifactIdData> {
+    plan.gimlet_sp
+        .values()
+        .chain(plan.psc_sp.values())
+        .chain(plan.sidecar_sp.values())
+        .chain(plan.gimlet_rot_a.iter())
+        .chain(plan.gimlet_rot_b.iter())
+        .chain(plan.psc_rot_a.iter())
+        .chain(plan.psc_rot_b.iter())
+        .chain(plan.sidecar_rot_a.iter())
+        .chain(plan.sidecar_rot_b.iter())
+        .chain(std::iter::once(&plan.host_phase_1))
+}
+
+/// Describes the artifacts in `plan` that the planner can ask to be
+/// delivered through MGS
+pub fn target_release_from_plan(
+    log: &Logger,
+    plan: &UpdatePlan,
+) -> TargetRelease {
+    let mut artifacts = Vec::new();
+
+    for (sp_type, images) in [
+        (SpType::Sled, &plan.gimlet_sp),
+        (SpType::Power, &plan.psc_sp),
+        (SpType::Switch, &plan.sidecar_sp),
+    ] {
+        for (board, image) in images {
+            artifacts.push(TargetReleaseArtifact {
+                kind: TargetReleaseArtifactKind::Sp { board: board.0.clone() },
+                sp_type,
+                version: image.id.version.clone(),
+                hash: image.data.hash(),
+            });
+        }
+    }
+
+    for (sp_type, slot, images) in [
+        (SpType::Sled, RotSlot::A, &plan.gimlet_rot_a),
+        (SpType::Sled, RotSlot::B, &plan.gimlet_rot_b),
+        (SpType::Power, RotSlot::A, &plan.psc_rot_a),
+        (SpType::Power, RotSlot::B, &plan.psc_rot_b),
+        (SpType::Switch, RotSlot::A, &plan.sidecar_rot_a),
+        (SpType::Switch, RotSlot::B, &plan.sidecar_rot_b),
+    ] {
+        // A repository may carry several RoT images for the same slot, signed
+        // with different keys.  Picking the right one requires knowing which
+        // keys each RoT accepts, which we don't track yet.  Until then, only
+        // update RoTs when there's no choice to make.
+        match images.as_slice() {
+            [image] => artifacts.push(TargetReleaseArtifact {
+                kind: TargetReleaseArtifactKind::Rot { slot },
+                sp_type,
+                version: image.id.version.clone(),
+                hash: image.data.hash(),
+            }),
+            _ => {
+                warn!(
+                    log,
+                    "not including RoT images in target release";
+                    "system_version" => %plan.system_version,
+                    "sp_type" => ?sp_type,
+                    "slot" => ?slot,
+                    "nimages" => images.len(),
+                );
+            }
+        }
+    }
+
+    artifacts.push(TargetReleaseArtifact {
+        kind: TargetReleaseArtifactKind::HostPhase1,
+        sp_type: SpType::Sled,
+        version: plan.host_phase_1.id.version.clone(),
+        hash: plan.host_phase_1.data.hash(),
+    });
+
+    TargetRelease { system_version: plan.system_version.clone(), artifacts }
+}
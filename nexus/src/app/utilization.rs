@@ -11,6 +11,7 @@ use nexus_db_queries::authz;
 use nexus_db_queries::context::OpContext;
 use nexus_db_queries::db;
 use nexus_db_queries::db::lookup;
+use nexus_types::external_api::views;
 use omicron_common::api::external::http_pagination::PaginatedBy;
 use omicron_common::api::external::Error;
 use omicron_common::api::external::ListResultVec;
@@ -26,6 +27,47 @@ impl super::Nexus {
         self.db_datastore.silo_utilization_view(opctx, &authz_silo).await
     }
 
+    /// Reports a project's provisioned resources against its capacity, which
+    /// for each resource is the project's own quota if one is set and the
+    /// quota of its silo otherwise.
+    pub async fn project_utilization_view(
+        &self,
+        opctx: &OpContext,
+        project_lookup: &lookup::Project<'_>,
+    ) -> Result<views::Utilization, Error> {
+        let (authz_silo, authz_project) =
+            project_lookup.lookup_for(authz::Action::Read).await?;
+        let project_quotas = self
+            .db_datastore
+            .project_quotas_view(opctx, &authz_project)
+            .await?;
+        let silo_quotas =
+            self.db_datastore.silo_quotas_view(opctx, &authz_silo).await?;
+        let provisioned = self
+            .db_datastore
+            .virtual_provisioning_collection_get(opctx, authz_project.id())
+            .await?;
+
+        Ok(views::Utilization {
+            provisioned: views::VirtualResourceCounts {
+                cpus: provisioned.cpus_provisioned,
+                memory: provisioned.ram_provisioned.into(),
+                storage: provisioned.virtual_disk_bytes_provisioned.into(),
+            },
+            capacity: views::VirtualResourceCounts {
+                cpus: project_quotas.cpus.unwrap_or(silo_quotas.cpus),
+                memory: project_quotas
+                    .memory
+                    .unwrap_or(silo_quotas.memory)
+                    .into(),
+                storage: project_quotas
+                    .storage
+                    .unwrap_or(silo_quotas.storage)
+                    .into(),
+            },
+        })
+    }
+
     pub async fn silo_utilization_list(
         &self,
         opctx: &OpContext,
@@ -441,9 +441,21 @@ impl authn::external::token::TokenContext for ServerContext {
     async fn token_actor(
         &self,
         token: String,
-    ) -> Result<authn::Actor, authn::Reason> {
+    ) -> Result<(authn::Actor, authn::Scope), authn::Reason> {
         let opctx = self.nexus.opctx_external_authn();
-        self.nexus.device_access_token_actor(opctx, token).await
+        // Device access tokens, API tokens, and service account tokens share
+        // a format, so try each in turn.
+        match self.nexus.device_access_token_actor(opctx, token.clone()).await {
+            Ok(actor) => return Ok((actor, authn::Scope::default())),
+            Err(authn::Reason::UnknownActor { .. }) => (),
+            Err(error) => return Err(error),
+        }
+        match self.nexus.api_token_actor(opctx, token.clone()).await {
+            Err(authn::Reason::UnknownActor { .. }) => {
+                self.nexus.service_account_token_actor(opctx, token).await
+            }
+            result => result,
+        }
     }
 }
 
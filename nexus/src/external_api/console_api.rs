@@ -32,6 +32,7 @@ use dropshot::{
 use http::{header, HeaderName, HeaderValue, Response, StatusCode, Uri};
 use hyper::Body;
 use nexus_db_model::AuthenticationMode;
+use nexus_db_model::IdentityProviderType as IdentityProviderTypeModel;
 use nexus_db_queries::authn::silos::IdentityProviderType;
 use nexus_db_queries::context::OpContext;
 use nexus_db_queries::{
@@ -70,13 +71,13 @@ use tokio_util::codec::{BytesCodec, FramedRead};
 //    logged out. This goes through `console_index_or_login_redirect` and
 //    therefore results in a redirect straight to either
 //    /login/{silo}/local?redirect_uri={} or
-//    /login/{silo}/saml/{provider}?redirect_uri={} depending on the silo's
-//    authn mode. Nexus takes the path the user was trying to hit and sticks it
-//    in a `redirect_uri` query param.
+//    /login/{silo}/{saml,oidc}/{provider}?redirect_uri={} depending on the
+//    silo's authn mode and the kind of identity provider. Nexus takes the path
+//    the user was trying to hit and sticks it in a `redirect_uri` query param.
 // 2. Hit a 401 on a background API call while already in the console (for
 //    example, if session expires while in use). In that case, the console will
 //    navigate to `/login?redirect_uri={current_path}`, which will respond with
-//    a redirect to local or SAML login as above.
+//    a redirect to local, SAML, or OIDC login as above.
 //
 // Local login is very simple. We show a login form, the username and password
 // are POSTed to the API, and on success, the console pulls `redirect_uri` out
@@ -299,6 +300,10 @@ pub(crate) async fn login_saml_redirect(
 
                 http_response_found(sign_in_url)
             }
+            IdentityProviderType::Oidc(_) => Err(Error::invalid_request(
+                "identity provider is not a SAML identity provider",
+            )
+            .into()),
         }
     };
 
@@ -348,6 +353,12 @@ pub(crate) async fn login_saml(
                         nexus.samael_max_issue_delay(),
                     )?
                 }
+                IdentityProviderType::Oidc(_) => {
+                    return Err(Error::invalid_request(
+                        "identity provider is not a SAML identity provider",
+                    )
+                    .into());
+                }
             };
 
         let relay_state =
@@ -396,6 +407,195 @@ pub(crate) async fn login_saml(
         .await
 }
 
+/// Log in via OpenID Connect
+///
+/// Redirects to the identity provider's authorization endpoint.  The provider
+/// sends the user back to `login_oidc_callback` once they've authenticated.
+#[endpoint {
+   method = GET,
+   path = "/login/{silo_name}/oidc/{provider_name}",
+   tags = ["login"],
+   unpublished = true,
+}]
+pub(crate) async fn login_oidc_begin(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<LoginToProviderPathParam>,
+    query_params: Query<LoginUrlQuery>,
+) -> Result<HttpResponseFound, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.context.nexus;
+        let path_params = path_params.into_inner();
+
+        // Use opctx_external_authn because this request will be
+        // unauthenticated.
+        let opctx = nexus.opctx_external_authn();
+
+        let (authz_silo, _, identity_provider) = IdentityProviderType::lookup(
+            &nexus.datastore(),
+            &opctx,
+            &path_params.silo_name,
+            &path_params.provider_name,
+        )
+        .await?;
+
+        let IdentityProviderType::Oidc(oidc_identity_provider) =
+            identity_provider
+        else {
+            return Err(Error::invalid_request(
+                "identity provider is not an OIDC identity provider",
+            )
+            .into());
+        };
+
+        // Unlike SAML, the relay state never leaves Nexus: it's stored with
+        // the login request and handed back when the login completes.
+        let redirect_uri = query_params.into_inner().redirect_uri;
+        let relay_state =
+            RelayState { redirect_uri }.to_encoded().map_err(|e| {
+                HttpError::for_internal_error(format!(
+                    "encoding relay state failed: {}",
+                    e
+                ))
+            })?;
+
+        let authorization_url = nexus
+            .oidc_login_begin(
+                &opctx,
+                &authz_silo,
+                &oidc_identity_provider,
+                Some(relay_state),
+            )
+            .await?;
+        http_response_found(authorization_url)
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct OidcCallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+    error: Option<String>,
+    error_description: Option<String>,
+}
+
+/// Complete an OpenID Connect login
+///
+/// The identity provider redirects the user here with an authorization code
+/// (or an error).
+#[endpoint {
+   method = GET,
+   path = "/login/{silo_name}/oidc/{provider_name}/callback",
+   tags = ["login"],
+   unpublished = true,
+}]
+pub(crate) async fn login_oidc_callback(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<LoginToProviderPathParam>,
+    query_params: Query<OidcCallbackQuery>,
+) -> Result<HttpResponseSeeOther, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.context.nexus;
+        let path_params = path_params.into_inner();
+        let query = query_params.into_inner();
+
+        // By definition, this request is not authenticated.  These operations
+        // happen using the Nexus "external authentication" context, which we
+        // keep specifically for this purpose.
+        let opctx = nexus.opctx_external_authn();
+
+        let (code, state) = match (query.error, query.code, query.state) {
+            (Some(error), ..) => {
+                return Err(Error::Unauthenticated {
+                    internal_message: format!(
+                        "OIDC provider returned error {:?}: {}",
+                        error,
+                        query.error_description.unwrap_or_default(),
+                    ),
+                }
+                .into());
+            }
+            (None, Some(code), Some(state)) => (code, state),
+            _ => {
+                return Err(Error::invalid_request(
+                    "expected \"code\" and \"state\" query parameters",
+                )
+                .into());
+            }
+        };
+
+        let (authz_silo, db_silo, identity_provider) =
+            IdentityProviderType::lookup(
+                &nexus.datastore(),
+                &opctx,
+                &path_params.silo_name,
+                &path_params.provider_name,
+            )
+            .await?;
+
+        let IdentityProviderType::Oidc(oidc_identity_provider) =
+            identity_provider
+        else {
+            return Err(Error::invalid_request(
+                "identity provider is not an OIDC identity provider",
+            )
+            .into());
+        };
+
+        let (user, relay_state_string) = nexus
+            .oidc_login_complete(
+                &opctx,
+                &authz_silo,
+                &db_silo,
+                &oidc_identity_provider,
+                &code,
+                &state,
+            )
+            .await?;
+
+        let relay_state =
+            if let Some(value) = relay_state_string {
+                Some(RelayState::from_encoded(value).map_err(|e| {
+                    HttpError::for_internal_error(format!("{}", e))
+                })?)
+            } else {
+                None
+            };
+
+        let session = create_session(opctx, apictx, user).await?;
+        let next_url = relay_state
+            .and_then(|r| r.redirect_uri)
+            .map(|u| u.to_string())
+            .unwrap_or_else(|| "/".to_string());
+        let mut response = http_response_see_other(next_url)?;
+
+        {
+            let headers = response.headers_mut();
+            let cookie = session_cookie_header_value(
+                &session.token,
+                // use absolute timeout even though session might idle out first.
+                // browser expiration is mostly for convenience, as the API will
+                // reject requests with an expired session regardless
+                apictx.context.session_absolute_timeout(),
+                apictx.context.external_tls_enabled,
+            )?;
+            headers.append(header::SET_COOKIE, cookie);
+        }
+        Ok(response)
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
 #[derive(Deserialize, JsonSchema)]
 pub struct LoginPathParam {
     pub silo_name: nexus_db_queries::db::model::Name,
@@ -634,11 +834,12 @@ async fn get_login_url(
             );
         }
 
-        format!(
-            "/login/{}/saml/{}",
-            silo.name(),
-            idps.into_iter().next().unwrap().name()
-        )
+        let idp = idps.into_iter().next().unwrap();
+        let kind = match idp.provider_type {
+            IdentityProviderTypeModel::Saml => "saml",
+            IdentityProviderTypeModel::Oidc => "oidc",
+        };
+        format!("/login/{}/{}/{}", silo.name(), kind, idp.name())
     };
 
     // Stick redirect_url into the state param and URL encode it so it can be
@@ -20,6 +20,7 @@ use dropshot::{
 use http::{header, Response, StatusCode};
 use hyper::Body;
 use nexus_db_queries::db::model::DeviceAccessToken;
+use omicron_common::api::external::Error;
 use omicron_common::api::external::InternalContext;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -153,12 +154,14 @@ pub(crate) async fn device_auth_confirm(
         let &actor = opctx.authn.actor_required().internal_context(
             "creating new device auth session for current user",
         )?;
-        let _token = nexus
-            .device_auth_request_verify(
-                &opctx,
-                params.user_code,
-                actor.actor_id(),
+        let Some(silo_user_id) = actor.silo_user_id() else {
+            return Err(Error::invalid_request(
+                "only silo users can authorize devices",
             )
+            .into());
+        };
+        let _token = nexus
+            .device_auth_request_verify(&opctx, params.user_code, silo_user_id)
             .await?;
         Ok(HttpResponseUpdatedNoContent())
     };
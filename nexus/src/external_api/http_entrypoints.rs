@@ -5,7 +5,7 @@
 //! Handler functions (entrypoints) for external HTTP APIs
 
 use super::{
-    console_api, device_auth, params,
+    console_api, device_auth, params, scim,
     views::{
         self, Certificate, FloatingIp, Group, IdentityProvider, Image, IpPool,
         IpPoolRange, PhysicalDisk, Project, Rack, Role, Silo, SiloQuotas,
@@ -34,6 +34,7 @@ use dropshot::{
 use dropshot::{ApiDescription, StreamingBody};
 use dropshot::{ApiEndpoint, EmptyScanParams};
 use ipnetwork::IpNetwork;
+use nexus_db_queries::authz::ApiResource;
 use nexus_db_queries::db;
 use nexus_db_queries::db::identity::Resource;
 use nexus_db_queries::db::lookup::ImageLookup;
@@ -116,6 +117,8 @@ pub(crate) fn external_api() -> NexusApiDescription {
         api.register(project_update)?;
         api.register(project_policy_view)?;
         api.register(project_policy_update)?;
+        api.register(project_quotas_view)?;
+        api.register(project_quotas_update)?;
         api.register(project_ip_pool_list)?;
         api.register(project_ip_pool_view)?;
 
@@ -231,6 +234,9 @@ pub(crate) fn external_api() -> NexusApiDescription {
         api.register(sled_list)?;
         api.register(sled_view)?;
         api.register(sled_set_provision_policy)?;
+        api.register(sled_power_cycle)?;
+        api.register(sled_power_off)?;
+        api.register(sled_reset_sp)?;
         api.register(sled_instance_list)?;
         api.register(sled_physical_disk_list)?;
         api.register(physical_disk_list)?;
@@ -253,6 +259,11 @@ pub(crate) fn external_api() -> NexusApiDescription {
         api.register(current_user_ssh_key_create)?;
         api.register(current_user_ssh_key_delete)?;
 
+        api.register(current_user_token_list)?;
+        api.register(current_user_token_view)?;
+        api.register(current_user_token_create)?;
+        api.register(current_user_token_delete)?;
+
         // Customer network integration
         api.register(networking_address_lot_list)?;
         api.register(networking_address_lot_create)?;
@@ -312,6 +323,12 @@ pub(crate) fn external_api() -> NexusApiDescription {
 
         api.register(saml_identity_provider_create)?;
         api.register(saml_identity_provider_view)?;
+        api.register(oidc_identity_provider_create)?;
+        api.register(oidc_identity_provider_view)?;
+
+        api.register(scim_client_bearer_token_list)?;
+        api.register(scim_client_bearer_token_create)?;
+        api.register(scim_client_bearer_token_delete)?;
 
         api.register(local_idp_user_create)?;
         api.register(local_idp_user_delete)?;
@@ -329,6 +346,8 @@ pub(crate) fn external_api() -> NexusApiDescription {
 
         api.register(system_update_put_repository)?;
         api.register(system_update_get_repository)?;
+        api.register(target_release_view)?;
+        api.register(target_release_update)?;
 
         api.register(user_list)?;
         api.register(silo_user_list)?;
@@ -336,6 +355,14 @@ pub(crate) fn external_api() -> NexusApiDescription {
         api.register(group_list)?;
         api.register(group_view)?;
 
+        api.register(service_account_list)?;
+        api.register(service_account_create)?;
+        api.register(service_account_view)?;
+        api.register(service_account_delete)?;
+        api.register(service_account_token_list)?;
+        api.register(service_account_token_create)?;
+        api.register(service_account_token_delete)?;
+
         // Console API operations
         api.register(console_api::login_begin)?;
         api.register(console_api::login_local_begin)?;
@@ -343,6 +370,8 @@ pub(crate) fn external_api() -> NexusApiDescription {
         api.register(console_api::login_saml_begin)?;
         api.register(console_api::login_saml_redirect)?;
         api.register(console_api::login_saml)?;
+        api.register(console_api::login_oidc_begin)?;
+        api.register(console_api::login_oidc_callback)?;
         api.register(console_api::logout)?;
 
         api.register(console_api::console_lookup)?;
@@ -362,6 +391,19 @@ pub(crate) fn external_api() -> NexusApiDescription {
         api.register(device_auth::device_auth_confirm)?;
         api.register(device_auth::device_access_token)?;
 
+        // SCIM provisioning operations
+        api.register(scim::scim_user_list)?;
+        api.register(scim::scim_user_create)?;
+        api.register(scim::scim_user_view)?;
+        api.register(scim::scim_user_replace)?;
+        api.register(scim::scim_user_update)?;
+        api.register(scim::scim_user_delete)?;
+        api.register(scim::scim_group_list)?;
+        api.register(scim::scim_group_create)?;
+        api.register(scim::scim_group_view)?;
+        api.register(scim::scim_group_update)?;
+        api.register(scim::scim_group_delete)?;
+
         Ok(())
     }
 
@@ -572,6 +614,9 @@ async fn policy_update(
 }
 
 /// Fetch resource utilization for user's current silo
+///
+/// If a project is specified, report that project's utilization against its
+/// capacity instead.
 #[endpoint {
     method = GET,
     path = "/v1/utilization",
@@ -579,16 +624,27 @@ async fn policy_update(
 }]
 async fn utilization_view(
     rqctx: RequestContext<ApiContext>,
+    query_params: Query<params::OptionalProjectSelector>,
 ) -> Result<HttpResponseOk<Utilization>, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
         let nexus = &apictx.context.nexus;
         let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
-        let silo_lookup = nexus.current_silo_lookup(&opctx)?;
-        let utilization =
-            nexus.silo_utilization_view(&opctx, &silo_lookup).await?;
+        let query = query_params.into_inner();
+        let utilization = match query.project {
+            Some(project) => {
+                let project_selector = params::ProjectSelector { project };
+                let project_lookup =
+                    nexus.project_lookup(&opctx, project_selector)?;
+                nexus.project_utilization_view(&opctx, &project_lookup).await?
+            }
+            None => {
+                let silo_lookup = nexus.current_silo_lookup(&opctx)?;
+                nexus.silo_utilization_view(&opctx, &silo_lookup).await?.into()
+            }
+        };
 
-        Ok(HttpResponseOk(utilization.into()))
+        Ok(HttpResponseOk(utilization))
     };
     apictx
         .context
@@ -1179,8 +1235,195 @@ async fn saml_identity_provider_view(
         .await
 }
 
+// Silo OIDC identity providers
+
+/// Create OIDC IdP
+#[endpoint {
+    method = POST,
+    path = "/v1/system/identity-providers/oidc",
+    tags = ["system/silos"],
+}]
+async fn oidc_identity_provider_create(
+    rqctx: RequestContext<ApiContext>,
+    query_params: Query<params::SiloSelector>,
+    new_provider: TypedBody<params::OidcIdentityProviderCreate>,
+) -> Result<HttpResponseCreated<views::OidcIdentityProvider>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.context.nexus;
+        let query = query_params.into_inner();
+        let silo_lookup = nexus.silo_lookup(&opctx, query.silo)?;
+        let provider = nexus
+            .oidc_identity_provider_create(
+                &opctx,
+                &silo_lookup,
+                new_provider.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseCreated(provider.into()))
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
+/// Fetch OIDC IdP
+#[endpoint {
+    method = GET,
+    path = "/v1/system/identity-providers/oidc/{provider}",
+    tags = ["system/silos"],
+}]
+async fn oidc_identity_provider_view(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<params::ProviderPath>,
+    query_params: Query<params::SiloSelector>,
+) -> Result<HttpResponseOk<views::OidcIdentityProvider>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.context.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let oidc_identity_provider_selector =
+            params::OidcIdentityProviderSelector {
+                silo: Some(query.silo),
+                oidc_identity_provider: path.provider,
+            };
+        let (.., provider) = nexus
+            .oidc_identity_provider_lookup(
+                &opctx,
+                oidc_identity_provider_selector,
+            )?
+            .fetch()
+            .await?;
+        Ok(HttpResponseOk(provider.into()))
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
 // TODO: no DELETE for identity providers?
 
+// Silo SCIM client bearer tokens
+
+/// List SCIM client bearer tokens
+///
+/// Lists the bearer tokens that identity providers can use to provision users
+/// and groups in a Silo via SCIM.  The token values themselves are only ever
+/// returned when the token is created.
+#[endpoint {
+    method = GET,
+    path = "/v1/system/scim/tokens",
+    tags = ["system/silos"],
+}]
+async fn scim_client_bearer_token_list(
+    rqctx: RequestContext<ApiContext>,
+    query_params: Query<PaginatedById<params::SiloSelector>>,
+) -> Result<HttpResponseOk<ResultsPage<views::ScimClientBearerToken>>, HttpError>
+{
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.context.nexus;
+        let query = query_params.into_inner();
+        let pag_params = data_page_params_for(&rqctx, &query)?;
+        let scan_params = ScanById::from_query(&query)?;
+        let silo_lookup =
+            nexus.silo_lookup(&opctx, scan_params.selector.silo.clone())?;
+        let tokens = nexus
+            .scim_client_bearer_token_list(&opctx, &silo_lookup, &pag_params)
+            .await?
+            .into_iter()
+            .map(|t| t.into())
+            .collect();
+        Ok(HttpResponseOk(ScanById::results_page(
+            &query,
+            tokens,
+            &|_, token: &views::ScimClientBearerToken| token.id,
+        )?))
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
+/// Create SCIM client bearer token
+///
+/// The Silo must have been created with the `saml_scim` identity mode.
+#[endpoint {
+    method = POST,
+    path = "/v1/system/scim/tokens",
+    tags = ["system/silos"],
+}]
+async fn scim_client_bearer_token_create(
+    rqctx: RequestContext<ApiContext>,
+    query_params: Query<params::SiloSelector>,
+) -> Result<HttpResponseCreated<views::ScimClientBearerTokenValue>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.context.nexus;
+        let query = query_params.into_inner();
+        let silo_lookup = nexus.silo_lookup(&opctx, query.silo)?;
+        let token =
+            nexus.scim_client_bearer_token_create(&opctx, &silo_lookup).await?;
+        Ok(HttpResponseCreated(token.into()))
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
+/// Path parameters for SCIM client bearer token requests
+#[derive(Deserialize, JsonSchema)]
+struct ScimClientBearerTokenPathParam {
+    token_id: Uuid,
+}
+
+/// Delete SCIM client bearer token
+#[endpoint {
+    method = DELETE,
+    path = "/v1/system/scim/tokens/{token_id}",
+    tags = ["system/silos"],
+}]
+async fn scim_client_bearer_token_delete(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<ScimClientBearerTokenPathParam>,
+    query_params: Query<params::SiloSelector>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.context.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let silo_lookup = nexus.silo_lookup(&opctx, query.silo)?;
+        nexus
+            .scim_client_bearer_token_delete(
+                &opctx,
+                &silo_lookup,
+                path.token_id,
+            )
+            .await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
 // "Local" Identity Provider
 
 /// Create user
@@ -1503,6 +1746,72 @@ async fn project_policy_update(
         .await
 }
 
+/// Fetch project's resource quotas
+#[endpoint {
+    method = GET,
+    path = "/v1/projects/{project}/quotas",
+    tags = ["projects"],
+}]
+async fn project_quotas_view(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<params::ProjectPath>,
+) -> Result<HttpResponseOk<views::ProjectQuotas>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.context.nexus;
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let path = path_params.into_inner();
+        let project_selector =
+            params::ProjectSelector { project: path.project };
+        let project_lookup = nexus.project_lookup(&opctx, project_selector)?;
+        let quotas = nexus.project_quotas_view(&opctx, &project_lookup).await?;
+        Ok(HttpResponseOk(quotas.into()))
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
+/// Update project's resource quotas
+///
+/// Replaces all of the project's limits. Only silo administrators may change
+/// a project's quotas.
+#[endpoint {
+    method = PUT,
+    path = "/v1/projects/{project}/quotas",
+    tags = ["projects"],
+}]
+async fn project_quotas_update(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<params::ProjectPath>,
+    new_quotas: TypedBody<params::ProjectQuotasUpdate>,
+) -> Result<HttpResponseOk<views::ProjectQuotas>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.context.nexus;
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let path = path_params.into_inner();
+        let project_selector =
+            params::ProjectSelector { project: path.project };
+        let project_lookup = nexus.project_lookup(&opctx, project_selector)?;
+        let quotas = nexus
+            .project_quotas_update(
+                &opctx,
+                &project_lookup,
+                &new_quotas.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseOk(quotas.into()))
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
 // IP Pools
 
 /// List IP pools
@@ -3011,6 +3320,9 @@ async fn instance_stop(
 }
 
 /// Fetch instance serial console
+///
+/// Output is kept across instance stops, starts, and migrations, up to a fixed
+/// size, after which the oldest output is discarded.
 #[endpoint {
     method = GET,
     path = "/v1/instances/{instance}/serial-console",
@@ -6053,39 +6365,27 @@ async fn sled_set_provision_policy(
         .await
 }
 
-/// List instances running on given sled
+/// Power cycle sled
+///
+/// Resets the sled's power through its service processor's ignition
+/// controller.
 #[endpoint {
-    method = GET,
-    path = "/v1/system/hardware/sleds/{sled_id}/instances",
+    method = POST,
+    path = "/v1/system/hardware/sleds/{sled_id}/power-cycle",
     tags = ["system/hardware"],
 }]
-async fn sled_instance_list(
+async fn sled_power_cycle(
     rqctx: RequestContext<ApiContext>,
     path_params: Path<params::SledPath>,
-    query_params: Query<PaginatedById>,
-) -> Result<HttpResponseOk<ResultsPage<views::SledInstance>>, HttpError> {
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
         let nexus = &apictx.context.nexus;
         let path = path_params.into_inner();
-        let query = query_params.into_inner();
         let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
         let sled_lookup = nexus.sled_lookup(&opctx, &path.sled_id)?;
-        let sled_instances = nexus
-            .sled_instance_list(
-                &opctx,
-                &sled_lookup,
-                &data_page_params_for(&rqctx, &query)?,
-            )
-            .await?
-            .into_iter()
-            .map(|s| s.into())
-            .collect();
-        Ok(HttpResponseOk(ScanById::results_page(
-            &query,
-            sled_instances,
-            &|_, sled_instance: &views::SledInstance| sled_instance.identity.id,
-        )?))
+        nexus.sled_power_cycle(&opctx, &sled_lookup).await?;
+        Ok(HttpResponseUpdatedNoContent())
     };
     apictx
         .context
@@ -6094,30 +6394,130 @@ async fn sled_instance_list(
         .await
 }
 
-// Physical disks
-
-/// List physical disks
+/// Power off sled
+///
+/// Fails if the sled still has control plane zones that have not been
+/// expunged, unless `force` is set.
 #[endpoint {
-    method = GET,
-    path = "/v1/system/hardware/disks",
+    method = POST,
+    path = "/v1/system/hardware/sleds/{sled_id}/power-off",
     tags = ["system/hardware"],
 }]
-async fn physical_disk_list(
+async fn sled_power_off(
     rqctx: RequestContext<ApiContext>,
-    query_params: Query<PaginatedById>,
-) -> Result<HttpResponseOk<ResultsPage<PhysicalDisk>>, HttpError> {
+    path_params: Path<params::SledPath>,
+    query_params: Query<params::SledPowerOffParams>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
     let apictx = rqctx.context();
     let handler = async {
         let nexus = &apictx.context.nexus;
+        let path = path_params.into_inner();
         let query = query_params.into_inner();
         let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
-        let disks = nexus
-            .physical_disk_list(&opctx, &data_page_params_for(&rqctx, &query)?)
-            .await?
-            .into_iter()
-            .map(|s| s.into())
-            .collect();
-        Ok(HttpResponseOk(ScanById::results_page(
+        let sled_lookup = nexus.sled_lookup(&opctx, &path.sled_id)?;
+        nexus.sled_power_off(&opctx, &sled_lookup, query.force).await?;
+        Ok(HttpResponseUpdatedNoContent())
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
+/// Reset sled's service processor
+///
+/// The sled itself keeps running while its service processor restarts.
+#[endpoint {
+    method = POST,
+    path = "/v1/system/hardware/sleds/{sled_id}/reset-sp",
+    tags = ["system/hardware"],
+}]
+async fn sled_reset_sp(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<params::SledPath>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.context.nexus;
+        let path = path_params.into_inner();
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let sled_lookup = nexus.sled_lookup(&opctx, &path.sled_id)?;
+        nexus.sled_reset_sp(&opctx, &sled_lookup).await?;
+        Ok(HttpResponseUpdatedNoContent())
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
+/// List instances running on given sled
+#[endpoint {
+    method = GET,
+    path = "/v1/system/hardware/sleds/{sled_id}/instances",
+    tags = ["system/hardware"],
+}]
+async fn sled_instance_list(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<params::SledPath>,
+    query_params: Query<PaginatedById>,
+) -> Result<HttpResponseOk<ResultsPage<views::SledInstance>>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.context.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let sled_lookup = nexus.sled_lookup(&opctx, &path.sled_id)?;
+        let sled_instances = nexus
+            .sled_instance_list(
+                &opctx,
+                &sled_lookup,
+                &data_page_params_for(&rqctx, &query)?,
+            )
+            .await?
+            .into_iter()
+            .map(|s| s.into())
+            .collect();
+        Ok(HttpResponseOk(ScanById::results_page(
+            &query,
+            sled_instances,
+            &|_, sled_instance: &views::SledInstance| sled_instance.identity.id,
+        )?))
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
+// Physical disks
+
+/// List physical disks
+#[endpoint {
+    method = GET,
+    path = "/v1/system/hardware/disks",
+    tags = ["system/hardware"],
+}]
+async fn physical_disk_list(
+    rqctx: RequestContext<ApiContext>,
+    query_params: Query<PaginatedById>,
+) -> Result<HttpResponseOk<ResultsPage<PhysicalDisk>>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let nexus = &apictx.context.nexus;
+        let query = query_params.into_inner();
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let disks = nexus
+            .physical_disk_list(&opctx, &data_page_params_for(&rqctx, &query)?)
+            .await?
+            .into_iter()
+            .map(|s| s.into())
+            .collect();
+        Ok(HttpResponseOk(ScanById::results_page(
             &query,
             disks,
             &|_, disk: &PhysicalDisk| disk.identity.id,
@@ -6518,6 +6918,63 @@ async fn system_update_get_repository(
         .await
 }
 
+/// Fetch the current target release
+///
+/// The target release is the version of the system software that the system
+/// is being updated to (or is already running).
+#[endpoint {
+    method = GET,
+    path = "/v1/system/update/target-release",
+    tags = ["system/update"],
+    unpublished = true,
+}]
+async fn target_release_view(
+    rqctx: RequestContext<ApiContext>,
+) -> Result<HttpResponseOk<views::TargetRelease>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.context.nexus;
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let release = nexus.target_release_view(&opctx).await?;
+        Ok(HttpResponseOk(release))
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
+/// Set the target release
+///
+/// The TUF repository for the release must already have been uploaded.  The
+/// system will begin updating its SPs, RoTs, and host phase 1 images to the
+/// versions in that repository.
+#[endpoint {
+    method = PUT,
+    path = "/v1/system/update/target-release",
+    tags = ["system/update"],
+    unpublished = true,
+}]
+async fn target_release_update(
+    rqctx: RequestContext<ApiContext>,
+    body: TypedBody<params::SetTargetReleaseParams>,
+) -> Result<HttpResponseOk<views::TargetRelease>, HttpError> {
+    let apictx = rqctx.context();
+    let nexus = &apictx.context.nexus;
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let params = body.into_inner();
+        let release = nexus.target_release_update(&opctx, params).await?;
+        Ok(HttpResponseOk(release))
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
 // Silo users
 
 /// List users
@@ -6626,6 +7083,248 @@ async fn group_view(
         .await
 }
 
+// Service accounts
+
+/// List service accounts
+#[endpoint {
+    method = GET,
+    path = "/v1/service-accounts",
+    tags = ["silos"],
+}]
+async fn service_account_list(
+    rqctx: RequestContext<ApiContext>,
+    query_params: Query<PaginatedByNameOrId>,
+) -> Result<HttpResponseOk<ResultsPage<views::ServiceAccount>>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.context.nexus;
+        let query = query_params.into_inner();
+        let pag_params = data_page_params_for(&rqctx, &query)?;
+        let scan_params = ScanByNameOrId::from_query(&query)?;
+        let paginated_by = name_or_id_pagination(&pag_params, scan_params)?;
+        let service_accounts = nexus
+            .service_accounts_list(&opctx, &paginated_by)
+            .await?
+            .into_iter()
+            .map(views::ServiceAccount::from)
+            .collect::<Vec<_>>();
+        Ok(HttpResponseOk(ScanByNameOrId::results_page(
+            &query,
+            service_accounts,
+            &marker_for_name_or_id,
+        )?))
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
+/// Create service account
+///
+/// Create a service account in the current silo.  A new service account has no
+/// access to anything; grant it roles through the silo or project policy.
+#[endpoint {
+    method = POST,
+    path = "/v1/service-accounts",
+    tags = ["silos"],
+}]
+async fn service_account_create(
+    rqctx: RequestContext<ApiContext>,
+    new_service_account: TypedBody<params::ServiceAccountCreate>,
+) -> Result<HttpResponseCreated<views::ServiceAccount>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.context.nexus;
+        let service_account = nexus
+            .service_account_create(&opctx, new_service_account.into_inner())
+            .await?;
+        Ok(HttpResponseCreated(service_account.into()))
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
+/// Fetch service account
+#[endpoint {
+    method = GET,
+    path = "/v1/service-accounts/{service_account}",
+    tags = ["silos"],
+}]
+async fn service_account_view(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<params::ServiceAccountPath>,
+) -> Result<HttpResponseOk<views::ServiceAccount>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.context.nexus;
+        let path = path_params.into_inner();
+        let (.., service_account) = nexus
+            .service_account_lookup(&opctx, path.service_account)?
+            .fetch()
+            .await?;
+        Ok(HttpResponseOk(service_account.into()))
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
+/// Delete service account
+///
+/// Delete a service account, revoking all of its tokens and removing it from
+/// any policies that grant it roles.
+#[endpoint {
+    method = DELETE,
+    path = "/v1/service-accounts/{service_account}",
+    tags = ["silos"],
+}]
+async fn service_account_delete(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<params::ServiceAccountPath>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.context.nexus;
+        let path = path_params.into_inner();
+        let service_account_lookup =
+            nexus.service_account_lookup(&opctx, path.service_account)?;
+        nexus.service_account_delete(&opctx, &service_account_lookup).await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
+/// List service account tokens
+///
+/// Token values are not included.
+#[endpoint {
+    method = GET,
+    path = "/v1/service-accounts/{service_account}/tokens",
+    tags = ["silos"],
+}]
+async fn service_account_token_list(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<params::ServiceAccountPath>,
+    query_params: Query<PaginatedById>,
+) -> Result<HttpResponseOk<ResultsPage<views::ServiceAccountToken>>, HttpError>
+{
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.context.nexus;
+        let path = path_params.into_inner();
+        let query = query_params.into_inner();
+        let pagparams = data_page_params_for(&rqctx, &query)?;
+        let service_account_lookup =
+            nexus.service_account_lookup(&opctx, path.service_account)?;
+        let tokens = nexus
+            .service_account_tokens_list(
+                &opctx,
+                &service_account_lookup,
+                &pagparams,
+            )
+            .await?
+            .into_iter()
+            .map(views::ServiceAccountToken::from)
+            .collect();
+        Ok(HttpResponseOk(ScanById::results_page(
+            &query,
+            tokens,
+            &|_, token: &views::ServiceAccountToken| token.id,
+        )?))
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
+/// Create service account token
+///
+/// The token value is only returned in this response; it cannot be retrieved
+/// later.
+#[endpoint {
+    method = POST,
+    path = "/v1/service-accounts/{service_account}/tokens",
+    tags = ["silos"],
+}]
+async fn service_account_token_create(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<params::ServiceAccountPath>,
+    new_token: TypedBody<params::ServiceAccountTokenCreate>,
+) -> Result<HttpResponseCreated<views::ServiceAccountTokenValue>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.context.nexus;
+        let path = path_params.into_inner();
+        let service_account_lookup =
+            nexus.service_account_lookup(&opctx, path.service_account)?;
+        let token = nexus
+            .service_account_token_create(
+                &opctx,
+                &service_account_lookup,
+                new_token.into_inner(),
+            )
+            .await?;
+        Ok(HttpResponseCreated(token.into()))
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
+/// Revoke service account token
+#[endpoint {
+    method = DELETE,
+    path = "/v1/service-accounts/{service_account}/tokens/{token_id}",
+    tags = ["silos"],
+}]
+async fn service_account_token_delete(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<params::ServiceAccountTokenPath>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.context.nexus;
+        let path = path_params.into_inner();
+        let service_account_lookup =
+            nexus.service_account_lookup(&opctx, path.service_account)?;
+        nexus
+            .service_account_token_delete(
+                &opctx,
+                &service_account_lookup,
+                path.token_id,
+            )
+            .await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
 // Built-in (system) users
 
 /// List built-in users
@@ -6997,6 +7696,163 @@ async fn current_user_ssh_key_delete(
         .await
 }
 
+// Per-user API tokens
+
+/// List API tokens
+///
+/// Lists API tokens for the currently authenticated user.  Token values are
+/// not included.
+#[endpoint {
+    method = GET,
+    path = "/v1/me/tokens",
+    tags = ["session"],
+}]
+async fn current_user_token_list(
+    rqctx: RequestContext<ApiContext>,
+    query_params: Query<PaginatedByNameOrId>,
+) -> Result<HttpResponseOk<ResultsPage<views::ApiToken>>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.context.nexus;
+        let query = query_params.into_inner();
+        let pag_params = data_page_params_for(&rqctx, &query)?;
+        let scan_params = ScanByNameOrId::from_query(&query)?;
+        let paginated_by = name_or_id_pagination(&pag_params, scan_params)?;
+        let &actor = opctx
+            .authn
+            .actor_required()
+            .internal_context("listing current user's API tokens")?;
+        let tokens = nexus
+            .api_tokens_list(&opctx, actor.actor_id(), &paginated_by)
+            .await?
+            .into_iter()
+            .map(views::ApiToken::from)
+            .collect::<Vec<views::ApiToken>>();
+        Ok(HttpResponseOk(ScanByNameOrId::results_page(
+            &query,
+            tokens,
+            &marker_for_name_or_id,
+        )?))
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
+/// Create API token
+///
+/// Create an API token for the currently authenticated user.  The token value
+/// is only returned in this response; it cannot be retrieved later.
+#[endpoint {
+    method = POST,
+    path = "/v1/me/tokens",
+    tags = ["session"],
+}]
+async fn current_user_token_create(
+    rqctx: RequestContext<ApiContext>,
+    new_token: TypedBody<params::ApiTokenCreate>,
+) -> Result<HttpResponseCreated<views::ApiTokenValue>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.context.nexus;
+        let &actor = opctx
+            .authn
+            .actor_required()
+            .internal_context("creating API token for current user")?;
+        let token = nexus
+            .api_token_create(&opctx, actor.actor_id(), new_token.into_inner())
+            .await?;
+        Ok(HttpResponseCreated(token.into()))
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
+/// Fetch API token
+///
+/// Fetch an API token belonging to the currently authenticated user.
+#[endpoint {
+    method = GET,
+    path = "/v1/me/tokens/{token}",
+    tags = ["session"],
+}]
+async fn current_user_token_view(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<params::ApiTokenPath>,
+) -> Result<HttpResponseOk<views::ApiToken>, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.context.nexus;
+        let path = path_params.into_inner();
+        let &actor = opctx
+            .authn
+            .actor_required()
+            .internal_context("fetching one of current user's API tokens")?;
+        let token_selector = params::ApiTokenSelector {
+            silo_user_id: actor.actor_id(),
+            token: path.token,
+        };
+        let token_lookup = nexus.api_token_lookup(&opctx, &token_selector)?;
+        let (.., silo_user, authz_token, token) = token_lookup.fetch().await?;
+        // Tokens are private to their owner, even from other users who can
+        // see that owner.
+        if silo_user.id() != actor.actor_id() {
+            return Err(authz_token.not_found().into());
+        }
+        Ok(HttpResponseOk(token.into()))
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
+/// Revoke API token
+///
+/// Revoke an API token belonging to the currently authenticated user.  The
+/// token can no longer be used to authenticate.
+#[endpoint {
+    method = DELETE,
+    path = "/v1/me/tokens/{token}",
+    tags = ["session"],
+}]
+async fn current_user_token_delete(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<params::ApiTokenPath>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let apictx = rqctx.context();
+    let handler = async {
+        let opctx = crate::context::op_context_for_external_api(&rqctx).await?;
+        let nexus = &apictx.context.nexus;
+        let path = path_params.into_inner();
+        let &actor = opctx
+            .authn
+            .actor_required()
+            .internal_context("revoking one of current user's API tokens")?;
+        let token_selector = params::ApiTokenSelector {
+            silo_user_id: actor.actor_id(),
+            token: path.token,
+        };
+        let token_lookup = nexus.api_token_lookup(&opctx, &token_selector)?;
+        nexus.api_token_delete(&opctx, actor.actor_id(), &token_lookup).await?;
+        Ok(HttpResponseDeleted())
+    };
+    apictx
+        .context
+        .external_latencies
+        .instrument_dropshot_handler(&rqctx, handler)
+        .await
+}
+
 /// List instrumentation probes
 #[endpoint {
     method = GET,
@@ -0,0 +1,898 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Entrypoints for SCIM 2.0 provisioning of Silo users and groups
+//!
+//! These endpoints are used by a Silo's identity provider, not by people.  They
+//! speak SCIM (RFC 7643 and RFC 7644) rather than the usual Oxide API
+//! conventions, so they're unpublished and they build their own responses.
+//! See `nexus/src/app/scim.rs` for an overview.
+
+use crate::ApiContext;
+use dropshot::{endpoint, HttpError, Path, Query, RequestContext, UntypedBody};
+use headers::authorization::{Authorization, Bearer};
+use headers::HeaderMapExt;
+use http::{header, Response, StatusCode};
+use hyper::Body;
+use nexus_db_queries::authz;
+use nexus_db_queries::context::OpContext;
+use nexus_db_queries::db;
+use nexus_db_queries::db::identity::Asset;
+use omicron_common::api::external::Error;
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Prefix used on SCIM client bearer tokens
+///
+/// This is distinct from the prefix used by device access tokens so that these
+/// tokens can never be mistaken for a user's credentials (and vice versa).
+pub const SCIM_TOKEN_PREFIX: &str = "oxide-scim-";
+
+const SCIM_CONTENT_TYPE: &str = "application/scim+json";
+
+const SCHEMA_USER: &str = "urn:ietf:params:scim:schemas:core:2.0:User";
+const SCHEMA_GROUP: &str = "urn:ietf:params:scim:schemas:core:2.0:Group";
+const SCHEMA_LIST_RESPONSE: &str =
+    "urn:ietf:params:scim:api:messages:2.0:ListResponse";
+const SCHEMA_PATCH_OP: &str = "urn:ietf:params:scim:api:messages:2.0:PatchOp";
+const SCHEMA_ERROR: &str = "urn:ietf:params:scim:api:messages:2.0:Error";
+
+// Wire types (RFC 7643 and RFC 7644)
+//
+// We only model the attributes that we can do something with.  Identity
+// providers routinely send other attributes (names, email addresses, etc.),
+// which we ignore.
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimMeta {
+    pub resource_type: String,
+    pub created: chrono::DateTime<chrono::Utc>,
+    pub last_modified: chrono::DateTime<chrono::Utc>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUser {
+    pub schemas: Vec<String>,
+    pub id: Uuid,
+    pub user_name: String,
+    pub active: bool,
+    pub meta: ScimMeta,
+}
+
+impl From<db::model::SiloUser> for ScimUser {
+    fn from(user: db::model::SiloUser) -> Self {
+        ScimUser {
+            schemas: vec![SCHEMA_USER.to_string()],
+            id: user.id(),
+            meta: ScimMeta {
+                resource_type: String::from("User"),
+                created: user.time_created(),
+                last_modified: user.time_modified(),
+            },
+            user_name: user.external_id,
+            active: user.active,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimUserCreate {
+    pub user_name: String,
+    #[serde(default = "default_active")]
+    pub active: bool,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ScimGroupMember {
+    pub value: Uuid,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimGroup {
+    pub schemas: Vec<String>,
+    pub id: Uuid,
+    pub display_name: String,
+    pub members: Vec<ScimGroupMember>,
+    pub meta: ScimMeta,
+}
+
+impl ScimGroup {
+    fn new(
+        group: db::model::SiloGroup,
+        members: Vec<db::model::SiloUser>,
+    ) -> Self {
+        ScimGroup {
+            schemas: vec![SCHEMA_GROUP.to_string()],
+            id: group.id(),
+            meta: ScimMeta {
+                resource_type: String::from("Group"),
+                created: group.time_created(),
+                last_modified: group.time_modified(),
+            },
+            display_name: group.external_id,
+            members: members
+                .into_iter()
+                .map(|u| ScimGroupMember {
+                    value: u.id(),
+                    display: Some(u.external_id),
+                })
+                .collect(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimGroupCreate {
+    pub display_name: String,
+    #[serde(default)]
+    pub members: Vec<ScimGroupMember>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimListResponse<T> {
+    pub schemas: Vec<String>,
+    pub total_results: usize,
+    pub start_index: usize,
+    pub items_per_page: usize,
+    #[serde(rename = "Resources")]
+    pub resources: Vec<T>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ScimPatchRequest {
+    pub schemas: Vec<String>,
+    #[serde(rename = "Operations")]
+    pub operations: Vec<ScimPatchOperation>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ScimPatchOperation {
+    pub op: String,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub value: Option<serde_json::Value>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScimError {
+    pub schemas: Vec<String>,
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scim_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
+}
+
+/// Query parameters for SCIM list operations (RFC 7644 §3.4.2)
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ScimListParams {
+    /// Filter expression.  Only `userName eq "..."` (for users) and
+    /// `displayName eq "..."` (for groups) are supported.
+    pub filter: Option<String>,
+    /// 1-based index of the first result to return
+    #[serde(rename = "startIndex")]
+    pub start_index: Option<usize>,
+    /// Maximum number of results to return
+    pub count: Option<usize>,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ScimUserPathParam {
+    user_id: Uuid,
+}
+
+#[derive(Deserialize, JsonSchema)]
+pub struct ScimGroupPathParam {
+    group_id: Uuid,
+}
+
+// Helpers
+
+fn build_scim_response<T>(
+    status: StatusCode,
+    body: &T,
+) -> Result<Response<Body>, HttpError>
+where
+    T: ?Sized + Serialize,
+{
+    let body = serde_json::to_string(body)
+        .map_err(|e| HttpError::for_internal_error(e.to_string()))?;
+    Ok(Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, SCIM_CONTENT_TYPE)
+        .body(body.into())?)
+}
+
+fn build_scim_empty_response(
+    status: StatusCode,
+) -> Result<Response<Body>, HttpError> {
+    Ok(Response::builder().status(status).body(Body::empty())?)
+}
+
+/// Converts the result of a SCIM operation into a response
+///
+/// SCIM clients expect errors in the format described by RFC 7644 §3.12, so we
+/// translate our usual errors into that format here.
+fn scim_result(
+    result: Result<Response<Body>, Error>,
+) -> Result<Response<Body>, HttpError> {
+    match result {
+        Ok(response) => Ok(response),
+        Err(error) => {
+            // SCIM clients expect a conflict (rather than our usual "bad
+            // request") when creating a resource that already exists.
+            let scim_type = match &error {
+                Error::ObjectAlreadyExists { .. } => Some("uniqueness"),
+                _ => None,
+            };
+            let http_error = HttpError::from(error);
+            let status_code = if scim_type.is_some() {
+                StatusCode::CONFLICT
+            } else {
+                http_error.status_code
+            };
+            build_scim_response(
+                status_code,
+                &ScimError {
+                    schemas: vec![SCHEMA_ERROR.to_string()],
+                    status: status_code.as_u16().to_string(),
+                    scim_type: scim_type.map(String::from),
+                    detail: Some(http_error.external_message),
+                },
+            )
+        }
+    }
+}
+
+fn parse_scim_body<T: DeserializeOwned>(
+    body: &UntypedBody,
+) -> Result<T, Error> {
+    serde_json::from_slice(body.as_bytes()).map_err(|e| {
+        Error::invalid_request(&format!("failed to parse request body: {}", e))
+    })
+}
+
+/// Extracts the SCIM client's bearer token from the request
+fn scim_bearer_token(request: &dropshot::RequestInfo) -> Result<String, Error> {
+    let unauthenticated = |message: &str| Error::Unauthenticated {
+        internal_message: message.to_string(),
+    };
+    let header: Authorization<Bearer> = request
+        .headers()
+        .typed_get()
+        .ok_or_else(|| unauthenticated("missing SCIM bearer token"))?;
+    header
+        .token()
+        .strip_prefix(SCIM_TOKEN_PREFIX)
+        .map(String::from)
+        .ok_or_else(|| unauthenticated("not a SCIM bearer token"))
+}
+
+async fn scim_authenticate<'a>(
+    rqctx: &'a RequestContext<ApiContext>,
+) -> Result<(&'a OpContext, authz::Silo), Error> {
+    let nexus = &rqctx.context().context.nexus;
+    let token = scim_bearer_token(&rqctx.request)?;
+    nexus.scim_authenticate(&token).await
+}
+
+/// Parses the simple `attribute eq "value"` filter expressions that identity
+/// providers use to check whether a user or group already exists
+///
+/// Returns the value being compared against.  Other filter expressions are
+/// rejected.
+fn parse_scim_filter(
+    filter: &Option<String>,
+    attribute: &str,
+) -> Result<Option<String>, Error> {
+    let Some(filter) = filter else { return Ok(None) };
+    let unsupported =
+        || Error::invalid_request(&format!("unsupported filter: {:?}", filter));
+    let mut parts = filter.trim().splitn(3, ' ');
+    let (Some(attr), Some(op), Some(value)) =
+        (parts.next(), parts.next(), parts.next())
+    else {
+        return Err(unsupported());
+    };
+    if !attr.eq_ignore_ascii_case(attribute) || !op.eq_ignore_ascii_case("eq") {
+        return Err(unsupported());
+    }
+    let value = value
+        .trim()
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .ok_or_else(unsupported)?;
+    Ok(Some(value.to_string()))
+}
+
+/// Returns the requested page of `items` in a SCIM list response
+fn scim_list_response<T: Serialize>(
+    items: Vec<T>,
+    params: &ScimListParams,
+) -> Result<Response<Body>, Error> {
+    let total_results = items.len();
+    // Per RFC 7644 §3.4.2.4, values less than 1 are interpreted as 1.
+    let start_index = params.start_index.unwrap_or(1).max(1);
+    let resources: Vec<T> = items
+        .into_iter()
+        .skip(start_index - 1)
+        .take(params.count.unwrap_or(usize::MAX))
+        .collect();
+    build_scim_response(
+        StatusCode::OK,
+        &ScimListResponse {
+            schemas: vec![SCHEMA_LIST_RESPONSE.to_string()],
+            total_results,
+            start_index,
+            items_per_page: resources.len(),
+            resources,
+        },
+    )
+    .map_err(|e| Error::internal_error(&e.internal_message))
+}
+
+/// Returns whether this PATCH operation activates or deactivates a user, or
+/// `None` if it does neither
+///
+/// Identity providers express this either as `{"path": "active", "value":
+/// false}` or as `{"value": {"active": false}}`.
+fn patch_user_active(op: &ScimPatchOperation) -> Option<bool> {
+    if !op.op.eq_ignore_ascii_case("replace") {
+        return None;
+    }
+    match (op.path.as_deref(), &op.value) {
+        (Some(path), Some(value)) if path.eq_ignore_ascii_case("active") => {
+            value_as_bool(value)
+        }
+        (None, Some(serde_json::Value::Object(map))) => {
+            map.get("active").and_then(value_as_bool)
+        }
+        _ => None,
+    }
+}
+
+fn value_as_bool(value: &serde_json::Value) -> Option<bool> {
+    match value {
+        serde_json::Value::Bool(b) => Some(*b),
+        // Some identity providers send booleans as strings.
+        serde_json::Value::String(s) if s.eq_ignore_ascii_case("true") => {
+            Some(true)
+        }
+        serde_json::Value::String(s) if s.eq_ignore_ascii_case("false") => {
+            Some(false)
+        }
+        _ => None,
+    }
+}
+
+/// Extracts the user ids from the value of a group membership PATCH operation
+fn patch_member_ids(
+    value: &Option<serde_json::Value>,
+) -> Result<Vec<Uuid>, Error> {
+    let Some(value) = value else { return Ok(Vec::new()) };
+    let members: Vec<ScimGroupMember> = match value {
+        serde_json::Value::Array(_) => serde_json::from_value(value.clone()),
+        _ => serde_json::from_value(value.clone()).map(|m| vec![m]),
+    }
+    .map_err(|e| {
+        Error::invalid_request(&format!("invalid group members: {}", e))
+    })?;
+    Ok(members.into_iter().map(|m| m.value).collect())
+}
+
+/// Extracts the user id from a PATCH path like `members[value eq "<id>"]`
+fn patch_path_member_id(path: &str) -> Result<Option<Uuid>, Error> {
+    let Some(filter) =
+        path.strip_prefix("members[").and_then(|p| p.strip_suffix(']'))
+    else {
+        return Ok(None);
+    };
+    parse_scim_filter(&Some(filter.to_string()), "value")?
+        .map(|id| {
+            id.parse::<Uuid>().map_err(|_| {
+                Error::invalid_request(&format!("invalid member id: {:?}", id))
+            })
+        })
+        .transpose()
+}
+
+// Users
+
+/// List users (SCIM)
+#[endpoint {
+    method = GET,
+    path = "/scim/v2/Users",
+    unpublished = true,
+}]
+pub(crate) async fn scim_user_list(
+    rqctx: RequestContext<ApiContext>,
+    query_params: Query<ScimListParams>,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().context.nexus;
+    let query = query_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) = scim_authenticate(&rqctx).await?;
+        let user_name = parse_scim_filter(&query.filter, "userName")?;
+        let users: Vec<ScimUser> = nexus
+            .scim_user_list(opctx, &authz_silo, user_name.as_deref())
+            .await?
+            .into_iter()
+            .map(ScimUser::from)
+            .collect();
+        scim_list_response(users, &query)
+    };
+    scim_result(handler.await)
+}
+
+/// Create user (SCIM)
+#[endpoint {
+    method = POST,
+    path = "/scim/v2/Users",
+    unpublished = true,
+}]
+pub(crate) async fn scim_user_create(
+    rqctx: RequestContext<ApiContext>,
+    body: UntypedBody,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().context.nexus;
+    let handler = async {
+        let (opctx, authz_silo) = scim_authenticate(&rqctx).await?;
+        let params: ScimUserCreate = parse_scim_body(&body)?;
+        let user = nexus
+            .scim_user_create(
+                opctx,
+                &authz_silo,
+                params.user_name,
+                params.active,
+            )
+            .await?;
+        build_scim_response(StatusCode::CREATED, &ScimUser::from(user))
+            .map_err(|e| Error::internal_error(&e.internal_message))
+    };
+    scim_result(handler.await)
+}
+
+/// Fetch user (SCIM)
+#[endpoint {
+    method = GET,
+    path = "/scim/v2/Users/{user_id}",
+    unpublished = true,
+}]
+pub(crate) async fn scim_user_view(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<ScimUserPathParam>,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().context.nexus;
+    let path = path_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) = scim_authenticate(&rqctx).await?;
+        let user =
+            nexus.scim_user_fetch(opctx, &authz_silo, path.user_id).await?;
+        build_scim_response(StatusCode::OK, &ScimUser::from(user))
+            .map_err(|e| Error::internal_error(&e.internal_message))
+    };
+    scim_result(handler.await)
+}
+
+/// Replace user (SCIM)
+///
+/// The only supported change is deactivating the user (setting `active` to
+/// false), which prevents them from logging in, or reactivating them.
+#[endpoint {
+    method = PUT,
+    path = "/scim/v2/Users/{user_id}",
+    unpublished = true,
+}]
+pub(crate) async fn scim_user_replace(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<ScimUserPathParam>,
+    body: UntypedBody,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().context.nexus;
+    let path = path_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) = scim_authenticate(&rqctx).await?;
+        let params: ScimUserCreate = parse_scim_body(&body)?;
+        let user =
+            nexus.scim_user_fetch(opctx, &authz_silo, path.user_id).await?;
+        if params.user_name != user.external_id {
+            return Err(Error::invalid_request("userName cannot be changed"));
+        }
+        let user = nexus
+            .scim_user_set_active(
+                opctx,
+                &authz_silo,
+                path.user_id,
+                params.active,
+            )
+            .await?;
+        build_scim_response(StatusCode::OK, &ScimUser::from(user))
+            .map_err(|e| Error::internal_error(&e.internal_message))
+    };
+    scim_result(handler.await)
+}
+
+/// Update user (SCIM)
+///
+/// The only supported change is deactivating the user (setting `active` to
+/// false), which prevents them from logging in, or reactivating them.  Other
+/// operations are ignored.
+#[endpoint {
+    method = PATCH,
+    path = "/scim/v2/Users/{user_id}",
+    unpublished = true,
+}]
+pub(crate) async fn scim_user_update(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<ScimUserPathParam>,
+    body: UntypedBody,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().context.nexus;
+    let path = path_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) = scim_authenticate(&rqctx).await?;
+        let params: ScimPatchRequest = parse_scim_body(&body)?;
+        if !params.schemas.iter().any(|s| s == SCHEMA_PATCH_OP) {
+            return Err(Error::invalid_request("expected PatchOp schema"));
+        }
+        // If several operations set `active`, the last one wins.
+        let active =
+            params.operations.iter().filter_map(patch_user_active).last();
+        let user = match active {
+            Some(active) => {
+                nexus
+                    .scim_user_set_active(
+                        opctx,
+                        &authz_silo,
+                        path.user_id,
+                        active,
+                    )
+                    .await?
+            }
+            None => {
+                nexus.scim_user_fetch(opctx, &authz_silo, path.user_id).await?
+            }
+        };
+        build_scim_response(StatusCode::OK, &ScimUser::from(user))
+            .map_err(|e| Error::internal_error(&e.internal_message))
+    };
+    scim_result(handler.await)
+}
+
+/// Delete user (SCIM)
+///
+/// This deprovisions the user, revoking their console sessions and device
+/// access tokens.
+#[endpoint {
+    method = DELETE,
+    path = "/scim/v2/Users/{user_id}",
+    unpublished = true,
+}]
+pub(crate) async fn scim_user_delete(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<ScimUserPathParam>,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().context.nexus;
+    let path = path_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) = scim_authenticate(&rqctx).await?;
+        nexus.scim_user_delete(opctx, &authz_silo, path.user_id).await?;
+        build_scim_empty_response(StatusCode::NO_CONTENT)
+            .map_err(|e| Error::internal_error(&e.internal_message))
+    };
+    scim_result(handler.await)
+}
+
+// Groups
+
+async fn scim_group_view_internal(
+    nexus: &crate::Nexus,
+    opctx: &OpContext,
+    authz_silo: &authz::Silo,
+    group_id: Uuid,
+) -> Result<ScimGroup, Error> {
+    let (authz_silo_group, db_silo_group) =
+        nexus.scim_group_fetch(opctx, authz_silo, group_id).await?;
+    let members =
+        nexus.scim_group_members(opctx, authz_silo, &authz_silo_group).await?;
+    Ok(ScimGroup::new(db_silo_group, members))
+}
+
+/// List groups (SCIM)
+#[endpoint {
+    method = GET,
+    path = "/scim/v2/Groups",
+    unpublished = true,
+}]
+pub(crate) async fn scim_group_list(
+    rqctx: RequestContext<ApiContext>,
+    query_params: Query<ScimListParams>,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().context.nexus;
+    let query = query_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) = scim_authenticate(&rqctx).await?;
+        let display_name = parse_scim_filter(&query.filter, "displayName")?;
+        let groups = nexus
+            .scim_group_list(opctx, &authz_silo, display_name.as_deref())
+            .await?;
+        let mut scim_groups = Vec::with_capacity(groups.len());
+        for group in groups {
+            scim_groups.push(
+                scim_group_view_internal(nexus, opctx, &authz_silo, group.id())
+                    .await?,
+            );
+        }
+        scim_list_response(scim_groups, &query)
+    };
+    scim_result(handler.await)
+}
+
+/// Create group (SCIM)
+#[endpoint {
+    method = POST,
+    path = "/scim/v2/Groups",
+    unpublished = true,
+}]
+pub(crate) async fn scim_group_create(
+    rqctx: RequestContext<ApiContext>,
+    body: UntypedBody,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().context.nexus;
+    let handler = async {
+        let (opctx, authz_silo) = scim_authenticate(&rqctx).await?;
+        let params: ScimGroupCreate = parse_scim_body(&body)?;
+        let member_ids: Vec<Uuid> =
+            params.members.iter().map(|m| m.value).collect();
+        let group = nexus
+            .scim_group_create(
+                opctx,
+                &authz_silo,
+                params.display_name,
+                &member_ids,
+            )
+            .await?;
+        let scim_group =
+            scim_group_view_internal(nexus, opctx, &authz_silo, group.id())
+                .await?;
+        build_scim_response(StatusCode::CREATED, &scim_group)
+            .map_err(|e| Error::internal_error(&e.internal_message))
+    };
+    scim_result(handler.await)
+}
+
+/// Fetch group (SCIM)
+#[endpoint {
+    method = GET,
+    path = "/scim/v2/Groups/{group_id}",
+    unpublished = true,
+}]
+pub(crate) async fn scim_group_view(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<ScimGroupPathParam>,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().context.nexus;
+    let path = path_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) = scim_authenticate(&rqctx).await?;
+        let scim_group =
+            scim_group_view_internal(nexus, opctx, &authz_silo, path.group_id)
+                .await?;
+        build_scim_response(StatusCode::OK, &scim_group)
+            .map_err(|e| Error::internal_error(&e.internal_message))
+    };
+    scim_result(handler.await)
+}
+
+/// Update group membership (SCIM)
+///
+/// Supports adding and removing members.  Renaming groups is not supported.
+#[endpoint {
+    method = PATCH,
+    path = "/scim/v2/Groups/{group_id}",
+    unpublished = true,
+}]
+pub(crate) async fn scim_group_update(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<ScimGroupPathParam>,
+    body: UntypedBody,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().context.nexus;
+    let path = path_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) = scim_authenticate(&rqctx).await?;
+        let params: ScimPatchRequest = parse_scim_body(&body)?;
+        if !params.schemas.iter().any(|s| s == SCHEMA_PATCH_OP) {
+            return Err(Error::invalid_request("expected PatchOp schema"));
+        }
+
+        // Make sure the group exists (and is in this Silo) before we start
+        // changing memberships.
+        let (authz_silo_group, _) =
+            nexus.scim_group_fetch(opctx, &authz_silo, path.group_id).await?;
+
+        for op in &params.operations {
+            let path_member = match &op.path {
+                Some(p) => patch_path_member_id(p)?,
+                None => None,
+            };
+            let is_members_path = op.path.as_deref() == Some("members");
+            let (additions, removals): (Vec<Uuid>, Vec<Uuid>) =
+                match op.op.to_ascii_lowercase().as_str() {
+                    "add" if is_members_path => {
+                        (patch_member_ids(&op.value)?, Vec::new())
+                    }
+                    "remove" if is_members_path => {
+                        (Vec::new(), patch_member_ids(&op.value)?)
+                    }
+                    "remove" if path_member.is_some() => {
+                        (Vec::new(), path_member.into_iter().collect())
+                    }
+                    "replace" if is_members_path => {
+                        let new_members = patch_member_ids(&op.value)?;
+                        let old_members = nexus
+                            .scim_group_members(
+                                opctx,
+                                &authz_silo,
+                                &authz_silo_group,
+                            )
+                            .await?
+                            .into_iter()
+                            .map(|u| u.id())
+                            .filter(|id| !new_members.contains(id))
+                            .collect();
+                        (new_members, old_members)
+                    }
+                    _ => {
+                        return Err(Error::invalid_request(&format!(
+                            "unsupported group PATCH operation: {:?} {:?}",
+                            op.op, op.path
+                        )));
+                    }
+                };
+
+            for silo_user_id in additions {
+                nexus
+                    .scim_group_membership_set(
+                        opctx,
+                        &authz_silo,
+                        path.group_id,
+                        silo_user_id,
+                        true,
+                    )
+                    .await?;
+            }
+            for silo_user_id in removals {
+                nexus
+                    .scim_group_membership_set(
+                        opctx,
+                        &authz_silo,
+                        path.group_id,
+                        silo_user_id,
+                        false,
+                    )
+                    .await?;
+            }
+        }
+
+        let scim_group =
+            scim_group_view_internal(nexus, opctx, &authz_silo, path.group_id)
+                .await?;
+        build_scim_response(StatusCode::OK, &scim_group)
+            .map_err(|e| Error::internal_error(&e.internal_message))
+    };
+    scim_result(handler.await)
+}
+
+/// Delete group (SCIM)
+#[endpoint {
+    method = DELETE,
+    path = "/scim/v2/Groups/{group_id}",
+    unpublished = true,
+}]
+pub(crate) async fn scim_group_delete(
+    rqctx: RequestContext<ApiContext>,
+    path_params: Path<ScimGroupPathParam>,
+) -> Result<Response<Body>, HttpError> {
+    let nexus = &rqctx.context().context.nexus;
+    let path = path_params.into_inner();
+    let handler = async {
+        let (opctx, authz_silo) = scim_authenticate(&rqctx).await?;
+        nexus.scim_group_delete(opctx, &authz_silo, path.group_id).await?;
+        build_scim_empty_response(StatusCode::NO_CONTENT)
+            .map_err(|e| Error::internal_error(&e.internal_message))
+    };
+    scim_result(handler.await)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_parse_scim_filter() {
+        assert_eq!(parse_scim_filter(&None, "userName").unwrap(), None);
+        assert_eq!(
+            parse_scim_filter(
+                &Some(String::from("userName eq \"alice@example.com\"")),
+                "userName"
+            )
+            .unwrap(),
+            Some(String::from("alice@example.com"))
+        );
+        assert_eq!(
+            parse_scim_filter(
+                &Some(String::from("username EQ \"two words\"")),
+                "userName"
+            )
+            .unwrap(),
+            Some(String::from("two words"))
+        );
+        for bad in [
+            "userName sw \"alice\"",
+            "displayName eq \"alice\"",
+            "userName eq alice",
+            "userName",
+        ] {
+            parse_scim_filter(&Some(String::from(bad)), "userName")
+                .expect_err(bad);
+        }
+    }
+
+    #[test]
+    fn test_patch_user_active() {
+        let by_path: ScimPatchOperation = serde_json::from_value(
+            serde_json::json!({"op": "Replace", "path": "active", "value": false}),
+        )
+        .unwrap();
+        assert_eq!(patch_user_active(&by_path), Some(false));
+
+        let by_value: ScimPatchOperation = serde_json::from_value(
+            serde_json::json!({"op": "replace", "value": {"active": "False"}}),
+        )
+        .unwrap();
+        assert_eq!(patch_user_active(&by_value), Some(false));
+
+        let reactivate: ScimPatchOperation = serde_json::from_value(
+            serde_json::json!({"op": "replace", "path": "active", "value": true}),
+        )
+        .unwrap();
+        assert_eq!(patch_user_active(&reactivate), Some(true));
+
+        let other: ScimPatchOperation = serde_json::from_value(
+            serde_json::json!({"op": "replace", "path": "name.givenName", "value": "Al"}),
+        )
+        .unwrap();
+        assert_eq!(patch_user_active(&other), None);
+    }
+
+    #[test]
+    fn test_patch_path_member_id() {
+        let id = Uuid::new_v4();
+        assert_eq!(
+            patch_path_member_id(&format!("members[value eq \"{}\"]", id))
+                .unwrap(),
+            Some(id)
+        );
+        assert_eq!(patch_path_member_id("members").unwrap(), None);
+        patch_path_member_id("members[value eq \"not-a-uuid\"]")
+            .expect_err("expected bad uuid to fail");
+    }
+}
@@ -17,6 +17,9 @@ use dropshot::HandlerTaskMode;
 use futures::future::BoxFuture;
 use futures::FutureExt;
 use gateway_test_utils::setup::GatewayTestContext;
+use http_testing::AuthnMode;
+use http_testing::NexusRequest;
+use http_testing::RequestBuilder;
 use nexus_config::Database;
 use nexus_config::DpdConfig;
 use nexus_config::InternalDns;
@@ -32,7 +35,11 @@ use nexus_types::deployment::BlueprintZoneType;
 use nexus_types::deployment::BlueprintZonesConfig;
 use nexus_types::deployment::OmicronZoneExternalFloatingAddr;
 use nexus_types::deployment::OmicronZoneExternalFloatingIp;
+use nexus_types::deployment::PendingMgsUpdates;
+use nexus_types::external_api::params::SledProvisionPolicyParams;
+use nexus_types::external_api::params::SledSelector;
 use nexus_types::external_api::params::UserId;
+use nexus_types::external_api::views::SledProvisionPolicy;
 use nexus_types::external_api::views::SledState;
 use nexus_types::internal_api::params::Certificate;
 use nexus_types::internal_api::params::DatasetCreateRequest;
@@ -70,6 +77,7 @@ use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::net::{IpAddr, Ipv6Addr, SocketAddr, SocketAddrV6};
+use std::sync::Arc;
 use std::time::Duration;
 use trust_dns_resolver::config::NameServerConfig;
 use trust_dns_resolver::config::Protocol;
@@ -78,12 +86,18 @@ use trust_dns_resolver::config::ResolverOpts;
 use trust_dns_resolver::TokioAsyncResolver;
 use uuid::Uuid;
 
+pub use sim::SimMode;
 pub use sim::TEST_HARDWARE_THREADS;
 pub use sim::TEST_RESERVOIR_RAM;
 
 pub mod db;
 pub mod http_testing;
 pub mod resource_helpers;
+pub mod sim_rack;
+
+use sim_rack::SimRackConfig;
+use sim_rack::SimSledConfig;
+use sim_rack::SimSledPolicy;
 
 pub const SLED_AGENT_UUID: &str = "b6d65341-167c-41df-9b5c-41cded99c229";
 pub const SLED_AGENT2_UUID: &str = "039be560-54cc-49e3-88df-1a29dadbf913";
@@ -101,6 +115,13 @@ pub const RACK_SUBNET: &str = "fd00:1122:3344:0100::/56";
 /// both transient deployments with no sensitive data.
 pub const TEST_SUITE_PASSWORD: &str = "oxide";
 
+/// A simulated sled agent other than the two that host the control plane's
+/// services
+pub struct ExtraSledAgent {
+    pub storage: camino_tempfile::Utf8TempDir,
+    pub server: sim::Server,
+}
+
 pub struct ControlPlaneTestContext<N> {
     pub start_time: chrono::DateTime<chrono::Utc>,
     pub external_client: ClientTestContext,
@@ -114,6 +135,7 @@ pub struct ControlPlaneTestContext<N> {
     pub sled_agent: sim::Server,
     pub sled_agent2_storage: camino_tempfile::Utf8TempDir,
     pub sled_agent2: sim::Server,
+    pub extra_sled_agents: Vec<ExtraSledAgent>,
     pub oximeter: Oximeter,
     pub producer: ProducerServer,
     pub gateway: HashMap<SwitchLocation, GatewayTestContext>,
@@ -132,12 +154,113 @@ impl<N: NexusServer> ControlPlaneTestContext<N> {
         format!("*.sys.{}", self.external_dns_zone_name)
     }
 
+    /// Returns every running simulated sled agent
+    pub fn sled_agents(&self) -> impl Iterator<Item = &sim::Server> {
+        [&self.sled_agent, &self.sled_agent2]
+            .into_iter()
+            .chain(self.extra_sled_agents.iter().map(|extra| &extra.server))
+    }
+
+    /// Returns the simulated sled agent with id `sled_id`, if there is one
+    pub fn sled_agent_by_id(
+        &self,
+        sled_id: Uuid,
+    ) -> Option<&Arc<sim::SledAgent>> {
+        self.sled_agents()
+            .map(|server| &server.sled_agent)
+            .find(|sled_agent| sled_agent.id == sled_id)
+    }
+
+    /// Starts a new simulated sled agent described by `sled`, waits for it to
+    /// register with Nexus, and then applies the sled's policy
+    ///
+    /// Returns the new sled's id.
+    pub async fn add_sled_agent(
+        &mut self,
+        sled: &SimSledConfig,
+        sim_mode: sim::SimMode,
+    ) -> Result<Uuid> {
+        let sled_id = sled.id.unwrap_or_else(Uuid::new_v4);
+        if self.sled_agent_by_id(sled_id).is_some() {
+            anyhow::bail!("sled {sled_id} is already running");
+        }
+        let nexus_address =
+            self.server.get_http_server_internal_address().await;
+        let extra = start_extra_sled_agent(
+            &self.logctx.log,
+            nexus_address,
+            sled_id,
+            sled,
+            sim_mode,
+        )
+        .await?;
+        self.extra_sled_agents.push(extra);
+        set_sled_policy(
+            &self.external_client,
+            &self.internal_client,
+            sled_id,
+            sled.policy,
+        )
+        .await?;
+        Ok(sled_id)
+    }
+
+    /// Stops the simulated sled agent `sled_id` and expunges its sled, as
+    /// though it had been pulled from the rack
+    ///
+    /// Only sleds other than the two that host the control plane's services
+    /// can be removed.
+    pub async fn remove_sled_agent(&mut self, sled_id: Uuid) -> Result<()> {
+        let Some(index) = self
+            .extra_sled_agents
+            .iter()
+            .position(|extra| extra.server.sled_agent.id == sled_id)
+        else {
+            anyhow::bail!(
+                "sled {sled_id} is not a simulated sled that can be removed"
+            );
+        };
+        let extra = self.extra_sled_agents.remove(index);
+        extra
+            .server
+            .http_server
+            .close()
+            .await
+            .map_err(|e| anyhow::anyhow!(e))
+            .with_context(|| format!("stopping sled agent {sled_id}"))?;
+        set_sled_policy(
+            &self.external_client,
+            &self.internal_client,
+            sled_id,
+            SimSledPolicy::Expunged,
+        )
+        .await
+    }
+
+    /// Replaces the faults being injected into every simulated sled agent
+    ///
+    /// Tests that need different faults on different sleds can use
+    /// [`Self::sled_agent_by_id()`] and set them on each sled agent directly.
+    pub fn set_sled_agent_faults(&self, faults: sim::SimFaults) {
+        for server in self.sled_agents() {
+            server.sled_agent.set_faults(faults.clone());
+        }
+    }
+
+    /// Stops injecting faults into every simulated sled agent
+    pub fn clear_sled_agent_faults(&self) {
+        self.set_sled_agent_faults(sim::SimFaults::default());
+    }
+
     pub async fn teardown(mut self) {
         self.server.close().await;
         self.database.cleanup().await.unwrap();
         self.clickhouse.cleanup().await.unwrap();
         self.sled_agent.http_server.close().await.unwrap();
         self.sled_agent2.http_server.close().await.unwrap();
+        for extra in self.extra_sled_agents {
+            extra.server.http_server.close().await.unwrap();
+        }
         self.oximeter.close().await.unwrap();
         self.producer.close().await.unwrap();
         for (_, gateway) in self.gateway {
@@ -268,12 +391,16 @@ pub struct ControlPlaneTestContextBuilder<'a, N: NexusServer> {
     pub sled_agent: Option<sim::Server>,
     pub sled_agent2_storage: Option<camino_tempfile::Utf8TempDir>,
     pub sled_agent2: Option<sim::Server>,
+    pub extra_sled_agents: Vec<ExtraSledAgent>,
     pub oximeter: Option<Oximeter>,
     pub producer: Option<ProducerServer>,
     pub gateway: HashMap<SwitchLocation, GatewayTestContext>,
     pub dendrite: HashMap<SwitchLocation, dev::dendrite::DendriteInstance>,
     pub mgd: HashMap<SwitchLocation, dev::maghemite::MgdInstance>,
 
+    /// The rack being simulated
+    pub rack_config: SimRackConfig,
+
     // NOTE: Only exists after starting Nexus, until external Nexus is
     // initialized.
     nexus_internal: Option<<N as NexusServer>::InternalServer>,
@@ -318,11 +445,13 @@ impl<'a, N: NexusServer> ControlPlaneTestContextBuilder<'a, N> {
             sled_agent: None,
             sled_agent2_storage: None,
             sled_agent2: None,
+            extra_sled_agents: Vec::new(),
             oximeter: None,
             producer: None,
             gateway: HashMap::new(),
             dendrite: HashMap::new(),
             mgd: HashMap::new(),
+            rack_config: SimRackConfig::default(),
             nexus_internal: None,
             nexus_internal_addr: None,
             external_dns_zone_name: None,
@@ -488,7 +617,10 @@ impl<'a, N: NexusServer> ControlPlaneTestContextBuilder<'a, N> {
     ) {
         debug!(&self.logctx.log, "Starting Management Gateway");
         let (mgs_config, sp_sim_config) =
-            gateway_test_utils::setup::load_test_config();
+            gateway_test_utils::setup::load_config_with_overrides(
+                self.rack_config.mgs.mgs_config.as_deref(),
+                self.rack_config.mgs.sp_sim_config.as_deref(),
+            );
         let mgs_addr =
             port.map(|port| SocketAddrV6::new(Ipv6Addr::LOCALHOST, port, 0, 0));
         let gateway = gateway_test_utils::setup::test_setup_with_config(
@@ -783,6 +915,7 @@ impl<'a, N: NexusServer> ControlPlaneTestContextBuilder<'a, N> {
                 //
                 // However, for now, this isn't necessary.
                 blueprint_disks: BTreeMap::new(),
+                pending_mgs_updates: PendingMgsUpdates::new(),
                 sled_state,
                 parent_blueprint_id: None,
                 internal_dns_version: dns_config
@@ -876,15 +1009,14 @@ impl<'a, N: NexusServer> ControlPlaneTestContextBuilder<'a, N> {
             self.nexus_internal_addr.expect("Must launch Nexus first");
 
         // Set up a single sled agent.
-        let sa_id: Uuid = if switch_location == SwitchLocation::Switch0 {
-            SLED_AGENT_UUID
-        } else {
-            SLED_AGENT2_UUID
-        }
-        .parse()
-        .unwrap();
+        let (sa_id, sled): (Uuid, _) =
+            if switch_location == SwitchLocation::Switch0 {
+                (SLED_AGENT_UUID.parse().unwrap(), &self.rack_config.sleds[0])
+            } else {
+                (SLED_AGENT2_UUID.parse().unwrap(), &self.rack_config.sleds[1])
+            };
         let tempdir = camino_tempfile::tempdir().unwrap();
-        let sled_agent = start_sled_agent(
+        let sled_agent = start_sled_agent_with_zpools(
             self.logctx.log.new(o!(
                 "component" => "omicron_sled_agent::sim::Server",
                 "sled_id" => sa_id.to_string(),
@@ -893,6 +1025,7 @@ impl<'a, N: NexusServer> ControlPlaneTestContextBuilder<'a, N> {
             sa_id,
             tempdir.path(),
             sim_mode,
+            Some(sled.zpools()),
         )
         .await
         .expect("Failed to start sled agent");
@@ -953,6 +1086,55 @@ impl<'a, N: NexusServer> ControlPlaneTestContextBuilder<'a, N> {
             .expect("Failed to write early networking config to bootstore");
     }
 
+    /// Starts the simulated sleds in the rack description beyond the first
+    /// two
+    pub async fn start_extra_sleds(&mut self, sim_mode: sim::SimMode) {
+        let nexus_address =
+            self.nexus_internal_addr.expect("Must launch Nexus first");
+        for sled in self.rack_config.sleds.iter().skip(2) {
+            let sled_id = sled.id.unwrap_or_else(Uuid::new_v4);
+            let extra = start_extra_sled_agent(
+                &self.logctx.log,
+                nexus_address,
+                sled_id,
+                sled,
+                sim_mode,
+            )
+            .await
+            .expect("Failed to start sled agent");
+            self.extra_sled_agents.push(extra);
+        }
+    }
+
+    /// Applies the policy from the rack description to each simulated sled
+    pub async fn set_sled_policies(&mut self) {
+        let external_client = self
+            .external_client
+            .as_ref()
+            .expect("Must start Nexus before setting sled policies");
+        let internal_client = self.internal_client.as_ref().unwrap();
+        let sled_ids = [
+            SLED_AGENT_UUID.parse().unwrap(),
+            SLED_AGENT2_UUID.parse().unwrap(),
+        ]
+        .into_iter()
+        .chain(
+            self.extra_sled_agents
+                .iter()
+                .map(|extra| extra.server.sled_agent.id),
+        );
+        for (sled_id, sled) in sled_ids.zip(&self.rack_config.sleds) {
+            set_sled_policy(
+                external_client,
+                internal_client,
+                sled_id,
+                sled.policy,
+            )
+            .await
+            .expect("Failed to set sled policy");
+        }
+    }
+
     // Set up the Crucible Pantry on an existing Sled Agent.
     pub async fn start_crucible_pantry(&mut self) {
         let sled_agent = self
@@ -1106,6 +1288,7 @@ impl<'a, N: NexusServer> ControlPlaneTestContextBuilder<'a, N> {
             sled_agent: self.sled_agent.unwrap(),
             sled_agent2_storage: self.sled_agent2_storage.unwrap(),
             sled_agent2: self.sled_agent2.unwrap(),
+            extra_sled_agents: self.extra_sled_agents,
             oximeter: self.oximeter.unwrap(),
             producer: self.producer.unwrap(),
             logctx: self.logctx,
@@ -1137,6 +1320,9 @@ impl<'a, N: NexusServer> ControlPlaneTestContextBuilder<'a, N> {
         if let Some(sled_agent2) = self.sled_agent2 {
             sled_agent2.http_server.close().await.unwrap();
         }
+        for extra in self.extra_sled_agents {
+            extra.server.http_server.close().await.unwrap();
+        }
         if let Some(oximeter) = self.oximeter {
             oximeter.close().await.unwrap();
         }
@@ -1186,9 +1372,12 @@ enum PopulateCrdb {
 #[cfg(feature = "omicron-dev")]
 pub async fn omicron_dev_setup_with_config<N: NexusServer>(
     config: &mut NexusConfig,
+    rack_config: SimRackConfig,
 ) -> Result<ControlPlaneTestContext<N>> {
-    let builder =
+    rack_config.validate().context("invalid rack description")?;
+    let mut builder =
         ControlPlaneTestContextBuilder::<N>::new("omicron-dev", config);
+    builder.rack_config = rack_config;
 
     let log = &builder.logctx.log;
     debug!(log, "Ensuring seed tarball exists");
@@ -1349,6 +1538,12 @@ async fn setup_with_config_impl<N: NexusServer>(
                             .boxed()
                     }),
                 ),
+                (
+                    "start_extra_sleds",
+                    Box::new(move |builder| {
+                        builder.start_extra_sleds(sim_mode).boxed()
+                    }),
+                ),
                 (
                     "start_nexus_external",
                     Box::new(|builder| {
@@ -1359,6 +1554,10 @@ async fn setup_with_config_impl<N: NexusServer>(
                             .boxed()
                     }),
                 ),
+                (
+                    "set_sled_policies",
+                    Box::new(|builder| builder.set_sled_policies().boxed()),
+                ),
                 (
                     "start_oximeter",
                     Box::new(|builder| builder.start_oximeter().boxed()),
@@ -1381,13 +1580,34 @@ pub async fn start_sled_agent(
     id: Uuid,
     update_directory: &Utf8Path,
     sim_mode: sim::SimMode,
+) -> Result<sim::Server, String> {
+    start_sled_agent_with_zpools(
+        log,
+        nexus_address,
+        id,
+        update_directory,
+        sim_mode,
+        None,
+    )
+    .await
+}
+
+/// Like [`start_sled_agent`], but with the given zpools rather than the
+/// simulated sled agent's default set
+pub async fn start_sled_agent_with_zpools(
+    log: Logger,
+    nexus_address: SocketAddr,
+    id: Uuid,
+    update_directory: &Utf8Path,
+    sim_mode: sim::SimMode,
+    zpools: Option<Vec<sim::ConfigZpool>>,
 ) -> Result<sim::Server, String> {
     let config = sim::Config::for_testing(
         id,
         sim_mode,
         Some(nexus_address),
         Some(update_directory),
-        None,
+        zpools,
     );
     let server = sim::Server::start(&config, &log, true)
         .await
@@ -1395,6 +1615,79 @@ pub async fn start_sled_agent(
     Ok(server)
 }
 
+async fn start_extra_sled_agent(
+    log: &Logger,
+    nexus_address: SocketAddr,
+    sled_id: Uuid,
+    sled: &SimSledConfig,
+    sim_mode: sim::SimMode,
+) -> Result<ExtraSledAgent> {
+    let storage = camino_tempfile::tempdir()
+        .context("creating sled agent storage directory")?;
+    let server = start_sled_agent_with_zpools(
+        log.new(o!(
+            "component" => "omicron_sled_agent::sim::Server",
+            "sled_id" => sled_id.to_string(),
+        )),
+        nexus_address,
+        sled_id,
+        storage.path(),
+        sim_mode,
+        Some(sled.zpools()),
+    )
+    .await
+    .map_err(|e| anyhow::anyhow!(e))
+    .with_context(|| format!("starting sled agent {sled_id}"))?;
+    Ok(ExtraSledAgent { storage, server })
+}
+
+/// Gives sled `sled_id` the operator policy `policy`
+///
+/// Sleds start out in service, so there's nothing to do for
+/// [`SimSledPolicy::InService`].
+async fn set_sled_policy(
+    external_client: &ClientTestContext,
+    internal_client: &ClientTestContext,
+    sled_id: Uuid,
+    policy: SimSledPolicy,
+) -> Result<()> {
+    match policy {
+        SimSledPolicy::InService => Ok(()),
+        SimSledPolicy::NonProvisionable => {
+            let params = SledProvisionPolicyParams {
+                state: SledProvisionPolicy::NonProvisionable,
+            };
+            NexusRequest::object_put(
+                external_client,
+                &format!(
+                    "/v1/system/hardware/sleds/{sled_id}/provision-policy"
+                ),
+                Some(&params),
+            )
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute()
+            .await
+            .with_context(|| {
+                format!("marking sled {sled_id} non-provisionable")
+            })?;
+            Ok(())
+        }
+        SimSledPolicy::Expunged => {
+            RequestBuilder::new(
+                internal_client,
+                http::Method::POST,
+                "/sleds/expunge",
+            )
+            .body(Some(&SledSelector { sled: sled_id }))
+            .expect_status(Some(http::StatusCode::OK))
+            .execute()
+            .await
+            .with_context(|| format!("expunging sled {sled_id}"))?;
+            Ok(())
+        }
+    }
+}
+
 pub async fn start_oximeter(
     log: Logger,
     nexus_address: SocketAddr,
@@ -0,0 +1,332 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Description of a simulated rack
+//!
+//! By default, a [`crate::ControlPlaneTestContext`] simulates two sleds (one
+//! per switch), each with ten 1 TiB disks.  `omicron-dev run-all` can instead
+//! be given a [`SimRackConfig`] (usually from a TOML file) describing how many
+//! sleds to simulate, the disks on each one, the policy each sled should have
+//! once it has registered with Nexus, and which SPs MGS should find on the
+//! simulated management network.
+
+use anyhow::bail;
+use camino::Utf8PathBuf;
+use omicron_sled_agent::sim;
+use serde::Deserialize;
+use serde::Serialize;
+use std::collections::BTreeSet;
+use uuid::Uuid;
+
+/// Description of a simulated rack
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SimRackConfig {
+    /// Sleds to simulate, in the order they're started
+    ///
+    /// The first two sleds host the control plane's own services (and are
+    /// the ones each switch's services are associated with), so there must be
+    /// at least two and neither may be expunged.
+    #[serde(default = "default_sleds")]
+    pub sleds: Vec<SimSledConfig>,
+
+    /// How to simulate the management network
+    #[serde(default)]
+    pub mgs: SimMgsConfig,
+}
+
+impl Default for SimRackConfig {
+    fn default() -> Self {
+        SimRackConfig { sleds: default_sleds(), mgs: SimMgsConfig::default() }
+    }
+}
+
+fn default_sleds() -> Vec<SimSledConfig> {
+    vec![SimSledConfig::default(); 2]
+}
+
+impl SimRackConfig {
+    /// Checks that this rack can actually be simulated
+    pub fn validate(&self) -> anyhow::Result<()> {
+        if self.sleds.len() < 2 {
+            bail!(
+                "a simulated rack needs at least 2 sleds (found {})",
+                self.sleds.len()
+            );
+        }
+        for (i, sled) in self.sleds.iter().take(2).enumerate() {
+            if sled.policy == SimSledPolicy::Expunged {
+                bail!(
+                    "sled {i} hosts control plane services and cannot be \
+                     expunged"
+                );
+            }
+        }
+
+        let mut ids = BTreeSet::new();
+        for (i, sled) in self.sleds.iter().enumerate() {
+            if sled.disk_size_gib == 0 {
+                bail!("sled {i}: disk_size_gib must be greater than zero");
+            }
+            if sled.disk_size_bytes().is_none() {
+                bail!(
+                    "sled {i}: disk_size_gib is too large ({} GiB)",
+                    sled.disk_size_gib
+                );
+            }
+            if let Some(id) = sled.id {
+                if i < 2 {
+                    bail!(
+                        "sled {i}: the ids of the first two sleds are fixed \
+                         and cannot be configured"
+                    );
+                }
+                if !ids.insert(id) {
+                    bail!("sled {i}: duplicate sled id {id}");
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Description of one simulated sled
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SimSledConfig {
+    /// The sled's id
+    ///
+    /// If unset, a random id is chosen.  This can only be set for sleds after
+    /// the first two.
+    #[serde(default)]
+    pub id: Option<Uuid>,
+
+    /// Number of (simulated) U.2 disks in the sled, each of which gets a
+    /// zpool
+    #[serde(default = "default_disks")]
+    pub disks: usize,
+
+    /// Size of each disk, in GiB
+    #[serde(default = "default_disk_size_gib")]
+    pub disk_size_gib: u64,
+
+    /// Policy to give the sled once it has registered with Nexus
+    #[serde(default)]
+    pub policy: SimSledPolicy,
+}
+
+fn default_disks() -> usize {
+    10
+}
+
+fn default_disk_size_gib() -> u64 {
+    1024
+}
+
+impl Default for SimSledConfig {
+    fn default() -> Self {
+        SimSledConfig {
+            id: None,
+            disks: default_disks(),
+            disk_size_gib: default_disk_size_gib(),
+            policy: SimSledPolicy::default(),
+        }
+    }
+}
+
+impl SimSledConfig {
+    /// Returns the zpools the simulated sled agent should report
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size of each disk doesn't fit in a `u64` (which
+    /// [`SimRackConfig::validate()`] checks for).
+    pub fn zpools(&self) -> Vec<sim::ConfigZpool> {
+        let size = self.disk_size_bytes().unwrap_or_else(|| {
+            panic!("disk size too large: {} GiB", self.disk_size_gib)
+        });
+        vec![sim::ConfigZpool { size }; self.disks]
+    }
+
+    /// Returns the size of each disk in bytes, or `None` if it doesn't fit
+    /// in a `u64`
+    fn disk_size_bytes(&self) -> Option<u64> {
+        self.disk_size_gib.checked_mul(1 << 30)
+    }
+}
+
+/// Operator policy applied to a simulated sled after it has registered
+#[derive(
+    Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SimSledPolicy {
+    /// The sled is in service and may have new resources placed on it.
+    #[default]
+    InService,
+    /// The sled is in service but new resources won't be placed on it.
+    NonProvisionable,
+    /// The sled has been permanently removed from service.
+    Expunged,
+}
+
+/// Description of the simulated management network
+///
+/// MGS is always started for both switches, since Nexus expects to find it.
+/// By default it uses the test suite's configuration, which simulates two
+/// sidecars and two gimlets.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SimMgsConfig {
+    /// MGS configuration file to use instead of the test suite's
+    ///
+    /// This must describe a switch port for each SP in `sp_sim_config`.
+    #[serde(default)]
+    pub mgs_config: Option<Utf8PathBuf>,
+
+    /// SP simulator configuration file to use instead of the test suite's
+    #[serde(default)]
+    pub sp_sim_config: Option<Utf8PathBuf>,
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn sled(policy: SimSledPolicy) -> SimSledConfig {
+        SimSledConfig { policy, ..SimSledConfig::default() }
+    }
+
+    fn assert_invalid(config: &SimRackConfig, expected: &str) {
+        let error = config
+            .validate()
+            .expect_err("config should be invalid")
+            .to_string();
+        assert!(
+            error.contains(expected),
+            "error {error:?} doesn't contain {expected:?}"
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        let mut config = SimRackConfig::default();
+        config.validate().expect("default config is valid");
+
+        // Sleds after the first two may be expunged or have their ids set.
+        config.sleds.push(sled(SimSledPolicy::Expunged));
+        config.sleds.push(SimSledConfig {
+            id: Some(Uuid::new_v4()),
+            ..sled(SimSledPolicy::NonProvisionable)
+        });
+        config.validate().expect("extra sleds are valid");
+
+        let too_few = SimRackConfig {
+            sleds: vec![SimSledConfig::default()],
+            ..SimRackConfig::default()
+        };
+        assert_invalid(&too_few, "at least 2 sleds (found 1)");
+
+        let mut expunged = config.clone();
+        expunged.sleds[1].policy = SimSledPolicy::Expunged;
+        assert_invalid(&expunged, "sled 1 hosts control plane services");
+
+        let mut fixed_id = config.clone();
+        fixed_id.sleds[0].id = Some(Uuid::new_v4());
+        assert_invalid(&fixed_id, "sled 0: the ids of the first two sleds");
+
+        let mut duplicate_id = config.clone();
+        duplicate_id.sleds[2].id = duplicate_id.sleds[3].id;
+        assert_invalid(&duplicate_id, "sled 3: duplicate sled id");
+
+        let mut empty_disks = config.clone();
+        empty_disks.sleds[2].disk_size_gib = 0;
+        assert_invalid(&empty_disks, "sled 2: disk_size_gib must be greater");
+
+        // The largest disk whose size in bytes fits in a u64 is fine; one
+        // more GiB is not.
+        let mut huge_disks = config.clone();
+        huge_disks.sleds[3].disk_size_gib = u64::MAX >> 30;
+        huge_disks.validate().expect("largest disk size is valid");
+        assert_eq!(
+            huge_disks.sleds[3].zpools()[0].size,
+            (u64::MAX >> 30) << 30
+        );
+        huge_disks.sleds[3].disk_size_gib += 1;
+        assert_invalid(&huge_disks, "sled 3: disk_size_gib is too large");
+    }
+
+    #[test]
+    fn test_parse_toml() {
+        // Everything has a default.
+        let config: SimRackConfig = toml::from_str("").unwrap();
+        assert_eq!(config, SimRackConfig::default());
+        assert_eq!(config.sleds.len(), 2);
+        assert_eq!(config.sleds[0].zpools().len(), 10);
+        assert_eq!(config.sleds[0].zpools()[0].size, 1 << 40);
+
+        let id = Uuid::new_v4();
+        let config: SimRackConfig = toml::from_str(&format!(
+            r#"
+            [[sleds]]
+            [[sleds]]
+            disks = 4
+            [[sleds]]
+            id = "{id}"
+            disk_size_gib = 16
+            policy = "non_provisionable"
+
+            [mgs]
+            sp_sim_config = "sp-sim.toml"
+            "#
+        ))
+        .unwrap();
+        config.validate().unwrap();
+        assert_eq!(
+            config.sleds,
+            vec![
+                SimSledConfig::default(),
+                SimSledConfig { disks: 4, ..SimSledConfig::default() },
+                SimSledConfig {
+                    id: Some(id),
+                    disks: 10,
+                    disk_size_gib: 16,
+                    policy: SimSledPolicy::NonProvisionable,
+                },
+            ]
+        );
+        assert_eq!(
+            config.mgs,
+            SimMgsConfig {
+                mgs_config: None,
+                sp_sim_config: Some("sp-sim.toml".into()),
+            }
+        );
+
+        // Unknown fields are rejected at every level, so that typos don't
+        // silently fall back to the defaults.
+        for input in [
+            "sled = []",
+            "[[sleds]]\ndisk = 4",
+            "[mgs]\nmgs_conifg = \"mgs.toml\"",
+        ] {
+            let error = toml::from_str::<SimRackConfig>(input)
+                .expect_err("unknown field should be rejected");
+            assert!(
+                error.to_string().contains("unknown field"),
+                "unexpected error for {input:?}: {error}"
+            );
+        }
+
+        let error = toml::from_str::<SimRackConfig>(
+            "[[sleds]]\npolicy = \"decommissioned\"",
+        )
+        .expect_err("unknown policy should be rejected");
+        assert!(
+            error.to_string().contains("unknown variant"),
+            "unexpected error: {error}"
+        );
+    }
+}
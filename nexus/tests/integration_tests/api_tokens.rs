@@ -0,0 +1,381 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tests for user-managed API tokens and their scopes
+
+use chrono::{Duration, Utc};
+use dropshot::test_util::ClientTestContext;
+use http::{header, method::Method, StatusCode};
+use nexus_test_utils::http_testing::{
+    AuthnMode, NexusRequest, RequestBuilder, TestResponse,
+};
+use nexus_test_utils::resource_helpers::{
+    create_project, objects_list_page_authz,
+};
+use nexus_test_utils_macros::nexus_test;
+use nexus_types::external_api::params;
+use nexus_types::external_api::shared::ApiTokenScope;
+use nexus_types::external_api::views::{self, ApiToken, ApiTokenValue};
+use omicron_common::api::external::{
+    IdentityMetadataCreateParams, IdentityMetadataUpdateParams, Name,
+};
+
+type ControlPlaneTestContext =
+    nexus_test_utils::ControlPlaneTestContext<omicron_nexus::Server>;
+
+const TOKENS_URL: &str = "/v1/me/tokens";
+
+async fn token_create(
+    client: &ClientTestContext,
+    name: &str,
+    scopes: Vec<ApiTokenScope>,
+) -> ApiTokenValue {
+    NexusRequest::objects_post(
+        client,
+        TOKENS_URL,
+        &params::ApiTokenCreate {
+            identity: IdentityMetadataCreateParams {
+                name: name.parse().unwrap(),
+                description: format!("token {}", name),
+            },
+            time_expires: None,
+            scopes,
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute_and_parse_unwrap()
+    .await
+}
+
+/// Make a request authenticated only by `token`, expecting `status`
+async fn token_request(
+    client: &ClientTestContext,
+    token: &ApiTokenValue,
+    method: Method,
+    url: &str,
+    body: Option<serde_json::Value>,
+    status: StatusCode,
+) -> TestResponse {
+    RequestBuilder::new(client, method.clone(), url)
+        .header(header::AUTHORIZATION, format!("Bearer {}", token.bearer_token))
+        .body(body.as_ref())
+        .expect_status(Some(status))
+        .execute()
+        .await
+        .unwrap_or_else(|e| panic!("{} {}: {:#}", method, url, e))
+}
+
+#[nexus_test]
+async fn test_api_token_lifecycle(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    let tokens =
+        objects_list_page_authz::<ApiToken>(client, TOKENS_URL).await.items;
+    assert!(tokens.is_empty());
+
+    let token = token_create(client, "ci", vec![]).await;
+    assert!(token.bearer_token.starts_with("oxide-token-"));
+    assert!(token.scopes.is_empty());
+    assert_eq!(token.time_expires, None);
+
+    // Names are unique per user.
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        TOKENS_URL,
+        &params::ApiTokenCreate {
+            identity: IdentityMetadataCreateParams {
+                name: "ci".parse().unwrap(),
+                description: String::new(),
+            },
+            time_expires: None,
+            scopes: vec![],
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    // The token value is never shown again, and it hasn't been used yet.
+    let tokens =
+        objects_list_page_authz::<ApiToken>(client, TOKENS_URL).await.items;
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0].identity, token.identity);
+    assert_eq!(tokens[0].time_last_used, None);
+
+    // The token authenticates as its owner.
+    let me: views::CurrentUser = token_request(
+        client,
+        &token,
+        Method::GET,
+        "/v1/me",
+        None,
+        StatusCode::OK,
+    )
+    .await
+    .parsed_body()
+    .unwrap();
+    let me_session: views::CurrentUser =
+        NexusRequest::object_get(client, "/v1/me")
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute_and_parse_unwrap()
+            .await;
+    assert_eq!(me.user.id, me_session.user.id);
+
+    let viewed: ApiToken = NexusRequest::object_get(
+        client,
+        &format!("{}/{}", TOKENS_URL, token.identity.name),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute_and_parse_unwrap()
+    .await;
+    assert!(viewed.time_last_used.is_some());
+
+    // Tokens are private to their owner.
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::NOT_FOUND,
+        Method::GET,
+        &format!("{}/{}", TOKENS_URL, token.identity.id),
+    )
+    .authn_as(AuthnMode::UnprivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    // Once revoked, the token no longer works.
+    NexusRequest::object_delete(
+        client,
+        &format!("{}/{}", TOKENS_URL, token.identity.name),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+    token_request(
+        client,
+        &token,
+        Method::GET,
+        "/v1/me",
+        None,
+        StatusCode::UNAUTHORIZED,
+    )
+    .await;
+    let tokens =
+        objects_list_page_authz::<ApiToken>(client, TOKENS_URL).await.items;
+    assert!(tokens.is_empty());
+}
+
+#[nexus_test]
+async fn test_api_token_expiry(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    let create = |time_expires| params::ApiTokenCreate {
+        identity: IdentityMetadataCreateParams {
+            name: "short-lived".parse().unwrap(),
+            description: String::new(),
+        },
+        time_expires: Some(time_expires),
+        scopes: vec![],
+    };
+
+    // Tokens cannot be created already expired.
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        TOKENS_URL,
+        &create(Utc::now() - Duration::seconds(1)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    let token: ApiTokenValue = NexusRequest::objects_post(
+        client,
+        TOKENS_URL,
+        &create(Utc::now() + Duration::seconds(1)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute_and_parse_unwrap()
+    .await;
+    tokio::time::sleep(std::time::Duration::from_secs(2)).await;
+    token_request(
+        client,
+        &token,
+        Method::GET,
+        "/v1/me",
+        None,
+        StatusCode::UNAUTHORIZED,
+    )
+    .await;
+}
+
+#[nexus_test]
+async fn test_api_token_scopes(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    create_project(client, "springfield").await;
+    create_project(client, "shelbyville").await;
+
+    let update = serde_json::to_value(params::ProjectUpdate {
+        identity: IdentityMetadataUpdateParams {
+            name: None,
+            description: Some(String::from("updated")),
+        },
+    })
+    .unwrap();
+    let new_project = |name: &str| {
+        serde_json::to_value(params::ProjectCreate {
+            identity: IdentityMetadataCreateParams {
+                name: name.parse().unwrap(),
+                description: String::new(),
+            },
+        })
+        .unwrap()
+    };
+    let new_token = serde_json::to_value(params::ApiTokenCreate {
+        identity: IdentityMetadataCreateParams {
+            name: "escalate".parse().unwrap(),
+            description: String::new(),
+        },
+        time_expires: None,
+        scopes: vec![],
+    })
+    .unwrap();
+
+    // A read-only token can read anything its owner can, but modify nothing.
+    let read_only =
+        token_create(client, "read-only", vec![ApiTokenScope::ReadOnly]).await;
+    assert_eq!(read_only.scopes, vec![ApiTokenScope::ReadOnly]);
+    for url in ["/v1/projects", "/v1/projects/springfield", "/v1/me/tokens"] {
+        token_request(
+            client,
+            &read_only,
+            Method::GET,
+            url,
+            None,
+            StatusCode::OK,
+        )
+        .await;
+    }
+    token_request(
+        client,
+        &read_only,
+        Method::PUT,
+        "/v1/projects/springfield",
+        Some(update.clone()),
+        StatusCode::FORBIDDEN,
+    )
+    .await;
+    token_request(
+        client,
+        &read_only,
+        Method::POST,
+        "/v1/projects",
+        Some(new_project("ogdenville")),
+        StatusCode::FORBIDDEN,
+    )
+    .await;
+
+    // A project-scoped token can only touch that project.  Scopes are always
+    // reported by id.
+    let project_scoped = token_create(
+        client,
+        "springfield-only",
+        vec![ApiTokenScope::Project {
+            project: "springfield".parse::<Name>().unwrap().into(),
+        }],
+    )
+    .await;
+    let springfield: views::Project =
+        NexusRequest::object_get(client, "/v1/projects/springfield")
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute_and_parse_unwrap()
+            .await;
+    assert_eq!(
+        project_scoped.scopes,
+        vec![ApiTokenScope::Project {
+            project: springfield.identity.id.into()
+        }]
+    );
+    token_request(
+        client,
+        &project_scoped,
+        Method::PUT,
+        "/v1/projects/springfield",
+        Some(update.clone()),
+        StatusCode::OK,
+    )
+    .await;
+    token_request(
+        client,
+        &project_scoped,
+        Method::GET,
+        "/v1/projects/shelbyville",
+        None,
+        StatusCode::FORBIDDEN,
+    )
+    .await;
+    token_request(
+        client,
+        &project_scoped,
+        Method::PUT,
+        "/v1/projects/shelbyville",
+        Some(update.clone()),
+        StatusCode::FORBIDDEN,
+    )
+    .await;
+    token_request(
+        client,
+        &project_scoped,
+        Method::POST,
+        "/v1/projects",
+        Some(new_project("ogdenville")),
+        StatusCode::FORBIDDEN,
+    )
+    .await;
+
+    // Neither kind of scoped token can be used to mint an unscoped one.
+    for token in [&read_only, &project_scoped] {
+        token_request(
+            client,
+            token,
+            Method::POST,
+            TOKENS_URL,
+            Some(new_token.clone()),
+            StatusCode::FORBIDDEN,
+        )
+        .await;
+    }
+
+    // At most one project scope is allowed.
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        TOKENS_URL,
+        &params::ApiTokenCreate {
+            identity: IdentityMetadataCreateParams {
+                name: "two-projects".parse().unwrap(),
+                description: String::new(),
+            },
+            time_expires: None,
+            scopes: vec![
+                ApiTokenScope::Project {
+                    project: springfield.identity.id.into(),
+                },
+                ApiTokenScope::Project {
+                    project: "shelbyville".parse::<Name>().unwrap().into(),
+                },
+            ],
+        },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
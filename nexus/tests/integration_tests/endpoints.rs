@@ -51,6 +51,15 @@ pub static HARDWARE_SLED_URL: Lazy<String> =
 pub static HARDWARE_SLED_PROVISION_POLICY_URL: Lazy<String> = Lazy::new(|| {
     format!("/v1/system/hardware/sleds/{}/provision-policy", SLED_AGENT_UUID)
 });
+pub static HARDWARE_SLED_POWER_CYCLE_URL: Lazy<String> = Lazy::new(|| {
+    format!("/v1/system/hardware/sleds/{}/power-cycle", SLED_AGENT_UUID)
+});
+pub static HARDWARE_SLED_POWER_OFF_URL: Lazy<String> = Lazy::new(|| {
+    format!("/v1/system/hardware/sleds/{}/power-off", SLED_AGENT_UUID)
+});
+pub static HARDWARE_SLED_RESET_SP_URL: Lazy<String> = Lazy::new(|| {
+    format!("/v1/system/hardware/sleds/{}/reset-sp", SLED_AGENT_UUID)
+});
 pub static DEMO_SLED_PROVISION_POLICY: Lazy<params::SledProvisionPolicyParams> =
     Lazy::new(|| params::SledProvisionPolicyParams {
         state: SledProvisionPolicy::NonProvisionable,
@@ -102,6 +111,29 @@ pub static DEMO_SILO_CREATE: Lazy<params::SiloCreate> =
         mapped_fleet_roles: Default::default(),
     });
 
+// Silo provisioned via SCIM, used for testing SCIM client bearer tokens
+pub static DEMO_SCIM_SILO_NAME: Lazy<Name> =
+    Lazy::new(|| "demo-scim-silo".parse().unwrap());
+pub static DEMO_SCIM_SILO_CREATE: Lazy<params::SiloCreate> =
+    Lazy::new(|| params::SiloCreate {
+        identity: IdentityMetadataCreateParams {
+            name: DEMO_SCIM_SILO_NAME.clone(),
+            description: String::from(""),
+        },
+        quotas: params::SiloQuotasCreate::arbitrarily_high_default(),
+        discoverable: true,
+        identity_mode: shared::SiloIdentityMode::SamlScim,
+        admin_group_name: None,
+        tls_certificates: vec![],
+        mapped_fleet_roles: Default::default(),
+    });
+pub static DEMO_SCIM_TOKENS_URL: Lazy<String> = Lazy::new(|| {
+    format!("/v1/system/scim/tokens?silo={}", *DEMO_SCIM_SILO_NAME)
+});
+pub static DEMO_SCIM_TOKEN_URL: Lazy<String> = Lazy::new(|| {
+    format!("/v1/system/scim/tokens/{{id}}?silo={}", *DEMO_SCIM_SILO_NAME)
+});
+
 pub static DEMO_SILO_UTIL_URL: Lazy<String> =
     Lazy::new(|| format!("/v1/system/utilization/silos/{}", *DEMO_SILO_NAME));
 
@@ -140,6 +172,10 @@ pub static DEMO_PROJECT_SELECTOR: Lazy<String> =
     Lazy::new(|| format!("project={}", *DEMO_PROJECT_NAME));
 pub static DEMO_PROJECT_POLICY_URL: Lazy<String> =
     Lazy::new(|| format!("/v1/projects/{}/policy", *DEMO_PROJECT_NAME));
+pub static DEMO_PROJECT_QUOTAS_URL: Lazy<String> =
+    Lazy::new(|| format!("/v1/projects/{}/quotas", *DEMO_PROJECT_NAME));
+pub static DEMO_PROJECT_UTILIZATION_URL: Lazy<String> =
+    Lazy::new(|| format!("/v1/utilization?project={}", *DEMO_PROJECT_NAME));
 pub static DEMO_PROJECT_URL_IMAGES: Lazy<String> =
     Lazy::new(|| format!("/v1/images?project={}", *DEMO_PROJECT_NAME));
 pub static DEMO_PROJECT_URL_INSTANCES: Lazy<String> =
@@ -757,6 +793,46 @@ pub static DEMO_SSHKEY_CREATE: Lazy<params::SshKeyCreate> =
 pub static DEMO_SPECIFIC_SSHKEY_URL: Lazy<String> =
     Lazy::new(|| format!("{}/{}", DEMO_SSHKEYS_URL, *DEMO_SSHKEY_NAME));
 
+// API tokens
+pub const DEMO_API_TOKENS_URL: &'static str = "/v1/me/tokens";
+pub static DEMO_API_TOKEN_NAME: Lazy<Name> =
+    Lazy::new(|| "aaaaa-api-token".parse().unwrap());
+
+pub static DEMO_API_TOKEN_CREATE: Lazy<params::ApiTokenCreate> =
+    Lazy::new(|| params::ApiTokenCreate {
+        identity: IdentityMetadataCreateParams {
+            name: DEMO_API_TOKEN_NAME.clone(),
+            description: "a demo token".to_string(),
+        },
+        time_expires: None,
+        scopes: vec![],
+    });
+
+pub static DEMO_SPECIFIC_API_TOKEN_URL: Lazy<String> =
+    Lazy::new(|| format!("{}/{}", DEMO_API_TOKENS_URL, *DEMO_API_TOKEN_NAME));
+
+// Service accounts
+pub const DEMO_SERVICE_ACCOUNTS_URL: &'static str = "/v1/service-accounts";
+pub static DEMO_SERVICE_ACCOUNT_NAME: Lazy<Name> =
+    Lazy::new(|| "demo-service-account".parse().unwrap());
+pub static DEMO_SERVICE_ACCOUNT_URL: Lazy<String> = Lazy::new(|| {
+    format!("{}/{}", DEMO_SERVICE_ACCOUNTS_URL, *DEMO_SERVICE_ACCOUNT_NAME)
+});
+pub static DEMO_SERVICE_ACCOUNT_CREATE: Lazy<params::ServiceAccountCreate> =
+    Lazy::new(|| params::ServiceAccountCreate {
+        identity: IdentityMetadataCreateParams {
+            name: DEMO_SERVICE_ACCOUNT_NAME.clone(),
+            description: "a demo service account".to_string(),
+        },
+    });
+pub static DEMO_SERVICE_ACCOUNT_TOKENS_URL: Lazy<String> =
+    Lazy::new(|| format!("{}/tokens", *DEMO_SERVICE_ACCOUNT_URL));
+pub static DEMO_SERVICE_ACCOUNT_TOKEN_URL: Lazy<String> =
+    Lazy::new(|| format!("{}/tokens/{{id}}", *DEMO_SERVICE_ACCOUNT_URL));
+pub static DEMO_SERVICE_ACCOUNT_TOKEN_CREATE: Lazy<
+    params::ServiceAccountTokenCreate,
+> = Lazy::new(|| params::ServiceAccountTokenCreate { time_expires: None });
+
 // Project Floating IPs
 pub static DEMO_FLOAT_IP_NAME: Lazy<Name> =
     Lazy::new(|| "float-ip".parse().unwrap());
@@ -844,6 +920,35 @@ pub static SAML_IDENTITY_PROVIDER: Lazy<params::SamlIdentityProviderCreate> =
         group_attribute_name: None,
     });
 
+pub const OIDC_IDENTITY_PROVIDERS_URL: &'static str =
+    "/v1/system/identity-providers/oidc?silo=demo-silo";
+pub static DEMO_OIDC_IDENTITY_PROVIDER_NAME: Lazy<Name> =
+    Lazy::new(|| "demo-oidc-provider".parse().unwrap());
+
+pub static SPECIFIC_OIDC_IDENTITY_PROVIDER_URL: Lazy<String> =
+    Lazy::new(|| {
+        format!(
+            "/v1/system/identity-providers/oidc/{}?silo=demo-silo",
+            *DEMO_OIDC_IDENTITY_PROVIDER_NAME
+        )
+    });
+
+pub static OIDC_IDENTITY_PROVIDER: Lazy<params::OidcIdentityProviderCreate> =
+    Lazy::new(|| params::OidcIdentityProviderCreate {
+        identity: IdentityMetadataCreateParams {
+            name: DEMO_OIDC_IDENTITY_PROVIDER_NAME.clone(),
+            description: "a demo provider".to_string(),
+        },
+
+        issuer: HTTP_SERVER.url("/oidc").to_string(),
+        client_id: "client_id".to_string(),
+        client_secret: "client_secret".to_string(),
+        redirect_uri: "http://callback".to_string(),
+        scopes: vec!["openid".to_string()],
+        user_claim: "sub".to_string(),
+        group_claim: None,
+    });
+
 pub static DEMO_SYSTEM_METRICS_URL: Lazy<String> = Lazy::new(|| {
     format!(
         "/v1/system/metrics/virtual_disk_space_provisioned?start_time={:?}&end_time={:?}",
@@ -1434,6 +1539,29 @@ pub static VERIFY_ENDPOINTS: Lazy<Vec<VerifyEndpoint>> = Lazy::new(|| {
             ],
         },
 
+        VerifyEndpoint {
+            url: &DEMO_PROJECT_QUOTAS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Put(
+                    serde_json::to_value(
+                        params::ProjectQuotasUpdate::default()
+                    ).unwrap()
+                ),
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &DEMO_PROJECT_UTILIZATION_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get
+            ],
+        },
+
         /* VPCs */
         VerifyEndpoint {
             url: &DEMO_PROJECT_URL_VPCS,
@@ -1973,6 +2101,33 @@ pub static VERIFY_ENDPOINTS: Lazy<Vec<VerifyEndpoint>> = Lazy::new(|| {
             )],
         },
 
+        VerifyEndpoint {
+            url: &HARDWARE_SLED_POWER_CYCLE_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(serde_json::Value::Null)
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &HARDWARE_SLED_POWER_OFF_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(serde_json::Value::Null)
+            ],
+        },
+
+        VerifyEndpoint {
+            url: &HARDWARE_SLED_RESET_SP_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Post(serde_json::Value::Null)
+            ],
+        },
+
         VerifyEndpoint {
             url: "/v1/system/hardware/switches",
             visibility: Visibility::Public,
@@ -2031,6 +2186,21 @@ pub static VERIFY_ENDPOINTS: Lazy<Vec<VerifyEndpoint>> = Lazy::new(|| {
             allowed_methods: vec![AllowedMethod::GetUnimplemented],
         },
 
+        VerifyEndpoint {
+            url: "/v1/system/update/target-release",
+            visibility: Visibility::Public,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Put(
+                    serde_json::to_value(&params::SetTargetReleaseParams {
+                        system_version: "1.0.0".parse().unwrap(),
+                    })
+                    .unwrap(),
+                ),
+            ],
+        },
+
         /* Metrics */
 
         VerifyEndpoint {
@@ -2105,6 +2275,39 @@ pub static VERIFY_ENDPOINTS: Lazy<Vec<VerifyEndpoint>> = Lazy::new(|| {
             unprivileged_access: UnprivilegedAccess::None,
             allowed_methods: vec![AllowedMethod::Get],
         },
+        VerifyEndpoint {
+            url: &OIDC_IDENTITY_PROVIDERS_URL,
+            // See the SAML identity provider for why this is Protected.
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![AllowedMethod::Post(
+                serde_json::to_value(&*OIDC_IDENTITY_PROVIDER).unwrap(),
+            )],
+        },
+        VerifyEndpoint {
+            url: &SPECIFIC_OIDC_IDENTITY_PROVIDER_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![AllowedMethod::Get],
+        },
+
+        /* SCIM client bearer tokens */
+
+        VerifyEndpoint {
+            url: &DEMO_SCIM_TOKENS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Post(serde_json::value::Value::Null),
+            ],
+        },
+        VerifyEndpoint {
+            url: &DEMO_SCIM_TOKEN_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![AllowedMethod::Delete],
+        },
         /* Misc */
 
         VerifyEndpoint {
@@ -2147,6 +2350,71 @@ pub static VERIFY_ENDPOINTS: Lazy<Vec<VerifyEndpoint>> = Lazy::new(|| {
             ],
         },
 
+        /* API tokens */
+
+        VerifyEndpoint {
+            url: &DEMO_API_TOKENS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::Full,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_API_TOKEN_CREATE).unwrap(),
+                ),
+            ],
+        },
+        VerifyEndpoint {
+            url: &DEMO_SPECIFIC_API_TOKEN_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::Full,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Delete,
+            ],
+        },
+
+        /* Service accounts */
+
+        VerifyEndpoint {
+            url: &DEMO_SERVICE_ACCOUNTS_URL,
+            visibility: Visibility::Public,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_SERVICE_ACCOUNT_CREATE)
+                        .unwrap(),
+                ),
+            ],
+        },
+        VerifyEndpoint {
+            url: &DEMO_SERVICE_ACCOUNT_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Delete,
+            ],
+        },
+        VerifyEndpoint {
+            url: &DEMO_SERVICE_ACCOUNT_TOKENS_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![
+                AllowedMethod::Get,
+                AllowedMethod::Post(
+                    serde_json::to_value(&*DEMO_SERVICE_ACCOUNT_TOKEN_CREATE)
+                        .unwrap(),
+                ),
+            ],
+        },
+        VerifyEndpoint {
+            url: &DEMO_SERVICE_ACCOUNT_TOKEN_URL,
+            visibility: Visibility::Protected,
+            unprivileged_access: UnprivilegedAccess::None,
+            allowed_methods: vec![AllowedMethod::Delete],
+        },
+
         /* Certificates */
         VerifyEndpoint {
             url: &DEMO_CERTIFICATES_URL,
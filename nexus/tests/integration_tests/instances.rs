@@ -4,6 +4,7 @@
 
 //! Tests basic instance support in the API
 
+use crate::integration_tests::metrics::activate_background_task;
 use crate::integration_tests::metrics::wait_for_producer;
 
 use super::external_ips::floating_ip_get;
@@ -1009,6 +1010,74 @@ async fn test_instance_failed_after_sled_agent_error(
     assert_eq!(instance_next.runtime.run_state, InstanceState::Failed);
 }
 
+// Verifies that if the sled agent rejects an instance's registration while
+// it's being started, the start saga unwinds and leaves the instance stopped
+// and startable.  (A 500-level error would instead mark the instance as
+// failed; see above.)
+#[nexus_test]
+async fn test_instance_start_unwinds_after_sled_agent_error(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+    let apictx = &cptestctx.server.server_context();
+    let nexus = &apictx.nexus;
+    let instance_name = "try-try-again";
+
+    create_project_and_pool(&client).await;
+    let instance_url = get_instance_url(instance_name);
+    let instance = nexus_test_utils::resource_helpers::create_instance_with(
+        client,
+        PROJECT_NAME,
+        instance_name,
+        &params::InstanceNetworkInterfaceAttachment::Default,
+        Vec::<params::InstanceDiskAttachment>::new(),
+        Vec::<params::ExternalIpCreate>::new(),
+        false,
+    )
+    .await;
+    assert_eq!(instance.runtime.run_state, InstanceState::Stopped);
+
+    // We don't know which sled the instance will land on, so fail the next
+    // registration on all of them.
+    let mut faults = sim::SimFaults::default();
+    faults.endpoints.insert(
+        String::from("instance_register"),
+        sim::EndpointFault::times(
+            sim::FaultKind::Error {
+                status_code: 400,
+                message: String::from(
+                    "injected by test_instance_start_unwinds_after_sled_agent_error",
+                ),
+            },
+            1,
+        ),
+    );
+    cptestctx.set_sled_agent_faults(faults);
+
+    let url = get_instance_url(format!("{}/start", instance_name).as_str());
+    NexusRequest::new(
+        RequestBuilder::new(client, Method::POST, &url)
+            .body(None as Option<&serde_json::Value>),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body::<Instance>()
+    .expect_err("expected injected failure");
+
+    let instance_next = instance_get(&client, &instance_url).await;
+    assert_eq!(instance_next.runtime.run_state, InstanceState::Stopped);
+
+    // Once the sled agents behave again, the instance can be started.
+    cptestctx.clear_sled_agent_faults();
+    let instance =
+        instance_post(client, instance_name, InstanceOp::Start).await;
+    instance_simulate(nexus, &instance.identity.id).await;
+    let instance_next = instance_get(&client, &instance_url).await;
+    assert_eq!(instance_next.runtime.run_state, InstanceState::Running);
+}
+
 /// Assert values for fleet, silo, and project using both system and silo
 /// metrics endpoints
 async fn assert_metrics(
@@ -3873,6 +3942,15 @@ async fn test_instance_serial(cptestctx: &ControlPlaneTestContext) {
     }
     assert_eq!(&actual[..expected.len()], expected);
 
+    // Capture the output before the instance stops.  Nexus asks the
+    // background task to do this when the stop is requested, but doesn't wait
+    // for it.
+    activate_background_task(
+        &cptestctx.internal_client,
+        "instance_serial_console",
+    )
+    .await;
+
     // Request a halt and verify both the immediate state and the finished state.
     let instance = instance_next;
     let instance_next =
@@ -3892,6 +3970,34 @@ async fn test_instance_serial(cptestctx: &ControlPlaneTestContext) {
             > instance.runtime.time_run_state_updated
     );
 
+    // The output captured while the instance was running is still available
+    // now that it has stopped.
+    let serial_data: params::InstanceSerialConsoleData =
+        NexusRequest::object_get(
+            client,
+            &format!("{}&from_start=0", instance_serial_url),
+        )
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .expect("failed to make request")
+        .parsed_body()
+        .unwrap();
+    assert!(serial_data.data.starts_with(&actual));
+    assert_eq!(serial_data.data.len(), serial_data.last_byte_offset as usize);
+
+    // A request must specify where to start reading.
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::GET,
+        &instance_serial_url,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
     // Delete the instance.
     NexusRequest::object_delete(client, &instance_url)
         .authn_as(AuthnMode::PrivilegedUser)
@@ -353,75 +353,8 @@ async fn test_instance_watcher_metrics(
     let nexus = &cptestctx.server.server_context().nexus;
     let oximeter = &cptestctx.oximeter;
 
-    // TODO(eliza): consider factoring this out to a generic
-    // `activate_background_task` function in `nexus-test-utils` eventually?
     let activate_instance_watcher = || async {
-        use nexus_client::types::BackgroundTask;
-        use nexus_client::types::CurrentStatus;
-        use nexus_client::types::CurrentStatusRunning;
-        use nexus_client::types::LastResult;
-        use nexus_client::types::LastResultCompleted;
-
-        fn most_recent_start_time(
-            task: &BackgroundTask,
-        ) -> Option<chrono::DateTime<chrono::Utc>> {
-            match task.current {
-                CurrentStatus::Idle => match task.last {
-                    LastResult::Completed(LastResultCompleted {
-                        start_time,
-                        ..
-                    }) => Some(start_time),
-                    LastResult::NeverCompleted => None,
-                },
-                CurrentStatus::Running(CurrentStatusRunning {
-                    start_time,
-                    ..
-                }) => Some(start_time),
-            }
-        }
-
-        eprintln!("\n --- activating instance watcher ---\n");
-        let task = NexusRequest::object_get(
-            internal_client,
-            "/bgtasks/view/instance_watcher",
-        )
-        .execute_and_parse_unwrap::<BackgroundTask>()
-        .await;
-        let last_start = most_recent_start_time(&task);
-
-        internal_client
-            .make_request(
-                http::Method::POST,
-                "/bgtasks/activate",
-                Some(serde_json::json!({
-                    "bgtask_names": vec![String::from("instance_watcher")]
-                })),
-                http::StatusCode::NO_CONTENT,
-            )
-            .await
-            .unwrap();
-        // Wait for the instance watcher task to finish
-        wait_for_condition(
-            || async {
-                let task = NexusRequest::object_get(
-                    internal_client,
-                    "/bgtasks/view/instance_watcher",
-                )
-                .execute_and_parse_unwrap::<BackgroundTask>()
-                .await;
-                if matches!(&task.current, CurrentStatus::Idle)
-                    && most_recent_start_time(&task) > last_start
-                {
-                    Ok(())
-                } else {
-                    Err(CondCheckError::<()>::NotYet)
-                }
-            },
-            &Duration::from_millis(500),
-            &Duration::from_secs(60),
-        )
-        .await
-        .unwrap();
+        activate_background_task(internal_client, "instance_watcher").await;
         // Make sure that the latest metrics have been collected.
         oximeter.force_collect().await;
     };
@@ -612,3 +545,77 @@ pub async fn wait_for_producer(
     .await
     .expect("Failed to find producer within time limit");
 }
+
+/// Activate the background task named `task_name` and wait for it to finish
+/// an activation that started after this was called.
+// TODO(eliza): consider moving this to `nexus-test-utils` eventually?
+pub async fn activate_background_task(
+    internal_client: &ClientTestContext,
+    task_name: &str,
+) {
+    use nexus_client::types::BackgroundTask;
+    use nexus_client::types::CurrentStatus;
+    use nexus_client::types::CurrentStatusRunning;
+    use nexus_client::types::LastResult;
+    use nexus_client::types::LastResultCompleted;
+
+    fn most_recent_start_time(
+        task: &BackgroundTask,
+    ) -> Option<chrono::DateTime<chrono::Utc>> {
+        match task.current {
+            CurrentStatus::Idle => match task.last {
+                LastResult::Completed(LastResultCompleted {
+                    start_time,
+                    ..
+                }) => Some(start_time),
+                LastResult::NeverCompleted => None,
+            },
+            CurrentStatus::Running(CurrentStatusRunning {
+                start_time, ..
+            }) => Some(start_time),
+        }
+    }
+
+    eprintln!("\n --- activating {task_name} ---\n");
+    let task = NexusRequest::object_get(
+        internal_client,
+        &format!("/bgtasks/view/{task_name}"),
+    )
+    .execute_and_parse_unwrap::<BackgroundTask>()
+    .await;
+    let last_start = most_recent_start_time(&task);
+
+    internal_client
+        .make_request(
+            http::Method::POST,
+            "/bgtasks/activate",
+            Some(serde_json::json!({
+                "bgtask_names": vec![task_name.to_string()]
+            })),
+            http::StatusCode::NO_CONTENT,
+        )
+        .await
+        .unwrap();
+    // Wait for the task to finish
+    wait_for_condition(
+        || async {
+            let task = NexusRequest::object_get(
+                internal_client,
+                &format!("/bgtasks/view/{task_name}"),
+            )
+            .execute_and_parse_unwrap::<BackgroundTask>()
+            .await;
+            if matches!(&task.current, CurrentStatus::Idle)
+                && most_recent_start_time(&task) > last_start
+            {
+                Ok(())
+            } else {
+                Err(CondCheckError::<()>::NotYet)
+            }
+        },
+        &Duration::from_millis(500),
+        &Duration::from_secs(60),
+    )
+    .await
+    .unwrap();
+}
@@ -5,6 +5,7 @@
 
 mod address_lots;
 mod allow_list;
+mod api_tokens;
 mod authn_http;
 mod authz;
 mod basic;
@@ -20,6 +21,7 @@ mod initialization;
 mod instances;
 mod ip_pools;
 mod metrics;
+mod oidc;
 mod oximeter;
 mod pantry;
 mod password_login;
@@ -33,6 +35,8 @@ mod rot_updater;
 mod router_routes;
 mod saml;
 mod schema;
+mod scim;
+mod service_accounts;
 mod silo_users;
 mod silos;
 mod sleds;
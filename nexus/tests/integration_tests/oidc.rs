@@ -0,0 +1,437 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tests for logging in with OpenID Connect identity providers
+
+use base64::Engine;
+use dropshot::test_util::ClientTestContext;
+use dropshot::ResultsPage;
+use http::method::Method;
+use http::StatusCode;
+use httptest::{matchers::*, responders::*, Expectation, Server};
+use nexus_test_utils::http_testing::{AuthnMode, NexusRequest, RequestBuilder};
+use nexus_test_utils::resource_helpers::{create_silo, object_create};
+use nexus_test_utils_macros::nexus_test;
+use nexus_types::external_api::{params, shared, views};
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use openssl::hash::MessageDigest;
+use openssl::pkey::{PKey, Private};
+use openssl::rsa::Rsa;
+use openssl::sign::Signer;
+use serde_json::json;
+use std::collections::HashMap;
+
+type ControlPlaneTestContext =
+    nexus_test_utils::ControlPlaneTestContext<omicron_nexus::Server>;
+
+const SILO_NAME: &str = "oidc-silo";
+const PROVIDER_NAME: &str = "mock-idp";
+const CLIENT_ID: &str = "oxide-rack";
+const KEY_ID: &str = "mock-key";
+
+fn b64(data: &[u8]) -> String {
+    base64::engine::general_purpose::URL_SAFE_NO_PAD.encode(data)
+}
+
+/// A fake OpenID provider, serving its configuration and signing keys
+struct MockIdp {
+    server: Server,
+    issuer: String,
+    key: PKey<Private>,
+}
+
+impl MockIdp {
+    fn new() -> MockIdp {
+        let server = Server::run();
+        let issuer = server.url("/idp").to_string();
+        let rsa = Rsa::generate(2048).unwrap();
+
+        server.expect(
+            Expectation::matching(request::method_path(
+                "GET",
+                "/idp/.well-known/openid-configuration",
+            ))
+            .respond_with(json_encoded(json!({
+                "issuer": issuer,
+                "authorization_endpoint": format!("{}/authorize", issuer),
+                "token_endpoint": format!("{}/token", issuer),
+                "jwks_uri": format!("{}/jwks", issuer),
+                "code_challenge_methods_supported": ["S256"],
+            }))),
+        );
+
+        // Nexus should fetch the signing keys once and cache them across
+        // logins.
+        server.expect(
+            Expectation::matching(request::method_path("GET", "/idp/jwks"))
+                .times(1)
+                .respond_with(json_encoded(json!({
+                    "keys": [{
+                        "kty": "RSA",
+                        "kid": KEY_ID,
+                        "use": "sig",
+                        "n": b64(&rsa.n().to_vec()),
+                        "e": b64(&rsa.e().to_vec()),
+                    }]
+                }))),
+        );
+
+        MockIdp { server, issuer, key: PKey::from_rsa(rsa).unwrap() }
+    }
+
+    /// Returns a signed ID token with the given claims, on top of a set of
+    /// valid default ones
+    fn id_token(&self, nonce: &str, claims: serde_json::Value) -> String {
+        let now = chrono::Utc::now().timestamp();
+        let mut all_claims = json!({
+            "iss": self.issuer,
+            "aud": CLIENT_ID,
+            "sub": "jane",
+            "nonce": nonce,
+            "iat": now,
+            "exp": now + 300,
+        });
+        for (k, v) in claims.as_object().unwrap() {
+            all_claims[k] = v.clone();
+        }
+
+        let header = json!({ "alg": "RS256", "kid": KEY_ID });
+        let signing_input = format!(
+            "{}.{}",
+            b64(header.to_string().as_bytes()),
+            b64(all_claims.to_string().as_bytes())
+        );
+        let mut signer =
+            Signer::new(MessageDigest::sha256(), &self.key).unwrap();
+        signer.update(signing_input.as_bytes()).unwrap();
+        format!("{}.{}", signing_input, b64(&signer.sign_to_vec().unwrap()))
+    }
+
+    /// Have the token endpoint exchange `code` for the given ID token
+    fn expect_token_request(&self, code: &'static str, id_token: String) {
+        self.server.expect(
+            Expectation::matching(all_of![
+                request::method_path("POST", "/idp/token"),
+                request::body(url_decoded(contains((
+                    "grant_type",
+                    "authorization_code"
+                )))),
+                request::body(url_decoded(contains(("code", code)))),
+                request::body(url_decoded(contains(key("code_verifier")))),
+            ])
+            .respond_with(json_encoded(json!({
+                "access_token": "not-used",
+                "token_type": "Bearer",
+                "id_token": id_token,
+            }))),
+        );
+    }
+}
+
+async fn create_oidc_idp(client: &ClientTestContext, idp: &MockIdp) {
+    create_silo(client, SILO_NAME, true, shared::SiloIdentityMode::SamlJit)
+        .await;
+
+    let provider: views::OidcIdentityProvider = object_create(
+        client,
+        &format!("/v1/system/identity-providers/oidc?silo={}", SILO_NAME),
+        &params::OidcIdentityProviderCreate {
+            identity: IdentityMetadataCreateParams {
+                name: PROVIDER_NAME.parse().unwrap(),
+                description: "a mock provider".to_string(),
+            },
+            issuer: idp.issuer.clone(),
+            client_id: CLIENT_ID.to_string(),
+            client_secret: "hunter2".to_string(),
+            redirect_uri: format!(
+                "https://oxide.example/login/{}/oidc/{}/callback",
+                SILO_NAME, PROVIDER_NAME
+            ),
+            scopes: vec!["openid".to_string(), "email".to_string()],
+            user_claim: "sub".to_string(),
+            group_claim: Some("groups".to_string()),
+        },
+    )
+    .await;
+    assert_eq!(provider.token_endpoint, format!("{}/token", idp.issuer));
+}
+
+/// Start logging in, returning the query parameters that Nexus sent to the
+/// identity provider
+async fn login_begin(client: &ClientTestContext) -> HashMap<String, String> {
+    let response = RequestBuilder::new(
+        client,
+        Method::GET,
+        &format!(
+            "/login/{}/oidc/{}?redirect_uri=%2Fprojects",
+            SILO_NAME, PROVIDER_NAME
+        ),
+    )
+    .expect_status(Some(StatusCode::FOUND))
+    .execute()
+    .await
+    .unwrap();
+
+    let location = response.headers["Location"].to_str().unwrap();
+    let (endpoint, query) = location.split_once('?').unwrap();
+    assert!(endpoint.ends_with("/idp/authorize"));
+    serde_urlencoded::from_str(query).unwrap()
+}
+
+async fn login_callback(
+    client: &ClientTestContext,
+    code: &str,
+    state: &str,
+    expected_status: StatusCode,
+) -> Option<String> {
+    let response = RequestBuilder::new(
+        client,
+        Method::GET,
+        &format!(
+            "/login/{}/oidc/{}/callback?code={}&state={}",
+            SILO_NAME, PROVIDER_NAME, code, state
+        ),
+    )
+    .expect_status(Some(expected_status))
+    .execute()
+    .await
+    .unwrap();
+
+    if expected_status != StatusCode::SEE_OTHER {
+        return None;
+    }
+    assert_eq!(response.headers["Location"], "/projects");
+    let cookie = response.headers["Set-Cookie"].to_str().unwrap();
+    Some(cookie.split_once(';').unwrap().0.to_string())
+}
+
+#[nexus_test]
+async fn test_oidc_login(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let idp = MockIdp::new();
+    create_oidc_idp(client, &idp).await;
+
+    // The provider can be fetched back.
+    let provider: views::OidcIdentityProvider = NexusRequest::object_get(
+        client,
+        &format!(
+            "/v1/system/identity-providers/oidc/{}?silo={}",
+            PROVIDER_NAME, SILO_NAME
+        ),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute_and_parse_unwrap()
+    .await;
+    assert_eq!(provider.scopes, vec!["openid", "email"]);
+
+    // Log in.
+    let params = login_begin(client).await;
+    assert_eq!(params["response_type"], "code");
+    assert_eq!(params["client_id"], CLIENT_ID);
+    assert_eq!(params["scope"], "openid email");
+    assert_eq!(params["code_challenge_method"], "S256");
+    assert_eq!(params["redirect_uri"], provider.redirect_uri);
+
+    idp.expect_token_request(
+        "code-1",
+        idp.id_token(&params["nonce"], json!({ "groups": ["engineering"] })),
+    );
+    let session = login_callback(
+        client,
+        "code-1",
+        &params["state"],
+        StatusCode::SEE_OTHER,
+    )
+    .await
+    .unwrap();
+
+    let me: views::CurrentUser = NexusRequest::new(
+        RequestBuilder::new(client, Method::GET, "/v1/me")
+            .header(http::header::COOKIE, &session)
+            .expect_status(Some(StatusCode::OK)),
+    )
+    .execute_and_parse_unwrap()
+    .await;
+    assert_eq!(me.user.display_name, "jane");
+    assert_eq!(me.silo_name.as_str(), SILO_NAME);
+
+    let groups: ResultsPage<views::Group> = NexusRequest::new(
+        RequestBuilder::new(client, Method::GET, "/v1/me/groups")
+            .header(http::header::COOKIE, &session)
+            .expect_status(Some(StatusCode::OK)),
+    )
+    .execute_and_parse_unwrap()
+    .await;
+    assert_eq!(groups.items.len(), 1);
+    assert_eq!(groups.items[0].display_name, "engineering");
+
+    // Login requests can't be replayed.
+    login_callback(
+        client,
+        "code-1",
+        &params["state"],
+        StatusCode::UNAUTHORIZED,
+    )
+    .await;
+
+    // Logging in again finds the same user, and reuses the cached signing
+    // keys.
+    let params = login_begin(client).await;
+    idp.expect_token_request(
+        "code-2",
+        idp.id_token(&params["nonce"], json!({})),
+    );
+    let session = login_callback(
+        client,
+        "code-2",
+        &params["state"],
+        StatusCode::SEE_OTHER,
+    )
+    .await
+    .unwrap();
+    let me_again: views::CurrentUser = NexusRequest::new(
+        RequestBuilder::new(client, Method::GET, "/v1/me")
+            .header(http::header::COOKIE, &session)
+            .expect_status(Some(StatusCode::OK)),
+    )
+    .execute_and_parse_unwrap()
+    .await;
+    assert_eq!(me_again.user.id, me.user.id);
+
+    idp.server.verify_and_clear();
+}
+
+#[nexus_test]
+async fn test_oidc_login_failures(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    let idp = MockIdp::new();
+    create_oidc_idp(client, &idp).await;
+
+    // A state that Nexus never issued is rejected without contacting the
+    // provider.
+    login_begin(client).await;
+    login_callback(client, "code-1", "bogus-state", StatusCode::UNAUTHORIZED)
+        .await;
+
+    // ID tokens with the wrong nonce, audience, or issuer are rejected.
+    let bad_claims = [
+        ("code-2", json!({ "nonce": "some-other-nonce" })),
+        ("code-3", json!({ "aud": "some-other-client" })),
+        ("code-4", json!({ "iss": "https://evil.example" })),
+    ];
+    for (code, claims) in bad_claims {
+        let params = login_begin(client).await;
+        let id_token = idp.id_token(&params["nonce"], claims);
+        idp.expect_token_request(code, id_token);
+        login_callback(
+            client,
+            code,
+            &params["state"],
+            StatusCode::UNAUTHORIZED,
+        )
+        .await;
+    }
+
+    // Errors reported by the provider are passed along as login failures.
+    RequestBuilder::new(
+        client,
+        Method::GET,
+        &format!(
+            "/login/{}/oidc/{}/callback?error=access_denied&state=whatever",
+            SILO_NAME, PROVIDER_NAME
+        ),
+    )
+    .expect_status(Some(StatusCode::UNAUTHORIZED))
+    .execute()
+    .await
+    .unwrap();
+
+    // Nobody got logged in.
+    let users: ResultsPage<views::User> = NexusRequest::object_get(
+        client,
+        &format!("/v1/system/users?silo={}", SILO_NAME),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute_and_parse_unwrap()
+    .await;
+    assert!(users.items.is_empty());
+
+    // Anyone can start a login, so the relay state that gets saved with it
+    // is limited in size.
+    RequestBuilder::new(
+        client,
+        Method::GET,
+        &format!(
+            "/login/{}/oidc/{}?redirect_uri=%2F{}",
+            SILO_NAME,
+            PROVIDER_NAME,
+            "a".repeat(4096)
+        ),
+    )
+    .expect_status(Some(StatusCode::BAD_REQUEST))
+    .execute()
+    .await
+    .unwrap();
+
+    // SAML login routes don't work with an OIDC provider.
+    RequestBuilder::new(
+        client,
+        Method::GET,
+        &format!("/login/{}/saml/{}/redirect", SILO_NAME, PROVIDER_NAME),
+    )
+    .expect_status(Some(StatusCode::BAD_REQUEST))
+    .execute()
+    .await
+    .unwrap();
+}
+
+#[nexus_test]
+async fn test_oidc_idp_create_checks_issuer(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+    create_silo(client, SILO_NAME, true, shared::SiloIdentityMode::SamlJit)
+        .await;
+
+    // The discovery document claims to be for a different issuer.
+    let server = Server::run();
+    server.expect(
+        Expectation::matching(request::method_path(
+            "GET",
+            "/idp/.well-known/openid-configuration",
+        ))
+        .respond_with(json_encoded(json!({
+            "issuer": "https://somewhere-else.example",
+            "authorization_endpoint": "https://somewhere-else.example/a",
+            "token_endpoint": "https://somewhere-else.example/t",
+            "jwks_uri": "https://somewhere-else.example/j",
+        }))),
+    );
+
+    NexusRequest::new(
+        RequestBuilder::new(
+            client,
+            Method::POST,
+            &format!("/v1/system/identity-providers/oidc?silo={}", SILO_NAME),
+        )
+        .body(Some(&params::OidcIdentityProviderCreate {
+            identity: IdentityMetadataCreateParams {
+                name: PROVIDER_NAME.parse().unwrap(),
+                description: "a mock provider".to_string(),
+            },
+            issuer: server.url("/idp").to_string(),
+            client_id: CLIENT_ID.to_string(),
+            client_secret: "hunter2".to_string(),
+            redirect_uri: "https://oxide.example/callback".to_string(),
+            scopes: vec!["openid".to_string()],
+            user_claim: "sub".to_string(),
+            group_claim: None,
+        }))
+        .expect_status(Some(StatusCode::BAD_REQUEST)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
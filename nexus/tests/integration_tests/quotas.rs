@@ -16,7 +16,9 @@ use nexus_test_utils_macros::nexus_test;
 use nexus_types::external_api::params;
 use nexus_types::external_api::shared;
 use nexus_types::external_api::shared::SiloRole;
-use nexus_types::external_api::views::{Silo, SiloQuotas};
+use nexus_types::external_api::views::{
+    ProjectQuotas, Silo, SiloQuotas, Utilization,
+};
 use omicron_common::api::external::ByteCount;
 use omicron_common::api::external::IdentityMetadataCreateParams;
 use omicron_common::api::external::InstanceCpuCount;
@@ -62,6 +64,47 @@ impl ResourceAllocator {
         .expect("failed to parse quotas")
     }
 
+    async fn set_project_quotas(
+        &self,
+        client: &ClientTestContext,
+        quotas: params::ProjectQuotasUpdate,
+    ) -> Result<TestResponse, Error> {
+        NexusRequest::object_put(
+            client,
+            "/v1/projects/project/quotas",
+            Some(&quotas),
+        )
+        .authn_as(self.auth.clone())
+        .execute()
+        .await
+    }
+
+    async fn get_project_quotas(
+        &self,
+        client: &ClientTestContext,
+    ) -> ProjectQuotas {
+        NexusRequest::object_get(client, "/v1/projects/project/quotas")
+            .authn_as(self.auth.clone())
+            .execute()
+            .await
+            .expect("failed to fetch project quotas")
+            .parsed_body()
+            .expect("failed to parse project quotas")
+    }
+
+    async fn get_project_utilization(
+        &self,
+        client: &ClientTestContext,
+    ) -> Utilization {
+        NexusRequest::object_get(client, "/v1/utilization?project=project")
+            .authn_as(self.auth.clone())
+            .execute()
+            .await
+            .expect("failed to fetch project utilization")
+            .parsed_body()
+            .expect("failed to parse project utilization")
+    }
+
     async fn provision_instance(
         &self,
         client: &ClientTestContext,
@@ -382,3 +425,139 @@ async fn test_quota_limits(cptestctx: &ControlPlaneTestContext) {
         assert_eq!(quotas.limits.storage, quota_limit.storage.unwrap());
     }
 }
+
+#[nexus_test]
+async fn test_project_quotas(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    // Simulate space for disks
+    DiskTest::new(&cptestctx).await;
+
+    let system = setup_silo_with_quota(
+        &client,
+        "quota-test-silo",
+        params::SiloQuotasCreate {
+            cpus: 4,
+            memory: ByteCount::from_gibibytes_u32(16),
+            storage: ByteCount::from_gibibytes_u32(4),
+        },
+    )
+    .await;
+
+    // A project without its own quotas is bounded only by its silo's.
+    let quotas = system.get_project_quotas(client).await;
+    assert_eq!(quotas.cpus, None);
+    assert_eq!(quotas.memory, None);
+    assert_eq!(quotas.storage, None);
+    let utilization = system.get_project_utilization(client).await;
+    assert_eq!(utilization.capacity.cpus, 4);
+    assert_eq!(utilization.capacity.memory, ByteCount::from_gibibytes_u32(16));
+    assert_eq!(utilization.capacity.storage, ByteCount::from_gibibytes_u32(4));
+
+    // Limit the project's CPUs and storage, leaving memory to the silo.
+    system
+        .set_project_quotas(
+            client,
+            params::ProjectQuotasUpdate {
+                cpus: Some(2),
+                memory: None,
+                storage: Some(ByteCount::from_gibibytes_u32(1)),
+            },
+        )
+        .await
+        .expect("failed to set project quotas");
+    let quotas = system.get_project_quotas(client).await;
+    assert_eq!(quotas.cpus, Some(2));
+    assert_eq!(quotas.memory, None);
+    assert_eq!(quotas.storage, Some(ByteCount::from_gibibytes_u32(1)));
+    let utilization = system.get_project_utilization(client).await;
+    assert_eq!(utilization.capacity.cpus, 2);
+    assert_eq!(utilization.capacity.memory, ByteCount::from_gibibytes_u32(16));
+    assert_eq!(utilization.capacity.storage, ByteCount::from_gibibytes_u32(1));
+
+    // The silo has room for this instance, but the project does not.
+    let err = system
+        .provision_instance(client, "instance", 3, 1)
+        .await
+        .unwrap()
+        .parsed_body::<HttpErrorResponseBody>()
+        .expect("failed to parse error body");
+    assert!(
+        err.message.contains("Project vCPU Limit Exceeded"),
+        "Unexpected error: {0}",
+        err.message
+    );
+    system.cleanup_instance(client, "instance").await;
+
+    let err = system
+        .provision_disk(client, "disk", 2)
+        .await
+        .unwrap()
+        .parsed_body::<HttpErrorResponseBody>()
+        .expect("failed to parse error body");
+    assert!(
+        err.message.contains("Project Storage Limit Exceeded"),
+        "Unexpected error: {0}",
+        err.message
+    );
+
+    system
+        .provision_instance(client, "instance", 2, 1)
+        .await
+        .expect("Instance should fit within the project's quota");
+    let utilization = system.get_project_utilization(client).await;
+    assert_eq!(utilization.provisioned.cpus, 2);
+    assert_eq!(
+        utilization.provisioned.memory,
+        ByteCount::from_gibibytes_u32(1)
+    );
+
+    // Negative limits are rejected.
+    NexusRequest::expect_failure_with_body(
+        client,
+        http::StatusCode::BAD_REQUEST,
+        http::Method::PUT,
+        "/v1/projects/project/quotas",
+        &json!({ "cpus": -1 }),
+    )
+    .authn_as(system.auth.clone())
+    .execute()
+    .await
+    .expect("sent project quota update");
+
+    // Project collaborators cannot lift their own project's quotas.
+    let silo: Silo =
+        NexusRequest::object_get(client, "/v1/system/silos/quota-test-silo")
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute()
+            .await
+            .expect("failed to fetch silo")
+            .parsed_body()
+            .expect("failed to parse silo");
+    let collaborator = create_local_user(
+        client,
+        &silo,
+        &"collaborator".parse().unwrap(),
+        params::UserPassword::LoginDisallowed,
+    )
+    .await;
+    grant_iam(
+        client,
+        "/v1/projects/project",
+        shared::ProjectRole::Collaborator,
+        collaborator.id,
+        system.auth.clone(),
+    )
+    .await;
+    NexusRequest::expect_failure_with_body(
+        client,
+        http::StatusCode::FORBIDDEN,
+        http::Method::PUT,
+        "/v1/projects/project/quotas",
+        &params::ProjectQuotasUpdate::default(),
+    )
+    .authn_as(AuthnMode::SiloUser(collaborator.id))
+    .execute()
+    .await
+    .expect("collaborator should not be able to update project quotas");
+}
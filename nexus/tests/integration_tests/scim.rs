@@ -0,0 +1,389 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tests for SCIM provisioning of Silo users and groups
+
+use dropshot::test_util::ClientTestContext;
+use dropshot::ResultsPage;
+use http::{method::Method, StatusCode};
+use nexus_test_utils::http_testing::{AuthnMode, NexusRequest, RequestBuilder};
+use nexus_test_utils::resource_helpers::{
+    create_silo, grant_iam, objects_list_page_authz,
+};
+use nexus_test_utils_macros::nexus_test;
+use nexus_types::external_api::{shared, views};
+use serde_json::json;
+use uuid::Uuid;
+
+type ControlPlaneTestContext =
+    nexus_test_utils::ControlPlaneTestContext<omicron_nexus::Server>;
+
+const SCIM_SILO_NAME: &str = "scim-silo";
+
+async fn scim_token_create(
+    client: &ClientTestContext,
+    silo_name: &str,
+) -> views::ScimClientBearerTokenValue {
+    NexusRequest::objects_post(
+        client,
+        &format!("/v1/system/scim/tokens?silo={}", silo_name),
+        &serde_json::Value::Null,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute_and_parse_unwrap()
+    .await
+}
+
+/// Makes a SCIM request using the given bearer token
+async fn scim_request(
+    client: &ClientTestContext,
+    bearer_token: &str,
+    method: Method,
+    uri: &str,
+    body: Option<serde_json::Value>,
+    expected_status: StatusCode,
+) -> Option<serde_json::Value> {
+    let response = RequestBuilder::new(client, method, uri)
+        .header(http::header::AUTHORIZATION, format!("Bearer {}", bearer_token))
+        .body(body.as_ref())
+        .expect_status(Some(expected_status))
+        .allow_non_dropshot_errors()
+        .execute()
+        .await
+        .unwrap();
+    if response.body.is_empty() {
+        None
+    } else {
+        Some(response.parsed_body().unwrap())
+    }
+}
+
+async fn silo_user_ids(client: &ClientTestContext) -> Vec<Uuid> {
+    objects_list_page_authz::<views::User>(
+        client,
+        &format!("/v1/system/users?silo={}", SCIM_SILO_NAME),
+    )
+    .await
+    .items
+    .into_iter()
+    .map(|u| u.id)
+    .collect()
+}
+
+#[nexus_test]
+async fn test_scim_client_bearer_tokens(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    // Tokens can only be created for Silos that are provisioned via SCIM.
+    create_silo(client, "jit-silo", true, shared::SiloIdentityMode::SamlJit)
+        .await;
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::BAD_REQUEST,
+        Method::POST,
+        "/v1/system/scim/tokens?silo=jit-silo",
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    create_silo(
+        client,
+        SCIM_SILO_NAME,
+        true,
+        shared::SiloIdentityMode::SamlScim,
+    )
+    .await;
+    let token = scim_token_create(client, SCIM_SILO_NAME).await;
+    assert!(token.bearer_token.starts_with("oxide-scim-"));
+
+    let tokens_url = format!("/v1/system/scim/tokens?silo={}", SCIM_SILO_NAME);
+    let tokens: ResultsPage<views::ScimClientBearerToken> =
+        NexusRequest::object_get(client, &tokens_url)
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute_and_parse_unwrap()
+            .await;
+    assert_eq!(tokens.items.len(), 1);
+    assert_eq!(tokens.items[0].id, token.id);
+
+    // The token works until it's deleted.
+    scim_request(
+        client,
+        &token.bearer_token,
+        Method::GET,
+        "/scim/v2/Users",
+        None,
+        StatusCode::OK,
+    )
+    .await;
+
+    NexusRequest::object_delete(
+        client,
+        &format!("/v1/system/scim/tokens/{}?silo={}", token.id, SCIM_SILO_NAME),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    scim_request(
+        client,
+        &token.bearer_token,
+        Method::GET,
+        "/scim/v2/Users",
+        None,
+        StatusCode::UNAUTHORIZED,
+    )
+    .await;
+
+    // Garbage tokens and ordinary credentials don't work either.
+    for bad_token in ["oxide-scim-0123456789abcdef", "oxide-token-abc"] {
+        scim_request(
+            client,
+            bad_token,
+            Method::GET,
+            "/scim/v2/Users",
+            None,
+            StatusCode::UNAUTHORIZED,
+        )
+        .await;
+    }
+}
+
+#[nexus_test]
+async fn test_scim_provisioning(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    create_silo(
+        client,
+        SCIM_SILO_NAME,
+        true,
+        shared::SiloIdentityMode::SamlScim,
+    )
+    .await;
+    let token = scim_token_create(client, SCIM_SILO_NAME).await.bearer_token;
+
+    // Create two users.
+    let alice = scim_request(
+        client,
+        &token,
+        Method::POST,
+        "/scim/v2/Users",
+        Some(json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "userName": "alice@example.com",
+            "name": { "givenName": "Alice" },
+            "active": true,
+        })),
+        StatusCode::CREATED,
+    )
+    .await
+    .unwrap();
+    let alice_id: Uuid = alice["id"].as_str().unwrap().parse().unwrap();
+    assert_eq!(alice["userName"], "alice@example.com");
+
+    let bob = scim_request(
+        client,
+        &token,
+        Method::POST,
+        "/scim/v2/Users",
+        Some(json!({ "userName": "bob@example.com" })),
+        StatusCode::CREATED,
+    )
+    .await
+    .unwrap();
+    let bob_id: Uuid = bob["id"].as_str().unwrap().parse().unwrap();
+
+    // Creating a user that already exists fails with a conflict.
+    scim_request(
+        client,
+        &token,
+        Method::POST,
+        "/scim/v2/Users",
+        Some(json!({ "userName": "bob@example.com" })),
+        StatusCode::CONFLICT,
+    )
+    .await;
+
+    let mut user_ids = silo_user_ids(client).await;
+    user_ids.sort();
+    let mut expected = vec![alice_id, bob_id];
+    expected.sort();
+    assert_eq!(user_ids, expected);
+
+    // Identity providers look users up by name before creating them.
+    let list = scim_request(
+        client,
+        &token,
+        Method::GET,
+        "/scim/v2/Users?filter=userName%20eq%20%22alice%40example.com%22",
+        None,
+        StatusCode::OK,
+    )
+    .await
+    .unwrap();
+    assert_eq!(list["totalResults"], 1);
+    assert_eq!(list["Resources"][0]["id"], alice_id.to_string());
+
+    // Create a group containing both users, then remove one of them.
+    let group = scim_request(
+        client,
+        &token,
+        Method::POST,
+        "/scim/v2/Groups",
+        Some(json!({
+            "displayName": "engineers",
+            "members": [{ "value": alice_id }, { "value": bob_id }],
+        })),
+        StatusCode::CREATED,
+    )
+    .await
+    .unwrap();
+    let group_id: Uuid = group["id"].as_str().unwrap().parse().unwrap();
+    assert_eq!(group["members"].as_array().unwrap().len(), 2);
+
+    let group = scim_request(
+        client,
+        &token,
+        Method::PATCH,
+        &format!("/scim/v2/Groups/{}", group_id),
+        Some(json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{
+                "op": "remove",
+                "path": format!("members[value eq \"{}\"]", bob_id),
+            }],
+        })),
+        StatusCode::OK,
+    )
+    .await
+    .unwrap();
+    let members = group["members"].as_array().unwrap();
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0]["value"], alice_id.to_string());
+
+    // Deactivating a user keeps them, their group memberships and their role
+    // assignments, so that they can be reactivated later.
+    let silo_url = format!("/v1/system/silos/{}", SCIM_SILO_NAME);
+    grant_iam(
+        client,
+        &silo_url,
+        shared::SiloRole::Viewer,
+        alice_id,
+        AuthnMode::PrivilegedUser,
+    )
+    .await;
+    let alice = scim_request(
+        client,
+        &token,
+        Method::PATCH,
+        &format!("/scim/v2/Users/{}", alice_id),
+        Some(json!({
+            "schemas": ["urn:ietf:params:scim:api:messages:2.0:PatchOp"],
+            "Operations": [{ "op": "replace", "value": { "active": false } }],
+        })),
+        StatusCode::OK,
+    )
+    .await
+    .unwrap();
+    assert_eq!(alice["active"], false);
+    let alice = scim_request(
+        client,
+        &token,
+        Method::GET,
+        &format!("/scim/v2/Users/{}", alice_id),
+        None,
+        StatusCode::OK,
+    )
+    .await
+    .unwrap();
+    assert_eq!(alice["active"], false);
+    let mut user_ids = silo_user_ids(client).await;
+    user_ids.sort();
+    assert_eq!(user_ids, expected);
+
+    let group = scim_request(
+        client,
+        &token,
+        Method::GET,
+        &format!("/scim/v2/Groups/{}", group_id),
+        None,
+        StatusCode::OK,
+    )
+    .await
+    .unwrap();
+    let members = group["members"].as_array().unwrap();
+    assert_eq!(members.len(), 1);
+    assert_eq!(members[0]["value"], alice_id.to_string());
+
+    // Reactivating the user restores them as they were.
+    let alice = scim_request(
+        client,
+        &token,
+        Method::PUT,
+        &format!("/scim/v2/Users/{}", alice_id),
+        Some(json!({
+            "schemas": ["urn:ietf:params:scim:schemas:core:2.0:User"],
+            "userName": "alice@example.com",
+            "active": true,
+        })),
+        StatusCode::OK,
+    )
+    .await
+    .unwrap();
+    assert_eq!(alice["active"], true);
+    assert_eq!(alice["id"], alice_id.to_string());
+    let policy: shared::Policy<shared::SiloRole> =
+        NexusRequest::object_get(client, &format!("{}/policy", silo_url))
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute_and_parse_unwrap()
+            .await;
+    assert!(policy.role_assignments.iter().any(|assignment| {
+        assignment.identity_id == alice_id
+            && assignment.role_name == shared::SiloRole::Viewer
+    }));
+
+    // Deleting works too.
+    scim_request(
+        client,
+        &token,
+        Method::DELETE,
+        &format!("/scim/v2/Users/{}", bob_id),
+        None,
+        StatusCode::NO_CONTENT,
+    )
+    .await;
+    assert_eq!(silo_user_ids(client).await, vec![alice_id]);
+    scim_request(
+        client,
+        &token,
+        Method::GET,
+        &format!("/scim/v2/Users/{}", bob_id),
+        None,
+        StatusCode::NOT_FOUND,
+    )
+    .await;
+
+    scim_request(
+        client,
+        &token,
+        Method::DELETE,
+        &format!("/scim/v2/Groups/{}", group_id),
+        None,
+        StatusCode::NO_CONTENT,
+    )
+    .await;
+    let groups = scim_request(
+        client,
+        &token,
+        Method::GET,
+        "/scim/v2/Groups",
+        None,
+        StatusCode::OK,
+    )
+    .await
+    .unwrap();
+    assert_eq!(groups["totalResults"], 0);
+}
@@ -0,0 +1,182 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Tests for service accounts and their tokens
+
+use dropshot::test_util::ClientTestContext;
+use http::{header, method::Method, StatusCode};
+use nexus_test_utils::http_testing::{AuthnMode, NexusRequest, RequestBuilder};
+use nexus_test_utils::resource_helpers::{
+    create_project, objects_list_page_authz,
+};
+use nexus_test_utils_macros::nexus_test;
+use nexus_types::external_api::params;
+use nexus_types::external_api::shared::{
+    self, IdentityType, ProjectRole, RoleAssignment,
+};
+use nexus_types::external_api::views::{
+    ServiceAccount, ServiceAccountToken, ServiceAccountTokenValue,
+};
+use omicron_common::api::external::IdentityMetadataCreateParams;
+use uuid::Uuid;
+
+type ControlPlaneTestContext =
+    nexus_test_utils::ControlPlaneTestContext<omicron_nexus::Server>;
+
+const SERVICE_ACCOUNTS_URL: &str = "/v1/service-accounts";
+const PROJECT_NAME: &str = "robots-welcome";
+
+fn service_account_create_params(name: &str) -> params::ServiceAccountCreate {
+    params::ServiceAccountCreate {
+        identity: IdentityMetadataCreateParams {
+            name: name.parse().unwrap(),
+            description: format!("service account {}", name),
+        },
+    }
+}
+
+/// GET `url` authenticated only by `token`, expecting `status`
+async fn token_get(
+    client: &ClientTestContext,
+    token: &ServiceAccountTokenValue,
+    url: &str,
+    status: StatusCode,
+) {
+    RequestBuilder::new(client, Method::GET, url)
+        .header(header::AUTHORIZATION, format!("Bearer {}", token.bearer_token))
+        .expect_status(Some(status))
+        .execute()
+        .await
+        .unwrap_or_else(|e| panic!("GET {}: {:#}", url, e));
+}
+
+async fn project_policy(
+    client: &ClientTestContext,
+    policy_url: &str,
+) -> shared::Policy<ProjectRole> {
+    NexusRequest::object_get(client, policy_url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute_and_parse_unwrap()
+        .await
+}
+
+async fn project_grant_viewer(
+    client: &ClientTestContext,
+    policy_url: &str,
+    service_account_id: Uuid,
+) {
+    let mut policy = project_policy(client, policy_url).await;
+    policy.role_assignments.push(RoleAssignment {
+        identity_type: IdentityType::ServiceAccount,
+        identity_id: service_account_id,
+        role_name: ProjectRole::Viewer,
+    });
+    NexusRequest::object_put(client, policy_url, Some(&policy))
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .expect("failed to update policy");
+}
+
+#[nexus_test]
+async fn test_service_account_lifecycle(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+    create_project(client, PROJECT_NAME).await;
+    let project_url = format!("/v1/projects/{}", PROJECT_NAME);
+    let policy_url = format!("{}/policy", project_url);
+
+    // Only silo administrators may create service accounts.
+    NexusRequest::expect_failure_with_body(
+        client,
+        StatusCode::FORBIDDEN,
+        Method::POST,
+        SERVICE_ACCOUNTS_URL,
+        &service_account_create_params("ci"),
+    )
+    .authn_as(AuthnMode::UnprivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+
+    let service_account: ServiceAccount = NexusRequest::objects_post(
+        client,
+        SERVICE_ACCOUNTS_URL,
+        &service_account_create_params("ci"),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute_and_parse_unwrap()
+    .await;
+    let service_account_url =
+        format!("{}/{}", SERVICE_ACCOUNTS_URL, service_account.identity.name);
+    let tokens_url = format!("{}/tokens", service_account_url);
+
+    let service_accounts =
+        objects_list_page_authz::<ServiceAccount>(client, SERVICE_ACCOUNTS_URL)
+            .await
+            .items;
+    assert_eq!(service_accounts, vec![service_account.clone()]);
+
+    let token: ServiceAccountTokenValue = NexusRequest::objects_post(
+        client,
+        &tokens_url,
+        &params::ServiceAccountTokenCreate { time_expires: None },
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute_and_parse_unwrap()
+    .await;
+    assert!(token.bearer_token.starts_with("oxide-token-"));
+
+    // A new service account can authenticate, but can't see anything.
+    token_get(client, &token, &project_url, StatusCode::NOT_FOUND).await;
+
+    // Once granted a role on the project, it can see the project, but it still
+    // can't manage service accounts.
+    project_grant_viewer(client, &policy_url, service_account.identity.id)
+        .await;
+    token_get(client, &token, &project_url, StatusCode::OK).await;
+    token_get(client, &token, SERVICE_ACCOUNTS_URL, StatusCode::FORBIDDEN)
+        .await;
+
+    let tokens =
+        objects_list_page_authz::<ServiceAccountToken>(client, &tokens_url)
+            .await
+            .items;
+    assert_eq!(tokens.len(), 1);
+    assert_eq!(tokens[0].id, token.id);
+    assert!(tokens[0].time_last_used.is_some());
+
+    // A revoked token no longer authenticates.
+    NexusRequest::object_delete(
+        client,
+        &format!("{}/{}", tokens_url, token.id),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .expect("failed to revoke token");
+    token_get(client, &token, &project_url, StatusCode::UNAUTHORIZED).await;
+
+    // Deleting the service account removes it from the policies that
+    // mentioned it.
+    NexusRequest::object_delete(client, &service_account_url)
+        .authn_as(AuthnMode::PrivilegedUser)
+        .execute()
+        .await
+        .expect("failed to delete service account");
+    let policy = project_policy(client, &policy_url).await;
+    assert!(policy
+        .role_assignments
+        .iter()
+        .all(|r| r.identity_id != service_account.identity.id));
+    NexusRequest::expect_failure(
+        client,
+        StatusCode::NOT_FOUND,
+        Method::GET,
+        &service_account_url,
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
@@ -4,21 +4,32 @@
 
 //! Tests for APIs against sled-based endpoints.
 
+use crate::integration_tests::metrics::activate_background_task;
 use camino::Utf8Path;
 use dropshot::test_util::ClientTestContext;
+use dropshot::HttpErrorResponseBody;
+use http::method::Method;
+use http::StatusCode;
 use nexus_db_model::PhysicalDisk as DbPhysicalDisk;
 use nexus_db_model::PhysicalDiskKind as DbPhysicalDiskKind;
 use nexus_db_queries::context::OpContext;
 use nexus_test_interface::NexusServer;
+use nexus_test_utils::http_testing::AuthnMode;
+use nexus_test_utils::http_testing::NexusRequest;
+use nexus_test_utils::http_testing::RequestBuilder;
 use nexus_test_utils::resource_helpers::create_default_ip_pool;
 use nexus_test_utils::resource_helpers::create_instance;
 use nexus_test_utils::resource_helpers::create_project;
 use nexus_test_utils::resource_helpers::objects_list_page_authz;
+use nexus_test_utils::sim_rack::SimSledConfig;
+use nexus_test_utils::sim_rack::SimSledPolicy;
 use nexus_test_utils::start_sled_agent;
 use nexus_test_utils::SLED_AGENT_UUID;
 use nexus_test_utils_macros::nexus_test;
 use nexus_types::external_api::views::SledInstance;
 use nexus_types::external_api::views::{PhysicalDisk, Sled};
+use nexus_types::external_api::views::{SledPolicy, SledProvisionPolicy};
+use omicron_common::api::external::SwitchLocation;
 use omicron_sled_agent::sim;
 use std::str::FromStr;
 use uuid::Uuid;
@@ -90,6 +101,82 @@ async fn test_sleds_list(cptestctx: &ControlPlaneTestContext) {
     }
 }
 
+#[tokio::test]
+async fn test_sled_agent_add_remove() {
+    let mut cptestctx = nexus_test_utils::test_setup::<omicron_nexus::Server>(
+        "test_sled_agent_add_remove",
+    )
+    .await;
+    let sleds_url = "/v1/system/hardware/sleds";
+
+    // Add a sled, which should register with Nexus and then be given the
+    // requested policy.
+    let sled = SimSledConfig {
+        disks: 2,
+        policy: SimSledPolicy::NonProvisionable,
+        ..SimSledConfig::default()
+    };
+    let sled_id = cptestctx
+        .add_sled_agent(&sled, sim::SimMode::Explicit)
+        .await
+        .expect("added sled agent");
+    assert!(cptestctx.sled_agent_by_id(sled_id).is_some());
+    assert_eq!(cptestctx.sled_agents().count(), 3);
+
+    let sleds = sleds_list(&cptestctx.external_client, sleds_url).await;
+    assert_eq!(sleds.len(), 3);
+    let added = sleds
+        .iter()
+        .find(|sled| sled.identity.id == sled_id)
+        .expect("added sled is listed");
+    assert_eq!(
+        added.policy,
+        SledPolicy::InService {
+            provision_policy: SledProvisionPolicy::NonProvisionable
+        }
+    );
+
+    // A sled that's already running can't be added again.
+    let duplicate = SimSledConfig { id: Some(sled_id), ..sled };
+    cptestctx
+        .add_sled_agent(&duplicate, sim::SimMode::Explicit)
+        .await
+        .expect_err("sled already running");
+    assert_eq!(cptestctx.sled_agents().count(), 3);
+
+    // Removing the sled stops its sled agent and expunges it, so it's no
+    // longer listed.
+    cptestctx.remove_sled_agent(sled_id).await.expect("removed sled agent");
+    assert!(cptestctx.sled_agent_by_id(sled_id).is_none());
+    assert_eq!(cptestctx.sled_agents().count(), 2);
+
+    let sleds = sleds_list(&cptestctx.external_client, sleds_url).await;
+    assert_eq!(sleds.len(), 2);
+    assert!(sleds.iter().all(|sled| sled.identity.id != sled_id));
+    let removed = NexusRequest::object_get(
+        &cptestctx.external_client,
+        &format!("{sleds_url}/{sled_id}"),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute_and_parse_unwrap::<Sled>()
+    .await;
+    assert_eq!(removed.policy, SledPolicy::Expunged);
+
+    // It can't be removed twice, and the sleds hosting the control plane's
+    // services can't be removed at all.
+    cptestctx
+        .remove_sled_agent(sled_id)
+        .await
+        .expect_err("sled already removed");
+    cptestctx
+        .remove_sled_agent(SLED_AGENT_UUID.parse().unwrap())
+        .await
+        .expect_err("control plane sled can't be removed");
+    assert_eq!(cptestctx.sled_agents().count(), 2);
+
+    cptestctx.teardown().await;
+}
+
 #[nexus_test]
 async fn test_physical_disk_create_list_delete(
     cptestctx: &ControlPlaneTestContext,
@@ -183,3 +270,127 @@ async fn test_sled_instance_list(cptestctx: &ControlPlaneTestContext) {
     assert_eq!(project.identity.name, sled_instances[0].project_name);
     assert_eq!(instance.identity.name, sled_instances[0].name);
 }
+
+#[nexus_test]
+async fn test_sled_power_off_requires_force(
+    cptestctx: &ControlPlaneTestContext,
+) {
+    let client = &cptestctx.external_client;
+    let power_off_url =
+        format!("/v1/system/hardware/sleds/{SLED_AGENT_UUID}/power-off");
+
+    // The initial blueprint puts control plane zones on this sled, so we
+    // can't power it off without forcing it.
+    let error = NexusRequest::new(
+        RequestBuilder::new(client, Method::POST, &power_off_url)
+            .expect_status(Some(StatusCode::CONFLICT)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap()
+    .parsed_body::<HttpErrorResponseBody>()
+    .unwrap();
+    assert!(
+        error.message.contains("not been expunged"),
+        "unexpected error: {}",
+        error.message
+    );
+
+    // With `force`, we get past that check.  This sled agent's baseboard
+    // doesn't correspond to any simulated SP, though, so there's nothing to
+    // send the ignition command to.  (See `test_sled_power_control()` for a
+    // sled that does have one.)
+    NexusRequest::new(
+        RequestBuilder::new(
+            client,
+            Method::POST,
+            &format!("{power_off_url}?force=true"),
+        )
+        .expect_status(Some(StatusCode::SERVICE_UNAVAILABLE)),
+    )
+    .authn_as(AuthnMode::PrivilegedUser)
+    .execute()
+    .await
+    .unwrap();
+}
+
+#[nexus_test]
+async fn test_sled_power_control(cptestctx: &ControlPlaneTestContext) {
+    let client = &cptestctx.external_client;
+
+    // Start a sled agent whose baseboard matches the gimlet that sp-sim
+    // simulates in sled slot 1, so that Nexus can find the SP managing it.
+    let sled_id = Uuid::new_v4();
+    let mut config = sim::Config::for_testing(
+        sled_id,
+        sim::SimMode::Explicit,
+        Some(cptestctx.server.get_http_server_internal_address().await),
+        Some(Utf8Path::new("/should/not/be/used")),
+        None,
+    );
+    config.hardware.baseboard = sim::Baseboard::Gimlet {
+        identifier: String::from("SimGimlet01"),
+        model: String::from("i86pc"),
+        revision: 0,
+    };
+    let log = cptestctx.logctx.log.new(o!("sled_id" => sled_id.to_string()));
+    let sled_agent = sim::Server::start(&config, &log, true).await.unwrap();
+
+    // Nexus finds the SP through the latest inventory collection.
+    activate_background_task(
+        &cptestctx.internal_client,
+        "inventory_collection",
+    )
+    .await;
+
+    let sled_url = format!("/v1/system/hardware/sleds/{sled_id}");
+    let post = |path: &'static str| {
+        let url = format!("{sled_url}/{path}");
+        async move {
+            NexusRequest::new(
+                RequestBuilder::new(client, Method::POST, &url)
+                    .expect_status(Some(StatusCode::NO_CONTENT)),
+            )
+            .authn_as(AuthnMode::PrivilegedUser)
+            .execute()
+            .await
+            .unwrap_or_else(|error| panic!("POST {url}: {error:#}"));
+        }
+    };
+
+    // Each switch's MGS has its own simulated sidecar, and Nexus sends the
+    // ignition command through whichever MGS it reaches first, so check that
+    // one of them saw the sled's power change.
+    let sled_powered_on = || async {
+        let mut powered_on = Vec::new();
+        for switch in [SwitchLocation::Switch0, SwitchLocation::Switch1] {
+            let ignition = NexusRequest::object_get(
+                &cptestctx.gateway[&switch].client,
+                "/ignition/sled/1",
+            )
+            .execute_and_parse_unwrap::<serde_json::Value>()
+            .await;
+            powered_on.push(
+                ignition["details"]["power"]
+                    .as_bool()
+                    .expect("sled is present with a power state"),
+            );
+        }
+        powered_on
+    };
+    assert_eq!(sled_powered_on().await, [true, true]);
+
+    // This sled has no control plane zones, but `force` skips checking for
+    // them anyway.
+    post("power-off?force=true").await;
+    assert!(
+        sled_powered_on().await.contains(&false),
+        "sled powered off through one of the switches"
+    );
+
+    post("power-cycle").await;
+    post("reset-sp").await;
+
+    sled_agent.http_server.close().await.unwrap();
+}
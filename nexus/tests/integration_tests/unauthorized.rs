@@ -190,6 +190,21 @@ pub static HTTP_SERVER: Lazy<httptest::Server> =
                 .respond_with(status_code(200).body(SAML_IDP_DESCRIPTOR)),
         );
 
+        let oidc_issuer = server.url("/oidc").to_string();
+        server.expect(
+            Expectation::matching(request::method_path(
+                "GET",
+                "/oidc/.well-known/openid-configuration",
+            ))
+            .times(1..)
+            .respond_with(json_encoded(serde_json::json!({
+                "issuer": oidc_issuer,
+                "authorization_endpoint": format!("{}/authorize", oidc_issuer),
+                "token_endpoint": format!("{}/token", oidc_issuer),
+                "jwks_uri": format!("{}/jwks", oidc_issuer),
+            }))),
+        );
+
         server
     });
 
@@ -202,6 +217,30 @@ static SETUP_REQUESTS: Lazy<Vec<SetupReq>> = Lazy::new(|| {
             body: serde_json::to_value(&*DEMO_SILO_CREATE).unwrap(),
             id_routes: vec![],
         },
+        // Create a Silo provisioned via SCIM, along with a bearer token for
+        // its SCIM client
+        SetupReq::Post {
+            url: "/v1/system/silos",
+            body: serde_json::to_value(&*DEMO_SCIM_SILO_CREATE).unwrap(),
+            id_routes: vec![],
+        },
+        SetupReq::Post {
+            url: &DEMO_SCIM_TOKENS_URL,
+            body: serde_json::Value::Null,
+            id_routes: vec![&*DEMO_SCIM_TOKEN_URL],
+        },
+        // Create a service account, along with a token for it
+        SetupReq::Post {
+            url: &DEMO_SERVICE_ACCOUNTS_URL,
+            body: serde_json::to_value(&*DEMO_SERVICE_ACCOUNT_CREATE).unwrap(),
+            id_routes: vec![],
+        },
+        SetupReq::Post {
+            url: &DEMO_SERVICE_ACCOUNT_TOKENS_URL,
+            body: serde_json::to_value(&*DEMO_SERVICE_ACCOUNT_TOKEN_CREATE)
+                .unwrap(),
+            id_routes: vec![&*DEMO_SERVICE_ACCOUNT_TOKEN_URL],
+        },
         // Create a local User
         SetupReq::Post {
             url: &DEMO_SILO_USERS_CREATE_URL,
@@ -307,6 +346,12 @@ static SETUP_REQUESTS: Lazy<Vec<SetupReq>> = Lazy::new(|| {
             body: serde_json::to_value(&*SAML_IDENTITY_PROVIDER).unwrap(),
             id_routes: vec![],
         },
+        // Create an OIDC identity provider
+        SetupReq::Post {
+            url: &OIDC_IDENTITY_PROVIDERS_URL,
+            body: serde_json::to_value(&*OIDC_IDENTITY_PROVIDER).unwrap(),
+            id_routes: vec![],
+        },
         // Create a SSH key
         SetupReq::Post {
             url: &DEMO_SSHKEYS_URL,
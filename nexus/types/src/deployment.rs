@@ -47,11 +47,15 @@ use uuid::Uuid;
 
 mod blueprint_diff;
 mod blueprint_display;
+mod mgs_updates;
 mod network_resources;
 mod planning_input;
 mod tri_map;
 mod zone_type;
 
+pub use mgs_updates::PendingMgsUpdate;
+pub use mgs_updates::PendingMgsUpdateDetails;
+pub use mgs_updates::PendingMgsUpdates;
 pub use network_resources::AddNetworkResourceError;
 pub use network_resources::OmicronZoneExternalFloatingAddr;
 pub use network_resources::OmicronZoneExternalFloatingIp;
@@ -71,6 +75,9 @@ pub use planning_input::SledDetails;
 pub use planning_input::SledDisk;
 pub use planning_input::SledFilter;
 pub use planning_input::SledResources;
+pub use planning_input::TargetRelease;
+pub use planning_input::TargetReleaseArtifact;
+pub use planning_input::TargetReleaseArtifactKind;
 pub use planning_input::ZpoolFilter;
 pub use zone_type::blueprint_zone_type;
 pub use zone_type::BlueprintZoneType;
@@ -117,10 +124,11 @@ pub use blueprint_diff::BlueprintDiff;
 // particular, by the time we get to execution, all the hard choices have
 // already been made.
 //
-// Currently, blueprints are limited to describing only the set of Omicron
-// zones deployed on each host and some supporting configuration (e.g., DNS).
-// This is aimed at supporting add/remove sleds.  The plan is to grow this to
-// include more of the system as we support more use cases.
+// Currently, blueprints are limited to describing the set of Omicron zones
+// deployed on each host, some supporting configuration (e.g., DNS), and the
+// updates to MGS-managed software (SP, RoT, and host phase 1) that are in
+// progress.  The plan is to grow this to include more of the system as we
+// support more use cases.
 #[derive(Clone, Debug, Eq, PartialEq, JsonSchema, Deserialize, Serialize)]
 pub struct Blueprint {
     /// unique identifier for this blueprint
@@ -144,6 +152,9 @@ pub struct Blueprint {
     /// A map of sled id -> disks in use on each sled.
     pub blueprint_disks: BTreeMap<SledUuid, BlueprintPhysicalDisksConfig>,
 
+    /// updates to SP, RoT, and host phase 1 software that are in progress
+    pub pending_mgs_updates: PendingMgsUpdates,
+
     /// which blueprint this blueprint is based on
     pub parent_blueprint_id: Option<Uuid>,
 
@@ -235,6 +246,8 @@ impl Blueprint {
                 .map(|(sled_id, disks)| (*sled_id, disks.clone().into()))
                 .collect(),
             self.blueprint_disks.clone(),
+            before.pending_mgs_updates.clone(),
+            self.pending_mgs_updates.clone(),
         )
     }
 
@@ -281,6 +294,8 @@ impl Blueprint {
             self.blueprint_zones.clone(),
             before_disks,
             self.blueprint_disks.clone(),
+            PendingMgsUpdates::new(),
+            self.pending_mgs_updates.clone(),
         )
     }
 
@@ -448,6 +463,14 @@ impl<'a> fmt::Display for BlueprintDisplay<'a> {
 
         writeln!(f, "{}", self.make_metadata_table())?;
 
+        if !b.pending_mgs_updates.is_empty() {
+            writeln!(f, " {PENDING_MGS_UPDATES_HEADING}:")?;
+            for update in b.pending_mgs_updates.iter() {
+                writeln!(f, "      {update}")?;
+            }
+            writeln!(f)?;
+        }
+
         Ok(())
     }
 }
@@ -898,6 +921,7 @@ impl BlueprintZoneDisposition {
                 BlueprintZoneFilter::ShouldBeExternallyReachable => true,
                 BlueprintZoneFilter::ShouldBeInInternalDns => true,
                 BlueprintZoneFilter::ShouldDeployVpcFirewallRules => true,
+                BlueprintZoneFilter::ShouldPreventSledPowerOff => true,
             },
             Self::Quiesced => match filter {
                 BlueprintZoneFilter::All => true,
@@ -914,6 +938,10 @@ impl BlueprintZoneDisposition {
 
                 // Quiesced zones should get firewall rules.
                 BlueprintZoneFilter::ShouldDeployVpcFirewallRules => true,
+
+                // Quiesced zones are still part of the control plane, so
+                // powering off their sled would take them away unexpectedly.
+                BlueprintZoneFilter::ShouldPreventSledPowerOff => true,
             },
             Self::Expunged => match filter {
                 BlueprintZoneFilter::All => true,
@@ -921,6 +949,7 @@ impl BlueprintZoneDisposition {
                 BlueprintZoneFilter::ShouldBeExternallyReachable => false,
                 BlueprintZoneFilter::ShouldBeInInternalDns => false,
                 BlueprintZoneFilter::ShouldDeployVpcFirewallRules => false,
+                BlueprintZoneFilter::ShouldPreventSledPowerOff => false,
             },
         }
     }
@@ -972,6 +1001,10 @@ pub enum BlueprintZoneFilter {
 
     /// Filter by zones that should be sent VPC firewall rules.
     ShouldDeployVpcFirewallRules,
+
+    /// Filter by zones whose presence should keep an operator from powering
+    /// off their sled (unless forced).
+    ShouldPreventSledPowerOff,
 }
 
 /// Information about an Omicron physical disk as recorded in a blueprint.
@@ -24,7 +24,7 @@ use crate::deployment::{
     BlueprintOrCollectionZoneConfig, BlueprintOrCollectionZonesConfig,
     BlueprintPhysicalDisksConfig, BlueprintZoneConfig,
     BlueprintZoneDisposition, BlueprintZonesConfig, DiffBeforeMetadata,
-    ZoneSortKey,
+    PendingMgsUpdates, ZoneSortKey,
 };
 
 /// Diffs for omicron zones on a given sled with a given `BpDiffState`
@@ -561,6 +561,8 @@ pub struct BlueprintDiff {
     pub sleds_removed: BTreeSet<SledUuid>,
     pub sleds_unchanged: BTreeSet<SledUuid>,
     pub sleds_modified: BTreeSet<SledUuid>,
+    pub pending_mgs_updates_before: PendingMgsUpdates,
+    pub pending_mgs_updates_after: PendingMgsUpdates,
 }
 
 impl BlueprintDiff {
@@ -573,6 +575,8 @@ impl BlueprintDiff {
         after_zones: BTreeMap<SledUuid, BlueprintZonesConfig>,
         before_disks: BTreeMap<SledUuid, BlueprintOrCollectionDisksConfig>,
         after_disks: BTreeMap<SledUuid, BlueprintPhysicalDisksConfig>,
+        pending_mgs_updates_before: PendingMgsUpdates,
+        pending_mgs_updates_after: PendingMgsUpdates,
     ) -> Self {
         let before_sleds: BTreeSet<_> =
             before_zones.keys().chain(before_disks.keys()).collect();
@@ -637,9 +641,22 @@ impl BlueprintDiff {
             sleds_removed,
             sleds_unchanged: unchanged_sleds,
             sleds_modified,
+            pending_mgs_updates_before,
+            pending_mgs_updates_after,
         }
     }
 
+    /// Returns true if any sleds, zones, physical disks, or pending MGS updates
+    /// differ between the two sides of the diff.
+    ///
+    /// Differences in metadata (like the blueprint id or comment) are ignored.
+    pub fn has_changes(&self) -> bool {
+        !self.sleds_added.is_empty()
+            || !self.sleds_removed.is_empty()
+            || !self.sleds_modified.is_empty()
+            || self.pending_mgs_updates_before != self.pending_mgs_updates_after
+    }
+
     /// Return a struct that can be used to display the diff.
     pub fn display(&self) -> BlueprintDiffDisplay<'_> {
         BlueprintDiffDisplay::new(self)
@@ -729,6 +746,44 @@ impl<'diff> BlueprintDiffDisplay<'diff> {
         KvListWithHeading::new(METADATA_HEADING, kv)
     }
 
+    /// Write out the pending MGS updates that were added, removed, or kept
+    fn write_pending_mgs_updates(
+        &self,
+        f: &mut fmt::Formatter<'_>,
+    ) -> fmt::Result {
+        let before = &self.diff.pending_mgs_updates_before;
+        let after = &self.diff.pending_mgs_updates_after;
+        if before.is_empty() && after.is_empty() {
+            return Ok(());
+        }
+
+        writeln!(f, " {PENDING_MGS_UPDATES_HEADING}:")?;
+        for update in before.iter() {
+            match after.get(&update.baseboard_id) {
+                Some(new) if new == update => {
+                    writeln!(
+                        f,
+                        "{}     {update}",
+                        BpDiffState::Unchanged.prefix()
+                    )?;
+                }
+                _ => {
+                    writeln!(
+                        f,
+                        "{}     {update}",
+                        BpDiffState::Removed.prefix()
+                    )?;
+                }
+            }
+        }
+        for update in after.iter() {
+            if before.get(&update.baseboard_id) != Some(update) {
+                writeln!(f, "{}     {update}", BpDiffState::Added.prefix())?;
+            }
+        }
+        writeln!(f)
+    }
+
     /// Write out physical disk and zone tables for a given `sled_id`
     fn write_tables(
         &self,
@@ -849,6 +904,9 @@ impl<'diff> fmt::Display for BlueprintDiffDisplay<'diff> {
         // Write out metadata diff table
         writeln!(f, "{}", self.make_metadata_diff_table())?;
 
+        // Write out any pending MGS updates
+        self.write_pending_mgs_updates(f)?;
+
         Ok(())
     }
 }
@@ -24,6 +24,7 @@ pub mod constants {
     pub const INTERNAL_DNS_VERSION: &str = "internal DNS version";
     pub const EXTERNAL_DNS_VERSION: &str = "external DNS version";
     pub const COMMENT: &str = "comment";
+    pub const PENDING_MGS_UPDATES_HEADING: &str = "PENDING MGS UPDATES";
 
     pub const UNCHANGED_PARENS: &str = "(unchanged)";
     pub const NONE_PARENS: &str = "(none)";
@@ -0,0 +1,240 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Types describing updates to software that's delivered through MGS: service
+//! processor firmware, root of trust firmware, and host phase 1 images
+
+use crate::inventory::BaseboardId;
+use crate::inventory::CabooseWhich;
+use crate::inventory::Collection;
+use crate::inventory::RotSlot;
+use crate::inventory::SpType;
+use omicron_common::api::external::SemverVersion;
+use omicron_common::update::ArtifactHash;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use std::fmt;
+
+/// Describes the updates to MGS-managed components that the planner wants
+/// carried out
+///
+/// There is at most one pending update for each baseboard.
+#[derive(
+    Clone, Debug, Default, Eq, PartialEq, JsonSchema, Deserialize, Serialize,
+)]
+#[serde(transparent)]
+pub struct PendingMgsUpdates {
+    // This is kept sorted by baseboard id so that equivalent sets of updates
+    // compare equal regardless of the order in which they were inserted.
+    updates: Vec<PendingMgsUpdate>,
+}
+
+impl PendingMgsUpdates {
+    pub fn new() -> PendingMgsUpdates {
+        PendingMgsUpdates::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.updates.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.updates.len()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &PendingMgsUpdate> + '_ {
+        self.updates.iter()
+    }
+
+    /// Returns the pending update for the given baseboard, if any
+    pub fn get(&self, baseboard_id: &BaseboardId) -> Option<&PendingMgsUpdate> {
+        self.position(baseboard_id).ok().map(|i| &self.updates[i])
+    }
+
+    /// Adds a pending update, returning the update that it replaced for the
+    /// same baseboard, if any
+    pub fn insert(
+        &mut self,
+        update: PendingMgsUpdate,
+    ) -> Option<PendingMgsUpdate> {
+        match self.position(&update.baseboard_id) {
+            Ok(i) => Some(std::mem::replace(&mut self.updates[i], update)),
+            Err(i) => {
+                self.updates.insert(i, update);
+                None
+            }
+        }
+    }
+
+    /// Removes the pending update for the given baseboard, if any
+    pub fn remove(
+        &mut self,
+        baseboard_id: &BaseboardId,
+    ) -> Option<PendingMgsUpdate> {
+        self.position(baseboard_id).ok().map(|i| self.updates.remove(i))
+    }
+
+    fn position(&self, baseboard_id: &BaseboardId) -> Result<usize, usize> {
+        self.updates.binary_search_by(|u| u.baseboard_id.cmp(baseboard_id))
+    }
+}
+
+impl FromIterator<PendingMgsUpdate> for PendingMgsUpdates {
+    fn from_iter<T: IntoIterator<Item = PendingMgsUpdate>>(iter: T) -> Self {
+        let mut updates = PendingMgsUpdates::new();
+        for update in iter {
+            updates.insert(update);
+        }
+        updates
+    }
+}
+
+/// Describes one update to a component managed through MGS
+///
+/// Each update carries a precondition: the state that the planner observed in
+/// inventory when it decided on the update.  Executors only deliver the update
+/// while the component is still in that state.  This way, if the system
+/// changes underneath us (e.g., the component was updated some other way, or
+/// another Nexus already finished this update), we don't clobber it, and the
+/// planner gets a chance to look at the new state.
+#[derive(Clone, Debug, Eq, PartialEq, JsonSchema, Deserialize, Serialize)]
+pub struct PendingMgsUpdate {
+    /// identifies the baseboard whose component is being updated
+    pub baseboard_id: BaseboardId,
+    /// what type of baseboard this is
+    pub sp_type: SpType,
+    /// last known MGS slot (cubby number) of the baseboard
+    pub slot_id: u16,
+    /// component-specific details of the update
+    pub details: PendingMgsUpdateDetails,
+    /// hash of the artifact to deliver
+    pub artifact_hash: ArtifactHash,
+    /// version of the artifact to deliver
+    pub artifact_version: SemverVersion,
+}
+
+impl PendingMgsUpdate {
+    /// Returns a short name for the component being updated
+    pub fn component_name(&self) -> &'static str {
+        self.details.component_name()
+    }
+}
+
+impl PendingMgsUpdate {
+    /// Returns whether the component is still in the state that the planner
+    /// expected when it decided on this update
+    ///
+    /// `last_host_phase_1` is the host phase 1 artifact that the control plane
+    /// last delivered to this baseboard, if any.  (Inventory can't tell us
+    /// what's in the host's boot flash.)
+    pub fn precondition_holds(
+        &self,
+        collection: &Collection,
+        last_host_phase_1: Option<ArtifactHash>,
+    ) -> bool {
+        let caboose_version = |which| {
+            collection
+                .caboose_for(which, &self.baseboard_id)
+                .map(|found| found.caboose.version.as_str())
+        };
+
+        match &self.details {
+            PendingMgsUpdateDetails::Sp {
+                expected_active_version,
+                expected_inactive_version,
+            } => {
+                caboose_version(CabooseWhich::SpSlot0)
+                    == Some(expected_active_version.as_str())
+                    && caboose_version(CabooseWhich::SpSlot1)
+                        == expected_inactive_version.as_deref()
+            }
+            PendingMgsUpdateDetails::Rot {
+                expected_active_slot,
+                expected_active_version,
+                expected_inactive_version,
+            } => {
+                let Some(rot) = collection.rots.get(&self.baseboard_id) else {
+                    return false;
+                };
+                let (active, inactive) = match expected_active_slot {
+                    RotSlot::A => {
+                        (CabooseWhich::RotSlotA, CabooseWhich::RotSlotB)
+                    }
+                    RotSlot::B => {
+                        (CabooseWhich::RotSlotB, CabooseWhich::RotSlotA)
+                    }
+                };
+                rot.active_slot == *expected_active_slot
+                    && caboose_version(active)
+                        == Some(expected_active_version.as_str())
+                    && caboose_version(inactive)
+                        == expected_inactive_version.as_deref()
+            }
+            PendingMgsUpdateDetails::HostPhase1 { expected_artifact } => {
+                collection.sps.contains_key(&self.baseboard_id)
+                    && last_host_phase_1 == *expected_artifact
+            }
+        }
+    }
+}
+
+impl fmt::Display for PendingMgsUpdate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:?} {} ({}:{}): {} to {} ({})",
+            self.sp_type,
+            self.slot_id,
+            self.baseboard_id.part_number,
+            self.baseboard_id.serial_number,
+            self.component_name(),
+            self.artifact_version,
+            self.artifact_hash,
+        )
+    }
+}
+
+/// Describes which component of a baseboard is being updated and what state
+/// it's expected to be in before the update is delivered
+#[derive(Clone, Debug, Eq, PartialEq, JsonSchema, Deserialize, Serialize)]
+#[serde(tag = "component", rename_all = "snake_case")]
+pub enum PendingMgsUpdateDetails {
+    /// the SP itself is being updated
+    Sp {
+        /// expected version of the active SP slot
+        expected_active_version: String,
+        /// expected version of the inactive SP slot, if it's valid
+        expected_inactive_version: Option<String>,
+    },
+    /// the RoT is being updated
+    ///
+    /// The new image is written to whichever slot is not `expected_active_slot`.
+    Rot {
+        /// expected active RoT slot
+        expected_active_slot: RotSlot,
+        /// expected version of the active RoT slot
+        expected_active_version: String,
+        /// expected version of the inactive RoT slot, if it's valid
+        expected_inactive_version: Option<String>,
+    },
+    /// the host phase 1 image is being updated
+    #[serde(rename = "host_phase_1")]
+    HostPhase1 {
+        /// the phase 1 artifact that the control plane last delivered to this
+        /// host, if any
+        expected_artifact: Option<ArtifactHash>,
+    },
+}
+
+impl PendingMgsUpdateDetails {
+    /// Returns a short name for the component being updated
+    pub fn component_name(&self) -> &'static str {
+        match self {
+            PendingMgsUpdateDetails::Sp { .. } => "sp",
+            PendingMgsUpdateDetails::Rot { .. } => "rot",
+            PendingMgsUpdateDetails::HostPhase1 { .. } => "host_phase_1",
+        }
+    }
+}
@@ -14,20 +14,26 @@ use crate::external_api::views::PhysicalDiskState;
 use crate::external_api::views::SledPolicy;
 use crate::external_api::views::SledProvisionPolicy;
 use crate::external_api::views::SledState;
+use crate::inventory::BaseboardId;
+use crate::inventory::RotSlot;
+use crate::inventory::SpType;
 use clap::ValueEnum;
 use ipnetwork::IpNetwork;
 use omicron_common::address::IpRange;
 use omicron_common::address::Ipv6Subnet;
 use omicron_common::address::SLED_PREFIX;
 use omicron_common::api::external::Generation;
+use omicron_common::api::external::SemverVersion;
 use omicron_common::api::internal::shared::SourceNatConfigError;
 use omicron_common::disk::DiskIdentity;
+use omicron_common::update::ArtifactHash;
 use omicron_uuid_kinds::OmicronZoneUuid;
 use omicron_uuid_kinds::PhysicalDiskUuid;
 use omicron_uuid_kinds::SledUuid;
 use omicron_uuid_kinds::ZpoolUuid;
 use serde::Deserialize;
 use serde::Serialize;
+use serde_with::serde_as;
 use std::collections::btree_map::Entry;
 use std::collections::BTreeMap;
 use strum::IntoEnumIterator;
@@ -48,6 +54,7 @@ use strum::IntoEnumIterator;
 /// - Each Omicron zone has at most one external IP and at most one vNIC.
 /// - A given external IP or vNIC is only associated with a single Omicron
 ///   zone.
+#[serde_as]
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct PlanningInput {
     /// fleet-wide policy
@@ -64,6 +71,10 @@ pub struct PlanningInput {
 
     /// per-zone network resources
     network_resources: OmicronZoneNetworkResources,
+
+    /// host phase 1 artifact most recently delivered to each baseboard
+    #[serde_as(as = "Vec<(_, _)>")]
+    host_phase_1_artifacts: BTreeMap<BaseboardId, ArtifactHash>,
 }
 
 impl PlanningInput {
@@ -83,6 +94,19 @@ impl PlanningInput {
         &self.policy.service_ip_pool_ranges
     }
 
+    pub fn target_release(&self) -> Option<&TargetRelease> {
+        self.policy.target_release.as_ref()
+    }
+
+    /// Returns the host phase 1 artifact that the control plane most recently
+    /// delivered to the given baseboard, if any
+    pub fn host_phase_1_artifact(
+        &self,
+        baseboard_id: &BaseboardId,
+    ) -> Option<&ArtifactHash> {
+        self.host_phase_1_artifacts.get(baseboard_id)
+    }
+
     pub fn all_sleds(
         &self,
         filter: SledFilter,
@@ -132,6 +156,7 @@ impl PlanningInput {
             external_dns_version: self.external_dns_version,
             sleds: self.sleds,
             network_resources: self.network_resources,
+            host_phase_1_artifacts: self.host_phase_1_artifacts,
         }
     }
 }
@@ -447,6 +472,90 @@ pub struct Policy {
 
     /// desired total number of deployed Nexus zones
     pub target_nexus_zone_count: usize,
+
+    /// release that the operator wants the system to be running, if any
+    ///
+    /// If this is `None`, the planner does not update any software that's
+    /// delivered through MGS.
+    pub target_release: Option<TargetRelease>,
+}
+
+/// Describes the system software release that the operator has asked the
+/// system to run
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TargetRelease {
+    /// system version of the TUF repository that this release came from
+    pub system_version: SemverVersion,
+
+    /// artifacts from the release that are delivered through MGS
+    pub artifacts: Vec<TargetReleaseArtifact>,
+}
+
+impl TargetRelease {
+    /// Returns the SP artifact for the given type of baseboard with the given
+    /// board name (from its caboose), if any
+    pub fn sp_artifact(
+        &self,
+        sp_type: SpType,
+        board: &str,
+    ) -> Option<&TargetReleaseArtifact> {
+        self.artifacts.iter().find(|a| {
+            a.sp_type == sp_type
+                && matches!(
+                    &a.kind,
+                    TargetReleaseArtifactKind::Sp { board: b } if b == board
+                )
+        })
+    }
+
+    /// Returns the RoT artifact for the given type of baseboard and the given
+    /// RoT slot, if any
+    pub fn rot_artifact(
+        &self,
+        sp_type: SpType,
+        slot: &RotSlot,
+    ) -> Option<&TargetReleaseArtifact> {
+        self.artifacts.iter().find(|a| {
+            a.sp_type == sp_type
+                && matches!(
+                    &a.kind,
+                    TargetReleaseArtifactKind::Rot { slot: s } if s == slot
+                )
+        })
+    }
+
+    /// Returns the host phase 1 artifact, if any
+    pub fn host_phase_1_artifact(&self) -> Option<&TargetReleaseArtifact> {
+        self.artifacts
+            .iter()
+            .find(|a| a.kind == TargetReleaseArtifactKind::HostPhase1)
+    }
+}
+
+/// Describes one artifact in a [`TargetRelease`]
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct TargetReleaseArtifact {
+    /// which component this artifact is for
+    pub kind: TargetReleaseArtifactKind,
+    /// which type of baseboard this artifact is for
+    pub sp_type: SpType,
+    /// version of the artifact
+    pub version: SemverVersion,
+    /// hash of the artifact's contents
+    pub hash: ArtifactHash,
+}
+
+/// Describes which component a [`TargetReleaseArtifact`] is for
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "component", rename_all = "snake_case")]
+pub enum TargetReleaseArtifactKind {
+    /// SP image for baseboards whose caboose reports this board name
+    Sp { board: String },
+    /// RoT image for the given RoT slot
+    Rot { slot: RotSlot },
+    /// host phase 1 image
+    #[serde(rename = "host_phase_1")]
+    HostPhase1,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -485,6 +594,7 @@ pub struct PlanningInputBuilder {
     external_dns_version: Generation,
     sleds: BTreeMap<SledUuid, SledDetails>,
     network_resources: OmicronZoneNetworkResources,
+    host_phase_1_artifacts: BTreeMap<BaseboardId, ArtifactHash>,
 }
 
 impl PlanningInputBuilder {
@@ -494,11 +604,13 @@ impl PlanningInputBuilder {
             policy: Policy {
                 service_ip_pool_ranges: Vec::new(),
                 target_nexus_zone_count: 0,
+                target_release: None,
             },
             internal_dns_version: Generation::new(),
             external_dns_version: Generation::new(),
             sleds: BTreeMap::new(),
             network_resources: OmicronZoneNetworkResources::new(),
+            host_phase_1_artifacts: BTreeMap::new(),
         }
     }
 
@@ -513,6 +625,7 @@ impl PlanningInputBuilder {
             external_dns_version,
             sleds: BTreeMap::new(),
             network_resources: OmicronZoneNetworkResources::new(),
+            host_phase_1_artifacts: BTreeMap::new(),
         }
     }
 
@@ -548,6 +661,16 @@ impl PlanningInputBuilder {
         Ok(self.network_resources.add_nic(zone_id, nic)?)
     }
 
+    /// Records the host phase 1 artifact that the control plane most recently
+    /// delivered to the given baseboard
+    pub fn set_host_phase_1_artifact(
+        &mut self,
+        baseboard_id: BaseboardId,
+        hash: ArtifactHash,
+    ) {
+        self.host_phase_1_artifacts.insert(baseboard_id, hash);
+    }
+
     pub fn network_resources_mut(
         &mut self,
     ) -> &mut OmicronZoneNetworkResources {
@@ -581,6 +704,7 @@ impl PlanningInputBuilder {
             external_dns_version: self.external_dns_version,
             sleds: self.sleds,
             network_resources: self.network_resources,
+            host_phase_1_artifacts: self.host_phase_1_artifacts,
         }
     }
 }
@@ -79,6 +79,8 @@ path_param!(SiloPath, silo, "silo");
 path_param!(ProviderPath, provider, "SAML identity provider");
 path_param!(IpPoolPath, pool, "IP pool");
 path_param!(SshKeyPath, ssh_key, "SSH key");
+path_param!(ApiTokenPath, token, "API token");
+path_param!(ServiceAccountPath, service_account, "service account");
 path_param!(AddressLotPath, address_lot, "address lot");
 path_param!(ProbePath, probe, "probe");
 
@@ -116,6 +118,17 @@ pub struct SledProvisionPolicyResponse {
     pub new_state: super::views::SledProvisionPolicy,
 }
 
+/// Query parameters for `sled_power_off`.
+#[derive(
+    Clone, Debug, Default, Serialize, Deserialize, JsonSchema, PartialEq,
+)]
+pub struct SledPowerOffParams {
+    /// Power off the sled even if it still has control plane zones that have
+    /// not been expunged.
+    #[serde(default)]
+    pub force: bool,
+}
+
 pub struct SwitchSelector {
     /// ID of the switch
     pub switch: Uuid,
@@ -147,6 +160,14 @@ pub struct SamlIdentityProviderSelector {
     pub saml_identity_provider: NameOrId,
 }
 
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct OidcIdentityProviderSelector {
+    /// Name or ID of the silo in which the OIDC identity provider is associated
+    pub silo: Option<NameOrId>,
+    /// Name or ID of the OIDC identity provider
+    pub oidc_identity_provider: NameOrId,
+}
+
 // The shape of this selector is slightly different than the others given that
 // silos users can only be specified via ID and are automatically provided by
 // the environment the user is authetnicated in
@@ -158,6 +179,25 @@ pub struct SshKeySelector {
     pub ssh_key: NameOrId,
 }
 
+// Like `SshKeySelector`, API tokens are managed under `/v1/me` and so are
+// always looked up relative to the current user.
+#[derive(Clone, Debug, Serialize, Deserialize, JsonSchema, PartialEq)]
+pub struct ApiTokenSelector {
+    /// ID of the silo user
+    pub silo_user_id: Uuid,
+    /// Name or ID of the API token
+    pub token: NameOrId,
+}
+
+/// Path parameters for a single service account token
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct ServiceAccountTokenPath {
+    /// Name or ID of the service account
+    pub service_account: NameOrId,
+    /// ID of the token
+    pub token_id: Uuid,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema, PartialEq)]
 pub struct ProjectSelector {
     /// Name or ID of the project
@@ -382,6 +422,27 @@ pub struct SiloQuotasUpdate {
     pub storage: Option<ByteCount>,
 }
 
+/// Resource limits for a Project
+///
+/// Unlike Silo quotas, every limit is optional, and this replaces all of the
+/// Project's limits: an omitted value means the Project is limited only by its
+/// Silo's quota for that resource.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, JsonSchema)]
+pub struct ProjectQuotasUpdate {
+    /// The number of virtual CPUs available for running instances in the
+    /// Project
+    #[serde(default)]
+    pub cpus: Option<i64>,
+    /// The amount of RAM (in bytes) available for running instances in the
+    /// Project
+    #[serde(default)]
+    pub memory: Option<ByteCount>,
+    /// The amount of storage (in bytes) available for disks or snapshots in
+    /// the Project
+    #[serde(default)]
+    pub storage: Option<ByteCount>,
+}
+
 /// Create-time parameters for a `User`
 #[derive(Clone, Deserialize, Serialize, JsonSchema)]
 pub struct UserCreate {
@@ -689,6 +750,52 @@ pub struct SamlIdentityProviderCreate {
     pub group_attribute_name: Option<String>,
 }
 
+fn default_oidc_scopes() -> Vec<String> {
+    vec![String::from("openid"), String::from("email"), String::from("profile")]
+}
+
+fn default_oidc_user_claim() -> String {
+    String::from("sub")
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct OidcIdentityProviderCreate {
+    #[serde(flatten)]
+    pub identity: IdentityMetadataCreateParams,
+
+    /// Issuer identifier of the OpenID provider
+    ///
+    /// The provider's configuration is fetched from
+    /// `{issuer}/.well-known/openid-configuration` when the identity provider
+    /// is created.
+    pub issuer: String,
+
+    /// Client ID registered with the OpenID provider
+    pub client_id: String,
+
+    /// Client secret registered with the OpenID provider
+    pub client_secret: String,
+
+    /// Endpoint to which the provider sends users after they authenticate
+    ///
+    /// This must be registered with the provider.  It should be the external
+    /// URL of `/login/{silo_name}/oidc/{provider_name}/callback`.
+    pub redirect_uri: String,
+
+    /// Scopes to request during login.  `openid` is always requested.
+    #[serde(default = "default_oidc_scopes")]
+    pub scopes: Vec<String>,
+
+    /// ID token claim to use as the user's external identifier
+    #[serde(default = "default_oidc_user_claim")]
+    pub user_claim: String,
+
+    /// If set, the ID token claim with this name will be considered to denote
+    /// a user's group membership.  The claim's value may be either a list of
+    /// group names or a comma-separated string of group names.
+    pub group_claim: Option<String>,
+}
+
 /// sign some junk data and validate it with the key pair
 fn sign_junk_data(key_pair: &DerEncodedKeyPair) -> Result<(), anyhow::Error> {
     let private_key = {
@@ -1140,8 +1247,8 @@ pub struct InstanceSerialConsoleRequest {
     /// Name or ID of the project, only required if `instance` is provided as a `Name`
     pub project: Option<NameOrId>,
     /// Character index in the serial buffer from which to read, counting the bytes output since
-    /// instance start. If this is not provided, `most_recent` must be provided, and if this *is*
-    /// provided, `most_recent` must *not* be provided.
+    /// the instance was created, across all of its runs. If this is not provided, `most_recent`
+    /// must be provided, and if this *is* provided, `most_recent` must *not* be provided.
     pub from_start: Option<u64>,
     /// Character index in the serial buffer from which to read, counting *backward* from the most
     /// recently buffered data retrieved from the instance. (See note on `from_start` about mutual
@@ -1158,6 +1265,12 @@ pub struct InstanceSerialConsoleRequest {
 pub struct InstanceSerialConsoleStreamRequest {
     /// Name or ID of the project, only required if `instance` is provided as a `Name`
     pub project: Option<NameOrId>,
+    /// Character index in the serial buffer from which to read, counting the bytes output since
+    /// the instance was created, as for the `serial-console` endpoint. Output from before the
+    /// instance's current run is sent from what was captured of it. At most one of `from_start`
+    /// and `most_recent` may be provided; if neither is, output is sent from the start of the
+    /// current run.
+    pub from_start: Option<u64>,
     /// Character index in the serial buffer from which to read, counting *backward* from the most
     /// recently buffered data retrieved from the instance.
     pub most_recent: Option<u64>,
@@ -1169,8 +1282,8 @@ pub struct InstanceSerialConsoleData {
     /// The bytes starting from the requested offset up to either the end of the buffer or the
     /// request's `max_bytes`. Provided as a u8 array rather than a string, as it may not be UTF-8.
     pub data: Vec<u8>,
-    /// The absolute offset since boot (suitable for use as `byte_offset` in a subsequent request)
-    /// of the last byte returned in `data`.
+    /// The absolute offset since instance creation (suitable for use as `from_start` in a
+    /// subsequent request) of the last byte returned in `data`.
     pub last_byte_offset: u64,
 }
 
@@ -1894,6 +2007,45 @@ pub struct SshKeyCreate {
     pub public_key: String,
 }
 
+// API TOKENS
+
+/// Create-time parameters for an `ApiToken`
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ApiTokenCreate {
+    #[serde(flatten)]
+    pub identity: IdentityMetadataCreateParams,
+
+    /// Time at which the token stops being valid. If omitted, the token does
+    /// not expire and remains valid until it is deleted.
+    #[serde(default)]
+    pub time_expires: Option<DateTime<Utc>>,
+
+    /// Restrictions on what the token may be used for. Scopes only ever
+    /// narrow what the token's owner could do; a token never grants access
+    /// the owner does not already have. With no scopes, the token carries
+    /// all of the owner's privileges.
+    #[serde(default)]
+    pub scopes: Vec<shared::ApiTokenScope>,
+}
+
+// SERVICE ACCOUNTS
+
+/// Create-time parameters for a `ServiceAccount`
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ServiceAccountCreate {
+    #[serde(flatten)]
+    pub identity: IdentityMetadataCreateParams,
+}
+
+/// Create-time parameters for a service account token
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct ServiceAccountTokenCreate {
+    /// Time at which the token stops being valid. If omitted, the token does
+    /// not expire and remains valid until it is deleted.
+    #[serde(default)]
+    pub time_expires: Option<DateTime<Utc>>,
+}
+
 // METRICS
 
 /// Query parameters common to resource metrics endpoints.
@@ -1924,6 +2076,13 @@ pub struct UpdatesGetRepositoryParams {
     pub system_version: SemverVersion,
 }
 
+/// Parameters for PUT requests to `/v1/system/update/target-release`.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct SetTargetReleaseParams {
+    /// The system version of an uploaded TUF repository.
+    pub system_version: SemverVersion,
+}
+
 // Probes
 
 /// Create time parameters for probes.
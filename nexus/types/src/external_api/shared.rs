@@ -7,6 +7,7 @@
 use std::net::IpAddr;
 
 use omicron_common::api::external::Name;
+use omicron_common::api::external::NameOrId;
 use parse_display::FromStr;
 use schemars::JsonSchema;
 use serde::de::Error as _;
@@ -143,6 +144,21 @@ pub enum ProjectRole {
     Viewer,
 }
 
+/// A restriction on the operations an API token may be used for
+///
+/// When a token is viewed, any project is always identified by its id.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ApiTokenScope {
+    /// The token may only be used to read resources, never to create, modify
+    /// or delete them.
+    ReadOnly,
+    /// The token may only be used on resources within the given project.
+    /// Resources outside any project (e.g., silo images or IP pools) may
+    /// still be read.
+    Project { project: NameOrId },
+}
+
 /// Describes what kind of identity is described by an id
 // This is a subset of the identity types that might be found in the database
 // because we do not expose some (e.g., built-in users) externally.
@@ -153,6 +169,7 @@ pub enum ProjectRole {
 pub enum IdentityType {
     SiloUser,
     SiloGroup,
+    ServiceAccount,
 }
 
 /// Describes how identities are managed and users are authenticated in this
@@ -168,6 +185,12 @@ pub enum SiloIdentityMode {
     /// groups).
     SamlJit,
 
+    /// Users are authenticated with SAML using an external authentication
+    /// provider.  Users and groups are provisioned and deprovisioned by the
+    /// identity provider using SCIM 2.0.  They are not created during
+    /// authentication.
+    SamlScim,
+
     /// The system is the source of truth about users.  There is no linkage to
     /// an external authentication provider or identity provider.
     // NOTE: authentication for these users is not supported yet at all.  It
@@ -180,6 +203,7 @@ impl SiloIdentityMode {
         match self {
             SiloIdentityMode::LocalOnly => AuthenticationMode::Local,
             SiloIdentityMode::SamlJit => AuthenticationMode::Saml,
+            SiloIdentityMode::SamlScim => AuthenticationMode::Saml,
         }
     }
 
@@ -187,6 +211,7 @@ impl SiloIdentityMode {
         match self {
             SiloIdentityMode::LocalOnly => UserProvisionType::ApiOnly,
             SiloIdentityMode::SamlJit => UserProvisionType::Jit,
+            SiloIdentityMode::SamlScim => UserProvisionType::Scim,
         }
     }
 }
@@ -214,6 +239,10 @@ pub enum UserProvisionType {
     /// Users and groups are created or updated during authentication using
     /// information provided by the authentication provider
     Jit,
+
+    /// Users and groups are created, updated, and deleted by the identity
+    /// provider using SCIM 2.0.  Nothing is created during authentication.
+    Scim,
 }
 
 /// The service intended to use this certificate.
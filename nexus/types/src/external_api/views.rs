@@ -14,7 +14,7 @@ use chrono::Utc;
 use omicron_common::api::external::{
     AllowedSourceIps as ExternalAllowedSourceIps, ByteCount, Digest, Error,
     IdentityMetadata, InstanceState, Ipv4Net, Ipv6Net, Name, ObjectIdentity,
-    RoleName, SimpleIdentity,
+    RoleName, SemverVersion, SimpleIdentity,
 };
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -71,6 +71,21 @@ pub struct SiloQuotas {
     pub limits: VirtualResourceCounts,
 }
 
+/// The resource limits set on a project
+///
+/// A limit that is not set means the project may use as much of that resource
+/// as its silo's quota allows.
+#[derive(Clone, Debug, PartialEq, Deserialize, Serialize, JsonSchema)]
+pub struct ProjectQuotas {
+    pub project_id: Uuid,
+    /// Number of virtual CPUs
+    pub cpus: Option<i64>,
+    /// Amount of memory in bytes
+    pub memory: Option<ByteCount>,
+    /// Amount of disk storage in bytes
+    pub storage: Option<ByteCount>,
+}
+
 // For the eyes of end users
 /// View of the current silo's resource utilization and capacity
 #[derive(Clone, Debug, Deserialize, Serialize, JsonSchema)]
@@ -79,7 +94,9 @@ pub struct Utilization {
     /// Note that CPU and memory resources associated with a stopped instances are not counted here
     /// whereas associated disks will still be counted
     pub provisioned: VirtualResourceCounts,
-    /// The total amount of resources that can be provisioned in this silo
+    /// The total amount of resources that can be provisioned in this silo (or
+    /// project, if one was specified). A project's capacity is its own quota
+    /// where one is set, and its silo's otherwise.
     /// Actions that would exceed this limit will fail
     pub capacity: VirtualResourceCounts,
 }
@@ -117,6 +134,8 @@ impl SimpleIdentity for SiloUtilization {
 pub enum IdentityProviderType {
     /// SAML identity provider
     Saml,
+    /// OpenID Connect identity provider
+    Oidc,
 }
 
 /// View of an Identity Provider
@@ -157,6 +176,40 @@ pub struct SamlIdentityProvider {
     pub group_attribute_name: Option<String>,
 }
 
+#[derive(ObjectIdentity, Clone, Debug, Deserialize, Serialize, JsonSchema)]
+pub struct OidcIdentityProvider {
+    #[serde(flatten)]
+    pub identity: IdentityMetadata,
+
+    /// Issuer identifier of the OpenID provider
+    pub issuer: String,
+
+    /// Client ID registered with the OpenID provider
+    pub client_id: String,
+
+    /// Endpoint to which users are sent to authenticate
+    pub authorization_endpoint: String,
+
+    /// Endpoint used to exchange authorization codes for tokens
+    pub token_endpoint: String,
+
+    /// Endpoint from which the provider's signing keys are fetched
+    pub jwks_uri: String,
+
+    /// Endpoint to which the provider sends users after they authenticate
+    pub redirect_uri: String,
+
+    /// Scopes requested during login
+    pub scopes: Vec<String>,
+
+    /// ID token claim used as the user's external identifier
+    pub user_claim: String,
+
+    /// If set, the ID token claim with this name will be considered to denote
+    /// a user's group membership, where the values will be the group names.
+    pub group_claim: Option<String>,
+}
+
 // PROJECTS
 
 /// View of a Project
@@ -899,6 +952,88 @@ pub struct SshKey {
     pub public_key: String,
 }
 
+// API TOKENS
+
+/// View of an API token
+///
+/// The token value itself is only returned once, when the token is created.
+#[derive(
+    ObjectIdentity, Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq,
+)]
+pub struct ApiToken {
+    #[serde(flatten)]
+    pub identity: IdentityMetadata,
+
+    /// Time at which the token stops being valid, if any
+    pub time_expires: Option<DateTime<Utc>>,
+
+    /// Approximate time at which the token was last used to authenticate a
+    /// request. This is updated at most once a minute.
+    pub time_last_used: Option<DateTime<Utc>>,
+
+    /// Restrictions on what the token may be used for
+    pub scopes: Vec<shared::ApiTokenScope>,
+}
+
+/// A newly-created API token, including the token value
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct ApiTokenValue {
+    #[serde(flatten)]
+    pub identity: IdentityMetadata,
+
+    pub time_expires: Option<DateTime<Utc>>,
+    pub scopes: Vec<shared::ApiTokenScope>,
+
+    /// The value to present in the `Authorization` header, as
+    /// `Authorization: Bearer <token>`
+    pub bearer_token: String,
+}
+
+// SERVICE ACCOUNTS
+
+/// View of a service account
+///
+/// A service account is a non-human identity within a silo.  It has no
+/// password or identity provider; it authenticates only with tokens, and it
+/// has only the access granted to it through silo or project role
+/// assignments.
+#[derive(
+    ObjectIdentity, Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq,
+)]
+pub struct ServiceAccount {
+    #[serde(flatten)]
+    pub identity: IdentityMetadata,
+
+    /// ID of the silo containing this service account
+    pub silo_id: Uuid,
+}
+
+/// View of a token belonging to a service account
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct ServiceAccountToken {
+    pub id: Uuid,
+    pub time_created: DateTime<Utc>,
+
+    /// Time at which the token stops being valid, if any
+    pub time_expires: Option<DateTime<Utc>>,
+
+    /// Approximate time at which the token was last used to authenticate a
+    /// request
+    pub time_last_used: Option<DateTime<Utc>>,
+}
+
+/// A newly-created service account token, including the token value
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct ServiceAccountTokenValue {
+    pub id: Uuid,
+    pub time_created: DateTime<Utc>,
+    pub time_expires: Option<DateTime<Utc>>,
+
+    /// The value to present in the `Authorization` header, as
+    /// `Authorization: Bearer <token>`
+    pub bearer_token: String,
+}
+
 // OAUTH 2.0 DEVICE AUTHORIZATION REQUESTS & TOKENS
 
 /// Response to an initial device authorization request.
@@ -937,6 +1072,31 @@ pub enum DeviceAccessTokenType {
     Bearer,
 }
 
+// SCIM CLIENT BEARER TOKENS
+
+/// View of a bearer token used by an identity provider to provision users and
+/// groups into a Silo via SCIM 2.0
+///
+/// The token itself is only returned once, when it's created.
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct ScimClientBearerToken {
+    pub id: Uuid,
+    pub time_created: DateTime<Utc>,
+    pub time_expires: Option<DateTime<Utc>>,
+}
+
+/// A newly-created SCIM client bearer token, including the token value
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct ScimClientBearerTokenValue {
+    pub id: Uuid,
+    pub time_created: DateTime<Utc>,
+    pub time_expires: Option<DateTime<Utc>>,
+
+    /// The value that the identity provider should present in the
+    /// `Authorization` header of its SCIM requests
+    pub bearer_token: String,
+}
+
 // SYSTEM HEALTH
 
 #[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize, JsonSchema)]
@@ -964,3 +1124,17 @@ pub struct AllowList {
     /// The allowlist of IPs or subnets.
     pub allowed_ips: ExternalAllowedSourceIps,
 }
+
+// SYSTEM UPDATE
+
+/// The release of the system software that the system should be running
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+pub struct TargetRelease {
+    /// The target release's generation number.  This increases each time the
+    /// target release is changed.
+    pub generation: i64,
+    /// Time the target release was requested.
+    pub time_requested: DateTime<Utc>,
+    /// The system version of the release, if one has been set.
+    pub system_version: Option<SemverVersion>,
+}
@@ -21,10 +21,12 @@ use omicron_common::api::external::ByteCount;
 pub use omicron_common::api::internal::shared::NetworkInterface;
 pub use omicron_common::api::internal::shared::NetworkInterfaceKind;
 pub use omicron_common::api::internal::shared::SourceNatConfig;
+pub use omicron_common::api::internal::shared::SwitchLocation;
 pub use omicron_common::zpool_name::ZpoolName;
 use omicron_uuid_kinds::CollectionUuid;
 use omicron_uuid_kinds::SledUuid;
 use omicron_uuid_kinds::ZpoolUuid;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use serde_with::serde_as;
 pub use sled_agent_client::types::OmicronZoneConfig;
@@ -109,12 +111,24 @@ pub struct Collection {
     #[serde_as(as = "BTreeMap<_, Vec<(_, _)>>")]
     pub rot_pages_found:
         BTreeMap<RotPageWhich, BTreeMap<Arc<BaseboardId>, RotPageFound>>,
+    /// readings from each SP's environmental sensors, keyed by baseboard id
+    ///
+    /// In practice, these will be inserted into the `inv_sp_sensor` table.
+    #[serde_as(as = "Vec<(_, _)>")]
+    pub sensor_readings: BTreeMap<Arc<BaseboardId>, Vec<SensorReading>>,
 
     /// Sled Agent information, by *sled* id
     pub sled_agents: BTreeMap<SledUuid, SledAgent>,
 
     /// Omicron zones found, by *sled* id
     pub omicron_zones: BTreeMap<SledUuid, OmicronZonesFound>,
+
+    /// Switch state reported by each switch zone's dataplane daemon (dpd), by
+    /// switch location
+    ///
+    /// In practice, these will be inserted into the `inv_switch` and
+    /// `inv_switch_port` tables.
+    pub switches: BTreeMap<SwitchLocation, Switch>,
 }
 
 impl Collection {
@@ -168,7 +182,15 @@ impl Collection {
 /// the same part number and serial number but a new revision number, we'd want
 /// to treat that as the same baseboard as one with a different revision number.
 #[derive(
-    Clone, Debug, Ord, Eq, PartialOrd, PartialEq, Deserialize, Serialize,
+    Clone,
+    Debug,
+    Ord,
+    Eq,
+    PartialOrd,
+    PartialEq,
+    Deserialize,
+    Serialize,
+    JsonSchema,
 )]
 pub struct BaseboardId {
     /// Oxide Part Number
@@ -347,6 +369,87 @@ impl IntoRotPage for gateway_client::types::RotCfpa {
     }
 }
 
+/// A reading from one of an SP's environmental sensors (what the SP calls a
+/// "measurement channel")
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct SensorReading {
+    pub time_collected: DateTime<Utc>,
+    pub source: String,
+    /// SP component that owns the sensor (e.g., `dev-0`)
+    pub component: String,
+    /// the physical device behind the component (e.g., `tmp117`)
+    pub device: String,
+    /// name of the sensor, unique within a component and kind
+    pub name: String,
+    pub kind: SensorKind,
+    /// the value read from the sensor, or the error code the SP reported in
+    /// place of a value
+    ///
+    /// Units depend on `kind`: degrees Celsius, watts, amps, volts, or RPM.
+    pub value: Result<f32, String>,
+}
+
+// Sensor values are compared bitwise so that `Collection` can be `Eq`.
+impl PartialEq for SensorReading {
+    fn eq(&self, other: &Self) -> bool {
+        self.time_collected == other.time_collected
+            && self.source == other.source
+            && self.component == other.component
+            && self.device == other.device
+            && self.name == other.name
+            && self.kind == other.kind
+            && self.value.as_ref().map(|v| v.to_bits())
+                == other.value.as_ref().map(|v| v.to_bits())
+    }
+}
+
+impl Eq for SensorReading {}
+
+/// What an SP sensor measures
+#[derive(
+    Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SensorKind {
+    Temperature,
+    Power,
+    Current,
+    Voltage,
+    InputCurrent,
+    InputVoltage,
+    Speed,
+}
+
+impl From<gateway_client::types::MeasurementKind> for SensorKind {
+    fn from(kind: gateway_client::types::MeasurementKind) -> Self {
+        use gateway_client::types::MeasurementKind;
+        match kind {
+            MeasurementKind::Temperature => SensorKind::Temperature,
+            MeasurementKind::Power => SensorKind::Power,
+            MeasurementKind::Current => SensorKind::Current,
+            MeasurementKind::Voltage => SensorKind::Voltage,
+            MeasurementKind::InputCurrent => SensorKind::InputCurrent,
+            MeasurementKind::InputVoltage => SensorKind::InputVoltage,
+            MeasurementKind::Speed => SensorKind::Speed,
+        }
+    }
+}
+
+impl std::fmt::Display for SensorKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            SensorKind::Temperature => "temperature",
+            SensorKind::Power => "power",
+            SensorKind::Current => "current",
+            SensorKind::Voltage => "voltage",
+            SensorKind::InputCurrent => "input_current",
+            SensorKind::InputVoltage => "input_voltage",
+            SensorKind::Speed => "speed",
+        };
+        f.write_str(s)
+    }
+}
+
 /// A physical disk reported by a sled agent.
 ///
 /// This identifies that a physical disk appears in a Sled.
@@ -414,3 +517,60 @@ pub struct OmicronZonesFound {
     pub sled_id: SledUuid,
     pub zones: OmicronZonesConfig,
 }
+
+/// Inventory reported by the dataplane daemon (dpd) in a switch zone
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Switch {
+    pub time_collected: DateTime<Utc>,
+    pub source: String,
+    pub switch_location: SwitchLocation,
+    /// version of dpd running in the switch zone
+    pub dpd_version: String,
+    /// state of each switch port, keyed by dpd port id (e.g., `qsfp0`)
+    pub ports: BTreeMap<String, SwitchPortState>,
+}
+
+/// State of one switch port, as reported by dpd
+#[derive(Clone, Debug, PartialEq, Eq, Deserialize, Serialize)]
+pub struct SwitchPortState {
+    /// state of the port's link, or `None` if no link has been created on it
+    pub link_state: Option<PortLinkState>,
+    /// the transceiver plugged into the port, or `None` if there isn't one
+    /// (or the port can't hold one)
+    pub transceiver: Option<Transceiver>,
+}
+
+/// Health of a switch port's link
+#[derive(
+    Clone, Copy, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum PortLinkState {
+    Up,
+    Down,
+    Unknown,
+    Faulted,
+}
+
+impl std::fmt::Display for PortLinkState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            PortLinkState::Up => "up",
+            PortLinkState::Down => "down",
+            PortLinkState::Unknown => "unknown",
+            PortLinkState::Faulted => "faulted",
+        };
+        f.write_str(s)
+    }
+}
+
+/// Vendor data read from a transceiver (e.g., a QSFP module) in a switch port
+#[derive(
+    Clone, Debug, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord,
+)]
+pub struct Transceiver {
+    pub vendor: String,
+    pub part: String,
+    pub revision: String,
+    pub serial: String,
+}
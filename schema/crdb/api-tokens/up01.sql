@@ -0,0 +1,23 @@
+CREATE TABLE IF NOT EXISTS omicron.public.api_token (
+    id UUID PRIMARY KEY,
+    name STRING(63) NOT NULL,
+    description STRING(512) NOT NULL,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_modified TIMESTAMPTZ NOT NULL,
+    time_deleted TIMESTAMPTZ,
+
+    /* FK into silo_user table */
+    silo_user_id UUID NOT NULL,
+
+    /* The bearer token value, without the "oxide-token-" prefix */
+    token STRING(40) NOT NULL,
+
+    /* NULL means the token never expires */
+    time_expires TIMESTAMPTZ,
+    time_last_used TIMESTAMPTZ,
+
+    /* Scopes: a read-only token may only perform reads */
+    read_only BOOL NOT NULL,
+    /* If set, the token may only modify resources within this project */
+    project_id UUID
+);
@@ -0,0 +1,5 @@
+CREATE UNIQUE INDEX IF NOT EXISTS lookup_api_token_by_silo_user ON omicron.public.api_token (
+    silo_user_id,
+    name
+) WHERE
+    time_deleted IS NULL;
@@ -0,0 +1,3 @@
+CREATE UNIQUE INDEX IF NOT EXISTS lookup_api_token_by_token ON omicron.public.api_token (
+    token
+);
@@ -679,7 +679,8 @@ CREATE TYPE IF NOT EXISTS omicron.public.authentication_mode AS ENUM (
 
 CREATE TYPE IF NOT EXISTS omicron.public.user_provision_type AS ENUM (
   'api_only',
-  'jit'
+  'jit',
+  'scim'
 );
 
 CREATE TABLE IF NOT EXISTS omicron.public.silo (
@@ -716,7 +717,13 @@ CREATE TABLE IF NOT EXISTS omicron.public.silo_user (
     time_deleted TIMESTAMPTZ,
 
     silo_id UUID NOT NULL,
-    external_id TEXT NOT NULL
+    external_id TEXT NOT NULL,
+
+    /*
+     * Whether the user may log in.  Users in SCIM Silos are deactivated by
+     * the identity provider, which may reactivate them later.
+     */
+    active BOOL NOT NULL DEFAULT TRUE
 );
 
 /* This index lets us quickly find users for a given silo. */
@@ -775,12 +782,62 @@ CREATE INDEX IF NOT EXISTS lookup_silo_group_by_user ON omicron.public.silo_grou
     silo_group_id
 );
 
+/*
+ * Service accounts: non-human identities within a Silo.  These can be granted
+ * roles like any Silo user, but they never log in; they authenticate only with
+ * the bearer tokens in "service_account_token", which Silo administrators
+ * manage on their behalf.
+ */
+
+CREATE TABLE IF NOT EXISTS omicron.public.service_account (
+    id UUID PRIMARY KEY,
+    name STRING(63) NOT NULL,
+    description STRING(512) NOT NULL,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_modified TIMESTAMPTZ NOT NULL,
+    time_deleted TIMESTAMPTZ,
+
+    silo_id UUID NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS lookup_service_account_by_silo ON omicron.public.service_account (
+    silo_id,
+    name
+) WHERE
+    time_deleted IS NULL;
+
+CREATE TABLE IF NOT EXISTS omicron.public.service_account_token (
+    id UUID PRIMARY KEY,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_deleted TIMESTAMPTZ,
+
+    /* FK into service_account table */
+    service_account_id UUID NOT NULL,
+
+    /* The bearer token value, without the "oxide-token-" prefix */
+    token STRING(40) NOT NULL,
+
+    /* NULL means the token never expires */
+    time_expires TIMESTAMPTZ,
+    time_last_used TIMESTAMPTZ
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS lookup_service_account_token_by_token ON omicron.public.service_account_token (
+    token
+);
+
+CREATE INDEX IF NOT EXISTS lookup_service_account_token_by_account ON omicron.public.service_account_token (
+    service_account_id
+) WHERE
+    time_deleted IS NULL;
+
 /*
  * Silo identity provider list
  */
 
 CREATE TYPE IF NOT EXISTS omicron.public.provider_type AS ENUM (
-  'saml'
+  'saml',
+  'oidc'
 );
 
 CREATE TABLE IF NOT EXISTS omicron.public.identity_provider (
@@ -848,6 +905,75 @@ CREATE UNIQUE INDEX IF NOT EXISTS lookup_saml_idp_by_silo_name ON omicron.public
 ) WHERE
     time_deleted IS NULL;
 
+/*
+ * Silo OpenID Connect identity provider
+ */
+CREATE TABLE IF NOT EXISTS omicron.public.oidc_identity_provider (
+    /* Identity metadata */
+    id UUID PRIMARY KEY,
+    name STRING(63) NOT NULL,
+    description STRING(512) NOT NULL,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_modified TIMESTAMPTZ NOT NULL,
+    time_deleted TIMESTAMPTZ,
+
+    silo_id UUID NOT NULL,
+
+    issuer TEXT NOT NULL,
+    client_id TEXT NOT NULL,
+    client_secret TEXT NOT NULL,
+
+    authorization_endpoint TEXT NOT NULL,
+    token_endpoint TEXT NOT NULL,
+    jwks_uri TEXT NOT NULL,
+    redirect_uri TEXT NOT NULL,
+
+    scopes TEXT NOT NULL,
+    user_claim TEXT NOT NULL,
+    group_claim TEXT
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS lookup_oidc_idp_by_silo_id ON omicron.public.oidc_identity_provider (
+    silo_id,
+    id
+) WHERE
+    time_deleted IS NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS lookup_oidc_idp_by_silo_name ON omicron.public.oidc_identity_provider (
+    silo_id,
+    name
+) WHERE
+    time_deleted IS NULL;
+
+/*
+ * Pending OpenID Connect logins
+ *
+ * Rows are created when Nexus redirects a user to an OIDC identity provider and
+ * consumed when the provider redirects the user back.  The state is a random
+ * value that ties the two requests together.  The code verifier is the PKCE
+ * secret (RFC 7636) that Nexus presents when exchanging the authorization code
+ * for tokens.
+ *
+ * Anyone can start a login, so the table is kept bounded: expired rows are
+ * swept whenever a new one is created and each provider has a cap on the
+ * number of logins in progress (see `oidc_login_request_create()`).
+ */
+CREATE TABLE IF NOT EXISTS omicron.public.oidc_login_request (
+    state STRING(64) PRIMARY KEY,
+    provider_id UUID NOT NULL,
+    code_verifier STRING(64) NOT NULL,
+    nonce STRING(64) NOT NULL,
+    relay_state STRING(2048),
+    time_created TIMESTAMPTZ NOT NULL,
+    time_expires TIMESTAMPTZ NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS lookup_oidc_login_request_by_expiry
+    ON omicron.public.oidc_login_request (time_expires);
+
+CREATE INDEX IF NOT EXISTS lookup_oidc_login_request_by_provider
+    ON omicron.public.oidc_login_request (provider_id, time_expires);
+
 /*
  * Users' public SSH keys, per RFD 44
  */
@@ -875,6 +1001,45 @@ CREATE UNIQUE INDEX IF NOT EXISTS lookup_ssh_key_by_silo_user ON omicron.public.
 ) WHERE
     time_deleted IS NULL;
 
+/*
+ * Long-lived API tokens created by users for programmatic access.  Unlike
+ * device access tokens, these are named, may be revoked by their owner, and
+ * may carry scopes that narrow what the token can do beyond the owner's roles.
+ */
+CREATE TABLE IF NOT EXISTS omicron.public.api_token (
+    id UUID PRIMARY KEY,
+    name STRING(63) NOT NULL,
+    description STRING(512) NOT NULL,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_modified TIMESTAMPTZ NOT NULL,
+    time_deleted TIMESTAMPTZ,
+
+    /* FK into silo_user table */
+    silo_user_id UUID NOT NULL,
+
+    /* The bearer token value, without the "oxide-token-" prefix */
+    token STRING(40) NOT NULL,
+
+    /* NULL means the token never expires */
+    time_expires TIMESTAMPTZ,
+    time_last_used TIMESTAMPTZ,
+
+    /* Scopes: a read-only token may only perform reads */
+    read_only BOOL NOT NULL,
+    /* If set, the token may only modify resources within this project */
+    project_id UUID
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS lookup_api_token_by_silo_user ON omicron.public.api_token (
+    silo_user_id,
+    name
+) WHERE
+    time_deleted IS NULL;
+
+CREATE UNIQUE INDEX IF NOT EXISTS lookup_api_token_by_token ON omicron.public.api_token (
+    token
+);
+
 /**
  * Represents the SSH keys copied to an instance at create time by cloud-init.
  * Entries are added here when an instance is created (with configured SSH keys)
@@ -950,6 +1115,19 @@ CREATE UNIQUE INDEX IF NOT EXISTS lookup_project_by_silo ON omicron.public.proje
 ) WHERE
     time_deleted IS NULL;
 
+CREATE TABLE IF NOT EXISTS omicron.public.project_quotas (
+    project_id UUID PRIMARY KEY,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_modified TIMESTAMPTZ NOT NULL,
+    /*
+     * Each limit is optional: NULL means the project is limited only by its
+     * silo's quota.
+     */
+    cpus INT8,
+    memory_bytes INT8,
+    storage_bytes INT8
+);
+
 /*
  * Instances
  */
@@ -1935,7 +2113,9 @@ WHERE
 CREATE TYPE IF NOT EXISTS omicron.public.saga_state AS ENUM (
     'running',
     'unwinding',
-    'done'
+    'done',
+    /* marked by an operator as never to be resumed or recovered */
+    'abandoned'
 );
 
 
@@ -2117,6 +2297,69 @@ CREATE TABLE IF NOT EXISTS omicron.public.tuf_repo_artifact (
     )
 );
 
+-- The release that the operator has asked the system to run.
+--
+-- This is append-only: the row with the highest generation is the current
+-- target.
+CREATE TABLE IF NOT EXISTS omicron.public.target_release (
+    -- each change to the target release gets a new generation number
+    generation INT8 NOT NULL PRIMARY KEY,
+    time_requested TIMESTAMPTZ NOT NULL,
+
+    -- system version of an uploaded TUF repo, or NULL if the operator has not
+    -- asked for any particular release
+    system_version STRING(64)
+);
+
+-- Start with a target release that doesn't specify any system version.
+INSERT INTO omicron.public.target_release (
+    generation,
+    time_requested,
+    system_version
+) VALUES (
+    1,
+    NOW(),
+    NULL
+) ON CONFLICT DO NOTHING;
+
+-- Records the host phase 1 artifact most recently delivered to each baseboard
+-- by the control plane.
+--
+-- The SP does not report anything about the host phase 1 image it has, so
+-- this is the only way for the planner to know whether a host phase 1 update
+-- has been carried out.
+CREATE TABLE IF NOT EXISTS omicron.public.host_phase_1_delivery (
+    part_number TEXT NOT NULL,
+    serial_number TEXT NOT NULL,
+
+    -- the host phase 1 artifact most recently written to this baseboard
+    artifact_sha256 STRING(64) NOT NULL,
+    time_delivered TIMESTAMPTZ NOT NULL,
+
+    PRIMARY KEY (part_number, serial_number)
+);
+
+-- Records the artifact most recently delivered to each baseboard through MGS
+-- (whatever the component).
+--
+-- Inventory keeps showing a component's old state until it's collected again
+-- after the delivery, so this keeps Nexus instances from delivering the same
+-- artifact again in the meantime.
+CREATE TABLE IF NOT EXISTS omicron.public.mgs_update_delivery (
+    part_number TEXT NOT NULL,
+    serial_number TEXT NOT NULL,
+
+    -- the artifact most recently claimed for delivery to this baseboard (of
+    -- any component) by some Nexus, when that Nexus started writing it, and
+    -- when it finished (NULL while the delivery is still in progress)
+    id UUID NOT NULL,
+    artifact_sha256 STRING(64) NOT NULL,
+    time_started TIMESTAMPTZ NOT NULL,
+    time_delivered TIMESTAMPTZ,
+
+    PRIMARY KEY (part_number, serial_number)
+);
+
 /*******************************************************************/
 
 /*
@@ -2311,6 +2554,25 @@ CREATE INDEX IF NOT EXISTS lookup_device_access_token_by_silo_user ON omicron.pu
     silo_user_id
 );
 
+-- Bearer tokens used by a Silo's identity provider to provision users and
+-- groups via SCIM 2.0.  Each token is scoped to exactly one Silo.
+CREATE TABLE IF NOT EXISTS omicron.public.scim_client_bearer_token (
+    id UUID PRIMARY KEY,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_deleted TIMESTAMPTZ,
+    time_expires TIMESTAMPTZ,
+    silo_id UUID NOT NULL,
+    bearer_token STRING(40) NOT NULL
+);
+
+CREATE UNIQUE INDEX IF NOT EXISTS lookup_scim_client_bearer_token_by_token ON omicron.public.scim_client_bearer_token (
+    bearer_token
+) WHERE time_deleted IS NULL;
+
+CREATE INDEX IF NOT EXISTS lookup_scim_client_bearer_token_by_silo ON omicron.public.scim_client_bearer_token (
+    silo_id, id
+) WHERE time_deleted IS NULL;
+
 /*
  * Roles built into the system
  *
@@ -2374,7 +2636,8 @@ CREATE TABLE IF NOT EXISTS omicron.public.role_builtin (
 CREATE TYPE IF NOT EXISTS omicron.public.identity_type AS ENUM (
   'user_builtin',
   'silo_user',
-  'silo_group'
+  'silo_group',
+  'service_account'
 );
 
 CREATE TABLE IF NOT EXISTS omicron.public.role_assignment (
@@ -2981,6 +3244,51 @@ CREATE TABLE IF NOT EXISTS omicron.public.inv_root_of_trust_page (
     PRIMARY KEY (inv_collection_id, hw_baseboard_id, which)
 );
 
+CREATE TYPE IF NOT EXISTS omicron.public.sp_sensor_kind AS ENUM (
+    'temperature',
+    'power',
+    'current',
+    'voltage',
+    'input_current',
+    'input_voltage',
+    'speed'
+);
+
+-- environmental sensor readings reported by SPs
+CREATE TABLE IF NOT EXISTS omicron.public.inv_sp_sensor (
+    -- where this observation came from
+    -- (foreign key into `inv_collection` table)
+    inv_collection_id UUID NOT NULL,
+    -- which system this SP reports it is part of
+    -- (foreign key into `hw_baseboard_id` table)
+    hw_baseboard_id UUID NOT NULL,
+    -- order in which the SP reported this reading
+    idx INT4 NOT NULL,
+    -- when this observation was made
+    time_collected TIMESTAMPTZ NOT NULL,
+    -- which MGS instance reported this data
+    source TEXT NOT NULL,
+
+    -- SP component (and its device type) that provides the sensor
+    component TEXT NOT NULL,
+    device TEXT NOT NULL,
+    name TEXT NOT NULL,
+    kind omicron.public.sp_sensor_kind NOT NULL,
+
+    -- exactly one of these is set, depending on whether the SP was able to
+    -- read the sensor
+    value FLOAT4,
+    error TEXT,
+
+    CONSTRAINT value_or_error CHECK (
+        (value IS NULL AND error IS NOT NULL)
+        OR
+        (value IS NOT NULL AND error IS NULL)
+    ),
+
+    PRIMARY KEY (inv_collection_id, hw_baseboard_id, idx)
+);
+
 CREATE TYPE IF NOT EXISTS omicron.public.sled_role AS ENUM (
     -- this sled is directly attached to a Sidecar
     'scrimlet',
@@ -3182,6 +3490,54 @@ CREATE TABLE IF NOT EXISTS omicron.public.inv_omicron_zone_nic (
     PRIMARY KEY (inv_collection_id, id)
 );
 
+CREATE TYPE IF NOT EXISTS omicron.public.port_link_state AS ENUM (
+    'up',
+    'down',
+    'unknown',
+    'faulted'
+);
+
+-- observations from and about the Dendrite (dpd) instance in each switch zone
+CREATE TABLE IF NOT EXISTS omicron.public.inv_switch (
+    inv_collection_id UUID NOT NULL,
+    time_collected TIMESTAMPTZ NOT NULL,
+    source TEXT NOT NULL,
+    switch_location TEXT NOT NULL,
+
+    dpd_version TEXT NOT NULL,
+
+    PRIMARY KEY (inv_collection_id, switch_location)
+);
+
+-- state of each switch port reported by dpd, including the transceiver (if
+-- any) plugged into it
+CREATE TABLE IF NOT EXISTS omicron.public.inv_switch_port (
+    inv_collection_id UUID NOT NULL,
+    switch_location TEXT NOT NULL,
+    port_id TEXT NOT NULL,
+
+    link_state omicron.public.port_link_state,
+
+    transceiver_vendor TEXT,
+    transceiver_part TEXT,
+    transceiver_revision TEXT,
+    transceiver_serial TEXT,
+
+    CONSTRAINT transceiver_all_or_nothing CHECK (
+        (transceiver_vendor IS NULL
+            AND transceiver_part IS NULL
+            AND transceiver_revision IS NULL
+            AND transceiver_serial IS NULL)
+        OR
+        (transceiver_vendor IS NOT NULL
+            AND transceiver_part IS NOT NULL
+            AND transceiver_revision IS NOT NULL
+            AND transceiver_serial IS NOT NULL)
+    ),
+
+    PRIMARY KEY (inv_collection_id, switch_location, port_id)
+);
+
 /*
  * System-level blueprints
  *
@@ -3415,6 +3771,82 @@ CREATE TABLE IF NOT EXISTS omicron.public.bp_omicron_zone_nic (
     PRIMARY KEY (blueprint_id, id)
 );
 
+-- the components whose software is delivered through MGS
+CREATE TYPE IF NOT EXISTS omicron.public.mgs_update_component AS ENUM (
+    'sp',
+    'rot',
+    'host_phase_1'
+);
+
+-- description of an update to an MGS-managed component that a blueprint wants
+-- carried out
+CREATE TABLE IF NOT EXISTS omicron.public.bp_pending_mgs_update (
+    -- foreign key into the `blueprint` table
+    blueprint_id UUID NOT NULL,
+
+    -- identifies the baseboard being updated
+    part_number TEXT NOT NULL,
+    serial_number TEXT NOT NULL,
+
+    -- last known MGS location of the baseboard
+    sp_type omicron.public.sp_type NOT NULL,
+    sp_slot INT4 NOT NULL CHECK (sp_slot >= 0),
+
+    component omicron.public.mgs_update_component NOT NULL,
+
+    -- artifact to deliver
+    artifact_sha256 STRING(64) NOT NULL,
+    artifact_version STRING(64) NOT NULL,
+
+    -- preconditions for delivering the update (which ones are set depends on
+    -- `component`)
+    expected_active_version TEXT,
+    expected_inactive_version TEXT,
+    expected_active_rot_slot omicron.public.hw_rot_slot,
+    expected_host_phase_1_sha256 STRING(64),
+
+    CONSTRAINT sp_rot_expected_active_version CHECK (
+        component = 'host_phase_1' OR expected_active_version IS NOT NULL
+    ),
+    CONSTRAINT rot_expected_active_slot CHECK (
+        (component = 'rot') = (expected_active_rot_slot IS NOT NULL)
+    ),
+
+    PRIMARY KEY (blueprint_id, part_number, serial_number)
+);
+
+-- The artifacts from a target release's TUF repository that are delivered
+-- through MGS.
+--
+-- These are recorded along with the target release so that every Nexus can
+-- plan updates, not just the one that the repository was uploaded to.
+CREATE TABLE IF NOT EXISTS omicron.public.target_release_artifact (
+    -- foreign key into the `target_release` table
+    target_release_generation INT8 NOT NULL,
+
+    component omicron.public.mgs_update_component NOT NULL,
+    sp_type omicron.public.sp_type NOT NULL,
+
+    -- board name from the caboose (SP artifacts only)
+    board TEXT,
+    -- RoT slot that the image is built for (RoT artifacts only)
+    rot_slot omicron.public.hw_rot_slot,
+
+    artifact_version STRING(64) NOT NULL,
+    artifact_sha256 STRING(64) NOT NULL,
+
+    CONSTRAINT sp_board CHECK (
+        (component = 'sp') = (board IS NOT NULL)
+    ),
+    CONSTRAINT rot_slot CHECK (
+        (component = 'rot') = (rot_slot IS NOT NULL)
+    ),
+
+    PRIMARY KEY (
+        target_release_generation, component, sp_type, artifact_sha256
+    )
+);
+
 /*******************************************************************/
 
 /*
@@ -3443,6 +3875,44 @@ CREATE INDEX IF NOT EXISTS lookup_vmms_by_sled_id ON omicron.public.vmm (
     sled_id
 ) WHERE time_deleted IS NULL;
 
+/*
+ * Serial console output captured from an instance's VMMs
+ *
+ * Propolis only keeps a VMM's output in memory, so Nexus copies it here to
+ * keep it available after the VMM goes away. Each row is a chunk of output
+ * that is written once and never modified, so Nexuses capturing the same
+ * output concurrently can't overwrite each other: whichever inserts a chunk at
+ * a given offset first wins. Nexus deletes the oldest chunks to bound the
+ * total size.
+ */
+CREATE TABLE IF NOT EXISTS omicron.public.instance_serial_console_log (
+    instance_id UUID NOT NULL,
+
+    /*
+     * Range of this chunk, in bytes of all output produced by the instance
+     * since it was created
+     */
+    start_offset INT8 NOT NULL CHECK (start_offset >= 0),
+    end_offset INT8 NOT NULL CHECK (end_offset > start_offset),
+
+    time_created TIMESTAMPTZ NOT NULL,
+
+    /*
+     * The output in this range, or NULL if it was lost before it could be
+     * captured (e.g., because Propolis had already discarded it)
+     */
+    data BYTES CHECK (data IS NULL OR length(data) = end_offset - start_offset),
+
+    /*
+     * The VMM that produced this output, and the offset in that VMM's output
+     * just past the end of this chunk
+     */
+    vmm_id UUID NOT NULL,
+    vmm_offset INT8 NOT NULL CHECK (vmm_offset >= 0),
+
+    PRIMARY KEY (instance_id, start_offset)
+);
+
 /*
  * A special view of an instance provided to operators for insights into what's
  * running on a sled.
@@ -3859,7 +4329,7 @@ INSERT INTO omicron.public.db_metadata (
     version,
     target_version
 ) VALUES
-    (TRUE, NOW(), NOW(), '63.0.0', NULL)
+    (TRUE, NOW(), NOW(), '74.0.0', NULL)
 ON CONFLICT DO NOTHING;
 
 COMMIT;
@@ -0,0 +1,27 @@
+CREATE TABLE IF NOT EXISTS omicron.public.instance_serial_console_log (
+    instance_id UUID NOT NULL,
+
+    /*
+     * Range of this chunk, in bytes of all output produced by the instance
+     * since it was created
+     */
+    start_offset INT8 NOT NULL CHECK (start_offset >= 0),
+    end_offset INT8 NOT NULL CHECK (end_offset > start_offset),
+
+    time_created TIMESTAMPTZ NOT NULL,
+
+    /*
+     * The output in this range, or NULL if it was lost before it could be
+     * captured (e.g., because Propolis had already discarded it)
+     */
+    data BYTES CHECK (data IS NULL OR length(data) = end_offset - start_offset),
+
+    /*
+     * The VMM that produced this output, and the offset in that VMM's output
+     * just past the end of this chunk
+     */
+    vmm_id UUID NOT NULL,
+    vmm_offset INT8 NOT NULL CHECK (vmm_offset >= 0),
+
+    PRIMARY KEY (instance_id, start_offset)
+);
@@ -0,0 +1,9 @@
+CREATE TYPE IF NOT EXISTS omicron.public.sp_sensor_kind AS ENUM (
+    'temperature',
+    'power',
+    'current',
+    'voltage',
+    'input_current',
+    'input_voltage',
+    'speed'
+);
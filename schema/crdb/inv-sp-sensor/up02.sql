@@ -0,0 +1,23 @@
+CREATE TABLE IF NOT EXISTS omicron.public.inv_sp_sensor (
+    inv_collection_id UUID NOT NULL,
+    hw_baseboard_id UUID NOT NULL,
+    idx INT4 NOT NULL,
+    time_collected TIMESTAMPTZ NOT NULL,
+    source TEXT NOT NULL,
+
+    component TEXT NOT NULL,
+    device TEXT NOT NULL,
+    name TEXT NOT NULL,
+    kind omicron.public.sp_sensor_kind NOT NULL,
+
+    value FLOAT4,
+    error TEXT,
+
+    CONSTRAINT value_or_error CHECK (
+        (value IS NULL AND error IS NOT NULL)
+        OR
+        (value IS NOT NULL AND error IS NULL)
+    ),
+
+    PRIMARY KEY (inv_collection_id, hw_baseboard_id, idx)
+);
@@ -0,0 +1,6 @@
+CREATE TYPE IF NOT EXISTS omicron.public.port_link_state AS ENUM (
+    'up',
+    'down',
+    'unknown',
+    'faulted'
+);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS omicron.public.inv_switch (
+    inv_collection_id UUID NOT NULL,
+    time_collected TIMESTAMPTZ NOT NULL,
+    source TEXT NOT NULL,
+    switch_location TEXT NOT NULL,
+
+    dpd_version TEXT NOT NULL,
+
+    PRIMARY KEY (inv_collection_id, switch_location)
+);
@@ -0,0 +1,26 @@
+CREATE TABLE IF NOT EXISTS omicron.public.inv_switch_port (
+    inv_collection_id UUID NOT NULL,
+    switch_location TEXT NOT NULL,
+    port_id TEXT NOT NULL,
+
+    link_state omicron.public.port_link_state,
+
+    transceiver_vendor TEXT,
+    transceiver_part TEXT,
+    transceiver_revision TEXT,
+    transceiver_serial TEXT,
+
+    CONSTRAINT transceiver_all_or_nothing CHECK (
+        (transceiver_vendor IS NULL
+            AND transceiver_part IS NULL
+            AND transceiver_revision IS NULL
+            AND transceiver_serial IS NULL)
+        OR
+        (transceiver_vendor IS NOT NULL
+            AND transceiver_part IS NOT NULL
+            AND transceiver_revision IS NOT NULL
+            AND transceiver_serial IS NOT NULL)
+    ),
+
+    PRIMARY KEY (inv_collection_id, switch_location, port_id)
+);
@@ -0,0 +1,5 @@
+CREATE TYPE IF NOT EXISTS omicron.public.mgs_update_component AS ENUM (
+    'sp',
+    'rot',
+    'host_phase_1'
+);
@@ -0,0 +1,34 @@
+CREATE TABLE IF NOT EXISTS omicron.public.bp_pending_mgs_update (
+    -- foreign key into the `blueprint` table
+    blueprint_id UUID NOT NULL,
+
+    -- identifies the baseboard being updated
+    part_number TEXT NOT NULL,
+    serial_number TEXT NOT NULL,
+
+    -- last known MGS location of the baseboard
+    sp_type omicron.public.sp_type NOT NULL,
+    sp_slot INT4 NOT NULL CHECK (sp_slot >= 0),
+
+    component omicron.public.mgs_update_component NOT NULL,
+
+    -- artifact to deliver
+    artifact_sha256 STRING(64) NOT NULL,
+    artifact_version STRING(64) NOT NULL,
+
+    -- preconditions for delivering the update (which ones are set depends on
+    -- `component`)
+    expected_active_version TEXT,
+    expected_inactive_version TEXT,
+    expected_active_rot_slot omicron.public.hw_rot_slot,
+    expected_host_phase_1_sha256 STRING(64),
+
+    CONSTRAINT sp_rot_expected_active_version CHECK (
+        component = 'host_phase_1' OR expected_active_version IS NOT NULL
+    ),
+    CONSTRAINT rot_expected_active_slot CHECK (
+        (component = 'rot') = (expected_active_rot_slot IS NOT NULL)
+    ),
+
+    PRIMARY KEY (blueprint_id, part_number, serial_number)
+);
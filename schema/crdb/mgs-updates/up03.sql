@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS omicron.public.target_release (
+    -- each change to the target release gets a new generation number
+    generation INT8 NOT NULL PRIMARY KEY,
+    time_requested TIMESTAMPTZ NOT NULL,
+
+    -- system version of an uploaded TUF repo, or NULL if the operator has not
+    -- asked for any particular release
+    system_version STRING(64)
+);
@@ -0,0 +1,10 @@
+CREATE TABLE IF NOT EXISTS omicron.public.host_phase_1_delivery (
+    part_number TEXT NOT NULL,
+    serial_number TEXT NOT NULL,
+
+    -- the host phase 1 artifact most recently written to this baseboard
+    artifact_sha256 STRING(64) NOT NULL,
+    time_delivered TIMESTAMPTZ NOT NULL,
+
+    PRIMARY KEY (part_number, serial_number)
+);
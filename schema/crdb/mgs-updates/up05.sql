@@ -0,0 +1,9 @@
+INSERT INTO omicron.public.target_release (
+    generation,
+    time_requested,
+    system_version
+) VALUES (
+    1,
+    NOW(),
+    NULL
+) ON CONFLICT DO NOTHING;
@@ -0,0 +1,26 @@
+CREATE TABLE IF NOT EXISTS omicron.public.target_release_artifact (
+    -- foreign key into the `target_release` table
+    target_release_generation INT8 NOT NULL,
+
+    component omicron.public.mgs_update_component NOT NULL,
+    sp_type omicron.public.sp_type NOT NULL,
+
+    -- board name from the caboose (SP artifacts only)
+    board TEXT,
+    -- RoT slot that the image is built for (RoT artifacts only)
+    rot_slot omicron.public.hw_rot_slot,
+
+    artifact_version STRING(64) NOT NULL,
+    artifact_sha256 STRING(64) NOT NULL,
+
+    CONSTRAINT sp_board CHECK (
+        (component = 'sp') = (board IS NOT NULL)
+    ),
+    CONSTRAINT rot_slot CHECK (
+        (component = 'rot') = (rot_slot IS NOT NULL)
+    ),
+
+    PRIMARY KEY (
+        target_release_generation, component, sp_type, artifact_sha256
+    )
+);
@@ -0,0 +1,14 @@
+CREATE TABLE IF NOT EXISTS omicron.public.mgs_update_delivery (
+    part_number TEXT NOT NULL,
+    serial_number TEXT NOT NULL,
+
+    -- the artifact most recently claimed for delivery to this baseboard (of
+    -- any component) by some Nexus, when that Nexus started writing it, and
+    -- when it finished (NULL while the delivery is still in progress)
+    id UUID NOT NULL,
+    artifact_sha256 STRING(64) NOT NULL,
+    time_started TIMESTAMPTZ NOT NULL,
+    time_delivered TIMESTAMPTZ,
+
+    PRIMARY KEY (part_number, serial_number)
+);
@@ -0,0 +1,24 @@
+CREATE TABLE IF NOT EXISTS omicron.public.oidc_identity_provider (
+    /* Identity metadata */
+    id UUID PRIMARY KEY,
+    name STRING(63) NOT NULL,
+    description STRING(512) NOT NULL,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_modified TIMESTAMPTZ NOT NULL,
+    time_deleted TIMESTAMPTZ,
+
+    silo_id UUID NOT NULL,
+
+    issuer TEXT NOT NULL,
+    client_id TEXT NOT NULL,
+    client_secret TEXT NOT NULL,
+
+    authorization_endpoint TEXT NOT NULL,
+    token_endpoint TEXT NOT NULL,
+    jwks_uri TEXT NOT NULL,
+    redirect_uri TEXT NOT NULL,
+
+    scopes TEXT NOT NULL,
+    user_claim TEXT NOT NULL,
+    group_claim TEXT
+);
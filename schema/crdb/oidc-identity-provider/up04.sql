@@ -0,0 +1,5 @@
+CREATE UNIQUE INDEX IF NOT EXISTS lookup_oidc_idp_by_silo_name ON omicron.public.oidc_identity_provider (
+    silo_id,
+    name
+) WHERE
+    time_deleted IS NULL;
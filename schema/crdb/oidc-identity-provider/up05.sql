@@ -0,0 +1,9 @@
+CREATE TABLE IF NOT EXISTS omicron.public.oidc_login_request (
+    state STRING(64) PRIMARY KEY,
+    provider_id UUID NOT NULL,
+    code_verifier STRING(64) NOT NULL,
+    nonce STRING(64) NOT NULL,
+    relay_state STRING(2048),
+    time_created TIMESTAMPTZ NOT NULL,
+    time_expires TIMESTAMPTZ NOT NULL
+);
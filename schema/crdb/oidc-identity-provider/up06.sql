@@ -0,0 +1,2 @@
+CREATE INDEX IF NOT EXISTS lookup_oidc_login_request_by_expiry
+    ON omicron.public.oidc_login_request (time_expires);
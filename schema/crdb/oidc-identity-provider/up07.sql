@@ -0,0 +1,2 @@
+CREATE INDEX IF NOT EXISTS lookup_oidc_login_request_by_provider
+    ON omicron.public.oidc_login_request (provider_id, time_expires);
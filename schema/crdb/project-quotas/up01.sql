@@ -0,0 +1,12 @@
+CREATE TABLE IF NOT EXISTS omicron.public.project_quotas (
+    project_id UUID PRIMARY KEY,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_modified TIMESTAMPTZ NOT NULL,
+    /*
+     * Each limit is optional: NULL means the project is limited only by its
+     * silo's quota.
+     */
+    cpus INT8,
+    memory_bytes INT8,
+    storage_bytes INT8
+);
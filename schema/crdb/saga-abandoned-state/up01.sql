@@ -0,0 +1 @@
+ALTER TYPE omicron.public.saga_state ADD VALUE IF NOT EXISTS 'abandoned' AFTER 'done';
@@ -0,0 +1 @@
+ALTER TYPE omicron.public.identity_type ADD VALUE IF NOT EXISTS 'service_account' AFTER 'silo_group';
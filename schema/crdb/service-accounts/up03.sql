@@ -0,0 +1,5 @@
+CREATE UNIQUE INDEX IF NOT EXISTS lookup_service_account_by_silo ON omicron.public.service_account (
+    silo_id,
+    name
+) WHERE
+    time_deleted IS NULL;
@@ -0,0 +1,15 @@
+CREATE TABLE IF NOT EXISTS omicron.public.service_account_token (
+    id UUID PRIMARY KEY,
+    time_created TIMESTAMPTZ NOT NULL,
+    time_deleted TIMESTAMPTZ,
+
+    /* FK into service_account table */
+    service_account_id UUID NOT NULL,
+
+    /* The bearer token value, without the "oxide-token-" prefix */
+    token STRING(40) NOT NULL,
+
+    /* NULL means the token never expires */
+    time_expires TIMESTAMPTZ,
+    time_last_used TIMESTAMPTZ
+);
@@ -0,0 +1,3 @@
+CREATE UNIQUE INDEX IF NOT EXISTS lookup_service_account_token_by_token ON omicron.public.service_account_token (
+    token
+);
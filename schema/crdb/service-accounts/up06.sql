@@ -0,0 +1,4 @@
+CREATE INDEX IF NOT EXISTS lookup_service_account_token_by_account ON omicron.public.service_account_token (
+    service_account_id
+) WHERE
+    time_deleted IS NULL;
@@ -217,7 +217,10 @@ impl BootDiskOsWriter {
         .await
     }
 
-    async fn start_update_impl<S, Writer>(
+    /// Like `start_update()`, but writes the image using `disk_writer`
+    /// rather than to a real device (e.g., for the simulated sled agent's
+    /// in-memory boot disks).
+    pub(crate) async fn start_update_impl<S, Writer>(
         &self,
         boot_disk: M2Slot,
         disk_devfs_path: Utf8PathBuf,
@@ -745,14 +748,15 @@ impl<W: DiskInterface> BootDiskOsWriteTask<W> {
     }
 }
 
-// Utility traits to allow injecting an in-memory "disk" for unit tests.
+// Utility traits to allow injecting an in-memory "disk" for unit tests and
+// the simulated sled agent.
 #[async_trait]
-trait DiskWriter: AsyncWrite + Send + Sized + Unpin {
+pub(crate) trait DiskWriter: AsyncWrite + Send + Sized + Unpin {
     fn block_size(&self) -> usize;
     async fn finalize(self) -> io::Result<()>;
 }
 #[async_trait]
-trait DiskInterface: Send + Sync + 'static {
+pub(crate) trait DiskInterface: Send + Sync + 'static {
     type Writer: DiskWriter;
     type Reader: io::Read + Send;
     async fn open_writer(&self, path: &Path) -> io::Result<Self::Writer>;
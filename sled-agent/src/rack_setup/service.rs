@@ -94,6 +94,7 @@ use nexus_client::{
 use nexus_types::deployment::{
     Blueprint, BlueprintPhysicalDisksConfig, BlueprintZoneConfig,
     BlueprintZoneDisposition, BlueprintZonesConfig, InvalidOmicronZoneType,
+    PendingMgsUpdates,
 };
 use nexus_types::external_api::views::SledState;
 use omicron_common::address::get_sled_address;
@@ -1428,6 +1429,7 @@ pub(crate) fn build_initial_blueprint_from_sled_configs(
         id: Uuid::new_v4(),
         blueprint_zones,
         blueprint_disks,
+        pending_mgs_updates: PendingMgsUpdates::new(),
         sled_state,
         parent_blueprint_id: None,
         internal_dns_version,
@@ -0,0 +1,120 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Simulated boot disks
+//!
+//! OS image writes to the simulated sled agent's boot disks go through the
+//! real `BootDiskOsWriter`, but the images are kept in memory.
+
+use crate::boot_disk_os_writer::DiskInterface;
+use crate::boot_disk_os_writer::DiskWriter;
+use async_trait::async_trait;
+use camino::Utf8PathBuf;
+use installinator_common::BlockSizeBufWriter;
+use installinator_common::M2Slot;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use std::sync::Arc;
+use std::sync::Mutex;
+use tokio::io::AsyncWrite;
+use tokio::io::AsyncWriteExt;
+
+/// The boot disks of a simulated sled, held in memory
+#[derive(Clone, Debug, Default)]
+pub(super) struct SimulatedBootDisks {
+    contents: Arc<Mutex<BTreeMap<Utf8PathBuf, Vec<u8>>>>,
+}
+
+impl SimulatedBootDisks {
+    // Real M.2s have 512-byte blocks; use the same size so that simulated
+    // updates reject the same images real ones would.
+    const BLOCK_SIZE: usize = 512;
+
+    /// The path under which updates to the given disk are written
+    pub(super) fn devfs_path(boot_disk: M2Slot) -> Utf8PathBuf {
+        Utf8PathBuf::from(format!("/sim/boot-disk/{boot_disk:?}"))
+    }
+}
+
+#[async_trait]
+impl DiskInterface for SimulatedBootDisks {
+    type Writer = SimulatedBootDiskWriter;
+    type Reader = io::Cursor<Vec<u8>>;
+
+    async fn open_writer(&self, path: &Path) -> io::Result<Self::Writer> {
+        let path = Utf8PathBuf::try_from(path.to_owned())
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidInput, err))?;
+        Ok(SimulatedBootDiskWriter {
+            path,
+            data: BlockSizeBufWriter::with_block_size(
+                Self::BLOCK_SIZE,
+                Vec::new(),
+            ),
+            disks: self.clone(),
+        })
+    }
+
+    fn open_reader(&self, path: &Path) -> io::Result<Self::Reader> {
+        self.contents
+            .lock()
+            .unwrap()
+            .iter()
+            .find(|(disk_path, _)| disk_path.as_std_path() == path)
+            .map(|(_, data)| io::Cursor::new(data.clone()))
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("nothing written to {}", path.display()),
+                )
+            })
+    }
+}
+
+pub(super) struct SimulatedBootDiskWriter {
+    path: Utf8PathBuf,
+    data: BlockSizeBufWriter<Vec<u8>>,
+    disks: SimulatedBootDisks,
+}
+
+impl AsyncWrite for SimulatedBootDiskWriter {
+    fn poll_write(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+        buf: &[u8],
+    ) -> std::task::Poll<io::Result<usize>> {
+        std::pin::Pin::new(&mut self.data).poll_write(cx, buf)
+    }
+
+    fn poll_flush(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<io::Result<()>> {
+        std::pin::Pin::new(&mut self.data).poll_flush(cx)
+    }
+
+    fn poll_shutdown(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<io::Result<()>> {
+        std::pin::Pin::new(&mut self.data).poll_shutdown(cx)
+    }
+}
+
+#[async_trait]
+impl DiskWriter for SimulatedBootDiskWriter {
+    fn block_size(&self) -> usize {
+        self.data.block_size()
+    }
+
+    async fn finalize(mut self) -> io::Result<()> {
+        self.data.flush().await?;
+        self.disks
+            .contents
+            .lock()
+            .unwrap()
+            .insert(self.path, self.data.into_inner());
+        Ok(())
+    }
+}
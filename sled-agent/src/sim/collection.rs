@@ -183,8 +183,12 @@ pub struct SimCollection<S: Simulatable> {
     sim_mode: SimMode,
     /// list of objects being simulated
     objects: Mutex<BTreeMap<Uuid, SimObject<S>>>,
+    /// invoked for each object that comes to rest destroyed
+    destroy_hook: Option<DestroyHook<S>>,
 }
 
+type DestroyHook<S> = Box<dyn Fn(&Uuid, &S) + Send + Sync>;
+
 impl<S: Simulatable + 'static> SimCollection<S> {
     /// Returns a new collection of simulated objects.
     pub fn new(
@@ -197,9 +201,23 @@ impl<S: Simulatable + 'static> SimCollection<S> {
             log,
             sim_mode,
             objects: Mutex::new(BTreeMap::new()),
+            destroy_hook: None,
         }
     }
 
+    /// Arranges for `hook` to be invoked with each object that comes to rest
+    /// in its "Destroyed" state, just before it's removed from the collection.
+    ///
+    /// Objects removed with `sim_force_remove()` or `sim_force_remove_all()`
+    /// are not passed to the hook.
+    pub fn with_destroy_hook(
+        mut self,
+        hook: impl Fn(&Uuid, &S) + Send + Sync + 'static,
+    ) -> SimCollection<S> {
+        self.destroy_hook = Some(Box::new(hook));
+        self
+    }
+
     pub async fn size(&self) -> usize {
         self.objects.lock().await.len()
     }
@@ -227,6 +245,17 @@ impl<S: Simulatable + 'static> SimCollection<S> {
         }
     }
 
+    /// Forcibly removes every object from the collection without simulating
+    /// any further state changes for them.
+    pub async fn sim_force_remove_all(&self) {
+        let mut objects = self.objects.lock().await;
+        for (_, object) in std::mem::take(&mut *objects) {
+            if let Some(mut tx) = object.channel_tx {
+                tx.close_channel();
+            }
+        }
+    }
+
     /// Complete a desired asynchronous state transition for object `id`.
     /// This is invoked either by `sim_step()` (if the simulation mode is
     /// `SimMode::Auto`) or `instance_finish_transition` (if the simulation mode
@@ -282,6 +311,9 @@ impl<S: Simulatable + 'static> SimCollection<S> {
             // waits on the background task?  If we did it here, we'd deadlock,
             // since we're invoked from the background task.
             if let Some(destroyed_object) = to_destroy {
+                if let Some(hook) = &self.destroy_hook {
+                    hook(&id, &destroyed_object.object);
+                }
                 if let Some(mut tx) = destroyed_object.channel_tx {
                     tx.close_channel();
                 }
@@ -361,6 +393,12 @@ impl<S: Simulatable + 'static> SimCollection<S> {
         rv
     }
 
+    /// Returns the current state of each object in the collection.
+    pub async fn sim_current_states(&self) -> Vec<S::CurrentState> {
+        let objects = self.objects.lock().await;
+        objects.values().map(|o| o.object.current()).collect()
+    }
+
     pub async fn contains_key(self: &Arc<Self>, id: &Uuid) -> bool {
         self.objects.lock().await.contains_key(id)
     }
@@ -0,0 +1,303 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Fault injection for the simulated sled agent
+//!
+//! Tests can ask a simulated sled agent to misbehave in various ways (e.g.,
+//! to slow down or fail particular requests, to forget its instances as
+//! though the sled had rebooted, or to stop responding altogether) in order to
+//! exercise how Nexus copes with partial failures.  Faults are configured
+//! either directly through [`super::SledAgent::set_faults()`] or through the
+//! `/sim/faults` endpoints of the simulated sled agent API.
+
+use dropshot::HttpError;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use serde::Serialize;
+use slog::Logger;
+use std::collections::BTreeMap;
+use std::time::Duration;
+use tokio::sync::watch;
+
+/// Key in [`SimFaults::endpoints`] for a fault that applies to every endpoint
+pub const ALL_ENDPOINTS: &str = "*";
+
+/// Faults to inject into a simulated sled agent
+#[derive(
+    Clone, Debug, Default, Deserialize, Serialize, JsonSchema, PartialEq,
+)]
+pub struct SimFaults {
+    /// Faults to inject into requests, keyed by the operation ID of the
+    /// endpoint they apply to (e.g., `instance_put_state`)
+    ///
+    /// A fault under the key `*` applies to any endpoint that doesn't have
+    /// its own.
+    #[serde(default)]
+    pub endpoints: BTreeMap<String, EndpointFault>,
+
+    /// If true, the sled behaves as though it can't be reached: requests
+    /// (other than those to the fault injection endpoints) go unanswered
+    /// until this is cleared, and then fail.
+    #[serde(default)]
+    pub unreachable: bool,
+
+    /// If true, requests to snapshot a disk fail
+    #[serde(default)]
+    pub fail_disk_snapshots: bool,
+
+    /// If true, every disk in a request to ensure the sled's physical disks
+    /// (and so the zpools on them) is reported as having failed
+    #[serde(default)]
+    pub fail_zpool_operations: bool,
+}
+
+/// A fault to inject into requests to a particular endpoint
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+pub struct EndpointFault {
+    /// What to do to each affected request
+    pub kind: FaultKind,
+
+    /// How many requests to inject this fault into before removing it
+    ///
+    /// If unset, the fault is injected into every request until it's
+    /// cleared.
+    #[serde(default)]
+    pub count: Option<u32>,
+}
+
+impl EndpointFault {
+    /// Returns a fault that affects every request until it's cleared
+    pub fn always(kind: FaultKind) -> Self {
+        EndpointFault { kind, count: None }
+    }
+
+    /// Returns a fault that affects only the next `count` requests
+    pub fn times(kind: FaultKind, count: u32) -> Self {
+        EndpointFault { kind, count: Some(count) }
+    }
+}
+
+/// What to do to a request affected by an [`EndpointFault`]
+#[derive(Clone, Debug, Deserialize, Serialize, JsonSchema, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum FaultKind {
+    /// Wait `delay_ms` milliseconds before handling the request normally
+    Latency { delay_ms: u64 },
+
+    /// Fail the request with the given HTTP status code and message
+    Error { status_code: u16, message: String },
+
+    /// Never respond to the request, as though the connection had been
+    /// dropped
+    ///
+    /// The request is held until the sled's faults are next changed (at which
+    /// point it fails), so the client sees it time out.
+    DropConnection,
+
+    /// Forget all instances and disks, as though the sled had rebooted, and
+    /// then handle the request normally
+    SledRebooted,
+}
+
+/// What the sled agent should do after [`FaultInjector::check()`] lets a
+/// request through
+#[derive(Debug, PartialEq, Eq)]
+pub(super) enum FaultOutcome {
+    /// Handle the request normally.
+    Proceed,
+    /// Forget all instances and disks before handling the request.
+    Reboot,
+}
+
+/// Tracks the faults injected into a simulated sled agent
+pub(super) struct FaultInjector {
+    log: Logger,
+    faults: watch::Sender<SimFaults>,
+}
+
+impl FaultInjector {
+    pub(super) fn new(log: Logger) -> Self {
+        FaultInjector { log, faults: watch::Sender::new(SimFaults::default()) }
+    }
+
+    pub(super) fn get(&self) -> SimFaults {
+        self.faults.borrow().clone()
+    }
+
+    /// Replaces the current set of faults
+    ///
+    /// This releases any requests being held by a `DropConnection` fault or
+    /// by the sled being unreachable.
+    pub(super) fn set(&self, faults: SimFaults) {
+        info!(self.log, "setting injected faults"; "faults" => ?faults);
+        self.faults.send_replace(faults);
+    }
+
+    pub(super) fn fail_disk_snapshots(&self) -> bool {
+        self.faults.borrow().fail_disk_snapshots
+    }
+
+    pub(super) fn fail_zpool_operations(&self) -> bool {
+        self.faults.borrow().fail_zpool_operations
+    }
+
+    /// Applies whatever fault is currently configured for `endpoint` to a
+    /// request to it
+    pub(super) async fn check(
+        &self,
+        endpoint: &str,
+    ) -> Result<FaultOutcome, HttpError> {
+        let mut rx = self.faults.subscribe();
+        if rx.borrow().unreachable {
+            debug!(self.log, "holding request to unreachable sled";
+                "endpoint" => endpoint);
+            // The only way this can fail is if the sender is dropped, which
+            // can't happen while `self` is around.
+            let _ = rx.wait_for(|faults| !faults.unreachable).await;
+            return Err(simulated_error(
+                http::StatusCode::SERVICE_UNAVAILABLE,
+                "simulated sled was unreachable",
+            ));
+        }
+
+        let Some(fault) = self.take_fault(endpoint) else {
+            return Ok(FaultOutcome::Proceed);
+        };
+        info!(self.log, "injecting fault";
+            "endpoint" => endpoint, "fault" => ?fault);
+        match fault {
+            FaultKind::Latency { delay_ms } => {
+                tokio::time::sleep(Duration::from_millis(delay_ms)).await;
+                Ok(FaultOutcome::Proceed)
+            }
+            FaultKind::Error { status_code, message } => {
+                let status_code = http::StatusCode::from_u16(status_code)
+                    .unwrap_or(http::StatusCode::INTERNAL_SERVER_ERROR);
+                Err(simulated_error(status_code, &message))
+            }
+            FaultKind::DropConnection => {
+                let _ = rx.changed().await;
+                Err(simulated_error(
+                    http::StatusCode::SERVICE_UNAVAILABLE,
+                    "simulated dropped connection",
+                ))
+            }
+            FaultKind::SledRebooted => Ok(FaultOutcome::Reboot),
+        }
+    }
+
+    /// Returns the fault to inject into a request to `endpoint`, if any,
+    /// counting it against the fault's remaining uses
+    fn take_fault(&self, endpoint: &str) -> Option<FaultKind> {
+        let mut taken = None;
+        // Using up a fault doesn't notify receivers: requests held by a
+        // `DropConnection` fault are only released when the faults are
+        // replaced.
+        self.faults.send_if_modified(|faults| {
+            let key = if faults.endpoints.contains_key(endpoint) {
+                endpoint
+            } else {
+                ALL_ENDPOINTS
+            };
+            let Some(fault) = faults.endpoints.get_mut(key) else {
+                return false;
+            };
+            taken = Some(fault.kind.clone());
+            if let Some(count) = &mut fault.count {
+                *count = count.saturating_sub(1);
+                if *count == 0 {
+                    faults.endpoints.remove(key);
+                }
+            }
+            false
+        });
+        taken
+    }
+}
+
+fn simulated_error(status_code: http::StatusCode, message: &str) -> HttpError {
+    HttpError {
+        status_code,
+        error_code: Some(String::from("SimulatedFault")),
+        external_message: message.to_string(),
+        internal_message: message.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use omicron_test_utils::dev::test_setup_log;
+
+    #[tokio::test]
+    async fn test_fault_counts() {
+        let logctx = test_setup_log("test_fault_counts");
+        let injector = FaultInjector::new(logctx.log.clone());
+
+        let error = FaultKind::Error {
+            status_code: 500,
+            message: String::from("boom"),
+        };
+        let mut faults = SimFaults::default();
+        faults.endpoints.insert(
+            String::from("instance_put_state"),
+            EndpointFault::times(error, 2),
+        );
+        faults.endpoints.insert(
+            String::from(ALL_ENDPOINTS),
+            EndpointFault::always(FaultKind::SledRebooted),
+        );
+        injector.set(faults);
+
+        // The endpoint-specific fault takes precedence over the wildcard one
+        // until it has been used up.
+        for _ in 0..2 {
+            let error = injector.check("instance_put_state").await.unwrap_err();
+            assert_eq!(
+                error.status_code,
+                http::StatusCode::INTERNAL_SERVER_ERROR
+            );
+            assert_eq!(error.external_message, "boom");
+        }
+        assert_eq!(
+            injector.check("instance_put_state").await.unwrap(),
+            FaultOutcome::Reboot
+        );
+        assert_eq!(
+            injector.check("disk_put").await.unwrap(),
+            FaultOutcome::Reboot
+        );
+        assert!(!injector.get().endpoints.contains_key("instance_put_state"));
+
+        injector.set(SimFaults::default());
+        assert_eq!(
+            injector.check("instance_put_state").await.unwrap(),
+            FaultOutcome::Proceed
+        );
+
+        logctx.cleanup_successful();
+    }
+
+    #[tokio::test]
+    async fn test_unreachable() {
+        let logctx = test_setup_log("test_unreachable");
+        let injector =
+            std::sync::Arc::new(FaultInjector::new(logctx.log.clone()));
+        injector.set(SimFaults { unreachable: true, ..Default::default() });
+
+        let task = tokio::spawn({
+            let injector = injector.clone();
+            async move { injector.check("instance_get_state").await }
+        });
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert!(!task.is_finished());
+
+        // Once the sled is reachable again, the held request fails.
+        injector.set(SimFaults::default());
+        let error = task.await.unwrap().unwrap_err();
+        assert_eq!(error.status_code, http::StatusCode::SERVICE_UNAVAILABLE);
+
+        logctx.cleanup_successful();
+    }
+}
@@ -6,20 +6,35 @@
 
 use crate::bootstrap::early_networking::EarlyNetworkConfig;
 use crate::bootstrap::params::AddSledRequest;
+use crate::http_entrypoints::{
+    BootDiskOsWriteStatus, BootDiskPathParams, BootDiskUpdatePathParams,
+    BootDiskWriteStartQueryParams,
+};
 use crate::params::{
-    DiskEnsureBody, InstanceEnsureBody, InstanceExternalIpBody,
-    InstancePutMigrationIdsBody, InstancePutStateBody,
+    BootstoreStatus, CleanupContextUpdate, DiskEnsureBody, InstanceEnsureBody,
+    InstanceExternalIpBody, InstancePutMigrationIdsBody, InstancePutStateBody,
     InstancePutStateResponse, InstanceUnregisterResponse, Inventory,
-    OmicronPhysicalDisksConfig, OmicronZonesConfig, VpcFirewallRulesEnsureBody,
+    OmicronPhysicalDisksConfig, OmicronZonesConfig, TimeSync,
+    VpcFirewallRulesEnsureBody, ZoneBundleId, ZoneBundleMetadata,
 };
+use crate::sled_agent::Error as SledAgentError;
+use crate::zone_bundle;
+use camino::Utf8PathBuf;
 use dropshot::endpoint;
 use dropshot::ApiDescription;
+use dropshot::FreeformBody;
 use dropshot::HttpError;
+use dropshot::HttpResponseCreated;
+use dropshot::HttpResponseDeleted;
+use dropshot::HttpResponseHeaders;
 use dropshot::HttpResponseOk;
 use dropshot::HttpResponseUpdatedNoContent;
 use dropshot::Path;
+use dropshot::Query;
 use dropshot::RequestContext;
+use dropshot::StreamingBody;
 use dropshot::TypedBody;
+use hyper::Body;
 use illumos_utils::opte::params::DeleteVirtualNetworkInterfaceHost;
 use illumos_utils::opte::params::SetVirtualNetworkInterfaceHost;
 use omicron_common::api::internal::nexus::DiskRuntimeState;
@@ -29,9 +44,11 @@ use omicron_common::api::internal::shared::SwitchPorts;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use sled_storage::resources::DisksManagementResult;
+use std::collections::BTreeMap;
 use std::sync::Arc;
 use uuid::Uuid;
 
+use super::faults::SimFaults;
 use super::sled_agent::SledAgent;
 
 type SledApiDescription = ApiDescription<Arc<SledAgent>>;
@@ -63,6 +80,24 @@ pub fn api() -> SledApiDescription {
         api.register(omicron_zones_get)?;
         api.register(omicron_zones_put)?;
         api.register(sled_add)?;
+        api.register(zone_bundle_list_all)?;
+        api.register(zone_bundle_list)?;
+        api.register(zone_bundle_create)?;
+        api.register(zone_bundle_get)?;
+        api.register(zone_bundle_delete)?;
+        api.register(zone_bundle_utilization)?;
+        api.register(zone_bundle_cleanup_context)?;
+        api.register(zone_bundle_cleanup_context_update)?;
+        api.register(zone_bundle_cleanup)?;
+        api.register(timesync_get)?;
+        api.register(bootstore_status)?;
+        api.register(host_os_write_start)?;
+        api.register(host_os_write_status_get)?;
+        api.register(host_os_write_status_delete)?;
+        api.register(sim_faults_get)?;
+        api.register(sim_faults_put)?;
+        api.register(sim_faults_delete)?;
+        api.register(sim_timesync_put)?;
 
         Ok(())
     }
@@ -90,6 +125,7 @@ async fn instance_register(
     body: TypedBody<InstanceEnsureBody>,
 ) -> Result<HttpResponseOk<SledInstanceState>, HttpError> {
     let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
     let instance_id = path_params.into_inner().instance_id;
     let body_args = body.into_inner();
     Ok(HttpResponseOk(
@@ -114,6 +150,7 @@ async fn instance_unregister(
     path_params: Path<InstancePathParam>,
 ) -> Result<HttpResponseOk<InstanceUnregisterResponse>, HttpError> {
     let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
     let instance_id = path_params.into_inner().instance_id;
     Ok(HttpResponseOk(sa.instance_unregister(instance_id).await?))
 }
@@ -128,6 +165,7 @@ async fn instance_put_state(
     body: TypedBody<InstancePutStateBody>,
 ) -> Result<HttpResponseOk<InstancePutStateResponse>, HttpError> {
     let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
     let instance_id = path_params.into_inner().instance_id;
     let body_args = body.into_inner();
     Ok(HttpResponseOk(
@@ -144,6 +182,7 @@ async fn instance_get_state(
     path_params: Path<InstancePathParam>,
 ) -> Result<HttpResponseOk<SledInstanceState>, HttpError> {
     let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
     let instance_id = path_params.into_inner().instance_id;
     Ok(HttpResponseOk(sa.instance_get_state(instance_id).await?))
 }
@@ -158,6 +197,7 @@ async fn instance_put_migration_ids(
     body: TypedBody<InstancePutMigrationIdsBody>,
 ) -> Result<HttpResponseOk<SledInstanceState>, HttpError> {
     let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
     let instance_id = path_params.into_inner().instance_id;
     let body_args = body.into_inner();
     Ok(HttpResponseOk(
@@ -180,6 +220,7 @@ async fn instance_put_external_ip(
     body: TypedBody<InstanceExternalIpBody>,
 ) -> Result<HttpResponseUpdatedNoContent, HttpError> {
     let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
     let instance_id = path_params.into_inner().instance_id;
     let body_args = body.into_inner();
     sa.instance_put_external_ip(instance_id, &body_args).await?;
@@ -196,6 +237,7 @@ async fn instance_delete_external_ip(
     body: TypedBody<InstanceExternalIpBody>,
 ) -> Result<HttpResponseUpdatedNoContent, HttpError> {
     let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
     let instance_id = path_params.into_inner().instance_id;
     let body_args = body.into_inner();
     sa.instance_delete_external_ip(instance_id, &body_args).await?;
@@ -211,6 +253,7 @@ async fn instance_poke_post(
     path_params: Path<InstancePathParam>,
 ) -> Result<HttpResponseUpdatedNoContent, HttpError> {
     let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
     let instance_id = path_params.into_inner().instance_id;
     sa.instance_poke(instance_id).await;
     Ok(HttpResponseUpdatedNoContent())
@@ -232,6 +275,7 @@ async fn disk_put(
     body: TypedBody<DiskEnsureBody>,
 ) -> Result<HttpResponseOk<DiskRuntimeState>, HttpError> {
     let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
     let disk_id = path_params.into_inner().disk_id;
     let body_args = body.into_inner();
     Ok(HttpResponseOk(
@@ -253,6 +297,7 @@ async fn disk_poke_post(
     path_params: Path<DiskPathParam>,
 ) -> Result<HttpResponseUpdatedNoContent, HttpError> {
     let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
     let disk_id = path_params.into_inner().disk_id;
     sa.disk_poke(disk_id).await;
     Ok(HttpResponseUpdatedNoContent())
@@ -267,6 +312,7 @@ async fn update_artifact(
     artifact: TypedBody<UpdateArtifactId>,
 ) -> Result<HttpResponseUpdatedNoContent, HttpError> {
     let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
     sa.updates()
         .download_artifact(
             artifact.into_inner(),
@@ -305,6 +351,7 @@ async fn instance_issue_disk_snapshot_request(
 ) -> Result<HttpResponseOk<InstanceIssueDiskSnapshotRequestResponse>, HttpError>
 {
     let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
     let path_params = path_params.into_inner();
     let body = body.into_inner();
 
@@ -336,7 +383,8 @@ async fn vpc_firewall_rules_put(
     path_params: Path<VpcPathParam>,
     body: TypedBody<VpcFirewallRulesEnsureBody>,
 ) -> Result<HttpResponseUpdatedNoContent, HttpError> {
-    let _sa = rqctx.context();
+    let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
     let _vpc_id = path_params.into_inner().vpc_id;
     let _body_args = body.into_inner();
 
@@ -360,6 +408,7 @@ async fn set_v2p(
     body: TypedBody<SetVirtualNetworkInterfaceHost>,
 ) -> Result<HttpResponseUpdatedNoContent, HttpError> {
     let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
     let interface_id = path_params.into_inner().interface_id;
     let body_args = body.into_inner();
 
@@ -381,6 +430,7 @@ async fn del_v2p(
     body: TypedBody<DeleteVirtualNetworkInterfaceHost>,
 ) -> Result<HttpResponseUpdatedNoContent, HttpError> {
     let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
     let interface_id = path_params.into_inner().interface_id;
     let body_args = body.into_inner();
 
@@ -396,9 +446,10 @@ async fn del_v2p(
     path = "/switch-ports",
 }]
 async fn uplink_ensure(
-    _rqctx: RequestContext<Arc<SledAgent>>,
+    rqctx: RequestContext<Arc<SledAgent>>,
     _body: TypedBody<SwitchPorts>,
 ) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    rqctx.context().inject_faults(&rqctx).await?;
     Ok(HttpResponseUpdatedNoContent())
 }
 
@@ -409,6 +460,7 @@ async fn uplink_ensure(
 async fn read_network_bootstore_config(
     rqctx: RequestContext<Arc<SledAgent>>,
 ) -> Result<HttpResponseOk<EarlyNetworkConfig>, HttpError> {
+    rqctx.context().inject_faults(&rqctx).await?;
     let config = rqctx.context().bootstore_network_config.lock().await.clone();
     Ok(HttpResponseOk(config))
 }
@@ -421,6 +473,7 @@ async fn write_network_bootstore_config(
     rqctx: RequestContext<Arc<SledAgent>>,
     body: TypedBody<EarlyNetworkConfig>,
 ) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    rqctx.context().inject_faults(&rqctx).await?;
     let mut config = rqctx.context().bootstore_network_config.lock().await;
     *config = body.into_inner();
     Ok(HttpResponseUpdatedNoContent())
@@ -435,6 +488,7 @@ async fn inventory(
     rqctx: RequestContext<Arc<SledAgent>>,
 ) -> Result<HttpResponseOk<Inventory>, HttpError> {
     let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
     Ok(HttpResponseOk(
         sa.inventory(rqctx.server.local_addr)
             .await
@@ -451,6 +505,7 @@ async fn omicron_physical_disks_put(
     body: TypedBody<OmicronPhysicalDisksConfig>,
 ) -> Result<HttpResponseOk<DisksManagementResult>, HttpError> {
     let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
     let body_args = body.into_inner();
     let result = sa.omicron_physical_disks_ensure(body_args).await?;
     Ok(HttpResponseOk(result))
@@ -464,6 +519,7 @@ async fn omicron_physical_disks_get(
     rqctx: RequestContext<Arc<SledAgent>>,
 ) -> Result<HttpResponseOk<OmicronPhysicalDisksConfig>, HttpError> {
     let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
     Ok(HttpResponseOk(sa.omicron_physical_disks_list().await?))
 }
 
@@ -475,6 +531,7 @@ async fn omicron_zones_get(
     rqctx: RequestContext<Arc<SledAgent>>,
 ) -> Result<HttpResponseOk<OmicronZonesConfig>, HttpError> {
     let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
     Ok(HttpResponseOk(sa.omicron_zones_list().await))
 }
 
@@ -487,6 +544,7 @@ async fn omicron_zones_put(
     body: TypedBody<OmicronZonesConfig>,
 ) -> Result<HttpResponseUpdatedNoContent, HttpError> {
     let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
     let body_args = body.into_inner();
     sa.omicron_zones_ensure(body_args).await;
     Ok(HttpResponseUpdatedNoContent())
@@ -497,8 +555,330 @@ async fn omicron_zones_put(
     path = "/sleds"
 }]
 async fn sled_add(
-    _rqctx: RequestContext<Arc<SledAgent>>,
+    rqctx: RequestContext<Arc<SledAgent>>,
     _body: TypedBody<AddSledRequest>,
 ) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    rqctx.context().inject_faults(&rqctx).await?;
+    Ok(HttpResponseUpdatedNoContent())
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+struct ZonePathParam {
+    /// The name of the zone.
+    zone_name: String,
+}
+
+#[derive(Clone, Debug, Deserialize, JsonSchema, Serialize)]
+struct ZoneBundleFilter {
+    /// An optional substring used to filter zone bundles.
+    filter: Option<String>,
+}
+
+/// List all zone bundles that exist, even for now-deleted zones.
+#[endpoint {
+    method = GET,
+    path = "/zones/bundles",
+}]
+async fn zone_bundle_list_all(
+    rqctx: RequestContext<Arc<SledAgent>>,
+    query: Query<ZoneBundleFilter>,
+) -> Result<HttpResponseOk<Vec<ZoneBundleMetadata>>, HttpError> {
+    let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
+    let filter = query.into_inner().filter;
+    Ok(HttpResponseOk(sa.list_all_zone_bundles(filter.as_deref())))
+}
+
+/// List the zone bundles that are available for a running zone.
+#[endpoint {
+    method = GET,
+    path = "/zones/bundles/{zone_name}",
+}]
+async fn zone_bundle_list(
+    rqctx: RequestContext<Arc<SledAgent>>,
+    params: Path<ZonePathParam>,
+) -> Result<HttpResponseOk<Vec<ZoneBundleMetadata>>, HttpError> {
+    let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
+    let zone_name = params.into_inner().zone_name;
+    Ok(HttpResponseOk(sa.list_zone_bundles(&zone_name)))
+}
+
+/// Ask the sled agent to create a zone bundle.
+#[endpoint {
+    method = POST,
+    path = "/zones/bundles/{zone_name}",
+}]
+async fn zone_bundle_create(
+    rqctx: RequestContext<Arc<SledAgent>>,
+    params: Path<ZonePathParam>,
+) -> Result<HttpResponseCreated<ZoneBundleMetadata>, HttpError> {
+    let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
+    let zone_name = params.into_inner().zone_name;
+    sa.create_zone_bundle(&zone_name)
+        .await
+        .map(HttpResponseCreated)
+        .map_err(|e| HttpError::from(SledAgentError::from(e)))
+}
+
+fn zone_bundle_not_found(id: &ZoneBundleId) -> HttpError {
+    HttpError::for_not_found(
+        None,
+        format!(
+            "No zone bundle for zone '{}' with ID '{}'",
+            id.zone_name, id.bundle_id
+        ),
+    )
+}
+
+/// Fetch the binary content of a single zone bundle.
+#[endpoint {
+    method = GET,
+    path = "/zones/bundles/{zone_name}/{bundle_id}",
+}]
+async fn zone_bundle_get(
+    rqctx: RequestContext<Arc<SledAgent>>,
+    params: Path<ZoneBundleId>,
+) -> Result<HttpResponseHeaders<HttpResponseOk<FreeformBody>>, HttpError> {
+    let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
+    let id = params.into_inner();
+    let contents =
+        sa.get_zone_bundle(&id).ok_or_else(|| zone_bundle_not_found(&id))?;
+    let body = FreeformBody(Body::from(contents));
+    let mut response = HttpResponseHeaders::new_unnamed(HttpResponseOk(body));
+    response.headers_mut().append(
+        http::header::CONTENT_TYPE,
+        "application/gzip".try_into().unwrap(),
+    );
+    Ok(response)
+}
+
+/// Delete a zone bundle.
+#[endpoint {
+    method = DELETE,
+    path = "/zones/bundles/{zone_name}/{bundle_id}",
+}]
+async fn zone_bundle_delete(
+    rqctx: RequestContext<Arc<SledAgent>>,
+    params: Path<ZoneBundleId>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
+    let id = params.into_inner();
+    if !sa.delete_zone_bundle(&id) {
+        return Err(zone_bundle_not_found(&id));
+    }
+    Ok(HttpResponseDeleted())
+}
+
+/// Return utilization information about all zone bundles.
+#[endpoint {
+    method = GET,
+    path = "/zones/bundle-cleanup/utilization",
+}]
+async fn zone_bundle_utilization(
+    rqctx: RequestContext<Arc<SledAgent>>,
+) -> Result<
+    HttpResponseOk<BTreeMap<Utf8PathBuf, zone_bundle::BundleUtilization>>,
+    HttpError,
+> {
+    let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
+    Ok(HttpResponseOk(sa.zone_bundle_utilization()))
+}
+
+/// Return context used by the zone-bundle cleanup task.
+#[endpoint {
+    method = GET,
+    path = "/zones/bundle-cleanup/context",
+}]
+async fn zone_bundle_cleanup_context(
+    rqctx: RequestContext<Arc<SledAgent>>,
+) -> Result<HttpResponseOk<zone_bundle::CleanupContext>, HttpError> {
+    let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
+    Ok(HttpResponseOk(sa.zone_bundle_cleanup_context()))
+}
+
+/// Update context used by the zone-bundle cleanup task.
+#[endpoint {
+    method = PUT,
+    path = "/zones/bundle-cleanup/context",
+}]
+async fn zone_bundle_cleanup_context_update(
+    rqctx: RequestContext<Arc<SledAgent>>,
+    body: TypedBody<CleanupContextUpdate>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
+    let params = body.into_inner();
+    let new_period = params
+        .period
+        .map(zone_bundle::CleanupPeriod::new)
+        .transpose()
+        .map_err(|e| HttpError::from(SledAgentError::from(e)))?;
+    let new_priority = params.priority;
+    let new_limit = params
+        .storage_limit
+        .map(zone_bundle::StorageLimit::new)
+        .transpose()
+        .map_err(|e| HttpError::from(SledAgentError::from(e)))?;
+    sa.update_zone_bundle_cleanup_context(new_period, new_limit, new_priority);
+    Ok(HttpResponseUpdatedNoContent())
+}
+
+/// Trigger a zone bundle cleanup.
+#[endpoint {
+    method = POST,
+    path = "/zones/bundle-cleanup",
+}]
+async fn zone_bundle_cleanup(
+    rqctx: RequestContext<Arc<SledAgent>>,
+) -> Result<
+    HttpResponseOk<BTreeMap<Utf8PathBuf, zone_bundle::CleanupCount>>,
+    HttpError,
+> {
+    let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
+    Ok(HttpResponseOk(sa.zone_bundle_cleanup()))
+}
+
+#[endpoint {
+    method = GET,
+    path = "/timesync",
+}]
+async fn timesync_get(
+    rqctx: RequestContext<Arc<SledAgent>>,
+) -> Result<HttpResponseOk<TimeSync>, HttpError> {
+    let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
+    Ok(HttpResponseOk(sa.timesync_get().await))
+}
+
+/// Get the internal state of the local bootstore node
+#[endpoint {
+    method = GET,
+    path = "/bootstore/status",
+}]
+async fn bootstore_status(
+    rqctx: RequestContext<Arc<SledAgent>>,
+) -> Result<HttpResponseOk<BootstoreStatus>, HttpError> {
+    let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
+    Ok(HttpResponseOk(sa.bootstore_status().await))
+}
+
+/// Write a new host OS image to the specified boot disk
+#[endpoint {
+    method = POST,
+    path = "/boot-disk/{boot_disk}/os/write",
+}]
+async fn host_os_write_start(
+    rqctx: RequestContext<Arc<SledAgent>>,
+    path_params: Path<BootDiskPathParams>,
+    query_params: Query<BootDiskWriteStartQueryParams>,
+    body: StreamingBody,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
+    let boot_disk = path_params.into_inner().boot_disk;
+    let BootDiskWriteStartQueryParams { update_id, sha3_256_digest } =
+        query_params.into_inner();
+    sa.boot_disk_os_write_start(
+        boot_disk,
+        update_id,
+        sha3_256_digest,
+        body.into_stream(),
+    )
+    .await
+    .map_err(|err| HttpError::from(&*err))?;
+    Ok(HttpResponseUpdatedNoContent())
+}
+
+/// Get the status of writing a new host OS
+#[endpoint {
+    method = GET,
+    path = "/boot-disk/{boot_disk}/os/write/status",
+}]
+async fn host_os_write_status_get(
+    rqctx: RequestContext<Arc<SledAgent>>,
+    path_params: Path<BootDiskPathParams>,
+) -> Result<HttpResponseOk<BootDiskOsWriteStatus>, HttpError> {
+    let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
+    let boot_disk = path_params.into_inner().boot_disk;
+    Ok(HttpResponseOk(sa.boot_disk_os_write_status(boot_disk)))
+}
+
+/// Clear the status of a completed write of a new host OS
+#[endpoint {
+    method = DELETE,
+    path = "/boot-disk/{boot_disk}/os/write/status/{update_id}",
+}]
+async fn host_os_write_status_delete(
+    rqctx: RequestContext<Arc<SledAgent>>,
+    path_params: Path<BootDiskUpdatePathParams>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    let sa = rqctx.context();
+    sa.inject_faults(&rqctx).await?;
+    let BootDiskUpdatePathParams { boot_disk, update_id } =
+        path_params.into_inner();
+    sa.boot_disk_os_write_status_clear(boot_disk, update_id)
+        .map_err(|err| HttpError::from(&err))?;
+    Ok(HttpResponseUpdatedNoContent())
+}
+
+// Fault injection and other simulator controls (simulated sled agent only).
+// These endpoints are never themselves subject to injected faults so that
+// tests can always undo them.
+
+/// Fetch the faults currently being injected into this simulated sled agent
+#[endpoint {
+    method = GET,
+    path = "/sim/faults",
+}]
+async fn sim_faults_get(
+    rqctx: RequestContext<Arc<SledAgent>>,
+) -> Result<HttpResponseOk<SimFaults>, HttpError> {
+    Ok(HttpResponseOk(rqctx.context().faults()))
+}
+
+/// Replace the faults being injected into this simulated sled agent
+#[endpoint {
+    method = PUT,
+    path = "/sim/faults",
+}]
+async fn sim_faults_put(
+    rqctx: RequestContext<Arc<SledAgent>>,
+    body: TypedBody<SimFaults>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    rqctx.context().set_faults(body.into_inner());
+    Ok(HttpResponseUpdatedNoContent())
+}
+
+/// Stop injecting faults into this simulated sled agent
+#[endpoint {
+    method = DELETE,
+    path = "/sim/faults",
+}]
+async fn sim_faults_delete(
+    rqctx: RequestContext<Arc<SledAgent>>,
+) -> Result<HttpResponseDeleted, HttpError> {
+    rqctx.context().clear_faults();
+    Ok(HttpResponseDeleted())
+}
+
+/// Set the time synchronization state reported by this simulated sled agent
+#[endpoint {
+    method = PUT,
+    path = "/sim/timesync",
+}]
+async fn sim_timesync_put(
+    rqctx: RequestContext<Arc<SledAgent>>,
+    body: TypedBody<TimeSync>,
+) -> Result<HttpResponseUpdatedNoContent, HttpError> {
+    rqctx.context().set_timesync(body.into_inner()).await;
     Ok(HttpResponseUpdatedNoContent())
 }
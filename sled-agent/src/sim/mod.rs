@@ -4,9 +4,11 @@
 
 //! Simulated sled agent implementation
 
+mod boot_disk;
 mod collection;
 mod config;
 mod disk;
+mod faults;
 mod http_entrypoints;
 mod http_entrypoints_pantry;
 mod http_entrypoints_storage;
@@ -15,11 +17,13 @@ mod server;
 mod simulatable;
 mod sled_agent;
 mod storage;
+mod zone_bundle;
 
 pub use crate::updates::ConfigUpdates;
 pub use config::{
     Baseboard, Config, ConfigHardware, ConfigStorage, ConfigZpool, SimMode,
     TEST_HARDWARE_THREADS, TEST_RESERVOIR_RAM,
 };
+pub use faults::{EndpointFault, FaultKind, SimFaults, ALL_ENDPOINTS};
 pub use server::{run_standalone_server, RssArgs, Server};
 pub use sled_agent::SledAgent;
@@ -9,6 +9,7 @@ use super::http_entrypoints::api as http_api;
 use super::sled_agent::SledAgent;
 use super::storage::PantryServer;
 use crate::nexus::d2n_params;
+use crate::nexus::ConvertInto;
 use crate::nexus::NexusClient;
 use crate::params::OmicronZoneConfig;
 use crate::params::OmicronZoneDataset;
@@ -118,14 +119,11 @@ impl Server {
                         &NexusTypes::SledAgentInfo {
                             sa_address: sa_address.to_string(),
                             role: NexusTypes::SledRole::Scrimlet,
-                            baseboard: NexusTypes::Baseboard {
-                                serial: format!(
-                                    "sim-{}",
-                                    &config.id.to_string()[0..8]
-                                ),
-                                part: String::from("Unknown"),
-                                revision: 0,
-                            },
+                            baseboard: config
+                                .hardware
+                                .baseboard
+                                .clone()
+                                .convert(),
                             usable_hardware_threads: config
                                 .hardware
                                 .hardware_threads,
@@ -4,31 +4,50 @@
 
 //! Simulated sled agent implementation
 
+use super::boot_disk::SimulatedBootDisks;
 use super::collection::{PokeMode, SimCollection};
 use super::config::Config;
 use super::disk::SimDisk;
+use super::faults::FaultInjector;
+use super::faults::FaultOutcome;
+use super::faults::SimFaults;
 use super::instance::SimInstance;
 use super::storage::CrucibleData;
 use super::storage::Storage;
+use super::zone_bundle::SimZoneBundles;
+use crate::boot_disk_os_writer::BootDiskOsWriteError;
+use crate::boot_disk_os_writer::BootDiskOsWriter;
 use crate::bootstrap::early_networking::{
     EarlyNetworkConfig, EarlyNetworkConfigBody,
 };
+use crate::http_entrypoints::BootDiskOsWriteStatus;
 use crate::nexus::NexusClient;
 use crate::params::{
-    DiskStateRequested, InstanceExternalIpBody, InstanceHardware,
-    InstanceMetadata, InstanceMigrationSourceParams, InstancePutStateResponse,
-    InstanceStateRequested, InstanceUnregisterResponse, Inventory,
-    OmicronPhysicalDisksConfig, OmicronZonesConfig, SledRole,
+    BootstoreStatus, DiskStateRequested, InstanceExternalIpBody,
+    InstanceHardware, InstanceMetadata, InstanceMigrationSourceParams,
+    InstancePutStateResponse, InstanceStateRequested,
+    InstanceUnregisterResponse, Inventory, OmicronPhysicalDisksConfig,
+    OmicronZonesConfig, SledRole, TimeSync, ZoneBundleCause, ZoneBundleId,
+    ZoneBundleMetadata,
 };
 use crate::sim::simulatable::Simulatable;
 use crate::updates::UpdateManager;
+use crate::zone_bundle::{
+    BundleError, BundleUtilization, CleanupContext, CleanupCount,
+    CleanupPeriod, PriorityOrder, StorageLimit,
+};
 use anyhow::bail;
 use anyhow::Context;
-use dropshot::{HttpError, HttpServer};
+use bytes::Bytes;
+use camino::Utf8PathBuf;
+use dropshot::{HttpError, HttpServer, RequestContext};
 use futures::lock::Mutex;
+use futures::Stream;
 use illumos_utils::opte::params::{
     DeleteVirtualNetworkInterfaceHost, SetVirtualNetworkInterfaceHost,
 };
+use illumos_utils::zone::{PROPOLIS_ZONE_PREFIX, ZONE_PREFIX};
+use installinator_common::M2Slot;
 use ipnetwork::Ipv6Network;
 use omicron_common::api::external::{
     ByteCount, DiskState, Error, Generation, ResourceType,
@@ -46,9 +65,10 @@ use propolis_client::{
     types::VolumeConstructionRequest, Client as PropolisClient,
 };
 use propolis_mock_server::Context as PropolisContext;
+use sled_storage::resources::DiskManagementError;
 use sled_storage::resources::DisksManagementResult;
 use slog::Logger;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
 use std::str::FromStr;
 use std::sync::Arc;
@@ -82,10 +102,39 @@ pub struct SledAgent {
     config: Config,
     fake_zones: Mutex<OmicronZonesConfig>,
     instance_ensure_state_error: Mutex<Option<Error>>,
+    faults: FaultInjector,
     pub bootstore_network_config: Mutex<EarlyNetworkConfig>,
+    zone_bundles: Arc<SimZoneBundles>,
+    timesync: Mutex<TimeSync>,
+    boot_disk_os_writer: BootDiskOsWriter,
+    boot_disks: SimulatedBootDisks,
     pub log: Logger,
 }
 
+fn propolis_zone_name(propolis_id: Uuid) -> String {
+    format!("{PROPOLIS_ZONE_PREFIX}{propolis_id}")
+}
+
+// Take a bundle of an instance's Propolis zone once it's gone, as the real
+// sled agent does whenever an instance stops.
+fn bundle_terminated_instance(
+    log: &Logger,
+    zone_bundles: &SimZoneBundles,
+    propolis_id: Uuid,
+) {
+    let zone_name = propolis_zone_name(propolis_id);
+    if let Err(e) =
+        zone_bundles.create(&zone_name, ZoneBundleCause::TerminatedInstance)
+    {
+        error!(
+            log,
+            "Failed to take zone bundle for terminated instance";
+            "zone_name" => &zone_name,
+            "reason" => ?e,
+        );
+    }
+}
+
 fn extract_targets_from_volume_construction_request(
     vcr: &VolumeConstructionRequest,
 ) -> Result<Vec<SocketAddr>, std::net::AddrParseError> {
@@ -149,6 +198,10 @@ impl SledAgent {
         let instance_log = log.new(o!("kind" => "instances"));
         let disk_log = log.new(o!("kind" => "disks"));
         let storage_log = log.new(o!("kind" => "storage"));
+        let faults_log = log.new(o!("kind" => "faults"));
+        let zone_bundles = Arc::new(SimZoneBundles::new(
+            log.new(o!("kind" => "zone-bundles")),
+        ));
 
         let bootstore_network_config = Mutex::new(EarlyNetworkConfig {
             generation: 0,
@@ -170,11 +223,23 @@ impl SledAgent {
         Arc::new(SledAgent {
             id,
             ip: config.dropshot.bind_address.ip(),
-            instances: Arc::new(SimCollection::new(
-                Arc::clone(&nexus_client),
-                instance_log,
-                sim_mode,
-            )),
+            instances: Arc::new(
+                SimCollection::new(
+                    Arc::clone(&nexus_client),
+                    instance_log.clone(),
+                    sim_mode,
+                )
+                .with_destroy_hook({
+                    let zone_bundles = Arc::clone(&zone_bundles);
+                    move |_id: &Uuid, instance: &SimInstance| {
+                        bundle_terminated_instance(
+                            &instance_log,
+                            &zone_bundles,
+                            instance.current().propolis_id,
+                        )
+                    }
+                }),
+            ),
             disks: Arc::new(SimCollection::new(
                 Arc::clone(&nexus_client),
                 disk_log,
@@ -198,8 +263,22 @@ impl SledAgent {
                 zones: vec![],
             }),
             instance_ensure_state_error: Mutex::new(None),
+            faults: FaultInjector::new(faults_log),
+            boot_disk_os_writer: BootDiskOsWriter::new(&log),
             log,
             bootstore_network_config,
+            zone_bundles,
+            // Until a test says otherwise, report time as synchronized, as the
+            // real sled agent does when it's configured to skip timesync.
+            timesync: Mutex::new(TimeSync {
+                sync: true,
+                ref_id: 0,
+                ip_addr: IpAddr::V6(Ipv6Addr::UNSPECIFIED),
+                stratum: 0,
+                ref_time: 0.0,
+                correction: 0.0,
+            }),
+            boot_disks: SimulatedBootDisks::default(),
         })
     }
 
@@ -382,8 +461,14 @@ impl SledAgent {
             };
 
         self.detach_disks_from_instance(instance_id).await?;
+        let updated_runtime = instance.terminate();
+        bundle_terminated_instance(
+            &self.log,
+            &self.zone_bundles,
+            updated_runtime.propolis_id,
+        );
         let response = InstanceUnregisterResponse {
-            updated_runtime: Some(instance.terminate()),
+            updated_runtime: Some(updated_runtime),
         };
 
         self.instances.sim_force_remove(instance_id).await;
@@ -497,6 +582,48 @@ impl SledAgent {
         *self.instance_ensure_state_error.lock().await = error;
     }
 
+    /// Returns the faults currently being injected into this sled agent
+    pub fn faults(&self) -> SimFaults {
+        self.faults.get()
+    }
+
+    /// Replaces the faults being injected into this sled agent
+    pub fn set_faults(&self, faults: SimFaults) {
+        self.faults.set(faults);
+    }
+
+    /// Stops injecting faults into this sled agent
+    pub fn clear_faults(&self) {
+        self.faults.set(SimFaults::default());
+    }
+
+    /// Applies any fault configured for the endpoint handling `rqctx` to the
+    /// request
+    ///
+    /// Each API endpoint calls this before doing anything else.  Faults are
+    /// looked up by the endpoint's operation ID.
+    pub(super) async fn inject_faults(
+        &self,
+        rqctx: &RequestContext<Arc<SledAgent>>,
+    ) -> Result<(), HttpError> {
+        let endpoint = &rqctx.endpoint.operation_id;
+        if self.faults.check(endpoint).await? == FaultOutcome::Reboot {
+            self.simulate_reboot().await;
+        }
+        Ok(())
+    }
+
+    /// Forgets all instances and disks (and the networking state set up for
+    /// them), as a real sled agent would after its sled rebooted
+    async fn simulate_reboot(&self) {
+        warn!(self.log, "simulating sled reboot");
+        self.instances.sim_force_remove_all().await;
+        self.disks.sim_force_remove_all().await;
+        self.disk_id_to_region_ids.lock().await.clear();
+        self.v2p_mappings.lock().await.clear();
+        self.external_ips.lock().await.clear();
+    }
+
     async fn detach_disks_from_instance(
         &self,
         instance_id: Uuid,
@@ -640,6 +767,12 @@ impl SledAgent {
         disk_id: Uuid,
         snapshot_id: Uuid,
     ) -> Result<(), Error> {
+        if self.faults.fail_disk_snapshots() {
+            return Err(Error::internal_error(
+                "simulated disk snapshot failure",
+            ));
+        }
+
         // In order to fulfill the snapshot request, emulate creating snapshots
         // for each region that makes up the disk. Use the disk_id_to_region_ids
         // map to perform lookup based on this function's disk id argument.
@@ -857,7 +990,20 @@ impl SledAgent {
         &self,
         config: OmicronPhysicalDisksConfig,
     ) -> Result<DisksManagementResult, HttpError> {
-        self.storage.lock().await.omicron_physical_disks_ensure(config).await
+        let mut result = self
+            .storage
+            .lock()
+            .await
+            .omicron_physical_disks_ensure(config)
+            .await?;
+        if self.faults.fail_zpool_operations() {
+            for status in &mut result.status {
+                status.err = Some(DiskManagementError::Other(String::from(
+                    "simulated zpool failure",
+                )));
+            }
+        }
+        Ok(result)
     }
 
     pub async fn omicron_zones_list(&self) -> OmicronZonesConfig {
@@ -870,4 +1016,160 @@ impl SledAgent {
     ) {
         *self.fake_zones.lock().await = requested_zones;
     }
+
+    /// List all zone bundles that match the provided filter, including those
+    /// of zones that no longer exist.
+    pub fn list_all_zone_bundles(
+        &self,
+        filter: Option<&str>,
+    ) -> Vec<ZoneBundleMetadata> {
+        self.zone_bundles.list(filter)
+    }
+
+    /// List zone bundles for the provided zone.
+    pub fn list_zone_bundles(&self, name: &str) -> Vec<ZoneBundleMetadata> {
+        self.zone_bundles.list_for_zone(name)
+    }
+
+    /// Create a zone bundle for the provided zone.
+    ///
+    /// Only the Propolis zones of registered instances and the Omicron zones
+    /// this sled has been asked to run can be bundled.
+    pub async fn create_zone_bundle(
+        &self,
+        name: &str,
+    ) -> Result<ZoneBundleMetadata, BundleError> {
+        let exists = if name.starts_with(PROPOLIS_ZONE_PREFIX) {
+            self.instances
+                .sim_current_states()
+                .await
+                .iter()
+                .any(|state| propolis_zone_name(state.propolis_id) == name)
+        } else if name.starts_with(ZONE_PREFIX) {
+            self.fake_zones
+                .lock()
+                .await
+                .zones
+                .iter()
+                .any(|zone| zone.zone_name() == name)
+        } else {
+            false
+        };
+        if !exists {
+            return Err(BundleError::NoSuchZone { name: name.to_string() });
+        }
+        self.zone_bundles.create(name, ZoneBundleCause::ExplicitRequest)
+    }
+
+    /// Fetch the contents of a zone bundle, if it exists.
+    pub fn get_zone_bundle(&self, id: &ZoneBundleId) -> Option<Bytes> {
+        self.zone_bundles.get(id)
+    }
+
+    /// Delete a zone bundle, returning false if it doesn't exist.
+    pub fn delete_zone_bundle(&self, id: &ZoneBundleId) -> bool {
+        self.zone_bundles.delete(id)
+    }
+
+    /// Fetch the current utilization of the (simulated) zone bundle
+    /// directories.
+    pub fn zone_bundle_utilization(
+        &self,
+    ) -> BTreeMap<Utf8PathBuf, BundleUtilization> {
+        self.zone_bundles.utilization()
+    }
+
+    /// Fetch the zone bundle cleanup context.
+    pub fn zone_bundle_cleanup_context(&self) -> CleanupContext {
+        self.zone_bundles.cleanup_context()
+    }
+
+    /// Update the zone bundle cleanup context.
+    pub fn update_zone_bundle_cleanup_context(
+        &self,
+        period: Option<CleanupPeriod>,
+        storage_limit: Option<StorageLimit>,
+        priority: Option<PriorityOrder>,
+    ) {
+        self.zone_bundles.update_cleanup_context(
+            period,
+            storage_limit,
+            priority,
+        )
+    }
+
+    /// Trigger an explicit request to cleanup old zone bundles.
+    pub fn zone_bundle_cleanup(&self) -> BTreeMap<Utf8PathBuf, CleanupCount> {
+        self.zone_bundles.cleanup()
+    }
+
+    pub async fn timesync_get(&self) -> TimeSync {
+        self.timesync.lock().await.clone()
+    }
+
+    /// Sets the time synchronization state this sled agent reports
+    pub async fn set_timesync(&self, timesync: TimeSync) {
+        info!(self.log, "setting simulated timesync state";
+            "timesync" => ?timesync);
+        *self.timesync.lock().await = timesync;
+    }
+
+    /// Returns the state of this sled's bootstore node
+    ///
+    /// The simulated sled agent doesn't run a bootstore node, so this
+    /// describes a node of an initialized rack that has no peers, with a
+    /// network config ledger reflecting whatever has been written to
+    /// `bootstore_network_config`.
+    pub async fn bootstore_status(&self) -> BootstoreStatus {
+        let generation = self.bootstore_network_config.lock().await.generation;
+        BootstoreStatus {
+            fsm_ledger_generation: 1,
+            network_config_ledger_generation: (generation > 0)
+                .then_some(generation),
+            fsm_state: String::from("initial_member"),
+            peers: BTreeSet::new(),
+            established_connections: Vec::new(),
+            accepted_connections: BTreeSet::new(),
+            negotiating_connections: BTreeSet::new(),
+        }
+    }
+
+    /// Start writing a new OS image to one of this sled's (in-memory) boot
+    /// disks
+    pub(crate) async fn boot_disk_os_write_start<S>(
+        &self,
+        boot_disk: M2Slot,
+        update_id: Uuid,
+        sha3_256_digest: [u8; 32],
+        image_upload: S,
+    ) -> Result<(), Arc<BootDiskOsWriteError>>
+    where
+        S: Stream<Item = Result<Bytes, HttpError>> + Send + 'static,
+    {
+        self.boot_disk_os_writer
+            .start_update_impl(
+                boot_disk,
+                SimulatedBootDisks::devfs_path(boot_disk),
+                update_id,
+                sha3_256_digest,
+                image_upload,
+                self.boot_disks.clone(),
+            )
+            .await
+    }
+
+    pub(crate) fn boot_disk_os_write_status(
+        &self,
+        boot_disk: M2Slot,
+    ) -> BootDiskOsWriteStatus {
+        self.boot_disk_os_writer.status(boot_disk)
+    }
+
+    pub(crate) fn boot_disk_os_write_status_clear(
+        &self,
+        boot_disk: M2Slot,
+        update_id: Uuid,
+    ) -> Result<(), BootDiskOsWriteError> {
+        self.boot_disk_os_writer.clear_terminal_status(boot_disk, update_id)
+    }
 }
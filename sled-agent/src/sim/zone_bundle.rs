@@ -0,0 +1,400 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+//! Simulated zone bundles
+//!
+//! The simulated sled agent keeps its zone bundles in memory, as though they
+//! were stored in the debug dataset of each of the sled's two M.2s (so, like
+//! real bundles, each one appears in both places).  Bundles are created when an
+//! instance's Propolis zone goes away or when a client asks for one, and they
+//! are removed (periodically or on request) using the same cleanup context as
+//! the real sled agent.  The simulated datasets have a much smaller quota than
+//! real ones, so a handful of bundles is enough to exceed the storage limit.
+
+use crate::params::ZoneBundleCause;
+use crate::params::ZoneBundleId;
+use crate::params::ZoneBundleMetadata;
+use crate::zone_bundle::insert_data;
+use crate::zone_bundle::BundleError;
+use crate::zone_bundle::BundleUtilization;
+use crate::zone_bundle::CleanupContext;
+use crate::zone_bundle::CleanupCount;
+use crate::zone_bundle::CleanupPeriod;
+use crate::zone_bundle::PriorityOrder;
+use crate::zone_bundle::StorageLimit;
+use crate::zone_bundle::ZONE_BUNDLE_METADATA_FILENAME;
+use bytes::Bytes;
+use camino::Utf8PathBuf;
+use rand::RngCore;
+use slog::Logger;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::Weak;
+use tokio::sync::Notify;
+use tokio::time::Instant;
+use uuid::Uuid;
+
+/// Quota of each simulated debug dataset, in bytes
+const SIM_DEBUG_DATASET_QUOTA: u64 = 1 << 20;
+
+/// Size of the (incompressible) log data stored in each simulated bundle
+const SIM_BUNDLE_LOG_SIZE: usize = 64 << 10;
+
+/// Zone bundles stored by a simulated sled agent
+pub(super) struct SimZoneBundles {
+    log: Logger,
+    inner: Arc<Mutex<Inner>>,
+    // Used to tell the periodic cleanup task that the cleanup period may have
+    // changed.
+    notify_cleanup: Arc<Notify>,
+}
+
+struct Inner {
+    // Bundles in each simulated bundle directory
+    dirs: BTreeMap<Utf8PathBuf, BTreeMap<ZoneBundleId, SimBundle>>,
+    context: CleanupContext,
+    last_cleanup_at: Instant,
+}
+
+struct SimBundle {
+    metadata: ZoneBundleMetadata,
+    // The gzipped tarball itself
+    contents: Bytes,
+}
+
+impl SimBundle {
+    fn bytes(&self) -> u64 {
+        self.contents.len() as u64
+    }
+}
+
+impl Inner {
+    fn next_cleanup(&self) -> Instant {
+        self.last_cleanup_at + self.context.period.as_duration()
+    }
+
+    fn utilization(&self) -> BTreeMap<Utf8PathBuf, BundleUtilization> {
+        let bytes_available =
+            self.context.storage_limit.bytes_available(SIM_DEBUG_DATASET_QUOTA);
+        self.dirs
+            .iter()
+            .map(|(dir, bundles)| {
+                let usage = BundleUtilization {
+                    dataset_quota: SIM_DEBUG_DATASET_QUOTA,
+                    bytes_available,
+                    bytes_used: bundles.values().map(SimBundle::bytes).sum(),
+                };
+                (dir.clone(), usage)
+            })
+            .collect()
+    }
+
+    // Remove the lowest-priority bundles from each directory until it's back
+    // within the storage limit, as `zone_bundle::run_cleanup()` does.
+    fn cleanup(&mut self, log: &Logger) -> BTreeMap<Utf8PathBuf, CleanupCount> {
+        self.last_cleanup_at = Instant::now();
+        let usages = self.utilization();
+        if usages
+            .values()
+            .all(|usage| usage.bytes_used <= usage.bytes_available)
+        {
+            debug!(log, "all usages below storage limit, returning");
+            return BTreeMap::new();
+        }
+
+        let priority = self.context.priority;
+        let mut cleanup_counts = BTreeMap::new();
+        for (dir, bundles) in self.dirs.iter_mut() {
+            let usage = &usages[dir];
+            let mut by_priority: Vec<_> =
+                bundles.values().map(|b| b.metadata.clone()).collect();
+            by_priority.sort_by(|lhs, rhs| priority.compare_metadata(lhs, rhs));
+
+            let mut count = CleanupCount::default();
+            let mut n_bytes = usage.bytes_used;
+            for metadata in by_priority {
+                if n_bytes <= usage.bytes_available {
+                    break;
+                }
+                let bundle = bundles.remove(&metadata.id).unwrap();
+                trace!(log, "removed old zone bundle";
+                    "directory" => %dir, "metadata" => ?metadata);
+                n_bytes = n_bytes.saturating_sub(bundle.bytes());
+                count.bundles += 1;
+                count.bytes += bundle.bytes();
+            }
+            cleanup_counts.insert(dir.clone(), count);
+        }
+        info!(log, "finished bundle cleanup";
+            "cleanup_counts" => ?&cleanup_counts);
+        cleanup_counts
+    }
+}
+
+impl SimZoneBundles {
+    pub(super) fn new(log: Logger) -> Self {
+        // Mirror the layout of the real bundle directories, each under the
+        // debug dataset of an (internal) M.2 zpool.
+        let dirs = (0..2)
+            .map(|_| {
+                let dir = Utf8PathBuf::from(format!(
+                    "/pool/int/{}/debug/bundle/zone",
+                    Uuid::new_v4()
+                ));
+                (dir, BTreeMap::new())
+            })
+            .collect();
+        let inner = Arc::new(Mutex::new(Inner {
+            dirs,
+            context: CleanupContext::default(),
+            last_cleanup_at: Instant::now(),
+        }));
+        let notify_cleanup = Arc::new(Notify::new());
+        tokio::spawn(Self::periodic_cleanup(
+            log.new(o!("component" => "auto-cleanup-task")),
+            Arc::downgrade(&inner),
+            Arc::clone(&notify_cleanup),
+        ));
+        SimZoneBundles { log, inner, notify_cleanup }
+    }
+
+    // Periodically cleans up bundles, until the bundles themselves are
+    // dropped along with the sled agent.
+    async fn periodic_cleanup(
+        log: Logger,
+        inner: Weak<Mutex<Inner>>,
+        notify_cleanup: Arc<Notify>,
+    ) {
+        loop {
+            let Some(strong) = inner.upgrade() else {
+                return;
+            };
+            let next_cleanup = strong.lock().unwrap().next_cleanup();
+            drop(strong);
+            tokio::select! {
+                _ = tokio::time::sleep_until(next_cleanup) => {
+                    let Some(inner) = inner.upgrade() else {
+                        return;
+                    };
+                    info!(log, "running automatic periodic zone bundle cleanup");
+                    inner.lock().unwrap().cleanup(&log);
+                }
+                _ = notify_cleanup.notified() => {
+                    debug!(log, "notified about cleanup context change");
+                }
+            }
+        }
+    }
+
+    /// Creates a bundle of the zone `zone_name`
+    ///
+    /// The caller is responsible for checking that the zone exists.
+    pub(super) fn create(
+        &self,
+        zone_name: &str,
+        cause: ZoneBundleCause,
+    ) -> Result<ZoneBundleMetadata, BundleError> {
+        let metadata = ZoneBundleMetadata::new(zone_name, cause);
+        let contents = Bytes::from(Self::build_tarball(&metadata)?);
+        info!(self.log, "creating zone bundle";
+            "zone_name" => zone_name,
+            "cause" => ?cause,
+            "bytes" => contents.len(),
+        );
+        let mut inner = self.inner.lock().unwrap();
+        for bundles in inner.dirs.values_mut() {
+            bundles.insert(
+                metadata.id.clone(),
+                SimBundle {
+                    metadata: metadata.clone(),
+                    contents: contents.clone(),
+                },
+            );
+        }
+        Ok(metadata)
+    }
+
+    // Build a tarball laid out like a real zone bundle: metadata first,
+    // followed by some (random) log data.
+    fn build_tarball(
+        metadata: &ZoneBundleMetadata,
+    ) -> Result<Vec<u8>, BundleError> {
+        let filename = format!("{}.tar.gz", metadata.id.bundle_id);
+        let gz = flate2::GzBuilder::new()
+            .filename(filename.as_str())
+            .write(Vec::new(), flate2::Compression::best());
+        let mut builder = tar::Builder::new(gz);
+        let contents = toml::to_string(metadata)?;
+        insert_data(
+            &mut builder,
+            ZONE_BUNDLE_METADATA_FILENAME,
+            contents.as_bytes(),
+        )?;
+        let mut log_data = vec![0; SIM_BUNDLE_LOG_SIZE];
+        rand::thread_rng().fill_bytes(&mut log_data);
+        insert_data(&mut builder, "simulated.log", &log_data)?;
+        builder.into_inner().and_then(|gz| gz.finish()).map_err(|err| {
+            BundleError::AddBundleData { tarball_path: filename.into(), err }
+        })
+    }
+
+    /// Lists all bundles whose zone name contains `filter`, or all bundles if
+    /// there's no filter
+    pub(super) fn list(&self, filter: Option<&str>) -> Vec<ZoneBundleMetadata> {
+        self.list_where(|metadata| {
+            filter.map_or(true, |filt| metadata.id.zone_name.contains(filt))
+        })
+    }
+
+    /// Lists the bundles of the zone `zone_name`
+    pub(super) fn list_for_zone(
+        &self,
+        zone_name: &str,
+    ) -> Vec<ZoneBundleMetadata> {
+        self.list_where(|metadata| metadata.id.zone_name == zone_name)
+    }
+
+    fn list_where(
+        &self,
+        filter: impl Fn(&ZoneBundleMetadata) -> bool,
+    ) -> Vec<ZoneBundleMetadata> {
+        // Bundles are replicated in each directory, so use a set to avoid
+        // reporting them more than once.
+        let inner = self.inner.lock().unwrap();
+        inner
+            .dirs
+            .values()
+            .flat_map(|bundles| bundles.values())
+            .map(|bundle| &bundle.metadata)
+            .filter(|metadata| filter(metadata))
+            .cloned()
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    /// Returns the contents of a bundle, if it still exists
+    pub(super) fn get(&self, id: &ZoneBundleId) -> Option<Bytes> {
+        let inner = self.inner.lock().unwrap();
+        inner
+            .dirs
+            .values()
+            .find_map(|bundles| bundles.get(id))
+            .map(|bundle| bundle.contents.clone())
+    }
+
+    /// Deletes every copy of a bundle, returning whether there were any
+    pub(super) fn delete(&self, id: &ZoneBundleId) -> bool {
+        let mut inner = self.inner.lock().unwrap();
+        let mut found = false;
+        for bundles in inner.dirs.values_mut() {
+            found |= bundles.remove(id).is_some();
+        }
+        found
+    }
+
+    pub(super) fn utilization(
+        &self,
+    ) -> BTreeMap<Utf8PathBuf, BundleUtilization> {
+        self.inner.lock().unwrap().utilization()
+    }
+
+    pub(super) fn cleanup_context(&self) -> CleanupContext {
+        self.inner.lock().unwrap().context
+    }
+
+    pub(super) fn update_cleanup_context(
+        &self,
+        new_period: Option<CleanupPeriod>,
+        new_storage_limit: Option<StorageLimit>,
+        new_priority: Option<PriorityOrder>,
+    ) {
+        info!(
+            self.log,
+            "received request to update cleanup context";
+            "period" => ?new_period,
+            "priority" => ?new_priority,
+            "storage_limit" => ?new_storage_limit,
+        );
+        let mut inner = self.inner.lock().unwrap();
+        if let Some(new_period) = new_period {
+            inner.context.period = new_period;
+        }
+        if let Some(new_priority) = new_priority {
+            inner.context.priority = new_priority;
+        }
+        if let Some(new_storage_limit) = new_storage_limit {
+            inner.context.storage_limit = new_storage_limit;
+        }
+        self.notify_cleanup.notify_one();
+    }
+
+    /// Immediately removes low-priority bundles to bring each directory back
+    /// within the storage limit
+    pub(super) fn cleanup(&self) -> BTreeMap<Utf8PathBuf, CleanupCount> {
+        let counts = self.inner.lock().unwrap().cleanup(&self.log);
+        self.notify_cleanup.notify_one();
+        counts
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use omicron_test_utils::dev::test_setup_log;
+
+    #[tokio::test]
+    async fn test_cleanup_honors_storage_limit() {
+        let logctx = test_setup_log("test_cleanup_honors_storage_limit");
+        let bundles = SimZoneBundles::new(logctx.log.clone());
+
+        // Each bundle is a little larger than `SIM_BUNDLE_LOG_SIZE`, so with
+        // the default storage limit (25% of the quota) only three fit.
+        let explicit = bundles
+            .create("oxz_ntp_0", ZoneBundleCause::ExplicitRequest)
+            .unwrap();
+        let terminated: Vec<_> = (0..4)
+            .map(|i| {
+                bundles
+                    .create(
+                        &format!("oxz_propolis-server_{i}"),
+                        ZoneBundleCause::TerminatedInstance,
+                    )
+                    .unwrap()
+            })
+            .collect();
+        assert_eq!(bundles.list(None).len(), 5);
+        assert_eq!(bundles.list(Some("propolis")).len(), 4);
+        assert!(bundles
+            .utilization()
+            .values()
+            .all(|usage| usage.bytes_used > usage.bytes_available));
+
+        // The oldest bundles of terminated instances go first.
+        let counts = bundles.cleanup();
+        assert_eq!(counts.len(), 2);
+        assert!(counts.values().all(|count| count.bundles == 2));
+        let mut expected = vec![explicit.clone()];
+        expected.extend(terminated[2..].iter().cloned());
+        expected.sort();
+        assert_eq!(bundles.list(None), expected);
+        assert!(bundles.get(&terminated[0].id).is_none());
+
+        // Cleaning up again doesn't remove anything else...
+        assert!(bundles.cleanup().is_empty());
+
+        // ... until the storage limit is lowered.
+        bundles.update_cleanup_context(
+            None,
+            Some(StorageLimit::new(10).unwrap()),
+            None,
+        );
+        bundles.cleanup();
+        assert_eq!(bundles.list(None), vec![explicit]);
+
+        logctx.cleanup_successful();
+    }
+}
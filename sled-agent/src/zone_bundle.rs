@@ -539,7 +539,7 @@ const ZONE_WIDE_COMMANDS: [&[&str]; 6] = [
 ];
 
 // The name for zone bundle metadata files.
-const ZONE_BUNDLE_METADATA_FILENAME: &str = "metadata.toml";
+pub(crate) const ZONE_BUNDLE_METADATA_FILENAME: &str = "metadata.toml";
 
 /// Errors related to managing service zone bundles.
 #[derive(Debug, thiserror::Error)]
@@ -679,7 +679,7 @@ pub enum BundleError {
 
 // Helper function to write an array of bytes into the tar archive, with
 // the provided name.
-fn insert_data<W: std::io::Write>(
+pub(crate) fn insert_data<W: std::io::Write>(
     builder: &mut Builder<W>,
     name: &str,
     contents: &[u8],
@@ -1562,9 +1562,9 @@ async fn enumerate_zone_bundles(
 #[derive(Clone, Copy, Debug, Default, Deserialize, JsonSchema, Serialize)]
 pub struct CleanupCount {
     /// The number of bundles removed.
-    bundles: u64,
+    pub(crate) bundles: u64,
     /// The number of bytes removed.
-    bytes: u64,
+    pub(crate) bytes: u64,
 }
 
 // Run a cleanup, removing old bundles according to the strategy.
@@ -1846,7 +1846,7 @@ impl StorageLimit {
     }
 
     // Compute the number of bytes available from a dataset quota, in bytes.
-    const fn bytes_available(&self, dataset_quota: u64) -> u64 {
+    pub(crate) const fn bytes_available(&self, dataset_quota: u64) -> u64 {
         (dataset_quota * self.as_u8() as u64) / 100
     }
 }
@@ -1927,14 +1927,21 @@ impl PriorityOrder {
         &self,
         lhs: &ZoneBundleInfo,
         rhs: &ZoneBundleInfo,
+    ) -> Ordering {
+        self.compare_metadata(&lhs.metadata, &rhs.metadata)
+    }
+
+    // Order zone bundle metadata according to the contained priority.
+    pub(crate) fn compare_metadata(
+        &self,
+        lhs: &ZoneBundleMetadata,
+        rhs: &ZoneBundleMetadata,
     ) -> Ordering {
         for dim in self.0.iter() {
             let ord = match dim {
-                PriorityDimension::Cause => {
-                    lhs.metadata.cause.cmp(&rhs.metadata.cause)
-                }
+                PriorityDimension::Cause => lhs.cause.cmp(&rhs.cause),
                 PriorityDimension::Time => {
-                    lhs.metadata.time_created.cmp(&rhs.metadata.time_created)
+                    lhs.time_created.cmp(&rhs.time_created)
                 }
             };
             if matches!(ord, Ordering::Equal) {
@@ -6,6 +6,8 @@
 //! configuration
 
 use dropshot::ConfigLogging;
+use gateway_messages::measurement::MeasurementError;
+use gateway_messages::measurement::MeasurementKind;
 use gateway_messages::DeviceCapabilities;
 use gateway_messages::DevicePresence;
 use serde::Deserialize;
@@ -52,6 +54,25 @@ pub struct SpComponentConfig {
     ///
     /// Only supported for components inside a [`GimletConfig`].
     pub serial_console: Option<SocketAddrV6>,
+    /// Fake measurement channels reported as this component's details.
+    ///
+    /// Components with sensors should also set
+    /// [`DeviceCapabilities::HAS_MEASUREMENT_CHANNELS`].
+    #[serde(skip_serializing_if = "Vec::is_empty", default)]
+    pub sensors: Vec<SpSensorConfig>,
+}
+
+/// Configuration of a simulated sensor (measurement channel)
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize)]
+pub struct SpSensorConfig {
+    pub name: String,
+    pub kind: MeasurementKind,
+    /// Reading reported by the sensor (e.g., degrees Celsius for temperature
+    /// sensors, RPM for fan speed sensors)
+    pub value: f32,
+    /// If set, the sensor reports this error instead of `value`.
+    #[serde(skip_serializing_if = "Option::is_none", default)]
+    pub error: Option<MeasurementError>,
 }
 
 /// Configuration of a simulated sidecar SP
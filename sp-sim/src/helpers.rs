@@ -2,7 +2,10 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use gateway_messages::{RotSlotId, SpError};
+use crate::config::SpComponentConfig;
+use gateway_messages::measurement::Measurement;
+use gateway_messages::sp_impl::BoundsChecked;
+use gateway_messages::{ComponentDetails, RotSlotId, SpComponent, SpError};
 
 pub(crate) fn rot_slot_id_to_u16(slot_id: RotSlotId) -> u16 {
     match slot_id {
@@ -18,3 +21,44 @@ pub(crate) fn rot_slot_id_from_u16(slot_id: u16) -> Result<RotSlotId, SpError> {
         _ => Err(SpError::InvalidSlotForComponent),
     }
 }
+
+fn find_component<'a>(
+    components: &'a [SpComponentConfig],
+    component: SpComponent,
+) -> Option<&'a SpComponentConfig> {
+    components
+        .iter()
+        .find(|c| SpComponent::try_from(c.id.as_str()).ok() == Some(component))
+}
+
+/// Number of details (simulated sensor readings) reported for `component`
+pub(crate) fn num_component_details(
+    components: &[SpComponentConfig],
+    component: SpComponent,
+) -> Result<u32, SpError> {
+    let c = find_component(components, component)
+        .ok_or(SpError::RequestUnsupportedForComponent)?;
+    Ok(c.sensors.len().try_into().unwrap())
+}
+
+/// Detail (simulated sensor reading) `index` for `component`
+///
+/// `index` must have been bounds checked against the count returned by
+/// [`num_component_details()`].
+pub(crate) fn component_details(
+    components: &[SpComponentConfig],
+    component: SpComponent,
+    index: BoundsChecked,
+) -> ComponentDetails {
+    let c = find_component(components, component)
+        .expect("component details requested for unknown component");
+    let sensor = &c.sensors[index.0 as usize];
+    ComponentDetails::Measurement(Measurement {
+        name: sensor.name.clone(),
+        kind: sensor.kind,
+        value: match sensor.error {
+            Some(error) => Err(error),
+            None => Ok(sensor.value),
+        },
+    })
+}
@@ -6,6 +6,7 @@ use crate::config::Config;
 use crate::config::SidecarConfig;
 use crate::config::SimulatedSpsConfig;
 use crate::config::SpComponentConfig;
+use crate::helpers;
 use crate::helpers::rot_slot_id_from_u16;
 use crate::helpers::rot_slot_id_to_u16;
 use crate::rot::RotSprocketExt;
@@ -937,13 +938,16 @@ impl SpHandler for Handler {
         port: SpPort,
         component: SpComponent,
     ) -> Result<u32, SpError> {
-        warn!(
-            &self.log, "asked for component details (returning 0 details)";
+        let num_details =
+            helpers::num_component_details(&self.components, component);
+        debug!(
+            &self.log, "asked for component details";
             "sender" => %sender,
             "port" => ?port,
             "component" => ?component,
+            "num_details" => ?num_details,
         );
-        Ok(0)
+        num_details
     }
 
     fn component_details(
@@ -951,9 +955,7 @@ impl SpHandler for Handler {
         component: SpComponent,
         index: BoundsChecked,
     ) -> ComponentDetails {
-        // We return 0 for all components, so we should never be called (`index`
-        // would have to have been bounds checked to live in 0..0).
-        unreachable!("asked for {component:?} details index {index:?}")
+        helpers::component_details(&self.components, component, index)
     }
 
     fn component_clear_status(
@@ -6,7 +6,10 @@ use anyhow::{Context, Result};
 use camino::{Utf8Path, Utf8PathBuf};
 use chrono::{DateTime, Utc};
 
-use crate::{AddArtifact, Key, OmicronRepo};
+use crate::{
+    AddArtifact, Key, OmicronRepo, OmicronRepoEditor, RootKeys, RootRole,
+    UnverifiedRepo,
+};
 
 use super::ArtifactManifest;
 
@@ -16,6 +19,7 @@ pub struct OmicronRepoAssembler {
     log: slog::Logger,
     manifest: ArtifactManifest,
     build_dir: Option<Utf8PathBuf>,
+    roots: Option<Vec<RootRole>>,
     keys: Vec<Key>,
     expiry: DateTime<Utc>,
     output_path: Utf8PathBuf,
@@ -33,6 +37,7 @@ impl OmicronRepoAssembler {
             log: log.new(slog::o!("component" => "OmicronRepoAssembler")),
             manifest,
             build_dir: None,
+            roots: None,
             keys,
             expiry,
             output_path,
@@ -44,6 +49,17 @@ impl OmicronRepoAssembler {
         self
     }
 
+    /// Signs the repository according to an existing chain of root roles
+    /// rather than generating a root that trusts each of the keys.
+    ///
+    /// If the keys don't meet the latest root's threshold, the archive is
+    /// written anyway, and other key holders must countersign it (see
+    /// [`UnverifiedRepo::sign`]) before it can be used.
+    pub fn set_roots(&mut self, roots: Vec<RootRole>) -> &mut Self {
+        self.roots = Some(roots);
+        self
+    }
+
     pub async fn build(&self) -> Result<()> {
         let (build_dir, is_temp) = match &self.build_dir {
             Some(dir) => (dir.clone(), false),
@@ -93,15 +109,22 @@ impl OmicronRepoAssembler {
     }
 
     async fn build_impl(&self, build_dir: &Utf8Path) -> Result<()> {
-        let mut repository = OmicronRepo::initialize(
-            &self.log,
-            build_dir,
+        let roots = match &self.roots {
+            Some(roots) => roots.clone(),
+            None => vec![
+                RootRole::generate(
+                    &RootKeys::any_of(&self.keys)?,
+                    &self.keys,
+                    self.expiry,
+                )
+                .await?,
+            ],
+        };
+        let mut repository = OmicronRepoEditor::initialize(
+            build_dir.to_owned(),
+            &roots,
             self.manifest.system_version.clone(),
-            self.keys.clone(),
-            self.expiry,
         )
-        .await?
-        .into_editor()
         .await?;
 
         // Add all the artifacts.
@@ -122,6 +145,24 @@ impl OmicronRepoAssembler {
         // Write out the repository.
         repository.sign_and_finish(self.keys.clone(), self.expiry).await?;
 
+        // If the targets role needs more signatures than we have keys for,
+        // the repository can't be loaded yet. Archive it as-is for the other
+        // key holders to countersign.
+        let unverified = UnverifiedRepo::load(build_dir)?;
+        if !unverified.is_fully_signed() {
+            for count in unverified.signature_status() {
+                slog::warn!(self.log, "{count}");
+            }
+            slog::warn!(
+                self.log,
+                "repository needs signatures from other key holders \
+                 before it can be used"
+            );
+            return unverified
+                .archive(&self.output_path)
+                .context("error archiving repository");
+        }
+
         // Now reopen the repository to archive it into a zip file.
         let repo2 = OmicronRepo::load_untrusted(&self.log, build_dir)
             .await
@@ -0,0 +1,221 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::OmicronRepo;
+use anyhow::Result;
+use omicron_common::{
+    api::external::SemverVersion,
+    update::{ArtifactHash, ArtifactKind},
+};
+use std::collections::BTreeMap;
+
+/// An artifact as compared by [`RepoDiff`].
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct DiffArtifact {
+    pub kind: ArtifactKind,
+    pub name: String,
+    pub version: SemverVersion,
+    pub hash: ArtifactHash,
+}
+
+/// An artifact whose version or contents differ between two repositories.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ChangedArtifact {
+    pub old: DiffArtifact,
+    pub new: DiffArtifact,
+}
+
+/// The differences between the artifacts of two repositories, as returned by
+/// [`RepoDiff::new`].
+///
+/// Artifacts are matched up by kind and name. An artifact present in both
+/// repositories is changed if its version or hash differs.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct RepoDiff {
+    /// The old and new system versions, if they differ.
+    pub system_version: Option<(SemverVersion, SemverVersion)>,
+    pub added: Vec<DiffArtifact>,
+    pub removed: Vec<DiffArtifact>,
+    pub changed: Vec<ChangedArtifact>,
+    /// The number of artifacts that are the same in both repositories.
+    pub unchanged: usize,
+}
+
+impl RepoDiff {
+    /// Compares the artifacts in `old` to the artifacts in `new`.
+    pub async fn new(old: &OmicronRepo, new: &OmicronRepo) -> Result<Self> {
+        let (old_version, old_artifacts) = diff_artifacts(old).await?;
+        let (new_version, new_artifacts) = diff_artifacts(new).await?;
+        Ok(Self::from_artifacts(
+            old_version,
+            old_artifacts,
+            new_version,
+            new_artifacts,
+        ))
+    }
+
+    /// Returns true if the two repositories have the same system version and
+    /// artifacts.
+    pub fn is_empty(&self) -> bool {
+        self.system_version.is_none()
+            && self.added.is_empty()
+            && self.removed.is_empty()
+            && self.changed.is_empty()
+    }
+
+    fn from_artifacts(
+        old_version: SemverVersion,
+        old_artifacts: Vec<DiffArtifact>,
+        new_version: SemverVersion,
+        new_artifacts: Vec<DiffArtifact>,
+    ) -> Self {
+        let mut diff = Self::default();
+        if old_version != new_version {
+            diff.system_version = Some((old_version, new_version));
+        }
+
+        let mut old_by_key = group_by_kind_and_name(old_artifacts);
+        for (key, mut new) in group_by_kind_and_name(new_artifacts) {
+            let mut old = old_by_key.remove(&key).unwrap_or_default();
+
+            // Artifacts that are identical in both repositories are unchanged.
+            let before = new.len();
+            new.retain(|artifact| {
+                match old.iter().position(|a| a == artifact) {
+                    Some(index) => {
+                        old.remove(index);
+                        false
+                    }
+                    None => true,
+                }
+            });
+            diff.unchanged += before - new.len();
+
+            // If exactly one artifact with this kind and name is left on each
+            // side, it changed; otherwise there's no way to pair them up, so
+            // treat them as removed and added.
+            if old.len() == 1 && new.len() == 1 {
+                diff.changed.push(ChangedArtifact {
+                    old: old.pop().unwrap(),
+                    new: new.pop().unwrap(),
+                });
+            } else {
+                diff.removed.extend(old);
+                diff.added.extend(new);
+            }
+        }
+        diff.removed.extend(old_by_key.into_values().flatten());
+
+        diff.added.sort();
+        diff.removed.sort();
+        diff.changed.sort_by(|a, b| a.new.cmp(&b.new));
+        diff
+    }
+}
+
+async fn diff_artifacts(
+    repo: &OmicronRepo,
+) -> Result<(SemverVersion, Vec<DiffArtifact>)> {
+    let document = repo.read_artifacts().await?;
+    let artifacts = document
+        .artifacts
+        .iter()
+        .map(|artifact| {
+            Ok(DiffArtifact {
+                kind: artifact.kind.clone(),
+                name: artifact.name.clone(),
+                version: artifact.version.clone(),
+                hash: repo.artifact_hash(artifact)?,
+            })
+        })
+        .collect::<Result<_>>()?;
+    Ok((document.system_version, artifacts))
+}
+
+fn group_by_kind_and_name(
+    artifacts: Vec<DiffArtifact>,
+) -> BTreeMap<(ArtifactKind, String), Vec<DiffArtifact>> {
+    let mut by_key = BTreeMap::<_, Vec<_>>::new();
+    for artifact in artifacts {
+        by_key
+            .entry((artifact.kind.clone(), artifact.name.clone()))
+            .or_default()
+            .push(artifact);
+    }
+    by_key
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn artifact(
+        kind: &str,
+        name: &str,
+        version: &str,
+        hash: u8,
+    ) -> DiffArtifact {
+        DiffArtifact {
+            kind: kind.parse().unwrap(),
+            name: name.to_owned(),
+            version: version.parse().unwrap(),
+            hash: ArtifactHash([hash; 32]),
+        }
+    }
+
+    #[test]
+    fn diff_matches_by_kind_and_name() {
+        let old = vec![
+            artifact("gimlet_sp", "gimlet-c", "1.0.0", 1),
+            artifact("gimlet_sp", "gimlet-d", "1.0.0", 2),
+            artifact("host", "host", "1.0.0", 3),
+            artifact("trampoline", "trampoline", "1.0.0", 4),
+        ];
+        let new = vec![
+            // unchanged
+            artifact("gimlet_sp", "gimlet-c", "1.0.0", 1),
+            // changed version
+            artifact("gimlet_sp", "gimlet-d", "2.0.0", 5),
+            // changed contents only
+            artifact("host", "host", "1.0.0", 6),
+            // added
+            artifact("control_plane", "control-plane", "2.0.0", 7),
+        ];
+
+        let diff = RepoDiff::from_artifacts(
+            "1.0.0".parse().unwrap(),
+            old,
+            "2.0.0".parse().unwrap(),
+            new,
+        );
+        assert_eq!(
+            diff,
+            RepoDiff {
+                system_version: Some((
+                    "1.0.0".parse().unwrap(),
+                    "2.0.0".parse().unwrap()
+                )),
+                added: vec![artifact(
+                    "control_plane",
+                    "control-plane",
+                    "2.0.0",
+                    7
+                )],
+                removed: vec![artifact("trampoline", "trampoline", "1.0.0", 4)],
+                changed: vec![
+                    ChangedArtifact {
+                        old: artifact("gimlet_sp", "gimlet-d", "1.0.0", 2),
+                        new: artifact("gimlet_sp", "gimlet-d", "2.0.0", 5),
+                    },
+                    ChangedArtifact {
+                        old: artifact("host", "host", "1.0.0", 3),
+                        new: artifact("host", "host", "1.0.0", 6),
+                    },
+                ],
+                unchanged: 1,
+            }
+        );
+        assert!(!diff.is_empty());
+    }
+}
@@ -2,11 +2,14 @@ use anyhow::{bail, Result};
 use hex::FromHex;
 use rand::{rngs::OsRng, RngCore};
 use ring::rand::SecureRandom;
-use ring::signature::Ed25519KeyPair;
+use ring::signature::{Ed25519KeyPair, KeyPair};
+use std::collections::HashMap;
 use std::fmt::Display;
 use std::str::FromStr;
 use tough::async_trait;
 use tough::key_source::KeySource;
+use tough::schema::decoded::{Decoded, Hex};
+use tough::schema::key::{Ed25519Key, Ed25519Scheme};
 use tough::sign::{Sign, SignKeyPair};
 
 pub(crate) fn boxed_keys(keys: Vec<Key>) -> Vec<Box<dyn KeySource>> {
@@ -29,6 +32,23 @@ impl Key {
         Key::Ed25519(key)
     }
 
+    /// Returns the public half of this key, which can be shared with others
+    /// to include in a root role.
+    pub fn public_key(&self) -> PublicKey {
+        match self {
+            Key::Ed25519(key) => {
+                let pair = Ed25519KeyPair::from_seed_unchecked(key)
+                    .expect("ed25519 key length mismatch");
+                PublicKey::Ed25519(
+                    pair.public_key()
+                        .as_ref()
+                        .try_into()
+                        .expect("ed25519 public key length mismatch"),
+                )
+            }
+        }
+    }
+
     pub(crate) fn as_sign(&self) -> SignKeyPair {
         match self {
             Key::Ed25519(key) => SignKeyPair::ED25519(
@@ -39,6 +59,35 @@ impl Key {
     }
 }
 
+/// The public half of a [`Key`].
+///
+/// Root roles are built from public keys, so that the holders of offline keys
+/// don't need to hand over their private keys to be included in one.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PublicKey {
+    Ed25519([u8; 32]),
+}
+
+impl PublicKey {
+    pub(crate) fn tuf_key(&self) -> tough::schema::key::Key {
+        match self {
+            PublicKey::Ed25519(key) => tough::schema::key::Key::Ed25519 {
+                keyval: Ed25519Key {
+                    public: key.to_vec().into(),
+                    _extra: HashMap::new(),
+                },
+                scheme: Ed25519Scheme::Ed25519,
+                _extra: HashMap::new(),
+            },
+        }
+    }
+
+    /// Returns the TUF key ID of this key.
+    pub(crate) fn key_id(&self) -> Result<Decoded<Hex>> {
+        Ok(self.tuf_key().key_id()?)
+    }
+}
+
 #[async_trait]
 impl Sign for Key {
     fn tuf_key(&self) -> tough::schema::key::Key {
@@ -95,11 +144,36 @@ impl Display for Key {
     }
 }
 
+impl FromStr for PublicKey {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<PublicKey> {
+        match s.split_once(':') {
+            Some(("ed25519", hex)) => {
+                Ok(PublicKey::Ed25519(FromHex::from_hex(hex)?))
+            }
+            Some((kind, _)) => bail!("Invalid public key kind: {}", kind),
+            None => bail!("Invalid public key (format is `kind:data`)"),
+        }
+    }
+}
+
+impl Display for PublicKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PublicKey::Ed25519(key) => {
+                write!(f, "ed25519:{}", hex::encode(key))
+            }
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::Key;
+    use super::{Key, PublicKey};
     use ring::signature::Ed25519KeyPair;
     use std::str::FromStr;
+    use tough::sign::Sign;
 
     #[test]
     fn test_from_str() {
@@ -110,4 +184,21 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_public_key() {
+        let key = Key::generate_ed25519();
+        let public_key = key.public_key();
+        assert_eq!(
+            public_key.to_string().parse::<PublicKey>().unwrap(),
+            public_key
+        );
+        // The public key must be the one tough derives from the private key,
+        // or roles signed by `key` won't verify against roots built from
+        // `public_key`.
+        assert_eq!(
+            public_key.key_id().unwrap(),
+            key.as_sign().tuf_key().key_id().unwrap()
+        );
+    }
 }
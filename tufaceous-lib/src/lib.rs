@@ -5,13 +5,20 @@
 mod archive;
 mod artifact;
 pub mod assemble;
+mod diff;
 mod key;
 pub mod oxide_metadata;
 mod repository;
 mod root;
 mod target;
+mod unverified;
+mod verify;
 
 pub use archive::*;
 pub use artifact::*;
+pub use diff::*;
 pub use key::*;
 pub use repository::*;
+pub use root::*;
+pub use unverified::*;
+pub use verify::*;
@@ -2,7 +2,10 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
-use crate::{key::Key, target::TargetWriter, AddArtifact, ArchiveBuilder};
+use crate::{
+    key::Key, target::TargetWriter, AddArtifact, ArchiveBuilder, RootKeys,
+    RootRole,
+};
 use anyhow::{anyhow, bail, Context, Result};
 use buf_list::BufList;
 use camino::{Utf8Path, Utf8PathBuf};
@@ -11,13 +14,12 @@ use fs_err::{self as fs};
 use futures::TryStreamExt;
 use omicron_common::{
     api::external::SemverVersion,
-    update::{Artifact, ArtifactsDocument},
+    update::{Artifact, ArtifactHash, ArtifactsDocument},
 };
 use std::{collections::BTreeSet, num::NonZeroU64};
 use tough::{
-    editor::{signed::SignedRole, RepositoryEditor},
-    schema::{Root, Target},
-    ExpirationEnforcement, Repository, RepositoryLoader, TargetName,
+    editor::RepositoryEditor, schema::Target, ExpirationEnforcement,
+    Repository, RepositoryLoader, TargetName,
 };
 use url::Url;
 
@@ -30,6 +32,9 @@ pub struct OmicronRepo {
 
 impl OmicronRepo {
     /// Initializes a new repository at the given path, writing it to disk.
+    ///
+    /// The repository's root role trusts each of `keys` to sign every role on
+    /// its own.
     pub async fn initialize(
         log: &slog::Logger,
         repo_path: &Utf8Path,
@@ -37,10 +42,36 @@ impl OmicronRepo {
         keys: Vec<Key>,
         expiry: DateTime<Utc>,
     ) -> Result<Self> {
-        let root = crate::root::new_root(keys.clone(), expiry).await?;
+        let root = RootRole::generate(&RootKeys::any_of(&keys)?, &keys, expiry)
+            .await?;
+        Self::initialize_with_roots(
+            log,
+            repo_path,
+            system_version,
+            &[root],
+            keys,
+            expiry,
+        )
+        .await
+    }
+
+    /// Initializes a new repository at the given path with an existing chain
+    /// of root roles (see [`RootRole::verify_chain`]), writing it to disk.
+    ///
+    /// `keys` must meet the latest root's threshold for the targets role; use
+    /// [`OmicronRepoAssembler`](crate::assemble::OmicronRepoAssembler) to
+    /// build a repository that other key holders will countersign.
+    pub async fn initialize_with_roots(
+        log: &slog::Logger,
+        repo_path: &Utf8Path,
+        system_version: SemverVersion,
+        roots: &[RootRole],
+        keys: Vec<Key>,
+        expiry: DateTime<Utc>,
+    ) -> Result<Self> {
         let editor = OmicronRepoEditor::initialize(
             repo_path.to_owned(),
-            root,
+            roots,
             system_version,
         )
         .await?;
@@ -50,7 +81,7 @@ impl OmicronRepo {
             .await
             .context("error signing new repository")?;
 
-        // In theory we "trust" the key we just used to sign this repository,
+        // In theory we "trust" the root we just used to sign this repository,
         // but the code path is equivalent to `load_untrusted`.
         Self::load_untrusted(log, repo_path).await
     }
@@ -63,8 +94,7 @@ impl OmicronRepo {
         log: &slog::Logger,
         repo_path: &Utf8Path,
     ) -> Result<Self> {
-        Self::load_untrusted_impl(log, repo_path, ExpirationEnforcement::Safe)
-            .await
+        Self::load_impl(log, repo_path, None, ExpirationEnforcement::Safe).await
     }
 
     /// Loads a repository from the given path, ignoring expiration.
@@ -77,21 +107,47 @@ impl OmicronRepo {
         log: &slog::Logger,
         repo_path: &Utf8Path,
     ) -> Result<Self> {
-        Self::load_untrusted_impl(log, repo_path, ExpirationEnforcement::Unsafe)
+        Self::load_impl(log, repo_path, None, ExpirationEnforcement::Unsafe)
             .await
     }
 
-    async fn load_untrusted_impl(
+    /// Loads a repository from the given path, verifying its metadata against
+    /// `trusted_root` (the contents of a `root.json`) rather than the root
+    /// role stored in the repository, and ignoring expiration.
+    ///
+    /// Expiration is ignored so that callers can report on it themselves; see
+    /// [`Self::verify`].
+    pub async fn load_trusted_ignore_expiration(
         log: &slog::Logger,
         repo_path: &Utf8Path,
+        trusted_root: &[u8],
+    ) -> Result<Self> {
+        Self::load_impl(
+            log,
+            repo_path,
+            Some(trusted_root),
+            ExpirationEnforcement::Unsafe,
+        )
+        .await
+    }
+
+    async fn load_impl(
+        log: &slog::Logger,
+        repo_path: &Utf8Path,
+        trusted_root: Option<&[u8]>,
         exp: ExpirationEnforcement,
     ) -> Result<Self> {
         let log = log.new(slog::o!("component" => "OmicronRepo"));
         let repo_path = repo_path.canonicalize_utf8()?;
-        let root_json = repo_path.join("metadata").join("1.root.json");
-        let root = tokio::fs::read(&root_json)
-            .await
-            .with_context(|| format!("error reading from {root_json}"))?;
+        let root = match trusted_root {
+            Some(root) => root.to_vec(),
+            None => {
+                let root_json = repo_path.join("metadata").join("1.root.json");
+                tokio::fs::read(&root_json).await.with_context(|| {
+                    format!("error reading from {root_json}")
+                })?
+            }
+        };
 
         let repo = RepositoryLoader::new(
             &root,
@@ -132,6 +188,30 @@ impl OmicronRepo {
             .context("error deserializing artifacts.json")
     }
 
+    /// Returns the SHA-256 hash of the target backing `artifact`, as recorded
+    /// in the signed targets role.
+    pub fn artifact_hash(&self, artifact: &Artifact) -> Result<ArtifactHash> {
+        let target_name = TargetName::try_from(artifact.target.as_str())
+            .with_context(|| {
+                format!("invalid target name `{}`", artifact.target)
+            })?;
+        let target =
+            self.repo.targets().signed.find_target(&target_name).with_context(
+                || format!("target `{}` not found", artifact.target),
+            )?;
+        let hash: [u8; 32] =
+            target.hashes.sha256.clone().into_vec().try_into().map_err(
+                |hash: Vec<u8>| {
+                    anyhow!(
+                        "target `{}` has a {}-byte SHA-256 hash",
+                        artifact.target,
+                        hash.len()
+                    )
+                },
+            )?;
+        Ok(ArtifactHash(hash))
+    }
+
     /// Archives the repository to the given path as a zip file.
     ///
     /// ## Why zip and not tar?
@@ -160,11 +240,7 @@ impl OmicronRepo {
             let entry =
                 entry.context("error reading entry from {metadata_dir}")?;
             let file_name = entry.file_name();
-            if file_name.ends_with(".root.json")
-                || file_name == "timestamp.json"
-                || file_name.ends_with(".snapshot.json")
-                || file_name.ends_with(".targets.json")
-            {
+            if is_metadata_file(file_name) {
                 // This is a valid metadata file.
                 builder.write_file(
                     entry.path(),
@@ -201,16 +277,42 @@ impl OmicronRepo {
     /// digest and the filename.
     ///
     /// Adapted from tough's source.
-    fn target_filename(&self, target: &Target, name: &TargetName) -> String {
-        let sha256 = &target.hashes.sha256.clone().into_vec();
-        if self.repo.root().signed.consistent_snapshot {
-            format!("{}.{}", hex::encode(sha256), name.resolved())
-        } else {
-            name.resolved().to_owned()
-        }
+    pub(crate) fn target_filename(
+        &self,
+        target: &Target,
+        name: &TargetName,
+    ) -> String {
+        target_filename(
+            self.repo.root().signed.consistent_snapshot,
+            target,
+            name,
+        )
+    }
+}
+
+/// Returns the name of the file in the `targets` directory holding `target`.
+pub(crate) fn target_filename(
+    consistent_snapshot: bool,
+    target: &Target,
+    name: &TargetName,
+) -> String {
+    let sha256 = &target.hashes.sha256.clone().into_vec();
+    if consistent_snapshot {
+        format!("{}.{}", hex::encode(sha256), name.resolved())
+    } else {
+        name.resolved().to_owned()
     }
 }
 
+/// Returns true if `file_name` is a TUF metadata file that belongs in an
+/// archive.
+pub(crate) fn is_metadata_file(file_name: &str) -> bool {
+    file_name.ends_with(".root.json")
+        || file_name == "timestamp.json"
+        || file_name.ends_with(".snapshot.json")
+        || file_name.ends_with(".targets.json")
+}
+
 /// An [`OmicronRepo`] than can be edited.
 ///
 /// Created by [`OmicronRepo::into_editor`].
@@ -253,19 +355,31 @@ impl OmicronRepoEditor {
         })
     }
 
-    async fn initialize(
+    /// Creates an empty repository at `repo_path` whose metadata will be
+    /// signed according to the last of `roots`.
+    pub(crate) async fn initialize(
         repo_path: Utf8PathBuf,
-        root: SignedRole<Root>,
+        roots: &[RootRole],
         system_version: SemverVersion,
     ) -> Result<Self> {
+        RootRole::verify_chain(roots)?;
+
         let metadata_dir = repo_path.join("metadata");
         let targets_dir = repo_path.join("targets");
-        let root_path = metadata_dir
-            .join(format!("{}.root.json", root.signed().signed.version));
 
         fs::create_dir_all(&metadata_dir)?;
         fs::create_dir_all(&targets_dir)?;
-        fs::write(&root_path, root.buffer())?;
+
+        // TUF clients start from the first root they trust and walk forward
+        // through each later version, so every version has to be present.
+        let mut root_path = None;
+        for root in roots {
+            let path =
+                metadata_dir.join(format!("{}.root.json", root.version()));
+            root.write(&path)?;
+            root_path = Some(path);
+        }
+        let root_path = root_path.expect("verify_chain checked for a root");
 
         let editor = RepositoryEditor::new(&root_path).await?;
 
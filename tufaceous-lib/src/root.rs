@@ -1,52 +1,442 @@
-use crate::key::Key;
-use anyhow::Result;
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::key::{Key, PublicKey};
+use anyhow::{ensure, Context, Result};
+use camino::Utf8Path;
 use chrono::{DateTime, Utc};
 use ring::rand::SystemRandom;
-use std::collections::HashMap;
+use serde::Serialize;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt;
 use std::num::NonZeroU64;
 use tough::editor::signed::SignedRole;
-use tough::schema::{KeyHolder, RoleKeys, RoleType, Root};
-use tough::sign::Sign;
-
-pub(crate) async fn new_root(
-    keys: Vec<Key>,
-    expires: DateTime<Utc>,
-) -> Result<SignedRole<Root>> {
-    let mut root = Root {
-        spec_version: "1.0.0".to_string(),
-        consistent_snapshot: true,
-        version: NonZeroU64::new(1).unwrap(),
-        expires,
-        keys: HashMap::new(),
-        roles: HashMap::new(),
-        _extra: HashMap::new(),
-    };
-    for key in &keys {
-        let key = key.as_sign().tuf_key();
-        root.keys.insert(key.key_id()?, key);
-    }
-    for kind in [
-        RoleType::Root,
-        RoleType::Snapshot,
-        RoleType::Targets,
-        RoleType::Timestamp,
-    ] {
-        root.roles.insert(
-            kind,
-            RoleKeys {
-                keyids: root.keys.keys().cloned().collect(),
-                threshold: NonZeroU64::new(1).unwrap(),
-                _extra: HashMap::new(),
-            },
+use tough::schema::{
+    KeyHolder, Role, RoleKeys, RoleType, Root, Signature, Signed,
+};
+
+/// The keys trusted by a root role, and how many of them must sign.
+#[derive(Clone, Debug)]
+pub struct RootKeys {
+    keys: BTreeSet<PublicKey>,
+    threshold: NonZeroU64,
+}
+
+impl RootKeys {
+    /// Trusts `keys`, requiring `threshold` of them to sign the root and
+    /// targets roles.
+    ///
+    /// Any one of `keys` can sign the snapshot and timestamp roles. Those
+    /// roles only record the hashes of other metadata, so they have to be
+    /// signed again each time the targets role is countersigned; requiring
+    /// more than one signature would make countersigning impossible.
+    pub fn new(
+        keys: impl IntoIterator<Item = PublicKey>,
+        threshold: NonZeroU64,
+    ) -> Result<Self> {
+        let keys: BTreeSet<_> = keys.into_iter().collect();
+        ensure!(
+            threshold.get() <= keys.len() as u64,
+            "threshold {threshold} is greater than the number of keys ({})",
+            keys.len()
+        );
+        Ok(Self { keys, threshold })
+    }
+
+    /// Trusts each of `keys` to sign every role on its own.
+    pub fn any_of(keys: &[Key]) -> Result<Self> {
+        Self::new(keys.iter().map(Key::public_key), NonZeroU64::new(1).unwrap())
+    }
+
+    fn to_root(
+        &self,
+        version: NonZeroU64,
+        expires: DateTime<Utc>,
+    ) -> Result<Root> {
+        let mut root = Root {
+            spec_version: "1.0.0".to_string(),
+            consistent_snapshot: true,
+            version,
+            expires,
+            keys: HashMap::new(),
+            roles: HashMap::new(),
+            _extra: HashMap::new(),
+        };
+        for key in &self.keys {
+            root.keys.insert(key.key_id()?, key.tuf_key());
+        }
+        for (kind, threshold) in [
+            (RoleType::Root, self.threshold),
+            (RoleType::Snapshot, NonZeroU64::new(1).unwrap()),
+            (RoleType::Targets, self.threshold),
+            (RoleType::Timestamp, NonZeroU64::new(1).unwrap()),
+        ] {
+            root.roles.insert(
+                kind,
+                RoleKeys {
+                    keyids: root.keys.keys().cloned().collect(),
+                    threshold,
+                    _extra: HashMap::new(),
+                },
+            );
+        }
+        Ok(root)
+    }
+}
+
+/// A version of a root role, along with the signatures it has collected so
+/// far.
+///
+/// A root role with fewer signatures than its threshold can be passed between
+/// key holders, each adding their own with [`RootRole::sign`].
+#[derive(Clone, Debug)]
+pub struct RootRole {
+    signed: Signed<Root>,
+}
+
+impl RootRole {
+    /// Creates version 1 of a root role trusting `root_keys`, signed by
+    /// those of `signing_keys` that it trusts.
+    pub async fn generate(
+        root_keys: &RootKeys,
+        signing_keys: &[Key],
+        expires: DateTime<Utc>,
+    ) -> Result<Self> {
+        let root = root_keys.to_root(NonZeroU64::new(1).unwrap(), expires)?;
+        let mut role =
+            Self { signed: Signed { signed: root, signatures: vec![] } };
+        role.sign(None, signing_keys).await?;
+        Ok(role)
+    }
+
+    /// Creates the next version of this root role, trusting `root_keys`
+    /// instead.
+    ///
+    /// Per the TUF specification, clients only accept the new version if it's
+    /// signed by a threshold of both this version's keys and its own. The new
+    /// version is signed by those of `signing_keys` that either one trusts;
+    /// other key holders can add their signatures with [`RootRole::sign`],
+    /// passing `self` as the previous version.
+    pub async fn rotate(
+        &self,
+        root_keys: &RootKeys,
+        signing_keys: &[Key],
+        expires: DateTime<Utc>,
+    ) -> Result<Self> {
+        let version = self
+            .version()
+            .checked_add(1)
+            .context("root version would overflow")?;
+        let root = root_keys.to_root(version, expires)?;
+        let mut role =
+            Self { signed: Signed { signed: root, signatures: vec![] } };
+        role.sign(Some(self), signing_keys).await?;
+        Ok(role)
+    }
+
+    /// Reads a root role from a `root.json` file.
+    pub fn from_path(path: &Utf8Path) -> Result<Self> {
+        let data = fs_err::read(path)?;
+        Self::from_slice(&data)
+            .with_context(|| format!("error reading root role from {path}"))
+    }
+
+    /// Deserializes a root role from the contents of a `root.json` file.
+    pub fn from_slice(data: &[u8]) -> Result<Self> {
+        let signed = serde_json::from_slice(data)
+            .context("error deserializing root role")?;
+        Ok(Self { signed })
+    }
+
+    /// Serializes this root role as the contents of a `root.json` file.
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        signed_bytes(&self.signed)
+    }
+
+    /// Writes this root role to a `root.json` file.
+    pub fn write(&self, path: &Utf8Path) -> Result<()> {
+        fs_err::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    pub fn version(&self) -> NonZeroU64 {
+        self.signed.signed.version
+    }
+
+    pub fn expires(&self) -> DateTime<Utc> {
+        self.signed.signed.expires
+    }
+
+    /// Adds signatures from those of `keys` that this version trusts, or that
+    /// `previous` (the version before this one, if any) trusts.
+    ///
+    /// Keys that neither version trusts are ignored, as are keys that have
+    /// already signed this version.
+    pub async fn sign(
+        &mut self,
+        previous: Option<&RootRole>,
+        keys: &[Key],
+    ) -> Result<()> {
+        let mut signatures =
+            sign_role(&self.signed.signed, &self.signed.signed, keys).await?;
+        if let Some(previous) = previous {
+            self.check_previous(previous)?;
+            signatures.extend(
+                sign_role(&self.signed.signed, &previous.signed.signed, keys)
+                    .await?,
+            );
+        }
+        add_signatures(&mut self.signed.signatures, signatures);
+        Ok(())
+    }
+
+    /// Returns how many of the signatures this version requires it has: from
+    /// its own keys and, if `previous` is given, from the previous version's
+    /// keys.
+    ///
+    /// This only counts signatures by trusted keys; it doesn't check that
+    /// they're valid. Use [`RootRole::verify`] for that.
+    pub fn signature_status(
+        &self,
+        previous: Option<&RootRole>,
+    ) -> Vec<SignatureCount> {
+        let mut status = vec![SignatureCount::new(
+            format!("root version {}", self.version()),
+            &self.signed,
+            &self.signed.signed,
+        )];
+        if let Some(previous) = previous {
+            status.push(SignatureCount::new(
+                format!(
+                    "root version {} (from version {})",
+                    self.version(),
+                    previous.version()
+                ),
+                &self.signed,
+                &previous.signed.signed,
+            ));
+        }
+        status
+    }
+
+    /// Checks that this version is signed by a threshold of its own keys and,
+    /// if `previous` is given, of the previous version's keys.
+    pub fn verify(&self, previous: Option<&RootRole>) -> Result<()> {
+        self.signed.signed.verify_role(&self.signed).with_context(|| {
+            format!(
+                "root version {} is not signed by enough of its own keys",
+                self.version()
+            )
+        })?;
+        if let Some(previous) = previous {
+            self.check_previous(previous)?;
+            previous.signed.signed.verify_role(&self.signed).with_context(
+                || {
+                    format!(
+                        "root version {} is not signed by enough keys from \
+                         version {}",
+                        self.version(),
+                        previous.version()
+                    )
+                },
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Checks that `roots` is a chain of root versions starting at version 1,
+    /// each fully signed by its own keys and those of the version before it.
+    pub fn verify_chain(roots: &[RootRole]) -> Result<()> {
+        let first = roots.first().context("no root roles provided")?;
+        ensure!(
+            first.version().get() == 1,
+            "the first root role must be version 1, not version {}",
+            first.version()
         );
+        first.verify(None)?;
+        for pair in roots.windows(2) {
+            pair[1].verify(Some(&pair[0]))?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn root(&self) -> &Root {
+        &self.signed.signed
+    }
+
+    fn check_previous(&self, previous: &RootRole) -> Result<()> {
+        ensure!(
+            previous.version().checked_add(1) == Some(self.version()),
+            "root version {} does not follow version {}",
+            self.version(),
+            previous.version()
+        );
+        Ok(())
+    }
+}
+
+/// The number of signatures a role has from the keys trusted to sign it,
+/// and the number it requires.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SignatureCount {
+    /// A description of the role and the keys being counted.
+    pub role: String,
+    pub signatures: usize,
+    pub threshold: NonZeroU64,
+}
+
+impl SignatureCount {
+    pub(crate) fn new<T: Role>(
+        role: String,
+        signed: &Signed<T>,
+        trusted_by: &Root,
+    ) -> Self {
+        let (signatures, threshold) = match trusted_by.roles.get(&T::TYPE) {
+            Some(role_keys) => {
+                let signed_by: HashSet<_> = signed
+                    .signatures
+                    .iter()
+                    .map(|signature| &signature.keyid)
+                    .filter(|keyid| role_keys.keyids.contains(keyid))
+                    .collect();
+                (signed_by.len(), role_keys.threshold)
+            }
+            // A root without this role can't be satisfied by any number of
+            // signatures.
+            None => (0, NonZeroU64::MAX),
+        };
+        Self { role, signatures, threshold }
+    }
+
+    /// Returns true if the role has as many signatures as it requires.
+    pub fn is_complete(&self) -> bool {
+        self.signatures as u64 >= self.threshold.get()
+    }
+}
+
+impl fmt::Display for SignatureCount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}: {} of {} required signatures",
+            self.role, self.signatures, self.threshold
+        )
+    }
+}
+
+/// Signs `role` with those of `keys` that `trusted_by` trusts to sign it,
+/// returning the signatures. Returns no signatures if none of `keys` are
+/// trusted.
+pub(crate) async fn sign_role<T: Role + Clone>(
+    role: &T,
+    trusted_by: &Root,
+    keys: &[Key],
+) -> Result<Vec<Signature>> {
+    let Some(role_keys) = trusted_by.roles.get(&T::TYPE) else {
+        return Ok(Vec::new());
+    };
+    let mut trusted_keys = Vec::new();
+    for key in keys {
+        if role_keys.keyids.contains(&key.public_key().key_id()?) {
+            trusted_keys.push(key.clone());
+        }
+    }
+    if trusted_keys.is_empty() {
+        return Ok(Vec::new());
     }
 
-    let keys = crate::key::boxed_keys(keys);
-    Ok(SignedRole::new(
-        root.clone(),
-        &KeyHolder::Root(root),
-        &keys,
+    let signed = SignedRole::new(
+        role.clone(),
+        &KeyHolder::Root(trusted_by.clone()),
+        &crate::key::boxed_keys(trusted_keys),
         &SystemRandom::new(),
     )
-    .await?)
+    .await?;
+    Ok(signed.signed().signatures.clone())
+}
+
+/// Adds each of `new` to `signatures`, unless the same key already signed.
+pub(crate) fn add_signatures(
+    signatures: &mut Vec<Signature>,
+    new: Vec<Signature>,
+) {
+    for signature in new {
+        if !signatures.iter().any(|s| s.keyid == signature.keyid) {
+            signatures.push(signature);
+        }
+    }
+}
+
+/// Serializes signed metadata the same way tough does when writing it out.
+pub(crate) fn signed_bytes<T: Serialize>(
+    signed: &Signed<T>,
+) -> Result<Vec<u8>> {
+    let mut data = serde_json::to_vec_pretty(signed)
+        .context("error serializing signed metadata")?;
+    data.push(b'\n');
+    Ok(data)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Days;
+
+    #[tokio::test]
+    async fn threshold_and_rotation() {
+        let [a, b, c, d] = [(); 4].map(|()| Key::generate_ed25519());
+        let expires = Utc::now() + Days::new(1);
+        let two_of_three = RootKeys::new(
+            [&a, &b, &c].map(Key::public_key),
+            NonZeroU64::new(2).unwrap(),
+        )
+        .unwrap();
+
+        // One signature isn't enough; countersigning with a second is.
+        let mut v1 = RootRole::generate(&two_of_three, &[a.clone()], expires)
+            .await
+            .unwrap();
+        assert!(!v1.signature_status(None)[0].is_complete());
+        v1.verify(None).unwrap_err();
+        // Signing again with the same key doesn't count twice.
+        v1.sign(None, &[a.clone()]).await.unwrap();
+        v1.verify(None).unwrap_err();
+        v1.sign(None, &[b.clone()]).await.unwrap();
+        assert!(v1.signature_status(None)[0].is_complete());
+        RootRole::verify_chain(&[v1.clone()]).unwrap();
+
+        // Rotate c out for d. The new version must be signed by two of the
+        // old keys (a, b, c) and two of the new keys (a, b, d).
+        let rotated = RootKeys::new(
+            [&a, &b, &d].map(Key::public_key),
+            NonZeroU64::new(2).unwrap(),
+        )
+        .unwrap();
+        let mut v2 = v1
+            .rotate(&rotated, &[c.clone(), d.clone()], expires)
+            .await
+            .unwrap();
+        assert_eq!(v2.version().get(), 2);
+        let status = v2.signature_status(Some(&v1));
+        assert_eq!(
+            status.iter().map(|count| count.signatures).collect::<Vec<_>>(),
+            [1, 1]
+        );
+        RootRole::verify_chain(&[v1.clone(), v2.clone()]).unwrap_err();
+        v2.sign(Some(&v1), &[a.clone()]).await.unwrap();
+        RootRole::verify_chain(&[v1.clone(), v2.clone()]).unwrap();
+
+        // The serialized form round-trips.
+        let v2 = RootRole::from_slice(&v2.to_bytes().unwrap()).unwrap();
+        RootRole::verify_chain(&[v1.clone(), v2.clone()]).unwrap();
+
+        // Roots have to be chained in order.
+        RootRole::verify_chain(&[v2.clone()]).unwrap_err();
+        v1.verify(Some(&v2)).unwrap_err();
+
+        // A threshold can't exceed the number of keys.
+        RootKeys::new([a.public_key()], NonZeroU64::new(2).unwrap())
+            .unwrap_err();
+    }
 }
@@ -0,0 +1,334 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::{
+    repository::{is_metadata_file, target_filename},
+    root::{add_signatures, sign_role, signed_bytes},
+    ArchiveBuilder, Key, RootRole, SignatureCount,
+};
+use anyhow::{bail, ensure, Context, Result};
+use camino::{Utf8Path, Utf8PathBuf};
+use serde::de::DeserializeOwned;
+use sha2::{Digest, Sha256};
+use std::{collections::HashMap, num::NonZeroU64};
+use tough::schema::{Hashes, Signed, Snapshot, Targets, Timestamp};
+
+/// A repository on disk, read without checking its signatures.
+///
+/// This is how a repository that hasn't yet collected all the signatures its
+/// root requires is passed between key holders: each one adds their
+/// signatures with [`UnverifiedRepo::sign`]. Once it's fully signed, it can be
+/// loaded with [`OmicronRepo`](crate::OmicronRepo) like any other repository.
+#[derive(Debug)]
+pub struct UnverifiedRepo {
+    repo_path: Utf8PathBuf,
+    roots: Vec<RootRole>,
+    targets: Signed<Targets>,
+    snapshot: Signed<Snapshot>,
+    timestamp: Signed<Timestamp>,
+}
+
+impl UnverifiedRepo {
+    /// Reads the current metadata of the repository at `repo_path`.
+    pub fn load(repo_path: &Utf8Path) -> Result<Self> {
+        let metadata_dir = repo_path.join("metadata");
+
+        // Root versions are numbered consecutively from 1; read all of them.
+        let mut roots = Vec::new();
+        loop {
+            let path =
+                metadata_dir.join(format!("{}.root.json", roots.len() + 1));
+            if !path.exists() {
+                break;
+            }
+            let root = RootRole::from_path(&path)?;
+            ensure!(
+                root.version().get() == roots.len() as u64 + 1,
+                "{path} contains root version {}",
+                root.version()
+            );
+            roots.push(root);
+        }
+        if roots.is_empty() {
+            bail!("no root roles found in {metadata_dir}");
+        }
+
+        // Follow the timestamp role to the current snapshot and targets.
+        let consistent_snapshot = roots
+            .last()
+            .expect("roots is not empty")
+            .root()
+            .consistent_snapshot;
+        let timestamp: Signed<Timestamp> =
+            read_json(&metadata_dir.join("timestamp.json"))?;
+        let snapshot_version = timestamp
+            .signed
+            .meta
+            .get("snapshot.json")
+            .context("timestamp role does not refer to snapshot.json")?
+            .version;
+        let snapshot: Signed<Snapshot> =
+            read_json(&metadata_dir.join(metadata_filename(
+                consistent_snapshot,
+                snapshot_version,
+                "snapshot",
+            )))?;
+        let targets_version = snapshot
+            .signed
+            .meta
+            .get("targets.json")
+            .context("snapshot role does not refer to targets.json")?
+            .version;
+        let targets = read_json(&metadata_dir.join(metadata_filename(
+            consistent_snapshot,
+            targets_version,
+            "targets",
+        )))?;
+
+        Ok(Self {
+            repo_path: repo_path.to_owned(),
+            roots,
+            targets,
+            snapshot,
+            timestamp,
+        })
+    }
+
+    /// Returns how many of the signatures each role requires it has.
+    ///
+    /// This only counts signatures by trusted keys; it doesn't check that
+    /// they're valid. Loading the repository with
+    /// [`OmicronRepo`](crate::OmicronRepo) does that.
+    pub fn signature_status(&self) -> Vec<SignatureCount> {
+        let previous = self.roots.len().checked_sub(2).map(|i| &self.roots[i]);
+        let root = self.latest_root().root();
+        let mut status = self.latest_root().signature_status(previous);
+        status.extend([
+            SignatureCount::new("targets".to_owned(), &self.targets, root),
+            SignatureCount::new("snapshot".to_owned(), &self.snapshot, root),
+            SignatureCount::new("timestamp".to_owned(), &self.timestamp, root),
+        ]);
+        status
+    }
+
+    /// Returns true if every role has as many signatures as it requires.
+    pub fn is_fully_signed(&self) -> bool {
+        self.signature_status().iter().all(SignatureCount::is_complete)
+    }
+
+    /// Adds signatures from `keys` to the latest root role and the targets
+    /// role, and writes the result back to disk.
+    ///
+    /// Adding signatures changes the targets metadata, so the snapshot and
+    /// timestamp roles, which record its hash, are updated and signed again
+    /// with `keys`. At least one of `keys` must be trusted to sign them.
+    pub async fn sign(&mut self, keys: &[Key]) -> Result<()> {
+        let previous = match self.roots.len() {
+            1 => None,
+            n => Some(self.roots[n - 2].clone()),
+        };
+        let latest = self.roots.last_mut().expect("roots is not empty");
+        latest.sign(previous.as_ref(), keys).await?;
+        let root = latest.root().clone();
+
+        let signatures = sign_role(&self.targets.signed, &root, keys).await?;
+        add_signatures(&mut self.targets.signatures, signatures);
+        let targets = signed_bytes(&self.targets)?;
+
+        let meta = self
+            .snapshot
+            .signed
+            .meta
+            .get_mut("targets.json")
+            .context("snapshot role does not refer to targets.json")?;
+        meta.hashes = Some(sha256_hashes(&targets));
+        meta.length = Some(targets.len() as u64);
+        self.snapshot.signatures =
+            sign_role(&self.snapshot.signed, &root, keys).await?;
+        ensure!(
+            !self.snapshot.signatures.is_empty(),
+            "none of the provided keys are trusted to sign the snapshot role"
+        );
+        let snapshot = signed_bytes(&self.snapshot)?;
+
+        let meta = self
+            .timestamp
+            .signed
+            .meta
+            .get_mut("snapshot.json")
+            .context("timestamp role does not refer to snapshot.json")?;
+        meta.hashes = sha256_hashes(&snapshot);
+        meta.length = snapshot.len() as u64;
+        self.timestamp.signatures =
+            sign_role(&self.timestamp.signed, &root, keys).await?;
+        ensure!(
+            !self.timestamp.signatures.is_empty(),
+            "none of the provided keys are trusted to sign the timestamp role"
+        );
+        let timestamp = signed_bytes(&self.timestamp)?;
+
+        let metadata_dir = self.repo_path.join("metadata");
+        let latest = self.latest_root();
+        let consistent_snapshot = latest.root().consistent_snapshot;
+        latest.write(
+            &metadata_dir.join(format!("{}.root.json", latest.version())),
+        )?;
+        fs_err::write(
+            metadata_dir.join(metadata_filename(
+                consistent_snapshot,
+                self.targets.signed.version,
+                "targets",
+            )),
+            targets,
+        )?;
+        fs_err::write(
+            metadata_dir.join(metadata_filename(
+                consistent_snapshot,
+                self.snapshot.signed.version,
+                "snapshot",
+            )),
+            snapshot,
+        )?;
+        fs_err::write(metadata_dir.join("timestamp.json"), timestamp)?;
+        Ok(())
+    }
+
+    /// Archives the repository to the given path as a zip file, in the same
+    /// form as [`OmicronRepo::archive`](crate::OmicronRepo::archive).
+    pub fn archive(&self, output_path: &Utf8Path) -> Result<()> {
+        let mut builder = ArchiveBuilder::new(output_path.to_owned())?;
+
+        let metadata_dir = self.repo_path.join("metadata");
+        for entry in metadata_dir.read_dir_utf8().with_context(|| {
+            format!("error reading entries from {metadata_dir}")
+        })? {
+            let entry = entry.with_context(|| {
+                format!("error reading entry from {metadata_dir}")
+            })?;
+            if is_metadata_file(entry.file_name()) {
+                builder.write_file(
+                    entry.path(),
+                    &Utf8Path::new("metadata").join(entry.file_name()),
+                )?;
+            }
+        }
+
+        let consistent_snapshot = self.latest_root().root().consistent_snapshot;
+        for (name, target) in self.targets.signed.targets_iter() {
+            let target_filename =
+                target_filename(consistent_snapshot, target, name);
+            builder.write_file(
+                &self.repo_path.join("targets").join(&target_filename),
+                &Utf8Path::new("targets").join(&target_filename),
+            )?;
+        }
+
+        builder.finish()
+    }
+
+    fn latest_root(&self) -> &RootRole {
+        self.roots.last().expect("roots is not empty")
+    }
+}
+
+/// Returns the file name of the snapshot or targets metadata, which is
+/// prefixed with its version if the repository uses consistent snapshots.
+fn metadata_filename(
+    consistent_snapshot: bool,
+    version: NonZeroU64,
+    role: &str,
+) -> String {
+    if consistent_snapshot {
+        format!("{version}.{role}.json")
+    } else {
+        format!("{role}.json")
+    }
+}
+
+fn read_json<T: DeserializeOwned>(path: &Utf8Path) -> Result<T> {
+    let data = fs_err::read(path)?;
+    serde_json::from_slice(&data)
+        .with_context(|| format!("error deserializing {path}"))
+}
+
+fn sha256_hashes(data: &[u8]) -> Hashes {
+    Hashes {
+        sha256: Sha256::digest(data).to_vec().into(),
+        _extra: HashMap::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        AddArtifact, ArtifactSource, OmicronRepo, OmicronRepoEditor, RootKeys,
+    };
+    use camino_tempfile::Utf8TempDir;
+    use chrono::{Days, Utc};
+    use omicron_common::api::internal::nexus::KnownArtifactKind;
+    use omicron_test_utils::dev::test_setup_log;
+
+    #[tokio::test]
+    async fn countersign() {
+        let logctx = test_setup_log("countersign");
+        let tempdir = Utf8TempDir::new().unwrap();
+        let [a, b, c] = [(); 3].map(|()| Key::generate_ed25519());
+        let expiry = Utc::now() + Days::new(1);
+
+        let root_keys = RootKeys::new(
+            [&a, &b, &c].map(Key::public_key),
+            NonZeroU64::new(2).unwrap(),
+        )
+        .unwrap();
+        let root =
+            RootRole::generate(&root_keys, &[a.clone(), b.clone()], expiry)
+                .await
+                .unwrap();
+
+        // Build a repository signed only by `a`.
+        let mut editor = OmicronRepoEditor::initialize(
+            tempdir.path().to_owned(),
+            &[root],
+            "1.0.0".parse().unwrap(),
+        )
+        .await
+        .unwrap();
+        editor
+            .add_artifact(&AddArtifact::new(
+                KnownArtifactKind::GimletSp.into(),
+                "sp".to_owned(),
+                "1.0.0".parse().unwrap(),
+                ArtifactSource::Memory(
+                    std::iter::once(bytes::Bytes::from_static(b"sp")).collect(),
+                ),
+            ))
+            .unwrap();
+        editor.sign_and_finish(vec![a.clone()], expiry).await.unwrap();
+
+        let repo = UnverifiedRepo::load(tempdir.path()).unwrap();
+        assert!(!repo.is_fully_signed(), "{:?}", repo.signature_status());
+        OmicronRepo::load_untrusted(&logctx.log, tempdir.path())
+            .await
+            .expect_err("repository should be missing a signature");
+
+        // Countersigning with `c` completes the targets role, and the
+        // re-signed snapshot and timestamp roles still check out.
+        let mut repo = UnverifiedRepo::load(tempdir.path()).unwrap();
+        repo.sign(&[c.clone()]).await.unwrap();
+        assert!(repo.is_fully_signed(), "{:?}", repo.signature_status());
+        let repo = UnverifiedRepo::load(tempdir.path()).unwrap();
+        assert!(repo.is_fully_signed(), "{:?}", repo.signature_status());
+        let repo = OmicronRepo::load_untrusted(&logctx.log, tempdir.path())
+            .await
+            .unwrap();
+        assert_eq!(repo.read_artifacts().await.unwrap().artifacts.len(), 1);
+
+        // A key the root doesn't trust can't sign anything.
+        let mut repo = UnverifiedRepo::load(tempdir.path()).unwrap();
+        repo.sign(&[Key::generate_ed25519()]).await.unwrap_err();
+
+        logctx.cleanup_successful();
+    }
+}
@@ -0,0 +1,330 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use crate::{
+    ControlPlaneZoneImages, HostPhaseImages, OmicronRepo, RotArchives,
+};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use fs_err::File;
+use futures::TryStreamExt;
+use omicron_common::{
+    api::{external::SemverVersion, internal::nexus::KnownArtifactKind},
+    update::{Artifact, ArtifactHash},
+};
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    io::{self, BufReader},
+};
+use tough::TargetName;
+
+/// The result of [`OmicronRepo::verify`].
+#[derive(Clone, Debug)]
+pub struct VerifyReport {
+    /// The system version in `artifacts.json`.
+    pub system_version: SemverVersion,
+
+    /// The expiration time of each top-level role, keyed by role name.
+    pub expirations: BTreeMap<&'static str, DateTime<Utc>>,
+
+    /// The artifacts listed in `artifacts.json`, with the hashes of their
+    /// targets if they could be found.
+    pub artifacts: Vec<(Artifact, Option<ArtifactHash>)>,
+
+    /// Every problem found with the repository; the repository is valid if
+    /// this is empty.
+    pub problems: Vec<VerifyProblem>,
+}
+
+impl VerifyReport {
+    /// Returns true if no problems were found.
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// A problem found by [`OmicronRepo::verify`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum VerifyProblem {
+    /// A role expired before the time the repository was verified at.
+    Expired { role: &'static str, expires: DateTime<Utc> },
+
+    /// An artifact's target isn't in the signed targets role.
+    MissingTarget { artifact: Artifact },
+
+    /// More than one artifact has the same kind, name and version.
+    DuplicateArtifact { artifact: Artifact },
+
+    /// More than one artifact refers to the same target.
+    DuplicateTarget { target: String },
+
+    /// A target in the signed targets role isn't referred to by any artifact.
+    UnreferencedTarget { target: String },
+
+    /// A target's contents couldn't be read, or didn't match the length and
+    /// hash in the targets role.
+    InvalidTarget { target: String, error: String },
+
+    /// A composite artifact (e.g., a host OS image or RoT archive) doesn't
+    /// have the structure its kind requires.
+    InvalidComposite { artifact: Artifact, error: String },
+}
+
+impl fmt::Display for VerifyProblem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Expired { role, expires } => {
+                write!(f, "{role} role expired at {expires}")
+            }
+            Self::MissingTarget { artifact } => write!(
+                f,
+                "target `{}` for {} {} {} is not in the targets role",
+                artifact.target, artifact.kind, artifact.name, artifact.version,
+            ),
+            Self::DuplicateArtifact { artifact } => write!(
+                f,
+                "{} {} {} is listed more than once in artifacts.json",
+                artifact.kind, artifact.name, artifact.version,
+            ),
+            Self::DuplicateTarget { target } => {
+                write!(f, "target `{target}` is used by more than one artifact",)
+            }
+            Self::UnreferencedTarget { target } => write!(
+                f,
+                "target `{target}` is not referred to by artifacts.json",
+            ),
+            Self::InvalidTarget { target, error } => {
+                write!(f, "target `{target}` is invalid: {error}")
+            }
+            Self::InvalidComposite { artifact, error } => write!(
+                f,
+                "{} {} {} has an invalid structure: {error}",
+                artifact.kind, artifact.name, artifact.version,
+            ),
+        }
+    }
+}
+
+impl OmicronRepo {
+    /// Checks that this repository is well-formed and hasn't expired as of
+    /// `now`.
+    ///
+    /// Signatures are checked when the repository is loaded, so this checks
+    /// everything else: that no role has expired, that `artifacts.json` and
+    /// the targets role agree with each other, that every target matches its
+    /// length and hash, and that composite artifacts (host and trampoline OS
+    /// images, RoT archives and the control plane) have the structure their
+    /// kind requires.
+    ///
+    /// Problems with the repository are collected into the returned
+    /// [`VerifyReport`]; an error is only returned if the repository can't be
+    /// checked at all (e.g., `artifacts.json` is missing).
+    pub async fn verify(&self, now: DateTime<Utc>) -> Result<VerifyReport> {
+        let document = self.read_artifacts().await?;
+        let mut problems = Vec::new();
+
+        let repo = self.repo();
+        let expirations = BTreeMap::from([
+            ("root", repo.root().signed.expires),
+            ("snapshot", repo.snapshot().signed.expires),
+            ("targets", repo.targets().signed.expires),
+            ("timestamp", repo.timestamp().signed.expires),
+        ]);
+        for (&role, &expires) in &expirations {
+            if expires <= now {
+                problems.push(VerifyProblem::Expired { role, expires });
+            }
+        }
+
+        // Check that artifacts.json and the targets role agree.
+        let mut seen_ids = BTreeSet::new();
+        let mut seen_targets = BTreeSet::new();
+        let mut artifacts = Vec::new();
+        for artifact in &document.artifacts {
+            let id = (&artifact.kind, &artifact.name, &artifact.version);
+            if !seen_ids.insert(id) {
+                problems.push(VerifyProblem::DuplicateArtifact {
+                    artifact: artifact.clone(),
+                });
+            }
+            if !seen_targets.insert(artifact.target.as_str()) {
+                problems.push(VerifyProblem::DuplicateTarget {
+                    target: artifact.target.clone(),
+                });
+            }
+            let hash = self.artifact_hash(artifact).ok();
+            if hash.is_none() {
+                problems.push(VerifyProblem::MissingTarget {
+                    artifact: artifact.clone(),
+                });
+            }
+            artifacts.push((artifact.clone(), hash));
+        }
+
+        for (name, target) in repo.targets().signed.targets_iter() {
+            let resolved = name.resolved();
+            if resolved != "artifacts.json" && !seen_targets.contains(resolved)
+            {
+                problems.push(VerifyProblem::UnreferencedTarget {
+                    target: resolved.to_owned(),
+                });
+            }
+
+            // tough checks the length and hash of a target as it's read, so
+            // reading every target to the end checks all of them.
+            if let Err(error) = self.drain_target(name).await {
+                problems.push(VerifyProblem::InvalidTarget {
+                    target: resolved.to_owned(),
+                    error: format!("{error:#}"),
+                });
+                continue;
+            }
+
+            // Now that we know the target's contents are what was signed,
+            // check the structure of composite artifacts.
+            let target_artifacts = document
+                .artifacts
+                .iter()
+                .filter(|artifact| artifact.target == resolved);
+            for artifact in target_artifacts {
+                let path = self
+                    .repo_path()
+                    .join("targets")
+                    .join(self.target_filename(target, name));
+                if let Err(error) = check_composite(artifact, &path) {
+                    problems.push(VerifyProblem::InvalidComposite {
+                        artifact: artifact.clone(),
+                        error: format!("{error:#}"),
+                    });
+                }
+            }
+        }
+
+        Ok(VerifyReport {
+            system_version: document.system_version,
+            expirations,
+            artifacts,
+            problems,
+        })
+    }
+
+    async fn drain_target(&self, name: &TargetName) -> Result<()> {
+        let stream = self
+            .repo()
+            .read_target(name)
+            .await?
+            .context("target is not in the repository")?;
+        stream.try_for_each(|_| futures::future::ok(())).await?;
+        Ok(())
+    }
+}
+
+/// Checks that the file at `path` has the structure required for
+/// `artifact`'s kind. Kinds that aren't composite always pass.
+fn check_composite(artifact: &Artifact, path: &camino::Utf8Path) -> Result<()> {
+    let Some(kind) = artifact.kind.to_known() else {
+        return Ok(());
+    };
+    let reader = || -> Result<_> { Ok(BufReader::new(File::open(path)?)) };
+    match kind {
+        KnownArtifactKind::Host | KnownArtifactKind::Trampoline => {
+            HostPhaseImages::extract_into(reader()?, io::sink(), io::sink())
+        }
+        KnownArtifactKind::GimletRot
+        | KnownArtifactKind::PscRot
+        | KnownArtifactKind::SwitchRot => {
+            RotArchives::extract_into(reader()?, io::sink(), io::sink())
+        }
+        KnownArtifactKind::ControlPlane => {
+            ControlPlaneZoneImages::extract(reader()?).map(|_| ())
+        }
+        KnownArtifactKind::GimletSp
+        | KnownArtifactKind::PscSp
+        | KnownArtifactKind::SwitchSp => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{AddArtifact, ArtifactSource, Key};
+    use buf_list::BufList;
+    use camino_tempfile::Utf8TempDir;
+    use chrono::Days;
+    use omicron_test_utils::dev::test_setup_log;
+
+    fn buf_list_of(data: &'static [u8]) -> BufList {
+        std::iter::once(bytes::Bytes::from_static(data)).collect()
+    }
+
+    #[tokio::test]
+    async fn verify_reports_problems() {
+        let logctx = test_setup_log("verify_reports_problems");
+        let tempdir = Utf8TempDir::new().unwrap();
+        let key = Key::generate_ed25519();
+        let expiry = Utc::now() + Days::new(1);
+        let mut editor = OmicronRepo::initialize(
+            &logctx.log,
+            tempdir.path(),
+            "0.0.0".parse().unwrap(),
+            vec![key.clone()],
+            expiry,
+        )
+        .await
+        .unwrap()
+        .into_editor()
+        .await
+        .unwrap();
+
+        // A host OS artifact that isn't actually a tarball.
+        editor
+            .add_artifact(&AddArtifact::new(
+                KnownArtifactKind::Host.into(),
+                "host".to_owned(),
+                "1.0.0".parse().unwrap(),
+                ArtifactSource::Memory(buf_list_of(b"not a tarball")),
+            ))
+            .unwrap();
+        // An artifact of a kind we don't know about, which isn't checked.
+        editor
+            .add_artifact(&AddArtifact::new(
+                "some_unknown_kind".parse().unwrap(),
+                "unknown".to_owned(),
+                "1.0.0".parse().unwrap(),
+                ArtifactSource::Memory(buf_list_of(b"anything")),
+            ))
+            .unwrap();
+        editor.sign_and_finish(vec![key], expiry).await.unwrap();
+
+        let repo = OmicronRepo::load_untrusted(&logctx.log, tempdir.path())
+            .await
+            .unwrap();
+
+        let report = repo.verify(Utc::now()).await.unwrap();
+        assert_eq!(report.artifacts.len(), 2);
+        assert!(report.artifacts.iter().all(|(_, hash)| hash.is_some()));
+        assert_eq!(report.problems.len(), 1, "{:?}", report.problems);
+        let VerifyProblem::InvalidComposite { artifact, .. } =
+            &report.problems[0]
+        else {
+            panic!("unexpected problem: {:?}", report.problems[0]);
+        };
+        assert_eq!(artifact.name, "host");
+
+        // Verifying in the future should report that every role has expired.
+        let report = repo.verify(expiry + Days::new(1)).await.unwrap();
+        let expired: Vec<_> = report
+            .problems
+            .iter()
+            .filter_map(|problem| match problem {
+                VerifyProblem::Expired { role, .. } => Some(*role),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(expired, ["root", "snapshot", "targets", "timestamp"]);
+
+        logctx.cleanup_successful();
+    }
+}
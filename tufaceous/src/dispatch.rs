@@ -3,13 +3,16 @@
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
 use anyhow::{bail, Context, Result};
-use camino::Utf8PathBuf;
+use camino::{Utf8Path, Utf8PathBuf};
+use camino_tempfile::Utf8TempDir;
 use chrono::{DateTime, Utc};
-use clap::{CommandFactory, Parser};
+use clap::{CommandFactory, Parser, Subcommand};
 use omicron_common::{api::external::SemverVersion, update::ArtifactKind};
+use std::num::NonZeroU64;
 use tufaceous_lib::{
     assemble::{ArtifactManifest, OmicronRepoAssembler},
-    AddArtifact, ArchiveExtractor, Key, OmicronRepo,
+    AddArtifact, ArchiveExtractor, DiffArtifact, Key, OmicronRepo, PublicKey,
+    RepoDiff, RootKeys, RootRole, SignatureCount, UnverifiedRepo,
 };
 
 #[derive(Debug, Parser)]
@@ -43,17 +46,28 @@ impl Args {
         };
 
         match self.command {
-            Command::Init { system_version, no_generate_key } => {
-                let keys = maybe_generate_keys(self.keys, no_generate_key);
-
-                let repo = OmicronRepo::initialize(
-                    &log,
-                    &repo_path,
-                    system_version,
-                    keys,
-                    self.expiry,
-                )
-                .await?;
+            Command::Init { system_version, no_generate_key, roots } => {
+                let repo = if roots.is_empty() {
+                    let keys = maybe_generate_keys(self.keys, no_generate_key);
+                    OmicronRepo::initialize(
+                        &log,
+                        &repo_path,
+                        system_version,
+                        keys,
+                        self.expiry,
+                    )
+                    .await?
+                } else {
+                    OmicronRepo::initialize_with_roots(
+                        &log,
+                        &repo_path,
+                        system_version,
+                        &read_roots(&roots)?,
+                        self.keys,
+                        self.expiry,
+                    )
+                    .await?
+                };
                 slog::info!(
                     log,
                     "Initialized TUF repository in {}",
@@ -149,6 +163,7 @@ impl Args {
                 build_dir,
                 no_generate_key,
                 skip_all_present,
+                roots,
             } => {
                 // The filename must end with "zip".
                 if output_path.extension() != Some("zip") {
@@ -161,7 +176,12 @@ impl Args {
                     manifest.verify_all_present()?;
                 }
 
-                let keys = maybe_generate_keys(self.keys, no_generate_key);
+                let keys = if roots.is_empty() {
+                    maybe_generate_keys(self.keys, no_generate_key)
+                } else {
+                    // A generated key wouldn't be trusted by the given roots.
+                    self.keys
+                };
                 let mut assembler = OmicronRepoAssembler::new(
                     &log,
                     manifest,
@@ -172,11 +192,137 @@ impl Args {
                 if let Some(dir) = build_dir {
                     assembler.set_build_dir(dir);
                 }
+                if !roots.is_empty() {
+                    assembler.set_roots(read_roots(&roots)?);
+                }
 
                 assembler.build().await?;
 
                 Ok(())
             }
+            Command::Verify { archive_file, root } => {
+                let trusted_root = match &root {
+                    Some(root) => Some(
+                        std::fs::read(root)
+                            .with_context(|| format!("error reading {root}"))?,
+                    ),
+                    None => {
+                        eprintln!(
+                            "warning: no trusted root provided; only checking \
+                             that {archive_file} is self-consistent"
+                        );
+                        None
+                    }
+                };
+                let (_dir, repo) =
+                    load_archive(log, &archive_file, trusted_root.as_deref())
+                        .await?;
+
+                let report = repo.verify(Utc::now()).await?;
+                println!("system version: {}", report.system_version);
+                for (role, expires) in &report.expirations {
+                    println!("{role} expires: {expires}");
+                }
+                for (artifact, hash) in &report.artifacts {
+                    let hash = hash.map_or_else(
+                        || "(missing)".to_owned(),
+                        |hash| hash.to_string(),
+                    );
+                    println!(
+                        "artifact: {} {} {} {hash}",
+                        artifact.kind, artifact.name, artifact.version,
+                    );
+                }
+                for problem in &report.problems {
+                    println!("problem: {problem}");
+                }
+
+                if !report.is_ok() {
+                    bail!(
+                        "{} problem(s) found in {archive_file}",
+                        report.problems.len()
+                    );
+                }
+                println!("{archive_file} verified successfully");
+                Ok(())
+            }
+            Command::Diff { old, new } => {
+                let (_old_dir, old_repo) =
+                    load_archive(log, &old, None).await?;
+                let (_new_dir, new_repo) =
+                    load_archive(log, &new, None).await?;
+
+                let diff = RepoDiff::new(&old_repo, &new_repo).await?;
+                if diff.is_empty() {
+                    println!("no differences");
+                    return Ok(());
+                }
+                if let Some((old_version, new_version)) = &diff.system_version {
+                    println!("system version: {old_version} -> {new_version}");
+                }
+                for artifact in &diff.added {
+                    println!("added: {}", display_diff_artifact(artifact));
+                }
+                for artifact in &diff.removed {
+                    println!("removed: {}", display_diff_artifact(artifact));
+                }
+                for changed in &diff.changed {
+                    println!(
+                        "changed: {} {}: {} {} -> {} {}",
+                        changed.new.kind,
+                        changed.new.name,
+                        changed.old.version,
+                        changed.old.hash,
+                        changed.new.version,
+                        changed.new.hash,
+                    );
+                }
+                println!("unchanged: {}", diff.unchanged);
+                Ok(())
+            }
+            Command::Sign { archive_file, output_path } => {
+                // The filename must end with "zip".
+                if output_path.extension() != Some("zip") {
+                    bail!("output path `{output_path}` must end with .zip");
+                }
+
+                let dir = Utf8TempDir::new()?;
+                let mut extractor = ArchiveExtractor::from_path(&archive_file)?;
+                extractor.extract(dir.path()).with_context(|| {
+                    format!("error extracting {archive_file}")
+                })?;
+
+                let mut repo = UnverifiedRepo::load(dir.path())?;
+                repo.sign(&self.keys).await?;
+                repo.archive(&output_path)?;
+                print_signature_status(&repo.signature_status());
+
+                if repo.is_fully_signed() {
+                    // Counting signatures doesn't check that they're valid;
+                    // loading the repository does.
+                    OmicronRepo::load_untrusted_ignore_expiration(
+                        log,
+                        dir.path(),
+                    )
+                    .await
+                    .with_context(|| {
+                        format!("error loading signed repository {output_path}")
+                    })?;
+                    println!("{output_path} is fully signed");
+                } else {
+                    println!("{output_path} needs more signatures");
+                }
+                Ok(())
+            }
+            Command::PublicKey { no_generate_key } => {
+                for key in maybe_generate_keys(self.keys, no_generate_key) {
+                    println!("{}", key.public_key());
+                }
+                Ok(())
+            }
+            Command::Root { command } => {
+                command.exec(&self.keys, self.expiry).await
+            }
         }
     }
 }
@@ -191,6 +337,11 @@ enum Command {
         /// Disable random key generation and exit if no keys are provided
         #[clap(long)]
         no_generate_key: bool,
+
+        /// Sign the repository according to these root.json files (in version
+        /// order, starting at version 1) [default: a root trusting each key]
+        #[clap(long = "root")]
+        roots: Vec<Utf8PathBuf>,
     },
     Add {
         /// The kind of artifact this is.
@@ -242,7 +393,181 @@ enum Command {
         /// Skip checking to ensure all expected artifacts are present.
         #[clap(long)]
         skip_all_present: bool,
+
+        /// Sign the repository according to these root.json files (in version
+        /// order, starting at version 1) [default: a root trusting each key]
+        #[clap(long = "root")]
+        roots: Vec<Utf8PathBuf>,
     },
+    /// Verifies a repository archive: its signatures, expiration, artifacts
+    /// and the structure of composite artifacts.
+    Verify {
+        /// The archive to verify.
+        archive_file: Utf8PathBuf,
+
+        /// Trusted root.json to verify signatures against [default: the
+        /// archive's own root]
+        #[clap(long)]
+        root: Option<Utf8PathBuf>,
+    },
+    /// Lists the artifacts added, removed and changed between two repository
+    /// archives.
+    Diff {
+        /// The older archive.
+        old: Utf8PathBuf,
+
+        /// The newer archive.
+        new: Utf8PathBuf,
+    },
+    /// Adds signatures to a repository archive that other key holders have
+    /// only partially signed.
+    Sign {
+        /// The archive to sign.
+        archive_file: Utf8PathBuf,
+
+        /// The path to write the signed archive to (must end with .zip).
+        output_path: Utf8PathBuf,
+    },
+    /// Prints the public keys corresponding to the provided keys.
+    PublicKey {
+        /// Disable random key generation and exit if no keys are provided
+        #[clap(long)]
+        no_generate_key: bool,
+    },
+    /// Creates, rotates and signs root roles.
+    Root {
+        #[clap(subcommand)]
+        command: RootCommand,
+    },
+}
+
+#[derive(Debug, Subcommand)]
+enum RootCommand {
+    /// Creates version 1 of a root role, signed by the provided keys.
+    Generate {
+        /// The path to write root.json to.
+        output_path: Utf8PathBuf,
+
+        /// Public keys to trust (see the `public-key` command)
+        #[clap(long = "public-key", required = true)]
+        public_keys: Vec<PublicKey>,
+
+        /// The number of keys required to sign the root and targets roles
+        #[clap(long, default_value = "1")]
+        threshold: NonZeroU64,
+    },
+    /// Creates the next version of a root role, trusting a new set of keys.
+    ///
+    /// The new version must be signed by enough of both the old and the new
+    /// keys; it's signed by whichever of the provided keys are trusted by
+    /// either.
+    Rotate {
+        /// The current version of the root role.
+        previous: Utf8PathBuf,
+
+        /// The path to write the new version to.
+        output_path: Utf8PathBuf,
+
+        /// Public keys for the new version to trust
+        #[clap(long = "public-key", required = true)]
+        public_keys: Vec<PublicKey>,
+
+        /// The number of keys required to sign the root and targets roles
+        #[clap(long, default_value = "1")]
+        threshold: NonZeroU64,
+    },
+    /// Adds signatures from the provided keys to a root role, in place.
+    Sign {
+        /// The root role to sign.
+        path: Utf8PathBuf,
+
+        /// The previous version of the root role, if it's being rotated
+        #[clap(long)]
+        previous: Option<Utf8PathBuf>,
+    },
+}
+
+impl RootCommand {
+    async fn exec(self, keys: &[Key], expiry: DateTime<Utc>) -> Result<()> {
+        match self {
+            RootCommand::Generate { output_path, public_keys, threshold } => {
+                let root_keys = RootKeys::new(public_keys, threshold)?;
+                let root = RootRole::generate(&root_keys, keys, expiry).await?;
+                root.write(&output_path)?;
+                print_signature_status(&root.signature_status(None));
+            }
+            RootCommand::Rotate {
+                previous,
+                output_path,
+                public_keys,
+                threshold,
+            } => {
+                let previous = RootRole::from_path(&previous)?;
+                let root_keys = RootKeys::new(public_keys, threshold)?;
+                let root = previous.rotate(&root_keys, keys, expiry).await?;
+                root.write(&output_path)?;
+                print_signature_status(&root.signature_status(Some(&previous)));
+            }
+            RootCommand::Sign { path, previous } => {
+                let previous =
+                    previous.as_deref().map(RootRole::from_path).transpose()?;
+                let mut root = RootRole::from_path(&path)?;
+                root.sign(previous.as_ref(), keys).await?;
+                root.write(&path)?;
+                print_signature_status(
+                    &root.signature_status(previous.as_ref()),
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Reads a chain of root roles from `paths`, in order.
+fn read_roots(paths: &[Utf8PathBuf]) -> Result<Vec<RootRole>> {
+    paths.iter().map(|path| RootRole::from_path(path)).collect()
+}
+
+fn print_signature_status(status: &[SignatureCount]) {
+    for count in status {
+        println!("{count}");
+    }
+}
+
+/// Extracts the repository archive at `archive_file` into a temporary
+/// directory and loads it, ignoring expiration.
+///
+/// The returned directory must be kept around for as long as the repository
+/// is in use.
+async fn load_archive(
+    log: &slog::Logger,
+    archive_file: &Utf8Path,
+    trusted_root: Option<&[u8]>,
+) -> Result<(Utf8TempDir, OmicronRepo)> {
+    let dir = Utf8TempDir::new()?;
+    let mut extractor = ArchiveExtractor::from_path(archive_file)?;
+    extractor
+        .extract(dir.path())
+        .with_context(|| format!("error extracting {archive_file}"))?;
+
+    let repo = match trusted_root {
+        Some(root) => {
+            OmicronRepo::load_trusted_ignore_expiration(log, dir.path(), root)
+                .await
+        }
+        None => {
+            OmicronRepo::load_untrusted_ignore_expiration(log, dir.path()).await
+        }
+    }
+    .with_context(|| format!("error loading repository from {archive_file}"))?;
+    Ok((dir, repo))
+}
+
+fn display_diff_artifact(artifact: &DiffArtifact) -> String {
+    format!(
+        "{} {} {} {}",
+        artifact.kind, artifact.name, artifact.version, artifact.hash
+    )
 }
 
 fn maybe_generate_keys(keys: Vec<Key>, no_generate_key: bool) -> Vec<Key> {
@@ -136,6 +136,191 @@ fn test_assemble_fake() -> Result<()> {
     Ok(())
 }
 
+#[test]
+fn test_verify_and_diff() -> Result<()> {
+    let logctx = test_setup_log("test_verify_and_diff");
+    let tempdir = tempfile::tempdir().unwrap();
+    let key = Key::generate_ed25519();
+
+    // Assemble the fake manifest twice.
+    let archive_path = tempdir.path().join("archive.zip");
+    let archive2_path = tempdir.path().join("archive2.zip");
+    for path in [&archive_path, &archive2_path] {
+        let mut cmd = make_cmd(&key);
+        cmd.args(["assemble", "manifests/fake.toml"]);
+        cmd.arg(path);
+        cmd.assert().success();
+    }
+
+    let mut cmd = make_cmd(&key);
+    cmd.arg("verify");
+    cmd.arg(&archive_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("verified successfully"));
+
+    // Both archives have the same artifacts.
+    let mut cmd = make_cmd(&key);
+    cmd.arg("diff");
+    cmd.arg(&archive_path);
+    cmd.arg(&archive2_path);
+    cmd.assert().success().stdout(predicate::str::contains("no differences"));
+
+    // Create an unrelated repository with a different key, version, and set
+    // of artifacts.
+    let other_key = Key::generate_ed25519();
+    let mut cmd = make_cmd_with_repo(tempdir.path(), &other_key);
+    cmd.args(["init", "2.0.0"]);
+    cmd.assert().success();
+
+    let nexus_path = tempdir.path().join("nexus.tar.gz");
+    fs_err::write(&nexus_path, "test")?;
+    let mut cmd = make_cmd_with_repo(tempdir.path(), &other_key);
+    cmd.args(["add", "gimlet_sp"]);
+    cmd.arg(&nexus_path);
+    cmd.arg("42.0.0");
+    cmd.assert().success();
+
+    let other_archive_path = tempdir.path().join("other.zip");
+    let mut cmd = make_cmd_with_repo(tempdir.path(), &other_key);
+    cmd.arg("archive");
+    cmd.arg(&other_archive_path);
+    cmd.assert().success();
+
+    let mut cmd = make_cmd(&key);
+    cmd.arg("diff");
+    cmd.arg(&archive_path);
+    cmd.arg(&other_archive_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("system version: 1.0.0 -> 2.0.0"))
+        .stdout(predicate::str::contains("added: gimlet_sp nexus 42.0.0"));
+
+    // The fake archive isn't signed by the other repository's root.
+    let mut cmd = make_cmd(&key);
+    cmd.arg("verify");
+    cmd.arg(&archive_path);
+    cmd.arg("--root");
+    cmd.arg(tempdir.path().join("repo/metadata/1.root.json"));
+    cmd.assert().failure();
+
+    logctx.cleanup_successful();
+    Ok(())
+}
+
+#[test]
+fn test_multi_signature_and_rotation() -> Result<()> {
+    let logctx = test_setup_log("test_multi_signature_and_rotation");
+    let tempdir = tempfile::tempdir().unwrap();
+    let [a, b, c, d] = [(); 4].map(|()| Key::generate_ed25519());
+    let root_path = tempdir.path().join("root.json");
+
+    // Create a 2-of-3 root, signed by a and then countersigned by b.
+    let mut cmd = make_cmd_with_keys(&[&a]);
+    cmd.args(["root", "generate"]);
+    cmd.arg(&root_path);
+    for key in [&a, &b, &c] {
+        cmd.args(["--public-key", &key.public_key().to_string()]);
+    }
+    cmd.args(["--threshold", "2"]);
+    cmd.assert().success().stdout(predicate::str::contains(
+        "root version 1: 1 of 2 required signatures",
+    ));
+
+    let mut cmd = make_cmd_with_keys(&[&b]);
+    cmd.args(["root", "sign"]);
+    cmd.arg(&root_path);
+    cmd.assert().success().stdout(predicate::str::contains(
+        "root version 1: 2 of 2 required signatures",
+    ));
+
+    // A repository assembled with only a's key is missing a signature.
+    let partial_path = tempdir.path().join("partial.zip");
+    let mut cmd = make_cmd_with_keys(&[&a]);
+    cmd.args(["assemble", "manifests/fake.toml"]);
+    cmd.arg(&partial_path);
+    cmd.arg("--root");
+    cmd.arg(&root_path);
+    cmd.assert().success();
+
+    let mut cmd = make_cmd_with_keys(&[&a]);
+    cmd.arg("verify");
+    cmd.arg(&partial_path);
+    cmd.assert().failure();
+
+    // Countersigning with c completes it.
+    let signed_path = tempdir.path().join("signed.zip");
+    let mut cmd = make_cmd_with_keys(&[&c]);
+    cmd.arg("sign");
+    cmd.arg(&partial_path);
+    cmd.arg(&signed_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("targets: 2 of 2 required signatures"))
+        .stdout(predicate::str::contains("is fully signed"));
+
+    let mut cmd = make_cmd_with_keys(&[&a]);
+    cmd.arg("verify");
+    cmd.arg(&signed_path);
+    cmd.arg("--root");
+    cmd.arg(&root_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("verified successfully"));
+
+    // Rotate c out for d. The new root must be signed by two of the old keys
+    // and two of the new keys.
+    let root2_path = tempdir.path().join("root2.json");
+    let mut cmd = make_cmd_with_keys(&[&a, &d]);
+    cmd.args(["root", "rotate"]);
+    cmd.arg(&root_path);
+    cmd.arg(&root2_path);
+    for key in [&a, &b, &d] {
+        cmd.args(["--public-key", &key.public_key().to_string()]);
+    }
+    cmd.args(["--threshold", "2"]);
+    cmd.assert().success().stdout(predicate::str::contains(
+        "root version 2 (from version 1): 1 of 2 required signatures",
+    ));
+
+    let mut cmd = make_cmd_with_keys(&[&b]);
+    cmd.args(["root", "sign", "--previous"]);
+    cmd.arg(&root_path);
+    cmd.arg(&root2_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "root version 2: 2 of 2 required signatures",
+        ))
+        .stdout(predicate::str::contains(
+            "root version 2 (from version 1): 2 of 2 required signatures",
+        ));
+
+    // A repository signed according to the new root is still trusted by
+    // clients that only know about the old one.
+    let rotated_path = tempdir.path().join("rotated.zip");
+    let mut cmd = make_cmd_with_keys(&[&b, &d]);
+    cmd.args(["assemble", "manifests/fake.toml"]);
+    cmd.arg(&rotated_path);
+    cmd.arg("--root");
+    cmd.arg(&root_path);
+    cmd.arg("--root");
+    cmd.arg(&root2_path);
+    cmd.assert().success();
+
+    let mut cmd = make_cmd_with_keys(&[&a]);
+    cmd.arg("verify");
+    cmd.arg(&rotated_path);
+    cmd.arg("--root");
+    cmd.arg(&root_path);
+    cmd.assert()
+        .success()
+        .stdout(predicate::str::contains("verified successfully"));
+
+    logctx.cleanup_successful();
+    Ok(())
+}
+
 fn make_cmd(key: &Key) -> Command {
     let mut cmd = Command::cargo_bin("tufaceous").unwrap();
     cmd.env("TUFACEOUS_KEY", key.to_string());
@@ -143,6 +328,15 @@ fn make_cmd(key: &Key) -> Command {
     cmd
 }
 
+fn make_cmd_with_keys(keys: &[&Key]) -> Command {
+    let mut cmd = Command::cargo_bin("tufaceous").unwrap();
+    for key in keys {
+        cmd.args(["--key", &key.to_string()]);
+    }
+
+    cmd
+}
+
 fn make_cmd_with_repo(tempdir: &Path, key: &Key) -> Command {
     let mut cmd = make_cmd(key);
     cmd.arg("--repo");
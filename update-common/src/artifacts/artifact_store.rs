@@ -0,0 +1,774 @@
+// This Source Code Form is subject to the terms of the Mozilla Public
+// License, v. 2.0. If a copy of the MPL was not distributed with this
+// file, You can obtain one at https://mozilla.org/MPL/2.0/.
+
+use super::ExtractedArtifactDataHandle;
+use super::HashingNamedUtf8TempFile;
+use crate::errors::ArtifactStoreError;
+use crate::errors::RepositoryError;
+use anyhow::Context;
+use camino::Utf8Path;
+use camino::Utf8PathBuf;
+use camino_tempfile::NamedUtf8TempFile;
+use camino_tempfile::Utf8TempDir;
+use futures::Stream;
+use futures::StreamExt;
+use omicron_common::update::ArtifactHash;
+use omicron_common::update::ArtifactHashId;
+use omicron_common::update::ArtifactKind;
+use serde::Deserialize;
+use serde::Serialize;
+use sha2::Digest;
+use sha2::Sha256;
+use slog::info;
+use slog::warn;
+use slog::Logger;
+use std::collections::BTreeMap;
+use std::collections::BTreeSet;
+use std::io;
+use std::io::Write;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::Weak;
+
+/// A persistent, content-addressed store for the artifacts extracted from
+/// uploaded TUF repositories.
+///
+/// Artifacts are stored by hash, so an artifact that's shared between
+/// repositories (or that's uploaded again) is only stored, and extracted,
+/// once. The store keeps track of the artifacts each uploaded repository
+/// refers to, as recorded with [`ArtifactStore::insert_repo`]. Artifacts that
+/// no repository refers to and that no [`ExtractedArtifactDataHandle`] points
+/// to are removed by [`ArtifactStore::gc`].
+///
+/// The store's directory is laid out as follows:
+///
+/// * `artifacts/<hash>`: the contents of each artifact
+/// * `repos/<hash>.json`: the [`RepoContents`] of each uploaded repository,
+///   named by the hash of the repository itself (i.e., of its archive)
+/// * `tmp/`: artifacts that are still being written
+///
+/// This can be cheaply cloned.
+#[derive(Clone, Debug)]
+pub struct ArtifactStore {
+    inner: Arc<ArtifactStoreInner>,
+}
+
+#[derive(Debug)]
+struct ArtifactStoreInner {
+    log: Logger,
+    dir: Utf8PathBuf,
+    // For stores created with `ArtifactStore::temporary`, this removes the
+    // store's directory once the last handle to the store is dropped.
+    _tempdir: Option<Utf8TempDir>,
+    // NOTE: this is a `std::sync::Mutex` rather than a `tokio::sync::Mutex`
+    // because the critical sections only do small, local filesystem
+    // operations. All changes to the `artifacts` directory happen with this
+    // lock held, so that garbage collection can't race with an artifact being
+    // stored.
+    state: Mutex<StoreState>,
+}
+
+#[derive(Debug, Default)]
+struct StoreState {
+    artifacts: BTreeMap<ArtifactHash, StoredArtifact>,
+    repos: BTreeMap<ArtifactHash, RepoContents>,
+}
+
+#[derive(Debug)]
+struct StoredArtifact {
+    size: usize,
+    // Every `ExtractedArtifactDataHandle` for this artifact holds a strong
+    // reference to this pin, so the artifact is in use as long as it can be
+    // upgraded.
+    pin: Weak<ArtifactPin>,
+}
+
+/// Keeps an artifact from being garbage collected while it's in use.
+#[derive(Debug)]
+pub(super) struct ArtifactPin;
+
+/// The artifacts that an uploaded repository refers to.
+#[derive(Clone, Debug, Default, Eq, PartialEq, Deserialize, Serialize)]
+pub struct RepoContents {
+    /// The hashes of all the artifacts extracted from the repository.
+    pub artifacts: BTreeSet<ArtifactHash>,
+
+    /// The pairs of artifacts extracted from nested archives in the
+    /// repository (e.g., the A and B images in an RoT archive), keyed by the
+    /// hash of the archive.
+    ///
+    /// These let us skip extracting an archive that we've seen before.
+    pub nested: BTreeMap<ArtifactHash, [ArtifactHash; 2]>,
+
+    /// The hash of the repository's own archive, if that's kept in the store
+    /// too (see [`RepoArchive`](super::RepoArchive)).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive: Option<ArtifactHash>,
+}
+
+impl RepoContents {
+    /// Iterates over every artifact in the store that the repository refers
+    /// to, including its archive.
+    fn all_artifacts(&self) -> impl Iterator<Item = &ArtifactHash> + '_ {
+        self.artifacts.iter().chain(&self.archive)
+    }
+}
+
+impl ArtifactStore {
+    /// Opens (creating if necessary) the artifact store in `dir`.
+    ///
+    /// The contents of every artifact already in the store are checked
+    /// against their hash, and any that don't match are removed, along with
+    /// any unreferenced artifacts.
+    pub async fn open(
+        log: &Logger,
+        dir: Utf8PathBuf,
+    ) -> Result<Self, ArtifactStoreError> {
+        let log = log.new(slog::o!(
+            "component" => "ArtifactStore",
+            "dir" => dir.to_string(),
+        ));
+        let store = {
+            let log = log.clone();
+            tokio::task::spawn_blocking(move || {
+                // Verifying artifacts requires reading all of them, so do this
+                // on the blocking thread pool.
+                Self::open_blocking(log, dir, None)
+            })
+            .await
+            .unwrap()?
+        };
+        info!(
+            log, "opened artifact store";
+            "artifacts" => store.inner.state.lock().unwrap().artifacts.len(),
+        );
+        Ok(store)
+    }
+
+    /// Creates an artifact store in a new temporary directory, which is
+    /// removed once the store (and every handle to its artifacts) is dropped.
+    pub fn temporary(log: &Logger) -> Result<Self, ArtifactStoreError> {
+        let tempdir = camino_tempfile::Builder::new()
+            .prefix("update-artifacts.")
+            .tempdir()
+            .map_err(ArtifactStoreError::TempDirCreate)?;
+        let dir = tempdir.path().to_owned();
+        info!(
+            log, "created directory to store extracted artifacts";
+            "path" => %dir,
+        );
+        let log = log.new(slog::o!(
+            "component" => "ArtifactStore",
+            "dir" => dir.to_string(),
+        ));
+        Self::open_blocking(log, dir, Some(tempdir))
+    }
+
+    fn open_blocking(
+        log: Logger,
+        dir: Utf8PathBuf,
+        tempdir: Option<Utf8TempDir>,
+    ) -> Result<Self, ArtifactStoreError> {
+        for subdir in [ARTIFACTS_DIR, REPOS_DIR, TMP_DIR] {
+            let path = dir.join(subdir);
+            std::fs::create_dir_all(&path).map_err(|error| {
+                ArtifactStoreError::CreateDir { path, error }
+            })?;
+        }
+
+        // Anything left in the temporary directory was being written when we
+        // last stopped.
+        let tmp_dir = dir.join(TMP_DIR);
+        for path in read_dir(&tmp_dir)? {
+            remove_file(&log, &path);
+        }
+
+        let mut state = StoreState::default();
+        for path in read_dir(&dir.join(ARTIFACTS_DIR))? {
+            let Some(hash) =
+                path.file_name().and_then(|name| name.parse().ok())
+            else {
+                warn!(log, "removing unexpected file"; "path" => %path);
+                remove_file(&log, &path);
+                continue;
+            };
+            match hash_file(&path) {
+                Ok((actual_hash, size)) if actual_hash == hash => {
+                    state.artifacts.insert(
+                        hash,
+                        StoredArtifact { size, pin: Weak::new() },
+                    );
+                }
+                Ok((actual_hash, _)) => {
+                    warn!(
+                        log, "removing corrupt artifact";
+                        "path" => %path,
+                        "actual_hash" => %actual_hash,
+                    );
+                    remove_file(&log, &path);
+                }
+                Err(error) => {
+                    warn!(
+                        log, "removing unreadable artifact";
+                        "path" => %path,
+                        "error" => #%error,
+                    );
+                    remove_file(&log, &path);
+                }
+            }
+        }
+
+        for path in read_dir(&dir.join(REPOS_DIR))? {
+            let Some(repo_hash) = path
+                .file_stem()
+                .filter(|_| path.extension() == Some("json"))
+                .and_then(|stem| stem.parse::<ArtifactHash>().ok())
+            else {
+                warn!(log, "removing unexpected file"; "path" => %path);
+                remove_file(&log, &path);
+                continue;
+            };
+            let contents = match read_json(&path) {
+                Ok(contents) => contents,
+                Err(error) => {
+                    warn!(
+                        log, "removing invalid repository record";
+                        "path" => %path,
+                        "error" => #%error,
+                    );
+                    remove_file(&log, &path);
+                    continue;
+                }
+            };
+
+            // If any of the repository's artifacts went missing, it's no use
+            // remembering the rest: the repository will have to be uploaded
+            // (and extracted) again anyway.
+            if let Some(missing) = contents
+                .all_artifacts()
+                .find(|hash| !state.artifacts.contains_key(hash))
+            {
+                warn!(
+                    log, "forgetting repository with missing artifact";
+                    "repo_hash" => %repo_hash,
+                    "missing" => %missing,
+                );
+                remove_file(&log, &path);
+                continue;
+            }
+            state.repos.insert(repo_hash, contents);
+        }
+
+        let store = Self {
+            inner: Arc::new(ArtifactStoreInner {
+                log,
+                dir,
+                _tempdir: tempdir,
+                state: Mutex::new(state),
+            }),
+        };
+        store.gc();
+        Ok(store)
+    }
+
+    /// Returns true if `self` and `other` are handles to the same store.
+    pub fn ptr_eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.inner, &other.inner)
+    }
+
+    /// Returns a handle to the artifact with the given hash, if it's in the
+    /// store.
+    pub fn get(
+        &self,
+        hash_id: ArtifactHashId,
+    ) -> Option<ExtractedArtifactDataHandle> {
+        let mut state = self.inner.state.lock().unwrap();
+        self.handle_locked(&mut state, hash_id)
+    }
+
+    /// Returns the contents of each repository recorded in the store, keyed
+    /// by the hash of the repository.
+    pub fn repos(&self) -> BTreeMap<ArtifactHash, RepoContents> {
+        self.inner.state.lock().unwrap().repos.clone()
+    }
+
+    /// Records that the repository with hash `repo_hash` refers to the
+    /// artifacts in `contents`, replacing any previous record for it.
+    ///
+    /// The record is persisted so that the artifacts survive a restart; if
+    /// that fails, the artifacts are only kept until then.
+    pub fn insert_repo(&self, repo_hash: ArtifactHash, contents: RepoContents) {
+        let path = self.repo_path(&repo_hash);
+        if let Err(error) = write_json_atomically(&path, &contents) {
+            warn!(
+                self.inner.log, "failed to persist repository record";
+                "repo_hash" => %repo_hash,
+                "error" => #%error,
+            );
+        }
+        self.inner.state.lock().unwrap().repos.insert(repo_hash, contents);
+    }
+
+    /// Forgets every recorded repository for which `keep` returns false, then
+    /// garbage collects the store.
+    pub fn retain_repos<F>(&self, mut keep: F)
+    where
+        F: FnMut(&ArtifactHash) -> bool,
+    {
+        let removed: Vec<_> = {
+            let mut state = self.inner.state.lock().unwrap();
+            let removed = state
+                .repos
+                .keys()
+                .filter(|hash| !keep(hash))
+                .copied()
+                .collect();
+            for repo_hash in &removed {
+                state.repos.remove(repo_hash);
+            }
+            removed
+        };
+        for repo_hash in removed {
+            info!(self.inner.log, "forgetting repository"; "repo_hash" => %repo_hash);
+            remove_file(&self.inner.log, &self.repo_path(&repo_hash));
+        }
+        self.gc();
+    }
+
+    /// Removes artifacts that no recorded repository refers to and that
+    /// aren't otherwise in use, returning the number of artifacts removed.
+    pub fn gc(&self) -> usize {
+        let mut state = self.inner.state.lock().unwrap();
+        let referenced: BTreeSet<_> = state
+            .repos
+            .values()
+            .flat_map(|contents| contents.all_artifacts().copied())
+            .collect();
+        let unused: Vec<_> = state
+            .artifacts
+            .iter()
+            .filter(|(hash, artifact)| {
+                !referenced.contains(hash) && artifact.pin.strong_count() == 0
+            })
+            .map(|(hash, _)| *hash)
+            .collect();
+        for hash in &unused {
+            let size = state.artifacts.remove(hash).map(|a| a.size);
+            info!(
+                self.inner.log, "removing unused artifact";
+                "hash" => %hash,
+                "size" => size,
+            );
+            remove_file(&self.inner.log, &self.artifact_path(hash));
+        }
+        unused.len()
+    }
+
+    /// Returns a handle to the archive of the repository with hash
+    /// `repo_hash`, if it's kept in the store.
+    pub(super) fn repo_archive(
+        &self,
+        repo_hash: ArtifactHash,
+    ) -> Option<ExtractedArtifactDataHandle> {
+        let mut state = self.inner.state.lock().unwrap();
+        let hash = state.repos.get(&repo_hash)?.archive?;
+        self.handle_locked(
+            &mut state,
+            ArtifactHashId { kind: REPO_ARCHIVE_KIND, hash },
+        )
+    }
+
+    /// Returns the pair of artifacts previously extracted from the nested
+    /// archive with hash `outer_hash`, if they're still in the store.
+    pub(super) fn nested_pair(
+        &self,
+        kind: ArtifactKind,
+        outer_hash: ArtifactHash,
+    ) -> Option<(ExtractedArtifactDataHandle, ExtractedArtifactDataHandle)>
+    {
+        let mut state = self.inner.state.lock().unwrap();
+        let [hash1, hash2] = state
+            .repos
+            .values()
+            .find_map(|contents| contents.nested.get(&outer_hash))
+            .copied()?;
+        let image1 = self.handle_locked(
+            &mut state,
+            ArtifactHashId { kind: kind.clone(), hash: hash1 },
+        )?;
+        let image2 = self
+            .handle_locked(&mut state, ArtifactHashId { kind, hash: hash2 })?;
+        Some((image1, image2))
+    }
+
+    /// Copies from `stream` into the store, returning a handle to the stored
+    /// artifact on success.
+    ///
+    /// If an artifact with the same hash is already in the store, `stream`
+    /// isn't read at all.
+    pub(super) async fn store_stream(
+        &self,
+        artifact_hash_id: ArtifactHashId,
+        stream: impl Stream<Item = Result<bytes::Bytes, tough::error::Error>>,
+    ) -> Result<ExtractedArtifactDataHandle, RepositoryError> {
+        if let Some(handle) = self.get(artifact_hash_id.clone()) {
+            return Ok(handle);
+        }
+
+        // Writes to a temporary file happen synchronously. That's what we
+        // already do for nested artifacts, and these writes go to local
+        // storage.
+        let mut file = self.new_tempfile()?;
+        let mut stream = std::pin::pin!(stream);
+        while let Some(res) = stream.next().await {
+            let chunk = res.map_err(|error| RepositoryError::ReadArtifact {
+                kind: artifact_hash_id.kind.clone(),
+                error: Box::new(error),
+            })?;
+            file.write_all(&chunk)
+                .context("failed writing to temp file")
+                .map_err(|error| RepositoryError::CopyExtractedArtifact {
+                    kind: artifact_hash_id.kind.clone(),
+                    error,
+                })?;
+        }
+
+        self.persist_tempfile_impl(
+            artifact_hash_id.kind,
+            file,
+            Some(artifact_hash_id.hash),
+        )
+    }
+
+    /// Creates a new temporary file in the store.
+    ///
+    /// As the returned file is written to, the data will be hashed; once
+    /// writing is complete, call [`ArtifactStore::persist_tempfile()`] to
+    /// move the file into the store.
+    pub(super) fn new_tempfile(
+        &self,
+    ) -> Result<HashingNamedUtf8TempFile, RepositoryError> {
+        let tmp_dir = self.inner.dir.join(TMP_DIR);
+        let file = NamedUtf8TempFile::new_in(&tmp_dir).map_err(|error| {
+            RepositoryError::NamedTempFileCreate { path: tmp_dir, error }
+        })?;
+        Ok(HashingNamedUtf8TempFile {
+            file: io::BufWriter::new(file),
+            hasher: Sha256::new(),
+            bytes_written: 0,
+        })
+    }
+
+    /// Moves a temporary file returned by [`ArtifactStore::new_tempfile()`]
+    /// into the store, returning a handle to it.
+    ///
+    /// If an artifact with the same contents is already in the store, the
+    /// temporary file is discarded instead.
+    pub(super) fn persist_tempfile(
+        &self,
+        kind: ArtifactKind,
+        file: HashingNamedUtf8TempFile,
+    ) -> Result<ExtractedArtifactDataHandle, RepositoryError> {
+        self.persist_tempfile_impl(kind, file, None)
+    }
+
+    fn persist_tempfile_impl(
+        &self,
+        kind: ArtifactKind,
+        file: HashingNamedUtf8TempFile,
+        expected_hash: Option<ArtifactHash>,
+    ) -> Result<ExtractedArtifactDataHandle, RepositoryError> {
+        let HashingNamedUtf8TempFile { file, hasher, bytes_written } = file;
+
+        // We don't need to `.flush()` explicitly: `into_inner()` does that for
+        // us.
+        let file = file
+            .into_inner()
+            .context("failed to flush temp file")
+            .map_err(|error| RepositoryError::CopyExtractedArtifact {
+                kind: kind.clone(),
+                error,
+            })?;
+
+        let hash = ArtifactHash(hasher.finalize().into());
+        if let Some(expected_hash) = expected_hash {
+            if hash != expected_hash {
+                // Dropping `file` removes it.
+                return Err(RepositoryError::CopyExtractedArtifact {
+                    kind,
+                    error: anyhow::anyhow!(
+                        "expected artifact with hash {expected_hash}, \
+                         got {hash}"
+                    ),
+                });
+            }
+        }
+        let artifact_hash_id = ArtifactHashId { kind, hash };
+
+        // Make sure the contents are on disk before the file shows up in the
+        // store, so that a crash can't leave a truncated artifact under its
+        // hash. (This is done before taking the lock, since it can take a
+        // while for large artifacts.)
+        file.as_file().sync_all().context("failed to sync temp file").map_err(
+            |error| RepositoryError::CopyExtractedArtifact {
+                kind: artifact_hash_id.kind.clone(),
+                error,
+            },
+        )?;
+
+        let mut state = self.inner.state.lock().unwrap();
+        if !state.artifacts.contains_key(&hash) {
+            let output_path = self.artifact_path(&hash);
+            file.persist(&output_path)
+                .map_err(|error| error.error)
+                .with_context(|| {
+                    format!("failed to persist temp file to {output_path}")
+                })
+                .map_err(|error| RepositoryError::CopyExtractedArtifact {
+                    kind: artifact_hash_id.kind.clone(),
+                    error,
+                })?;
+            state.artifacts.insert(
+                hash,
+                StoredArtifact { size: bytes_written, pin: Weak::new() },
+            );
+        }
+
+        Ok(self
+            .handle_locked(&mut state, artifact_hash_id)
+            .expect("artifact was just stored"))
+    }
+
+    /// Returns the path to the artifact with the given hash.
+    pub(super) fn artifact_path(&self, hash: &ArtifactHash) -> Utf8PathBuf {
+        self.inner.dir.join(ARTIFACTS_DIR).join(hash.to_string())
+    }
+
+    fn repo_path(&self, repo_hash: &ArtifactHash) -> Utf8PathBuf {
+        self.inner.dir.join(REPOS_DIR).join(format!("{repo_hash}.json"))
+    }
+
+    fn handle_locked(
+        &self,
+        state: &mut StoreState,
+        hash_id: ArtifactHashId,
+    ) -> Option<ExtractedArtifactDataHandle> {
+        let artifact = state.artifacts.get_mut(&hash_id.hash)?;
+        let pin = artifact.pin.upgrade().unwrap_or_else(|| {
+            let pin = Arc::new(ArtifactPin);
+            artifact.pin = Arc::downgrade(&pin);
+            pin
+        });
+        Some(ExtractedArtifactDataHandle::new(
+            self.clone(),
+            pin,
+            artifact.size,
+            hash_id,
+        ))
+    }
+}
+
+/// The kind used for handles to the archives of uploaded repositories
+pub(super) const REPO_ARCHIVE_KIND: ArtifactKind =
+    ArtifactKind::from_static("tuf_repo_archive");
+
+const ARTIFACTS_DIR: &str = "artifacts";
+const REPOS_DIR: &str = "repos";
+const TMP_DIR: &str = "tmp";
+
+fn read_dir(dir: &Utf8Path) -> Result<Vec<Utf8PathBuf>, ArtifactStoreError> {
+    let read_dir_error =
+        |error| ArtifactStoreError::ReadDir { path: dir.to_owned(), error };
+    dir.read_dir_utf8()
+        .map_err(read_dir_error)?
+        .map(|entry| {
+            entry.map(|entry| entry.into_path()).map_err(read_dir_error)
+        })
+        .collect()
+}
+
+fn remove_file(log: &Logger, path: &Utf8Path) {
+    if let Err(error) = std::fs::remove_file(path) {
+        if error.kind() != io::ErrorKind::NotFound {
+            warn!(
+                log, "failed to remove file";
+                "path" => %path,
+                "error" => %error,
+            );
+        }
+    }
+}
+
+fn hash_file(path: &Utf8Path) -> io::Result<(ArtifactHash, usize)> {
+    let mut file = std::fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let size = io::copy(&mut file, &mut hasher)?;
+    Ok((ArtifactHash(hasher.finalize().into()), size as usize))
+}
+
+fn read_json(path: &Utf8Path) -> anyhow::Result<RepoContents> {
+    let data = std::fs::read(path)
+        .with_context(|| format!("failed to read {path}"))?;
+    serde_json::from_slice(&data)
+        .with_context(|| format!("failed to parse {path}"))
+}
+
+fn write_json_atomically(
+    path: &Utf8Path,
+    contents: &RepoContents,
+) -> anyhow::Result<()> {
+    let dir = path.parent().expect("repository records are in a directory");
+    let mut file = NamedUtf8TempFile::new_in(dir)
+        .with_context(|| format!("failed to create temp file in {dir}"))?;
+    serde_json::to_writer_pretty(&mut file, contents)
+        .context("failed to serialize repository record")?;
+    file.as_file().sync_all().context("failed to sync temp file")?;
+    file.persist(path)
+        .map_err(|error| error.error)
+        .with_context(|| format!("failed to persist {path}"))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::TryStreamExt;
+    use omicron_test_utils::dev::test_setup_log;
+
+    fn stream_of(
+        data: &'static [u8],
+    ) -> impl Stream<Item = Result<bytes::Bytes, tough::error::Error>> {
+        futures::stream::iter([Ok(bytes::Bytes::from_static(data))])
+    }
+
+    fn hash_id_of(data: &[u8]) -> ArtifactHashId {
+        ArtifactHashId {
+            kind: ArtifactKind::HOST_PHASE_2,
+            hash: ArtifactHash(Sha256::digest(data).into()),
+        }
+    }
+
+    async fn read_all(handle: &ExtractedArtifactDataHandle) -> Vec<u8> {
+        handle
+            .reader_stream()
+            .await
+            .unwrap()
+            .map_ok(|chunk| chunk.to_vec())
+            .try_concat()
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_dedup_and_gc() {
+        let logctx = test_setup_log("test_dedup_and_gc");
+        let store = ArtifactStore::temporary(&logctx.log).unwrap();
+
+        let a = hash_id_of(b"artifact a");
+        let b = hash_id_of(b"artifact b");
+        let handle_a = store
+            .store_stream(a.clone(), stream_of(b"artifact a"))
+            .await
+            .unwrap();
+        let handle_b = store
+            .store_stream(b.clone(), stream_of(b"artifact b"))
+            .await
+            .unwrap();
+
+        // Storing the same artifact again doesn't read the stream.
+        let failing = futures::stream::poll_fn(
+            |_| -> std::task::Poll<
+                Option<Result<bytes::Bytes, tough::error::Error>>,
+            > { panic!("stream should not have been read") },
+        );
+        let handle_a2 = store.store_stream(a.clone(), failing).await.unwrap();
+        assert_eq!(read_all(&handle_a2).await, b"artifact a");
+
+        // A stream whose contents don't match the hash is rejected.
+        store
+            .store_stream(hash_id_of(b"expected"), stream_of(b"actual"))
+            .await
+            .expect_err("mismatched hash should be rejected");
+
+        // Only repository `a` refers to artifact a.
+        let repo_a = ArtifactHash([1; 32]);
+        store.insert_repo(
+            repo_a,
+            RepoContents {
+                artifacts: BTreeSet::from([a.hash]),
+                nested: BTreeMap::new(),
+                archive: None,
+            },
+        );
+
+        // Nothing is collected while there are handles around.
+        assert_eq!(store.gc(), 0);
+        drop((handle_a, handle_a2, handle_b));
+
+        // Artifact b is no longer in use.
+        assert_eq!(store.gc(), 1);
+        assert!(store.get(a.clone()).is_some());
+        assert!(store.get(b).is_none());
+
+        // Once the repository is forgotten, artifact a goes too.
+        store.retain_repos(|_| false);
+        assert!(store.get(a).is_none());
+
+        logctx.cleanup_successful();
+    }
+
+    #[tokio::test]
+    async fn test_reopen_verifies_artifacts() {
+        let logctx = test_setup_log("test_reopen_verifies_artifacts");
+        let tempdir = camino_tempfile::tempdir().unwrap();
+        let dir = tempdir.path().to_owned();
+
+        let a = hash_id_of(b"artifact a");
+        let b = hash_id_of(b"artifact b");
+        let repo_a = ArtifactHash([1; 32]);
+        let repo_b = ArtifactHash([2; 32]);
+        {
+            let store =
+                ArtifactStore::open(&logctx.log, dir.clone()).await.unwrap();
+            store
+                .store_stream(a.clone(), stream_of(b"artifact a"))
+                .await
+                .unwrap();
+            store
+                .store_stream(b.clone(), stream_of(b"artifact b"))
+                .await
+                .unwrap();
+            for (repo_hash, hash) in [(repo_a, a.hash), (repo_b, b.hash)] {
+                store.insert_repo(
+                    repo_hash,
+                    RepoContents {
+                        artifacts: BTreeSet::from([hash]),
+                        nested: BTreeMap::new(),
+                        archive: None,
+                    },
+                );
+            }
+        }
+
+        // Corrupt artifact b behind the store's back.
+        std::fs::write(
+            dir.join(ARTIFACTS_DIR).join(b.hash.to_string()),
+            b"corrupted",
+        )
+        .unwrap();
+
+        let store =
+            ArtifactStore::open(&logctx.log, dir.clone()).await.unwrap();
+        let handle = store.get(a.clone()).expect("artifact a survived");
+        assert_eq!(read_all(&handle).await, b"artifact a");
+        assert!(store.get(b).is_none(), "corrupt artifact was removed");
+        assert_eq!(
+            store.repos().keys().copied().collect::<Vec<_>>(),
+            vec![repo_a],
+            "repository with a corrupt artifact was forgotten",
+        );
+
+        logctx.cleanup_successful();
+    }
+}
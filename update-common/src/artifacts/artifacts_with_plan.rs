@@ -2,10 +2,13 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use super::ArtifactStore;
 use super::ExtractedArtifactDataHandle;
+use super::RepoContents;
 use super::UpdatePlan;
 use super::UpdatePlanBuildOutput;
 use super::UpdatePlanBuilder;
+use super::REPO_ARCHIVE_KIND;
 use crate::errors::RepositoryError;
 use anyhow::anyhow;
 use bytes::Bytes;
@@ -19,13 +22,12 @@ use omicron_common::api::external::TufRepoMeta;
 use omicron_common::update::ArtifactHash;
 use omicron_common::update::ArtifactHashId;
 use omicron_common::update::ArtifactId;
-use sha2::{Digest, Sha256};
 use slog::info;
 use slog::Logger;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::io;
-use tokio::io::AsyncWriteExt;
+use std::io::Write;
 use tough::TargetName;
 use tufaceous_lib::ArchiveExtractor;
 use tufaceous_lib::OmicronRepo;
@@ -65,52 +67,49 @@ pub struct ArtifactsWithPlan {
 impl ArtifactsWithPlan {
     /// Creates a new `ArtifactsWithPlan` from the given stream of `Bytes`.
     ///
-    /// This method reads the stream representing a TUF repo, and writes it to
-    /// a temporary file. Afterwards, it builds an `ArtifactsWithPlan` from the
-    /// contents of that file.
+    /// This method reads the stream representing a TUF repo into a temporary
+    /// file in `store`, then builds an `ArtifactsWithPlan` from the contents
+    /// of that file, extracting artifacts into `store`. `archive` says whether
+    /// the repo's archive is kept in the store afterwards.
     pub async fn from_stream(
         body: impl Stream<Item = Result<Bytes, HttpError>> + Send,
         file_name: Option<String>,
+        archive: RepoArchive,
+        store: &ArtifactStore,
         log: &Logger,
     ) -> Result<Self, RepositoryError> {
-        // Create a temporary file to store the incoming archive.``
-        let tempfile = tokio::task::spawn_blocking(|| {
-            camino_tempfile::tempfile().map_err(RepositoryError::TempFileCreate)
-        })
-        .await
-        .unwrap()?;
-        let mut tempfile =
-            tokio::io::BufWriter::new(tokio::fs::File::from_std(tempfile));
-
+        // Stream the uploaded body into a temporary file in the store, which
+        // hashes it as it's written. As with artifacts, writes to the file
+        // happen synchronously.
+        let mut tempfile = store.new_tempfile()?;
         let mut body = std::pin::pin!(body);
-
-        // Stream the uploaded body into our tempfile.
-        let mut hasher = Sha256::new();
         while let Some(bytes) = body
             .try_next()
             .await
             .map_err(RepositoryError::ReadChunkFromStream)?
         {
-            hasher.update(&bytes);
             tempfile
                 .write_all(&bytes)
-                .await
                 .map_err(RepositoryError::TempFileWrite)?;
         }
 
-        let repo_hash = ArtifactHash(hasher.finalize().into());
-
-        // Flush writes. We don't need to seek back to the beginning of the file
-        // because extracting the repository will do its own seeking as a part of
-        // unzipping this repo.
-        tempfile.flush().await.map_err(RepositoryError::TempFileFlush)?;
-
-        let tempfile = tempfile.into_inner().into_std().await;
+        // Move the archive into the store. If we don't keep it, nothing refers
+        // to it once we're done here, so the store's next garbage collection
+        // removes it.
+        let archive_handle =
+            store.persist_tempfile(REPO_ARCHIVE_KIND, tempfile)?;
+        let repo_hash = archive_handle.hash();
+        let zip_data = open_stored_archive(store, &archive_handle)?;
 
-        let artifacts_with_plan = Self::from_zip(
-            io::BufReader::new(tempfile),
+        let artifacts_with_plan = Self::from_zip_impl(
+            zip_data,
             file_name,
             repo_hash,
+            match archive {
+                RepoArchive::Keep => Some(repo_hash),
+                RepoArchive::Discard => None,
+            },
+            store,
             log,
         )
         .await?;
@@ -118,10 +117,59 @@ impl ArtifactsWithPlan {
         Ok(artifacts_with_plan)
     }
 
+    /// Rebuilds the `ArtifactsWithPlan` for the repo with hash `repo_hash`
+    /// from the archive kept in `store` when it was uploaded (see
+    /// [`RepoArchive::Keep`]).
+    ///
+    /// The archive is unpacked and checked again as if it had just been
+    /// uploaded, but artifacts that are still in the store aren't copied
+    /// again.
+    pub async fn from_store(
+        repo_hash: ArtifactHash,
+        file_name: Option<String>,
+        store: &ArtifactStore,
+        log: &Logger,
+    ) -> Result<Self, RepositoryError> {
+        let archive_handle = store
+            .repo_archive(repo_hash)
+            .ok_or(RepositoryError::MissingStoredArchive(repo_hash))?;
+        let zip_data = open_stored_archive(store, &archive_handle)?;
+        Self::from_zip_impl(
+            zip_data,
+            file_name,
+            repo_hash,
+            Some(repo_hash),
+            store,
+            log,
+        )
+        .await
+    }
+
+    /// Creates a new `ArtifactsWithPlan` from a zipped TUF repo with hash
+    /// `repo_hash`, extracting artifacts into `store`.
+    ///
+    /// On success, the artifacts extracted are recorded in `store` as
+    /// belonging to `repo_hash`.
     pub async fn from_zip<T>(
         zip_data: T,
         file_name: Option<String>,
         repo_hash: ArtifactHash,
+        store: &ArtifactStore,
+        log: &Logger,
+    ) -> Result<Self, RepositoryError>
+    where
+        T: io::Read + io::Seek + Send + 'static,
+    {
+        Self::from_zip_impl(zip_data, file_name, repo_hash, None, store, log)
+            .await
+    }
+
+    async fn from_zip_impl<T>(
+        zip_data: T,
+        file_name: Option<String>,
+        repo_hash: ArtifactHash,
+        archive: Option<ArtifactHash>,
+        store: &ArtifactStore,
         log: &Logger,
     ) -> Result<Self, RepositoryError>
     where
@@ -155,6 +203,11 @@ impl ArtifactsWithPlan {
         //
         // XXX we aren't checking against a root of trust at this point --
         // anyone can sign the repositories and this code will accept that.
+        //
+        // Loading starts from the archive's `1.root.json` and follows any
+        // later root versions in the archive, each of which must be signed by
+        // the version before it; that's how repositories signed after a key
+        // rotation are accepted.
         let repository =
             OmicronRepo::load_untrusted_ignore_expiration(log, dir.path())
                 .await
@@ -165,23 +218,28 @@ impl ArtifactsWithPlan {
             .await
             .map_err(RepositoryError::ReadArtifactsDocument)?;
 
-        // Create another temporary directory where we'll "permanently" (as long
-        // as this plan is in use) hold extracted artifacts we need; most of
-        // these are just direct copies of artifacts we just unpacked into
-        // `dir`, but we'll also unpack nested artifacts like the RoT dual A/B
-        // archives.
-        let mut builder =
-            UpdatePlanBuilder::new(artifacts.system_version.clone(), log)?;
+        // Copy the extracted artifacts we need into the artifact store, which
+        // holds onto them for as long as this plan is in use (or, once we've
+        // recorded this repo's contents below, until the store is told to
+        // forget this repo). Most of these are just direct copies of
+        // artifacts we just unpacked into `dir`, but we'll also unpack nested
+        // artifacts like the RoT dual A/B archives. Artifacts that the store
+        // already has from a previous upload aren't copied again.
+        let mut builder = UpdatePlanBuilder::new(
+            artifacts.system_version.clone(),
+            store,
+            log,
+        );
 
         // Make a pass through each artifact in the repo. For each artifact, we
         // do one of the following:
         //
         // 1. Ignore it (if it's of an artifact kind we don't understand)
-        // 2. Add it directly to tempdir managed by `extracted_artifacts`; we'll
-        //    keep a handle to any such file and use it later. (SP images fall
-        //    into this category.)
-        // 3. Unpack its contents and copy inner artifacts into the tempdir
-        //    managed by `extracted_artifacts`. (RoT artifacts and OS images
+        // 2. Add it directly to the artifact store; we'll keep a handle to
+        //    any such file and use it later. (SP images fall into this
+        //    category.)
+        // 3. Unpack its contents and copy inner artifacts into the artifact
+        //    store. (RoT artifacts and OS images
         //    fall into this category: RoT artifacts themselves contain A and B
         //    hubris archives, and OS images artifacts contain separate phase1
         //    and phase2 blobs.)
@@ -240,8 +298,14 @@ impl ArtifactsWithPlan {
 
         // Ensure we know how to apply updates from this set of artifacts; we'll
         // remember the plan we create.
-        let UpdatePlanBuildOutput { plan, by_id, by_hash, artifacts_meta } =
-            builder.build()?;
+        let UpdatePlanBuildOutput {
+            plan,
+            by_id,
+            by_hash,
+            artifacts_meta,
+            repo_contents,
+        } = builder.build()?;
+        store.insert_repo(repo_hash, RepoContents { archive, ..repo_contents });
 
         let tuf_repository = repository.repo();
 
@@ -297,6 +361,28 @@ impl ArtifactsWithPlan {
     }
 }
 
+/// What to do with the archive of an uploaded repo once its artifacts have
+/// been extracted
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum RepoArchive {
+    /// Keep the archive in the artifact store for as long as the repo is
+    /// recorded there, so that the repo can be rebuilt with
+    /// [`ArtifactsWithPlan::from_store`] (e.g., after a restart).
+    Keep,
+    /// Don't keep the archive.
+    Discard,
+}
+
+fn open_stored_archive(
+    store: &ArtifactStore,
+    archive_handle: &ExtractedArtifactDataHandle,
+) -> Result<io::BufReader<std::fs::File>, RepositoryError> {
+    let path = store.artifact_path(&archive_handle.hash());
+    let file = std::fs::File::open(&path)
+        .map_err(|error| RepositoryError::OpenStoredArchive { path, error })?;
+    Ok(io::BufReader::new(file))
+}
+
 fn unzip_into_tempdir<T>(
     zip_data: T,
     log: &Logger,
@@ -325,12 +411,15 @@ mod tests {
     use anyhow::{Context, Result};
     use camino::Utf8Path;
     use camino_tempfile::Utf8TempDir;
+    use chrono::{Days, Utc};
     use clap::Parser;
     use omicron_common::{
         api::internal::nexus::KnownArtifactKind, update::ArtifactKind,
     };
     use omicron_test_utils::dev::test_setup_log;
+    use sha2::{Digest, Sha256};
     use std::{collections::BTreeSet, time::Duration};
+    use tufaceous_lib::{Key, RootKeys, RootRole};
 
     /// Test that `ArtifactsWithPlan` can extract the fake repository generated
     /// by tufaceous.
@@ -347,8 +436,10 @@ mod tests {
         create_fake_archive(&logctx.log, &archive_path).await?;
 
         // Now check that it can be read by the archive extractor.
+        let store = ArtifactStore::temporary(&logctx.log)?;
         let plan =
-            build_artifacts_with_plan(&logctx.log, &archive_path).await?;
+            build_artifacts_with_plan(&logctx.log, &archive_path, &store)
+                .await?;
         // Check that all known artifact kinds are present in the map.
         let by_id_kinds: BTreeSet<_> =
             plan.by_id().keys().map(|id| id.kind.clone()).collect();
@@ -432,8 +523,10 @@ mod tests {
 
         // Create the archive and build a plan from it.
         create_fake_archive(&logctx.log, &archive_path).await?;
+        let store = ArtifactStore::temporary(&logctx.log)?;
         let mut plan1 =
-            build_artifacts_with_plan(&logctx.log, &archive_path).await?;
+            build_artifacts_with_plan(&logctx.log, &archive_path, &store)
+                .await?;
 
         // Add a 2 second delay to ensure that if we bake any second-based
         // timestamps in, that they end up being different from those in the
@@ -443,7 +536,8 @@ mod tests {
         let archive2_path = temp_dir.path().join("archive2.zip");
         create_fake_archive(&logctx.log, &archive2_path).await?;
         let mut plan2 =
-            build_artifacts_with_plan(&logctx.log, &archive2_path).await?;
+            build_artifacts_with_plan(&logctx.log, &archive2_path, &store)
+                .await?;
 
         // At the moment, the repo .zip itself doesn't match because it bakes
         // in timestamps. However, the artifacts inside should match exactly.
@@ -455,6 +549,137 @@ mod tests {
             "artifacts match"
         );
 
+        // Both repos should refer to the same set of artifacts in the store.
+        let repos = store.repos();
+        assert_eq!(repos.len(), 2, "both repos are recorded: {repos:?}");
+        let mut contents = repos.values();
+        assert_eq!(contents.next(), contents.next(), "repo contents match");
+
+        logctx.cleanup_successful();
+
+        Ok(())
+    }
+
+    /// Test that a repo uploaded with `RepoArchive::Keep` can be rebuilt from
+    /// the store once it's reopened, and that an archive that isn't kept is
+    /// collected.
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_rebuild_from_store() -> Result<()> {
+        let logctx = test_setup_log("test_rebuild_from_store");
+        let temp_dir = Utf8TempDir::new()?;
+        let archive_path = temp_dir.path().join("archive.zip");
+        create_fake_archive(&logctx.log, &archive_path).await?;
+        let zip_bytes = Bytes::from(std::fs::read(&archive_path)?);
+        let file_name = Some("archive.zip".to_owned());
+        let store_dir = temp_dir.path().join("store");
+
+        let mut description = {
+            let store =
+                ArtifactStore::open(&logctx.log, store_dir.clone()).await?;
+            let plan = ArtifactsWithPlan::from_stream(
+                futures::stream::iter([Ok(zip_bytes.clone())]),
+                file_name.clone(),
+                RepoArchive::Keep,
+                &store,
+                &logctx.log,
+            )
+            .await?;
+            plan.description().clone()
+        };
+        let repo_hash = description.repo.hash;
+
+        let store = ArtifactStore::open(&logctx.log, store_dir).await?;
+        assert_eq!(store.repos()[&repo_hash].archive, Some(repo_hash));
+        let mut plan = ArtifactsWithPlan::from_store(
+            repo_hash,
+            file_name.clone(),
+            &store,
+            &logctx.log,
+        )
+        .await?;
+        description.sort_artifacts();
+        plan.description.sort_artifacts();
+        assert_eq!(plan.description, description, "rebuilt repo matches");
+
+        // Without `RepoArchive::Keep`, there's nothing to rebuild from.
+        let store = ArtifactStore::temporary(&logctx.log)?;
+        ArtifactsWithPlan::from_stream(
+            futures::stream::iter([Ok(zip_bytes)]),
+            file_name.clone(),
+            RepoArchive::Discard,
+            &store,
+            &logctx.log,
+        )
+        .await?;
+        store.gc();
+        let archive_hash_id =
+            ArtifactHashId { kind: REPO_ARCHIVE_KIND, hash: repo_hash };
+        assert!(store.get(archive_hash_id).is_none(), "archive was collected");
+        ArtifactsWithPlan::from_store(
+            repo_hash,
+            file_name,
+            &store,
+            &logctx.log,
+        )
+        .await
+        .expect_err("repo can't be rebuilt without its archive");
+
+        logctx.cleanup_successful();
+
+        Ok(())
+    }
+
+    /// Test that a repository signed after rotating its root keys, and
+    /// carrying both root versions, is accepted.
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn test_rotated_root() -> Result<()> {
+        let logctx = test_setup_log("test_rotated_root");
+        let temp_dir = Utf8TempDir::new()?;
+        let [old_key, new_key] = [(); 2].map(|()| Key::generate_ed25519());
+        let expiry = Utc::now() + Days::new(1);
+
+        let root1 = RootRole::generate(
+            &RootKeys::any_of(&[old_key.clone()])?,
+            &[old_key.clone()],
+            expiry,
+        )
+        .await?;
+        let root2 = root1
+            .rotate(
+                &RootKeys::any_of(&[new_key.clone()])?,
+                &[old_key, new_key.clone()],
+                expiry,
+            )
+            .await?;
+        let root1_path = temp_dir.path().join("1.root.json");
+        let root2_path = temp_dir.path().join("2.root.json");
+        root1.write(&root1_path)?;
+        root2.write(&root2_path)?;
+
+        let archive_path = temp_dir.path().join("archive.zip");
+        let args = tufaceous::Args::try_parse_from([
+            "tufaceous",
+            "--key",
+            &new_key.to_string(),
+            "assemble",
+            "../tufaceous/manifests/fake.toml",
+            archive_path.as_str(),
+            "--root",
+            root1_path.as_str(),
+            "--root",
+            root2_path.as_str(),
+        ])
+        .context("error parsing args")?;
+        args.exec(&logctx.log)
+            .await
+            .context("error executing assemble command")?;
+
+        let store = ArtifactStore::temporary(&logctx.log)?;
+        let plan =
+            build_artifacts_with_plan(&logctx.log, &archive_path, &store)
+                .await?;
+        assert!(!plan.description().artifacts.is_empty());
+
         logctx.cleanup_successful();
 
         Ok(())
@@ -480,15 +705,22 @@ mod tests {
     async fn build_artifacts_with_plan(
         log: &slog::Logger,
         archive_path: &Utf8Path,
+        store: &ArtifactStore,
     ) -> Result<ArtifactsWithPlan> {
-        let zip_bytes = std::fs::File::open(&archive_path)
-            .context("error opening archive.zip")?;
-        // We could also compute the hash from the file here, but the repo hash
-        // doesn't matter for the test.
-        let repo_hash = ArtifactHash([0u8; 32]);
-        let plan = ArtifactsWithPlan::from_zip(zip_bytes, None, repo_hash, log)
-            .await
-            .with_context(|| format!("error reading {archive_path}"))?;
+        let zip_bytes =
+            std::fs::read(archive_path).context("error reading archive.zip")?;
+        // The store records artifacts by repo hash, so distinct archives need
+        // distinct hashes.
+        let repo_hash = ArtifactHash(Sha256::digest(&zip_bytes).into());
+        let plan = ArtifactsWithPlan::from_zip(
+            io::Cursor::new(zip_bytes),
+            None,
+            repo_hash,
+            store,
+            log,
+        )
+        .await
+        .with_context(|| format!("error reading {archive_path}"))?;
 
         Ok(plan)
     }
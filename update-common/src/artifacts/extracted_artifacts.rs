@@ -2,25 +2,25 @@
 // License, v. 2.0. If a copy of the MPL was not distributed with this
 // file, You can obtain one at https://mozilla.org/MPL/2.0/.
 
+use super::ArtifactPin;
+use super::ArtifactStore;
+use super::RepoContents;
 use crate::errors::RepositoryError;
 use anyhow::Context;
-use camino::Utf8PathBuf;
 use camino_tempfile::NamedUtf8TempFile;
-use camino_tempfile::Utf8TempDir;
 use futures::Stream;
-use futures::StreamExt;
 use omicron_common::update::ArtifactHash;
 use omicron_common::update::ArtifactHashId;
 use omicron_common::update::ArtifactKind;
 use sha2::Digest;
 use sha2::Sha256;
-use slog::info;
-use slog::Logger;
 use std::io;
 use std::io::Write;
+use std::ops::Range;
 use std::sync::Arc;
 use tokio::io::AsyncRead;
-use tokio::io::AsyncWriteExt;
+use tokio::io::AsyncReadExt;
+use tokio::io::AsyncSeekExt;
 use tokio_util::io::ReaderStream;
 
 /// Handle to the data of an extracted artifact.
@@ -30,27 +30,26 @@ use tokio_util::io::ReaderStream;
 ///
 /// Note that although this type implements `Clone` and that cloning is
 /// relatively cheap, it has additional implications on filesystem cleanup.
-/// `ExtractedArtifactDataHandle`s point to a file in a temporary directory
-/// created when a TUF repo is uploaded. That directory contains _all_
-/// extracted artifacts from the TUF repo, and the directory will not be
-/// cleaned up until all `ExtractedArtifactDataHandle`s that refer to files
-/// inside it have been dropped. Therefore, one must be careful not to squirrel
-/// away unneeded clones of `ExtractedArtifactDataHandle`s: only clone this in
-/// contexts where you need the data and need the temporary directory containing
-/// it to stick around.
+/// `ExtractedArtifactDataHandle`s point to a file in an [`ArtifactStore`], and
+/// while any handle to an artifact exists, the store will not garbage collect
+/// it, even if no repository refers to it anymore. Therefore, one must be
+/// careful not to squirrel away unneeded clones of
+/// `ExtractedArtifactDataHandle`s: only clone this in contexts where you need
+/// the data and need the file containing it to stick around.
 #[derive(Debug, Clone)]
 pub struct ExtractedArtifactDataHandle {
-    tempdir: Arc<Utf8TempDir>,
+    store: ArtifactStore,
+    _pin: Arc<ArtifactPin>,
     file_size: usize,
     hash_id: ArtifactHashId,
 }
 
-// We implement this by hand to use `Arc::ptr_eq`, because `Utf8TempDir`
-// (sensibly!) does not implement `PartialEq`. We only use it it in tests.
+// We implement this by hand to use `ArtifactStore::ptr_eq`. We only use it it
+// in tests.
 #[cfg(test)]
 impl PartialEq for ExtractedArtifactDataHandle {
     fn eq(&self, other: &Self) -> bool {
-        Arc::ptr_eq(&self.tempdir, &other.tempdir)
+        self.store.ptr_eq(&other.store)
             && self.file_size == other.file_size
             && self.hash_id == other.hash_id
     }
@@ -60,6 +59,15 @@ impl PartialEq for ExtractedArtifactDataHandle {
 impl Eq for ExtractedArtifactDataHandle {}
 
 impl ExtractedArtifactDataHandle {
+    pub(super) fn new(
+        store: ArtifactStore,
+        pin: Arc<ArtifactPin>,
+        file_size: usize,
+        hash_id: ArtifactHashId,
+    ) -> Self {
+        Self { store, _pin: pin, file_size, hash_id }
+    }
+
     /// File size of this artifact in bytes.
     pub fn file_size(&self) -> usize {
         self.file_size
@@ -72,11 +80,11 @@ impl ExtractedArtifactDataHandle {
     /// Async stream to read the contents of this artifact on demand.
     ///
     /// This can fail due to I/O errors outside our control (e.g., something
-    /// removed the contents of our temporary directory).
+    /// removed the contents of the artifact store).
     pub async fn reader_stream(
         &self,
     ) -> anyhow::Result<ReaderStream<impl AsyncRead>> {
-        let path = path_for_artifact(&self.tempdir, &self.hash_id);
+        let path = self.store.artifact_path(&self.hash_id.hash);
 
         let file = tokio::fs::File::open(&path)
             .await
@@ -84,103 +92,62 @@ impl ExtractedArtifactDataHandle {
 
         Ok(ReaderStream::new(file))
     }
+
+    /// Async stream to read the bytes of this artifact within `range`.
+    ///
+    /// Any part of `range` past the end of the artifact is ignored.  Like
+    /// `reader_stream()`, this can fail due to I/O errors outside our control.
+    pub async fn range_reader_stream(
+        &self,
+        range: Range<u64>,
+    ) -> anyhow::Result<ReaderStream<impl AsyncRead>> {
+        let path = self.store.artifact_path(&self.hash_id.hash);
+
+        let mut file = tokio::fs::File::open(&path)
+            .await
+            .with_context(|| format!("failed to open {path}"))?;
+        file.seek(io::SeekFrom::Start(range.start)).await.with_context(
+            || format!("failed to seek to offset {} in {path}", range.start),
+        )?;
+
+        Ok(ReaderStream::new(file.take(range.end.saturating_sub(range.start))))
+    }
 }
 
-/// `ExtractedArtifacts` is a temporary wrapper around a `Utf8TempDir` for use
-/// when ingesting a new TUF repository.
+/// `ExtractedArtifacts` is a wrapper around an [`ArtifactStore`] for use when
+/// ingesting a new TUF repository.
 ///
-/// It provides methods to copy artifacts into the tempdir (`store` and the
-/// combo of `new_tempfile` + `store_tempfile`) that return
-/// `ExtractedArtifactDataHandle`. The handles keep shared references to the
-/// `Utf8TempDir`, so it will not be removed until all handles are dropped
-/// (e.g., when a new TUF repository is uploaded). The handles can be used to
-/// on-demand read files that were copied into the temp dir during ingest.
+/// It provides methods to copy artifacts into the store (`store` and the combo
+/// of `new_tempfile` + `store_tempfile`) that return
+/// `ExtractedArtifactDataHandle`s, and it keeps track of every artifact copied
+/// so that the repository's [`RepoContents`] can be recorded in the store once
+/// ingest is complete.
 #[derive(Debug)]
 pub struct ExtractedArtifacts {
-    // Directory in which we store extracted artifacts. This is currently a
-    // single flat directory with files named by artifact hash; we don't expect
-    // more than a few dozen files total, so no need to nest directories.
-    tempdir: Arc<Utf8TempDir>,
+    store: ArtifactStore,
+    contents: RepoContents,
 }
 
 impl ExtractedArtifacts {
-    pub fn new(log: &Logger) -> Result<Self, RepositoryError> {
-        let tempdir = camino_tempfile::Builder::new()
-            .prefix("update-artifacts.")
-            .tempdir()
-            .map_err(RepositoryError::TempDirCreate)?;
-        info!(
-            log, "created directory to store extracted artifacts";
-            "path" => %tempdir.path(),
-        );
-        Ok(Self { tempdir: Arc::new(tempdir) })
-    }
-
-    fn path_for_artifact(
-        &self,
-        artifact_hash_id: &ArtifactHashId,
-    ) -> Utf8PathBuf {
-        self.tempdir.path().join(format!("{}", artifact_hash_id.hash))
+    pub fn new(store: &ArtifactStore) -> Self {
+        Self { store: store.clone(), contents: RepoContents::default() }
     }
 
-    /// Copy from `stream` into our temp directory, returning a handle to the
+    /// Copy from `stream` into the artifact store, returning a handle to the
     /// extracted artifact on success.
+    ///
+    /// If the store already has this artifact, `stream` is not read.
     pub async fn store(
         &mut self,
         artifact_hash_id: ArtifactHashId,
         stream: impl Stream<Item = Result<bytes::Bytes, tough::error::Error>>,
     ) -> Result<ExtractedArtifactDataHandle, RepositoryError> {
-        let output_path = self.path_for_artifact(&artifact_hash_id);
-
-        let mut writer = tokio::io::BufWriter::new(
-            tokio::fs::File::create(&output_path)
-                .await
-                .with_context(|| {
-                    format!("failed to create temp file {output_path}")
-                })
-                .map_err(|error| RepositoryError::CopyExtractedArtifact {
-                    kind: artifact_hash_id.kind.clone(),
-                    error,
-                })?,
-        );
-
-        let mut stream = std::pin::pin!(stream);
-
-        let mut file_size = 0;
-
-        while let Some(res) = stream.next().await {
-            let chunk = res.map_err(|error| RepositoryError::ReadArtifact {
-                kind: artifact_hash_id.kind.clone(),
-                error: Box::new(error),
-            })?;
-            file_size += chunk.len();
-            writer
-                .write_all(&chunk)
-                .await
-                .with_context(|| format!("failed writing to {output_path}"))
-                .map_err(|error| RepositoryError::CopyExtractedArtifact {
-                    kind: artifact_hash_id.kind.clone(),
-                    error,
-                })?;
-        }
-
-        writer
-            .flush()
-            .await
-            .with_context(|| format!("failed flushing {output_path}"))
-            .map_err(|error| RepositoryError::CopyExtractedArtifact {
-                kind: artifact_hash_id.kind.clone(),
-                error,
-            })?;
-
-        Ok(ExtractedArtifactDataHandle {
-            tempdir: Arc::clone(&self.tempdir),
-            file_size,
-            hash_id: artifact_hash_id,
-        })
+        let handle = self.store.store_stream(artifact_hash_id, stream).await?;
+        self.contents.artifacts.insert(handle.hash());
+        Ok(handle)
     }
 
-    /// Create a new temporary file inside this temporary directory.
+    /// Create a new temporary file inside the artifact store.
     ///
     /// As the returned file is written to, the data will be hashed; once
     /// writing is complete, call [`ExtractedArtifacts::store_tempfile()`] to
@@ -188,71 +155,57 @@ impl ExtractedArtifacts {
     pub fn new_tempfile(
         &self,
     ) -> Result<HashingNamedUtf8TempFile, RepositoryError> {
-        let file = NamedUtf8TempFile::new_in(self.tempdir.path()).map_err(
-            |error| RepositoryError::NamedTempFileCreate {
-                path: self.tempdir.path().to_owned(),
-                error,
-            },
-        )?;
-        Ok(HashingNamedUtf8TempFile {
-            file: io::BufWriter::new(file),
-            hasher: Sha256::new(),
-            bytes_written: 0,
-        })
+        self.store.new_tempfile()
     }
 
     /// Persist a temporary file that was returned by
     /// [`ExtractedArtifacts::new_tempfile()`] as an extracted artifact.
     pub fn store_tempfile(
-        &self,
+        &mut self,
         kind: ArtifactKind,
         file: HashingNamedUtf8TempFile,
     ) -> Result<ExtractedArtifactDataHandle, RepositoryError> {
-        let HashingNamedUtf8TempFile { file, hasher, bytes_written } = file;
-
-        // We don't need to `.flush()` explicitly: `into_inner()` does that for
-        // us.
-        let file = file
-            .into_inner()
-            .context("failed to flush temp file")
-            .map_err(|error| RepositoryError::CopyExtractedArtifact {
-                kind: kind.clone(),
-                error,
-            })?;
+        let handle = self.store.persist_tempfile(kind, file)?;
+        self.contents.artifacts.insert(handle.hash());
+        Ok(handle)
+    }
 
-        let hash = ArtifactHash(hasher.finalize().into());
-        let artifact_hash_id = ArtifactHashId { kind, hash };
-        let output_path = self.path_for_artifact(&artifact_hash_id);
-        file.persist(&output_path)
-            .map_err(|error| error.error)
-            .with_context(|| {
-                format!("failed to persist temp file to {output_path}")
-            })
-            .map_err(|error| RepositoryError::CopyExtractedArtifact {
-                kind: artifact_hash_id.kind.clone(),
-                error,
-            })?;
+    /// Returns the pair of artifacts that were previously extracted from the
+    /// nested archive with hash `outer_hash`, if the store still has both.
+    pub fn nested_pair(
+        &mut self,
+        kind: ArtifactKind,
+        outer_hash: ArtifactHash,
+    ) -> Option<(ExtractedArtifactDataHandle, ExtractedArtifactDataHandle)>
+    {
+        let (image1, image2) = self.store.nested_pair(kind, outer_hash)?;
+        self.record_nested_pair(outer_hash, &image1, &image2);
+        Some((image1, image2))
+    }
 
-        Ok(ExtractedArtifactDataHandle {
-            tempdir: Arc::clone(&self.tempdir),
-            file_size: bytes_written,
-            hash_id: artifact_hash_id,
-        })
+    /// Records that `image1` and `image2` were extracted from the nested
+    /// archive with hash `outer_hash`.
+    pub fn record_nested_pair(
+        &mut self,
+        outer_hash: ArtifactHash,
+        image1: &ExtractedArtifactDataHandle,
+        image2: &ExtractedArtifactDataHandle,
+    ) {
+        self.contents.artifacts.extend([image1.hash(), image2.hash()]);
+        self.contents.nested.insert(outer_hash, [image1.hash(), image2.hash()]);
     }
-}
 
-fn path_for_artifact(
-    tempdir: &Utf8TempDir,
-    artifact_hash_id: &ArtifactHashId,
-) -> Utf8PathBuf {
-    tempdir.path().join(format!("{}", artifact_hash_id.hash))
+    /// Returns the artifacts extracted so far.
+    pub fn into_contents(self) -> RepoContents {
+        self.contents
+    }
 }
 
 // Wrapper around a `NamedUtf8TempFile` that hashes contents as they're written.
 pub struct HashingNamedUtf8TempFile {
-    file: io::BufWriter<NamedUtf8TempFile>,
-    hasher: Sha256,
-    bytes_written: usize,
+    pub(super) file: io::BufWriter<NamedUtf8TempFile>,
+    pub(super) hasher: Sha256,
+    pub(super) bytes_written: usize,
 }
 
 impl Write for HashingNamedUtf8TempFile {
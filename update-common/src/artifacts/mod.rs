@@ -4,11 +4,13 @@
 
 //! Types to represent update artifacts.
 
+mod artifact_store;
 mod artifact_types;
 mod artifacts_with_plan;
 mod extracted_artifacts;
 mod update_plan;
 
+pub use artifact_store::*;
 pub use artifact_types::*;
 pub use artifacts_with_plan::*;
 pub use extracted_artifacts::*;
@@ -10,10 +10,12 @@
 //! elsewhere.
 
 use super::ArtifactIdData;
+use super::ArtifactStore;
 use super::Board;
 use super::ExtractedArtifactDataHandle;
 use super::ExtractedArtifacts;
 use super::HashingNamedUtf8TempFile;
+use super::RepoContents;
 use crate::errors::RepositoryError;
 use bytes::Bytes;
 use futures::Stream;
@@ -135,10 +137,11 @@ pub struct UpdatePlanBuilder<'a> {
 impl<'a> UpdatePlanBuilder<'a> {
     pub fn new(
         system_version: SemverVersion,
+        store: &ArtifactStore,
         log: &'a Logger,
-    ) -> Result<Self, RepositoryError> {
-        let extracted_artifacts = ExtractedArtifacts::new(log)?;
-        Ok(Self {
+    ) -> Self {
+        let extracted_artifacts = ExtractedArtifacts::new(store);
+        Self {
             system_version,
             gimlet_sp: BTreeMap::new(),
             gimlet_rot_a: Vec::new(),
@@ -162,7 +165,7 @@ impl<'a> UpdatePlanBuilder<'a> {
 
             extracted_artifacts,
             log,
-        })
+        }
     }
 
     /// Adds an artifact with these contents to the by_id and by_hash maps.
@@ -199,13 +202,19 @@ impl<'a> UpdatePlanBuilder<'a> {
             KnownArtifactKind::GimletRot
             | KnownArtifactKind::PscRot
             | KnownArtifactKind::SwitchRot => {
-                self.add_rot_artifact(artifact_id, artifact_kind, stream).await
+                self.add_rot_artifact(
+                    artifact_id,
+                    artifact_kind,
+                    artifact_hash,
+                    stream,
+                )
+                .await
             }
             KnownArtifactKind::Host => {
-                self.add_host_artifact(artifact_id, stream)
+                self.add_host_artifact(artifact_id, artifact_hash, stream)
             }
             KnownArtifactKind::Trampoline => {
-                self.add_trampoline_artifact(artifact_id, stream)
+                self.add_trampoline_artifact(artifact_id, artifact_hash, stream)
             }
             KnownArtifactKind::ControlPlane => {
                 self.add_control_plane_artifact(
@@ -292,6 +301,7 @@ impl<'a> UpdatePlanBuilder<'a> {
         &mut self,
         artifact_id: ArtifactId,
         artifact_kind: KnownArtifactKind,
+        artifact_hash: ArtifactHash,
         stream: impl Stream<Item = Result<bytes::Bytes, tough::error::Error>> + Send,
     ) -> Result<(), RepositoryError> {
         let (rot_a, rot_a_kind, rot_b, rot_b_kind) = match artifact_kind {
@@ -326,6 +336,7 @@ impl<'a> UpdatePlanBuilder<'a> {
             stream,
             &mut self.extracted_artifacts,
             artifact_kind,
+            artifact_hash,
             |reader, out_a, out_b| {
                 RotArchives::extract_into(reader, out_a, out_b)
             },
@@ -420,6 +431,7 @@ impl<'a> UpdatePlanBuilder<'a> {
     fn add_host_artifact(
         &mut self,
         artifact_id: ArtifactId,
+        artifact_hash: ArtifactHash,
         stream: impl Stream<Item = Result<bytes::Bytes, tough::error::Error>> + Send,
     ) -> Result<(), RepositoryError> {
         if self.host_phase_1.is_some() || self.host_phase_2_hash.is_some() {
@@ -432,6 +444,7 @@ impl<'a> UpdatePlanBuilder<'a> {
             stream,
             &mut self.extracted_artifacts,
             KnownArtifactKind::Host,
+            artifact_hash,
             |reader, out_1, out_2| {
                 HostPhaseImages::extract_into(reader, out_1, out_2)
             },
@@ -468,6 +481,7 @@ impl<'a> UpdatePlanBuilder<'a> {
     fn add_trampoline_artifact(
         &mut self,
         artifact_id: ArtifactId,
+        artifact_hash: ArtifactHash,
         stream: impl Stream<Item = Result<bytes::Bytes, tough::error::Error>> + Send,
     ) -> Result<(), RepositoryError> {
         if self.trampoline_phase_1.is_some()
@@ -482,6 +496,7 @@ impl<'a> UpdatePlanBuilder<'a> {
             stream,
             &mut self.extracted_artifacts,
             KnownArtifactKind::Trampoline,
+            artifact_hash,
             |reader, out_1, out_2| {
                 HostPhaseImages::extract_into(reader, out_1, out_2)
             },
@@ -599,10 +614,15 @@ impl<'a> UpdatePlanBuilder<'a> {
     ///
     /// Depending on how things shake out, we may want to revisit this in the
     /// future.
+    ///
+    /// If the artifact store already has the pair extracted from an artifact
+    /// with hash `outer_hash` (e.g., because the same artifact was part of a
+    /// previously-uploaded repository), `stream` is not read at all.
     fn extract_nested_artifact_pair<F>(
         stream: impl Stream<Item = Result<bytes::Bytes, tough::error::Error>> + Send,
         extracted_artifacts: &mut ExtractedArtifacts,
         kind: KnownArtifactKind,
+        outer_hash: ArtifactHash,
         extract: F,
     ) -> Result<
         (ExtractedArtifactDataHandle, ExtractedArtifactDataHandle),
@@ -616,6 +636,12 @@ impl<'a> UpdatePlanBuilder<'a> {
             ) -> anyhow::Result<()>
             + Send,
     {
+        if let Some(pair) =
+            extracted_artifacts.nested_pair(kind.into(), outer_hash)
+        {
+            return Ok(pair);
+        }
+
         // Since stream isn't guaranteed to be 'static, we have to use
         // block_in_place here, not spawn_blocking. This does mean that the
         // current task is taken over, and that this function can only be used
@@ -644,11 +670,14 @@ impl<'a> UpdatePlanBuilder<'a> {
             // context.
             let mut reader = tokio_util::io::SyncIoBridge::new(reader);
 
-            Self::extract_nested_artifact_pair_impl(
+            let (image1, image2) = Self::extract_nested_artifact_pair_impl(
                 extracted_artifacts,
                 kind,
                 |out_a, out_b| extract(&mut reader, out_a, out_b),
-            )
+            )?;
+            extracted_artifacts
+                .record_nested_pair(outer_hash, &image1, &image2);
+            Ok((image1, image2))
         })
     }
 
@@ -843,6 +872,7 @@ impl<'a> UpdatePlanBuilder<'a> {
             by_id: self.by_id,
             by_hash: self.by_hash,
             artifacts_meta: self.artifacts_meta,
+            repo_contents: self.extracted_artifacts.into_contents(),
         })
     }
 }
@@ -853,6 +883,7 @@ pub struct UpdatePlanBuildOutput {
     pub by_id: BTreeMap<ArtifactId, Vec<ArtifactHashId>>,
     pub by_hash: HashMap<ArtifactHashId, ExtractedArtifactDataHandle>,
     pub artifacts_meta: Vec<TufArtifactMeta>,
+    pub repo_contents: RepoContents,
 }
 
 // We take id solely to be able to output error messages
@@ -1042,8 +1073,9 @@ mod tests {
 
         let logctx = test_setup_log("test_multi_rot_version");
 
+        let store = ArtifactStore::temporary(&logctx.log).unwrap();
         let mut plan_builder =
-            UpdatePlanBuilder::new(VERSION_0, &logctx.log).unwrap();
+            UpdatePlanBuilder::new(VERSION_0, &store, &logctx.log);
 
         // The control plane artifact can be arbitrary bytes; just populate it
         // with random data.
@@ -1175,9 +1207,12 @@ mod tests {
 
         let logctx = test_setup_log("test_multi_rot_version");
 
-        let mut plan_builder =
-            UpdatePlanBuilder::new("0.0.0".parse().unwrap(), &logctx.log)
-                .unwrap();
+        let store = ArtifactStore::temporary(&logctx.log).unwrap();
+        let mut plan_builder = UpdatePlanBuilder::new(
+            "0.0.0".parse().unwrap(),
+            &store,
+            &logctx.log,
+        );
 
         // The control plane artifact can be arbitrary bytes; just populate it
         // with random data.
@@ -1325,9 +1360,12 @@ mod tests {
 
         let logctx = test_setup_log("test_update_plan_from_artifacts");
 
-        let mut plan_builder =
-            UpdatePlanBuilder::new("0.0.0".parse().unwrap(), &logctx.log)
-                .unwrap();
+        let store = ArtifactStore::temporary(&logctx.log).unwrap();
+        let mut plan_builder = UpdatePlanBuilder::new(
+            "0.0.0".parse().unwrap(),
+            &store,
+            &logctx.log,
+        );
 
         // Add a couple artifacts with kinds wicketd/nexus don't understand; it
         // should still ingest and serve them.
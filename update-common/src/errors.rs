@@ -9,7 +9,9 @@ use display_error_chain::DisplayErrorChain;
 use dropshot::HttpError;
 use omicron_common::api::external::SemverVersion;
 use omicron_common::api::internal::nexus::KnownArtifactKind;
-use omicron_common::update::{ArtifactHashId, ArtifactId, ArtifactKind};
+use omicron_common::update::{
+    ArtifactHash, ArtifactHashId, ArtifactId, ArtifactKind,
+};
 use slog::error;
 use thiserror::Error;
 
@@ -40,6 +42,16 @@ pub enum RepositoryError {
         error: std::io::Error,
     },
 
+    #[error("no archive kept in the artifact store for repository {0}")]
+    MissingStoredArchive(ArtifactHash),
+
+    #[error("error opening repository archive {path}")]
+    OpenStoredArchive {
+        path: Utf8PathBuf,
+        #[source]
+        error: std::io::Error,
+    },
+
     #[error("error extracting repository")]
     Extract(#[source] anyhow::Error),
 
@@ -162,6 +174,8 @@ impl RepositoryError {
             | RepositoryError::TempFileWrite(_)
             | RepositoryError::TempFileFlush(_)
             | RepositoryError::NamedTempFileCreate { .. }
+            | RepositoryError::MissingStoredArchive(_)
+            | RepositoryError::OpenStoredArchive { .. }
             | RepositoryError::ReadExtractedArchive { .. }
             | RepositoryError::CreateReaderStream { .. } => {
                 HttpError::for_unavail(None, message)
@@ -209,3 +223,23 @@ impl RepositoryError {
         }
     }
 }
+
+#[derive(Debug, Error)]
+pub enum ArtifactStoreError {
+    #[error("error creating temporary directory")]
+    TempDirCreate(#[source] std::io::Error),
+
+    #[error("error creating directory {path}")]
+    CreateDir {
+        path: Utf8PathBuf,
+        #[source]
+        error: std::io::Error,
+    },
+
+    #[error("error reading directory {path}")]
+    ReadDir {
+        path: Utf8PathBuf,
+        #[source]
+        error: std::io::Error,
+    },
+}
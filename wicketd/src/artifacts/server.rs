@@ -12,6 +12,7 @@ use installinator_artifactd::EventReportStatus;
 use omicron_common::update::ArtifactHashId;
 use slog::error;
 use slog::Logger;
+use std::ops::Range;
 use uuid::Uuid;
 
 /// The artifact server interface for wicketd.
@@ -36,11 +37,22 @@ impl WicketdArtifactServer {
 
 #[async_trait]
 impl ArtifactGetter for WicketdArtifactServer {
-    async fn get_by_hash(&self, id: &ArtifactHashId) -> Option<(u64, Body)> {
+    async fn get_by_hash(
+        &self,
+        id: &ArtifactHashId,
+        range: Option<Range<u64>>,
+    ) -> Option<(u64, Body)> {
         let data_handle = self.store.get_by_hash(id)?;
         let size = data_handle.file_size() as u64;
-        let data_stream = match data_handle.reader_stream().await {
-            Ok(stream) => stream,
+        let body = match range {
+            Some(range) => data_handle
+                .range_reader_stream(range)
+                .await
+                .map(Body::wrap_stream),
+            None => data_handle.reader_stream().await.map(Body::wrap_stream),
+        };
+        let body = match body {
+            Ok(body) => body,
             Err(err) => {
                 error!(
                     self.log, "failed to open extracted archive on demand";
@@ -50,7 +62,7 @@ impl ArtifactGetter for WicketdArtifactServer {
             }
         };
 
-        Some((size, Body::wrap_stream(data_stream)))
+        Some((size, body))
     }
 
     async fn report_progress(
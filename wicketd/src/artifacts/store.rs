@@ -8,6 +8,7 @@ use omicron_common::update::ArtifactHashId;
 use slog::Logger;
 use std::sync::Arc;
 use std::sync::Mutex;
+use update_common::artifacts::ArtifactStore;
 use update_common::artifacts::ArtifactsWithPlan;
 use update_common::artifacts::ExtractedArtifactDataHandle;
 use update_common::artifacts::UpdatePlan;
@@ -19,15 +20,23 @@ use update_common::artifacts::UpdatePlan;
 #[derive(Clone, Debug)]
 pub struct WicketdArtifactStore {
     log: Logger,
+    // The content-addressed store holding the data of extracted artifacts,
+    // both for the current plan and for any repository being uploaded.
+    store: ArtifactStore,
     // NOTE: this is a `std::sync::Mutex` rather than a `tokio::sync::Mutex`
     // because the critical sections are extremely small.
     artifacts_with_plan: Arc<Mutex<Option<ArtifactsWithPlan>>>,
 }
 
 impl WicketdArtifactStore {
-    pub(crate) fn new(log: &Logger) -> Self {
+    pub(crate) fn new(log: &Logger, store: ArtifactStore) -> Self {
         let log = log.new(slog::o!("component" => "wicketd artifact store"));
-        Self { log, artifacts_with_plan: Default::default() }
+        Self { log, store, artifacts_with_plan: Default::default() }
+    }
+
+    /// Returns the store that uploaded repositories should be extracted into.
+    pub(crate) fn store(&self) -> &ArtifactStore {
+        &self.store
     }
 
     pub(crate) fn set_artifacts_with_plan(
@@ -35,7 +44,18 @@ impl WicketdArtifactStore {
         artifacts_with_plan: ArtifactsWithPlan,
     ) {
         slog::debug!(self.log, "setting artifacts_with_plan");
-        self.replace(artifacts_with_plan);
+        let repo_hash = artifacts_with_plan.description().repo.hash;
+        drop(self.replace(artifacts_with_plan));
+
+        // We only ever serve artifacts from the most recent repository, so
+        // there's no reason to keep any others around.
+        self.store.retain_repos(|hash| *hash == repo_hash);
+    }
+
+    /// Removes artifacts that are no longer referenced from the store.
+    pub(crate) fn collect_garbage(&self) {
+        let removed = self.store.gc();
+        slog::debug!(self.log, "collected garbage"; "removed" => removed);
     }
 
     pub(crate) fn system_version_and_artifact_ids(
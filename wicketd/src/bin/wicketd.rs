@@ -57,6 +57,11 @@ enum Args {
         /// via `--read-smf-config` or an SMF refresh
         #[clap(long, action, conflicts_with("read_smf_config"))]
         rack_subnet: Option<Ipv6Addr>,
+
+        /// Directory in which to keep extracted artifacts, so that they
+        /// survive restarts; if not set, a temporary directory is used
+        #[clap(long, action)]
+        artifact_store_dir: Option<Utf8PathBuf>,
     },
 
     /// Instruct a running wicketd server to refresh its config
@@ -96,6 +101,7 @@ async fn do_run() -> Result<(), CmdError> {
             baseboard_file,
             read_smf_config,
             rack_subnet,
+            artifact_store_dir,
         } => {
             let baseboard = if let Some(baseboard_file) = baseboard_file {
                 let baseboard_file = std::fs::read_to_string(baseboard_file)
@@ -138,6 +144,7 @@ async fn do_run() -> Result<(), CmdError> {
                 nexus_proxy_address,
                 baseboard,
                 rack_subnet,
+                artifact_store_dir,
             };
             let log = config
                 .log
@@ -19,6 +19,7 @@ mod update_tracker;
 use anyhow::{anyhow, Context, Result};
 use artifacts::{WicketdArtifactServer, WicketdArtifactStore};
 use bootstrap_addrs::BootstrapPeers;
+use camino::Utf8PathBuf;
 pub use config::Config;
 pub(crate) use context::ServerContext;
 use display_error_chain::DisplayErrorChain;
@@ -40,6 +41,7 @@ use std::{
     net::{SocketAddr, SocketAddrV6},
     sync::Arc,
 };
+use update_common::artifacts::ArtifactStore;
 pub use update_tracker::{StartUpdateError, UpdateTracker};
 
 /// Run the OpenAPI generator for the API; which emits the OpenAPI spec
@@ -62,6 +64,9 @@ pub struct Args {
     pub nexus_proxy_address: SocketAddrV6,
     pub baseboard: Option<Baseboard>,
     pub rack_subnet: Option<Ipv6Subnet<AZ_PREFIX>>,
+    /// Directory in which to persist extracted artifacts across restarts; if
+    /// `None`, a temporary directory is used instead.
+    pub artifact_store_dir: Option<Utf8PathBuf>,
 }
 
 pub struct SmfConfigValues {
@@ -157,7 +162,17 @@ impl Server {
         let (ipr_artifact, ipr_update_tracker) =
             crate::installinator_progress::new(&log);
 
-        let store = WicketdArtifactStore::new(&log);
+        let artifact_store = match args.artifact_store_dir {
+            Some(dir) => ArtifactStore::open(&log, dir).await,
+            None => ArtifactStore::temporary(&log),
+        }
+        .map_err(|err| {
+            format!(
+                "failed to open artifact store: {}",
+                DisplayErrorChain::new(&err)
+            )
+        })?;
+        let store = WicketdArtifactStore::new(&log, artifact_store);
         let update_tracker = Arc::new(UpdateTracker::new(
             args.mgs_address,
             &log,
@@ -66,6 +66,7 @@ use tokio::task::JoinHandle;
 use tokio_util::io::StreamReader;
 use update_common::artifacts::ArtifactIdData;
 use update_common::artifacts::ArtifactsWithPlan;
+use update_common::artifacts::RepoArchive;
 use update_common::artifacts::UpdatePlan;
 use update_engine::events::ProgressUnits;
 use update_engine::AbortHandle;
@@ -348,12 +349,20 @@ impl UpdateTracker {
         &self,
         stream: impl Stream<Item = Result<Bytes, HttpError>> + Send + 'static,
     ) -> Result<(), HttpError> {
+        let store =
+            self.sp_update_data.lock().await.artifact_store.store().clone();
+
         // Build the ArtifactsWithPlan from the stream.
         let artifacts_with_plan = ArtifactsWithPlan::from_stream(
             stream,
             // We don't have a good file name here because file contents are
             // uploaded over stdin, so let ArtifactsWithPlan pick the name.
-            None, &self.log,
+            None,
+            // We never rebuild a repository from the store, so there's no
+            // reason to keep its archive around.
+            RepoArchive::Discard,
+            &store,
+            &self.log,
         )
         .await
         .map_err(|error| error.to_http_error())?;
@@ -759,6 +768,10 @@ impl UpdateTrackerData {
         // Reset all running data: a new repository means starting afresh.
         self.sp_update_data.clear();
 
+        // The update data we just cleared may have held the last handles to
+        // artifacts from the previous repository.
+        self.artifact_store.collect_garbage();
+
         Ok(())
     }
 }
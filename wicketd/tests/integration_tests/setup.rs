@@ -43,6 +43,7 @@ impl WicketdTestContext {
             nexus_proxy_address: localhost_port_0,
             baseboard: None,
             rack_subnet: None,
+            artifact_store_dir: None,
         };
 
         let server = wicketd::Server::start(log.clone(), args)